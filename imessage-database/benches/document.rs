@@ -0,0 +1,53 @@
+/*!
+ Times building a [`Document`] from a large generated fixture, with and without low-memory mode.
+
+ Usage: `cargo bench -p imessage-database --bench document -- [chats] [messages per chat] [runs]`
+*/
+
+use std::{
+    env::args,
+    time::{Duration, Instant},
+};
+
+use imessage_database::{
+    export::{document::Document, pipeline::Pipeline},
+    tables::table::get_connection,
+    util::query_context::QueryContext,
+};
+use imessage_fixtures::{Fixture, Schema};
+
+/// Build the document `runs` times, returning the fastest and mean durations
+fn measure(fixture: &Fixture, low_memory: bool, runs: u32) -> (Duration, Duration) {
+    let db = get_connection(&fixture.db_path()).unwrap();
+    let mut times = vec![];
+    for _ in 0..runs {
+        let start = Instant::now();
+        let mut pipeline = Pipeline::new(&db, QueryContext::default(), low_memory).unwrap();
+        let document = Document::build(&mut pipeline).unwrap();
+        times.push(start.elapsed());
+        assert!(!document.conversations.is_empty());
+    }
+    let fastest = times.iter().min().copied().unwrap_or_default();
+    let mean = times.iter().sum::<Duration>() / runs.max(1);
+    (fastest, mean)
+}
+
+fn main() {
+    // `cargo bench` passes `--bench` to every target
+    let args: Vec<usize> = args().skip(1).filter_map(|arg| arg.parse().ok()).collect();
+    let chats = args.first().copied().unwrap_or(20);
+    let messages_per_chat = args.get(1).copied().unwrap_or(500);
+    let runs = args.get(2).copied().unwrap_or(5) as u32;
+
+    let fixture = Fixture::temporary(Schema::latest()).unwrap();
+    fixture.populate_scaled(chats, messages_per_chat).unwrap();
+    println!(
+        "Building a document from {} messages, {runs} runs each",
+        chats * messages_per_chat
+    );
+
+    for (name, low_memory) in [("in memory", false), ("low memory", true)] {
+        let (fastest, mean) = measure(&fixture, low_memory, runs);
+        println!("{name:>10}: fastest {fastest:?}, mean {mean:?}");
+    }
+}
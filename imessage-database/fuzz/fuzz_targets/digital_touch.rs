@@ -0,0 +1,8 @@
+#![no_main]
+
+use imessage_database::message_types::digital_touch::from_payload;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = from_payload(data);
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use imessage_database::message_types::handwriting::HandwrittenMessage;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Exporters render every handwriting they can parse, so exercise both renderers too
+    if let Ok(handwriting) = HandwrittenMessage::from_payload(data) {
+        let _ = handwriting.render_svg();
+        let _ = handwriting.render_ascii(40);
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use imessage_database::util::plist::parse_plist;
+use libfuzzer_sys::fuzz_target;
+use plist::Value;
+
+fuzz_target!(|data: &[u8]| {
+    // `payload_data` and `message_summary_info` are read the same way in `Message`
+    if let Ok(plist) = Value::from_reader(Cursor::new(data)) {
+        let _ = parse_plist(&plist);
+    }
+});
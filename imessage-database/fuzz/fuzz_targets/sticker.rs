@@ -0,0 +1,8 @@
+#![no_main]
+
+use imessage_database::message_types::sticker::get_sticker_effect;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = get_sticker_effect(data.to_vec());
+});
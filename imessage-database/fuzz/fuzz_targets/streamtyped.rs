@@ -0,0 +1,8 @@
+#![no_main]
+
+use imessage_database::util::streamtyped::parse;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = parse(data.to_vec());
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use imessage_database::util::typedstream::parser::TypedStreamReader;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(components) = TypedStreamReader::from(data).parse() {
+        // Resolve the message text the same way `Message::generate_text()` does
+        let _ = components.first().and_then(|item| item.as_nsstring());
+    }
+});
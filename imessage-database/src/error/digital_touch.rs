@@ -0,0 +1,38 @@
+/*!
+ Errors that can happen when parsing `digital touch` data.
+*/
+
+use std::fmt::{Display, Formatter, Result};
+
+use serde_with::SerializeDisplay;
+
+/// Errors that can happen when parsing `digital touch` data
+#[derive(Debug, SerializeDisplay)]
+pub enum DigitalTouchError {
+    ProtobufError(protobuf::Error),
+    UnknownKind(i32),
+    InvalidFieldLength(&'static str, usize, usize),
+    InvalidStrokesLength(usize, usize),
+}
+
+impl Display for DigitalTouchError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result {
+        match self {
+            DigitalTouchError::ProtobufError(why) => {
+                write!(fmt, "failed to parse digital touch protobuf: {why}")
+            }
+            DigitalTouchError::UnknownKind(kind) => {
+                write!(fmt, "unknown digital touch kind {kind}")
+            }
+            DigitalTouchError::InvalidFieldLength(field, length, size) => {
+                write!(
+                    fmt,
+                    "{field} has length {length}, expected a multiple of {size}"
+                )
+            }
+            DigitalTouchError::InvalidStrokesLength(index, length) => {
+                write!(fmt, "can't access index {index} on array length {length}")
+            }
+        }
+    }
+}
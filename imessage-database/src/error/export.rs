@@ -0,0 +1,43 @@
+/*!
+ Errors that can happen when rendering messages with the [`export`](crate::export) pipeline.
+*/
+
+use std::fmt::{Display, Formatter, Result};
+
+use serde_with::SerializeDisplay;
+
+use crate::error::table::TableError;
+
+/// Errors that can happen when rendering messages with the [`export`](crate::export) pipeline
+#[derive(Debug, SerializeDisplay)]
+pub enum ExportError {
+    TableError(TableError),
+    DiskError(std::io::Error),
+    UnknownFormat(String),
+    Format(String),
+}
+
+impl From<TableError> for ExportError {
+    fn from(error: TableError) -> Self {
+        ExportError::TableError(error)
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(error: std::io::Error) -> Self {
+        ExportError::DiskError(error)
+    }
+}
+
+impl Display for ExportError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result {
+        match self {
+            ExportError::TableError(why) => write!(fmt, "{why}"),
+            ExportError::DiskError(why) => write!(fmt, "{why}"),
+            ExportError::UnknownFormat(name) => {
+                write!(fmt, "No export format is registered as {name}!")
+            }
+            ExportError::Format(why) => write!(fmt, "Unable to render message: {why}"),
+        }
+    }
+}
@@ -18,6 +18,8 @@ pub enum HandwritingError {
     DecompressedNotSet,
     InvalidDecompressedLength(usize, usize),
     ResizeError(std::num::TryFromIntError),
+    DecompressedTooLarge(usize),
+    EncodeError(png::EncodingError),
 }
 
 impl Display for HandwritingError {
@@ -42,6 +44,10 @@ impl Display for HandwritingError {
             HandwritingError::ResizeError(why) => {
                 write!(fmt, "failed to resize handwriting coordinates: {why}")
             }
+            HandwritingError::DecompressedTooLarge(length) => {
+                write!(fmt, "decompressed data is longer than {length} bytes")
+            }
+            HandwritingError::EncodeError(why) => write!(fmt, "failed to encode png: {why}"),
         }
     }
 }
@@ -3,6 +3,8 @@
 */
 
 pub mod attachment;
+pub mod digital_touch;
+pub mod export;
 pub mod handwriting;
 pub mod message;
 pub mod plist;
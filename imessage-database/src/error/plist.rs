@@ -2,10 +2,12 @@
  Errors that can happen when parsing plist data.
 */
 
+use crate::error::digital_touch::DigitalTouchError;
 use crate::error::handwriting::HandwritingError;
 use crate::error::streamtyped::StreamTypedError;
 use std::fmt::{Display, Formatter, Result};
 
+use serde::de;
 use serde_with::SerializeDisplay;
 
 /// Errors that can happen when parsing the plist data stored in the `payload_data` field
@@ -14,6 +16,7 @@ pub enum PlistParseError {
     MissingKey(String),
     NoValueAtIndex(usize),
     InvalidType(String, String),
+    InvalidValue(String, String),
     InvalidTypeIndex(usize, String),
     InvalidDictionarySize(usize, usize),
     NoPayload,
@@ -21,7 +24,9 @@ pub enum PlistParseError {
     InvalidEditedMessage(String),
     StreamTypedError(StreamTypedError),
     HandwritingError(HandwritingError),
-    DigitalTouchError,
+    DigitalTouchError(DigitalTouchError),
+    RecursionLimit(usize),
+    SizeLimit(usize),
 }
 
 impl Display for PlistParseError {
@@ -34,6 +39,9 @@ impl Display for PlistParseError {
             PlistParseError::InvalidType(key, value) => {
                 write!(fmt, "Invalid data found at {key}, expected {value}")
             }
+            PlistParseError::InvalidValue(key, why) => {
+                write!(fmt, "Invalid data found at {key}: {why}")
+            }
             PlistParseError::InvalidTypeIndex(idx, value) => {
                 write!(
                     fmt,
@@ -51,9 +59,35 @@ impl Display for PlistParseError {
             }
             PlistParseError::StreamTypedError(why) => write!(fmt, "{why}"),
             PlistParseError::HandwritingError(why) => write!(fmt, "{why}"),
-            PlistParseError::DigitalTouchError => {
-                write!(fmt, "Unable to parse Digital Touch Message!")
+            PlistParseError::DigitalTouchError(why) => {
+                write!(fmt, "Unable to parse Digital Touch Message: {why}")
+            }
+            PlistParseError::RecursionLimit(depth) => {
+                write!(
+                    fmt,
+                    "Payload references are nested more than {depth} levels deep!"
+                )
+            }
+            PlistParseError::SizeLimit(size) => {
+                write!(fmt, "Payload references expand to more than {size} values!")
             }
         }
     }
 }
+
+impl std::error::Error for PlistParseError {}
+
+/// Errors created while deserializing a resolved payload; the key path is filled in as the error is returned
+impl de::Error for PlistParseError {
+    fn custom<T: Display>(msg: T) -> Self {
+        PlistParseError::InvalidValue(String::new(), msg.to_string())
+    }
+
+    fn invalid_type(unexp: de::Unexpected, exp: &dyn de::Expected) -> Self {
+        PlistParseError::InvalidType(String::new(), format!("{exp}, found {unexp}"))
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        PlistParseError::MissingKey(field.to_string())
+    }
+}
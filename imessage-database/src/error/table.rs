@@ -16,6 +16,7 @@ pub enum TableError {
     Messages(rusqlite::Error),
     CannotConnect(String),
     CannotRead(std::io::Error),
+    CannotConfigure(rusqlite::Error),
     JsonError(serde_json::Error),
 }
 
@@ -35,6 +36,9 @@ impl Display for TableError {
             TableError::Messages(why) => write!(fmt, "Failed to parse messages row: {why}"),
             TableError::CannotConnect(why) => write!(fmt, "{why}"),
             TableError::CannotRead(why) => write!(fmt, "{why}"),
+            TableError::CannotConfigure(why) => {
+                write!(fmt, "Failed to configure database connection: {why}")
+            }
             TableError::JsonError(why) => write!(fmt, "{why}"),
         }
     }
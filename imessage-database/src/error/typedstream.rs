@@ -1,10 +1,11 @@
 /*!
- Errors that can happen when parsing `typedstream` data. This module is for the new `typedstream` deserializer.
+ Errors that can happen when parsing `typedstream` data. This module is for the new `typedstream` deserializer and serializer.
 */
 
 use std::{
     array::TryFromSliceError,
-    fmt::{Display, Formatter, Result}, str::Utf8Error,
+    fmt::{Display, Formatter, Result},
+    str::Utf8Error,
 };
 
 use serde_with::SerializeDisplay;
@@ -18,6 +19,8 @@ pub enum TypedStreamError {
     StringParseError(Utf8Error),
     InvalidArray,
     InvalidPointer(u8),
+    RecursionLimit(usize),
+    UnsupportedData(String),
 }
 
 impl Display for TypedStreamError {
@@ -33,6 +36,12 @@ impl Display for TypedStreamError {
             TypedStreamError::StringParseError(why) => write!(fmt, "Failed to parse string: {why}"),
             TypedStreamError::InvalidArray => write!(fmt, "Failed to parse array data"),
             TypedStreamError::InvalidPointer(why) => write!(fmt, "Failed to parse pointer: {why}"),
+            TypedStreamError::RecursionLimit(depth) => {
+                write!(fmt, "Data is nested more than {depth} levels deep!")
+            }
+            TypedStreamError::UnsupportedData(why) => {
+                write!(fmt, "Unable to write typedstream data: {why}")
+            }
         }
     }
 }
@@ -0,0 +1,112 @@
+/*!
+ Contains logic used to parse the app balloon attached to a message, so that formats do not need to know how each payload is stored.
+*/
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::{
+    error::plist::PlistParseError,
+    message_types::{
+        app::{AppMessage, OwnedAppMessage},
+        app_store::OwnedAppStoreMessage,
+        collaboration::OwnedCollaborationMessage,
+        digital_touch::{self, DigitalTouch},
+        handwriting::HandwrittenMessage,
+        music::OwnedMusicMessage,
+        placemark::OwnedPlacemarkMessage,
+        url::{OwnedURLMessage, URLMessage},
+        variants::{BalloonProvider, CustomBalloon, URLOverride, Variant},
+    },
+    tables::messages::Message,
+    util::plist::parse_plist,
+};
+
+/// The parsed contents of a message's app balloon
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Balloon {
+    /// A [`URL`](crate::message_types::url) preview
+    URL(OwnedURLMessage),
+    /// An [`Apple Music`](crate::message_types::music) message
+    Music(OwnedMusicMessage),
+    /// An [`App Store`](crate::message_types::app_store) link
+    AppStore(OwnedAppStoreMessage),
+    /// A [`Collaboration`](crate::message_types::collaboration) message
+    Collaboration(OwnedCollaborationMessage),
+    /// A shared [`Placemark`](crate::message_types::placemark)
+    Placemark(OwnedPlacemarkMessage),
+    /// A handwritten note
+    Handwriting(HandwrittenMessage),
+    /// A Digital Touch message
+    DigitalTouch(DigitalTouch),
+    /// An Apple Pay message
+    ApplePay(OwnedAppMessage),
+    /// A Fitness.app message
+    Fitness(OwnedAppMessage),
+    /// A Photos.app slideshow message
+    Slideshow(OwnedAppMessage),
+    /// A Check In message
+    CheckIn(OwnedAppMessage),
+    /// A Find My message
+    FindMy(OwnedAppMessage),
+    /// A generic app, generally third party, along with its bundle ID
+    Application(String, OwnedAppMessage),
+}
+
+impl Balloon {
+    /// Parse the balloon attached to a message
+    ///
+    /// Returns [`PlistParseError::WrongMessageType`] if the message is not an app message
+    /// and [`PlistParseError::NoPayload`] if the message does not have a payload to parse.
+    pub fn from_message(message: &Message, db: &Connection) -> Result<Self, PlistParseError> {
+        let Variant::App(balloon) = message.variant() else {
+            return Err(PlistParseError::WrongMessageType);
+        };
+
+        // Handwritten and Digital Touch messages use different payload types, so check them first
+        if message.is_handwriting() {
+            let payload = message
+                .raw_payload_data(db)
+                .ok_or(PlistParseError::NoPayload)?;
+            return HandwrittenMessage::from_payload(&payload)
+                .map(Self::Handwriting)
+                .map_err(PlistParseError::HandwritingError);
+        }
+
+        if message.is_digital_touch() {
+            let payload = message
+                .raw_payload_data(db)
+                .ok_or(PlistParseError::NoPayload)?;
+            return digital_touch::from_payload(&payload)
+                .map(Self::DigitalTouch)
+                .map_err(PlistParseError::DigitalTouchError);
+        }
+
+        let payload = message.payload_data(db).ok_or(PlistParseError::NoPayload)?;
+        let parsed = parse_plist(&payload)?;
+
+        if message.is_url() {
+            return Ok(match URLMessage::get_url_message_override(&parsed)? {
+                URLOverride::Normal(balloon) => Self::URL((&balloon).into()),
+                URLOverride::AppleMusic(balloon) => Self::Music((&balloon).into()),
+                URLOverride::AppStore(balloon) => Self::AppStore((&balloon).into()),
+                URLOverride::Collaboration(balloon) => Self::Collaboration((&balloon).into()),
+                URLOverride::SharedPlacemark(balloon) => Self::Placemark((&balloon).into()),
+            });
+        }
+
+        let app = OwnedAppMessage::from(&AppMessage::from_map(&parsed)?);
+        Ok(match balloon {
+            CustomBalloon::Application(bundle_id) => Self::Application(bundle_id.to_string(), app),
+            CustomBalloon::ApplePay => Self::ApplePay(app),
+            CustomBalloon::Fitness => Self::Fitness(app),
+            CustomBalloon::Slideshow => Self::Slideshow(app),
+            CustomBalloon::CheckIn => Self::CheckIn(app),
+            CustomBalloon::FindMy => Self::FindMy(app),
+            // These are handled above
+            CustomBalloon::URL | CustomBalloon::Handwriting | CustomBalloon::DigitalTouch => {
+                return Err(PlistParseError::WrongMessageType)
+            }
+        })
+    }
+}
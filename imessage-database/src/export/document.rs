@@ -0,0 +1,533 @@
+/*!
+ Contains a format-independent model of the conversations in the database.
+
+ Each message is built once by a [`Pipeline`], into a [`DocumentMessage`] that holds its parts, tapbacks, and
+ replies. Output formats implement [`Render`] to receive each finished message or announcement as soon as it is
+ built, or build a whole [`Document`] to render every conversation at once.
+*/
+
+use std::collections::HashMap;
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::{
+    error::{export::ExportError, plist::PlistParseError},
+    export::{
+        balloon::Balloon,
+        format::Format,
+        pipeline::{Pipeline, Walker},
+        tapbacks::TapbackStore,
+    },
+    message_types::edited::EditedMessagePart,
+    tables::{
+        attachment::Attachment,
+        messages::{
+            models::{OwnedTextAttributes, TextAttributes},
+            Message,
+        },
+    },
+};
+
+/// Every conversation rendered by a [`Pipeline`]
+#[derive(Debug, Default, Serialize)]
+pub struct Document {
+    /// The conversations, in the order their first message was rendered
+    pub conversations: Vec<Conversation>,
+}
+
+/// The messages and announcements that belong to a single chat
+#[derive(Debug, Serialize)]
+pub struct Conversation {
+    /// The `ROWID` of the chat, or `None` for messages that do not belong to a chat
+    pub chat_id: Option<i32>,
+    /// The items in the conversation, in the order they were rendered
+    pub items: Vec<Item>,
+}
+
+/// A single entry in a [`Conversation`]
+#[derive(Debug, Serialize)]
+pub enum Item {
+    /// A message, along with its tapbacks and replies
+    Message(DocumentMessage),
+    /// An announcement, i.e. a group name change
+    Announcement(Message),
+}
+
+impl Item {
+    /// Get the message the item was built from
+    pub fn message(&self) -> &Message {
+        match self {
+            Item::Message(message) => &message.message,
+            Item::Announcement(message) => message,
+        }
+    }
+}
+
+/// A message and everything rendered with it
+#[derive(Debug, Serialize)]
+pub struct DocumentMessage {
+    /// The message, with its text generated
+    pub message: Message,
+    /// The parts of the message body, in order
+    pub parts: Vec<Part>,
+}
+
+/// A single part of a message body, along with the tapbacks and replies that reference it
+#[derive(Debug, Serialize)]
+pub struct Part {
+    /// The index of the part in the message body
+    pub index: usize,
+    /// The contents of the part, or `None` if the part has nothing to render
+    pub content: Option<Content>,
+    /// The tapbacks and stickers that react to the part
+    pub tapbacks: Vec<Message>,
+    /// The replies to the part, in order
+    pub replies: Vec<DocumentMessage>,
+}
+
+/// The contents of a [`Part`]
+#[derive(Debug, Serialize)]
+pub enum Content {
+    /// Text, along with the ranges that have effects applied and the images for any Genmoji ranges
+    Text {
+        /// The full text of the message
+        text: String,
+        /// The ranges of `text` that have effects applied
+        attributes: Vec<OwnedTextAttributes>,
+        /// The images for any Genmoji ranges, in order
+        genmoji: Vec<Attachment>,
+    },
+    /// A part that was edited or unsent
+    Edited(EditedMessagePart),
+    /// An attachment or sticker
+    Attachment(Attachment),
+    /// An attachment that does not exist in the attachments table, with its index in the message's attachments
+    MissingAttachment(usize),
+    /// An app balloon
+    Balloon(Balloon),
+    /// An app balloon that was sent without a payload
+    MissingPayload,
+    /// An app balloon whose payload could not be parsed, with the reason
+    BalloonError(String),
+}
+
+impl Document {
+    /// Build a document from every message the pipeline renders
+    pub fn build(pipeline: &mut Pipeline<'_>) -> Result<Self, ExportError> {
+        let mut collector = Collector::default();
+        pipeline.run(&mut Builder::new(&mut collector))?;
+
+        let mut document = Document::default();
+        // Map of chat IDs to their index in `conversations`
+        let mut chats: HashMap<Option<i32>, usize> = HashMap::new();
+        for item in collector.items {
+            let chat_id = item.message().chat_id;
+            let conversations = &mut document.conversations;
+            let idx = *chats.entry(chat_id).or_insert_with(|| {
+                conversations.push(Conversation {
+                    chat_id,
+                    items: vec![],
+                });
+                conversations.len() - 1
+            });
+            conversations[idx].items.push(item);
+        }
+        Ok(document)
+    }
+
+    /// Get the conversation for a chat, if it has any items
+    pub fn conversation(&self, chat_id: Option<i32>) -> Option<&Conversation> {
+        self.conversations
+            .iter()
+            .find(|conversation| conversation.chat_id == chat_id)
+    }
+}
+
+impl DocumentMessage {
+    /// Build a single message, along with its tapbacks and replies
+    ///
+    /// This is useful for programs that walk the messages table on their own instead of with a [`Pipeline`].
+    pub fn build(
+        db: &Connection,
+        tapbacks: &TapbackStore,
+        message: &Message,
+    ) -> Result<Self, ExportError> {
+        let mut collector = Collector::default();
+        Walker::new(db, tapbacks).render_message(message, 0, &mut Builder::new(&mut collector))?;
+        match collector.items.pop() {
+            Some(Item::Message(message)) => Ok(message),
+            _ => Err(ExportError::Format(format!(
+                "Unable to build message {}",
+                message.guid
+            ))),
+        }
+    }
+}
+
+/// Defines how an output format renders the messages in a [`Document`]
+///
+/// A [`Builder`] passes each message to [`render`](Render::render) as soon as it and its replies are built,
+/// so a format can write a conversation without holding the whole document in memory.
+pub trait Render {
+    /// Called once, before the first item is rendered
+    fn start(&mut self, _db: &Connection) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a top-level message, along with its tapbacks and replies, or an announcement
+    fn render(&mut self, item: Item) -> Result<(), ExportError>;
+
+    /// Called once, after the last item is rendered
+    fn finish(&mut self) -> Result<(), ExportError> {
+        Ok(())
+    }
+}
+
+impl<R: Render + ?Sized> Render for &mut R {
+    fn start(&mut self, db: &Connection) -> Result<(), ExportError> {
+        (**self).start(db)
+    }
+
+    fn render(&mut self, item: Item) -> Result<(), ExportError> {
+        (**self).render(item)
+    }
+
+    fn finish(&mut self) -> Result<(), ExportError> {
+        (**self).finish()
+    }
+}
+
+/// Collects every item it is passed, in order
+#[derive(Default)]
+struct Collector {
+    items: Vec<Item>,
+}
+
+impl Render for Collector {
+    fn render(&mut self, item: Item) -> Result<(), ExportError> {
+        self.items.push(item);
+        Ok(())
+    }
+}
+
+/// Builds each message from the pieces rendered by a [`Pipeline`] and passes it to a [`Render`]
+pub struct Builder<R: Render> {
+    /// The format each finished item is passed to
+    renderer: R,
+    /// The messages that have started but not ended, outermost first
+    stack: Vec<DocumentMessage>,
+}
+
+impl<R: Render> Builder<R> {
+    /// Create a builder that passes each finished item to `renderer`
+    pub fn new(renderer: R) -> Self {
+        Self {
+            renderer,
+            stack: vec![],
+        }
+    }
+
+    /// Get the part currently being rendered
+    fn part(&mut self) -> Option<&mut Part> {
+        self.stack.last_mut()?.parts.last_mut()
+    }
+
+    /// Set the contents of the part currently being rendered
+    fn set_content(&mut self, content: Content) {
+        if let Some(part) = self.part() {
+            part.content = Some(content);
+        }
+    }
+}
+
+impl<R: Render> Format for Builder<R> {
+    fn start(&mut self, db: &Connection) -> Result<(), ExportError> {
+        self.renderer.start(db)
+    }
+
+    fn start_message(&mut self, message: &Message, _depth: usize) -> Result<(), ExportError> {
+        self.stack.push(DocumentMessage {
+            message: message.clone(),
+            parts: vec![],
+        });
+        Ok(())
+    }
+
+    fn start_part(&mut self, _message: &Message, idx: usize) -> Result<(), ExportError> {
+        if let Some(message) = self.stack.last_mut() {
+            message.parts.push(Part {
+                index: idx,
+                content: None,
+                tapbacks: vec![],
+                replies: vec![],
+            });
+        }
+        Ok(())
+    }
+
+    fn text(
+        &mut self,
+        _message: &Message,
+        text: &str,
+        attributes: &[TextAttributes],
+        genmoji: &[Attachment],
+    ) -> Result<(), ExportError> {
+        self.set_content(Content::Text {
+            text: text.to_string(),
+            attributes: attributes.iter().map(OwnedTextAttributes::from).collect(),
+            genmoji: genmoji.to_vec(),
+        });
+        Ok(())
+    }
+
+    fn edited(
+        &mut self,
+        _message: &Message,
+        part: &EditedMessagePart,
+        _idx: usize,
+    ) -> Result<(), ExportError> {
+        self.set_content(Content::Edited(part.clone()));
+        Ok(())
+    }
+
+    fn attachment(
+        &mut self,
+        _message: &Message,
+        attachment: &Attachment,
+    ) -> Result<(), ExportError> {
+        self.set_content(Content::Attachment(attachment.clone()));
+        Ok(())
+    }
+
+    fn missing_attachment(&mut self, _message: &Message, idx: usize) -> Result<(), ExportError> {
+        self.set_content(Content::MissingAttachment(idx));
+        Ok(())
+    }
+
+    fn balloon(&mut self, _message: &Message, balloon: &Balloon) -> Result<(), ExportError> {
+        self.set_content(Content::Balloon(balloon.clone()));
+        Ok(())
+    }
+
+    fn balloon_error(
+        &mut self,
+        _message: &Message,
+        why: &PlistParseError,
+    ) -> Result<(), ExportError> {
+        self.set_content(match why {
+            PlistParseError::NoPayload => Content::MissingPayload,
+            why => Content::BalloonError(why.to_string()),
+        });
+        Ok(())
+    }
+
+    fn tapbacks(
+        &mut self,
+        _message: &Message,
+        _idx: usize,
+        tapbacks: &[Message],
+    ) -> Result<(), ExportError> {
+        if let Some(part) = self.part() {
+            part.tapbacks.extend_from_slice(tapbacks);
+        }
+        Ok(())
+    }
+
+    fn end_message(&mut self, _message: &Message, _depth: usize) -> Result<(), ExportError> {
+        let Some(finished) = self.stack.pop() else {
+            return Ok(());
+        };
+
+        match self.part() {
+            // Replies belong to the part of the parent message that is currently being rendered
+            Some(parent) => {
+                parent.replies.push(finished);
+                Ok(())
+            }
+            None => self.renderer.render(Item::Message(finished)),
+        }
+    }
+
+    fn announcement(&mut self, message: &Message) -> Result<(), ExportError> {
+        self.renderer.render(Item::Announcement(message.clone()))
+    }
+
+    fn finish(&mut self) -> Result<(), ExportError> {
+        self.renderer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        export::{
+            document::{Content, Document, DocumentMessage, Item},
+            pipeline::Pipeline,
+            tapbacks::TapbackStore,
+        },
+        message_types::variants::{Announcement, Tapback, Variant},
+        tables::table::get_connection,
+        util::query_context::QueryContext,
+    };
+    use imessage_fixtures::{guid, Fixture, NewAttachment, NewMessage, Schema};
+
+    fn fixture() -> Fixture {
+        let fixture = Fixture::temporary(Schema::latest()).unwrap();
+        let handle = fixture
+            .add_handle("+15555550101", "iMessage", None)
+            .unwrap();
+        let chat = fixture
+            .add_chat("chat0", "iMessage", None, &[handle])
+            .unwrap();
+        let other = fixture
+            .add_chat("chat1", "iMessage", None, &[handle])
+            .unwrap();
+
+        let message = |number: u64, text: &str| NewMessage {
+            guid: guid(number),
+            text: Some(text.to_string()),
+            handle_id: handle,
+            date: number as i64,
+            chat_id: Some(chat),
+            ..Default::default()
+        };
+
+        fixture.add_message(&message(1, "Hello world")).unwrap();
+        fixture
+            .add_message(&NewMessage {
+                associated_message_guid: Some(format!("p:0/{}", guid(1))),
+                associated_message_type: 2000,
+                ..message(2, "Loved “Hello world”")
+            })
+            .unwrap();
+        fixture
+            .add_message(&NewMessage {
+                thread_originator_guid: Some(guid(1)),
+                thread_originator_part: Some("0:0:11".to_string()),
+                ..message(3, "Reply")
+            })
+            .unwrap();
+        fixture
+            .add_message(&NewMessage {
+                chat_id: Some(other),
+                ..message(4, "Elsewhere")
+            })
+            .unwrap();
+        fixture
+            .add_message(&NewMessage {
+                item_type: 2,
+                group_title: Some("New name".to_string()),
+                text: None,
+                ..message(5, "")
+            })
+            .unwrap();
+        let with_file = fixture.add_message(&message(6, "\u{FFFC}")).unwrap();
+        fixture
+            .add_attachment(
+                with_file,
+                &NewAttachment {
+                    guid: "file".to_string(),
+                    transfer_name: "photo.png".to_string(),
+                    mime_type: Some("image/png".to_string()),
+                    data: Some(vec![]),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        fixture
+    }
+
+    fn build(low_memory: bool) -> Document {
+        let fixture = fixture();
+        let db = get_connection(&fixture.db_path()).unwrap();
+        let mut pipeline = Pipeline::new(&db, QueryContext::default(), low_memory).unwrap();
+        Document::build(&mut pipeline).unwrap()
+    }
+
+    fn check(document: &Document) {
+        assert_eq!(document.conversations.len(), 2);
+
+        let conversation = &document.conversations[0];
+        let items = &conversation.items;
+        // The reply is also rendered in its place in the conversation
+        assert_eq!(items.len(), 4);
+
+        let Item::Message(first) = &items[0] else {
+            panic!("Expected a message!")
+        };
+        assert_eq!(first.message.rowid, 1);
+        assert_eq!(first.parts.len(), 1);
+
+        let part = &first.parts[0];
+        assert_eq!(part.index, 0);
+        assert!(matches!(
+            &part.content,
+            Some(Content::Text { text, .. }) if text == "Hello world"
+        ));
+        assert_eq!(part.tapbacks.len(), 1);
+        assert!(matches!(
+            part.tapbacks[0].variant(),
+            Variant::Tapback(0, true, Tapback::Loved)
+        ));
+        assert_eq!(part.replies.len(), 1);
+        assert_eq!(part.replies[0].message.text.as_deref(), Some("Reply"));
+
+        let Item::Announcement(announcement) = &items[2] else {
+            panic!("Expected an announcement!")
+        };
+        assert!(matches!(
+            announcement.get_announcement(),
+            Some(Announcement::NameChange("New name"))
+        ));
+
+        let Item::Message(with_file) = &items[3] else {
+            panic!("Expected a message!")
+        };
+        assert!(matches!(
+            &with_file.parts[0].content,
+            Some(Content::Attachment(attachment))
+                if attachment.transfer_name.as_deref() == Some("photo.png")
+        ));
+
+        let elsewhere = document.conversation(document.conversations[1].chat_id);
+        assert_eq!(elsewhere.map(|c| c.items.len()), Some(1));
+    }
+
+    #[test]
+    fn can_build_document() {
+        check(&build(false));
+    }
+
+    #[test]
+    fn can_build_document_low_memory() {
+        check(&build(true));
+    }
+
+    #[test]
+    fn can_build_document_message() {
+        let fixture = fixture();
+        let db = get_connection(&fixture.db_path()).unwrap();
+        let tapbacks = TapbackStore::build(&db, false).unwrap();
+
+        let mut pipeline = Pipeline::new(&db, QueryContext::default(), false).unwrap();
+        let document = Document::build(&mut pipeline).unwrap();
+        let first = document.conversations[0].items[0].message();
+        let message = DocumentMessage::build(&db, &tapbacks, first).unwrap();
+
+        assert_eq!(message.message.rowid, 1);
+        assert_eq!(message.parts[0].tapbacks.len(), 1);
+        assert_eq!(message.parts[0].replies.len(), 1);
+    }
+
+    #[test]
+    fn can_serialize_document() {
+        let document = build(false);
+        let json = serde_json::to_value(&document).unwrap();
+
+        let part = &json["conversations"][0]["items"][0]["Message"]["parts"][0];
+        assert_eq!(part["content"]["Text"]["text"], "Hello world");
+        assert_eq!(part["tapbacks"][0]["associated_message_type"], 2000);
+        assert_eq!(part["replies"][0]["message"]["text"], "Reply");
+    }
+}
@@ -0,0 +1,268 @@
+/*!
+ Contains the traits an output format implements to render messages from the [`Pipeline`](crate::export::pipeline::Pipeline).
+*/
+
+use rusqlite::Connection;
+
+use crate::{
+    error::{export::ExportError, plist::PlistParseError},
+    export::balloon::Balloon,
+    message_types::{
+        app::OwnedAppMessage, app_store::OwnedAppStoreMessage,
+        collaboration::OwnedCollaborationMessage, digital_touch::DigitalTouch,
+        edited::EditedMessagePart, handwriting::HandwrittenMessage, music::OwnedMusicMessage,
+        placemark::OwnedPlacemarkMessage, url::OwnedURLMessage,
+    },
+    tables::{attachment::Attachment, messages::models::TextAttributes, messages::Message},
+};
+
+/// Defines how an output format renders the pieces of a conversation
+///
+/// The [`Pipeline`](crate::export::pipeline::Pipeline) calls these methods in the order the pieces should appear:
+///
+/// - [`start_message`](Format::start_message)
+/// - For each part of the message body, [`start_part`](Format::start_part), then one of [`text`](Format::text), [`edited`](Format::edited),
+///   [`attachment`](Format::attachment), [`missing_attachment`](Format::missing_attachment),
+///   [`balloon`](Format::balloon), or [`balloon_error`](Format::balloon_error)
+///   - [`tapbacks`](Format::tapbacks) that react to the part, if any
+///   - The replies to the part, each rendered as a full message with a greater `depth`
+/// - [`end_message`](Format::end_message)
+///
+/// Announcements are not part of a message body, so they are rendered with [`announcement`](Format::announcement) instead.
+///
+/// Only [`start_message`](Format::start_message) and [`announcement`](Format::announcement) are required;
+/// every other piece is skipped unless the format implements it.
+pub trait Format {
+    /// Called once, before the first message is rendered
+    fn start(&mut self, _db: &Connection) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Called before the body of a message is rendered
+    ///
+    /// `depth` is `0` for messages in the conversation and increases by `1` for each level of reply.
+    fn start_message(&mut self, message: &Message, depth: usize) -> Result<(), ExportError>;
+
+    /// Called before each part of the message body is rendered, with the part's index
+    fn start_part(&mut self, _message: &Message, _idx: usize) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a part of the message body that contains text
+    ///
+    /// The `attributes` describe the ranges of `text` that have effects applied, and `genmoji` contains the
+    /// images for any [`Genmoji`](crate::message_types::text_effects::TextEffect::Genmoji) ranges, in order.
+    fn text(
+        &mut self,
+        _message: &Message,
+        _text: &str,
+        _attributes: &[TextAttributes],
+        _genmoji: &[Attachment],
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a part of the message body that was edited or unsent
+    fn edited(
+        &mut self,
+        _message: &Message,
+        _part: &EditedMessagePart,
+        _idx: usize,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render an attachment or sticker, after every [`AttachmentHook`] has run
+    fn attachment(
+        &mut self,
+        _message: &Message,
+        _attachment: &Attachment,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a placeholder for an attachment that does not exist in the attachments table
+    fn missing_attachment(&mut self, _message: &Message, _idx: usize) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render an app balloon
+    ///
+    /// By default, this calls the method for the specific type of balloon, i.e. [`url`](Format::url).
+    fn balloon(&mut self, message: &Message, balloon: &Balloon) -> Result<(), ExportError> {
+        match balloon {
+            Balloon::URL(balloon) => self.url(message, balloon),
+            Balloon::Music(balloon) => self.music(message, balloon),
+            Balloon::AppStore(balloon) => self.app_store(message, balloon),
+            Balloon::Collaboration(balloon) => self.collaboration(message, balloon),
+            Balloon::Placemark(balloon) => self.placemark(message, balloon),
+            Balloon::Handwriting(balloon) => self.handwriting(message, balloon),
+            Balloon::DigitalTouch(balloon) => self.digital_touch(message, balloon),
+            Balloon::ApplePay(balloon) => self.apple_pay(message, balloon),
+            Balloon::Fitness(balloon) => self.fitness(message, balloon),
+            Balloon::Slideshow(balloon) => self.slideshow(message, balloon),
+            Balloon::CheckIn(balloon) => self.check_in(message, balloon),
+            Balloon::FindMy(balloon) => self.find_my(message, balloon),
+            Balloon::Application(bundle_id, balloon) => {
+                self.generic_app(message, bundle_id, balloon)
+            }
+        }
+    }
+
+    /// Render an app balloon whose payload could not be parsed
+    fn balloon_error(
+        &mut self,
+        _message: &Message,
+        _why: &PlistParseError,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a URL preview
+    fn url(&mut self, _message: &Message, _balloon: &OwnedURLMessage) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render an Apple Music message
+    fn music(
+        &mut self,
+        _message: &Message,
+        _balloon: &OwnedMusicMessage,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render an App Store link
+    fn app_store(
+        &mut self,
+        _message: &Message,
+        _balloon: &OwnedAppStoreMessage,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a Rich Collaboration message
+    fn collaboration(
+        &mut self,
+        _message: &Message,
+        _balloon: &OwnedCollaborationMessage,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a shared location
+    fn placemark(
+        &mut self,
+        _message: &Message,
+        _balloon: &OwnedPlacemarkMessage,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a handwritten note
+    fn handwriting(
+        &mut self,
+        _message: &Message,
+        _balloon: &HandwrittenMessage,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a Digital Touch message
+    fn digital_touch(
+        &mut self,
+        _message: &Message,
+        _balloon: &DigitalTouch,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render an Apple Pay message
+    fn apple_pay(
+        &mut self,
+        _message: &Message,
+        _balloon: &OwnedAppMessage,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a Fitness message
+    fn fitness(
+        &mut self,
+        _message: &Message,
+        _balloon: &OwnedAppMessage,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a Photo Slideshow message
+    fn slideshow(
+        &mut self,
+        _message: &Message,
+        _balloon: &OwnedAppMessage,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a Check In message
+    fn check_in(
+        &mut self,
+        _message: &Message,
+        _balloon: &OwnedAppMessage,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a Find My message
+    fn find_my(
+        &mut self,
+        _message: &Message,
+        _balloon: &OwnedAppMessage,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render a generic app, generally third party
+    fn generic_app(
+        &mut self,
+        _message: &Message,
+        _bundle_id: &str,
+        _balloon: &OwnedAppMessage,
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render the tapbacks that react to the part of the message body at `idx`
+    fn tapbacks(
+        &mut self,
+        _message: &Message,
+        _idx: usize,
+        _tapbacks: &[Message],
+    ) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Called after the body, tapbacks, and replies of a message are rendered
+    fn end_message(&mut self, _message: &Message, _depth: usize) -> Result<(), ExportError> {
+        Ok(())
+    }
+
+    /// Render an announcement, i.e. a group name change
+    fn announcement(&mut self, message: &Message) -> Result<(), ExportError>;
+
+    /// Called once, after the last message is rendered
+    fn finish(&mut self) -> Result<(), ExportError> {
+        Ok(())
+    }
+}
+
+/// Defines behavior that runs on each attachment before it is rendered, i.e. copying or converting the file
+pub trait AttachmentHook {
+    /// Prepare an attachment that belongs to `message`
+    fn handle(
+        &mut self,
+        attachment: &mut Attachment,
+        message: &Message,
+        db: &Connection,
+    ) -> Result<(), ExportError>;
+}
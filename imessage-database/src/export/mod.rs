@@ -0,0 +1,55 @@
+/*!
+ A format-agnostic pipeline for rendering conversations, used to build custom output formats.
+
+ The [`Pipeline`](pipeline::Pipeline) walks the messages table, resolves the tapbacks and replies that belong to
+ each message, parses app balloons, and runs any [`AttachmentHook`](format::AttachmentHook)s. It then hands each
+ piece of a message to a [`Format`](format::Format), which only needs to decide how that piece looks.
+
+ Programs that walk the messages table on their own, i.e. to write each message as soon as it is rendered, can
+ use [`for_each_message`](pipeline::for_each_message) and render each message with a [`Walker`](pipeline::Walker).
+
+ Formats that would rather render whole messages than the pieces of one can implement [`Render`](document::Render)
+ instead. A [`Builder`](document::Builder) collects the pieces into a format-independent
+ [`DocumentMessage`](document::DocumentMessage) with its parts, tapbacks, and replies, and a
+ [`Document`](document::Document) holds every conversation at once.
+
+ Formats can be registered by name in a [`FormatRegistry`](registry::FormatRegistry), so a program can select
+ one at runtime, i.e. from a command line flag.
+
+ # Example
+
+ ```no_run
+ use imessage_database::{
+     error::export::ExportError,
+     export::{format::Format, pipeline::Pipeline},
+     tables::{messages::Message, table::get_connection},
+     util::{dirs::default_db_path, query_context::QueryContext},
+ };
+
+ /// Print the sender and text of every message
+ struct Plain;
+
+ impl Format for Plain {
+     fn start_message(&mut self, message: &Message, depth: usize) -> Result<(), ExportError> {
+         println!("{}{:?}: {:?}", " ".repeat(depth * 4), message.handle_id, message.text);
+         Ok(())
+     }
+
+     fn announcement(&mut self, message: &Message) -> Result<(), ExportError> {
+         println!("{:?}", message.group_title);
+         Ok(())
+     }
+ }
+
+ let db = get_connection(&default_db_path()).unwrap();
+ let mut pipeline = Pipeline::new(&db, QueryContext::default(), false).unwrap();
+ pipeline.run(&mut Plain).unwrap();
+ ```
+*/
+
+pub mod balloon;
+pub mod document;
+pub mod format;
+pub mod pipeline;
+pub mod registry;
+pub mod tapbacks;
@@ -0,0 +1,501 @@
+/*!
+ Contains logic used to walk the messages table and hand each piece of a conversation to a [`Format`].
+*/
+
+use std::borrow::Cow;
+
+use rusqlite::Connection;
+
+use crate::{
+    error::{export::ExportError, table::TableError},
+    export::{
+        balloon::Balloon,
+        format::{AttachmentHook, Format},
+        tapbacks::TapbackStore,
+    },
+    message_types::text_effects::TextEffect,
+    tables::{
+        attachment::Attachment,
+        messages::{models::BubbleComponent, Message},
+        table::Table,
+    },
+    util::query_context::QueryContext,
+};
+
+/// Walks the messages table and renders each message with a [`Format`]
+pub struct Pipeline<'a> {
+    /// Connection to the iMessage database
+    db: &'a Connection,
+    /// Filters applied to the messages that get rendered
+    context: QueryContext,
+    /// Lookup for the tapbacks that react to each message
+    tapbacks: Cow<'a, TapbackStore>,
+    /// Hooks that run on each attachment before it is rendered, in order
+    hooks: Vec<Box<dyn AttachmentHook + 'a>>,
+}
+
+impl<'a> Pipeline<'a> {
+    /// Create a pipeline that renders the messages matching `context`
+    ///
+    /// If `low_memory` is set, tapbacks are indexed in the database instead of cached in memory.
+    pub fn new(
+        db: &'a Connection,
+        context: QueryContext,
+        low_memory: bool,
+    ) -> Result<Self, ExportError> {
+        Ok(Self {
+            db,
+            context,
+            tapbacks: Cow::Owned(TapbackStore::build(db, low_memory)?),
+            hooks: vec![],
+        })
+    }
+
+    /// Create a pipeline that renders the messages matching `context` with tapbacks that were already built
+    pub fn with_tapbacks(
+        db: &'a Connection,
+        context: QueryContext,
+        tapbacks: &'a TapbackStore,
+    ) -> Self {
+        Self {
+            db,
+            context,
+            tapbacks: Cow::Borrowed(tapbacks),
+            hooks: vec![],
+        }
+    }
+
+    /// Get the connection the pipeline reads from
+    pub fn db(&self) -> &'a Connection {
+        self.db
+    }
+
+    /// Add a hook that runs on each attachment before it is rendered
+    pub fn add_attachment_hook(&mut self, hook: impl AttachmentHook + 'a) {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Render every message that matches the pipeline's [`QueryContext`]
+    ///
+    /// Tapbacks and replies are rendered with the messages they belong to instead of on their own.
+    pub fn run(&mut self, format: &mut dyn Format) -> Result<(), ExportError> {
+        format.start(self.db)?;
+
+        let Self {
+            db,
+            context,
+            tapbacks,
+            hooks,
+        } = self;
+        let mut walker = Walker {
+            db,
+            tapbacks,
+            hooks,
+        };
+
+        for_each_message(db, context, |message| {
+            if message.is_announcement() {
+                format.announcement(&message)
+            } else if !message.is_tapback() {
+                walker.render_message(&message, 0, format)
+            } else {
+                Ok(())
+            }
+        })?;
+
+        format.finish()
+    }
+}
+
+/// Call `f` with every message that matches `context`, in the order the messages should be rendered
+///
+/// Each message is passed once, with its text generated. Tapbacks and replies are passed as well, so callers
+/// that render them in context with a [`Walker`] should skip tapbacks.
+pub fn for_each_message<E: From<TableError>>(
+    db: &Connection,
+    context: &QueryContext,
+    mut f: impl FnMut(Message) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut statement = Message::stream_rows(db, context)?;
+    let messages = statement
+        .query_map([], |row| Ok(Message::from_row(row)))
+        .map_err(TableError::Messages)?;
+
+    // Keep track of the current message ROWID, as the same message may be returned more than once
+    // See https://github.com/ReagentX/imessage-exporter/issues/135 for rationale
+    let mut current_message_row = -1;
+
+    for message in messages {
+        let mut message = Message::extract(message)?;
+        if message.rowid == current_message_row {
+            continue;
+        }
+        current_message_row = message.rowid;
+
+        let _ = message.generate_text(db);
+        f(message)?;
+    }
+
+    Ok(())
+}
+
+/// Renders a single message, along with its tapbacks and replies, with a [`Format`]
+///
+/// A [`Pipeline`] uses a walker for each message it renders. Exporters that walk the messages table on their own
+/// can use one to render a message with a [`TapbackStore`] they already built.
+pub struct Walker<'p, 'a> {
+    /// Connection to the iMessage database
+    db: &'a Connection,
+    /// Lookup for the tapbacks that react to each message
+    tapbacks: &'p TapbackStore,
+    /// Hooks that run on each attachment before it is rendered, in order
+    hooks: &'p mut [Box<dyn AttachmentHook + 'a>],
+}
+
+impl<'p, 'a> Walker<'p, 'a> {
+    /// Create a walker that reads tapbacks from `tapbacks` and does not run any [`AttachmentHook`]s
+    pub fn new(db: &'a Connection, tapbacks: &'p TapbackStore) -> Self {
+        Self {
+            db,
+            tapbacks,
+            hooks: &mut [],
+        }
+    }
+
+    /// Render a message, its tapbacks, and its replies
+    ///
+    /// `depth` is passed to the [`Format`] as-is, and increases by `1` for each level of reply.
+    pub fn render_message(
+        &mut self,
+        message: &Message,
+        depth: usize,
+        format: &mut dyn Format,
+    ) -> Result<(), ExportError> {
+        format.start_message(message, depth)?;
+
+        // Genmoji are displayed inline with the text, so they are kept apart from the attachment bubbles
+        let mut all_attachments = Attachment::from_message(self.db, message)?;
+        for attachment in &mut all_attachments {
+            for hook in self.hooks.iter_mut() {
+                hook.handle(attachment, message, self.db)?;
+            }
+        }
+        let (genmoji, attachments): (Vec<_>, Vec<_>) = all_attachments
+            .into_iter()
+            .partition(Attachment::is_genmoji);
+
+        let mut replies = message.get_replies(self.db)?;
+        let tapbacks = self.tapbacks.get(message, self.db)?;
+
+        // Index of where we are in the attachment and Genmoji Vectors
+        let mut attachment_index: usize = 0;
+        let mut genmoji_index: usize = 0;
+
+        for (idx, part) in message.body().iter().enumerate() {
+            format.start_part(message, idx)?;
+            match part {
+                BubbleComponent::Text(attributes) => {
+                    if message.is_part_edited(idx) {
+                        if let Some(edited) =
+                            message.edited_parts.as_ref().and_then(|e| e.part(idx))
+                        {
+                            format.edited(message, edited, idx)?;
+                        }
+                    } else if let Some(text) = &message.text {
+                        let count = attributes
+                            .iter()
+                            .filter(|attr| matches!(attr.effect, TextEffect::Genmoji(_)))
+                            .count();
+                        let start = genmoji_index.min(genmoji.len());
+                        let end = (genmoji_index + count).min(genmoji.len());
+                        genmoji_index += count;
+                        format.text(message, text, attributes, &genmoji[start..end])?;
+                    }
+                }
+                BubbleComponent::Attachment(_) => match attachments.get(attachment_index) {
+                    Some(attachment) => {
+                        attachment_index += 1;
+                        format.attachment(message, attachment)?;
+                    }
+                    None => format.missing_attachment(message, attachment_index)?,
+                },
+                BubbleComponent::App => match Balloon::from_message(message, self.db) {
+                    Ok(balloon) => format.balloon(message, &balloon)?,
+                    Err(why) => format.balloon_error(message, &why)?,
+                },
+                BubbleComponent::Retracted => {
+                    if let Some(edited) = message.edited_parts.as_ref().and_then(|e| e.part(idx)) {
+                        format.edited(message, edited, idx)?;
+                    }
+                }
+            }
+
+            if let Some(tapbacks) = tapbacks.get(&idx) {
+                format.tapbacks(message, idx, tapbacks)?;
+            }
+
+            if let Some(replies) = replies.get_mut(&idx) {
+                for reply in replies {
+                    let _ = reply.generate_text(self.db);
+                    if !reply.is_tapback() {
+                        self.render_message(reply, depth + 1, format)?;
+                    }
+                }
+            }
+        }
+
+        format.end_message(message, depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use crate::{
+        error::{export::ExportError, plist::PlistParseError},
+        export::{
+            balloon::Balloon,
+            format::{AttachmentHook, Format},
+            pipeline::{for_each_message, Pipeline, Walker},
+            tapbacks::TapbackStore,
+        },
+        tables::{
+            attachment::Attachment, messages::models::TextAttributes, messages::Message,
+            table::get_connection,
+        },
+        util::query_context::QueryContext,
+    };
+    use imessage_fixtures::{guid, Fixture, NewAttachment, NewMessage, Schema};
+
+    /// Records the order the pipeline calls each method in
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+    }
+
+    impl Format for Recorder {
+        fn start(&mut self, _db: &Connection) -> Result<(), ExportError> {
+            self.events.push("start".to_string());
+            Ok(())
+        }
+
+        fn start_message(&mut self, message: &Message, depth: usize) -> Result<(), ExportError> {
+            self.events
+                .push(format!("message {} at {depth}", message.rowid));
+            Ok(())
+        }
+
+        fn text(
+            &mut self,
+            _message: &Message,
+            text: &str,
+            _attributes: &[TextAttributes],
+            _genmoji: &[Attachment],
+        ) -> Result<(), ExportError> {
+            self.events.push(format!("text {text}"));
+            Ok(())
+        }
+
+        fn attachment(
+            &mut self,
+            _message: &Message,
+            attachment: &Attachment,
+        ) -> Result<(), ExportError> {
+            self.events
+                .push(format!("attachment {:?}", attachment.transfer_name));
+            Ok(())
+        }
+
+        fn balloon(&mut self, _message: &Message, balloon: &Balloon) -> Result<(), ExportError> {
+            self.events.push(format!("balloon {balloon:?}"));
+            Ok(())
+        }
+
+        fn balloon_error(
+            &mut self,
+            _message: &Message,
+            why: &PlistParseError,
+        ) -> Result<(), ExportError> {
+            self.events.push(format!("balloon error {why}"));
+            Ok(())
+        }
+
+        fn tapbacks(
+            &mut self,
+            _message: &Message,
+            idx: usize,
+            tapbacks: &[Message],
+        ) -> Result<(), ExportError> {
+            self.events
+                .push(format!("{} tapbacks on {idx}", tapbacks.len()));
+            Ok(())
+        }
+
+        fn end_message(&mut self, message: &Message, _depth: usize) -> Result<(), ExportError> {
+            self.events.push(format!("end {}", message.rowid));
+            Ok(())
+        }
+
+        fn announcement(&mut self, message: &Message) -> Result<(), ExportError> {
+            self.events.push(format!("announcement {}", message.rowid));
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<(), ExportError> {
+            self.events.push("finish".to_string());
+            Ok(())
+        }
+    }
+
+    /// Renames every attachment, to show hooks run before the format sees the attachment
+    struct Rename;
+
+    impl AttachmentHook for Rename {
+        fn handle(
+            &mut self,
+            attachment: &mut Attachment,
+            _message: &Message,
+            _db: &Connection,
+        ) -> Result<(), ExportError> {
+            attachment.transfer_name = Some("renamed".to_string());
+            Ok(())
+        }
+    }
+
+    fn run(fixture: &Fixture, low_memory: bool) -> Vec<String> {
+        let db = get_connection(&fixture.db_path()).unwrap();
+        let mut pipeline = Pipeline::new(&db, QueryContext::default(), low_memory).unwrap();
+        pipeline.add_attachment_hook(Rename);
+
+        let mut recorder = Recorder::default();
+        pipeline.run(&mut recorder).unwrap();
+        recorder.events
+    }
+
+    fn conversation() -> Fixture {
+        let fixture = Fixture::temporary(Schema::latest()).unwrap();
+        let handle = fixture
+            .add_handle("+15555550101", "iMessage", None)
+            .unwrap();
+        let chat = fixture
+            .add_chat("chat0", "iMessage", None, &[handle])
+            .unwrap();
+
+        let message = |number: u64, text: &str| NewMessage {
+            guid: guid(number),
+            text: Some(text.to_string()),
+            handle_id: handle,
+            date: number as i64,
+            chat_id: Some(chat),
+            ..Default::default()
+        };
+
+        fixture.add_message(&message(1, "Hello world")).unwrap();
+        fixture
+            .add_message(&NewMessage {
+                associated_message_guid: Some(format!("p:0/{}", guid(1))),
+                associated_message_type: 2000,
+                ..message(2, "Loved “Hello world”")
+            })
+            .unwrap();
+        fixture
+            .add_message(&NewMessage {
+                thread_originator_guid: Some(guid(1)),
+                thread_originator_part: Some("0:0:11".to_string()),
+                ..message(3, "Reply")
+            })
+            .unwrap();
+        fixture
+            .add_message(&NewMessage {
+                item_type: 2,
+                group_title: Some("New name".to_string()),
+                text: None,
+                ..message(4, "")
+            })
+            .unwrap();
+        let with_file = fixture.add_message(&message(5, "\u{FFFC}")).unwrap();
+        fixture
+            .add_attachment(
+                with_file,
+                &NewAttachment {
+                    guid: "file".to_string(),
+                    transfer_name: "photo.png".to_string(),
+                    mime_type: Some("image/png".to_string()),
+                    data: Some(vec![]),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        fixture
+    }
+
+    fn expected() -> Vec<String> {
+        [
+            "start",
+            "message 1 at 0",
+            "text Hello world",
+            "1 tapbacks on 0",
+            "message 3 at 1",
+            "text Reply",
+            "end 3",
+            "end 1",
+            // Replies are also rendered in their place in the conversation
+            "message 3 at 0",
+            "text Reply",
+            "end 3",
+            "announcement 4",
+            "message 5 at 0",
+            "attachment Some(\"renamed\")",
+            "end 5",
+            "finish",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
+    #[test]
+    fn can_render_conversation() {
+        let fixture = conversation();
+
+        assert_eq!(run(&fixture, false), expected());
+    }
+
+    #[test]
+    fn can_render_conversation_low_memory() {
+        let fixture = conversation();
+
+        assert_eq!(run(&fixture, true), expected());
+    }
+
+    #[test]
+    fn can_render_each_message_with_walker() {
+        let fixture = conversation();
+        let db = get_connection(&fixture.db_path()).unwrap();
+        let tapbacks = TapbackStore::build(&db, false).unwrap();
+
+        let mut recorder = Recorder::default();
+        let mut walker = Walker::new(&db, &tapbacks);
+        for_each_message(&db, &QueryContext::default(), |message| {
+            if message.is_announcement() {
+                recorder.announcement(&message)
+            } else if !message.is_tapback() {
+                walker.render_message(&message, 0, &mut recorder)
+            } else {
+                Ok(())
+            }
+        })
+        .unwrap();
+
+        // Attachment hooks only run in a pipeline
+        let mut expected = expected();
+        expected.retain(|event| event != "start" && event != "finish");
+        expected
+            .iter_mut()
+            .filter(|event| event.starts_with("attachment"))
+            .for_each(|event| *event = "attachment Some(\"photo.png\")".to_string());
+        assert_eq!(recorder.events, expected);
+    }
+}
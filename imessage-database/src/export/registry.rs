@@ -0,0 +1,219 @@
+/*!
+ Contains a registry of output formats, so that a program can select a [`Format`] by name at runtime.
+*/
+
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{error::export::ExportError, export::format::Format};
+
+/// Builds a [`Format`] from the context `C` it borrows, which is the export directory by default
+pub type FormatFactory<C = Path> =
+    Box<dyn for<'a> Fn(&'a C) -> Result<Box<dyn Format + 'a>, ExportError>>;
+
+/// Maps the names of output formats to the functions that build them
+///
+/// By default, each name maps to a [`FormatFactory`] that builds a format for an export directory. Programs that build
+/// their formats from their own state can use a [`FormatFactory`] over that state instead, or store any other kind of
+/// factory with [`insert`](FormatRegistry::insert) and [`get`](FormatRegistry::get).
+///
+/// # Example:
+///
+/// ```
+/// use std::path::Path;
+///
+/// use imessage_database::{
+///     error::export::ExportError,
+///     export::{format::Format, registry::FormatRegistry},
+///     tables::messages::Message,
+/// };
+///
+/// struct Plain;
+///
+/// impl Format for Plain {
+///     fn start_message(&mut self, message: &Message, _: usize) -> Result<(), ExportError> {
+///         println!("{:?}", message.text);
+///         Ok(())
+///     }
+///
+///     fn announcement(&mut self, _: &Message) -> Result<(), ExportError> {
+///         Ok(())
+///     }
+/// }
+///
+/// let mut registry: FormatRegistry = FormatRegistry::new();
+/// registry.register("plain", |_| Ok(Box::new(Plain)));
+///
+/// assert!(registry.create("PLAIN", Path::new("export")).is_ok());
+/// assert!(registry.create("csv", Path::new("export")).is_err());
+/// ```
+pub struct FormatRegistry<F = FormatFactory> {
+    /// Factories keyed by their lowercase name
+    factories: BTreeMap<String, F>,
+}
+
+impl<F> Default for FormatRegistry<F> {
+    fn default() -> Self {
+        Self {
+            factories: BTreeMap::new(),
+        }
+    }
+}
+
+impl<C: ?Sized> FormatRegistry<FormatFactory<C>> {
+    /// Register a format under `name`, replacing any format already registered with that name
+    ///
+    /// Names are not case sensitive. Returns `true` if a format was replaced.
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl for<'a> Fn(&'a C) -> Result<Box<dyn Format + 'a>, ExportError> + 'static,
+    ) -> bool {
+        self.insert(name, Box::new(factory))
+    }
+
+    /// Build the format registered as `name` from `context`
+    pub fn create<'a>(
+        &self,
+        name: &str,
+        context: &'a C,
+    ) -> Result<Box<dyn Format + 'a>, ExportError> {
+        match self.get(name) {
+            Some(factory) => factory(context),
+            None => Err(ExportError::UnknownFormat(name.to_string())),
+        }
+    }
+}
+
+impl<F> FormatRegistry<F> {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store a factory under `name`, replacing any factory already stored with that name
+    ///
+    /// Names are not case sensitive. Returns `true` if a factory was replaced.
+    pub fn insert(&mut self, name: &str, factory: F) -> bool {
+        self.factories
+            .insert(name.to_lowercase(), factory)
+            .is_some()
+    }
+
+    /// Get the factory stored as `name`
+    pub fn get(&self, name: &str) -> Option<&F> {
+        self.factories.get(&name.to_lowercase())
+    }
+
+    /// Determine if a format is registered as `name`
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(&name.to_lowercase())
+    }
+
+    /// The names of every registered format, in alphabetical order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{
+        error::export::ExportError,
+        export::{
+            format::Format,
+            registry::{FormatFactory, FormatRegistry},
+        },
+        tables::messages::Message,
+    };
+
+    struct Empty;
+
+    struct Prefixed<'a>(&'a str);
+
+    impl Format for Prefixed<'_> {
+        fn start_message(&mut self, message: &Message, _: usize) -> Result<(), ExportError> {
+            println!("{}{:?}", self.0, message.text);
+            Ok(())
+        }
+
+        fn announcement(&mut self, _: &Message) -> Result<(), ExportError> {
+            Ok(())
+        }
+    }
+
+    impl Format for Empty {
+        fn start_message(&mut self, _: &Message, _: usize) -> Result<(), ExportError> {
+            Ok(())
+        }
+
+        fn announcement(&mut self, _: &Message) -> Result<(), ExportError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn can_register_format() {
+        let mut registry: FormatRegistry = FormatRegistry::new();
+
+        assert!(!registry.register("Markdown", |_| Ok(Box::new(Empty))));
+        assert!(registry.register("markdown", |_| Ok(Box::new(Empty))));
+        assert!(!registry.register("csv", |_| Ok(Box::new(Empty))));
+
+        assert!(registry.contains("MARKDOWN"));
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            vec!["csv", "markdown"]
+        );
+    }
+
+    #[test]
+    fn can_create_format() {
+        let mut registry: FormatRegistry = FormatRegistry::new();
+        registry.register("csv", |_| Ok(Box::new(Empty)));
+
+        assert!(registry.create("csv", Path::new("export")).is_ok());
+    }
+
+    #[test]
+    fn can_create_format_from_context() {
+        let mut registry: FormatRegistry<FormatFactory<str>> = FormatRegistry::new();
+        registry.register("prefixed", |prefix| Ok(Box::new(Prefixed(prefix))));
+
+        let prefix = String::from("> ");
+        assert!(registry.create("Prefixed", prefix.as_str()).is_ok());
+    }
+
+    #[test]
+    fn cant_create_unknown_format() {
+        let registry: FormatRegistry = FormatRegistry::new();
+
+        assert!(matches!(
+            registry.create("csv", Path::new("export")),
+            Err(ExportError::UnknownFormat(name)) if name == "csv"
+        ));
+    }
+
+    #[test]
+    fn can_get_custom_factory() {
+        let mut registry: FormatRegistry<fn() -> &'static str> = FormatRegistry::new();
+        registry.insert("csv", || "a,b,c");
+
+        assert_eq!(registry.get("CSV").map(|factory| factory()), Some("a,b,c"));
+        assert!(registry.get("markdown").is_none());
+    }
+
+    #[test]
+    fn cant_create_failing_format() {
+        let mut registry: FormatRegistry = FormatRegistry::new();
+        registry.register("broken", |path| {
+            Err(ExportError::Format(format!(
+                "{} is read only",
+                path.display()
+            )))
+        });
+
+        assert!(registry.create("broken", Path::new("export")).is_err());
+    }
+}
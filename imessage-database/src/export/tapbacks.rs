@@ -0,0 +1,62 @@
+/*!
+ Contains data structures used to look up the tapbacks that react to a message.
+*/
+
+use std::collections::HashMap;
+
+use rusqlite::Connection;
+
+use crate::{
+    error::table::TableError,
+    tables::{messages::Message, table::Cacheable},
+};
+
+/// Represents where the tapbacks for an export are stored
+#[derive(Clone)]
+pub enum TapbackStore {
+    /// Every tapback is cached in memory, keyed by the GUID of the message it reacts to
+    Memory(HashMap<String, HashMap<usize, Vec<Message>>>),
+    /// Tapbacks are indexed in a temporary table and read from the database as needed
+    Indexed,
+}
+
+impl TapbackStore {
+    /// Build the tapback store, indexing tapbacks on disk if `low_memory` is set
+    pub fn build(db: &Connection, low_memory: bool) -> Result<Self, TableError> {
+        if low_memory {
+            Message::index_tapbacks(db)?;
+            return Ok(Self::Indexed);
+        }
+        Ok(Self::Memory(Message::cache(db)?))
+    }
+
+    /// Get the tapbacks that react to a message
+    pub fn get<'a>(
+        &'a self,
+        message: &Message,
+        db: &Connection,
+    ) -> Result<Tapbacks<'a>, TableError> {
+        match self {
+            TapbackStore::Memory(map) => Ok(Tapbacks::Cached(map.get(&message.guid))),
+            TapbackStore::Indexed => Ok(Tapbacks::Queried(message.get_indexed_tapbacks(db)?)),
+        }
+    }
+}
+
+/// The tapbacks that react to a single message, keyed by the index of the message component they react to
+pub enum Tapbacks<'a> {
+    /// Tapbacks borrowed from [`TapbackStore::Memory`]
+    Cached(Option<&'a HashMap<usize, Vec<Message>>>),
+    /// Tapbacks read from [`TapbackStore::Indexed`]
+    Queried(HashMap<usize, Vec<Message>>),
+}
+
+impl Tapbacks<'_> {
+    /// Get the tapbacks that react to the message component at `idx`
+    pub fn get(&self, idx: &usize) -> Option<&Vec<Message>> {
+        match self {
+            Tapbacks::Cached(map) => map.and_then(|map| map.get(idx)),
+            Tapbacks::Queried(map) => map.get(idx),
+        }
+    }
+}
@@ -2,6 +2,7 @@
 #![doc = include_str!("../README.md")]
 
 pub mod error;
+pub mod export;
 pub mod message_types;
 pub mod tables;
 pub mod util;
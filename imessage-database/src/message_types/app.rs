@@ -6,7 +6,7 @@
 use std::collections::HashMap;
 
 use plist::Value;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     error::plist::PlistParseError,
@@ -40,6 +40,56 @@ pub struct AppMessage<'a> {
     pub ldtext: Option<&'a str>,
 }
 
+/// An owned version of [`AppMessage`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnedAppMessage {
+    /// An image used to represent the message in the transcript
+    pub image: Option<String>,
+    /// A URL pointing to a media file used to represent the message in the transcript
+    pub url: Option<String>,
+    /// The title for the image or media file
+    pub title: Option<String>,
+    /// The subtitle for the image or media file
+    pub subtitle: Option<String>,
+    /// A left-aligned caption for the message bubble
+    pub caption: Option<String>,
+    /// A left-aligned subcaption for the message bubble
+    pub subcaption: Option<String>,
+    /// A right-aligned caption for the message bubble
+    pub trailing_caption: Option<String>,
+    /// A right-aligned subcaption for the message bubble
+    pub trailing_subcaption: Option<String>,
+    /// The name of the app that created this message
+    pub app_name: Option<String>,
+    /// This property is set only for Apple system messages,
+    /// it represents the text that displays in the center of the bubble
+    pub ldtext: Option<String>,
+}
+
+impl From<&AppMessage<'_>> for OwnedAppMessage {
+    fn from(message: &AppMessage<'_>) -> Self {
+        Self {
+            image: message.image.map(str::to_string),
+            url: message.url.map(str::to_string),
+            title: message.title.map(str::to_string),
+            subtitle: message.subtitle.map(str::to_string),
+            caption: message.caption.map(str::to_string),
+            subcaption: message.subcaption.map(str::to_string),
+            trailing_caption: message.trailing_caption.map(str::to_string),
+            trailing_subcaption: message.trailing_subcaption.map(str::to_string),
+            app_name: message.app_name.map(str::to_string),
+            ldtext: message.ldtext.map(str::to_string),
+        }
+    }
+}
+
+impl OwnedAppMessage {
+    /// Parse key/value pairs from the query string in the balloon's a URL
+    pub fn parse_query_string(&self) -> HashMap<&str, &str> {
+        parse_query_string(self.url.as_deref())
+    }
+}
+
 impl<'a> BalloonProvider<'a> for AppMessage<'a> {
     fn from_map(payload: &'a Value) -> Result<Self, PlistParseError> {
         let user_info = payload
@@ -67,22 +117,27 @@ impl<'a> BalloonProvider<'a> for AppMessage<'a> {
 impl<'a> AppMessage<'a> {
     /// Parse key/value pairs from the query string in the balloon's a URL
     pub fn parse_query_string(&self) -> HashMap<&str, &str> {
-        let mut map = HashMap::new();
-
-        if let Some(url) = self.url {
-            if url.starts_with('?') {
-                let parts = url.strip_prefix('?').unwrap_or(url).split('&');
-                for part in parts {
-                    let key_val_split: Vec<&str> = part.split('=').collect();
-                    if key_val_split.len() == 2 {
-                        map.insert(key_val_split[0], key_val_split[1]);
-                    }
+        parse_query_string(self.url)
+    }
+}
+
+/// Parse key/value pairs from a query string, i.e. `?key=value&other=value`
+fn parse_query_string(url: Option<&str>) -> HashMap<&str, &str> {
+    let mut map = HashMap::new();
+
+    if let Some(url) = url {
+        if url.starts_with('?') {
+            let parts = url.strip_prefix('?').unwrap_or(url).split('&');
+            for part in parts {
+                let key_val_split: Vec<&str> = part.split('=').collect();
+                if key_val_split.len() == 2 {
+                    map.insert(key_val_split[0], key_val_split[1]);
                 }
             }
         }
-
-        map
     }
+
+    map
 }
 
 #[cfg(test)]
@@ -3,7 +3,7 @@
 */
 
 use plist::Value;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     error::plist::PlistParseError,
@@ -29,6 +29,36 @@ pub struct AppStoreMessage<'a> {
     pub genre: Option<&'a str>,
 }
 
+/// An owned version of [`AppStoreMessage`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnedAppStoreMessage {
+    /// The URL that ended up serving content, after all redirects
+    pub url: Option<String>,
+    /// The original url, before any redirects
+    pub original_url: Option<String>,
+    /// The full name of the app in the App Store
+    pub app_name: Option<String>,
+    /// The short description of the app in the App Store
+    pub description: Option<String>,
+    /// The platform the app is compiled for
+    pub platform: Option<String>,
+    /// The app's genre
+    pub genre: Option<String>,
+}
+
+impl From<&AppStoreMessage<'_>> for OwnedAppStoreMessage {
+    fn from(message: &AppStoreMessage<'_>) -> Self {
+        Self {
+            url: message.url.map(str::to_string),
+            original_url: message.original_url.map(str::to_string),
+            app_name: message.app_name.map(str::to_string),
+            description: message.description.map(str::to_string),
+            platform: message.platform.map(str::to_string),
+            genre: message.genre.map(str::to_string),
+        }
+    }
+}
+
 impl<'a> BalloonProvider<'a> for AppStoreMessage<'a> {
     fn from_map(payload: &'a Value) -> Result<Self, PlistParseError> {
         if let Ok((app_metadata, body)) = AppStoreMessage::get_body_and_url(payload) {
@@ -3,7 +3,7 @@
 */
 
 use plist::Value;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     error::plist::PlistParseError,
@@ -29,6 +29,43 @@ pub struct CollaborationMessage<'a> {
     pub app_name: Option<&'a str>,
 }
 
+/// An owned version of [`CollaborationMessage`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnedCollaborationMessage {
+    /// The URL the user interacts with to start the share session
+    pub original_url: Option<String>,
+    /// The unique URL for the collaboration item
+    pub url: Option<String>,
+    /// The title of the shared file
+    pub title: Option<String>,
+    /// The date the session was initiated
+    pub creation_date: Option<f64>,
+    /// The Bundle ID of the application that generated the message
+    pub bundle_id: Option<String>,
+    /// The name of the application that generated the message
+    pub app_name: Option<String>,
+}
+
+impl From<&CollaborationMessage<'_>> for OwnedCollaborationMessage {
+    fn from(message: &CollaborationMessage<'_>) -> Self {
+        Self {
+            original_url: message.original_url.map(str::to_string),
+            url: message.url.map(str::to_string),
+            title: message.title.map(str::to_string),
+            creation_date: message.creation_date,
+            bundle_id: message.bundle_id.map(str::to_string),
+            app_name: message.app_name.map(str::to_string),
+        }
+    }
+}
+
+impl OwnedCollaborationMessage {
+    /// Get the redirected URL from a URL message, falling back to the original URL, if it exists
+    pub fn get_url(&self) -> Option<&str> {
+        self.url.as_deref().or(self.original_url.as_deref())
+    }
+}
+
 impl<'a> BalloonProvider<'a> for CollaborationMessage<'a> {
     fn from_map(payload: &'a Value) -> Result<Self, PlistParseError> {
         if let Ok((meta, base)) = CollaborationMessage::get_meta_and_specialization(payload) {
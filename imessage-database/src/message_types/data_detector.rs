@@ -0,0 +1,155 @@
+/*!
+ These are the results of Apple's data detectors, which find addresses, dates, phone numbers, and more in message text.
+
+ Each result is stored in a message's `attributedBody` as an `NSKeyedArchiver` document containing a `DDScannerResult`,
+ under a key like `__kIMDataDetectedAttributeName`, `__kIMPhoneNumberAttributeName`, or `__kIMCalendarEventAttributeName`.
+*/
+
+use std::io::Cursor;
+
+use plist::Value;
+
+use crate::{
+    message_types::text_effects::TextEffect,
+    util::plist::{extract_array_key, extract_dictionary, extract_uid_key},
+};
+
+/// A single data detector result
+#[derive(Debug, PartialEq, Eq)]
+pub struct DataDetectorResult {
+    /// The type of data that was detected, i.e. `PhoneNumber` or `FullAddress`
+    pub kind: String,
+    /// The text the data was detected in
+    pub matched: String,
+}
+
+impl DataDetectorResult {
+    /// Parse the `NSKeyedArchiver` document stored under a data detector key
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use imessage_database::message_types::data_detector::DataDetectorResult;
+    ///
+    /// let result = DataDetectorResult::from_archive(&[]); // None
+    /// ```
+    pub fn from_archive(bytes: &[u8]) -> Option<Self> {
+        let plist = Value::from_reader(Cursor::new(bytes)).ok()?;
+        let body = plist.as_dictionary()?;
+        let objects = extract_array_key(body, "$objects").ok()?;
+
+        // The top of the document points to the scanner result
+        let root = extract_uid_key(extract_dictionary(body, "$top").ok()?, "dd-result").ok()?;
+        let result = objects.get(root)?.as_dictionary()?;
+
+        let get_string = |key: &str| -> Option<String> {
+            let idx = extract_uid_key(result, key).ok()?;
+            Some(objects.get(idx)?.as_string()?.to_string())
+        };
+
+        Some(DataDetectorResult {
+            kind: get_string("T")?,
+            matched: get_string("MS")?,
+        })
+    }
+
+    /// Get the [`TextEffect`] for the detected data, if it is a supported kind
+    pub fn into_effect(self) -> Option<TextEffect<'static>> {
+        match self.kind.as_str() {
+            "FullAddress" | "Address" | "PostalAddress" => Some(TextEffect::Address(self.matched)),
+            "Date" | "Time" | "DateTime" | "DateDuration" | "TimeDuration" | "Event" => {
+                Some(TextEffect::Event(self.matched))
+            }
+            "PhoneNumber" => Some(TextEffect::PhoneNumber(self.matched)),
+            "FlightInformation" | "FlightNumber" => Some(TextEffect::FlightNumber(self.matched)),
+            "TrackingNumber" | "ShipmentTrackingNumber" => {
+                Some(TextEffect::TrackingNumber(self.matched))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::message_types::{data_detector::DataDetectorResult, text_effects::TextEffect};
+
+    /// Build a minimal `NSKeyedArchiver` document containing a `DDScannerResult`
+    fn archive(kind: &str, matched: &str) -> Vec<u8> {
+        use plist::{Dictionary, Uid, Value};
+
+        let mut result = Dictionary::new();
+        result.insert("T".to_string(), Value::Uid(Uid::new(2)));
+        result.insert("MS".to_string(), Value::Uid(Uid::new(3)));
+
+        let mut top = Dictionary::new();
+        top.insert("dd-result".to_string(), Value::Uid(Uid::new(1)));
+
+        let mut body = Dictionary::new();
+        body.insert("$top".to_string(), Value::Dictionary(top));
+        body.insert(
+            "$objects".to_string(),
+            Value::Array(vec![
+                Value::String("$null".to_string()),
+                Value::Dictionary(result),
+                Value::String(kind.to_string()),
+                Value::String(matched.to_string()),
+            ]),
+        );
+
+        let mut bytes = vec![];
+        Value::Dictionary(body)
+            .to_writer_binary(&mut bytes)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn can_parse_archive() {
+        let result = DataDetectorResult::from_archive(&archive("PhoneNumber", "0000000000"));
+        assert_eq!(
+            result,
+            Some(DataDetectorResult {
+                kind: "PhoneNumber".to_string(),
+                matched: "0000000000".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn cant_parse_archive_invalid() {
+        assert_eq!(DataDetectorResult::from_archive(&[0, 1, 2]), None);
+    }
+
+    #[test]
+    fn can_get_effects() {
+        let effect = |kind: &str| {
+            DataDetectorResult::from_archive(&archive(kind, "text"))
+                .and_then(DataDetectorResult::into_effect)
+        };
+
+        assert_eq!(
+            effect("FullAddress"),
+            Some(TextEffect::Address("text".to_string()))
+        );
+        assert_eq!(effect("Date"), Some(TextEffect::Event("text".to_string())));
+        assert_eq!(
+            effect("PhoneNumber"),
+            Some(TextEffect::PhoneNumber("text".to_string()))
+        );
+        assert_eq!(
+            effect("FlightInformation"),
+            Some(TextEffect::FlightNumber("text".to_string()))
+        );
+        assert_eq!(
+            effect("TrackingNumber"),
+            Some(TextEffect::TrackingNumber("text".to_string()))
+        );
+    }
+
+    #[test]
+    fn cant_get_effect_unsupported() {
+        let result = DataDetectorResult::from_archive(&archive("HttpURL", "https://example.com"));
+        assert_eq!(result.and_then(DataDetectorResult::into_effect), None);
+    }
+}
@@ -3,108 +3,627 @@ Parser for [Digital Touch](https://support.apple.com/guide/ipod-touch/send-a-dig
 This message type is not documented by Apple, but represents messages displayed as `com.apple.DigitalTouchBalloonProvider`.
 */
 
-use crate::message_types::digital_touch::digital_touch_proto::{
-    BaseMessage, TouchKind as DigitalTouch,
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::{
+    error::digital_touch::DigitalTouchError,
+    message_types::digital_touch::digital_touch_proto::{
+        BaseMessage, FireballMessage, HeartbeatMessage, KissMessage, SketchMessage, TapMessage,
+        TouchKind,
+    },
 };
 
 use protobuf::Message;
 
+/// The width and height of the canvas Digital Touch messages are rendered to
+const CANVAS_SIZE: f64 = 1000.;
+/// The radius of a rendered tap
+const TAP_RADIUS: f64 = 60.;
+/// How long a rendered tap takes to appear, in seconds
+const TAP_DURATION: f64 = 0.5;
+/// The width of a rendered sketch stroke
+const STROKE_WIDTH: f64 = 12.;
+
+/// A Digital Touch message and the data needed to render it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum DigitalTouch {
+    Tap(Tap),
+    Heartbeat(Heartbeat),
+    Sketch(Sketch),
+    Kiss(Kiss),
+    Fireball(Fireball),
+}
+
+/// A color a Digital Touch element was drawn with
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+/// A location on the Digital Touch canvas
+///
+/// Both coordinates are scaled so that `0` is the top or left edge and [`u16::MAX`] is the bottom or right edge.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub struct Point {
+    pub x: u16,
+    pub y: u16,
+}
+
+/// One or more taps on the canvas
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Tap {
+    pub id: String,
+    pub taps: Vec<TapPoint>,
+}
+
+/// A single tap on the canvas
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TapPoint {
+    pub location: Point,
+    /// Milliseconds between the previous tap and this one
+    pub delay: u16,
+    pub color: Color,
+}
+
+/// A drawing made of one or more strokes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Sketch {
+    pub id: String,
+    pub strokes: Vec<Stroke>,
+}
+
+/// A single line in a sketch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Stroke {
+    pub color: Color,
+    pub points: Vec<Point>,
+}
+
+/// A heartbeat, or a heartbreak if the heart was broken partway through
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Heartbeat {
+    pub id: String,
+    /// Beats per minute of the recorded heartbeat
+    pub bpm: f32,
+    /// Length of the recording, in seconds
+    pub duration: u64,
+    /// Seconds into the recording that the heart was broken, if it was
+    pub broken_at: Option<f32>,
+}
+
+/// One or more kisses placed on the canvas
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Kiss {
+    pub id: String,
+    pub kisses: Vec<KissPoint>,
+}
+
+/// A single kiss on the canvas
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KissPoint {
+    pub location: Point,
+    /// Milliseconds between the previous kiss and this one
+    pub delay: u16,
+    /// Rotation of the kiss, scaled so that [`u16::MAX`] is a full turn
+    pub rotation: u16,
+}
+
+/// A fireball that was held on the canvas
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Fireball {
+    pub id: String,
+    /// Length of the recording, in seconds
+    pub duration: f32,
+    /// Horizontal start of the fireball, from `-1` at the left edge to `1` at the right edge
+    pub start_x: f32,
+    /// Vertical start of the fireball, from `-1` at the top edge to `1` at the bottom edge
+    pub start_y: f32,
+    /// The path the fireball was dragged along
+    pub path: Vec<TapPoint>,
+}
+
 /// Converts a raw byte payload from the database into a [`DigitalTouch`].
-pub fn from_payload(payload: &[u8]) -> Option<DigitalTouch> {
-    let msg = BaseMessage::parse_from_bytes(payload).ok()?;
+pub fn from_payload(payload: &[u8]) -> Result<DigitalTouch, DigitalTouchError> {
+    let msg = BaseMessage::parse_from_bytes(payload).map_err(DigitalTouchError::ProtobufError)?;
+    let id = msg.ID.to_string();
+    let data = &msg.TouchPayload;
+
+    match msg.TouchKind.enum_value() {
+        Ok(TouchKind::Tap) => {
+            let tap =
+                TapMessage::parse_from_bytes(data).map_err(DigitalTouchError::ProtobufError)?;
+            Ok(DigitalTouch::Tap(Tap {
+                id,
+                taps: parse_timed_points(&tap.Location, &tap.Delays, &tap.Color)?,
+            }))
+        }
+        Ok(TouchKind::Heartbeat) => {
+            let heartbeat = HeartbeatMessage::parse_from_bytes(data)
+                .map_err(DigitalTouchError::ProtobufError)?;
+            Ok(DigitalTouch::Heartbeat(Heartbeat {
+                id,
+                bpm: heartbeat.BPM,
+                duration: heartbeat.Duration,
+                broken_at: Some(heartbeat.HeartBrokenAt).filter(|time| *time > 0.),
+            }))
+        }
+        Ok(TouchKind::Sketch) => {
+            let sketch =
+                SketchMessage::parse_from_bytes(data).map_err(DigitalTouchError::ProtobufError)?;
+            Ok(DigitalTouch::Sketch(Sketch {
+                id,
+                strokes: parse_strokes(&sketch)?,
+            }))
+        }
+        Ok(TouchKind::Kiss) => {
+            let kiss =
+                KissMessage::parse_from_bytes(data).map_err(DigitalTouchError::ProtobufError)?;
+            let points = parse_points("Points", &kiss.Points)?;
+            let delays = parse_u16s("Delays", &kiss.Delays)?;
+            let rotations = parse_u16s("Rotations", &kiss.Rotations)?;
+            Ok(DigitalTouch::Kiss(Kiss {
+                id,
+                kisses: points
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, location)| KissPoint {
+                        location,
+                        delay: delays.get(idx).copied().unwrap_or_default(),
+                        rotation: rotations.get(idx).copied().unwrap_or_default(),
+                    })
+                    .collect(),
+            }))
+        }
+        Ok(TouchKind::Fireball) => {
+            let fireball = FireballMessage::parse_from_bytes(data)
+                .map_err(DigitalTouchError::ProtobufError)?;
+            Ok(DigitalTouch::Fireball(Fireball {
+                id,
+                duration: fireball.Duration,
+                start_x: fireball.StartX,
+                start_y: fireball.StartY,
+                path: parse_timed_points(&fireball.Points, &fireball.Delays, &[])?,
+            }))
+        }
+        Ok(TouchKind::Unknown) => Err(DigitalTouchError::UnknownKind(0)),
+        Err(kind) => Err(DigitalTouchError::UnknownKind(kind)),
+    }
+}
+
+impl DigitalTouch {
+    /// The kind of Digital Touch message, as displayed to the user
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DigitalTouch::Tap(_) => "Tap",
+            DigitalTouch::Heartbeat(heartbeat) => match heartbeat.broken_at {
+                Some(_) => "Heartbreak",
+                None => "Heartbeat",
+            },
+            DigitalTouch::Sketch(_) => "Sketch",
+            DigitalTouch::Kiss(_) => "Kiss",
+            DigitalTouch::Fireball(_) => "Fireball",
+        }
+    }
+
+    /// Describes the contents of the message in a short sentence
+    pub fn describe(&self) -> String {
+        match self {
+            DigitalTouch::Tap(tap) => format!("Tapped {}", plural(tap.taps.len(), "time")),
+            DigitalTouch::Heartbeat(heartbeat) => {
+                let mut out_s = format!(
+                    "{} at {} BPM for {}",
+                    self.kind(),
+                    heartbeat.bpm,
+                    plural(heartbeat.duration as usize, "second")
+                );
+                if let Some(broken_at) = heartbeat.broken_at {
+                    let _ = write!(out_s, ", broken after {broken_at:.2} seconds");
+                }
+                out_s
+            }
+            DigitalTouch::Sketch(sketch) => {
+                format!("Sketch with {}", plural(sketch.strokes.len(), "stroke"))
+            }
+            DigitalTouch::Kiss(kiss) => format!("Sent {}", plural(kiss.kisses.len(), "kiss")),
+            DigitalTouch::Fireball(fireball) => {
+                format!("Fireball for {:.2} seconds", fireball.duration)
+            }
+        }
+    }
+
+    /// Renders the message as an `svg` graphic, if it is a kind that can be drawn
+    ///
+    /// Taps are animated using the delays between them.
+    pub fn render_svg(&self) -> Option<String> {
+        let (id, body) = match self {
+            DigitalTouch::Tap(tap) => (&tap.id, render_taps(&tap.taps)),
+            DigitalTouch::Sketch(sketch) => (&sketch.id, render_strokes(&sketch.strokes)),
+            _ => return None,
+        };
+
+        let mut svg = String::new();
+        svg.push('\n');
+        let _ = writeln!(
+            svg,
+            r#"<svg viewBox="0 0 {CANVAS_SIZE} {CANVAS_SIZE}" preserveAspectRatio="xMidYMid meet" width="100%" height="100%" xmlns="http://www.w3.org/2000/svg">"#
+        );
+        let _ = writeln!(svg, "<title>{id}</title>");
+        svg.push_str("<metadata>\n");
+        let _ = writeln!(svg, "<id>{id}</id>");
+        let _ = writeln!(svg, "<kind>{}</kind>", self.kind());
+        svg.push_str("</metadata>\n");
+        svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"black\"/>\n");
+        svg.push_str(&body);
+        svg.push_str("</svg>\n");
+        Some(svg)
+    }
+}
+
+impl Color {
+    /// The color as an `svg` hex string, ignoring the alpha channel
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+
+    /// The alpha channel as a fraction from `0` to `1`
+    pub fn opacity(&self) -> f64 {
+        f64::from(self.alpha) / f64::from(u8::MAX)
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Self {
+            red: u8::MAX,
+            green: u8::MAX,
+            blue: u8::MAX,
+            alpha: u8::MAX,
+        }
+    }
+}
+
+impl Point {
+    /// The coordinates of the point on a canvas of [`CANVAS_SIZE`]
+    fn scaled(&self) -> (f64, f64) {
+        let scale = |v: u16| f64::from(v) * CANVAS_SIZE / f64::from(u16::MAX);
+        (scale(self.x), scale(self.y))
+    }
+}
+
+/// Formats `count` with a noun, adding an `s` or `es` if there is more than one
+fn plural(count: usize, noun: &str) -> String {
+    match (count, noun.ends_with('s')) {
+        (1, _) => format!("{count} {noun}"),
+        (_, true) => format!("{count} {noun}es"),
+        (_, false) => format!("{count} {noun}s"),
+    }
+}
+
+/// Generates animated `svg` circles for an array of taps.
+fn render_taps(taps: &[TapPoint]) -> String {
+    let mut svg = String::new();
+    let mut begin = 0.;
+    for tap in taps {
+        begin += f64::from(tap.delay) / 1000.;
+        let (x, y) = tap.location.scaled();
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{x:.1}" cy="{y:.1}" r="0" fill="{}" fill-opacity="{:.2}"><animate attributeName="r" values="0;{:.1};{TAP_RADIUS:.1}" dur="{TAP_DURATION}s" begin="{begin:.3}s" fill="freeze"/></circle>"#,
+            tap.color.to_hex(),
+            tap.color.opacity(),
+            TAP_RADIUS * 1.5,
+        );
+    }
+    svg
+}
+
+/// Generates `svg` lines for an array of strokes.
+fn render_strokes(strokes: &[Stroke]) -> String {
+    let mut svg = String::new();
+    for stroke in strokes {
+        let points = stroke
+            .points
+            .iter()
+            .map(|point| {
+                let (x, y) = point.scaled();
+                format!("{x:.1},{y:.1}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{points}" fill="none" stroke="{}" stroke-opacity="{:.2}" stroke-width="{STROKE_WIDTH}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+            stroke.color.to_hex(),
+            stroke.color.opacity(),
+        );
+    }
+    svg
+}
+
+/// Ensures `data` can be split into chunks of `size` bytes
+fn check_length(field: &'static str, data: &[u8], size: usize) -> Result<(), DigitalTouchError> {
+    if !data.len().is_multiple_of(size) {
+        return Err(DigitalTouchError::InvalidFieldLength(
+            field,
+            data.len(),
+            size,
+        ));
+    }
+    Ok(())
+}
+
+/// Parses an array of little-endian `u16` values
+fn parse_u16s(field: &'static str, data: &[u8]) -> Result<Vec<u16>, DigitalTouchError> {
+    check_length(field, data, 2)?;
+    Ok(data
+        .chunks_exact(2)
+        .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+        .collect())
+}
+
+/// Parses an array of points, each stored as a little-endian `u16` `x` and `y`
+fn parse_points(field: &'static str, data: &[u8]) -> Result<Vec<Point>, DigitalTouchError> {
+    Ok(parse_u16s(field, data)?
+        .chunks_exact(2)
+        .map(|chunk| Point {
+            x: chunk[0],
+            y: chunk[1],
+        })
+        .collect())
+}
+
+/// Parses an array of colors, each stored as `RGBA` bytes
+fn parse_colors(data: &[u8]) -> Result<Vec<Color>, DigitalTouchError> {
+    check_length("Colors", data, 4)?;
+    Ok(data
+        .chunks_exact(4)
+        .map(|chunk| Color {
+            red: chunk[0],
+            green: chunk[1],
+            blue: chunk[2],
+            alpha: chunk[3],
+        })
+        .collect())
+}
+
+/// Parses points that are drawn one after another, such as taps
+///
+/// If there are fewer colors than points, the last color is reused.
+fn parse_timed_points(
+    points: &[u8],
+    delays: &[u8],
+    colors: &[u8],
+) -> Result<Vec<TapPoint>, DigitalTouchError> {
+    check_length("Location", points, 4)?;
+    let delays = parse_u16s("Delays", delays)?;
+    let colors = parse_colors(colors)?;
+    Ok(parse_points("Location", points)?
+        .into_iter()
+        .enumerate()
+        .map(|(idx, location)| TapPoint {
+            location,
+            delay: delays.get(idx).copied().unwrap_or_default(),
+            color: colors
+                .get(idx)
+                .or(colors.last())
+                .copied()
+                .unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Parses raw stroke data into an array of strokes.
+///
+/// Each stroke starts with two `u16` values; the second is the number of points that follow.
+fn parse_strokes(sketch: &SketchMessage) -> Result<Vec<Stroke>, DigitalTouchError> {
+    let values = parse_u16s("Strokes", &sketch.Strokes)?;
+    let colors = parse_colors(&sketch.Colors)?;
+    let count = usize::try_from(sketch.StrokesCount).unwrap_or_default();
 
-    Some(msg.TouchKind.enum_value_or_default())
+    let mut strokes = Vec::with_capacity(count.min(colors.len().max(1)));
+    let mut idx = 0;
+    while idx < values.len() {
+        let length = *values
+            .get(idx + 1)
+            .ok_or(DigitalTouchError::InvalidStrokesLength(
+                idx + 1,
+                values.len(),
+            ))? as usize;
+        let start = idx + 2;
+        let end = start + length * 2;
+        let points = values
+            .get(start..end)
+            .ok_or(DigitalTouchError::InvalidStrokesLength(end, values.len()))?;
+        strokes.push(Stroke {
+            color: colors
+                .get(strokes.len())
+                .or(colors.last())
+                .copied()
+                .unwrap_or_default(),
+            points: points
+                .chunks_exact(2)
+                .map(|chunk| Point {
+                    x: chunk[0],
+                    y: chunk[1],
+                })
+                .collect(),
+        });
+        idx = end;
+    }
+    Ok(strokes)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::message_types::digital_touch::{from_payload, DigitalTouch};
+    use crate::message_types::digital_touch::{
+        from_payload,
+        models::{Color, DigitalTouch, Point},
+    };
 
     use std::env::current_dir;
     use std::fs::File;
     use std::io::Read;
 
-    #[test]
-    fn can_parse_tap() {
+    fn read(name: &str) -> Vec<u8> {
         let protobuf_path = current_dir()
             .unwrap()
             .as_path()
-            .join("test_data/digital_touch_message/tap.bin");
+            .join("test_data/digital_touch_message")
+            .join(name);
         let mut proto_data = File::open(protobuf_path).unwrap();
         let mut data = vec![];
         proto_data.read_to_end(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn can_parse_tap() {
+        let data = read("tap.bin");
 
-        let expected = from_payload(&data);
-        assert_eq!(expected, Some(DigitalTouch::Tap));
+        let DigitalTouch::Tap(tap) = from_payload(&data).unwrap() else {
+            panic!("Expected tap!")
+        };
+        assert_eq!(tap.id, "E3F4E72A-A863-43C3-8277-E17680251B06");
+        assert_eq!(tap.taps.len(), 1);
+        assert_eq!(tap.taps[0].location, Point { x: 30809, y: 37418 });
+        assert_eq!(tap.taps[0].delay, 0);
+        assert_eq!(
+            tap.taps[0].color,
+            Color {
+                red: 255,
+                green: 0,
+                blue: 252,
+                alpha: 255
+            }
+        );
     }
 
     #[test]
     fn can_parse_heartbeat() {
-        let protobuf_path = current_dir()
-            .unwrap()
-            .as_path()
-            .join("test_data/digital_touch_message/heartbeat.bin");
-        let mut proto_data = File::open(protobuf_path).unwrap();
-        let mut data = vec![];
-        proto_data.read_to_end(&mut data).unwrap();
+        let data = read("heartbeat.bin");
 
-        let expected = from_payload(&data);
-        assert_eq!(expected, Some(DigitalTouch::Heartbeat));
+        let DigitalTouch::Heartbeat(heartbeat) = from_payload(&data).unwrap() else {
+            panic!("Expected heartbeat!")
+        };
+        assert_eq!(heartbeat.bpm, 84.);
+        assert_eq!(heartbeat.duration, 2);
+        assert_eq!(heartbeat.broken_at, None);
     }
 
     #[test]
     fn can_parse_heartbreak() {
-        let protobuf_path = current_dir()
-            .unwrap()
-            .as_path()
-            .join("test_data/digital_touch_message/heartbreak.bin");
-        let mut proto_data = File::open(protobuf_path).unwrap();
-        let mut data = vec![];
-        proto_data.read_to_end(&mut data).unwrap();
+        let data = read("heartbreak.bin");
 
-        let expected = from_payload(&data);
-        assert_eq!(expected, Some(DigitalTouch::Heartbeat));
+        let DigitalTouch::Heartbeat(heartbeat) = from_payload(&data).unwrap() else {
+            panic!("Expected heartbeat!")
+        };
+        assert_eq!(heartbeat.bpm, 84.);
+        assert_eq!(heartbeat.duration, 2);
+        assert!(heartbeat.broken_at.is_some_and(|time| time > 1.7));
     }
 
     #[test]
     fn can_parse_sketch() {
-        let protobuf_path = current_dir()
-            .unwrap()
-            .as_path()
-            .join("test_data/digital_touch_message/sketch.bin");
-        let mut proto_data = File::open(protobuf_path).unwrap();
-        let mut data = vec![];
-        proto_data.read_to_end(&mut data).unwrap();
+        let data = read("sketch.bin");
 
-        let expected = from_payload(&data);
-        assert_eq!(expected, Some(DigitalTouch::Sketch));
+        let DigitalTouch::Sketch(sketch) = from_payload(&data).unwrap() else {
+            panic!("Expected sketch!")
+        };
+        assert_eq!(sketch.strokes.len(), 1);
+        assert_eq!(sketch.strokes[0].points.len(), 81);
+        assert_eq!(sketch.strokes[0].points[0], Point { x: 14168, y: 43154 });
+        assert_eq!(sketch.strokes[0].color.to_hex(), "#ff00fc");
     }
 
     #[test]
     fn can_parse_kiss() {
-        let protobuf_path = current_dir()
-            .unwrap()
-            .as_path()
-            .join("test_data/digital_touch_message/kiss.bin");
-        let mut proto_data = File::open(protobuf_path).unwrap();
-        let mut data = vec![];
-        proto_data.read_to_end(&mut data).unwrap();
+        let data = read("kiss.bin");
 
-        let expected = from_payload(&data);
-        assert_eq!(expected, Some(DigitalTouch::Kiss));
+        let DigitalTouch::Kiss(kiss) = from_payload(&data).unwrap() else {
+            panic!("Expected kiss!")
+        };
+        assert_eq!(kiss.kisses.len(), 1);
+        assert_eq!(kiss.kisses[0].location, Point { x: 33913, y: 34117 });
+        assert_eq!(kiss.kisses[0].rotation, 294);
     }
 
     #[test]
     fn can_parse_fireball() {
-        let protobuf_path = current_dir()
+        let data = read("fireball.bin");
+
+        let DigitalTouch::Fireball(fireball) = from_payload(&data).unwrap() else {
+            panic!("Expected fireball!")
+        };
+        assert_eq!(fireball.path.len(), 3);
+        assert_eq!(fireball.path[0].delay, 859);
+        assert!(fireball.duration > 2.);
+    }
+
+    #[test]
+    fn can_describe() {
+        let expected = [
+            ("tap.bin", "Tapped 1 time"),
+            ("heartbeat.bin", "Heartbeat at 84 BPM for 2 seconds"),
+            (
+                "heartbreak.bin",
+                "Heartbreak at 84 BPM for 2 seconds, broken after 1.71 seconds",
+            ),
+            ("sketch.bin", "Sketch with 1 stroke"),
+            ("kiss.bin", "Sent 1 kiss"),
+            ("fireball.bin", "Fireball for 2.08 seconds"),
+        ];
+        for (name, description) in expected {
+            assert_eq!(from_payload(&read(name)).unwrap().describe(), description);
+        }
+    }
+
+    #[test]
+    fn can_render_sketch_svg() {
+        let svg = from_payload(&read("sketch.bin"))
             .unwrap()
-            .as_path()
-            .join("test_data/digital_touch_message/fireball.bin");
-        let mut proto_data = File::open(protobuf_path).unwrap();
-        let mut data = vec![];
-        proto_data.read_to_end(&mut data).unwrap();
+            .render_svg()
+            .unwrap();
+        assert!(svg.contains("<polyline points=\"216.2,658.5 "));
+        assert!(svg.contains("stroke=\"#ff00fc\""));
+        assert!(!svg.contains("<animate"));
+    }
+
+    #[test]
+    fn can_render_tap_svg() {
+        let svg = from_payload(&read("tap.bin"))
+            .unwrap()
+            .render_svg()
+            .unwrap();
+        assert!(svg.contains("<circle cx=\"470.1\" cy=\"571.0\""));
+        assert!(svg.contains("<animate attributeName=\"r\""));
+    }
 
-        let expected = from_payload(&data);
-        assert_eq!(expected, Some(DigitalTouch::Fireball));
+    #[test]
+    fn cant_render_heartbeat_svg() {
+        let svg = from_payload(&read("heartbeat.bin")).unwrap().render_svg();
+        assert!(svg.is_none());
+    }
+
+    #[test]
+    fn cant_parse_truncated_sketch() {
+        let mut data = read("sketch.bin");
+        // Claim the stroke has more points than it does
+        let position = data
+            .windows(4)
+            .position(|window| window == [0x00, 0x00, 0x51, 0x00])
+            .unwrap();
+        data[position + 2] = 0x52;
+
+        assert!(from_payload(&data).is_err());
+    }
+
+    #[test]
+    fn cant_parse_garbage() {
+        assert!(from_payload(&[0xff, 0xff, 0xff]).is_err());
     }
 }
@@ -4,8 +4,10 @@
  The main data type used to represent these types of messages is [`EditedMessage`].
 */
 
+use std::ops::Range;
+
 use plist::Value;
-use serde::Serialize;
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
 use crate::{
     error::plist::PlistParseError,
@@ -19,7 +21,7 @@ use crate::{
 };
 
 /// The type of edit performed to a message body part
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum EditStatus {
     /// The content of the message body part was altered
     Edited,
@@ -30,7 +32,7 @@ pub enum EditStatus {
 }
 
 /// Represents a single edit event for a message part
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EditedEvent {
     /// The date the message part was edited
     pub date: i64,
@@ -46,8 +48,34 @@ impl EditedEvent {
     }
 }
 
-/// Tracks the edit status and history for a specific part of a message
+/// Text from one side of a comparison between two versions of a message part
+#[derive(Debug, PartialEq, Eq, Serialize, Clone, Copy)]
+#[serde(tag = "op", content = "text", rename_all = "lowercase")]
+pub enum DiffSpan<'a> {
+    /// Text that is in both versions
+    Unchanged(&'a str),
+    /// Text that was added in the newer version
+    Inserted(&'a str),
+    /// Text that was removed from the older version
+    Deleted(&'a str),
+}
+
+/// The changes made to a message part by a single edit
 #[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct EditDiff<'a> {
+    /// The date the edit was made
+    pub date: i64,
+    /// The spans of text that make up both versions, in order
+    ///
+    /// Joining the [`Unchanged`](DiffSpan::Unchanged) and [`Deleted`](DiffSpan::Deleted) spans yields the older text,
+    /// and joining the [`Unchanged`](DiffSpan::Unchanged) and [`Inserted`](DiffSpan::Inserted) spans yields the newer text.
+    pub spans: Vec<DiffSpan<'a>>,
+}
+
+/// Tracks the edit status and history for a specific part of a message
+///
+/// The serialized [`diffs`](EditedMessagePart::diffs) are derived from the history, so they are ignored when deserializing.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 pub struct EditedMessagePart {
     /// The type of edit made to the given message part
     pub status: EditStatus,
@@ -55,6 +83,31 @@ pub struct EditedMessagePart {
     pub edit_history: Vec<EditedEvent>,
 }
 
+impl EditedMessagePart {
+    /// Compare each version of the message part to the version before it
+    ///
+    /// The original version is not compared to anything, so there is one fewer diff than there are items in the `edit_history`.
+    pub fn diffs(&self) -> Vec<EditDiff<'_>> {
+        self.edit_history
+            .windows(2)
+            .map(|window| EditDiff {
+                date: window[1].date,
+                spans: diff_words(&window[0].text, &window[1].text),
+            })
+            .collect()
+    }
+}
+
+impl Serialize for EditedMessagePart {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("EditedMessagePart", 3)?;
+        state.serialize_field("status", &self.status)?;
+        state.serialize_field("edit_history", &self.edit_history)?;
+        state.serialize_field("diffs", &self.diffs())?;
+        state.end()
+    }
+}
+
 impl Default for EditedMessagePart {
     fn default() -> Self {
         Self {
@@ -93,7 +146,7 @@ impl Default for EditedMessagePart {
 /// # Documentation
 ///
 /// Apple describes editing and unsending messages [here](https://support.apple.com/guide/iphone/unsend-and-edit-messages-iphe67195653/ios).
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EditedMessage {
     /// Contains data representing each part of an edited message
     pub parts: Vec<EditedMessagePart>,
@@ -204,9 +257,146 @@ impl EditedMessage {
     }
 }
 
+/// The largest number of token comparisons [`diff_words`] will make before treating the texts as entirely different
+const MAX_DIFF_CELLS: usize = 1 << 20;
+
+/// Split text into runs of whitespace and runs of everything else, returning the byte range of each run
+fn tokenize(text: &str) -> Vec<Range<usize>> {
+    let mut tokens = vec![];
+    let mut start = 0;
+    let mut in_space = None;
+    for (idx, character) in text.char_indices() {
+        let is_space = character.is_whitespace();
+        if in_space.is_some_and(|previous| previous != is_space) {
+            tokens.push(start..idx);
+            start = idx;
+        }
+        in_space = Some(is_space);
+    }
+    if start < text.len() {
+        tokens.push(start..text.len());
+    }
+    tokens
+}
+
+/// The kind of change a token is part of, and which text its byte range points into
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// Range in the newer text
+    Unchanged,
+    /// Range in the newer text
+    Inserted,
+    /// Range in the older text
+    Deleted,
+}
+
+/// Add a token to the end of a list of changes, widening the last change if the token directly follows it
+fn push_op(ops: &mut Vec<(Op, Range<usize>)>, op: Op, range: Range<usize>) {
+    match ops.last_mut() {
+        Some((last_op, last_range)) if *last_op == op && last_range.end == range.start => {
+            last_range.end = range.end;
+        }
+        _ => ops.push((op, range)),
+    }
+}
+
+/// Compare two versions of some text word by word
+///
+/// Whitespace is compared separately from words, so changing one word does not mark the spaces around it as changed.
+/// Within each changed region, deleted text comes before inserted text.
+///
+/// # Example
+///
+/// ```
+/// use imessage_database::message_types::edited::{diff_words, DiffSpan};
+///
+/// let spans = diff_words("see you at 5", "see you at 6");
+/// assert_eq!(
+///     spans,
+///     vec![
+///         DiffSpan::Unchanged("see you at "),
+///         DiffSpan::Deleted("5"),
+///         DiffSpan::Inserted("6"),
+///     ]
+/// );
+/// ```
+pub fn diff_words<'a>(old: &'a str, new: &'a str) -> Vec<DiffSpan<'a>> {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+
+    // Very long texts are not worth comparing token by token
+    let cells = (old_tokens.len() + 1).saturating_mul(new_tokens.len() + 1);
+    if cells > MAX_DIFF_CELLS {
+        let mut spans = vec![];
+        if !old.is_empty() {
+            spans.push(DiffSpan::Deleted(old));
+        }
+        if !new.is_empty() {
+            spans.push(DiffSpan::Inserted(new));
+        }
+        return spans;
+    }
+
+    // Find the length of the longest common subsequence of tokens starting at each position
+    let width = new_tokens.len() + 1;
+    let mut lengths = vec![0_usize; cells];
+    for i in (0..old_tokens.len()).rev() {
+        for j in (0..new_tokens.len()).rev() {
+            lengths[i * width + j] = if old[old_tokens[i].clone()] == new[new_tokens[j].clone()] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    // Walk the table, holding insertions back so they come after the deletions in the same region
+    let mut ops = Vec::with_capacity(old_tokens.len().max(new_tokens.len()));
+    let mut inserted: Option<Range<usize>> = None;
+    let (mut i, mut j) = (0, 0);
+    while i < old_tokens.len() || j < new_tokens.len() {
+        if i < old_tokens.len()
+            && j < new_tokens.len()
+            && old[old_tokens[i].clone()] == new[new_tokens[j].clone()]
+        {
+            if let Some(range) = inserted.take() {
+                push_op(&mut ops, Op::Inserted, range);
+            }
+            push_op(&mut ops, Op::Unchanged, new_tokens[j].clone());
+            i += 1;
+            j += 1;
+        } else if i < old_tokens.len()
+            && (j == new_tokens.len() || lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+        {
+            push_op(&mut ops, Op::Deleted, old_tokens[i].clone());
+            i += 1;
+        } else {
+            let token = new_tokens[j].clone();
+            inserted = Some(match inserted {
+                Some(range) => range.start..token.end,
+                None => token,
+            });
+            j += 1;
+        }
+    }
+    if let Some(range) = inserted {
+        push_op(&mut ops, Op::Inserted, range);
+    }
+
+    ops.into_iter()
+        .map(|(op, range)| match op {
+            Op::Unchanged => DiffSpan::Unchanged(&new[range]),
+            Op::Inserted => DiffSpan::Inserted(&new[range]),
+            Op::Deleted => DiffSpan::Deleted(&old[range]),
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::message_types::edited::{EditStatus, EditedEvent, EditedMessagePart};
+    use crate::message_types::edited::{
+        diff_words, DiffSpan, EditStatus, EditedEvent, EditedMessagePart,
+    };
     use crate::message_types::{edited::EditedMessage, variants::BalloonProvider};
     use plist::Value;
     use std::env::current_dir;
@@ -441,4 +631,135 @@ mod tests {
 
         assert_eq!(parsed, expected);
     }
+
+    /// Rebuild the older and newer texts from a diff
+    fn rebuild(spans: &[DiffSpan]) -> (String, String) {
+        let mut old = String::new();
+        let mut new = String::new();
+        spans.iter().for_each(|span| match span {
+            DiffSpan::Unchanged(text) => {
+                old.push_str(text);
+                new.push_str(text);
+            }
+            DiffSpan::Inserted(text) => new.push_str(text),
+            DiffSpan::Deleted(text) => old.push_str(text),
+        });
+        (old, new)
+    }
+
+    #[test]
+    fn can_diff_words_replaced() {
+        assert_eq!(
+            diff_words("Edit 1", "Edited message"),
+            vec![
+                DiffSpan::Deleted("Edit"),
+                DiffSpan::Inserted("Edited"),
+                DiffSpan::Unchanged(" "),
+                DiffSpan::Deleted("1"),
+                DiffSpan::Inserted("message"),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_diff_words_inserted() {
+        assert_eq!(
+            diff_words("see you there", "see you over there"),
+            vec![
+                DiffSpan::Unchanged("see you "),
+                DiffSpan::Inserted("over "),
+                DiffSpan::Unchanged("there"),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_diff_words_deleted() {
+        assert_eq!(
+            diff_words("this is really bad", "this is bad"),
+            vec![
+                DiffSpan::Unchanged("this is "),
+                DiffSpan::Deleted("really "),
+                DiffSpan::Unchanged("bad"),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_diff_words_unchanged() {
+        assert_eq!(
+            diff_words("no changes", "no changes"),
+            vec![DiffSpan::Unchanged("no changes")]
+        );
+        assert_eq!(diff_words("", ""), vec![]);
+    }
+
+    #[test]
+    fn can_diff_words_from_empty() {
+        assert_eq!(diff_words("", "new"), vec![DiffSpan::Inserted("new")]);
+        assert_eq!(diff_words("old", ""), vec![DiffSpan::Deleted("old")]);
+    }
+
+    #[test]
+    fn can_diff_words_unicode() {
+        let old = "caf\u{e9} \u{1F600} ok";
+        let new = "caf\u{e9}  \u{1F602} ok";
+        let spans = diff_words(old, new);
+
+        assert_eq!(rebuild(&spans), (old.to_string(), new.to_string()));
+        assert_eq!(
+            spans,
+            vec![
+                DiffSpan::Unchanged("caf\u{e9}"),
+                DiffSpan::Deleted(" \u{1F600}"),
+                DiffSpan::Inserted("  \u{1F602}"),
+                DiffSpan::Unchanged(" ok"),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_diff_words_past_limit() {
+        let old = "a ".repeat(2000);
+        let new = "b ".repeat(2000);
+        let spans = diff_words(&old, &new);
+
+        assert_eq!(
+            spans,
+            vec![DiffSpan::Deleted(&old), DiffSpan::Inserted(&new)]
+        );
+    }
+
+    #[test]
+    fn can_diff_edited_parts() {
+        let plist_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/edited_message/EditedToLinkAndBack.plist");
+        let plist_data = File::open(plist_path).unwrap();
+        let plist = Value::from_reader(plist_data).unwrap();
+        let parsed = EditedMessage::from_map(&plist).unwrap();
+
+        let part = parsed.part(0).unwrap();
+        let diffs = part.diffs();
+        assert_eq!(diffs.len(), part.edit_history.len() - 1);
+
+        // Each diff rebuilds the versions on either side of it
+        for (idx, diff) in diffs.iter().enumerate() {
+            assert_eq!(diff.date, part.edit_history[idx + 1].date);
+            assert_eq!(
+                rebuild(&diff.spans),
+                (
+                    part.edit_history[idx].text.clone(),
+                    part.edit_history[idx + 1].text.clone()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn can_diff_unedited_part() {
+        let part = EditedMessagePart::default();
+        assert!(part.diffs().is_empty());
+    }
 }
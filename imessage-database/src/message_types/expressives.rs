@@ -2,12 +2,12 @@
  [Expressives](https://support.apple.com/en-us/HT206894) are effects that you can select by tapping and holding the send button.
 */
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Bubble effects are effects that alter the display of the chat bubble.
 ///
 /// Read more [here](https://www.imore.com/how-to-use-bubble-and-screen-effects-imessage-iphone-ipad).
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BubbleEffect {
     Slam,
     Loud,
@@ -18,7 +18,7 @@ pub enum BubbleEffect {
 /// Screen effects are effects that alter the entire background of the message view.
 ///
 /// Read more [here](https://www.imore.com/how-to-use-bubble-and-screen-effects-imessage-iphone-ipad).
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ScreenEffect {
     Confetti,
     Echo,
@@ -62,3 +62,27 @@ pub enum Expressive<'a> {
     /// Message is not an expressive
     None,
 }
+
+/// An owned version of [`Expressive`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OwnedExpressive {
+    /// Effects that use the entire screen
+    Screen(ScreenEffect),
+    /// Effects that display on a single bubble
+    Bubble(BubbleEffect),
+    /// Container for new or unknown messages
+    Unknown(String),
+    /// Message is not an expressive
+    None,
+}
+
+impl From<&Expressive<'_>> for OwnedExpressive {
+    fn from(expressive: &Expressive<'_>) -> Self {
+        match expressive {
+            Expressive::Screen(effect) => Self::Screen(*effect),
+            Expressive::Bubble(effect) => Self::Bubble(*effect),
+            Expressive::Unknown(effect) => Self::Unknown(effect.to_string()),
+            Expressive::None => Self::None,
+        }
+    }
+}
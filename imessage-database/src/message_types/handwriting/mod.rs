@@ -2,7 +2,7 @@
 [Handwritten](https://support.apple.com/en-us/HT206894) messages are animated doodles or messages sent in your own handwriting.
 */
 
-pub use models::HandwrittenMessage;
+pub use models::{HandwrittenMessage, RasterOptions, MAX_RASTER_SIZE};
 
 pub(crate) mod handwriting_proto;
 pub mod models;
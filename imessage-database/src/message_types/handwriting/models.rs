@@ -3,7 +3,7 @@
 */
 
 use std::fmt::Write;
-use std::io::Cursor;
+use std::io::{self, Cursor};
 
 use serde::Serialize;
 
@@ -14,11 +14,22 @@ use crate::{
 
 use protobuf::Message;
 
+/// The largest stroke buffer a handwriting message may decompress to, in bytes
+///
+/// Each point takes 8 bytes, so this allows for about two million points.
+pub const MAX_DECOMPRESSED_LENGTH: usize = 1 << 24;
+/// The longest side of a rasterized handwriting message, in pixels
+pub const MAX_RASTER_SIZE: u32 = 4096;
+/// How fast strokes are drawn in an animated `svg`, in canvas units per second
+const DRAW_SPEED: f64 = 300.;
+/// How long an animated `svg` waits between strokes, in seconds
+const STROKE_PAUSE: f64 = 0.15;
+
 /// Parser for [handwritten](https://support.apple.com/en-us/HT206894) iMessages.
 ///
 /// This message type is not documented by Apple, but represents messages displayed as
 /// `com.apple.Handwriting.HandwritingProvider`.
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct HandwrittenMessage {
     pub id: String,
     /// Timestamp for when the handwritten message was created, stored as a unix timestamp with an epoch of `2001-01-01 00:00:00` in the local time zone
@@ -29,8 +40,29 @@ pub struct HandwrittenMessage {
     pub strokes: Vec<Vec<Point>>,
 }
 
+/// Options used when rasterizing a handwritten message with [`HandwrittenMessage::render_png`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RasterOptions {
+    /// The length of the longest side of the image in pixels, up to [`MAX_RASTER_SIZE`]
+    pub size: u32,
+    /// The color of the strokes as `RGB`
+    pub color: [u8; 3],
+    /// The background color as `RGB`, or `None` for a transparent background
+    pub background: Option<[u8; 3]>,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        Self {
+            size: 512,
+            color: [0, 0, 0],
+            background: Some([255, 255, 255]),
+        }
+    }
+}
+
 /// Represents a point along a handwritten line.
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Point {
     pub x: u16,
     pub y: u16,
@@ -48,14 +80,76 @@ impl HandwrittenMessage {
         Ok(Self {
             id: msg.ID.to_string(),
             created_at: msg.CreatedAt,
-            height: height + 5,
-            width: width + 5,
+            height: height.saturating_add(5),
+            width: width.saturating_add(5),
             strokes: fit_strokes(&strokes, height, width, max_x, max_y, max_width),
         })
     }
 
     /// Renders the handwriting message as an `svg` graphic.
     pub fn render_svg(&self) -> String {
+        self.build_svg(false)
+    }
+
+    /// Renders the handwriting message as an `svg` graphic that draws each stroke in the order it was written.
+    ///
+    /// Handwriting does not store timing data, so strokes are drawn at a constant speed with a short pause between them.
+    pub fn render_svg_animated(&self) -> String {
+        self.build_svg(true)
+    }
+
+    /// Renders the handwriting message as a `png` image.
+    pub fn render_png(&self, options: &RasterOptions) -> Result<Vec<u8>, HandwritingError> {
+        let longest = self.width.max(self.height).max(1) as f64;
+        let scale = f64::from(options.size.clamp(1, MAX_RASTER_SIZE)) / longest;
+        let width = ((self.width as f64 * scale).round() as u32).max(1);
+        let height = ((self.height as f64 * scale).round() as u32).max(1);
+
+        // Draw each stroke into a coverage mask, then color it in
+        let mut mask = Canvas::new(width, height);
+        self.strokes.iter().for_each(|stroke| {
+            if let [point] = stroke.as_slice() {
+                mask.draw_segment(point, point, scale);
+            }
+            stroke.windows(2).for_each(|window| {
+                mask.draw_segment(&window[0], &window[1], scale);
+            });
+        });
+
+        let mut pixels = Vec::with_capacity(mask.coverage.len() * 4);
+        mask.coverage.iter().for_each(|&coverage| {
+            match options.background {
+                Some(background) => {
+                    (0..3).for_each(|channel| {
+                        let blended = f64::from(background[channel]) * (1. - coverage)
+                            + f64::from(options.color[channel]) * coverage;
+                        pixels.push(blended.round() as u8);
+                    });
+                    pixels.push(u8::MAX);
+                }
+                None => {
+                    pixels.extend_from_slice(&options.color);
+                    pixels.push((coverage * f64::from(u8::MAX)).round() as u8);
+                }
+            };
+        });
+
+        let mut png = vec![];
+        let mut encoder = png::Encoder::new(&mut png, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .map_err(HandwritingError::EncodeError)?;
+        writer
+            .write_image_data(&pixels)
+            .map_err(HandwritingError::EncodeError)?;
+        writer.finish().map_err(HandwritingError::EncodeError)?;
+        Ok(png)
+    }
+
+    /// Builds an `svg` graphic, optionally animating the strokes
+    fn build_svg(&self, animated: bool) -> String {
         let mut svg = String::new();
         svg.push('\n');
         svg.push_str(format!(r#"<svg viewBox="0 0 {} {}" preserveAspectRatio="xMidYMid meet" width="100%" height="100%" xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">"#, self.width, self.height).as_str());
@@ -76,7 +170,7 @@ impl HandwrittenMessage {
 "#,
         );
         svg.push_str("</style>\n");
-        generate_strokes(&mut svg, &self.strokes);
+        generate_strokes(&mut svg, &self.strokes, animated);
         svg.push_str("</svg>\n");
         svg
     }
@@ -151,38 +245,129 @@ fn draw_line(canvas: &mut [Vec<char>], start: &Point, end: &Point) {
 
 /// Draws a point on a 2d character grid.
 fn draw_point(canvas: &mut [Vec<char>], x: i64, y: i64) {
-    if x >= 0 && x < canvas[0].len() as i64 && y >= 0 && y < canvas.len() as i64 {
-        canvas[y as usize][x as usize] = '*';
+    if let Some(cell) = usize::try_from(y)
+        .ok()
+        .and_then(|y| canvas.get_mut(y))
+        .and_then(|row| row.get_mut(usize::try_from(x).ok()?))
+    {
+        *cell = '*';
     }
 }
 
 /// Generates svg lines from an array of strokes.
-fn generate_strokes(svg: &mut String, strokes: &[Vec<Point>]) {
+///
+/// If `animated` is set, each line is hidden until the lines before it finish drawing.
+fn generate_strokes(svg: &mut String, strokes: &[Vec<Point>], animated: bool) {
+    let mut begin = 0.;
     strokes.iter().for_each(|stroke| {
-        let mut segments = String::with_capacity(80 * (stroke.len() - 1));
+        let mut segments = String::with_capacity(80 * stroke.len().saturating_sub(1));
         group_points(stroke).iter().for_each(|(width, points)| {
             let mut points_svg = String::with_capacity(points.len() * 3);
             points.iter().for_each(|point| {
                 points_svg.push_str(&format!(" {},{}", point.x, point.y));
             });
-            segments.push_str(
-                format!(
-                    r#"<polyline class="line" points="{}" stroke-width="{}" />"#,
-                    points_svg.trim_start(),
-                    width
-                )
-                .as_str(),
-            );
+            if animated {
+                // Pad the dash so round line caps stay hidden until the line starts drawing
+                let length = line_length(points) + f64::from(*width);
+                let duration = length / DRAW_SPEED;
+                segments.push_str(
+                    format!(
+                        r#"<polyline class="line" points="{}" stroke-width="{}" stroke-dasharray="{length:.1}" stroke-dashoffset="{length:.1}"><animate attributeName="stroke-dashoffset" from="{length:.1}" to="0" begin="{begin:.3}s" dur="{duration:.3}s" fill="freeze" /></polyline>"#,
+                        points_svg.trim_start(),
+                        width
+                    )
+                    .as_str(),
+                );
+                begin += duration;
+            } else {
+                segments.push_str(
+                    format!(
+                        r#"<polyline class="line" points="{}" stroke-width="{}" />"#,
+                        points_svg.trim_start(),
+                        width
+                    )
+                    .as_str(),
+                );
+            }
             segments.push('\n');
         });
         svg.push_str(segments.as_str());
+        if animated {
+            begin += STROKE_PAUSE;
+        }
     });
 }
 
+/// The length of a line through a series of points
+fn line_length(points: &[&Point]) -> f64 {
+    points
+        .windows(2)
+        .map(|window| {
+            let dx = f64::from(window[1].x) - f64::from(window[0].x);
+            let dy = f64::from(window[1].y) - f64::from(window[0].y);
+            dx.hypot(dy)
+        })
+        .sum()
+}
+
+/// A grid of pixels that records how much of each pixel is covered by a stroke
+struct Canvas {
+    width: u32,
+    height: u32,
+    /// Coverage of each pixel from `0` to `1`, stored row by row
+    coverage: Vec<f64>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            coverage: vec![0.; width as usize * height as usize],
+        }
+    }
+
+    /// Draws an anti-aliased line between two points with round ends, scaling the points by `scale`
+    fn draw_segment(&mut self, start: &Point, end: &Point, scale: f64) {
+        let (x0, y0) = (f64::from(start.x) * scale, f64::from(start.y) * scale);
+        let (x1, y1) = (f64::from(end.x) * scale, f64::from(end.y) * scale);
+        let radius = (f64::from(start.width) + f64::from(end.width)) * scale / 4.;
+
+        // Only visit pixels that the line can touch
+        let reach = radius + 1.;
+        let min_x = (x0.min(x1) - reach).floor().max(0.) as u32;
+        let min_y = (y0.min(y1) - reach).floor().max(0.) as u32;
+        let max_x = ((x0.max(x1) + reach).ceil().max(0.) as u32).min(self.width);
+        let max_y = ((y0.max(y1) + reach).ceil().max(0.) as u32).min(self.height);
+
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let length_squared = dx * dx + dy * dy;
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                // Find the distance from the center of the pixel to the closest point on the line
+                let (px, py) = (f64::from(x) + 0.5, f64::from(y) + 0.5);
+                let t = if length_squared > 0. {
+                    (((px - x0) * dx + (py - y0) * dy) / length_squared).clamp(0., 1.)
+                } else {
+                    0.
+                };
+                let distance = (px - (x0 + t * dx)).hypot(py - (y0 + t * dy));
+                let coverage = (radius + 0.5 - distance).clamp(0., 1.);
+
+                let cell = &mut self.coverage[(y * self.width + x) as usize];
+                *cell = cell.max(coverage);
+            }
+        }
+    }
+}
+
 /// Group points along a stroke together by width
 fn group_points(stroke: &[Point]) -> Vec<(u16, Vec<&Point>)> {
     let mut groups = vec![];
-    let mut curr = stroke[0].width;
+    let Some(first) = stroke.first() else {
+        return groups;
+    };
+    let mut curr = first.width;
     let mut segment = vec![];
 
     stroke.iter().for_each(|point| {
@@ -245,7 +430,7 @@ fn get_max_dimension(strokes: &[Vec<Point>]) -> (u16, u16, u16) {
             (
                 max_x.max(point.x),
                 max_y.max(point.y),
-                max_width.max(point.width - 1),
+                max_width.max(point.width.saturating_sub(1)),
             )
         },
     )
@@ -291,37 +476,70 @@ fn decompress_strokes(msg: &BaseMessage) -> Result<Vec<u8>, HandwritingError> {
     let data = match msg.Handwriting.Compression.enum_value_or_default() {
         Compression::None => msg.Handwriting.Strokes.clone(),
         Compression::XZ => {
+            let length = match msg.Handwriting.DecompressedLength {
+                Some(decompress_size) => usize::try_from(decompress_size)
+                    .map_err(|_| HandwritingError::ConversionError)?,
+                None => return Err(HandwritingError::DecompressedNotSet),
+            };
+            if length > MAX_DECOMPRESSED_LENGTH {
+                return Err(HandwritingError::DecompressedTooLarge(
+                    MAX_DECOMPRESSED_LENGTH,
+                ));
+            }
+
+            // Stop decompressing as soon as the output is longer than the message claims it should be
             let mut cursor = Cursor::new(&msg.Handwriting.Strokes);
-            let mut buf = Vec::new();
-            lzma_rs::xz_decompress(&mut cursor, &mut buf).map_err(HandwritingError::XZError)?;
-            buf
+            let mut buf = LimitedWriter {
+                data: Vec::with_capacity(length),
+                limit: length,
+                overflowed: false,
+            };
+            if let Err(why) = lzma_rs::xz_decompress(&mut cursor, &mut buf) {
+                if buf.overflowed {
+                    return Err(HandwritingError::DecompressedTooLarge(length));
+                }
+                return Err(HandwritingError::XZError(why));
+            }
+            if length != buf.data.len() {
+                return Err(HandwritingError::InvalidDecompressedLength(
+                    length,
+                    buf.data.len(),
+                ));
+            }
+            buf.data
         }
         Compression::Unknown => {
             return Err(HandwritingError::CompressionUnknown);
         }
     };
 
-    let length = match msg.Handwriting.Compression.enum_value_or_default() {
-        Compression::None => data.len(),
-        Compression::XZ => {
-            if let Some(decompress_size) = msg.Handwriting.DecompressedLength {
-                usize::try_from(decompress_size).map_err(|_| HandwritingError::ConversionError)?
-            } else {
-                return Err(HandwritingError::DecompressedNotSet);
-            }
-        }
-        Compression::Unknown => {
-            return Err(HandwritingError::CompressionUnknown);
+    Ok(data)
+}
+
+/// A writer that refuses to grow past a fixed number of bytes
+struct LimitedWriter {
+    data: Vec<u8>,
+    limit: usize,
+    /// Set when a write would have grown the data past the limit
+    overflowed: bool,
+}
+
+impl io::Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.data.len() + buf.len() > self.limit {
+            self.overflowed = true;
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "decompressed data is longer than expected",
+            ));
         }
-    };
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
 
-    if length != data.len() {
-        return Err(HandwritingError::InvalidDecompressedLength(
-            length,
-            data.len(),
-        ));
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
-    Ok(data)
 }
 
 /// Parses the drawing size from the protobuf message.
@@ -343,7 +561,17 @@ fn parse_coordinates(b1: u8, b2: u8) -> u16 {
 
 #[cfg(test)]
 mod tests {
-    use crate::message_types::handwriting::models::{HandwrittenMessage, Point};
+    use crate::{
+        error::handwriting::HandwritingError,
+        message_types::handwriting::{
+            handwriting_proto::{BaseMessage, Compression},
+            models::{
+                HandwrittenMessage, Point, RasterOptions, MAX_DECOMPRESSED_LENGTH, MAX_RASTER_SIZE,
+            },
+        },
+    };
+
+    use protobuf::Message;
 
     use std::env::current_dir;
     use std::fs::File;
@@ -2734,4 +2962,175 @@ mod tests {
 
         assert_eq!(balloon.render_svg(), expected);
     }
+
+    /// Build a raw handwriting payload with the given stroke data
+    fn payload(strokes: Vec<u8>, compression: Compression, length: Option<i64>) -> Vec<u8> {
+        let mut msg = BaseMessage::new();
+        msg.ID = "test".to_string();
+        msg.Handwriting.mut_or_insert_default().Frame = vec![0, 0x80, 0, 0x80, 10, 0x80, 10, 0x80];
+        msg.Handwriting.mut_or_insert_default().Compression = compression.into();
+        msg.Handwriting.mut_or_insert_default().DecompressedLength = length;
+        msg.Handwriting.mut_or_insert_default().Strokes = strokes;
+        msg.write_to_bytes().unwrap()
+    }
+
+    #[test]
+    fn can_render_empty_stroke() {
+        // One stroke with no points
+        let data = payload(vec![0, 0], Compression::None, None);
+        let balloon = HandwrittenMessage::from_payload(&data).unwrap();
+
+        assert_eq!(balloon.strokes, vec![vec![]]);
+        assert!(!balloon.render_svg().contains("polyline"));
+        assert!(!balloon.render_ascii(0).contains('*'));
+    }
+
+    #[test]
+    fn can_render_zero_width_point() {
+        // One stroke with a single point whose width is `0`
+        let data = payload(
+            vec![1, 0, 0, 0x80, 0, 0x80, 0, 0x80, 0, 0],
+            Compression::None,
+            None,
+        );
+        let balloon = HandwrittenMessage::from_payload(&data).unwrap();
+
+        assert_eq!(balloon.strokes[0][0].width, 1);
+    }
+
+    #[test]
+    fn cant_decompress_past_limit() {
+        let data = payload(
+            vec![],
+            Compression::XZ,
+            Some(MAX_DECOMPRESSED_LENGTH as i64 + 1),
+        );
+
+        assert!(matches!(
+            HandwrittenMessage::from_payload(&data),
+            Err(HandwritingError::DecompressedTooLarge(
+                MAX_DECOMPRESSED_LENGTH
+            ))
+        ));
+    }
+
+    #[test]
+    fn cant_decompress_past_declared_length() {
+        let protobuf_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/handwritten_message/hello.bin");
+        let mut proto_data = File::open(protobuf_path).unwrap();
+        let mut data = vec![];
+        proto_data.read_to_end(&mut data).unwrap();
+
+        // Claim the strokes decompress to fewer bytes than they really do
+        let mut msg = BaseMessage::parse_from_bytes(&data).unwrap();
+        msg.Handwriting.mut_or_insert_default().DecompressedLength = Some(8);
+        let data = msg.write_to_bytes().unwrap();
+
+        assert!(matches!(
+            HandwrittenMessage::from_payload(&data),
+            Err(HandwritingError::DecompressedTooLarge(8))
+        ));
+    }
+
+    /// Read one of the handwriting payloads in the test data folder
+    fn read_balloon(name: &str) -> HandwrittenMessage {
+        let protobuf_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join(format!("test_data/handwritten_message/{name}.bin"));
+        let mut proto_data = File::open(protobuf_path).unwrap();
+        let mut data = vec![];
+        proto_data.read_to_end(&mut data).unwrap();
+        HandwrittenMessage::from_payload(&data).unwrap()
+    }
+
+    /// Decode a `png` image into its dimensions and `RGBA` pixels
+    fn decode_png(data: &[u8]) -> (u32, u32, Vec<u8>) {
+        let decoder = png::Decoder::new(data);
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        (info.width, info.height, pixels)
+    }
+
+    #[test]
+    fn can_render_animated_svg() {
+        let balloon = read_balloon("hello");
+        let svg = balloon.render_svg_animated();
+
+        assert_eq!(
+            svg.matches("<polyline").count(),
+            balloon.render_svg().matches("<polyline").count()
+        );
+        assert_eq!(
+            svg.matches("<polyline").count(),
+            svg.matches("<animate ").count()
+        );
+        assert!(svg.contains(r#"begin="0.000s""#));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn can_render_animated_svg_in_order() {
+        let balloon = read_balloon("hello");
+        let svg = balloon.render_svg_animated();
+
+        let starts: Vec<f64> = svg
+            .split(r#"begin=""#)
+            .skip(1)
+            .map(|part| part.split('s').next().unwrap().parse().unwrap())
+            .collect();
+        assert!(starts.windows(2).all(|window| window[0] <= window[1]));
+    }
+
+    #[test]
+    fn can_render_png() {
+        let balloon = read_balloon("hello");
+        let data = balloon.render_png(&RasterOptions::default()).unwrap();
+
+        assert_eq!(&data[..8], b"\x89PNG\r\n\x1a\n");
+        let (width, height, pixels) = decode_png(&data);
+        assert_eq!(width.max(height), 512);
+        // Opaque white background with black strokes
+        assert!(pixels.chunks(4).all(|pixel| pixel[3] == 255));
+        assert!(pixels.chunks(4).any(|pixel| pixel[..3] == [0, 0, 0]));
+        assert!(pixels.chunks(4).any(|pixel| pixel[..3] == [255, 255, 255]));
+    }
+
+    #[test]
+    fn can_render_png_size() {
+        let balloon = read_balloon("hello");
+        let options = RasterOptions {
+            size: 100,
+            ..Default::default()
+        };
+        let (width, height, _) = decode_png(&balloon.render_png(&options).unwrap());
+        assert_eq!(width.max(height), 100);
+
+        let options = RasterOptions {
+            size: u32::MAX,
+            ..Default::default()
+        };
+        let (width, height, _) = decode_png(&balloon.render_png(&options).unwrap());
+        assert_eq!(width.max(height), MAX_RASTER_SIZE);
+    }
+
+    #[test]
+    fn can_render_png_color() {
+        let balloon = read_balloon("hello");
+        let options = RasterOptions {
+            size: 128,
+            color: [255, 0, 0],
+            background: None,
+        };
+        let (_, _, pixels) = decode_png(&balloon.render_png(&options).unwrap());
+
+        // Every pixel is red, and only the strokes are visible
+        assert!(pixels.chunks(4).all(|pixel| pixel[..3] == [255, 0, 0]));
+        assert!(pixels.chunks(4).any(|pixel| pixel[3] == 0));
+        assert!(pixels.chunks(4).any(|pixel| pixel[3] == 255));
+    }
 }
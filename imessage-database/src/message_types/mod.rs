@@ -1,10 +1,16 @@
 /*!
  iMessages can represent different kinds of data. This module defines types for those data.
+
+ Most of these types borrow from the database row or payload they were parsed from. Each borrowed type has an
+ `Owned` counterpart, like [`OwnedURLMessage`](url::OwnedURLMessage) for [`URLMessage`](url::URLMessage),
+ that can be cloned, stored, sent between threads, and deserialized from the JSON the borrowed type serializes to.
+ Owned types are created from borrowed ones with [`From`].
 */
 
 pub mod app;
 pub mod app_store;
 pub mod collaboration;
+pub mod data_detector;
 pub mod digital_touch;
 pub mod edited;
 pub mod expressives;
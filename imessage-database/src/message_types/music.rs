@@ -3,7 +3,7 @@
 */
 
 use plist::Value;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     error::plist::PlistParseError,
@@ -27,6 +27,33 @@ pub struct MusicMessage<'a> {
     pub track_name: Option<&'a str>,
 }
 
+/// An owned version of [`MusicMessage`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnedMusicMessage {
+    /// URL in Apple Music
+    pub url: Option<String>,
+    /// URL pointing to the track preview stream
+    pub preview: Option<String>,
+    /// Artist name
+    pub artist: Option<String>,
+    /// Album name
+    pub album: Option<String>,
+    /// Track name
+    pub track_name: Option<String>,
+}
+
+impl From<&MusicMessage<'_>> for OwnedMusicMessage {
+    fn from(message: &MusicMessage<'_>) -> Self {
+        Self {
+            url: message.url.map(str::to_string),
+            preview: message.preview.map(str::to_string),
+            artist: message.artist.map(str::to_string),
+            album: message.album.map(str::to_string),
+            track_name: message.track_name.map(str::to_string),
+        }
+    }
+}
+
 impl<'a> BalloonProvider<'a> for MusicMessage<'a> {
     fn from_map(payload: &'a Value) -> Result<Self, PlistParseError> {
         if let Ok((music_metadata, body)) = MusicMessage::get_body_and_url(payload) {
@@ -3,7 +3,7 @@
 */
 
 use plist::Value;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     error::plist::PlistParseError,
@@ -36,6 +36,48 @@ pub struct Placemark<'a> {
     pub sub_locality: Option<&'a str>,
 }
 
+/// An owned version of [`Placemark`]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct OwnedPlacemark {
+    /// The name of the placemark
+    pub name: Option<String>,
+    /// The full address formatted associated with the placemark
+    pub address: Option<String>,
+    /// The state or province associated with the placemark
+    pub state: Option<String>,
+    /// The city associated with the placemark
+    pub city: Option<String>,
+    /// The abbreviated country or region name
+    pub iso_country_code: Option<String>,
+    /// The postal code associated with the placemark
+    pub postal_code: Option<String>,
+    /// The name of the country or region associated with the placemark
+    pub country: Option<String>,
+    /// The street associated with the placemark
+    pub street: Option<String>,
+    /// Additional administrative area information for the placemark
+    pub sub_administrative_area: Option<String>,
+    /// Additional city-level information for the placemark
+    pub sub_locality: Option<String>,
+}
+
+impl From<&Placemark<'_>> for OwnedPlacemark {
+    fn from(placemark: &Placemark<'_>) -> Self {
+        Self {
+            name: placemark.name.map(str::to_string),
+            address: placemark.address.map(str::to_string),
+            state: placemark.state.map(str::to_string),
+            city: placemark.city.map(str::to_string),
+            iso_country_code: placemark.iso_country_code.map(str::to_string),
+            postal_code: placemark.postal_code.map(str::to_string),
+            country: placemark.country.map(str::to_string),
+            street: placemark.street.map(str::to_string),
+            sub_administrative_area: placemark.sub_administrative_area.map(str::to_string),
+            sub_locality: placemark.sub_locality.map(str::to_string),
+        }
+    }
+}
+
 impl<'a> Placemark<'a> {
     /// Create a Placemark from a `specialization2` payload
     fn new(payload: &'a Value) -> Result<Self, PlistParseError> {
@@ -82,6 +124,37 @@ pub struct PlacemarkMessage<'a> {
     pub placemark: Placemark<'a>,
 }
 
+/// An owned version of [`PlacemarkMessage`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnedPlacemarkMessage {
+    /// The URL that ended up serving content, after all redirects
+    pub url: Option<String>,
+    /// The original url, before any redirects
+    pub original_url: Option<String>,
+    /// The full street address of the location
+    pub place_name: Option<String>,
+    /// [`OwnedPlacemark`] data for the specified location
+    pub placemark: OwnedPlacemark,
+}
+
+impl From<&PlacemarkMessage<'_>> for OwnedPlacemarkMessage {
+    fn from(message: &PlacemarkMessage<'_>) -> Self {
+        Self {
+            url: message.url.map(str::to_string),
+            original_url: message.original_url.map(str::to_string),
+            place_name: message.place_name.map(str::to_string),
+            placemark: OwnedPlacemark::from(&message.placemark),
+        }
+    }
+}
+
+impl OwnedPlacemarkMessage {
+    /// Get the redirected URL from a URL message, falling back to the original URL, if it exists
+    pub fn get_url(&self) -> Option<&str> {
+        self.url.as_deref().or(self.original_url.as_deref())
+    }
+}
+
 impl<'a> BalloonProvider<'a> for PlacemarkMessage<'a> {
     fn from_map(payload: &'a Value) -> Result<Self, PlistParseError> {
         if let Ok((placemark, body)) = PlacemarkMessage::get_body_and_url(payload) {
@@ -2,7 +2,7 @@
  Effects that can alter the appearance of message text.
 */
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Text effect container
 ///
@@ -35,12 +35,87 @@ pub enum TextEffect<'a> {
     ///
     /// The embedded data contains the unit that the range represents.
     Conversion(Unit),
+    /// An inline [Genmoji](https://support.apple.com/guide/iphone/create-genmoji-with-apple-intelligence-iph4e76f5667/ios), also known as an adaptive image glyph
+    ///
+    /// The embedded data contains the GUID of the attachment that stores the image.
+    Genmoji(&'a str),
+    /// A street address found by the data detector
+    ///
+    /// The embedded data contains the detected address.
+    Address(String),
+    /// A date, time, or event found by the data detector
+    ///
+    /// The embedded data contains the detected text.
+    Event(String),
+    /// A phone number found by the data detector
+    ///
+    /// The embedded data contains the detected phone number.
+    PhoneNumber(String),
+    /// A flight number found by the data detector
+    ///
+    /// The embedded data contains the detected flight number.
+    FlightNumber(String),
+    /// A shipment tracking number found by the data detector
+    ///
+    /// The embedded data contains the detected tracking number.
+    TrackingNumber(String),
+}
+
+/// An owned version of [`TextEffect`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OwnedTextEffect {
+    /// Default, unstyled text
+    Default,
+    /// A mentioned contact in the conversation
+    Mention(String),
+    /// A clickable link
+    Link(String),
+    /// A one-time code
+    OTP,
+    /// Traditional formatting styles
+    Styles(Vec<Style>),
+    /// Animation applied to the text
+    Animated(Animation),
+    /// Conversions that can be applied to text
+    Conversion(Unit),
+    /// An inline Genmoji
+    Genmoji(String),
+    /// A street address found by the data detector
+    Address(String),
+    /// A date, time, or event found by the data detector
+    Event(String),
+    /// A phone number found by the data detector
+    PhoneNumber(String),
+    /// A flight number found by the data detector
+    FlightNumber(String),
+    /// A shipment tracking number found by the data detector
+    TrackingNumber(String),
+}
+
+impl From<&TextEffect<'_>> for OwnedTextEffect {
+    fn from(effect: &TextEffect<'_>) -> Self {
+        match effect {
+            TextEffect::Default => Self::Default,
+            TextEffect::Mention(mention) => Self::Mention(mention.to_string()),
+            TextEffect::Link(link) => Self::Link(link.to_string()),
+            TextEffect::OTP => Self::OTP,
+            TextEffect::Styles(styles) => Self::Styles(styles.clone()),
+            TextEffect::Animated(animation) => Self::Animated(*animation),
+            TextEffect::Conversion(unit) => Self::Conversion(*unit),
+            TextEffect::Genmoji(guid) => Self::Genmoji(guid.to_string()),
+            TextEffect::Address(address) => Self::Address(address.clone()),
+            TextEffect::Event(event) => Self::Event(event.clone()),
+            TextEffect::PhoneNumber(number) => Self::PhoneNumber(number.clone()),
+            TextEffect::FlightNumber(number) => Self::FlightNumber(number.clone()),
+            TextEffect::TrackingNumber(number) => Self::TrackingNumber(number.clone()),
+        }
+    }
 }
 
 /// Unit conversion text effect container
 ///
 /// Read more about unit conversions [here](https://www.macrumors.com/how-to/convert-currencies-temperatures-more-ios-16/).
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Unit {
     Currency,
     Distance,
@@ -53,7 +128,7 @@ pub enum Unit {
 /// Traditional text effect container
 ///
 /// Read more about text styles [here](https://www.apple.com/newsroom/2024/06/ios-18-makes-iphone-more-personal-capable-and-intelligent-than-ever/).
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Style {
     /// **Bold** styled text
     Bold,
@@ -70,7 +145,7 @@ pub enum Style {
 /// A message's [`typedstream`](crate::util::typedstream) contains an [`i64`] identifier under the key `__kIMTextEffectAttributeName`.
 ///
 /// Read more about text styles [here](https://www.apple.com/newsroom/2024/06/ios-18-makes-iphone-more-personal-capable-and-intelligent-than-ever/).
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Animation {
     /// Denoted by an ID of `5`
     Big,
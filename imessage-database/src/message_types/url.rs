@@ -5,7 +5,7 @@
 */
 
 use plist::Value;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     error::plist::PlistParseError,
@@ -42,6 +42,55 @@ pub struct URLMessage<'a> {
     pub placeholder: bool,
 }
 
+/// An owned version of [`URLMessage`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnedURLMessage {
+    /// The webpage's `<og:title>` attribute
+    pub title: Option<String>,
+    /// The webpage's `<og:description>` attribute
+    pub summary: Option<String>,
+    /// The URL that ended up serving content, after all redirects
+    pub url: Option<String>,
+    /// The original url, before any redirects
+    pub original_url: Option<String>,
+    /// The type of webpage Apple thinks the link represents
+    pub item_type: Option<String>,
+    /// Up to 4 image previews displayed in the background of the bubble
+    pub images: Vec<String>,
+    /// Icons that represent the website, generally the favicon or apple-touch-icon
+    pub icons: Vec<String>,
+    /// The name of a website
+    pub site_name: Option<String>,
+    pub placeholder: bool,
+}
+
+impl From<&URLMessage<'_>> for OwnedURLMessage {
+    fn from(message: &URLMessage<'_>) -> Self {
+        Self {
+            title: message.title.map(str::to_string),
+            summary: message.summary.map(str::to_string),
+            url: message.url.map(str::to_string),
+            original_url: message.original_url.map(str::to_string),
+            item_type: message.item_type.map(str::to_string),
+            images: message
+                .images
+                .iter()
+                .map(|image| image.to_string())
+                .collect(),
+            icons: message.icons.iter().map(|icon| icon.to_string()).collect(),
+            site_name: message.site_name.map(str::to_string),
+            placeholder: message.placeholder,
+        }
+    }
+}
+
+impl OwnedURLMessage {
+    /// Get the redirected URL from a URL message, falling back to the original URL, if it exists
+    pub fn get_url(&self) -> Option<&str> {
+        self.url.as_deref().or(self.original_url.as_deref())
+    }
+}
+
 impl<'a> BalloonProvider<'a> for URLMessage<'a> {
     fn from_map(payload: &'a Value) -> Result<Self, PlistParseError> {
         let url_metadata = URLMessage::get_body(payload)?;
@@ -312,7 +361,10 @@ mod url_tests {
 #[cfg(test)]
 mod url_override_tests {
     use crate::{
-        message_types::{url::URLMessage, variants::URLOverride},
+        message_types::{
+            url::URLMessage,
+            variants::{OwnedURLOverride, URLOverride},
+        },
         util::plist::parse_plist,
     };
     use plist::Value;
@@ -389,4 +441,30 @@ mod url_override_tests {
         println!("{balloon:?}");
         assert!(matches!(balloon, URLOverride::SharedPlacemark(_)));
     }
+
+    #[test]
+    fn can_round_trip_owned() {
+        for fixture in [
+            "url_message/URL.plist",
+            "music_message/AppleMusic.plist",
+            "app_store/AppStoreLink.plist",
+            "collaboration_message/Freeform.plist",
+            "shared_placemark/SharedPlacemark.plist",
+        ] {
+            let plist_path = current_dir()
+                .unwrap()
+                .as_path()
+                .join("test_data")
+                .join(fixture);
+            let plist_data = File::open(plist_path).unwrap();
+            let plist = Value::from_reader(plist_data).unwrap();
+            let parsed = parse_plist(&plist).unwrap();
+
+            let balloon = URLMessage::get_url_message_override(&parsed).unwrap();
+            let json = serde_json::to_string(&balloon).unwrap();
+            let owned: OwnedURLOverride = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(owned, OwnedURLOverride::from(&balloon), "{fixture}");
+        }
+    }
 }
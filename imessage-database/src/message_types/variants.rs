@@ -5,13 +5,16 @@
 use std::fmt::Display;
 
 use plist::Value;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     error::plist::PlistParseError,
     message_types::{
-        app_store::AppStoreMessage, collaboration::CollaborationMessage, music::MusicMessage,
-        placemark::PlacemarkMessage, url::URLMessage,
+        app_store::{AppStoreMessage, OwnedAppStoreMessage},
+        collaboration::{CollaborationMessage, OwnedCollaborationMessage},
+        music::{MusicMessage, OwnedMusicMessage},
+        placemark::{OwnedPlacemarkMessage, PlacemarkMessage},
+        url::{OwnedURLMessage, URLMessage},
     },
 };
 
@@ -61,6 +64,39 @@ pub enum Tapback<'a> {
     Emoji(Option<&'a str>),
 }
 
+/// An owned version of [`Tapback`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OwnedTapback {
+    /// Heart
+    Loved,
+    /// Thumbs up
+    Liked,
+    /// Thumbs down
+    Disliked,
+    /// Laughing face
+    Laughed,
+    /// Exclamation points
+    Emphasized,
+    /// Question marks
+    Questioned,
+    /// Custom emoji tapbacks
+    Emoji(Option<String>),
+}
+
+impl From<&Tapback<'_>> for OwnedTapback {
+    fn from(tapback: &Tapback<'_>) -> Self {
+        match tapback {
+            Tapback::Loved => Self::Loved,
+            Tapback::Liked => Self::Liked,
+            Tapback::Disliked => Self::Disliked,
+            Tapback::Laughed => Self::Laughed,
+            Tapback::Emphasized => Self::Emphasized,
+            Tapback::Questioned => Self::Questioned,
+            Tapback::Emoji(emoji) => Self::Emoji(emoji.map(str::to_string)),
+        }
+    }
+}
+
 impl<'a> Display for Tapback<'a> {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -99,6 +135,45 @@ pub enum CustomBalloon<'a> {
     FindMy,
 }
 
+/// An owned version of [`CustomBalloon`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OwnedCustomBalloon {
+    /// Generic third party applications
+    Application(String),
+    /// URL previews
+    URL,
+    /// Handwritten animated messages
+    Handwriting,
+    /// Digital Touch message
+    DigitalTouch,
+    /// Apple Pay (one of Sent, Requested, Received)
+    ApplePay,
+    /// Fitness.app messages
+    Fitness,
+    /// Photos.app slideshow messages
+    Slideshow,
+    /// Check In messages
+    CheckIn,
+    /// Find My messages
+    FindMy,
+}
+
+impl From<&CustomBalloon<'_>> for OwnedCustomBalloon {
+    fn from(balloon: &CustomBalloon<'_>) -> Self {
+        match balloon {
+            CustomBalloon::Application(bundle_id) => Self::Application(bundle_id.to_string()),
+            CustomBalloon::URL => Self::URL,
+            CustomBalloon::Handwriting => Self::Handwriting,
+            CustomBalloon::DigitalTouch => Self::DigitalTouch,
+            CustomBalloon::ApplePay => Self::ApplePay,
+            CustomBalloon::Fitness => Self::Fitness,
+            CustomBalloon::Slideshow => Self::Slideshow,
+            CustomBalloon::CheckIn => Self::CheckIn,
+            CustomBalloon::FindMy => Self::FindMy,
+        }
+    }
+}
+
 /// URL Message Types
 ///
 /// Apple sometimes overloads `com.apple.messages.URLBalloonProvider` with
@@ -117,22 +192,92 @@ pub enum URLOverride<'a> {
     SharedPlacemark(PlacemarkMessage<'a>),
 }
 
+/// An owned version of [`URLOverride`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OwnedURLOverride {
+    /// URL previews
+    Normal(OwnedURLMessage),
+    /// Apple Music messages
+    AppleMusic(OwnedMusicMessage),
+    /// App Store messages
+    AppStore(OwnedAppStoreMessage),
+    /// Collaboration messages
+    Collaboration(OwnedCollaborationMessage),
+    /// Placemark messages
+    SharedPlacemark(OwnedPlacemarkMessage),
+}
+
+impl From<&URLOverride<'_>> for OwnedURLOverride {
+    fn from(balloon: &URLOverride<'_>) -> Self {
+        match balloon {
+            URLOverride::Normal(message) => Self::Normal(message.into()),
+            URLOverride::AppleMusic(message) => Self::AppleMusic(message.into()),
+            URLOverride::AppStore(message) => Self::AppStore(message.into()),
+            URLOverride::Collaboration(message) => Self::Collaboration(message.into()),
+            URLOverride::SharedPlacemark(message) => Self::SharedPlacemark(message.into()),
+        }
+    }
+}
+
 /// Announcement Message Types
 ///
 /// Announcements are messages sent to a thread for actions that are not balloons, i.e.
-/// updating the name of the group or changing the group photo
-#[derive(Debug, Serialize)]
+/// updating the name of the group, changing the group photo, or changing who is in the group
+#[derive(Debug, PartialEq, Eq, Serialize)]
 pub enum Announcement<'a> {
     /// Someone changed the name of the group
     NameChange(&'a str),
     /// Someone updated the group photo
     PhotoChange,
+    /// Someone added a participant to the group
+    ///
+    /// The `i32` is the handle ID of the participant that was added; the sender of the message added them.
+    ParticipantAdded(&'a i32),
+    /// Someone removed a participant from the group
+    ///
+    /// The `i32` is the handle ID of the participant that was removed; the sender of the message removed them.
+    ParticipantRemoved(&'a i32),
+    /// The sender of the message left the group
+    ParticipantLeft,
     /// All parts of the message were unsent
     FullyUnsent,
-    /// Types that may occur in the future, i.e. someone leaving or joining a group
+    /// Types that may occur in the future
     Unknown(&'a i32),
 }
 
+/// An owned version of [`Announcement`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OwnedAnnouncement {
+    /// Someone changed the name of the group
+    NameChange(String),
+    /// Someone updated the group photo
+    PhotoChange,
+    /// Someone added the participant with the given handle ID to the group
+    ParticipantAdded(i32),
+    /// Someone removed the participant with the given handle ID from the group
+    ParticipantRemoved(i32),
+    /// The sender of the message left the group
+    ParticipantLeft,
+    /// All parts of the message were unsent
+    FullyUnsent,
+    /// Types that may occur in the future
+    Unknown(i32),
+}
+
+impl From<&Announcement<'_>> for OwnedAnnouncement {
+    fn from(announcement: &Announcement<'_>) -> Self {
+        match announcement {
+            Announcement::NameChange(name) => Self::NameChange(name.to_string()),
+            Announcement::PhotoChange => Self::PhotoChange,
+            Announcement::ParticipantAdded(id) => Self::ParticipantAdded(**id),
+            Announcement::ParticipantRemoved(id) => Self::ParticipantRemoved(**id),
+            Announcement::ParticipantLeft => Self::ParticipantLeft,
+            Announcement::FullyUnsent => Self::FullyUnsent,
+            Announcement::Unknown(id) => Self::Unknown(**id),
+        }
+    }
+}
+
 /// Message variant container
 ///
 /// Messages can exist as one of many different variants, this encapsulates
@@ -163,6 +308,39 @@ pub enum Variant<'a> {
     SharePlay,
 }
 
+/// An owned version of [`Variant`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OwnedVariant {
+    /// A tapback applied to (`true`) or removed from (`false`) the body part at the given index
+    Tapback(usize, bool, OwnedTapback),
+    /// A sticker message, either placed on another message or by itself
+    Sticker(usize),
+    /// Container for new or unknown messages
+    Unknown(i32),
+    /// An iMessage app generated message
+    App(OwnedCustomBalloon),
+    /// An iMessage with a standard text body that may include attachments
+    Normal,
+    /// A message that has been edited or unsent
+    Edited,
+    /// A SharePlay message
+    SharePlay,
+}
+
+impl From<&Variant<'_>> for OwnedVariant {
+    fn from(variant: &Variant<'_>) -> Self {
+        match variant {
+            Variant::Tapback(idx, added, tapback) => Self::Tapback(*idx, *added, tapback.into()),
+            Variant::Sticker(idx) => Self::Sticker(*idx),
+            Variant::Unknown(num) => Self::Unknown(*num),
+            Variant::App(balloon) => Self::App(balloon.into()),
+            Variant::Normal => Self::Normal,
+            Variant::Edited => Self::Edited,
+            Variant::SharePlay => Self::SharePlay,
+        }
+    }
+}
+
 /// Defines behavior for different types of messages that have custom balloons
 pub trait BalloonProvider<'a> {
     /// Creates the object from a `HashMap` of item attributes
@@ -170,3 +348,46 @@ pub trait BalloonProvider<'a> {
     where
         Self: Sized;
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::message_types::variants::{
+        CustomBalloon, OwnedCustomBalloon, OwnedTapback, OwnedVariant, Tapback, Variant,
+    };
+
+    #[test]
+    fn can_convert_variant_to_owned() {
+        let variant = Variant::App(CustomBalloon::Application("com.apple.Handwriting"));
+        let owned = OwnedVariant::from(&variant);
+
+        assert_eq!(
+            owned,
+            OwnedVariant::App(OwnedCustomBalloon::Application(
+                "com.apple.Handwriting".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn can_round_trip_owned_variant() {
+        let variants = [
+            Variant::Tapback(1, true, Tapback::Emoji(Some("🤠"))),
+            Variant::Tapback(0, false, Tapback::Loved),
+            Variant::App(CustomBalloon::URL),
+            Variant::Unknown(42),
+            Variant::Normal,
+        ];
+
+        for variant in &variants {
+            let json = serde_json::to_string(variant).unwrap();
+            let owned: OwnedVariant = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(owned, OwnedVariant::from(variant));
+            assert_eq!(serde_json::to_string(&owned).unwrap(), json);
+        }
+        assert_eq!(
+            OwnedVariant::from(&variants[0]),
+            OwnedVariant::Tapback(1, true, OwnedTapback::Emoji(Some("🤠".to_string())))
+        );
+    }
+}
@@ -3,7 +3,7 @@
 */
 
 use rusqlite::{Connection, Error, Result, Row, Statement};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
 use std::{
     fs::File,
@@ -16,7 +16,7 @@ use crate::{
     message_types::sticker::{get_sticker_effect, StickerEffect},
     tables::{
         messages::Message,
-        table::{Table, ATTACHMENT},
+        table::{Table, ATTACHMENT, MESSAGE_ATTACHMENT_JOIN, RECENTLY_DELETED},
     },
     util::{
         dates::TIMESTAMP_FACTOR,
@@ -45,8 +45,34 @@ pub enum MediaType<'a> {
     Unknown,
 }
 
+/// An owned version of [`MediaType`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OwnedMediaType {
+    Image(String),
+    Video(String),
+    Audio(String),
+    Text(String),
+    Application(String),
+    Other(String),
+    Unknown,
+}
+
+impl From<&MediaType<'_>> for OwnedMediaType {
+    fn from(media_type: &MediaType<'_>) -> Self {
+        match media_type {
+            MediaType::Image(subtype) => Self::Image(subtype.to_string()),
+            MediaType::Video(subtype) => Self::Video(subtype.to_string()),
+            MediaType::Audio(subtype) => Self::Audio(subtype.to_string()),
+            MediaType::Text(subtype) => Self::Text(subtype.to_string()),
+            MediaType::Application(subtype) => Self::Application(subtype.to_string()),
+            MediaType::Other(subtype) => Self::Other(subtype.to_string()),
+            MediaType::Unknown => Self::Unknown,
+        }
+    }
+}
+
 /// Represents a single row in the `attachment` table.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Attachment {
     pub rowid: i32,
     /// The path to the file on disk
@@ -62,6 +88,10 @@ pub struct Attachment {
     /// `true` if the attachment was a sticker, else `false`
     pub is_sticker: bool,
     pub hide_attachment: i32,
+    /// The identifier of the image if the attachment is a [Genmoji](https://support.apple.com/guide/iphone/create-genmoji-with-apple-intelligence-iph4e76f5667/ios)
+    pub emoji_image_content_identifier: Option<String>,
+    /// The text description of a Genmoji image
+    pub emoji_image_short_description: Option<String>,
     /// Auxiliary data to denote that an attachment has been copied
     pub copied_path: Option<PathBuf>,
 }
@@ -77,11 +107,15 @@ impl Table for Attachment {
             total_bytes: row.get("total_bytes").unwrap_or_default(),
             is_sticker: row.get("is_sticker").unwrap_or(false),
             hide_attachment: row.get("hide_attachment").unwrap_or(0),
+            emoji_image_content_identifier: row
+                .get("emoji_image_content_identifier")
+                .unwrap_or(None),
+            emoji_image_short_description: row.get("emoji_image_short_description").unwrap_or(None),
             copied_path: None,
         })
     }
 
-    fn get(db: &Connection) -> Result<Statement, TableError> {
+    fn get(db: &Connection) -> Result<Statement<'_>, TableError> {
         db.prepare(&format!("SELECT * from {ATTACHMENT}"))
             .map_err(TableError::Attachment)
     }
@@ -156,6 +190,23 @@ impl Attachment {
         }
     }
 
+    /// `true` if the attachment is a Genmoji, else `false`
+    ///
+    /// Genmoji are displayed inline with the message text instead of as their own bubble,
+    /// see [`TextEffect::Genmoji`](crate::message_types::text_effects::TextEffect::Genmoji).
+    pub fn is_genmoji(&self) -> bool {
+        self.emoji_image_content_identifier.is_some()
+    }
+
+    /// Get the text to display in place of a Genmoji image
+    ///
+    /// Uses the image's description if it has one, otherwise `Genmoji`.
+    pub fn genmoji_description(&self) -> &str {
+        self.emoji_image_short_description
+            .as_deref()
+            .unwrap_or("Genmoji")
+    }
+
     /// Read the attachment from the disk into a vector of bytes in memory
     ///
     /// `db_path` is the path to the root of the backup directory.
@@ -249,22 +300,23 @@ impl Attachment {
         let mut bytes_query = if context.has_filters() {
             let mut statement = format!("SELECT SUM(total_bytes) FROM {ATTACHMENT} a");
 
-            if context.has_filters() {
-                statement.push_str(" WHERE ");
-                if let Some(start) = context.start {
-                    statement.push_str(&format!(
-                        "    a.created_date >= {}",
-                        start / TIMESTAMP_FACTOR
-                    ));
-                }
-                if let Some(end) = context.end {
-                    if context.start.is_some() {
-                        statement.push_str(" AND ");
-                    }
-                    statement
-                        .push_str(&format!("    a.created_date <= {}", end / TIMESTAMP_FACTOR));
-                }
+            let mut filters = vec![];
+            if let Some(start) = context.start {
+                filters.push(format!(
+                    "    a.created_date >= {}",
+                    start / TIMESTAMP_FACTOR
+                ));
+            }
+            if let Some(end) = context.end {
+                filters.push(format!("    a.created_date <= {}", end / TIMESTAMP_FACTOR));
             }
+            if context.recently_deleted {
+                filters.push(format!(
+                    "    a.ROWID IN (SELECT j.attachment_id FROM {MESSAGE_ATTACHMENT_JOIN} j JOIN {RECENTLY_DELETED} d ON j.message_id = d.message_id)"
+                ));
+            }
+            statement.push_str(" WHERE ");
+            statement.push_str(&filters.join(" AND "));
 
             db.prepare(&statement).map_err(TableError::Attachment)?
         } else {
@@ -455,6 +507,8 @@ mod tests {
             total_bytes: 100,
             is_sticker: false,
             hide_attachment: 0,
+            emoji_image_content_identifier: None,
+            emoji_image_short_description: None,
             copied_path: None,
         }
     }
@@ -512,6 +566,22 @@ mod tests {
         assert_eq!(attachment.mime_type(), MediaType::Unknown);
     }
 
+    #[test]
+    fn can_get_genmoji_description() {
+        let mut attachment = sample_attachment();
+        attachment.emoji_image_content_identifier = Some("8F1D0C9A".to_string());
+        attachment.emoji_image_short_description = Some("Crab wearing a party hat".to_string());
+        assert!(attachment.is_genmoji());
+        assert_eq!(attachment.genmoji_description(), "Crab wearing a party hat");
+    }
+
+    #[test]
+    fn cant_get_genmoji_description_missing() {
+        let attachment = sample_attachment();
+        assert!(!attachment.is_genmoji());
+        assert_eq!(attachment.genmoji_description(), "Genmoji");
+    }
+
     #[test]
     fn can_get_filename() {
         let attachment = sample_attachment();
@@ -30,7 +30,7 @@ impl Table for ChatToHandle {
         })
     }
 
-    fn get(db: &Connection) -> Result<Statement, TableError> {
+    fn get(db: &Connection) -> Result<Statement<'_>, TableError> {
         db.prepare(&format!("SELECT * FROM {CHAT_HANDLE_JOIN}"))
             .map_err(TableError::ChatToHandle)
     }
@@ -100,7 +100,7 @@ impl Deduplicate for ChatToHandle {
 
         // Iterate over the values in a deterministic order
         let mut sorted_dupes: Vec<(&i32, &Self::T)> = duplicated_data.iter().collect();
-        sorted_dupes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        sorted_dupes.sort_by_key(|(a, _)| *a);
 
         for (chat_id, participants) in sorted_dupes {
             if let Some(id) = participants_to_unique_chat_id.get(participants) {
@@ -0,0 +1,211 @@
+/*!
+ This module builds a history of the photos used for each group chat.
+
+ When someone changes a group chat's photo, iMessage writes a [`PhotoChange`](crate::message_types::variants::Announcement::PhotoChange)
+ announcement to the chat. The new photo is stored as an [`Attachment`](crate::tables::attachment::Attachment) of that announcement,
+ so the photos can be resolved with [`Attachment::from_message()`](crate::tables::attachment::Attachment::from_message).
+*/
+
+use std::collections::HashMap;
+
+use rusqlite::Connection;
+
+use crate::{
+    error::table::TableError,
+    tables::{
+        messages::Message,
+        table::{Cacheable, Queryable, Table, CHAT_MESSAGE_JOIN, MESSAGE, MESSAGE_ATTACHMENT_JOIN},
+    },
+};
+
+/// The photo changes made to a single group chat, in the order they happened
+#[derive(Debug, Default)]
+pub struct GroupPhotoHistory {
+    /// The announcement messages that changed the group photo
+    pub changes: Vec<Message>,
+}
+
+impl Cacheable for GroupPhotoHistory {
+    type K = i32;
+    type V = GroupPhotoHistory;
+    /// Generate a hashmap containing each chatroom's ID pointing to the messages that changed its photo
+    ///
+    /// Chats whose photo never changed are not included.
+    ///
+    /// # Example:
+    ///
+    /// ```no_run
+    /// use imessage_database::util::dirs::default_db_path;
+    /// use imessage_database::tables::table::{Cacheable, get_connection};
+    /// use imessage_database::tables::group_photo::GroupPhotoHistory;
+    ///
+    /// let db_path = default_db_path();
+    /// let conn = get_connection(&db_path).unwrap();
+    /// let photos = GroupPhotoHistory::cache(&conn);
+    /// ```
+    fn cache(db: &Connection) -> Result<HashMap<Self::K, Self::V>, TableError> {
+        let mut cache: HashMap<i32, GroupPhotoHistory> = HashMap::new();
+
+        let mut statement = db
+            .prepare(&changes_query(""))
+            .map_err(TableError::Messages)?;
+
+        let messages = statement
+            .query_map([], |row| Ok(Message::from_row(row)))
+            .map_err(TableError::Messages)?;
+
+        for message in messages {
+            let message = Message::extract(message)?;
+            if let (true, Some(chat_id)) = (message.is_group_photo_change(), message.chat_id) {
+                cache.entry(chat_id).or_default().changes.push(message);
+            }
+        }
+
+        Ok(cache)
+    }
+}
+
+impl Queryable for GroupPhotoHistory {
+    /// Read the messages that changed a single chatroom's photo
+    ///
+    /// Returns `None` if the chat's photo never changed, matching [`GroupPhotoHistory::cache()`].
+    fn query(db: &Connection, chat_id: &i32) -> Result<Option<Self>, TableError> {
+        let mut statement = db
+            .prepare(&changes_query("AND c.chat_id = ?1"))
+            .map_err(TableError::Messages)?;
+
+        let messages = statement
+            .query_map([chat_id], |row| Ok(Message::from_row(row)))
+            .map_err(TableError::Messages)?;
+
+        let mut history = GroupPhotoHistory::default();
+        for message in messages {
+            let message = Message::extract(message)?;
+            if message.is_group_photo_change() {
+                history.changes.push(message);
+            }
+        }
+
+        Ok((!history.changes.is_empty()).then_some(history))
+    }
+}
+
+/// Build the query that selects the announcements that may change a chat's photo, with an extra `WHERE` condition
+fn changes_query(condition: &str) -> String {
+    format!(
+        "SELECT
+             *,
+             c.chat_id,
+             (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
+             0 as num_replies
+         FROM
+             {MESSAGE} as m
+             JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id
+         WHERE m.group_action_type = 1 {condition}
+         ORDER BY m.date
+        "
+    )
+}
+
+impl GroupPhotoHistory {
+    /// The message that set the group's current photo, if the photo was ever changed
+    pub fn current(&self) -> Option<&Message> {
+        self.changes.last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tables::{
+        group_photo::GroupPhotoHistory,
+        table::{get_connection, Cacheable, Queryable},
+    };
+    use imessage_fixtures::{Fixture, NewAttachment, NewMessage, Schema};
+
+    #[test]
+    fn can_cache_group_photos() {
+        let fixture = Fixture::temporary(Schema::latest()).unwrap();
+        let alice = fixture
+            .add_handle("+15555550101", "iMessage", None)
+            .unwrap();
+        let group = fixture
+            .add_chat("chat0", "iMessage", Some("Group"), &[alice])
+            .unwrap();
+        let other = fixture
+            .add_chat("chat1", "iMessage", Some("Other"), &[alice])
+            .unwrap();
+
+        // Two photo changes, a membership change and a photo change in another chat
+        for (date, item_type, chat_id) in
+            [(1, 3, group), (2, 1, group), (3, 3, group), (4, 3, other)]
+        {
+            let message_id = fixture
+                .add_message(&NewMessage {
+                    guid: format!("{date}"),
+                    date,
+                    item_type,
+                    group_action_type: 1,
+                    handle_id: alice,
+                    chat_id: Some(chat_id),
+                    ..Default::default()
+                })
+                .unwrap();
+            fixture
+                .add_attachment(
+                    message_id,
+                    &NewAttachment {
+                        guid: format!("photo-{date}"),
+                        transfer_name: "GroupPhotoImage".to_string(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
+
+        let db = get_connection(&fixture.db_path()).unwrap();
+        let cache = GroupPhotoHistory::cache(&db).unwrap();
+
+        let history = cache.get(&group).unwrap();
+        assert_eq!(
+            history
+                .changes
+                .iter()
+                .map(|message| message.date)
+                .collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert_eq!(history.current().unwrap().date, 3);
+        assert_eq!(history.current().unwrap().num_attachments, 1);
+        assert_eq!(cache.get(&other).unwrap().changes.len(), 1);
+
+        // Reading a single chat gives the same history as the cache
+        let queried = GroupPhotoHistory::query(&db, &group).unwrap().unwrap();
+        assert_eq!(
+            queried
+                .changes
+                .iter()
+                .map(|message| message.rowid)
+                .collect::<Vec<_>>(),
+            history
+                .changes
+                .iter()
+                .map(|message| message.rowid)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(queried.current().unwrap().num_attachments, 1);
+    }
+
+    #[test]
+    fn cant_query_group_photos_unchanged() {
+        let fixture = Fixture::temporary(Schema::latest()).unwrap();
+        let chat = fixture.add_chat("chat0", "iMessage", None, &[]).unwrap();
+
+        let db = get_connection(&fixture.db_path()).unwrap();
+        assert!(GroupPhotoHistory::query(&db, &chat).unwrap().is_none());
+    }
+
+    #[test]
+    fn cant_get_current_empty() {
+        assert!(GroupPhotoHistory::default().current().is_none());
+    }
+}
@@ -30,7 +30,7 @@ impl Table for Handle {
         })
     }
 
-    fn get(db: &Connection) -> Result<Statement, TableError> {
+    fn get(db: &Connection) -> Result<Statement<'_>, TableError> {
         db.prepare(&format!("SELECT * from {HANDLE}"))
             .map_err(TableError::Handle)
     }
@@ -110,7 +110,7 @@ impl Deduplicate for Handle {
 
         // Iterate over the values in a deterministic order
         let mut sorted_dupes: Vec<(&i32, &Self::T)> = duplicated_data.iter().collect();
-        sorted_dupes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        sorted_dupes.sort_by_key(|(a, _)| *a);
 
         for (participant_id, participant) in sorted_dupes {
             if let Some(id) = participant_to_unique_participant_id.get(participant) {
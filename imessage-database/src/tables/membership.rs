@@ -0,0 +1,304 @@
+/*!
+ This module builds a history of who was in each group chat from the announcement messages sent to it.
+
+ The `chat_handle_join` table, cached by [`ChatToHandle`](crate::tables::chat_handle::ChatToHandle), only
+ contains a chat's current participants. When someone is added to, removed from, or leaves a group chat,
+ iMessage also writes an announcement message to the chat. Those messages are collected here into a
+ [`MembershipTimeline`] for each chat.
+*/
+
+use std::collections::{BTreeSet, HashMap};
+
+use rusqlite::{Connection, Row};
+use serde::Serialize;
+
+use crate::{
+    error::table::TableError,
+    tables::table::{Cacheable, Queryable, CHAT_MESSAGE_JOIN, MESSAGE},
+};
+
+/// The kind of change made to a group chat's participants
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum MembershipChange {
+    /// A participant was added to the group
+    Added,
+    /// A participant was removed from the group
+    Removed,
+    /// A participant left the group
+    Left,
+}
+
+/// A single change to a group chat's participants
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub struct MembershipEvent {
+    /// The date the change was made
+    pub date: i64,
+    /// What happened to the participant
+    pub change: MembershipChange,
+    /// The handle ID of the participant that joined, was removed, or left, or `0` for the database owner
+    pub participant: i32,
+    /// The handle ID of the participant that made the change, or `0` for the database owner
+    pub actor: i32,
+}
+
+impl MembershipEvent {
+    /// Build an event from a row selected by [`MembershipTimeline::cache`], if the row is a membership change
+    fn from_row(row: &Row) -> rusqlite::Result<Option<(i32, Self)>> {
+        let chat_id: i32 = row.get("chat_id")?;
+        let item_type: i32 = row.get("item_type")?;
+        let group_action_type: i32 = row.get("group_action_type")?;
+        let other_handle: i32 = row.get::<_, Option<i32>>("other_handle")?.unwrap_or(0);
+        let is_from_me: bool = row.get("is_from_me")?;
+        // The database owner has no handle, so their messages use `0`
+        let actor = if is_from_me {
+            0
+        } else {
+            row.get::<_, Option<i32>>("handle_id")?.unwrap_or(0)
+        };
+
+        let (change, participant) = match (item_type, group_action_type) {
+            (1, 0) => (MembershipChange::Added, other_handle),
+            (1, 1) => (MembershipChange::Removed, other_handle),
+            (3, 0) => (MembershipChange::Left, actor),
+            _ => return Ok(None),
+        };
+
+        Ok(Some((
+            chat_id,
+            MembershipEvent {
+                date: row.get("date")?,
+                change,
+                participant,
+                actor,
+            },
+        )))
+    }
+}
+
+/// The history of changes to a single group chat's participants, in the order they happened
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct MembershipTimeline {
+    pub events: Vec<MembershipEvent>,
+}
+
+impl Cacheable for MembershipTimeline {
+    type K = i32;
+    type V = MembershipTimeline;
+    /// Generate a hashmap containing each chatroom's ID pointing to the history of changes to its participants
+    ///
+    /// Chats whose participants never changed are not included.
+    ///
+    /// # Example:
+    ///
+    /// ```no_run
+    /// use imessage_database::util::dirs::default_db_path;
+    /// use imessage_database::tables::table::{Cacheable, get_connection};
+    /// use imessage_database::tables::membership::MembershipTimeline;
+    ///
+    /// let db_path = default_db_path();
+    /// let conn = get_connection(&db_path).unwrap();
+    /// let timelines = MembershipTimeline::cache(&conn);
+    /// ```
+    fn cache(db: &Connection) -> Result<HashMap<Self::K, Self::V>, TableError> {
+        let mut cache: HashMap<i32, MembershipTimeline> = HashMap::new();
+
+        let mut statement = db
+            .prepare(&events_query(""))
+            .map_err(TableError::Messages)?;
+
+        let events = statement
+            .query_map([], MembershipEvent::from_row)
+            .map_err(TableError::Messages)?;
+
+        for event in events {
+            if let Some((chat_id, event)) = event.map_err(TableError::Messages)? {
+                cache.entry(chat_id).or_default().events.push(event);
+            }
+        }
+
+        Ok(cache)
+    }
+}
+
+impl Queryable for MembershipTimeline {
+    /// Read the history of changes to a single chatroom's participants
+    ///
+    /// Returns `None` if the chat's participants never changed, matching [`MembershipTimeline::cache()`].
+    fn query(db: &Connection, chat_id: &i32) -> Result<Option<Self>, TableError> {
+        let mut statement = db
+            .prepare(&events_query("AND c.chat_id = ?1"))
+            .map_err(TableError::Messages)?;
+
+        let events = statement
+            .query_map([chat_id], MembershipEvent::from_row)
+            .map_err(TableError::Messages)?;
+
+        let mut timeline = MembershipTimeline::default();
+        for event in events {
+            if let Some((_, event)) = event.map_err(TableError::Messages)? {
+                timeline.events.push(event);
+            }
+        }
+
+        Ok((!timeline.events.is_empty()).then_some(timeline))
+    }
+}
+
+/// Build the query that selects the announcements that may change a chat's participants, with an extra `WHERE` condition
+fn events_query(condition: &str) -> String {
+    format!(
+        "SELECT
+            c.chat_id,
+            m.date,
+            m.item_type,
+            m.group_action_type,
+            m.handle_id,
+            m.other_handle,
+            m.is_from_me
+        FROM {MESSAGE} as m
+        JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id
+        WHERE m.item_type IN (1, 3) {condition}
+        ORDER BY m.date"
+    )
+}
+
+impl MembershipTimeline {
+    /// Every participant that has ever been in the chat, given the chat's current participants
+    ///
+    /// The database owner is never included, matching [`ChatToHandle`](crate::tables::chat_handle::ChatToHandle).
+    pub fn historical_participants(&self, current: &BTreeSet<i32>) -> BTreeSet<i32> {
+        let mut participants = current.clone();
+        self.events.iter().for_each(|event| {
+            participants.insert(event.participant);
+            participants.insert(event.actor);
+        });
+        participants.remove(&0);
+        participants
+    }
+
+    /// Combine the timelines of several chats, keeping the events in date order
+    ///
+    /// Useful when the same conversation is split across multiple chats.
+    pub fn merge<'a>(timelines: impl Iterator<Item = &'a MembershipTimeline>) -> Self {
+        let mut events: Vec<MembershipEvent> = timelines
+            .flat_map(|timeline| timeline.events.iter().copied())
+            .collect();
+        events.sort_by_key(|event| event.date);
+        MembershipTimeline { events }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::tables::{
+        membership::{MembershipChange, MembershipEvent, MembershipTimeline},
+        table::{get_connection, Cacheable, Queryable},
+    };
+    use imessage_fixtures::{Fixture, NewMessage, Schema};
+
+    fn event(date: i64, change: MembershipChange, participant: i32, actor: i32) -> MembershipEvent {
+        MembershipEvent {
+            date,
+            change,
+            participant,
+            actor,
+        }
+    }
+
+    #[test]
+    fn can_cache_timeline() {
+        let fixture = Fixture::temporary(Schema::latest()).unwrap();
+        let alice = fixture
+            .add_handle("+15555550101", "iMessage", None)
+            .unwrap();
+        let bob = fixture
+            .add_handle("+15555550102", "iMessage", None)
+            .unwrap();
+        let chat = fixture
+            .add_chat("chat0", "iMessage", Some("Group"), &[alice])
+            .unwrap();
+
+        // Alice adds Bob, the database owner removes Bob, and then Alice leaves
+        for (date, item_type, group_action_type, handle_id, other_handle, is_from_me) in [
+            (1, 1, 0, alice, bob, false),
+            (2, 0, 0, alice, 0, false),
+            (3, 1, 1, 0, bob, true),
+            (4, 3, 0, alice, 0, false),
+            (5, 3, 1, alice, 0, false),
+        ] {
+            fixture
+                .add_message(&NewMessage {
+                    guid: format!("{date}"),
+                    date,
+                    item_type,
+                    group_action_type,
+                    handle_id,
+                    other_handle,
+                    is_from_me,
+                    chat_id: Some(chat),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let db = get_connection(&fixture.db_path()).unwrap();
+        let cache = MembershipTimeline::cache(&db).unwrap();
+
+        assert_eq!(
+            cache.get(&chat).unwrap().events,
+            vec![
+                event(1, MembershipChange::Added, bob, alice),
+                event(3, MembershipChange::Removed, bob, 0),
+                event(4, MembershipChange::Left, alice, alice),
+            ]
+        );
+        // Reading a single chat gives the same timeline as the cache
+        assert_eq!(
+            MembershipTimeline::query(&db, &chat).unwrap().as_ref(),
+            cache.get(&chat)
+        );
+        assert!(MembershipTimeline::query(&db, &(chat + 1))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn can_get_historical_participants() {
+        let timeline = MembershipTimeline {
+            events: vec![
+                event(1, MembershipChange::Added, 2, 0),
+                event(2, MembershipChange::Left, 3, 3),
+            ],
+        };
+
+        assert_eq!(
+            timeline.historical_participants(&BTreeSet::from([1, 2])),
+            BTreeSet::from([1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn can_merge_timelines() {
+        let first = MembershipTimeline {
+            events: vec![
+                event(1, MembershipChange::Added, 2, 0),
+                event(3, MembershipChange::Left, 2, 2),
+            ],
+        };
+        let second = MembershipTimeline {
+            events: vec![event(2, MembershipChange::Removed, 4, 1)],
+        };
+
+        let merged = MembershipTimeline::merge([&first, &second].into_iter());
+        assert_eq!(
+            merged
+                .events
+                .iter()
+                .map(|event| event.date)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+}
@@ -2,6 +2,7 @@ use serde::Serialize;
 
 use crate::{
     message_types::{
+        data_detector::DataDetectorResult,
         edited::EditStatus,
         text_effects::{Animation, Style, TextEffect, Unit},
     },
@@ -26,7 +27,7 @@ pub enum BubbleResult<'a> {
 }
 
 /// Logic to use deserialized typedstream data to parse the message body
-pub(crate) fn parse_body_typedstream(message: &Message) -> Option<Vec<BubbleComponent>> {
+pub(crate) fn parse_body_typedstream(message: &Message) -> Option<Vec<BubbleComponent<'_>>> {
     // Create the output data
     let mut out_v = vec![];
 
@@ -106,6 +107,19 @@ pub(crate) fn parse_body_typedstream(message: &Message) -> Option<Vec<BubbleComp
     (!out_v.is_empty()).then_some(out_v)
 }
 
+/// Find the speech-to-text transcription of an audio message in the deserialized typedstream data
+pub(crate) fn parse_audio_transcription(message: &Message) -> Option<&str> {
+    let components = message.components.as_ref()?;
+    // The first item is the text itself, so skip over it when searching for the key
+    components
+        .iter()
+        .enumerate()
+        .skip(1)
+        .find(|(_, component)| component.as_nsstring() == Some("IMAudioTranscription"))
+        .and_then(|(idx, _)| components.get(idx + 1)?.as_nsstring())
+        .filter(|transcription| !transcription.is_empty())
+}
+
 fn get_range(component: &Archivable) -> Option<(&i64, &u64)> {
     if let Archivable::Data(items) = component {
         if items.len() == 2 {
@@ -164,6 +178,25 @@ fn get_bubble_type<'a>(
 ) -> Option<BubbleResult<'a>> {
     let range_start = get_char_idx(message.text.as_ref()?, start, char_indices);
     let range_end = get_char_idx(message.text.as_ref()?, end, char_indices);
+
+    // Genmoji are also file transfers, so they need to be found before the attachment key is
+    if let Some(guid) = get_genmoji_guid(components) {
+        return Some(BubbleResult::Continuation(TextAttributes::new(
+            range_start,
+            range_end,
+            TextEffect::Genmoji(guid),
+        )));
+    }
+
+    // Data detector results take precedence over the links generated for them
+    if let Some(effect) = get_data_detector_effect(components) {
+        return Some(BubbleResult::Continuation(TextAttributes::new(
+            range_start,
+            range_end,
+            effect,
+        )));
+    }
+
     for (idx, key) in components.iter().enumerate() {
         if let Some(key_name) = key.as_nsstring() {
             match key_name {
@@ -231,6 +264,52 @@ fn get_bubble_type<'a>(
     )))
 }
 
+/// Get the attachment GUID of a Genmoji from a range of key-value pairs, if the range is a Genmoji
+///
+/// Returns [`None`] if the range is a Genmoji without an attachment GUID, so it falls back to the default effect.
+fn get_genmoji_guid(components: &[Archivable]) -> Option<&str> {
+    if !components
+        .iter()
+        .any(|key| key.as_nsstring() == Some("__kIMAdaptiveImageGlyphAttributeName"))
+    {
+        return None;
+    }
+    components
+        .iter()
+        .position(|key| key.as_nsstring() == Some("__kIMFileTransferGUIDAttributeName"))
+        .and_then(|idx| components.get(idx + 1)?.as_nsstring())
+}
+
+/// Get the text effect for data found by the data detector, if the range contains a supported result
+///
+/// Calendar event ranges that do not contain a date or event result are left for [`get_bubble_type`] to handle as timezone conversions.
+fn get_data_detector_effect<'a>(components: &[Archivable]) -> Option<TextEffect<'a>> {
+    // The key is followed by the `NSMutableData` header, then the archived result
+    let detected = |idx: usize| {
+        DataDetectorResult::from_archive(get_bytes(components.get(idx + 2)?)?)?.into_effect()
+    };
+    components
+        .iter()
+        .enumerate()
+        .find_map(|(idx, key)| match key.as_nsstring()? {
+            "__kIMDataDetectedAttributeName" | "__kIMPhoneNumberAttributeName" => detected(idx),
+            "__kIMCalendarEventAttributeName" => {
+                detected(idx).filter(|effect| matches!(effect, TextEffect::Event(_)))
+            }
+            _ => None,
+        })
+}
+
+/// Get the raw bytes stored in an `NSData` object
+fn get_bytes(component: &Archivable) -> Option<&[u8]> {
+    if let Archivable::Data(items) = component {
+        if let Some(OutputData::Array(bytes)) = items.first() {
+            return Some(bytes);
+        }
+    }
+    None
+}
+
 /// Extract text styles from a range of key-value pairs
 fn resolve_styles(components: &[Archivable]) -> Vec<Style> {
     let mut styles = vec![];
@@ -249,7 +328,7 @@ fn resolve_styles(components: &[Archivable]) -> Vec<Style> {
 }
 
 /// Fallback logic to parse the body from the message string content
-pub(crate) fn parse_body_legacy(message: &Message) -> Vec<BubbleComponent> {
+pub(crate) fn parse_body_legacy(message: &Message) -> Vec<BubbleComponent<'_>> {
     let mut out_v = vec![];
     // Naive logic for when `typedstream` component parsing fails
     match &message.text {
@@ -303,11 +382,14 @@ mod typedstream_tests {
             text_effects::{Animation, Style, TextEffect, Unit},
         },
         tables::messages::{
-            body::parse_body_typedstream,
+            body::{parse_audio_transcription, parse_body_typedstream},
             models::{BubbleComponent, TextAttributes},
             Message,
         },
-        util::typedstream::parser::TypedStreamReader,
+        util::typedstream::{
+            models::{Archivable, OutputData},
+            parser::TypedStreamReader,
+        },
     };
 
     pub(super) fn blank() -> Message {
@@ -338,6 +420,8 @@ mod typedstream_tests {
             thread_originator_part: None,
             date_edited: 0,
             associated_message_emoji: None,
+            schedule_type: 0,
+            schedule_state: 0,
             chat_id: None,
             num_attachments: 0,
             deleted_from: None,
@@ -565,6 +649,117 @@ mod typedstream_tests {
         );
     }
 
+    #[test]
+    fn can_get_audio_transcription() {
+        let mut m = blank();
+        m.text = Some("\u{FFFC}".to_string());
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/typedstream/AudioTranscription");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        m.components = parser.parse().ok();
+
+        assert_eq!(
+            parse_audio_transcription(&m),
+            Some("Hey, I'm running late but I'll be there soon")
+        );
+        assert_eq!(
+            parse_body_typedstream(&m).unwrap(),
+            vec![BubbleComponent::Attachment(
+                "at_0_2E5F12C3-E649-48AA-954D-3EA67C016BCC"
+            )]
+        );
+    }
+
+    #[test]
+    fn cant_get_audio_transcription_attachment() {
+        let mut m = blank();
+        m.text = Some(
+            "\u{FFFC}This is how the notes look to me fyi, in case it helps make sense of anything"
+                .to_string(),
+        );
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/typedstream/Attachment");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        m.components = parser.parse().ok();
+
+        assert_eq!(parse_audio_transcription(&m), None);
+    }
+
+    #[test]
+    fn can_get_message_body_genmoji() {
+        let mut m = blank();
+        m.text = Some("\u{FFFC}".to_string());
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/typedstream/Genmoji");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        m.components = parser.parse().ok();
+
+        assert_eq!(
+            parse_body_typedstream(&m).unwrap(),
+            vec![BubbleComponent::Text(vec![TextAttributes::new(
+                0,
+                3,
+                TextEffect::Genmoji("at_0_5B0E7A14-2C9D-4F83-B6E1-0A9D3C7F2E48")
+            )])]
+        );
+    }
+
+    #[test]
+    fn can_get_message_body_genmoji_without_guid() {
+        let mut m = blank();
+        m.text = Some("\u{FFFC}".to_string());
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/typedstream/Genmoji");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        let mut components = parser.parse().unwrap();
+
+        // Remove the attachment GUID from the Genmoji range
+        let idx = components
+            .iter()
+            .position(|key| key.as_nsstring() == Some("__kIMFileTransferGUIDAttributeName"))
+            .unwrap();
+        components[idx] = Archivable::Data(vec![OutputData::Array(vec![])]);
+        components[idx + 1] = Archivable::Data(vec![OutputData::Array(vec![])]);
+        m.components = Some(components);
+
+        assert_eq!(
+            parse_body_typedstream(&m).unwrap(),
+            vec![BubbleComponent::Text(vec![TextAttributes::new(
+                0,
+                3,
+                TextEffect::Default
+            )])]
+        );
+    }
+
     #[test]
     fn can_get_message_body_attachment_i16() {
         let mut m = blank();
@@ -710,6 +905,35 @@ mod typedstream_tests {
             .enumerate()
             .for_each(|(idx, item)| println!("\t{idx}: {item:?}"));
 
+        assert_eq!(
+            parse_body_typedstream(&m).unwrap(),
+            vec![BubbleComponent::Text(vec![
+                TextAttributes::new(0, 11, TextEffect::Default),
+                TextAttributes::new(11, 21, TextEffect::PhoneNumber("0000000000".to_string())),
+            ])]
+        );
+    }
+
+    #[test]
+    fn can_get_message_body_phone_without_detector_result() {
+        let mut m = blank();
+        m.text = Some("What about 0000000000".to_string());
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/typedstream/PhoneNumber");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        let mut components = parser.parse().unwrap();
+
+        // Replace the archived data detector result with data that does not contain one
+        components[18] = Archivable::Data(vec![OutputData::Array(vec![])]);
+        m.components = Some(components);
+
         assert_eq!(
             parse_body_typedstream(&m).unwrap(),
             vec![BubbleComponent::Text(vec![
@@ -774,6 +998,36 @@ mod typedstream_tests {
             .enumerate()
             .for_each(|(idx, item)| println!("\t{idx}: {item:?}"));
 
+        assert_eq!(
+            parse_body_typedstream(&m).unwrap(),
+            vec![BubbleComponent::Text(vec![
+                TextAttributes::new(0, 17, TextEffect::Default),
+                TextAttributes::new(17, 25, TextEffect::Event("tomorrow".to_string())),
+                TextAttributes::new(25, 26, TextEffect::Default),
+            ])]
+        );
+    }
+
+    #[test]
+    fn can_get_message_body_date_without_event() {
+        let mut m = blank();
+        m.text = Some("Hi. Right now or tomorrow?".to_string());
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/typedstream/Date");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        let mut components = parser.parse().unwrap();
+
+        // Replace the archived data detector result with data that does not contain one
+        components[9] = Archivable::Data(vec![OutputData::Array(vec![])]);
+        m.components = Some(components);
+
         assert_eq!(
             parse_body_typedstream(&m).unwrap(),
             vec![BubbleComponent::Text(vec![
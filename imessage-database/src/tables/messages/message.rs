@@ -18,12 +18,13 @@ use crate::{
     },
     tables::{
         messages::{
-            body::{parse_body_legacy, parse_body_typedstream},
-            models::{BubbleComponent, Service},
+            body::{parse_audio_transcription, parse_body_legacy, parse_body_typedstream},
+            models::{BubbleComponent, ScheduleState, Service},
         },
         table::{
             Cacheable, Diagnostic, Table, ATTRIBUTED_BODY, CHAT_MESSAGE_JOIN, MESSAGE,
             MESSAGE_ATTACHMENT_JOIN, MESSAGE_PAYLOAD, MESSAGE_SUMMARY_INFO, RECENTLY_DELETED,
+            TAPBACK_INDEX,
         },
     },
     util::{
@@ -39,10 +40,12 @@ use crate::{
 const COLS: &str = "rowid, guid, text, service, handle_id, destination_caller_id, subject, date, date_read, date_delivered, is_from_me, is_read, item_type, other_handle, share_status, share_direction, group_title, group_action_type, associated_message_guid, associated_message_type, balloon_bundle_id, expressive_send_style_id, thread_originator_guid, thread_originator_part, date_edited, chat_id";
 
 /// Represents a single row in the `message` table.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[allow(non_snake_case)]
 pub struct Message {
+    /// The `ROWID` of the message in the `message` table
     pub rowid: i32,
+    /// The globally unique identifier of the message
     pub guid: String,
     /// The text of the message, which may require calling [`Self::generate_text()`] to populate
     pub text: Option<String>,
@@ -50,6 +53,7 @@ pub struct Message {
     pub service: Option<String>,
     /// The ID of the person who sent the message
     pub handle_id: Option<i32>,
+    /// The address the database owner used to send or receive the message
     pub destination_caller_id: Option<String>,
     /// The content of the Subject field
     pub subject: Option<String>,
@@ -91,6 +95,10 @@ pub struct Message {
     pub date_edited: i64,
     /// If present, this is the emoji associated with a custom emoji tapback
     pub associated_message_emoji: Option<String>,
+    /// Nonzero if the message was scheduled with Send Later, see [`Self::is_scheduled()`]
+    pub schedule_type: i32,
+    /// Intermediate data for determining the [`ScheduleState`] of a scheduled message
+    pub schedule_state: i32,
     /// The [`identifier`](crate::tables::chat::Chat::chat_identifier) of the chat the message belongs to
     pub chat_id: Option<i32>,
     /// The number of attached files included in the message
@@ -134,6 +142,8 @@ impl Table for Message {
             thread_originator_part: row.get("thread_originator_part").unwrap_or(None),
             date_edited: row.get("date_edited").unwrap_or(0),
             associated_message_emoji: row.get("associated_message_emoji").unwrap_or(None),
+            schedule_type: row.get("schedule_type").unwrap_or(0),
+            schedule_state: row.get("schedule_state").unwrap_or(0),
             chat_id: row.get("chat_id").unwrap_or(None),
             num_attachments: row.get("num_attachments")?,
             deleted_from: row.get("deleted_from").unwrap_or(None),
@@ -145,13 +155,15 @@ impl Table for Message {
 
     /// Convert data from the messages table to native Rust data structures, falling back to
     /// more compatible queries to ensure compatibility with older database schemas
-    fn get(db: &Connection) -> Result<Statement, TableError> {
+    fn get(db: &Connection) -> Result<Statement<'_>, TableError> {
         // If the database has `chat_recoverable_message_join`, we can restore some deleted messages.
         // If database has `thread_originator_guid`, we can parse replies, otherwise default to 0
         Ok(db.prepare(&format!(
-            // macOS Ventura+ and i0S 16+ schema, interpolated with required columns for performance
+            // macOS Sequoia+ and iOS 18+ schema, interpolated with required columns for performance
             "SELECT
                  {COLS},
+                 schedule_type,
+                 schedule_state,
                  c.chat_id,
                  (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
                  (SELECT b.chat_id FROM {RECENTLY_DELETED} b WHERE m.ROWID = b.message_id) as deleted_from,
@@ -163,6 +175,20 @@ impl Table for Message {
                  m.date;
             "
         )).or(db.prepare(&format!(
+            // macOS Ventura to Sonoma, iOS 16 to iOS 17 schema, interpolated with required columns for performance
+            "SELECT
+                 {COLS},
+                 c.chat_id,
+                 (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
+                 (SELECT b.chat_id FROM {RECENTLY_DELETED} b WHERE m.ROWID = b.message_id) as deleted_from,
+                 (SELECT COUNT(*) FROM {MESSAGE} m2 WHERE m2.thread_originator_guid = m.guid) as num_replies
+             FROM
+                 message as m
+                 LEFT JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id
+             ORDER BY
+                 m.date;
+            "
+        ))).or(db.prepare(&format!(
             // macOS Big Sur to Monterey, iOS 14 to iOS 15 schema
             "SELECT
                  *,
@@ -316,7 +342,7 @@ impl Cacheable for Message {
                  *, 
                  c.chat_id, 
                  (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
-                 (SELECT COUNT(*) FROM {MESSAGE} m2 WHERE m2.thread_originator_guid = m.guid) as num_replies
+                 0 as num_replies
              FROM 
                  message as m 
                  LEFT JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id
@@ -437,7 +463,7 @@ impl Message {
     /// If the message has attachments, there will be one [`U+FFFC`](https://www.compart.com/en/unicode/U+FFFC) character
     /// for each attachment and one [`U+FFFD`](https://www.compart.com/en/unicode/U+FFFD) for app messages that we need
     /// to format.
-    pub fn body(&self) -> Vec<BubbleComponent> {
+    pub fn body(&self) -> Vec<BubbleComponent<'_>> {
         // If the message is an app, it will be rendered differently, so just escape there
         if self.balloon_bundle_id.is_some() {
             return vec![BubbleComponent::App];
@@ -451,6 +477,14 @@ impl Message {
         parse_body_legacy(self)
     }
 
+    /// Get the speech-to-text transcription of an audio message, if the device that sent it created one
+    ///
+    /// Newer versions of iOS and macOS store the transcription in the `attributedBody` alongside the audio
+    /// attachment, so this requires [`Self::generate_text()`] to have been called.
+    pub fn audio_transcription(&self) -> Option<&str> {
+        parse_audio_transcription(self)
+    }
+
     /// Calculates the date a message was written to the database.
     ///
     /// This field is stored as a unix timestamp with an epoch of `2001-01-01 00:00:00` in the local time zone
@@ -509,7 +543,10 @@ impl Message {
 
     /// `true` if the message is an [`Announcement`], else `false`
     pub fn is_announcement(&self) -> bool {
-        self.group_title.is_some() || self.group_action_type != 0 || self.is_fully_unsent()
+        self.group_title.is_some()
+            || self.group_action_type != 0
+            || self.is_fully_unsent()
+            || self.is_membership_change()
     }
 
     /// `true` if the message is a [`Tapback`] to another message, else `false`
@@ -560,7 +597,7 @@ impl Message {
 
     /// `true` if all message components were unsent, else `false`
     pub fn is_fully_unsent(&self) -> bool {
-        self.edited_parts.as_ref().map_or(false, |ep| {
+        self.edited_parts.as_ref().is_some_and(|ep| {
             ep.parts
                 .iter()
                 .all(|part| matches!(part.status, EditStatus::Unsent))
@@ -582,9 +619,24 @@ impl Message {
         self.item_type == 6
     }
 
+    /// `true` if the message adds someone to, removes someone from, or leaves a group chat, else `false`
+    pub fn is_membership_change(&self) -> bool {
+        matches!(
+            (self.item_type, self.group_action_type),
+            (1, 0) | (1, 1) | (3, 0)
+        )
+    }
+
+    /// `true` if the message changes the photo of a group chat, else `false`
+    pub fn is_group_photo_change(&self) -> bool {
+        matches!(self.get_announcement(), Some(Announcement::PhotoChange))
+    }
+
     /// `true` if the message was sent by the database owner, else `false`
     pub fn is_from_me(&self) -> bool {
-        self.is_from_me || self.other_handle != 0 && !self.share_direction
+        // Membership changes use `other_handle` for the participant that was added or removed, not the recipient
+        self.is_from_me
+            || !self.is_membership_change() && self.other_handle != 0 && !self.share_direction
     }
 
     /// `true` if the message indicates a user started sharing their location, else `false`
@@ -612,6 +664,35 @@ impl Message {
         self.deleted_from.is_some()
     }
 
+    /// `true` if the message was scheduled with Send Later, whether or not it has been sent yet, else `false`
+    ///
+    /// Only macOS Sequoia and iOS 18 or newer can schedule messages.
+    pub fn is_scheduled(&self) -> bool {
+        self.schedule_type == 2
+    }
+
+    /// Get the [`ScheduleState`] of a message scheduled with Send Later, or `None` if the message was not scheduled
+    pub fn schedule_state(&self) -> Option<ScheduleState> {
+        if !self.is_scheduled() {
+            return None;
+        }
+        Some(match self.schedule_state {
+            1 => ScheduleState::Pending,
+            2 => ScheduleState::Sent,
+            3 => ScheduleState::Canceled,
+            other => ScheduleState::Unknown(other),
+        })
+    }
+
+    /// Calculates the time a scheduled message is set to be sent, or `None` if the message was not scheduled
+    ///
+    /// The Send Later columns added in macOS Sequoia, `schedule_type` and `schedule_state`, do not include a time. Instead,
+    /// the time the message is set to be sent is written to the `date` column, which is why pending messages have a
+    /// `date` in the future and sort where they will be delivered.
+    pub fn date_scheduled(&self, offset: &i64) -> Option<Result<DateTime<Local>, MessageError>> {
+        self.is_scheduled().then(|| self.date(offset))
+    }
+
     /// Get the index of the part of a message a reply is pointing to
     fn get_reply_index(&self) -> usize {
         if let Some(parts) = &self.thread_originator_part {
@@ -784,6 +865,118 @@ impl Message {
         Ok(out_h)
     }
 
+    /// Build a temporary table that maps each tapback to the GUID of the message it reacts to
+    ///
+    /// This is a low-memory alternative to [`Message::cache()`]: only the tapback's `ROWID` and target GUID
+    /// are stored, in an indexed `TEMP` table that `SQLite` can page to disk. Read the tapbacks for a message
+    /// with [`Message::get_indexed_tapbacks()`].
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use imessage_database::util::dirs::default_db_path;
+    /// use imessage_database::tables::table::get_connection;
+    /// use imessage_database::tables::messages::Message;
+    ///
+    /// let db_path = default_db_path();
+    /// let conn = get_connection(&db_path).unwrap();
+    /// Message::index_tapbacks(&conn);
+    /// ```
+    pub fn index_tapbacks(db: &Connection) -> Result<(), TableError> {
+        db.execute_batch(&format!(
+            "DROP TABLE IF EXISTS temp.{TAPBACK_INDEX};
+             CREATE TEMP TABLE {TAPBACK_INDEX} (target_guid TEXT NOT NULL, message_id INTEGER NOT NULL);
+             CREATE INDEX temp.{TAPBACK_INDEX}_target ON {TAPBACK_INDEX} (target_guid);"
+        ))
+        .map_err(TableError::Messages)?;
+
+        // The counts are not used to determine the variant, so skip the subqueries
+        let mut statement = db
+            .prepare(&format!(
+                "SELECT
+                     *,
+                     0 as num_attachments,
+                     0 as num_replies
+                 FROM
+                     {MESSAGE} as m
+                 WHERE m.associated_message_guid NOT NULL
+                "
+            ))
+            .map_err(TableError::Messages)?;
+
+        let transaction = db.unchecked_transaction().map_err(TableError::Messages)?;
+        let mut insert = transaction
+            .prepare(&format!(
+                "INSERT INTO {TAPBACK_INDEX} (target_guid, message_id) VALUES (?1, ?2)"
+            ))
+            .map_err(TableError::Messages)?;
+
+        let messages = statement
+            .query_map([], |row| Ok(Message::from_row(row)))
+            .map_err(TableError::Messages)?;
+
+        // Rows are inserted in the same order `Message::cache()` reads them, so lookups keep that order
+        for message in messages {
+            let message = Self::extract(message)?;
+            if message.is_tapback() {
+                if let Some((_, tapback_target_guid)) = message.clean_associated_guid() {
+                    insert
+                        .execute((tapback_target_guid, message.rowid))
+                        .map_err(TableError::Messages)?;
+                }
+            }
+        }
+
+        drop(insert);
+        transaction.commit().map_err(TableError::Messages)
+    }
+
+    /// Build a `HashMap` of message component index to tapbacks that react to that component
+    /// from the table built by [`Message::index_tapbacks()`]
+    ///
+    /// The result is the same as this message's entry in [`Message::cache()`].
+    pub fn get_indexed_tapbacks(
+        &self,
+        db: &Connection,
+    ) -> Result<HashMap<usize, Vec<Self>>, TableError> {
+        let mut out_h: HashMap<usize, Vec<Self>> = HashMap::new();
+
+        let mut statement = db.prepare_cached(&format!(
+            "SELECT
+                 m.*,
+                 c.chat_id,
+                 (SELECT COUNT(*) FROM {MESSAGE_ATTACHMENT_JOIN} a WHERE m.ROWID = a.message_id) as num_attachments,
+                 0 as num_replies
+             FROM
+                 {TAPBACK_INDEX} as t
+                 INNER JOIN {MESSAGE} as m ON m.ROWID = t.message_id
+                 LEFT JOIN {CHAT_MESSAGE_JOIN} as c ON m.ROWID = c.message_id
+             WHERE t.target_guid = ?1
+             ORDER BY
+                 t.ROWID;
+            "
+        ))
+        .map_err(TableError::Messages)?;
+
+        let messages = statement
+            .query_map([&self.guid], |row| Ok(Message::from_row(row)))
+            .map_err(TableError::Messages)?;
+
+        for message in messages {
+            let msg = Message::extract(message)?;
+            if let Some((idx, _)) = msg.clean_associated_guid() {
+                match out_h.get_mut(&idx) {
+                    Some(body_part) => body_part.push(msg),
+                    None => {
+                        out_h.insert(idx, vec![msg]);
+                    }
+                }
+            }
+        }
+
+        Ok(out_h)
+    }
+
     /// Build a `HashMap` of message component index to messages that reply to that component
     pub fn get_replies(&self, db: &Connection) -> Result<HashMap<usize, Vec<Self>>, TableError> {
         let mut out_h: HashMap<usize, Vec<Self>> = HashMap::new();
@@ -846,7 +1039,7 @@ impl Message {
     }
 
     /// Get the variant of a message, see [`variants`](crate::message_types::variants) for detail.
-    pub fn variant(&self) -> Variant {
+    pub fn variant(&self) -> Variant<'_> {
         // Check if a message was edited first as those have special properties
         if self.is_edited() {
             return Variant::Edited;
@@ -927,7 +1120,7 @@ impl Message {
     }
 
     /// Determine the type of announcement a message contains, if it contains one
-    pub fn get_announcement(&self) -> Option<Announcement> {
+    pub fn get_announcement(&self) -> Option<Announcement<'_>> {
         if let Some(name) = &self.group_title {
             return Some(Announcement::NameChange(name));
         }
@@ -936,15 +1129,18 @@ impl Message {
             return Some(Announcement::FullyUnsent);
         }
 
-        return match &self.group_action_type {
-            0 => None,
-            1 => Some(Announcement::PhotoChange),
-            other => Some(Announcement::Unknown(other)),
-        };
+        match (self.item_type, &self.group_action_type) {
+            (1, 0) => Some(Announcement::ParticipantAdded(&self.other_handle)),
+            (1, 1) => Some(Announcement::ParticipantRemoved(&self.other_handle)),
+            (3, 0) => Some(Announcement::ParticipantLeft),
+            (_, 0) => None,
+            (_, 1) => Some(Announcement::PhotoChange),
+            (_, other) => Some(Announcement::Unknown(other)),
+        }
     }
 
     /// Determine the service the message was sent from, i.e. iMessage, SMS, IRC, etc.
-    pub fn service(&self) -> Service {
+    pub fn service(&self) -> Service<'_> {
         if let Some(service_name) = self.service.as_deref() {
             return match service_name.trim() {
                 "iMessage" => Service::iMessage,
@@ -958,16 +1154,14 @@ impl Message {
 
     /// Extract a blob of data that belongs to a single message from a given column
     fn get_blob<'a>(&self, db: &'a Connection, column: &str) -> Option<Blob<'a>> {
-        match db.blob_open(
+        db.blob_open(
             rusqlite::DatabaseName::Main,
             MESSAGE,
             column,
             self.rowid as i64,
             true,
-        ) {
-            Ok(blob) => Some(blob),
-            Err(_) => None,
-        }
+        )
+        .ok()
     }
 
     /// Get a message's plist from the `payload_data` BLOB column
@@ -1019,7 +1213,7 @@ impl Message {
     }
 
     /// Determine which expressive the message was sent with
-    pub fn get_expressive(&self) -> Expressive {
+    pub fn get_expressive(&self) -> Expressive<'_> {
         match &self.expressive_send_style_id {
             Some(content) => match content.as_str() {
                 "com.apple.MobileSMS.expressivesend.gentle" => {
@@ -1066,15 +1260,23 @@ impl Message {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use rusqlite::Connection;
+
     use crate::{
         message_types::{
             edited::{EditStatus, EditedMessage, EditedMessagePart},
             expressives,
-            variants::{CustomBalloon, Variant},
+            variants::{Announcement, CustomBalloon, Variant},
+        },
+        tables::{
+            messages::{models::ScheduleState, Message},
+            table::{get_connection, Cacheable, Table},
         },
-        tables::messages::Message,
         util::dates::get_offset,
     };
+    use imessage_fixtures::{Fixture, NewMessage, Schema};
 
     fn blank() -> Message {
         Message {
@@ -1104,6 +1306,8 @@ mod tests {
             thread_originator_part: None,
             date_edited: 0,
             associated_message_emoji: None,
+            schedule_type: 0,
+            schedule_state: 0,
             chat_id: None,
             num_attachments: 0,
             deleted_from: None,
@@ -1406,4 +1610,299 @@ mod tests {
 
         assert!(!m.is_fully_unsent());
     }
+
+    #[test]
+    fn can_get_announcement_participant_added() {
+        let mut m = blank();
+        m.item_type = 1;
+        m.other_handle = 3;
+
+        assert!(m.is_announcement());
+        assert!(!m.is_from_me());
+        assert_eq!(
+            m.get_announcement(),
+            Some(Announcement::ParticipantAdded(&3))
+        );
+    }
+
+    #[test]
+    fn can_get_announcement_participant_removed() {
+        let mut m = blank();
+        m.item_type = 1;
+        m.group_action_type = 1;
+        m.other_handle = 3;
+        m.is_from_me = true;
+
+        assert!(m.is_announcement());
+        assert!(m.is_from_me());
+        assert_eq!(
+            m.get_announcement(),
+            Some(Announcement::ParticipantRemoved(&3))
+        );
+    }
+
+    #[test]
+    fn can_get_announcement_participant_left() {
+        let mut m = blank();
+        m.item_type = 3;
+
+        assert!(m.is_announcement());
+        assert_eq!(m.get_announcement(), Some(Announcement::ParticipantLeft));
+    }
+
+    #[test]
+    fn can_get_announcement_photo_change() {
+        let mut m = blank();
+        m.item_type = 3;
+        m.group_action_type = 1;
+
+        assert_eq!(m.get_announcement(), Some(Announcement::PhotoChange));
+    }
+
+    #[test]
+    fn cant_get_announcement_normal_message() {
+        let m = blank();
+
+        assert!(!m.is_announcement());
+        assert_eq!(m.get_announcement(), None);
+    }
+
+    #[test]
+    fn can_get_schedule_state_pending() {
+        let mut m = blank();
+        m.schedule_type = 2;
+        m.schedule_state = 1;
+
+        assert!(m.is_scheduled());
+        assert_eq!(m.schedule_state(), Some(ScheduleState::Pending));
+    }
+
+    #[test]
+    fn can_get_schedule_state_sent() {
+        let mut m = blank();
+        m.schedule_type = 2;
+        m.schedule_state = 2;
+
+        assert!(m.is_scheduled());
+        assert_eq!(m.schedule_state(), Some(ScheduleState::Sent));
+    }
+
+    #[test]
+    fn cant_get_schedule_state_not_scheduled() {
+        let mut m = blank();
+        m.schedule_state = 1;
+
+        assert!(!m.is_scheduled());
+        assert_eq!(m.schedule_state(), None);
+        assert!(m.date_scheduled(&get_offset()).is_none());
+    }
+
+    #[test]
+    fn can_get_date_scheduled() {
+        let offset = get_offset();
+        let mut m = blank();
+        m.date = 674526582885055488;
+        m.schedule_type = 2;
+
+        assert_eq!(
+            m.date_scheduled(&offset).unwrap().unwrap(),
+            m.date(&offset).unwrap()
+        );
+    }
+
+    #[test]
+    fn can_read_date_scheduled_from_date_column() {
+        let fixture = Fixture::temporary(Schema::Sequoia).unwrap();
+        let chat = fixture
+            .add_chat("+15555550100", "iMessage", None, &[])
+            .unwrap();
+        fixture
+            .add_message(&NewMessage {
+                guid: "00000000-0000-0000-0000-000000000001".to_string(),
+                text: Some("Happy birthday!".to_string()),
+                service: Some("iMessage".to_string()),
+                date: 674526582885055488,
+                is_from_me: true,
+                schedule_type: 2,
+                schedule_state: 1,
+                chat_id: Some(chat),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let db = get_connection(&fixture.db_path()).unwrap();
+        let mut statement = Message::get(&db).unwrap();
+        let message = statement
+            .query_map([], |row| Ok(Message::from_row(row)))
+            .unwrap()
+            .map(|message| Message::extract(message).unwrap())
+            .next()
+            .unwrap();
+
+        let offset = get_offset();
+        assert_eq!(message.schedule_state(), Some(ScheduleState::Pending));
+        assert_eq!(message.date, 674526582885055488);
+        assert_eq!(
+            message.date_scheduled(&offset).unwrap().unwrap(),
+            message.date(&offset).unwrap()
+        );
+    }
+
+    #[test]
+    fn can_read_scheduled_by_schema() {
+        for (schema, scheduled) in [(Schema::Ventura, 0), (Schema::Sequoia, 2)] {
+            let fixture = Fixture::temporary(schema).unwrap();
+            fixture.populate().unwrap();
+
+            let db = get_connection(&fixture.db_path()).unwrap();
+            let mut statement = Message::get(&db).unwrap();
+            let messages: Vec<Message> = statement
+                .query_map([], |row| Ok(Message::from_row(row)))
+                .unwrap()
+                .map(|message| Message::extract(message).unwrap())
+                .collect();
+
+            assert_eq!(
+                messages.iter().filter(|m| m.is_scheduled()).count(),
+                scheduled
+            );
+            // Older schemas still read from the fastest query they support
+            assert_eq!(messages.iter().filter(|m| m.is_deleted()).count(), 1);
+        }
+    }
+
+    /// Build a small database with a few messages and tapbacks, returning it and a read-only connection to it
+    fn tapback_fixture() -> (Fixture, Connection) {
+        let fixture = Fixture::temporary(Schema::latest()).unwrap();
+        let chat = fixture
+            .add_chat("+15555550100", "iMessage", None, &[])
+            .unwrap();
+        let other_chat = fixture
+            .add_chat("+15555550101", "iMessage", None, &[])
+            .unwrap();
+
+        for (guid, text) in [
+            ("00000000-0000-0000-0000-000000000001", "First"),
+            ("00000000-0000-0000-0000-000000000002", "Second"),
+        ] {
+            fixture
+                .add_message(&NewMessage {
+                    guid: guid.to_string(),
+                    text: Some(text.to_string()),
+                    chat_id: Some(chat),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        for (guid, target, kind) in [
+            ("A", "p:0/00000000-0000-0000-0000-000000000001", 2000),
+            ("B", "p:1/00000000-0000-0000-0000-000000000001", 2001),
+            ("C", "bp:00000000-0000-0000-0000-000000000002", 2003),
+            ("D", "p:0/00000000-0000-0000-0000-000000000001", 3000),
+            ("E", "p:0/00000000-0000-0000-0000-000000000001", 1000),
+        ] {
+            let rowid = fixture
+                .add_message(&NewMessage {
+                    guid: guid.to_string(),
+                    associated_message_guid: Some(target.to_string()),
+                    associated_message_type: kind,
+                    chat_id: Some(chat),
+                    ..Default::default()
+                })
+                .unwrap();
+            // The same tapback can be joined to more than one chat
+            if guid == "D" {
+                fixture
+                    .db
+                    .execute(
+                        "INSERT INTO chat_message_join (chat_id, message_id) VALUES (?1, ?2)",
+                        (other_chat, rowid),
+                    )
+                    .unwrap();
+            }
+        }
+
+        let db = get_connection(&fixture.db_path()).unwrap();
+        (fixture, db)
+    }
+
+    /// Read a single message from the tapback fixture
+    fn get_message(db: &Connection, guid: &str) -> Message {
+        db.query_row(
+            "SELECT *, 0 as num_attachments, 0 as num_replies FROM message WHERE guid = ?1",
+            [guid],
+            Message::from_row,
+        )
+        .unwrap()
+    }
+
+    /// Reduce a map of tapbacks to the GUIDs of each tapback so they can be compared
+    fn guids(tapbacks: &HashMap<usize, Vec<Message>>) -> HashMap<usize, Vec<String>> {
+        tapbacks
+            .iter()
+            .map(|(idx, messages)| (*idx, messages.iter().map(|m| m.guid.clone()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn can_index_tapbacks_read_only() {
+        let (_fixture, db) = tapback_fixture();
+        assert!(Message::index_tapbacks(&db).is_ok());
+        // Building the index again should replace the old one
+        assert!(Message::index_tapbacks(&db).is_ok());
+    }
+
+    #[test]
+    fn cant_index_tapbacks_without_messages() {
+        let (fixture, db) = tapback_fixture();
+        fixture.db.execute_batch("DROP TABLE message").unwrap();
+        assert!(Message::index_tapbacks(&db).is_err());
+    }
+
+    #[test]
+    fn can_get_indexed_tapbacks_same_as_cache() {
+        let (_fixture, db) = tapback_fixture();
+        let cache = Message::cache(&db).unwrap();
+        Message::index_tapbacks(&db).unwrap();
+
+        for guid in [
+            "00000000-0000-0000-0000-000000000001",
+            "00000000-0000-0000-0000-000000000002",
+        ] {
+            let indexed = get_message(&db, guid).get_indexed_tapbacks(&db).unwrap();
+            assert_eq!(guids(&indexed), guids(cache.get(guid).unwrap()));
+        }
+    }
+
+    #[test]
+    fn can_get_indexed_tapbacks_duplicated_across_chats() {
+        let (_fixture, db) = tapback_fixture();
+        Message::index_tapbacks(&db).unwrap();
+
+        let indexed = get_message(&db, "00000000-0000-0000-0000-000000000001")
+            .get_indexed_tapbacks(&db)
+            .unwrap();
+        let expected = HashMap::from([
+            (
+                0,
+                vec![
+                    "A".to_string(),
+                    "D".to_string(),
+                    "D".to_string(),
+                    "E".to_string(),
+                ],
+            ),
+            (1, vec!["B".to_string()]),
+        ]);
+        assert_eq!(guids(&indexed), expected);
+    }
+
+    #[test]
+    fn can_get_indexed_tapbacks_empty() {
+        let (_fixture, db) = tapback_fixture();
+        Message::index_tapbacks(&db).unwrap();
+
+        let indexed = get_message(&db, "A").get_indexed_tapbacks(&db).unwrap();
+        assert!(indexed.is_empty());
+    }
 }
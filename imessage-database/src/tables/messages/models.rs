@@ -2,9 +2,16 @@
  This module contains Data structures and models that represent message data.
 */
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::message_types::text_effects::TextEffect;
+use crate::{
+    message_types::{
+        edited::EditedMessage,
+        text_effects::{OwnedTextEffect, TextEffect},
+    },
+    tables::messages::Message,
+    util::typedstream::models::Archivable,
+};
 
 /// Defines the parts of a message bubble, i.e. the content that can exist in a single message.
 ///
@@ -25,6 +32,32 @@ pub enum BubbleComponent<'a> {
     Retracted,
 }
 
+/// An owned version of [`BubbleComponent`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OwnedBubbleComponent {
+    /// A text message with associated formatting
+    Text(Vec<OwnedTextAttributes>),
+    /// An attachment
+    Attachment(String),
+    /// An app integration
+    App,
+    /// A component that was retracted
+    Retracted,
+}
+
+impl From<&BubbleComponent<'_>> for OwnedBubbleComponent {
+    fn from(component: &BubbleComponent<'_>) -> Self {
+        match component {
+            BubbleComponent::Text(attributes) => {
+                Self::Text(attributes.iter().map(OwnedTextAttributes::from).collect())
+            }
+            BubbleComponent::Attachment(guid) => Self::Attachment(guid.to_string()),
+            BubbleComponent::App => Self::App,
+            BubbleComponent::Retracted => Self::Retracted,
+        }
+    }
+}
+
 /// Defines different types of services we can receive messages from.
 #[derive(Debug, Serialize)]
 pub enum Service<'a> {
@@ -41,6 +74,47 @@ pub enum Service<'a> {
     Unknown,
 }
 
+/// An owned version of [`Service`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OwnedService {
+    /// An iMessage
+    #[allow(non_camel_case_types)]
+    iMessage,
+    /// A message sent as SMS
+    SMS,
+    /// A message sent as RCS
+    RCS,
+    /// Any other type of message
+    Other(String),
+    /// Used when service field is not set
+    Unknown,
+}
+
+impl From<&Service<'_>> for OwnedService {
+    fn from(service: &Service<'_>) -> Self {
+        match service {
+            Service::iMessage => Self::iMessage,
+            Service::SMS => Self::SMS,
+            Service::RCS => Self::RCS,
+            Service::Other(service) => Self::Other(service.to_string()),
+            Service::Unknown => Self::Unknown,
+        }
+    }
+}
+
+/// Defines the states of a message scheduled with Send Later.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum ScheduleState {
+    /// The message is waiting to be sent at its scheduled time
+    Pending,
+    /// The message was sent at its scheduled time
+    Sent,
+    /// The message was canceled before it was sent
+    Canceled,
+    /// Any state we do not know how to interpret
+    Unknown(i32),
+}
+
 /// Defines ranges of text and associated attributes parsed from [`typedstream`](crate::util::typedstream) `attributedBody` data.
 ///
 /// Ranges specify locations attributes applied to specific portions of a [`Message`](crate::tables::messages::Message)'s [`text`](crate::tables::messages::Message::text). For example, given message text with a [`Mention`](TextEffect::Mention) like:
@@ -71,8 +145,216 @@ pub struct TextAttributes<'a> {
     pub effect: TextEffect<'a>,
 }
 
+/// An owned version of [`TextAttributes`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnedTextAttributes {
+    /// The start index of the affected range of message text
+    pub start: usize,
+    /// The end index of the affected range of message text
+    pub end: usize,
+    /// The effects applied to the specified range
+    pub effect: OwnedTextEffect,
+}
+
+impl From<&TextAttributes<'_>> for OwnedTextAttributes {
+    fn from(attributes: &TextAttributes<'_>) -> Self {
+        Self {
+            start: attributes.start,
+            end: attributes.end,
+            effect: OwnedTextEffect::from(&attributes.effect),
+        }
+    }
+}
+
 impl<'a> TextAttributes<'a> {
     pub fn new(start: usize, end: usize, effect: TextEffect<'a>) -> Self {
         Self { start, end, effect }
     }
 }
+
+/// An owned version of a [`Message`] row
+///
+/// This can read a serialized [`Message`] back, including the messages in `imessage-exporter`'s JSON exports.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnedMessage {
+    /// The `ROWID` of the message in the `message` table
+    pub rowid: i32,
+    /// The globally unique identifier of the message
+    pub guid: String,
+    /// The text of the message, which may require calling [`Message::generate_text()`] to populate
+    pub text: Option<String>,
+    /// The service the message was sent from
+    pub service: Option<String>,
+    /// The ID of the person who sent the message
+    pub handle_id: Option<i32>,
+    /// The address the database owner used to send or receive the message
+    pub destination_caller_id: Option<String>,
+    /// The content of the Subject field
+    pub subject: Option<String>,
+    /// The date the message was written to the database
+    pub date: i64,
+    /// The date the message was read
+    pub date_read: i64,
+    /// The date a message was delivered
+    pub date_delivered: i64,
+    /// `true` if the database owner sent the message, else `false`
+    pub is_from_me: bool,
+    /// `true` if the message was read by the recipient, else `false`
+    pub is_read: bool,
+    /// Intermediate data for determining the [`Variant`](crate::message_types::variants::Variant) of a message
+    pub item_type: i32,
+    /// Optional handle for the recipient of a message that includes shared content
+    pub other_handle: i32,
+    /// Boolean determining whether some shared data is active or inactive, i.e. shared location being enabled or disabled
+    pub share_status: bool,
+    /// Boolean determining the direction shared data was sent; `false` indicates it was sent from the database owner, `true` indicates it was sent to the database owner
+    pub share_direction: bool,
+    /// If the message updates the [`display_name`](crate::tables::chat::Chat::display_name) of the chat, this field will be populated
+    pub group_title: Option<String>,
+    /// If the message modified for a group, this will be nonzero
+    pub group_action_type: i32,
+    /// The message GUID of a message associated with this one
+    pub associated_message_guid: Option<String>,
+    /// Intermediate data for determining the [`Variant`](crate::message_types::variants::Variant) of a message
+    pub associated_message_type: Option<i32>,
+    /// The [bundle ID](https://developer.apple.com/help/app-store-connect/reference/app-bundle-information) of the app that generated the [`AppMessage`](crate::message_types::app::AppMessage)
+    pub balloon_bundle_id: Option<String>,
+    /// Intermediate data for determining the [`expressive`](crate::message_types::expressives) of a message
+    pub expressive_send_style_id: Option<String>,
+    /// Indicates the first message in a thread of replies in [`get_replies()`](crate::tables::messages::Message::get_replies)
+    pub thread_originator_guid: Option<String>,
+    /// Indicates the part of a message a reply is pointing to
+    pub thread_originator_part: Option<String>,
+    /// The date the message was most recently edited
+    pub date_edited: i64,
+    /// If present, this is the emoji associated with a custom emoji tapback
+    pub associated_message_emoji: Option<String>,
+    /// Nonzero if the message was scheduled with Send Later, see [`Message::is_scheduled()`]
+    pub schedule_type: i32,
+    /// Intermediate data for determining the [`ScheduleState`] of a scheduled message
+    pub schedule_state: i32,
+    /// The [`identifier`](crate::tables::chat::Chat::chat_identifier) of the chat the message belongs to
+    pub chat_id: Option<i32>,
+    /// The number of attached files included in the message
+    pub num_attachments: i32,
+    /// The [`identifier`](crate::tables::chat::Chat::chat_identifier) of the chat the message was deleted from
+    pub deleted_from: Option<i32>,
+    /// The number of replies to the message
+    pub num_replies: i32,
+    /// The components of the message body, parsed by [`TypedStreamReader`](crate::util::typedstream::parser::TypedStreamReader)
+    pub components: Option<Vec<Archivable>>,
+    /// The components of the message that may or may not have been edited or unsent
+    pub edited_parts: Option<EditedMessage>,
+}
+
+impl From<&Message> for OwnedMessage {
+    fn from(message: &Message) -> Self {
+        Self {
+            rowid: message.rowid,
+            guid: message.guid.clone(),
+            text: message.text.clone(),
+            service: message.service.clone(),
+            handle_id: message.handle_id,
+            destination_caller_id: message.destination_caller_id.clone(),
+            subject: message.subject.clone(),
+            date: message.date,
+            date_read: message.date_read,
+            date_delivered: message.date_delivered,
+            is_from_me: message.is_from_me,
+            is_read: message.is_read,
+            item_type: message.item_type,
+            other_handle: message.other_handle,
+            share_status: message.share_status,
+            share_direction: message.share_direction,
+            group_title: message.group_title.clone(),
+            group_action_type: message.group_action_type,
+            associated_message_guid: message.associated_message_guid.clone(),
+            associated_message_type: message.associated_message_type,
+            balloon_bundle_id: message.balloon_bundle_id.clone(),
+            expressive_send_style_id: message.expressive_send_style_id.clone(),
+            thread_originator_guid: message.thread_originator_guid.clone(),
+            thread_originator_part: message.thread_originator_part.clone(),
+            date_edited: message.date_edited,
+            associated_message_emoji: message.associated_message_emoji.clone(),
+            schedule_type: message.schedule_type,
+            schedule_state: message.schedule_state,
+            chat_id: message.chat_id,
+            num_attachments: message.num_attachments,
+            deleted_from: message.deleted_from,
+            num_replies: message.num_replies,
+            components: message.components.clone(),
+            edited_parts: message.edited_parts.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        message_types::text_effects::{OwnedTextEffect, Style, TextEffect},
+        tables::messages::models::{
+            BubbleComponent, OwnedBubbleComponent, OwnedMessage, OwnedService, OwnedTextAttributes,
+            Service, TextAttributes,
+        },
+    };
+
+    #[test]
+    fn can_round_trip_owned_component() {
+        let component = BubbleComponent::Text(vec![
+            TextAttributes::new(0, 11, TextEffect::Default),
+            TextAttributes::new(11, 22, TextEffect::Mention("+5558675309")),
+            TextAttributes::new(22, 23, TextEffect::Styles(vec![Style::Bold])),
+        ]);
+
+        let json = serde_json::to_string(&component).unwrap();
+        let owned: OwnedBubbleComponent = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(owned, OwnedBubbleComponent::from(&component));
+        assert_eq!(
+            owned,
+            OwnedBubbleComponent::Text(vec![
+                OwnedTextAttributes {
+                    start: 0,
+                    end: 11,
+                    effect: OwnedTextEffect::Default
+                },
+                OwnedTextAttributes {
+                    start: 11,
+                    end: 22,
+                    effect: OwnedTextEffect::Mention("+5558675309".to_string())
+                },
+                OwnedTextAttributes {
+                    start: 22,
+                    end: 23,
+                    effect: OwnedTextEffect::Styles(vec![Style::Bold])
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn can_round_trip_owned_service() {
+        let service = Service::Other("Satellite");
+
+        let json = serde_json::to_string(&service).unwrap();
+        let owned: OwnedService = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(owned, OwnedService::Other("Satellite".to_string()));
+    }
+
+    #[test]
+    fn can_read_owned_message() {
+        let json = r#"{"rowid":1,"guid":"guid","text":"Call 5558675309 tomorrow","service":"iMessage","handle_id":1,"destination_caller_id":null,"subject":null,"date":1,"date_read":2,"date_delivered":3,"is_from_me":false,"is_read":true,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":2,"schedule_state":1,"chat_id":1,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+
+        let owned: OwnedMessage = serde_json::from_str(json).unwrap();
+        assert_eq!(owned.text.as_deref(), Some("Call 5558675309 tomorrow"));
+        assert_eq!(owned.schedule_type, 2);
+        assert_eq!(owned.schedule_state, 1);
+
+        let round_trip: serde_json::Value = serde_json::to_value(&owned).unwrap();
+        assert_eq!(
+            round_trip,
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+    }
+}
@@ -8,6 +8,10 @@ of macOS, the schema of the iMessage database can vary.
 pub mod attachment;
 pub mod chat;
 pub mod chat_handle;
+pub mod group_photo;
 pub mod handle;
+pub mod membership;
 pub mod messages;
+pub mod recently_deleted;
+pub mod store;
 pub mod table;
@@ -0,0 +1,288 @@
+/*!
+ This module represents the `chat_recoverable_message_join` table, which tracks messages in Recently Deleted.
+
+ Messages removed by deleting a single message or an entire conversation are kept for [`RETENTION_DAYS`]
+ before they are permanently deleted. Until then, they can be restored to the chat they were deleted from.
+ This table only exists on macOS Ventura and iOS 16 or newer, so its dates are always stored in nanoseconds.
+*/
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Local};
+use rusqlite::{Connection, Error, OptionalExtension, Result, Row, Statement};
+use serde::Serialize;
+
+use crate::{
+    error::table::TableError,
+    tables::table::{Cacheable, Diagnostic, Queryable, Table, RECENTLY_DELETED},
+    util::{
+        dates::get_local_time,
+        output::{done_processing, processing},
+    },
+};
+
+/// The number of days a message stays in Recently Deleted before it is permanently deleted
+pub const RETENTION_DAYS: i64 = 30;
+
+/// Represents a single row in the `chat_recoverable_message_join` table.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub struct DeletedMessage {
+    /// The `ROWID` of the deleted message
+    pub message_id: i32,
+    /// The `ROWID` of the chat the message was deleted from
+    pub chat_id: i32,
+    /// The date the message was deleted, if the database recorded it
+    pub delete_date: Option<i64>,
+}
+
+impl Table for DeletedMessage {
+    fn from_row(row: &Row) -> Result<DeletedMessage> {
+        Ok(DeletedMessage {
+            message_id: row.get("message_id")?,
+            chat_id: row.get("chat_id")?,
+            delete_date: row.get("delete_date").unwrap_or(None),
+        })
+    }
+
+    fn get(db: &Connection) -> Result<Statement<'_>, TableError> {
+        db.prepare(&format!("SELECT * FROM {RECENTLY_DELETED}"))
+            .map_err(TableError::Messages)
+    }
+
+    fn extract(deleted: Result<Result<Self, Error>, Error>) -> Result<Self, TableError> {
+        match deleted {
+            Ok(Ok(deleted)) => Ok(deleted),
+            Err(why) | Ok(Err(why)) => Err(TableError::Messages(why)),
+        }
+    }
+}
+
+impl Cacheable for DeletedMessage {
+    type K = i32;
+    type V = DeletedMessage;
+    /// Generate a hashmap containing each recoverable message's ID pointing to where and when it was deleted
+    ///
+    /// Databases that do not support Recently Deleted produce an empty map.
+    ///
+    /// # Example:
+    ///
+    /// ```no_run
+    /// use imessage_database::util::dirs::default_db_path;
+    /// use imessage_database::tables::table::{Cacheable, get_connection};
+    /// use imessage_database::tables::recently_deleted::DeletedMessage;
+    ///
+    /// let db_path = default_db_path();
+    /// let conn = get_connection(&db_path).unwrap();
+    /// let deleted = DeletedMessage::cache(&conn);
+    /// ```
+    fn cache(db: &Connection) -> Result<HashMap<Self::K, Self::V>, TableError> {
+        let mut map = HashMap::new();
+        if !DeletedMessage::is_supported(db) {
+            return Ok(map);
+        }
+
+        let mut statement = DeletedMessage::get(db)?;
+        let rows = statement
+            .query_map([], |row| Ok(DeletedMessage::from_row(row)))
+            .map_err(TableError::Messages)?;
+
+        for deleted in rows {
+            let deleted = DeletedMessage::extract(deleted)?;
+            map.insert(deleted.message_id, deleted);
+        }
+
+        Ok(map)
+    }
+}
+
+impl Queryable for DeletedMessage {
+    /// Read where and when a single message was deleted, if it is in Recently Deleted
+    ///
+    /// Databases that do not support Recently Deleted have no deleted messages, matching [`DeletedMessage::cache()`].
+    fn query(db: &Connection, message_id: &i32) -> Result<Option<Self>, TableError> {
+        if !DeletedMessage::is_supported(db) {
+            return Ok(None);
+        }
+
+        db.query_row(
+            &format!("SELECT * FROM {RECENTLY_DELETED} WHERE message_id = ?1"),
+            [message_id],
+            DeletedMessage::from_row,
+        )
+        .optional()
+        .map_err(TableError::Messages)
+    }
+}
+
+impl Diagnostic for DeletedMessage {
+    /// Emit diagnostic data for messages in Recently Deleted
+    ///
+    /// # Example:
+    ///
+    /// ```no_run
+    /// use imessage_database::util::dirs::default_db_path;
+    /// use imessage_database::tables::table::{Diagnostic, get_connection};
+    /// use imessage_database::tables::recently_deleted::DeletedMessage;
+    ///
+    /// let db_path = default_db_path();
+    /// let conn = get_connection(&db_path).unwrap();
+    /// DeletedMessage::run_diagnostic(&conn);
+    /// ```
+    fn run_diagnostic(db: &Connection) -> Result<(), TableError> {
+        if !DeletedMessage::is_supported(db) {
+            return Ok(());
+        }
+
+        processing();
+        let deleted = DeletedMessage::cache(db)?;
+        let chats: HashSet<i32> = deleted.values().map(|message| message.chat_id).collect();
+        done_processing();
+
+        if !deleted.is_empty() {
+            println!("Recently Deleted diagnostic data:");
+            println!(
+                "    Recoverable messages: {} from {} chats",
+                deleted.len(),
+                chats.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl DeletedMessage {
+    /// `true` if the database has Recently Deleted, else `false`
+    pub fn is_supported(db: &Connection) -> bool {
+        db.prepare(&format!("SELECT 1 FROM {RECENTLY_DELETED} LIMIT 1"))
+            .is_ok()
+    }
+
+    /// Get the time the message was deleted, if the database recorded it
+    ///
+    /// Calculated from the deletion date and the iMessage epoch offset from [`get_offset()`](crate::util::dates::get_offset).
+    pub fn date_deleted(&self, offset: &i64) -> Option<DateTime<Local>> {
+        get_local_time(self.delete_date.as_ref().filter(|date| **date > 0)?, offset).ok()
+    }
+
+    /// The number of whole days before the message is permanently deleted, as of `now`
+    ///
+    /// Returns `0` if the message is past due to be removed and `None` if the database did not record when it was deleted.
+    pub fn days_until_purge(&self, offset: &i64, now: &DateTime<Local>) -> Option<i64> {
+        let deleted = self.date_deleted(offset)?;
+        let remaining = RETENTION_DAYS - (*now - deleted).num_days();
+        Some(remaining.clamp(0, RETENTION_DAYS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use crate::{
+        tables::{
+            recently_deleted::{DeletedMessage, RETENTION_DAYS},
+            table::{get_connection, Cacheable, Queryable},
+        },
+        util::dates::get_offset,
+    };
+    use imessage_fixtures::{Fixture, NewMessage, Schema};
+
+    fn deleted(delete_date: Option<i64>) -> DeletedMessage {
+        DeletedMessage {
+            message_id: 1,
+            chat_id: 1,
+            delete_date,
+        }
+    }
+
+    #[test]
+    fn can_cache_deleted() {
+        let fixture = Fixture::temporary(Schema::latest()).unwrap();
+        let handle = fixture
+            .add_handle("+15555550101", "iMessage", None)
+            .unwrap();
+        let chat = fixture
+            .add_chat("chat0", "iMessage", None, &[handle])
+            .unwrap();
+
+        fixture
+            .add_message(&NewMessage {
+                guid: "kept".to_string(),
+                chat_id: Some(chat),
+                ..Default::default()
+            })
+            .unwrap();
+        let message_id = fixture
+            .add_message(&NewMessage {
+                guid: "deleted".to_string(),
+                date: 674526582885055488,
+                deleted_from: Some(chat),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let db = get_connection(&fixture.db_path()).unwrap();
+        assert!(DeletedMessage::is_supported(&db));
+
+        let cache = DeletedMessage::cache(&db).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            cache.get(&message_id),
+            Some(&DeletedMessage {
+                message_id,
+                chat_id: chat,
+                delete_date: Some(674526582885055488),
+            })
+        );
+        // Reading a single message gives the same result as the cache
+        assert_eq!(
+            DeletedMessage::query(&db, &message_id).unwrap().as_ref(),
+            cache.get(&message_id)
+        );
+        assert!(DeletedMessage::query(&db, &(message_id - 1))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn cant_cache_deleted_unsupported() {
+        let fixture = Fixture::temporary(Schema::Catalina).unwrap();
+
+        let db = get_connection(&fixture.db_path()).unwrap();
+        assert!(!DeletedMessage::is_supported(&db));
+        assert!(DeletedMessage::cache(&db).unwrap().is_empty());
+        assert!(DeletedMessage::query(&db, &1).unwrap().is_none());
+    }
+
+    #[test]
+    fn can_get_days_until_purge() {
+        let offset = get_offset();
+        let message = deleted(Some(674526582885055488));
+        let deleted_at = message.date_deleted(&offset).unwrap();
+
+        assert_eq!(
+            message.days_until_purge(&offset, &deleted_at),
+            Some(RETENTION_DAYS)
+        );
+        assert_eq!(
+            message.days_until_purge(&offset, &(deleted_at + Duration::days(12))),
+            Some(18)
+        );
+        assert_eq!(
+            message.days_until_purge(&offset, &(deleted_at + Duration::days(45))),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn cant_get_days_until_purge_missing_date() {
+        let offset = get_offset();
+        let now = deleted(Some(674526582885055488))
+            .date_deleted(&offset)
+            .unwrap();
+
+        assert!(deleted(None).date_deleted(&offset).is_none());
+        assert!(deleted(Some(0)).date_deleted(&offset).is_none());
+        assert!(deleted(None).days_until_purge(&offset, &now).is_none());
+    }
+}
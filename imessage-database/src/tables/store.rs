@@ -0,0 +1,62 @@
+/*!
+ Contains data structures used to look up cached table data without always holding the whole table in memory.
+*/
+
+use std::{collections::HashMap, hash::Hash, ops::Deref};
+
+use rusqlite::Connection;
+
+use crate::{error::table::TableError, tables::table::Queryable};
+
+/// Represents where the data cached from a table is stored
+pub enum CacheStore<T: Queryable> {
+    /// Every value is cached in memory, as built by [`Cacheable::cache()`](crate::tables::table::Cacheable::cache)
+    Memory(HashMap<T::K, T::V>),
+    /// Values are read from the database as needed with [`Queryable::query()`]
+    Queried,
+}
+
+impl<T: Queryable> CacheStore<T>
+where
+    T::K: Eq + Hash,
+{
+    /// Build the store, reading values from the database as needed if `low_memory` is set
+    pub fn build(db: &Connection, low_memory: bool) -> Result<Self, TableError> {
+        if low_memory {
+            return Ok(Self::Queried);
+        }
+        Ok(Self::Memory(T::cache(db)?))
+    }
+
+    /// Get the value stored for `key`, if there is one
+    pub fn get<'a>(
+        &'a self,
+        db: &Connection,
+        key: &T::K,
+    ) -> Result<Option<Stored<'a, T::V>>, TableError> {
+        match self {
+            CacheStore::Memory(map) => Ok(map.get(key).map(Stored::Cached)),
+            CacheStore::Queried => Ok(T::query(db, key)?.map(Stored::Queried)),
+        }
+    }
+}
+
+/// A single value read from a [`CacheStore`]
+#[derive(Debug)]
+pub enum Stored<'a, V> {
+    /// A value borrowed from [`CacheStore::Memory`]
+    Cached(&'a V),
+    /// A value read from the database for [`CacheStore::Queried`]
+    Queried(V),
+}
+
+impl<V> Deref for Stored<'_, V> {
+    type Target = V;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Stored::Cached(value) => value,
+            Stored::Queried(value) => value,
+        }
+    }
+}
@@ -15,7 +15,7 @@ pub trait Table {
     where
         Self: Sized;
     /// Gets a statement we can execute to iterate over the data in the table
-    fn get(db: &Connection) -> Result<Statement, TableError>;
+    fn get(db: &Connection) -> Result<Statement<'_>, TableError>;
 
     /// Extract valid row data while handling both types of query errors
     fn extract(item: Result<Result<Self, Error>, Error>) -> Result<Self, TableError>
@@ -30,6 +30,12 @@ pub trait Cacheable {
     fn cache(db: &Connection) -> Result<HashMap<Self::K, Self::V>, TableError>;
 }
 
+/// Defines behavior for reading a single cached value from the table instead of caching the whole table
+pub trait Queryable: Cacheable {
+    /// Read the value [`Cacheable::cache()`] would store for `key`, or `None` if it would not store one
+    fn query(db: &Connection, key: &Self::K) -> Result<Option<Self::V>, TableError>;
+}
+
 /// Defines behavior for deduplicating data in a table
 pub trait Deduplicate {
     type T;
@@ -96,6 +102,33 @@ pub fn get_db_size(path: &Path) -> Result<u64, TableError> {
     Ok(metadata(path).map_err(TableError::CannotRead)?.len())
 }
 
+/// Cap the amount of memory `SQLite` uses for a connection
+///
+/// `limit` is in bytes. This bounds the page cache, sets a soft heap limit, and
+/// moves temporary tables (like the [tapback index](crate::tables::messages::Message::index_tapbacks)) to disk.
+///
+/// # Example:
+///
+/// ```
+/// use imessage_database::{
+///     util::dirs::default_db_path,
+///     tables::table::{get_connection, set_memory_limit}
+/// };
+///
+/// let db_path = default_db_path();
+/// let connection = get_connection(&db_path).unwrap();
+/// set_memory_limit(&connection, 256 * 1024 * 1024);
+/// ```
+pub fn set_memory_limit(db: &Connection, limit: u64) -> Result<(), TableError> {
+    db.execute_batch(&format!(
+        "PRAGMA cache_size = -{};
+         PRAGMA temp_store = FILE;
+         PRAGMA soft_heap_limit = {limit};",
+        limit / 1024
+    ))
+    .map_err(TableError::CannotConfigure)
+}
+
 // Table Names
 /// Handle table name
 pub const HANDLE: &str = "handle";
@@ -113,6 +146,8 @@ pub const MESSAGE_ATTACHMENT_JOIN: &str = "message_attachment_join";
 pub const CHAT_HANDLE_JOIN: &str = "chat_handle_join";
 /// Recently deleted messages table
 pub const RECENTLY_DELETED: &str = "chat_recoverable_message_join";
+/// Temporary table that maps tapbacks to the messages they react to
+pub const TAPBACK_INDEX: &str = "tapback_index";
 
 // Column names
 /// The payload data column contains app message data
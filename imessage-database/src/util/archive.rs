@@ -0,0 +1,495 @@
+/*!
+ Contains a [`serde`] [`Deserializer`] for `NSKeyedArchiver` payloads that have been resolved with [`parse_plist`](crate::util::plist::parse_plist).
+
+ Instead of extracting each key by hand, a balloon can declare the shape of its payload as a struct:
+
+ ```
+ use plist::Value;
+ use serde::Deserialize;
+
+ use imessage_database::util::{archive::from_archive, plist::parse_plist};
+
+ #[derive(Deserialize)]
+ struct Metadata<'a> {
+     title: Option<&'a str>,
+     #[serde(rename = "richLinkMetadata")]
+     rich_link: Option<RichLink<'a>>,
+ }
+
+ #[derive(Deserialize)]
+ struct RichLink<'a> {
+     #[serde(rename = "siteName")]
+     site_name: &'a str,
+ }
+
+ let plist = Value::Dictionary(Default::default()); // Example payload
+ if let Ok(resolved) = parse_plist(&plist) {
+     let metadata: Result<Metadata, _> = from_archive(&resolved);
+ }
+ ```
+
+ ## Data mapping
+
+ - Dictionaries deserialize as structs or maps, and arrays deserialize as sequences
+ - Strings, data, booleans, integers, and reals deserialize as their Rust equivalents, borrowing from the payload where possible
+ - Missing keys and `$null` values deserialize to [`None`]
+ - Dates deserialize as their `XML` representation
+ - Strings deserialize as unit enum variants
+
+ Errors are returned as [`PlistParseError`]s that contain the full key path to the value that failed,
+ like `richLinkMetadata.specialization.name` or `items[2].URL`.
+*/
+
+use plist::Value;
+use serde::de::{
+    self, value::BorrowedStrDeserializer, DeserializeSeed, Deserializer, IntoDeserializer,
+    MapAccess, SeqAccess, Visitor,
+};
+
+use crate::error::plist::PlistParseError;
+
+/// The value `NSKeyedArchiver` stores in place of `nil`
+const NULL: &str = "$null";
+
+/// Deserialize a type from a payload resolved with [`parse_plist`](crate::util::plist::parse_plist).
+///
+/// # Example:
+///
+/// ```
+/// use plist::{Dictionary, Value};
+/// use serde::Deserialize;
+///
+/// use imessage_database::util::archive::from_archive;
+///
+/// #[derive(Deserialize)]
+/// struct Link<'a> {
+///     #[serde(rename = "URL")]
+///     url: &'a str,
+/// }
+///
+/// let mut payload = Dictionary::new();
+/// payload.insert("URL".to_string(), Value::from("https://chrissardegna.com"));
+/// let payload = Value::Dictionary(payload);
+///
+/// let link: Link = from_archive(&payload).unwrap();
+/// assert_eq!(link.url, "https://chrissardegna.com");
+/// ```
+pub fn from_archive<'de, T>(payload: &'de Value) -> Result<T, PlistParseError>
+where
+    T: de::Deserialize<'de>,
+{
+    T::deserialize(ArchiveDeserializer::new(payload)).map_err(at_root)
+}
+
+/// A [`Deserializer`] that reads from a payload resolved with [`parse_plist`](crate::util::plist::parse_plist)
+pub struct ArchiveDeserializer<'de> {
+    /// The value to deserialize
+    value: &'de Value,
+}
+
+impl<'de> ArchiveDeserializer<'de> {
+    /// Create a new deserializer that reads from a resolved value
+    pub fn new(value: &'de Value) -> Self {
+        Self { value }
+    }
+
+    /// Describe the type of the value, for error messages
+    fn unexpected(&self) -> de::Unexpected<'de> {
+        match self.value {
+            Value::String(string) => de::Unexpected::Str(string),
+            Value::Boolean(boolean) => de::Unexpected::Bool(*boolean),
+            Value::Data(data) => de::Unexpected::Bytes(data),
+            Value::Real(real) => de::Unexpected::Float(*real),
+            Value::Integer(int) => match int.as_signed() {
+                Some(int) => de::Unexpected::Signed(int),
+                None => de::Unexpected::Unsigned(int.as_unsigned().unwrap_or_default()),
+            },
+            Value::Array(_) => de::Unexpected::Seq,
+            Value::Dictionary(_) => de::Unexpected::Map,
+            _ => de::Unexpected::Other("plist value"),
+        }
+    }
+}
+
+impl<'de> Deserializer<'de> for ArchiveDeserializer<'de> {
+    type Error = PlistParseError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::String(string) => visitor.visit_borrowed_str(string),
+            Value::Boolean(boolean) => visitor.visit_bool(*boolean),
+            Value::Data(data) => visitor.visit_borrowed_bytes(data),
+            Value::Real(real) => visitor.visit_f64(*real),
+            Value::Integer(int) => match int.as_signed() {
+                Some(int) => visitor.visit_i64(int),
+                None => visitor.visit_u64(int.as_unsigned().unwrap_or_default()),
+            },
+            Value::Date(date) => visitor.visit_string(date.to_xml_format()),
+            Value::Uid(uid) => visitor.visit_u64(uid.get()),
+            Value::Array(array) => visitor.visit_seq(ArchiveSeq {
+                items: array.iter().enumerate(),
+            }),
+            Value::Dictionary(dictionary) => visitor.visit_map(ArchiveMap {
+                items: dictionary.iter(),
+                value: None,
+            }),
+            _ => Err(de::Error::invalid_type(self.unexpected(), &visitor)),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value.as_string() {
+            Some(NULL) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value.as_string() {
+            Some(variant) => visitor.visit_enum(variant.into_deserializer()),
+            None => Err(de::Error::invalid_type(self.unexpected(), &visitor)),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Iterates over the items in an array
+struct ArchiveSeq<'de, I: Iterator<Item = (usize, &'de Value)>> {
+    /// The remaining items and their indexes
+    items: I,
+}
+
+impl<'de, I: Iterator<Item = (usize, &'de Value)>> SeqAccess<'de> for ArchiveSeq<'de, I> {
+    type Error = PlistParseError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.items.next() {
+            Some((idx, item)) => seed
+                .deserialize(ArchiveDeserializer::new(item))
+                .map(Some)
+                .map_err(|why| at_path(why, &format!("[{idx}]"))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Iterates over the keys and values in a dictionary
+struct ArchiveMap<'de, I: Iterator<Item = (&'de String, &'de Value)>> {
+    /// The remaining keys and values
+    items: I,
+    /// The key and value of the most recent item, whose value has not been deserialized yet
+    value: Option<(&'de str, &'de Value)>,
+}
+
+impl<'de, I: Iterator<Item = (&'de String, &'de Value)>> MapAccess<'de> for ArchiveMap<'de, I> {
+    type Error = PlistParseError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.items.next() {
+            Some((key, value)) => {
+                self.value = Some((key, value));
+                seed.deserialize(BorrowedStrDeserializer::new(key))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+        seed.deserialize(ArchiveDeserializer::new(value))
+            .map_err(|why| at_path(why, key))
+    }
+}
+
+/// Add a key or index to the front of the path stored in an error
+fn at_path(why: PlistParseError, segment: &str) -> PlistParseError {
+    map_path(why, |path| {
+        if path.is_empty() {
+            segment.to_string()
+        } else if path.starts_with('[') {
+            // Indexes attach directly to their parent key
+            format!("{segment}{path}")
+        } else {
+            format!("{segment}.{path}")
+        }
+    })
+}
+
+/// Name the root value in an error that did not happen inside of a key
+fn at_root(why: PlistParseError) -> PlistParseError {
+    map_path(why, |path| {
+        if path.is_empty() {
+            "root".to_string()
+        } else {
+            path
+        }
+    })
+}
+
+/// Update the path stored in an error, if the error has one
+fn map_path(why: PlistParseError, update: impl Fn(String) -> String) -> PlistParseError {
+    match why {
+        PlistParseError::MissingKey(path) => PlistParseError::MissingKey(update(path)),
+        PlistParseError::InvalidType(path, expected) => {
+            PlistParseError::InvalidType(update(path), expected)
+        }
+        PlistParseError::InvalidValue(path, message) => {
+            PlistParseError::InvalidValue(update(path), message)
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::current_dir, fs::File};
+
+    use plist::{Dictionary, Value};
+    use serde::Deserialize;
+
+    use crate::{
+        error::plist::PlistParseError,
+        message_types::{app_store::AppStoreMessage, variants::BalloonProvider},
+        util::{archive::from_archive, plist::parse_plist},
+    };
+
+    fn dictionary(items: Vec<(&str, Value)>) -> Value {
+        let mut dict = Dictionary::new();
+        items.into_iter().for_each(|(key, value)| {
+            dict.insert(key.to_string(), value);
+        });
+        Value::Dictionary(dict)
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Nested<'a> {
+        #[serde(rename = "URL")]
+        url: &'a str,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Kind {
+        #[serde(rename = "iOS")]
+        Ios,
+        #[serde(rename = "macOS")]
+        Mac,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Payload<'a> {
+        name: Option<&'a str>,
+        count: i64,
+        ratio: f64,
+        enabled: bool,
+        kind: Kind,
+        #[serde(default)]
+        tags: Vec<String>,
+        links: Vec<Nested<'a>>,
+    }
+
+    fn payload() -> Value {
+        dictionary(vec![
+            ("name", Value::from("Test")),
+            ("count", Value::from(3)),
+            ("ratio", Value::from(0.5)),
+            ("enabled", Value::from(true)),
+            ("kind", Value::from("iOS")),
+            (
+                "links",
+                Value::Array(vec![
+                    dictionary(vec![("URL", Value::from("https://a.com"))]),
+                    dictionary(vec![("URL", Value::from("https://b.com"))]),
+                ]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn can_deserialize_struct() {
+        let value = payload();
+        let parsed: Payload = from_archive(&value).unwrap();
+
+        let expected = Payload {
+            name: Some("Test"),
+            count: 3,
+            ratio: 0.5,
+            enabled: true,
+            kind: Kind::Ios,
+            tags: vec![],
+            links: vec![
+                Nested {
+                    url: "https://a.com",
+                },
+                Nested {
+                    url: "https://b.com",
+                },
+            ],
+        };
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn can_deserialize_null() {
+        let mut value = payload();
+        value
+            .as_dictionary_mut()
+            .unwrap()
+            .insert("name".to_string(), Value::from("$null"));
+        let parsed: Payload = from_archive(&value).unwrap();
+
+        assert_eq!(parsed.name, None);
+    }
+
+    #[test]
+    fn can_deserialize_integer_as_float() {
+        let mut value = payload();
+        value
+            .as_dictionary_mut()
+            .unwrap()
+            .insert("ratio".to_string(), Value::from(2));
+        let parsed: Payload = from_archive(&value).unwrap();
+
+        assert_eq!(parsed.ratio, 2.0);
+    }
+
+    #[test]
+    fn cant_deserialize_missing_key() {
+        let mut value = payload();
+        value.as_dictionary_mut().unwrap().remove("count");
+
+        let parsed: Result<Payload, _> = from_archive(&value);
+        assert!(matches!(parsed, Err(PlistParseError::MissingKey(path)) if path == "count"));
+    }
+
+    #[test]
+    fn cant_deserialize_nested_missing_key() {
+        let mut value = payload();
+        value.as_dictionary_mut().unwrap().insert(
+            "links".to_string(),
+            Value::Array(vec![
+                dictionary(vec![("URL", Value::from("https://a.com"))]),
+                dictionary(vec![]),
+            ]),
+        );
+
+        let parsed: Result<Payload, _> = from_archive(&value);
+        assert!(matches!(parsed, Err(PlistParseError::MissingKey(path)) if path == "links[1].URL"));
+    }
+
+    #[test]
+    fn cant_deserialize_invalid_type() {
+        let mut value = payload();
+        value.as_dictionary_mut().unwrap().insert(
+            "links".to_string(),
+            Value::Array(vec![dictionary(vec![("URL", Value::from(1))])]),
+        );
+
+        let parsed: Result<Payload, _> = from_archive(&value);
+        assert!(matches!(
+            parsed,
+            Err(PlistParseError::InvalidType(path, _)) if path == "links[0].URL"
+        ));
+    }
+
+    #[test]
+    fn cant_deserialize_invalid_variant() {
+        let mut value = payload();
+        value
+            .as_dictionary_mut()
+            .unwrap()
+            .insert("kind".to_string(), Value::from("watchOS"));
+
+        let parsed: Result<Payload, _> = from_archive(&value);
+        assert!(matches!(parsed, Err(PlistParseError::InvalidValue(path, _)) if path == "kind"));
+    }
+
+    #[test]
+    fn cant_deserialize_root() {
+        let value = Value::from("Test");
+
+        let parsed: Result<Payload, _> = from_archive(&value);
+        assert!(matches!(parsed, Err(PlistParseError::InvalidType(path, _)) if path == "root"));
+    }
+
+    #[test]
+    fn can_deserialize_balloon() {
+        #[derive(Deserialize)]
+        struct Specialization<'a> {
+            name: &'a str,
+            subtitle: Option<&'a str>,
+            platform: Kind,
+            genre: &'a str,
+        }
+
+        #[derive(Deserialize)]
+        struct RichLink<'a> {
+            #[serde(borrow)]
+            specialization: Specialization<'a>,
+        }
+
+        #[derive(Deserialize)]
+        struct Balloon<'a> {
+            #[serde(rename = "richLinkMetadata", borrow)]
+            rich_link: RichLink<'a>,
+        }
+
+        let plist_path = current_dir()
+            .unwrap()
+            .as_path()
+            .join("test_data/app_store/AppStoreLink.plist");
+        let plist = Value::from_reader(File::open(plist_path).unwrap()).unwrap();
+        let parsed = parse_plist(&plist).unwrap();
+
+        let balloon: Balloon = from_archive(&parsed).unwrap();
+        let expected = AppStoreMessage::from_map(&parsed).unwrap();
+
+        let specialization = balloon.rich_link.specialization;
+        assert_eq!(Some(specialization.name), expected.app_name);
+        assert_eq!(specialization.subtitle, expected.description);
+        assert_eq!(specialization.platform, Kind::Ios);
+        assert_eq!(Some(specialization.genre), expected.genre);
+    }
+}
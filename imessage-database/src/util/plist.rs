@@ -6,6 +6,21 @@ use plist::{Dictionary, Value};
 
 use crate::error::plist::PlistParseError;
 
+/// The deepest chain of UID pointers [`parse_plist`] will follow before giving up
+///
+/// Real payloads are rarely more than a few dozen levels deep, but a pointer cycle is infinitely deep.
+pub const MAX_DEPTH: usize = 256;
+/// The most values [`parse_plist`] will produce from a single document
+///
+/// Pointers may be shared, so a small document can otherwise expand exponentially as they are followed.
+pub const MAX_VALUES: usize = 1 << 16;
+
+/// Tracks how much work has been done while following the pointers in a document
+struct Budget {
+    /// The number of values produced so far
+    values: usize,
+}
+
 /// Serialize a message's `payload_data` BLOB from the `NSKeyedArchiver` format to a [`Dictionary`]
 /// that follows the references in the XML document's UID pointers. First, we find the root of the
 /// document, then walk the structure, promoting values to the places where their pointers are stored.
@@ -48,7 +63,7 @@ pub fn parse_plist(plist: &Value) -> Result<Value, PlistParseError> {
     // Index of root object
     let root = extract_uid_key(extract_dictionary(body, "$top")?, "root")?;
 
-    follow_uid(objects, root, &None, None)
+    follow_uid(objects, root, &None, None, 0, &mut Budget { values: 0 })
 }
 
 /// Recursively follows pointers in an `NSKeyedArchiver` format, promoting the values
@@ -58,7 +73,17 @@ fn follow_uid<'a>(
     root: usize,
     parent: &Option<String>,
     item: Option<&'a Value>,
+    depth: usize,
+    budget: &mut Budget,
 ) -> Result<Value, PlistParseError> {
+    if depth > MAX_DEPTH {
+        return Err(PlistParseError::RecursionLimit(MAX_DEPTH));
+    }
+    budget.values += 1;
+    if budget.values > MAX_VALUES {
+        return Err(PlistParseError::SizeLimit(MAX_VALUES));
+    }
+
     let item = match item {
         Some(item) => item,
         None => objects
@@ -76,6 +101,8 @@ fn follow_uid<'a>(
                         idx.get() as usize,
                         &parent.to_owned(),
                         None,
+                        depth + 1,
+                        budget,
                     )?);
                 }
             }
@@ -89,7 +116,14 @@ fn follow_uid<'a>(
                     if let Some(p) = &parent {
                         dictionary.insert(
                             p.to_string(),
-                            follow_uid(objects, idx.get() as usize, &Some(p.to_string()), None)?,
+                            follow_uid(
+                                objects,
+                                idx.get() as usize,
+                                &Some(p.to_string()),
+                                None,
+                                depth + 1,
+                                budget,
+                            )?,
                         );
                     }
                 }
@@ -114,7 +148,14 @@ fn follow_uid<'a>(
 
                     dictionary.insert(
                         key.to_string(),
-                        follow_uid(objects, value_index, &Some(key.to_string()), None)?,
+                        follow_uid(
+                            objects,
+                            value_index,
+                            &Some(key.to_string()),
+                            None,
+                            depth + 1,
+                            budget,
+                        )?,
                     );
                 }
             }
@@ -129,21 +170,35 @@ fn follow_uid<'a>(
                     if let Some(idx) = val.as_uid() {
                         dictionary.insert(
                             key.to_owned(),
-                            follow_uid(objects, idx.get() as usize, &Some(key.to_string()), None)?,
+                            follow_uid(
+                                objects,
+                                idx.get() as usize,
+                                &Some(key.to_string()),
+                                None,
+                                depth + 1,
+                                budget,
+                            )?,
                         );
                     }
                     // If the value is not a pointer, try and follow the data itself
                     else if let Some(p) = &parent {
                         dictionary.insert(
                             p.to_owned(),
-                            follow_uid(objects, root, &Some(p.to_string()), Some(val))?,
+                            follow_uid(
+                                objects,
+                                root,
+                                &Some(p.to_string()),
+                                Some(val),
+                                depth + 1,
+                                budget,
+                            )?,
                         );
                     }
                 }
             }
             Ok(plist::Value::Dictionary(dictionary))
         }
-        Value::Uid(uid) => follow_uid(objects, uid.get() as usize, &None, None),
+        Value::Uid(uid) => follow_uid(objects, uid.get() as usize, &None, None, depth + 1, budget),
         _ => Ok(item.to_owned()),
     }
 }
@@ -171,7 +226,7 @@ pub fn extract_array_key<'a>(
 }
 
 /// Extract a Uid from a specific key in a collection
-fn extract_uid_key(body: &Dictionary, key: &str) -> Result<usize, PlistParseError> {
+pub fn extract_uid_key(body: &Dictionary, key: &str) -> Result<usize, PlistParseError> {
     Ok(body
         .get(key)
         .ok_or_else(|| PlistParseError::MissingKey(key.to_string()))?
@@ -250,3 +305,58 @@ pub fn get_float_from_nested_dict<'a>(payload: &'a Value, key: &'a str) -> Optio
         .get(key)?
         .as_real()
 }
+
+#[cfg(test)]
+mod tests {
+    use plist::{Dictionary, Uid, Value};
+
+    use crate::{
+        error::plist::PlistParseError,
+        util::plist::{parse_plist, MAX_DEPTH, MAX_VALUES},
+    };
+
+    /// Build an `NSKeyedArchiver` document with the given object table whose root is the object at index `1`
+    fn archive(objects: Vec<Value>) -> Value {
+        let mut top = Dictionary::new();
+        top.insert("root".to_string(), Value::Uid(Uid::new(1)));
+
+        let mut body = Dictionary::new();
+        body.insert("$top".to_string(), Value::Dictionary(top));
+        body.insert("$objects".to_string(), Value::Array(objects));
+        Value::Dictionary(body)
+    }
+
+    /// Build a dictionary whose keys all point to the object at `idx`
+    fn pointers(keys: &[&str], idx: u64) -> Value {
+        let mut dict = Dictionary::new();
+        keys.iter().for_each(|key| {
+            dict.insert(key.to_string(), Value::Uid(Uid::new(idx)));
+        });
+        Value::Dictionary(dict)
+    }
+
+    #[test]
+    fn cant_follow_cycle() {
+        // The root object points to itself
+        let plist = archive(vec![Value::from("$null"), pointers(&["child"], 1)]);
+
+        assert!(matches!(
+            parse_plist(&plist),
+            Err(PlistParseError::RecursionLimit(MAX_DEPTH))
+        ));
+    }
+
+    #[test]
+    fn cant_expand_shared_references() {
+        // Each object points to the next one twice, doubling the output at every level
+        let mut objects = vec![Value::from("$null")];
+        (1..=32).for_each(|idx| objects.push(pointers(&["left", "right"], idx + 1)));
+        objects.push(Value::from("leaf"));
+        let plist = archive(objects);
+
+        assert!(matches!(
+            parse_plist(&plist),
+            Err(PlistParseError::SizeLimit(MAX_VALUES))
+        ));
+    }
+}
@@ -6,16 +6,19 @@ use serde::Serialize;
 
 use crate::{
     error::query_context::QueryContextError,
+    tables::table::RECENTLY_DELETED,
     util::dates::{get_offset, TIMESTAMP_FACTOR},
 };
 
-#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
 /// Represents filter configurations for a SQL query.
 pub struct QueryContext {
     /// The start date filter. Only messages sent on or after this date will be included.
     pub start: Option<i64>,
     /// The end date filter. Only messages sent before this date will be included.
     pub end: Option<i64>,
+    /// Only include messages that can be recovered from Recently Deleted.
+    pub recently_deleted: bool,
 }
 
 impl QueryContext {
@@ -96,10 +99,12 @@ impl QueryContext {
     /// assert!(context.has_filters());
     /// ```
     pub fn has_filters(&self) -> bool {
-        [self.start, self.end].iter().any(Option::is_some)
+        [self.start, self.end].iter().any(Option::is_some) || self.recently_deleted
     }
 
     /// Generate the SQL `WHERE` clause described by this `QueryContext`
+    ///
+    /// `field` is the date column to filter on. Its table alias, if any, is also used to filter Recently Deleted messages.
     /// # Example:
     ///
     /// ```
@@ -120,6 +125,18 @@ impl QueryContext {
             }
             filters.push_str(&format!("    {field} <= {end}"));
         }
+        if self.recently_deleted {
+            if !filters.is_empty() {
+                filters.push_str(" AND ");
+            }
+            let rowid = match field.rsplit_once('.') {
+                Some((table, _)) => format!("{table}.ROWID"),
+                None => "ROWID".to_string(),
+            };
+            filters.push_str(&format!(
+                "    {rowid} IN (SELECT message_id FROM {RECENTLY_DELETED})"
+            ));
+        }
 
         if !filters.is_empty() {
             return format!(
@@ -147,9 +164,41 @@ mod use_tests {
         let context = QueryContext::default();
         assert!(context.start.is_none());
         assert!(context.end.is_none());
+        assert!(!context.recently_deleted);
         assert!(!context.has_filters());
     }
 
+    #[test]
+    fn can_create_recently_deleted() {
+        let context = QueryContext {
+            recently_deleted: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            context.generate_filter_statement("m.date"),
+            " WHERE\n                     m.ROWID IN (SELECT message_id FROM chat_recoverable_message_join)"
+        );
+        assert!(context.has_filters());
+    }
+
+    #[test]
+    fn can_create_recently_deleted_with_dates() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        let mut context = QueryContext {
+            recently_deleted: true,
+            ..Default::default()
+        };
+        context.set_start("2020-01-01").unwrap();
+
+        assert_eq!(
+            context.generate_filter_statement("date"),
+            " WHERE\n                     date >= 599558400000000000 AND     ROWID IN (SELECT message_id FROM chat_recoverable_message_join)"
+        );
+    }
+
     #[test]
     fn can_create_start() {
         // Set timezone to America/Los_Angeles for consistent Local time
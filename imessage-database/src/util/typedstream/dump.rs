@@ -0,0 +1,154 @@
+/*!
+ Logic used to describe `typedstream` data as a JSON tree, for inspecting data the parser does not understand yet.
+
+ The [`TypedStreamReader`](crate::util::typedstream::parser::TypedStreamReader) emits a flat list of components;
+ the dump nests them the same way the [`TypedStreamWriter`](crate::util::typedstream::writer::TypedStreamWriter) does,
+ so that the keys and values of a dictionary appear inside of it.
+
+ ## Format
+
+ The dump is an object with a `version`, which changes only if the format changes, and a list of `components`.
+ Each component is one of:
+
+ - `{"kind": "object", "class": {"name": "NSString", "version": 1}, "embedded": false, "data": [...], "children": [...]}`
+ - `{"kind": "data", "data": [...]}`
+ - `{"kind": "class", "class": {"name": "NSObject", "version": 0}}`
+
+ Objects are `embedded` if their data is stored as embedded data, like the value of an `NSNumber`. Each item in
+ `data` has a `type` that matches an [`OutputData`] variant and a `value`:
+
+ - `{"type": "string", "value": "Hello world"}`
+ - `{"type": "signed_integer", "value": -1}`
+ - `{"type": "unsigned_integer", "value": 1}`
+ - `{"type": "float", "value": 0.5}`
+ - `{"type": "double", "value": 0.5}`
+ - `{"type": "byte", "value": 63}`
+ - `{"type": "array", "length": 2, "value": "62ff"}`, with the bytes as hex
+ - `{"type": "class", "value": {"name": "NSObject", "version": 0}}`
+*/
+
+use serde_json::{json, Value};
+
+use crate::{
+    error::typedstream::TypedStreamError,
+    util::typedstream::{
+        models::{Archivable, Class, OutputData},
+        parser::TypedStreamReader,
+        writer::{children, is_embedded},
+    },
+};
+
+/// The version of the dump format
+pub const DUMP_VERSION: u64 = 1;
+
+/// Parse a `typedstream` and describe its contents as a JSON tree.
+///
+/// # Example:
+///
+/// ```
+/// use imessage_database::util::typedstream::dump::dump;
+///
+/// let bytes: Vec<u8> = vec![]; // Example stream
+/// let tree = dump(&bytes);
+/// ```
+pub fn dump(stream: &[u8]) -> Result<Value, TypedStreamError> {
+    let components = TypedStreamReader::from(stream).parse()?;
+    Ok(dump_components(&components))
+}
+
+/// Describe some parsed components as a JSON tree.
+///
+/// # Example:
+///
+/// ```
+/// use imessage_database::util::typedstream::{
+///     dump::dump_components,
+///     models::{Archivable, Class, OutputData},
+/// };
+///
+/// let components = vec![Archivable::Object(
+///     Class {
+///         name: "NSString".to_string(),
+///         version: 1,
+///     },
+///     vec![OutputData::String("Hello world".to_string())],
+/// )];
+///
+/// let tree = dump_components(&components);
+/// assert_eq!(tree["components"][0]["class"]["name"], "NSString");
+/// ```
+pub fn dump_components(components: &[Archivable]) -> Value {
+    let mut nodes = vec![];
+    let mut idx = 0;
+    while idx < components.len() {
+        if let Some(node) = dump_component(components, &mut idx) {
+            nodes.push(node);
+        }
+    }
+
+    json!({
+        "version": DUMP_VERSION,
+        "components": nodes,
+    })
+}
+
+/// Describe the component at `idx`, as well as any components nested inside of it
+fn dump_component(components: &[Archivable], idx: &mut usize) -> Option<Value> {
+    let component = &components[*idx];
+    *idx += 1;
+
+    match component {
+        Archivable::Object(class, data) => {
+            let mut nested = vec![];
+            for _ in 0..children(class, data, components.get(*idx)) {
+                if *idx < components.len() {
+                    nested.extend(dump_component(components, idx));
+                }
+            }
+
+            Some(json!({
+                "kind": "object",
+                "class": dump_class(class),
+                "embedded": is_embedded(class),
+                "data": data.iter().map(dump_data).collect::<Vec<_>>(),
+                "children": nested,
+            }))
+        }
+        Archivable::Data(data) => Some(json!({
+            "kind": "data",
+            "data": data.iter().map(dump_data).collect::<Vec<_>>(),
+        })),
+        Archivable::Class(class) => Some(json!({
+            "kind": "class",
+            "class": dump_class(class),
+        })),
+        // These cases are used internally by the reader and are not present in any output
+        Archivable::Placeholder | Archivable::Type(_) => None,
+    }
+}
+
+/// Describe a class
+fn dump_class(class: &Class) -> Value {
+    json!({
+        "name": class.name,
+        "version": class.version,
+    })
+}
+
+/// Describe a single item of data
+fn dump_data(data: &OutputData) -> Value {
+    match data {
+        OutputData::String(value) => json!({"type": "string", "value": value}),
+        OutputData::SignedInteger(value) => json!({"type": "signed_integer", "value": value}),
+        OutputData::UnsignedInteger(value) => json!({"type": "unsigned_integer", "value": value}),
+        OutputData::Float(value) => json!({"type": "float", "value": value}),
+        OutputData::Double(value) => json!({"type": "double", "value": value}),
+        OutputData::Byte(value) => json!({"type": "byte", "value": value}),
+        OutputData::Array(bytes) => json!({
+            "type": "array",
+            "length": bytes.len(),
+            "value": bytes.iter().map(|byte| format!("{byte:02x}")).collect::<String>(),
+        }),
+        OutputData::Class(class) => json!({"type": "class", "value": dump_class(class)}),
+    }
+}
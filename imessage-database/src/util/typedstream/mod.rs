@@ -1,5 +1,6 @@
 /*!
- Contains logic and data structures used to parse and deserialize `typedstream` data into native Rust data structures.
+ Contains logic and data structures used to parse and deserialize `typedstream` data into native Rust data structures,
+ as well as to serialize them back into `typedstream` data.
 
  ## Overview
 
@@ -15,8 +16,12 @@
  - Pure Rust implementation for efficient and safe deserialization
  - No dependencies on Apple frameworks
  - Robust error handling for malformed or incomplete `typedstream` data
+ - Round-trip serialization of parsed data
+ - A stable JSON dump of parsed data, for inspecting new kinds of data
 */
 
+pub mod dump;
 pub mod models;
 pub mod parser;
+pub mod writer;
 mod tests;
@@ -2,10 +2,10 @@
  Data structures and models used by the `typedstream` parser.
 */
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Represents a class stored in the `typedstream`
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Class {
     /// The name of the class
     pub name: String,
@@ -20,7 +20,7 @@ impl Class {
 }
 
 /// Rust structures containing data stored in the `typedstream`
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum OutputData {
     /// Text data
     String(String),
@@ -41,7 +41,7 @@ pub enum OutputData {
 }
 
 /// Types of data that can be archived into the `typedstream`
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Archivable {
     /// An instance of a class that may contain some embedded data. `typedstream` data doesn't include property
     /// names, so data is stored in order of appearance.
@@ -137,7 +137,7 @@ impl Archivable {
 
 /// Represents primitive types of data that can be stored in a `typedstream`
 // TODO: Remove clone
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Type {
     /// Encoded string data, usually embedded in an object. Denoted by:
     /// - Hex: `0x2B`, UTF-8: [`+`](https://www.compart.com/en/unicode/U+002B)
@@ -200,16 +200,16 @@ impl Type {
 
     pub(crate) fn get_array_length(types: &[u8]) -> Option<Vec<Type>> {
         if types.first() == Some(&0x5b) {
-            let len =
-                types[1..]
-                    .iter()
-                    .take_while(|a| a.is_ascii_digit())
-                    .fold(None, |acc, ch| {
-                        char::from_u32(*ch as u32)?
-                            .to_digit(10)
-                            .map(|b| acc.unwrap_or(0) * 10 + b)
-                    })?;
-            return Some(vec![Type::Array(len as usize)]);
+            let mut len: Option<usize> = None;
+            for digit in types[1..].iter().take_while(|a| a.is_ascii_digit()) {
+                // A length too large to count is not a real array
+                len = Some(
+                    len.unwrap_or(0)
+                        .checked_mul(10)?
+                        .checked_add((digit - b'0') as usize)?,
+                );
+            }
+            return Some(vec![Type::Array(len?)]);
         }
         None
     }
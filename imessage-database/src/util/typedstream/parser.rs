@@ -14,19 +14,21 @@ use crate::{
 };
 
 /// Indicates an [`i16`] in the byte stream
-const I_16: u8 = 0x81;
+pub(crate) const I_16: u8 = 0x81;
 /// Indicates an [`i32`] in the byte stream
-const I_32: u8 = 0x82;
+pub(crate) const I_32: u8 = 0x82;
 /// Indicates an [`f32`] or [`f64`] in the byte stream; the [`Type`] determines the size
-const DECIMAL: u8 = 0x83;
+pub(crate) const DECIMAL: u8 = 0x83;
 /// Indicates the start of a new object
-const START: u8 = 0x84;
+pub(crate) const START: u8 = 0x84;
 /// Indicates that there is no more data to parse, for example the end of a class inheritance chain
-const EMPTY: u8 = 0x85;
+pub(crate) const EMPTY: u8 = 0x85;
 /// Indicates the last byte of an object
-const END: u8 = 0x86;
+pub(crate) const END: u8 = 0x86;
 /// Bytes equal or greater in value than the reference tag indicate an index in the table of already-seen types
-const REFERENCE_TAG: u64 = 0x92;
+pub(crate) const REFERENCE_TAG: u64 = 0x92;
+/// The deepest chain of nested classes or embedded data the reader will follow before giving up
+pub const MAX_DEPTH: usize = 256;
 
 /// Contains logic and data used to deserialize data from a `typedstream`.
 ///
@@ -53,6 +55,8 @@ pub struct TypedStreamReader<'a> {
     seen_embedded_types: HashSet<u32>,
     /// Stores the position of the current [`Archivable::Placeholder`]
     placeholder: Option<usize>,
+    /// How many nested classes or embedded data we are currently reading
+    depth: usize,
 }
 
 impl<'a> TypedStreamReader<'a> {
@@ -74,6 +78,7 @@ impl<'a> TypedStreamReader<'a> {
             object_table: vec![],
             seen_embedded_types: HashSet::new(),
             placeholder: None,
+            depth: 0,
         }
     }
 
@@ -102,9 +107,13 @@ impl<'a> TypedStreamReader<'a> {
                 Ok(value as i64)
             }
             _ => {
-                if self.get_current_byte()? > REFERENCE_TAG as u8 && self.get_next_byte()? != END {
+                // Skip any reference tags that precede the value
+                while self.get_current_byte()? > REFERENCE_TAG as u8 && self.get_next_byte()? != END
+                {
                     self.idx += 1;
-                    return self.read_signed_int();
+                    if matches!(self.get_current_byte()?, I_16 | I_32) {
+                        return self.read_signed_int();
+                    }
                 }
                 let value = i8::from_le_bytes([self.get_current_byte()?]);
                 self.idx += 1;
@@ -189,13 +198,11 @@ impl<'a> TypedStreamReader<'a> {
 
     /// Read exactly `n` bytes from the stream
     fn read_exact_bytes(&mut self, n: usize) -> Result<&[u8], TypedStreamError> {
-        let range =
-            self.stream
-                .get(self.idx..self.idx + n)
-                .ok_or(TypedStreamError::OutOfBounds(
-                    self.idx + n,
-                    self.stream.len(),
-                ))?;
+        let end = self.idx.saturating_add(n);
+        let range = self
+            .stream
+            .get(self.idx..end)
+            .ok_or(TypedStreamError::OutOfBounds(end, self.stream.len()))?;
         self.idx += n;
         Ok(range)
     }
@@ -275,7 +282,7 @@ impl<'a> TypedStreamReader<'a> {
                     return Ok(ClassResult::Index(index as usize));
                 }
 
-                let mut class_name = String::with_capacity(length as usize);
+                let mut class_name = String::new();
                 self.read_exact_as_string(length as usize, &mut class_name)?;
 
                 let version = self.read_unsigned_int()?;
@@ -285,7 +292,10 @@ impl<'a> TypedStreamReader<'a> {
 
                 out_v.push(Archivable::Class(Class::new(class_name, version)));
 
-                if let ClassResult::ClassHierarchy(parent) = self.read_class()? {
+                self.enter()?;
+                let parent = self.read_class();
+                self.depth -= 1;
+                if let ClassResult::ClassHierarchy(parent) = parent? {
                     out_v.extend(parent);
                 }
             }
@@ -330,7 +340,8 @@ impl<'a> TypedStreamReader<'a> {
     /// Read String data
     fn read_string(&mut self) -> Result<String, TypedStreamError> {
         let length = self.read_unsigned_int()?;
-        let mut string = String::with_capacity(length as usize);
+        // The length comes from the stream, so only allocate once we know that many bytes exist
+        let mut string = String::new();
         self.read_exact_as_string(length as usize, &mut string)?;
 
         Ok(string)
@@ -340,10 +351,23 @@ impl<'a> TypedStreamReader<'a> {
     fn read_embedded_data(&mut self) -> Result<Option<Archivable>, TypedStreamError> {
         // Skip the 0x84
         self.idx += 1;
-        match self.get_type(true)? {
-            Some(types) => self.read_types(types),
-            None => Ok(None),
+        self.enter()?;
+        let result = match self.get_type(true) {
+            Ok(Some(types)) => self.read_types(types),
+            Ok(None) => Ok(None),
+            Err(why) => Err(why),
+        };
+        self.depth -= 1;
+        result
+    }
+
+    /// Descend one level into nested data, failing if the stream is nested too deeply to be real
+    fn enter(&mut self) -> Result<(), TypedStreamError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(TypedStreamError::RecursionLimit(MAX_DEPTH));
         }
+        self.depth += 1;
+        Ok(())
     }
 
     /// Gets the current type from the stream, either by reading it from the stream or reading it from
@@ -1,2 +1,4 @@
+mod test_dump;
 mod test_parser;
 mod test_type;
+mod test_writer;
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod dump_tests {
+    use std::env::current_dir;
+    use std::fs::read;
+
+    use serde_json::json;
+
+    use crate::util::typedstream::{
+        dump::{dump, dump_components, DUMP_VERSION},
+        models::{Archivable, Class, OutputData},
+    };
+
+    fn object(name: &str, version: u64, data: Vec<OutputData>) -> Archivable {
+        Archivable::Object(
+            Class {
+                name: name.to_string(),
+                version,
+            },
+            data,
+        )
+    }
+
+    #[test]
+    fn can_dump_mention() {
+        let bytes = read(current_dir().unwrap().join("test_data/typedstream/Mention")).unwrap();
+        let tree = dump(&bytes).unwrap();
+
+        assert_eq!(tree["version"], DUMP_VERSION);
+
+        let components = tree["components"].as_array().unwrap();
+        assert_eq!(
+            components[0],
+            json!({
+                "kind": "object",
+                "class": {"name": "NSMutableString", "version": 1},
+                "embedded": false,
+                "data": [{"type": "string", "value": "Test Dad "}],
+                "children": [],
+            })
+        );
+        assert_eq!(
+            components[1],
+            json!({
+                "kind": "data",
+                "data": [
+                    {"type": "signed_integer", "value": 1},
+                    {"type": "unsigned_integer", "value": 5},
+                ],
+            })
+        );
+
+        // The key and value are nested in the dictionary
+        let dictionary = &components[2];
+        assert_eq!(dictionary["class"]["name"], "NSDictionary");
+        assert_eq!(
+            dictionary["children"],
+            json!([
+                {
+                    "kind": "object",
+                    "class": {"name": "NSString", "version": 1},
+                    "embedded": false,
+                    "data": [{"type": "string", "value": "__kIMMessagePartAttributeName"}],
+                    "children": [],
+                },
+                {
+                    "kind": "object",
+                    "class": {"name": "NSNumber", "version": 0},
+                    "embedded": true,
+                    "data": [{"type": "signed_integer", "value": 0}],
+                    "children": [],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn can_dump_data() {
+        let components = vec![
+            object("NSString", 1, vec![OutputData::String("Test".to_string())]),
+            object("NSData", 0, vec![OutputData::SignedInteger(2)]),
+            Archivable::Data(vec![OutputData::Array(vec![0x62, 0xff])]),
+            object("NSNumber", 0, vec![OutputData::Double(0.5)]),
+        ];
+        let tree = dump_components(&components);
+
+        let components = tree["components"].as_array().unwrap();
+        assert_eq!(components.len(), 3);
+        assert_eq!(
+            components[1]["children"],
+            json!([{
+                "kind": "data",
+                "data": [{"type": "array", "length": 2, "value": "62ff"}],
+            }])
+        );
+        assert_eq!(
+            components[2]["data"],
+            json!([{"type": "double", "value": 0.5}])
+        );
+    }
+
+    #[test]
+    fn can_dump_empty() {
+        let tree = dump_components(&[]);
+
+        assert_eq!(tree, json!({"version": DUMP_VERSION, "components": []}));
+    }
+
+    #[test]
+    fn cant_dump_invalid() {
+        assert!(dump(&[0x04, 0x0b]).is_err());
+    }
+}
@@ -2530,4 +2530,62 @@ mod parser_tests {
 
         assert_eq!(result, expected);
     }
+
+    /// The `typedstream` header used by macOS and iOS
+    fn header() -> Vec<u8> {
+        let mut bytes = vec![0x04, 0x0b];
+        bytes.extend(b"streamtyped");
+        bytes.extend([0x81, 0xe8, 0x03]);
+        bytes
+    }
+
+    #[test]
+    fn cant_allocate_string_longer_than_stream() {
+        let mut bytes = header();
+        // A UTF-8 string type followed by a length of `i32::MAX`
+        bytes.extend([0x84, 0x01, 0x2b, 0x82, 0xff, 0xff, 0xff, 0x7f]);
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        let result = parser.parse().unwrap();
+
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn cant_overflow_stack_on_reference_tags() {
+        let mut bytes = header();
+        // A signed integer type followed by a long run of reference tags
+        bytes.extend([0x84, 0x01, 0x69]);
+        bytes.extend([0x93].repeat(1_000_000));
+
+        let mut parser = TypedStreamReader::from(&bytes);
+
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn cant_overflow_stack_on_nested_classes() {
+        let mut bytes = header();
+        // An object whose class hierarchy is deeper than any real class
+        bytes.extend([0x84, 0x01, 0x40]);
+        for _ in 0..100_000 {
+            bytes.extend([0x84, 0x01, 0x41, 0x00]);
+        }
+
+        let mut parser = TypedStreamReader::from(&bytes);
+
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn cant_overflow_stack_on_embedded_data() {
+        let mut bytes = header();
+        // Embedded data whose type is embedded data, repeated
+        bytes.extend([0x84, 0x01, 0x2a]);
+        bytes.extend([0x84, 0x92].repeat(100_000));
+
+        let mut parser = TypedStreamReader::from(&bytes);
+
+        assert!(parser.parse().is_err());
+    }
 }
@@ -20,4 +20,15 @@ mod type_tests {
 
         assert!(result.is_none())
     }
+
+    #[test]
+    fn cant_get_array_overflow() {
+        let mut items: Vec<u8> = vec![0x5b];
+        items.extend([0x39; 32]);
+        items.extend([0x63, 0x5d]);
+
+        let result = Type::get_array_length(&items);
+
+        assert!(result.is_none())
+    }
 }
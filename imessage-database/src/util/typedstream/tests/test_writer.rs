@@ -0,0 +1,275 @@
+#[cfg(test)]
+mod writer_tests {
+    use std::env::current_dir;
+    use std::fs::{read, read_dir};
+
+    use crate::util::typedstream::{
+        models::{Archivable, Class, OutputData},
+        parser::TypedStreamReader,
+        writer::TypedStreamWriter,
+    };
+
+    fn object(name: &str, version: u64, data: Vec<OutputData>) -> Archivable {
+        Archivable::Object(
+            Class {
+                name: name.to_string(),
+                version,
+            },
+            data,
+        )
+    }
+
+    fn round_trip(components: &[Archivable]) -> Vec<Archivable> {
+        let bytes = TypedStreamWriter::new().write(components).unwrap();
+        TypedStreamReader::from(&bytes).parse().unwrap()
+    }
+
+    #[test]
+    fn can_write_header() {
+        let bytes = TypedStreamWriter::new().write(&[]).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        assert!(parser.validate_header().is_ok());
+        assert!(TypedStreamReader::from(&bytes).parse().unwrap().is_empty());
+    }
+
+    #[test]
+    fn can_round_trip_fixtures() {
+        let fixtures = current_dir().unwrap().join("test_data/typedstream");
+        let mut checked = 0;
+
+        for entry in read_dir(fixtures).unwrap() {
+            let path = entry.unwrap().path();
+            let bytes = read(&path).unwrap();
+
+            // Legacy data that only the `streamtyped` parser can read
+            let Ok(expected) = TypedStreamReader::from(&bytes).parse() else {
+                continue;
+            };
+
+            let written = TypedStreamWriter::new().write(&expected).unwrap();
+            let actual = TypedStreamReader::from(&written).parse().unwrap();
+
+            assert_eq!(actual, expected, "{path:?} did not round trip");
+            checked += 1;
+        }
+
+        assert!(checked > 20);
+    }
+
+    #[test]
+    fn can_round_trip_rewritten_fixtures() {
+        let bytes = read(
+            current_dir()
+                .unwrap()
+                .join("test_data/typedstream/TextEffects"),
+        )
+        .unwrap();
+        let expected = TypedStreamReader::from(&bytes).parse().unwrap();
+
+        // Writing what was read from written data produces the same stream
+        let written = TypedStreamWriter::new().write(&expected).unwrap();
+        let rewritten = TypedStreamWriter::new()
+            .write(&TypedStreamReader::from(&written).parse().unwrap())
+            .unwrap();
+
+        assert_eq!(written, rewritten);
+    }
+
+    #[test]
+    fn can_round_trip_integers() {
+        let values: Vec<i64> = vec![
+            i32::MIN as i64,
+            i16::MIN as i64 - 1,
+            i16::MIN as i64,
+            -129,
+            -128,
+            -1,
+            0,
+            1,
+            0x7f,
+            0x80,
+            0x81,
+            0x86,
+            0x92,
+            0xff,
+            0x100,
+            i16::MAX as i64,
+            i16::MAX as i64 + 1,
+            u16::MAX as i64 + 1,
+            i32::MAX as i64,
+        ];
+
+        for value in values {
+            let unsigned = value.unsigned_abs();
+            let components = vec![
+                object("NSString", 1, vec![OutputData::String("Test".to_string())]),
+                Archivable::Data(vec![
+                    OutputData::SignedInteger(value),
+                    OutputData::UnsignedInteger(unsigned),
+                ]),
+                object("NSDictionary", 0, vec![OutputData::SignedInteger(1)]),
+                object("NSString", 1, vec![OutputData::String("Key".to_string())]),
+                object("NSNumber", 0, vec![OutputData::SignedInteger(value)]),
+            ];
+
+            assert_eq!(
+                round_trip(&components),
+                components,
+                "{value} did not round trip"
+            );
+        }
+    }
+
+    #[test]
+    fn can_round_trip_floats() {
+        let components = vec![
+            object("NSString", 1, vec![OutputData::String("Test".to_string())]),
+            object("NSNumber", 0, vec![OutputData::Double(1139.5)]),
+            object("NSNumber", 0, vec![OutputData::Float(-0.25)]),
+        ];
+
+        assert_eq!(round_trip(&components), components);
+    }
+
+    #[test]
+    fn can_round_trip_strings() {
+        for length in [0, 1, 0x80, 0x81, 0x92, 0xff, 0x100, 0x1_0000] {
+            let components = vec![object(
+                "NSMutableString",
+                1,
+                vec![OutputData::String("a".repeat(length))],
+            )];
+
+            assert_eq!(
+                round_trip(&components),
+                components,
+                "{length} did not round trip"
+            );
+        }
+    }
+
+    #[test]
+    fn can_round_trip_arrays() {
+        // Arrays whose first byte looks like a reference tag
+        for first in [0x00, 0x84, 0x92, 0x93, 0xff] {
+            let bytes = vec![first; 4];
+            let components = vec![
+                object("NSString", 1, vec![OutputData::String("Test".to_string())]),
+                object("NSData", 0, vec![OutputData::SignedInteger(4)]),
+                Archivable::Data(vec![OutputData::Array(bytes.clone())]),
+                object("NSData", 0, vec![OutputData::SignedInteger(4)]),
+                Archivable::Data(vec![OutputData::Array(bytes)]),
+            ];
+
+            assert_eq!(round_trip(&components), components);
+        }
+    }
+
+    #[test]
+    fn can_round_trip_many_references() {
+        // More objects than can be referenced with a single byte
+        let mut components = vec![object(
+            "NSString",
+            1,
+            vec![OutputData::String("Test".to_string())],
+        )];
+        for idx in 0..200 {
+            components.push(Archivable::Data(vec![
+                OutputData::SignedInteger(idx + 1),
+                OutputData::UnsignedInteger(1),
+            ]));
+            components.push(object(
+                "NSDictionary",
+                0,
+                vec![OutputData::SignedInteger(1)],
+            ));
+            components.push(object(
+                "NSString",
+                1,
+                vec![OutputData::String(format!("Key {idx}"))],
+            ));
+            components.push(object("NSNumber", 0, vec![OutputData::SignedInteger(idx)]));
+        }
+
+        assert_eq!(round_trip(&components), components);
+    }
+
+    #[test]
+    fn can_build_attributed_body() {
+        let components = vec![
+            object(
+                "NSMutableString",
+                1,
+                vec![OutputData::String(
+                    "Check out imessage-exporter".to_string(),
+                )],
+            ),
+            Archivable::Data(vec![
+                OutputData::SignedInteger(1),
+                OutputData::UnsignedInteger(10),
+            ]),
+            object("NSDictionary", 0, vec![OutputData::SignedInteger(1)]),
+            object(
+                "NSString",
+                1,
+                vec![OutputData::String(
+                    "__kIMMessagePartAttributeName".to_string(),
+                )],
+            ),
+            object("NSNumber", 0, vec![OutputData::SignedInteger(0)]),
+            Archivable::Data(vec![
+                OutputData::SignedInteger(2),
+                OutputData::UnsignedInteger(17),
+            ]),
+            object("NSDictionary", 0, vec![OutputData::SignedInteger(2)]),
+            object(
+                "NSString",
+                1,
+                vec![OutputData::String("__kIMLinkAttributeName".to_string())],
+            ),
+            object("NSURL", 0, vec![OutputData::SignedInteger(0)]),
+            object(
+                "NSString",
+                1,
+                vec![OutputData::String(
+                    "https://github.com/ReagentX/imessage-exporter".to_string(),
+                )],
+            ),
+            object(
+                "NSString",
+                1,
+                vec![OutputData::String(
+                    "__kIMMessagePartAttributeName".to_string(),
+                )],
+            ),
+            object("NSNumber", 0, vec![OutputData::SignedInteger(0)]),
+        ];
+
+        let bytes = TypedStreamWriter::new().write(&components).unwrap();
+        let parsed = TypedStreamReader::from(&bytes).parse().unwrap();
+
+        assert_eq!(parsed, components);
+        assert_eq!(
+            parsed.first().and_then(Archivable::as_nsstring),
+            Some("Check out imessage-exporter")
+        );
+    }
+
+    #[test]
+    fn cant_write_integer_too_large() {
+        let components = vec![Archivable::Data(vec![OutputData::SignedInteger(i64::MAX)])];
+
+        assert!(TypedStreamWriter::new().write(&components).is_err());
+    }
+
+    #[test]
+    fn cant_write_array_with_other_data() {
+        let components = vec![Archivable::Data(vec![
+            OutputData::Array(vec![0, 1, 2]),
+            OutputData::SignedInteger(1),
+        ])];
+
+        assert!(TypedStreamWriter::new().write(&components).is_err());
+    }
+}
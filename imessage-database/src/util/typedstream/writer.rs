@@ -0,0 +1,434 @@
+/*!
+ Logic used to serialize data into a `typedstream`, the inverse of the [`parser`](crate::util::typedstream::parser).
+
+ The [`TypedStreamReader`](crate::util::typedstream::parser::TypedStreamReader) flattens a `typedstream` into a list of
+ [`Archivable`] components, discarding the class inheritance chains and the nesting of objects. The [`TypedStreamWriter`]
+ rebuilds both from what it knows about the Foundation classes stored in an
+ [NSAttributedString](https://developer.apple.com/documentation/foundation/nsattributedstring), so that reading the
+ written stream produces the same components that were written.
+*/
+use std::collections::HashSet;
+
+use crate::{
+    error::typedstream::TypedStreamError,
+    util::typedstream::{
+        models::{Archivable, Class, OutputData, Type},
+        parser::{DECIMAL, EMPTY, END, I_16, I_32, REFERENCE_TAG, START},
+    },
+};
+
+/// The largest index that can be referenced with a single byte
+const MAX_REFERENCE: usize = (u8::MAX as u64 - REFERENCE_TAG) as usize;
+
+/// Contains logic and data used to serialize [`Archivable`] components into a `typedstream`.
+///
+/// The written stream is an `NSAttributedString` whose contents are the given components, in the same
+/// shape as the `attributedBody` column of the `message` table. Types, classes, and repeated objects are written
+/// once and referenced afterwards, the same way they are in `typedstream` data written by macOS and iOS.
+#[derive(Debug, Default)]
+pub struct TypedStreamWriter {
+    /// The `typedstream` we are building
+    stream: Vec<u8>,
+    /// Mirrors the [`TypedStreamReader`](crate::util::typedstream::parser::TypedStreamReader)'s table of seen [`Type`]s,
+    /// so that a [`Type`] can be referenced by index after the first time it is written
+    types_table: Vec<Vec<Type>>,
+    /// Mirrors the [`TypedStreamReader`](crate::util::typedstream::parser::TypedStreamReader)'s table of seen archivable data,
+    /// so that classes and objects can be referenced by index after the first time they are written
+    object_table: Vec<Archivable>,
+    /// Embedded types that have already been referenced
+    seen_embedded_types: HashSet<usize>,
+}
+
+impl TypedStreamWriter {
+    /// Construct a writer instance with an empty stream.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use imessage_database::util::typedstream::writer::TypedStreamWriter;
+    ///
+    /// let writer = TypedStreamWriter::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serialize some components into a `typedstream`.
+    ///
+    /// The components should be in the order emitted by
+    /// [`TypedStreamReader::parse()`](crate::util::typedstream::parser::TypedStreamReader::parse), i.e. the message text
+    /// followed by the ranges of the attributed string and the dictionaries of attributes that apply to them.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// use imessage_database::util::typedstream::{
+    ///     models::{Archivable, Class, OutputData},
+    ///     parser::TypedStreamReader,
+    ///     writer::TypedStreamWriter,
+    /// };
+    ///
+    /// let components = vec![Archivable::Object(
+    ///     Class {
+    ///         name: "NSString".to_string(),
+    ///         version: 1,
+    ///     },
+    ///     vec![OutputData::String("Hello world".to_string())],
+    /// )];
+    ///
+    /// let bytes = TypedStreamWriter::new().write(&components).unwrap();
+    /// let parsed = TypedStreamReader::from(&bytes).parse().unwrap();
+    /// assert_eq!(parsed, components);
+    /// ```
+    pub fn write(mut self, components: &[Archivable]) -> Result<Vec<u8>, TypedStreamError> {
+        self.write_header()?;
+
+        // Mutable text is stored in a mutable attributed string
+        let root = match components.first() {
+            Some(Archivable::Object(Class { name, .. }, _)) if name == "NSMutableString" => {
+                "NSMutableAttributedString"
+            }
+            _ => "NSAttributedString",
+        };
+        self.write_object_header(&Class::new(root.to_string(), 0))?;
+
+        let mut idx = 0;
+        while idx < components.len() {
+            self.write_component(components, &mut idx)?;
+        }
+        self.stream.push(END);
+
+        Ok(self.stream)
+    }
+
+    /// Write the header used by macOS/iOS, as validated by
+    /// [`TypedStreamReader::validate_header()`](crate::util::typedstream::parser::TypedStreamReader)
+    fn write_header(&mut self) -> Result<(), TypedStreamError> {
+        // Encoding type
+        self.write_unsigned_int(4)?;
+        // Encoding signature
+        self.write_string("streamtyped")?;
+        // System version
+        self.write_signed_int(1000)
+    }
+
+    /// Write a signed integer using the smallest width that the reader can distinguish from a tag
+    fn write_signed_int(&mut self, value: i64) -> Result<(), TypedStreamError> {
+        if (0..=i64::from(i8::MAX)).contains(&value) {
+            self.stream.push(value as u8);
+        } else if let Ok(value) = i16::try_from(value) {
+            self.stream.push(I_16);
+            self.stream.extend(value.to_le_bytes());
+        } else if let Ok(value) = i32::try_from(value) {
+            self.stream.push(I_32);
+            self.stream.extend(value.to_le_bytes());
+        } else {
+            return Err(TypedStreamError::UnsupportedData(format!(
+                "{value} is too large for a signed integer"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Write an unsigned integer using the smallest width that the reader can distinguish from a tag
+    fn write_unsigned_int(&mut self, value: u64) -> Result<(), TypedStreamError> {
+        if value < u64::from(I_16) {
+            self.stream.push(value as u8);
+        } else if let Ok(value) = u16::try_from(value) {
+            self.stream.push(I_16);
+            self.stream.extend(value.to_le_bytes());
+        } else if let Ok(value) = u32::try_from(value) {
+            self.stream.push(I_32);
+            self.stream.extend(value.to_le_bytes());
+        } else {
+            return Err(TypedStreamError::UnsupportedData(format!(
+                "{value} is too large for an unsigned integer"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Write String data, prefixed by its length
+    fn write_string(&mut self, string: &str) -> Result<(), TypedStreamError> {
+        self.write_unsigned_int(string.len() as u64)?;
+        self.stream.extend(string.as_bytes());
+        Ok(())
+    }
+
+    /// Get the byte that references an index in one of the tables, if the index fits in a single byte
+    fn reference(index: usize) -> Option<u8> {
+        (index <= MAX_REFERENCE).then(|| (index as u64 + REFERENCE_TAG) as u8)
+    }
+
+    /// Write some [`Type`]s, either literally or as a reference to [`TypedStreamWriter::types_table`].
+    ///
+    /// The reader skips a reference tag that is repeated, so `next` is the first byte written after the types;
+    /// if it matches the reference, the types are written literally instead.
+    ///
+    /// Returns the index of the types in the table and whether they were written literally.
+    fn write_type(
+        &mut self,
+        types: Vec<Type>,
+        encoded: &[u8],
+        next: Option<u8>,
+    ) -> Result<(usize, bool), TypedStreamError> {
+        if let Some(index) = self.types_table.iter().position(|seen| seen == &types) {
+            if let Some(tag) = Self::reference(index).filter(|tag| next.is_some_and(|n| n != *tag))
+            {
+                self.stream.push(tag);
+                return Ok((index, false));
+            }
+        }
+
+        self.stream.push(START);
+        self.write_unsigned_int(encoded.len() as u64)?;
+        self.stream.extend(encoded);
+        self.types_table.push(types);
+        Ok((self.types_table.len() - 1, true))
+    }
+
+    /// Write a class and its inheritance chain, either literally or as a reference to
+    /// [`TypedStreamWriter::object_table`]
+    fn write_class(&mut self, class: &Class, top: bool) -> Result<(), TypedStreamError> {
+        let seen = self
+            .object_table
+            .iter()
+            .position(|item| matches!(item, Archivable::Class(seen) if seen == class));
+
+        if let Some(index) = seen {
+            if let Some(tag) = Self::reference(index) {
+                self.stream.push(tag);
+                return Ok(());
+            }
+            // The class an object is an instance of is read as a length, so it can be any width
+            if top {
+                return self.write_unsigned_int(index as u64 + REFERENCE_TAG);
+            }
+        }
+
+        self.stream.extend([START, START]);
+        self.write_string(&class.name)?;
+        self.write_unsigned_int(class.version)?;
+        self.types_table
+            .push(vec![Type::new_string(class.name.clone())]);
+        self.object_table.push(Archivable::Class(class.clone()));
+
+        match parent(&class.name) {
+            Some(parent) => self.write_class(&parent, false),
+            None => {
+                self.stream.push(EMPTY);
+                Ok(())
+            }
+        }
+    }
+
+    /// Write the start of a new object, returning its position in [`TypedStreamWriter::object_table`]
+    fn write_object_header(&mut self, class: &Class) -> Result<usize, TypedStreamError> {
+        self.write_type(vec![Type::Object], b"@", Some(START))?;
+
+        let spot = self.object_table.len();
+        self.object_table.push(Archivable::Placeholder);
+
+        self.stream.push(START);
+        self.write_class(class, true)?;
+        Ok(spot)
+    }
+
+    /// Write the component at `idx`, as well as any components nested inside of it
+    fn write_component(
+        &mut self,
+        components: &[Archivable],
+        idx: &mut usize,
+    ) -> Result<(), TypedStreamError> {
+        let component = &components[*idx];
+        *idx += 1;
+
+        match component {
+            Archivable::Object(class, data) => {
+                let children = children(class, data, components.get(*idx));
+
+                // Objects that have already been written can be referenced instead
+                if children == 0 && !data.is_empty() {
+                    let seen = self.object_table.iter().position(|item| item == component);
+                    if let Some(tag) = seen.and_then(Self::reference) {
+                        self.write_type(vec![Type::Object], b"@", Some(tag))?;
+                        self.stream.push(tag);
+                        return Ok(());
+                    }
+                }
+
+                let spot = self.write_object_header(class)?;
+                if is_embedded(class) {
+                    self.write_embedded_data(data)?;
+                } else {
+                    self.write_data(data)?;
+                }
+                self.object_table[spot] = component.clone();
+
+                for _ in 0..children {
+                    if *idx < components.len() {
+                        self.write_component(components, idx)?;
+                    }
+                }
+                self.stream.push(END);
+            }
+            Archivable::Data(data) => self.write_data(data)?,
+            // These cases are used internally by the reader and are not present in any output
+            Archivable::Class(_) | Archivable::Placeholder | Archivable::Type(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Write some data that is stored directly in the stream
+    fn write_data(&mut self, data: &[OutputData]) -> Result<(), TypedStreamError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let Encoded {
+            types,
+            encoded,
+            values,
+        } = encode(data)?;
+
+        self.write_type(types, &encoded, values.first().copied())?;
+        self.stream.extend(values);
+        Ok(())
+    }
+
+    /// Write some data as [`Type::EmbeddedData`], like the value of an `NSNumber`
+    fn write_embedded_data(&mut self, data: &[OutputData]) -> Result<(), TypedStreamError> {
+        let Encoded {
+            types,
+            encoded,
+            values,
+        } = encode(data)?;
+
+        self.write_type(vec![Type::EmbeddedData], b"*", Some(START))?;
+        self.stream.push(START);
+
+        // Embedded types are also stored in the objects table, but references only the first time they are seen
+        let (index, literal) = self.write_type(types.clone(), &encoded, values.first().copied())?;
+        if literal || self.seen_embedded_types.insert(index) {
+            self.object_table.push(Archivable::Type(types));
+        }
+
+        self.stream.extend(values);
+        Ok(())
+    }
+}
+
+/// Some data encoded for the `typedstream`
+struct Encoded {
+    /// The [`Type`]s the reader will find
+    types: Vec<Type>,
+    /// The bytes that describe the types
+    encoded: Vec<u8>,
+    /// The bytes that contain the values
+    values: Vec<u8>,
+}
+
+/// Encode some data, along with the types that describe it
+fn encode(data: &[OutputData]) -> Result<Encoded, TypedStreamError> {
+    let mut writer = TypedStreamWriter::new();
+    let mut encoded = vec![];
+
+    for item in data {
+        match item {
+            OutputData::String(string) => {
+                encoded.push(b'+');
+                writer.write_string(string)?;
+            }
+            OutputData::SignedInteger(value) => {
+                encoded.push(b'i');
+                writer.write_signed_int(*value)?;
+            }
+            OutputData::UnsignedInteger(value) => {
+                encoded.push(b'I');
+                writer.write_unsigned_int(*value)?;
+            }
+            OutputData::Float(value) => {
+                encoded.push(b'f');
+                writer.stream.push(DECIMAL);
+                writer.stream.extend(value.to_le_bytes());
+            }
+            OutputData::Double(value) => {
+                encoded.push(b'd');
+                writer.stream.push(DECIMAL);
+                writer.stream.extend(value.to_le_bytes());
+            }
+            OutputData::Byte(byte) => {
+                // Bytes are types the reader does not know how to read, so they have no data
+                if !matches!(Type::from_byte(byte), Type::Unknown(_)) {
+                    return Err(TypedStreamError::UnsupportedData(format!(
+                        "{byte:x} is a known type"
+                    )));
+                }
+                encoded.push(*byte);
+            }
+            OutputData::Array(bytes) => {
+                // An array type describes the entire group of types
+                if data.len() > 1 {
+                    return Err(TypedStreamError::InvalidArray);
+                }
+                encoded.extend(format!("[{}c]", bytes.len()).as_bytes());
+                writer.stream.extend(bytes);
+            }
+            OutputData::Class(class) => {
+                return Err(TypedStreamError::UnsupportedData(format!(
+                    "class {} is not an object",
+                    class.name
+                )));
+            }
+        }
+    }
+
+    let types = match Type::get_array_length(&encoded) {
+        Some(array) => array,
+        None => encoded.iter().map(Type::from_byte).collect(),
+    };
+    Ok(Encoded {
+        types,
+        encoded,
+        values: writer.stream,
+    })
+}
+
+/// Get the class a Foundation class inherits from, if any
+fn parent(name: &str) -> Option<Class> {
+    let (parent, version) = match name {
+        "NSObject" => return None,
+        "NSMutableString" => ("NSString", 1),
+        "NSMutableAttributedString" => ("NSAttributedString", 0),
+        "NSMutableDictionary" => ("NSDictionary", 0),
+        "NSMutableArray" => ("NSArray", 0),
+        "NSMutableData" => ("NSData", 0),
+        "NSNumber" => ("NSValue", 0),
+        _ => ("NSObject", 0),
+    };
+    Some(Class::new(parent.to_string(), version))
+}
+
+/// Determine if an object's data is stored as [`Type::EmbeddedData`]
+pub(crate) fn is_embedded(class: &Class) -> bool {
+    matches!(class.name.as_str(), "NSNumber" | "NSValue")
+}
+
+/// Get the number of components that follow an object and are nested inside of it
+pub(crate) fn children(class: &Class, data: &[OutputData], next: Option<&Archivable>) -> usize {
+    let count = match data.first() {
+        Some(OutputData::SignedInteger(count)) => usize::try_from(*count).unwrap_or(0),
+        Some(OutputData::UnsignedInteger(count)) => usize::try_from(*count).unwrap_or(0),
+        _ => 0,
+    };
+
+    match class.name.as_str() {
+        // Each key is followed by its value
+        "NSDictionary" | "NSMutableDictionary" => count.saturating_mul(2),
+        "NSArray" | "NSMutableArray" => count,
+        // The relative string, preceded by the base URL if there is one
+        "NSURL" => count.min(1) + 1,
+        // The bytes follow the length
+        "NSData" | "NSMutableData" => usize::from(matches!(next, Some(Archivable::Data(_)))),
+        _ => 0,
+    }
+}
@@ -6,6 +6,8 @@ use std::{
 
 use crate::app::{
     converter::{convert_heic, Converter, ImageType},
+    handwriting_format::HandwritingFormat,
+    report::WarningKind,
     runtime::Config,
 };
 
@@ -18,9 +20,10 @@ use imessage_database::tables::{
 use filetime::{set_file_times, FileTime};
 
 /// Represents different ways the app can interact with attachment data
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Default)]
 pub enum AttachmentManager {
     /// Do not copy attachments
+    #[default]
     Disabled,
     /// Copy and convert attachments to more compatible formats using a [`Converter`]
     Compatible,
@@ -39,7 +42,7 @@ impl AttachmentManager {
         }
     }
 
-    /// Handle a handwriting message, optionally writing it to an SVG file
+    /// Handle a handwriting message, optionally writing it to a file in the selected [`HandwritingFormat`]
     pub fn handle_handwriting(
         &self,
         message: &Message,
@@ -58,8 +61,9 @@ impl AttachmentManager {
             // Each handwriting has a unique id, so cache then all in the same place
             to.push(&handwriting.id);
 
-            // Set the new file's extension to svg
-            to.set_extension("svg");
+            // Set the new file's extension to match the handwriting format
+            let format = config.options.handwriting_format;
+            to.set_extension(format.extension());
             if to.exists() {
                 return Some(to);
             }
@@ -68,14 +72,40 @@ impl AttachmentManager {
             if let Some(folder) = to.parent() {
                 if !folder.exists() {
                     if let Err(why) = create_dir_all(folder) {
-                        eprintln!("Unable to create {folder:?}: {why}");
+                        config.warn(
+                            message,
+                            WarningKind::Copy,
+                            format!("Unable to create {folder:?}: {why}"),
+                        );
                     }
                 }
             }
 
-            // Attempt the svg render
-            if let Err(why) = write(to.to_str()?, handwriting.render_svg()) {
-                eprintln!("Unable to write to {to:?}: {why}");
+            // Attempt the render
+            let rendered = match format {
+                HandwritingFormat::Svg => Ok(handwriting.render_svg().into_bytes()),
+                HandwritingFormat::Animated => Ok(handwriting.render_svg_animated().into_bytes()),
+                HandwritingFormat::Png => {
+                    handwriting.render_png(&config.options.handwriting_raster)
+                }
+            };
+            let rendered = match rendered {
+                Ok(rendered) => rendered,
+                Err(why) => {
+                    config.warn(
+                        message,
+                        WarningKind::Conversion,
+                        format!("Unable to render handwriting {}: {why}", handwriting.id),
+                    );
+                    return None;
+                }
+            };
+            if let Err(why) = write(to.to_str()?, rendered) {
+                config.warn(
+                    message,
+                    WarningKind::Copy,
+                    format!("Unable to write to {to:?}: {why}"),
+                );
             };
 
             // Update file metadata
@@ -96,18 +126,29 @@ impl AttachmentManager {
         config: &Config,
     ) -> Option<()> {
         // Resolve the path to the attachment
-        let attachment_path = attachment.resolved_attachment_path(
+        let Some(attachment_path) = attachment.resolved_attachment_path(
             &config.options.platform,
             &config.options.db_path,
             config.options.attachment_root.as_deref(),
-        )?;
+        ) else {
+            config.warn(
+                message,
+                WarningKind::MissingAttachment,
+                format!("Attachment {} has no path", attachment.rowid),
+            );
+            return None;
+        };
 
         if !matches!(self, AttachmentManager::Disabled) {
             let from = Path::new(&attachment_path);
 
             // Ensure the file exists at the specified location
             if !from.exists() {
-                eprintln!("Attachment not found at specified path: {from:?}");
+                config.warn(
+                    message,
+                    WarningKind::MissingAttachment,
+                    format!("Attachment not found at specified path: {from:?}"),
+                );
                 return None;
             }
 
@@ -137,11 +178,13 @@ impl AttachmentManager {
                             converter,
                             attachment.is_sticker,
                             attachment.mime_type(),
+                            message,
+                            config,
                         );
                     }
-                    None => Self::copy_raw(from, &to),
+                    None => Self::copy_raw(from, &to, message, config),
                 },
-                AttachmentManager::Efficient => Self::copy_raw(from, &to),
+                AttachmentManager::Efficient => Self::copy_raw(from, &to, message, config),
                 AttachmentManager::Disabled => unreachable!(),
             };
 
@@ -153,17 +196,25 @@ impl AttachmentManager {
     }
 
     /// Copy a file without altering it
-    fn copy_raw(from: &Path, to: &Path) {
+    fn copy_raw(from: &Path, to: &Path, message: &Message, config: &Config) {
         // Ensure the directory tree exists
         if let Some(folder) = to.parent() {
             if !folder.exists() {
                 if let Err(why) = create_dir_all(folder) {
-                    eprintln!("Unable to create {folder:?}: {why}");
+                    config.warn(
+                        message,
+                        WarningKind::Copy,
+                        format!("Unable to create {folder:?}: {why}"),
+                    );
                 }
             }
         }
         if let Err(why) = copy(from, to) {
-            eprintln!("Unable to copy {from:?} to {to:?}: {why}");
+            config.warn(
+                message,
+                WarningKind::Copy,
+                format!("Unable to copy {from:?} to {to:?}: {why}"),
+            );
         };
     }
 
@@ -179,6 +230,8 @@ impl AttachmentManager {
         converter: &Converter,
         is_sticker: bool,
         mime_type: MediaType,
+        message: &Message,
+        config: &Config,
     ) {
         // Handle sticker attachments
         if is_sticker {
@@ -193,11 +246,11 @@ impl AttachmentManager {
             match output_type {
                 Some(output_type) => {
                     to.set_extension(output_type.to_str());
-                    if convert_heic(from, to, converter, &output_type).is_none() {
-                        eprintln!("Unable to convert {from:?}");
+                    if let Err(why) = convert_heic(from, to, converter, &output_type) {
+                        config.warn(message, WarningKind::Conversion, why);
                     }
                 }
-                None => Self::copy_raw(from, to),
+                None => Self::copy_raw(from, to, message, config),
             }
         }
         // Normal attachments always get converted to jpeg
@@ -208,21 +261,15 @@ impl AttachmentManager {
             let output_type = ImageType::Jpeg;
             // Update extension for conversion
             to.set_extension(output_type.to_str());
-            if convert_heic(from, to, converter, &output_type).is_none() {
-                eprintln!("Unable to convert {from:?}");
+            if let Err(why) = convert_heic(from, to, converter, &output_type) {
+                config.warn(message, WarningKind::Conversion, why);
             }
         } else {
-            Self::copy_raw(from, to);
+            Self::copy_raw(from, to, message, config);
         }
     }
 }
 
-impl Default for AttachmentManager {
-    fn default() -> Self {
-        Self::Disabled
-    }
-}
-
 impl Display for AttachmentManager {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -247,7 +294,11 @@ fn update_file_metadata(from: &Path, to: &Path, message: &Message, config: &Conf
         let atime = FileTime::from_last_access_time(&metadata);
 
         if let Err(why) = set_file_times(to, atime, mtime) {
-            eprintln!("Unable to update {to:?} metadata: {why}");
+            config.warn(
+                message,
+                WarningKind::Metadata,
+                format!("Unable to update {to:?} metadata: {why}"),
+            );
         }
     }
 }
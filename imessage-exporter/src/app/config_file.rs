@@ -0,0 +1,364 @@
+/*!
+ Reads export options from a TOML config file, so repeated exports do not need long command lines.
+
+ Options in the file use the same names as the command line options, i.e. `db-path` or `format`. Named profiles live in
+ `[profile.<name>]` tables and replace the top-level values when they are selected with `--profile`.
+
+ Flags such as `strict` cannot be turned off from the command line once the file enables them, since the command line
+ has no `--no-<flag>` options. To disable one for some exports, set it to `false` in a profile instead.
+*/
+
+use std::{
+    collections::HashMap,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use clap::{parser::ValueSource, ArgAction, ArgMatches, Command};
+use toml::{Table, Value};
+
+use imessage_database::util::dirs::home;
+
+use crate::app::{
+    error::RuntimeError,
+    options::{
+        DEFAULT_EMBED_LIMIT, DEFAULT_MEMORY_LIMIT, OPTION_CONFIG, OPTION_EMBED_ATTACHMENTS,
+        OPTION_LOW_MEMORY, OPTION_PROFILE,
+    },
+};
+
+/// Default location of the config file, relative to the home directory
+pub const DEFAULT_CONFIG_FILE: &str = ".config/imessage-exporter/config.toml";
+/// The table in the config file that contains named profiles
+const PROFILE_TABLE: &str = "profile";
+
+/// Get the path to the default config file
+pub fn default_config_path() -> PathBuf {
+    PathBuf::from(home()).join(DEFAULT_CONFIG_FILE)
+}
+
+/// Options read from a config file, with the selected profile applied
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ConfigFile {
+    /// Options that take a value, keyed by their command line name
+    values: HashMap<String, String>,
+    /// Options that are enabled or disabled, keyed by their command line name
+    flags: HashMap<String, bool>,
+}
+
+impl ConfigFile {
+    /// Read the config file given with `--config`, or the config file at `default_path` if it exists
+    pub fn from_args(
+        args: &ArgMatches,
+        command: &Command,
+        default_path: &Path,
+    ) -> Result<Option<Self>, RuntimeError> {
+        let profile: Option<&String> = args.get_one(OPTION_PROFILE);
+
+        let path = match args.get_one::<String>(OPTION_CONFIG) {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let path = default_path.to_path_buf();
+                if !path.exists() {
+                    // A profile cannot be selected without a file to read it from
+                    if let Some(profile) = profile {
+                        return Err(RuntimeError::InvalidOptions(format!(
+                            "Option {OPTION_PROFILE} `{profile}` is enabled, but there is no config file at {}!",
+                            path.display()
+                        )));
+                    }
+                    return Ok(None);
+                }
+                path
+            }
+        };
+
+        let source = read_to_string(&path).map_err(|why| {
+            RuntimeError::InvalidOptions(format!(
+                "Unable to read config file {}: {why}",
+                path.display()
+            ))
+        })?;
+        Self::parse(&source, profile.map(String::as_str), command)
+            .map_err(|why| {
+                RuntimeError::InvalidOptions(format!(
+                    "Invalid config file {}: {why}",
+                    path.display()
+                ))
+            })
+            .map(Some)
+    }
+
+    /// Parse the contents of a config file, applying the named profile on top of the top-level options
+    fn parse(source: &str, profile: Option<&str>, command: &Command) -> Result<Self, String> {
+        let mut options: Table = source.parse().map_err(|why| format!("{why}"))?;
+
+        let profiles = match options.remove(PROFILE_TABLE) {
+            Some(Value::Table(profiles)) => profiles,
+            Some(_) => return Err(format!("`{PROFILE_TABLE}` must be a table of profiles")),
+            None => Table::new(),
+        };
+
+        if let Some(name) = profile {
+            match profiles.get(name) {
+                Some(Value::Table(overrides)) => options.extend(overrides.clone()),
+                Some(_) => return Err(format!("profile `{name}` must be a table")),
+                None => return Err(format!("profile `{name}` does not exist")),
+            }
+        }
+
+        let mut config = ConfigFile::default();
+        for (name, value) in options {
+            // Only options that are not about the config file itself can be set in it
+            let arg = command
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(&name))
+                .filter(|_| name != OPTION_CONFIG && name != OPTION_PROFILE)
+                .ok_or(format!("`{name}` is not a valid option"))?;
+
+            match (arg.get_action(), value) {
+                (ArgAction::SetTrue, Value::Boolean(enabled)) => {
+                    config.flags.insert(name, enabled);
+                }
+                (ArgAction::SetTrue, _) => {
+                    return Err(format!("`{name}` must be `true` or `false`"));
+                }
+                (_, Value::String(text)) => {
+                    config.values.insert(name, expand_home(&text));
+                }
+                (_, Value::Integer(number)) => {
+                    config.values.insert(name, number.to_string());
+                }
+                // Dates may be written without quotes, i.e. `start-date = 2020-01-01`
+                (_, Value::Datetime(date)) => {
+                    config.values.insert(name, date.to_string());
+                }
+                // Like on the command line, `low-memory` and `embed-attachments` can be enabled without a limit
+                (_, Value::Boolean(enabled)) if name == OPTION_LOW_MEMORY => {
+                    if enabled {
+                        config.values.insert(name, DEFAULT_MEMORY_LIMIT.to_string());
+                    }
+                }
+                (_, Value::Boolean(enabled)) if name == OPTION_EMBED_ATTACHMENTS => {
+                    if enabled {
+                        config.values.insert(name, DEFAULT_EMBED_LIMIT.to_string());
+                    }
+                }
+                (_, _) => return Err(format!("`{name}` must be a string")),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// Replace a leading `~` in a path with the home directory, since the shell does not expand it in config files
+fn expand_home(value: &str) -> String {
+    match value.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("{}{rest}", home()),
+        _ => value.to_string(),
+    }
+}
+
+/// Options from the command line and the config file, where options from the command line take precedence
+pub struct Settings<'a> {
+    /// Options passed on the command line
+    args: &'a ArgMatches,
+    /// Options read from the config file, if there is one
+    file: Option<ConfigFile>,
+}
+
+impl<'a> Settings<'a> {
+    pub fn new(args: &'a ArgMatches, file: Option<ConfigFile>) -> Self {
+        Self { args, file }
+    }
+
+    /// Get the value of an option
+    pub fn value(&self, name: &str) -> Option<&String> {
+        match (&self.file, self.args.value_source(name)) {
+            (Some(file), None | Some(ValueSource::DefaultValue | ValueSource::EnvVariable)) => {
+                file.values.get(name)
+            }
+            _ => self.args.get_one(name),
+        }
+    }
+
+    /// Determine whether an option is enabled
+    pub fn flag(&self, name: &str) -> bool {
+        match (&self.file, self.args.value_source(name)) {
+            (Some(file), None | Some(ValueSource::DefaultValue | ValueSource::EnvVariable)) => {
+                file.flags.get(name).copied().unwrap_or_default()
+            }
+            _ => self.args.get_flag(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, env::temp_dir, fs::write};
+
+    use imessage_database::util::dirs::home;
+
+    use crate::app::{
+        config_file::{ConfigFile, Settings},
+        options::get_command,
+    };
+
+    const CONFIG: &str = r#"
+format = "html"
+copy-method = "efficient"
+export-path = "~/exports"
+start-date = 2020-01-01
+no-lazy = true
+
+[profile.work-phone]
+db-path = "/Volumes/backup/work"
+platform = "iOS"
+format = "txt"
+no-lazy = false
+low-memory = true
+embed-attachments = true
+"#;
+
+    #[test]
+    fn can_parse_config() {
+        let actual = ConfigFile::parse(CONFIG, None, &get_command()).unwrap();
+        let expected = ConfigFile {
+            values: HashMap::from([
+                ("format".to_string(), "html".to_string()),
+                ("copy-method".to_string(), "efficient".to_string()),
+                ("export-path".to_string(), format!("{}/exports", home())),
+                ("start-date".to_string(), "2020-01-01".to_string()),
+            ]),
+            flags: HashMap::from([("no-lazy".to_string(), true)]),
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_parse_config_profile() {
+        let actual = ConfigFile::parse(CONFIG, Some("work-phone"), &get_command()).unwrap();
+        let expected = ConfigFile {
+            values: HashMap::from([
+                ("format".to_string(), "txt".to_string()),
+                ("copy-method".to_string(), "efficient".to_string()),
+                ("export-path".to_string(), format!("{}/exports", home())),
+                ("start-date".to_string(), "2020-01-01".to_string()),
+                ("db-path".to_string(), "/Volumes/backup/work".to_string()),
+                ("platform".to_string(), "iOS".to_string()),
+                ("low-memory".to_string(), "256".to_string()),
+                ("embed-attachments".to_string(), "10".to_string()),
+            ]),
+            flags: HashMap::from([("no-lazy".to_string(), false)]),
+        };
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn cant_parse_config_missing_profile() {
+        let actual = ConfigFile::parse(CONFIG, Some("home-phone"), &get_command());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_parse_config_unknown_option() {
+        let actual = ConfigFile::parse("fake-option = true", None, &get_command());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_parse_config_config_option() {
+        let actual = ConfigFile::parse("profile = \"work-phone\"", None, &get_command());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_parse_config_invalid_flag() {
+        let actual = ConfigFile::parse("no-lazy = \"yes\"", None, &get_command());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_parse_config_invalid_value() {
+        let actual = ConfigFile::parse("format = [\"txt\"]", None, &get_command());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_parse_config_invalid_toml() {
+        let actual = ConfigFile::parse("format = ", None, &get_command());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_prefer_command_line() {
+        let args = get_command().get_matches_from(["imessage-exporter", "-f", "json"]);
+        let file = ConfigFile::parse(CONFIG, None, &get_command()).unwrap();
+        let settings = Settings::new(&args, Some(file));
+
+        assert_eq!(settings.value("format"), Some(&"json".to_string()));
+        assert_eq!(
+            settings.value("copy-method"),
+            Some(&"efficient".to_string())
+        );
+        assert_eq!(settings.value("db-path"), None);
+        assert!(settings.flag("no-lazy"));
+        assert!(!settings.flag("strict"));
+    }
+
+    #[test]
+    fn can_use_command_line_without_config() {
+        let args = get_command().get_matches_from(["imessage-exporter", "-f", "json", "-l"]);
+        let settings = Settings::new(&args, None);
+
+        assert_eq!(settings.value("format"), Some(&"json".to_string()));
+        assert_eq!(settings.value("copy-method"), None);
+        assert!(settings.flag("no-lazy"));
+    }
+
+    #[test]
+    fn can_skip_missing_default_config() {
+        let args = get_command().get_matches_from(["imessage-exporter", "-f", "json"]);
+        let path = temp_dir().join("imessage-exporter-missing.toml");
+
+        assert_eq!(
+            ConfigFile::from_args(&args, &get_command(), &path).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn cant_select_profile_without_default_config() {
+        let args = get_command().get_matches_from(["imessage-exporter", "--profile", "work-phone"]);
+        let path = temp_dir().join("imessage-exporter-missing.toml");
+
+        assert!(ConfigFile::from_args(&args, &get_command(), &path).is_err());
+    }
+
+    #[test]
+    fn can_read_default_config() {
+        let args = get_command().get_matches_from(["imessage-exporter", "-f", "json"]);
+        let path = temp_dir().join("imessage-exporter-default.toml");
+        write(&path, "copy-method = \"efficient\"\n").unwrap();
+
+        let config = ConfigFile::from_args(&args, &get_command(), &path)
+            .unwrap()
+            .unwrap();
+        let settings = Settings::new(&args, Some(config));
+
+        assert_eq!(settings.value("format"), Some(&"json".to_string()));
+        assert_eq!(
+            settings.value("copy-method"),
+            Some(&"efficient".to_string())
+        );
+    }
+}
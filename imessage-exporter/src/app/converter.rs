@@ -80,84 +80,66 @@ fn exists(name: &str) -> bool {
 /// of failing, `sips` will create a file called `fake` in `/`. Subsequent writes
 /// by `sips` to the same location will not fail, but since it is a file instead
 /// of a directory, this will fail for non-`sips` copies.
+///
+/// Returns a description of the problem if the file could not be converted.
 pub fn convert_heic(
     from: &Path,
     to: &Path,
     converter: &Converter,
     output_image_type: &ImageType,
-) -> Option<()> {
+) -> Result<(), String> {
     // Get the path we want to copy from
-    let from_path = from.to_str()?;
+    let from_path = from.to_str().ok_or(format!(
+        "Unable to convert {from:?}: path is not valid UTF-8"
+    ))?;
 
     // Get the path we want to write to
-    let to_path = to.to_str()?;
+    let to_path = to.to_str().ok_or(format!(
+        "Unable to convert to {to:?}: path is not valid UTF-8"
+    ))?;
 
     // Ensure the directory tree exists
     if let Some(folder) = to.parent() {
         if !folder.exists() {
-            if let Err(why) = create_dir_all(folder) {
-                eprintln!("Unable to create {folder:?}: {why}");
-                return None;
-            }
+            create_dir_all(folder).map_err(|why| format!("Unable to create {folder:?}: {why}"))?;
         }
     }
 
-    match converter {
+    // Build the command
+    let mut command = match converter {
         Converter::Sips => {
-            // Build the command
-            match Command::new("sips")
-                .args(vec![
-                    "-s",
-                    "format",
-                    output_image_type.to_str(),
-                    from_path,
-                    "-o",
-                    to_path,
-                ])
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .stdin(Stdio::null())
-                .spawn()
-            {
-                Ok(mut sips) => match sips.wait() {
-                    Ok(_) => Some(()),
-                    Err(why) => {
-                        eprintln!("Conversion failed: {why}");
-                        None
-                    }
-                },
-                Err(why) => {
-                    eprintln!("Conversion failed: {why}");
-                    None
-                }
-            }
+            let mut command = Command::new("sips");
+            command.args(vec![
+                "-s",
+                "format",
+                output_image_type.to_str(),
+                from_path,
+                "-o",
+                to_path,
+            ]);
+            command
         }
-        Converter::Imagemagick =>
-        // Build the command
-        {
-            match Command::new("magick")
-                .args(vec![from_path, to_path])
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .stdin(Stdio::null())
-                .spawn()
-            {
-                Ok(mut convert) => match convert.wait() {
-                    Ok(_) => Some(()),
-                    Err(why) => {
-                        eprintln!("Conversion failed: {why}");
-                        None
-                    }
-                },
-                Err(why) => {
-                    eprintln!("Conversion failed: {why}");
-                    None
-                }
-            }
+        Converter::Imagemagick => {
+            let mut command = Command::new("magick");
+            command.args(vec![from_path, to_path]);
+            command
         }
     };
 
-    Some(())
+    let status = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .stdin(Stdio::null())
+        .status()
+        .map_err(|why| format!("Unable to convert {from:?}: {why}"))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Unable to convert {from:?}: {converter:?} {status}"
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,124 @@
+/*!
+ Contains logic used to dump `typedstream` data as JSON, for inspecting message attributes the parser does not understand yet.
+*/
+
+use std::{
+    fs::read,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use rusqlite::DatabaseName;
+
+use imessage_database::{
+    error::table::TableError,
+    tables::table::{get_connection, ATTRIBUTED_BODY, MESSAGE},
+    util::typedstream::dump::dump,
+};
+
+use crate::app::error::RuntimeError;
+
+/// Represents where the `typedstream` to dump is stored
+#[derive(PartialEq, Eq, Debug)]
+pub enum DumpSource {
+    /// A file containing a `typedstream`
+    File(PathBuf),
+    /// The `attributedBody` of the message with the given `ROWID`
+    Message(i32),
+}
+
+impl DumpSource {
+    /// Given user's input, return a variant if the input is an existing file or a message `ROWID`
+    pub fn from_cli(source: &str) -> Option<Self> {
+        let path = PathBuf::from(source);
+        if path.is_file() {
+            return Some(Self::File(path));
+        }
+        source.parse().ok().map(Self::Message)
+    }
+
+    /// Read the `typedstream` bytes from the source
+    fn read(&self, db_path: &Path) -> Result<Vec<u8>, RuntimeError> {
+        match self {
+            DumpSource::File(path) => {
+                read(path).map_err(|why| RuntimeError::CreateError(why, path.clone()))
+            }
+            DumpSource::Message(rowid) => {
+                let db = get_connection(db_path).map_err(RuntimeError::DatabaseError)?;
+                let mut blob = db
+                    .blob_open(
+                        DatabaseName::Main,
+                        MESSAGE,
+                        ATTRIBUTED_BODY,
+                        i64::from(*rowid),
+                        true,
+                    )
+                    .map_err(|why| RuntimeError::DatabaseError(TableError::Messages(why)))?;
+
+                let mut body = vec![];
+                blob.read_to_end(&mut body)
+                    .map_err(|why| RuntimeError::DatabaseError(TableError::CannotRead(why)))?;
+                Ok(body)
+            }
+        }
+    }
+}
+
+/// Describe the `typedstream` stored in the source as pretty-printed JSON
+pub fn dump_typedstream(source: &DumpSource, db_path: &Path) -> Result<String, RuntimeError> {
+    let bytes = source.read(db_path)?;
+    let tree = dump(&bytes).map_err(RuntimeError::TypedStreamError)?;
+    serde_json::to_string_pretty(&tree)
+        .map_err(|why| RuntimeError::DatabaseError(TableError::JsonError(why)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::current_dir, path::PathBuf};
+
+    use crate::app::dump::{dump_typedstream, DumpSource};
+
+    fn fixture(name: &str) -> PathBuf {
+        current_dir()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("imessage-database/test_data/typedstream")
+            .join(name)
+    }
+
+    #[test]
+    fn can_parse_source_file() {
+        let path = fixture("Mention");
+        assert_eq!(
+            DumpSource::from_cli(path.to_str().unwrap()),
+            Some(DumpSource::File(path))
+        );
+    }
+
+    #[test]
+    fn can_parse_source_rowid() {
+        assert_eq!(DumpSource::from_cli("42"), Some(DumpSource::Message(42)));
+    }
+
+    #[test]
+    fn cant_parse_source_invalid() {
+        assert_eq!(DumpSource::from_cli("fake/path"), None);
+    }
+
+    #[test]
+    fn can_dump_file() {
+        let source = DumpSource::File(fixture("Mention"));
+        let dumped = dump_typedstream(&source, &PathBuf::new()).unwrap();
+
+        assert!(dumped.contains("\"name\": \"NSMutableString\""));
+        assert!(dumped.contains("\"value\": \"Test Dad \""));
+    }
+
+    #[test]
+    fn cant_dump_message_missing_database() {
+        let source = DumpSource::Message(1);
+
+        assert!(dump_typedstream(&source, &PathBuf::from("fake/chat.db")).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+/*!
+ Contains data structures used to list the edited and unsent messages in each conversation.
+*/
+
+use std::{cell::RefCell, collections::BTreeMap};
+
+use serde_json::{json, Value};
+
+use crate::app::report::JsonReport;
+
+/// The name of the file the edit report is written to in the export directory
+pub const EDIT_REPORT_FILE: &str = "edit_report.json";
+
+/// Collects edited and unsent messages during an export so they can be reviewed in one place
+#[derive(Debug, Default)]
+pub struct EditReport {
+    /// Map of the file a conversation is written to, relative to the export directory, to the edited and unsent messages in it
+    conversations: RefCell<BTreeMap<String, Vec<Value>>>,
+}
+
+impl EditReport {
+    /// Record an edited or unsent message in a conversation
+    pub fn record(&self, conversation: &str, message: Value) {
+        self.conversations
+            .borrow_mut()
+            .entry(conversation.to_string())
+            .or_default()
+            .push(message);
+    }
+
+    /// The number of messages recorded
+    pub fn len(&self) -> usize {
+        self.conversations.borrow().values().map(Vec::len).sum()
+    }
+
+    /// `true` if no messages were recorded
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl JsonReport for EditReport {
+    const FILE_NAME: &'static str = EDIT_REPORT_FILE;
+
+    fn to_json(&self) -> Value {
+        json!({
+            "total": self.len(),
+            "conversations": self
+                .conversations
+                .borrow()
+                .iter()
+                .map(|(conversation, messages)| json!({
+                    "conversation": conversation,
+                    "messages": messages,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::app::{edits::EditReport, report::JsonReport};
+
+    #[test]
+    fn can_record_edits() {
+        let report = EditReport::default();
+        assert!(report.is_empty());
+
+        report.record("b", json!({"guid": "1"}));
+        report.record("a", json!({"guid": "2"}));
+        report.record("b", json!({"guid": "3"}));
+
+        assert_eq!(report.len(), 3);
+        assert!(!report.is_empty());
+    }
+
+    #[test]
+    fn can_group_edits_by_conversation() {
+        let report = EditReport::default();
+        report.record("b", json!({"guid": "1"}));
+        report.record("a", json!({"guid": "2"}));
+        report.record("b", json!({"guid": "3"}));
+
+        let json = report.to_json();
+        assert_eq!(json["total"], 3);
+        assert_eq!(json["conversations"][0]["conversation"], "a");
+        assert_eq!(json["conversations"][1]["conversation"], "b");
+        assert_eq!(json["conversations"][1]["messages"][1]["guid"], "3");
+    }
+}
@@ -0,0 +1,101 @@
+/*!
+ Contains data structures used to embed exported files in HTML exports as `data:` URIs.
+*/
+
+use std::{fmt::Display, path::Path};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Represents what happens to files that are too large to embed
+#[derive(PartialEq, Eq, Debug, Default, Clone, Copy)]
+pub enum EmbedOverflow {
+    /// Link to the file, like exports that do not embed files
+    #[default]
+    Link,
+    /// Leave the file out of the export
+    Skip,
+}
+
+impl EmbedOverflow {
+    /// Given user's input, return a variant if the input matches one
+    pub fn from_cli(overflow: &str) -> Option<Self> {
+        match overflow.to_lowercase().as_str() {
+            "link" => Some(Self::Link),
+            "skip" => Some(Self::Skip),
+            _ => None,
+        }
+    }
+}
+
+impl Display for EmbedOverflow {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EmbedOverflow::Link => write!(fmt, "link"),
+            EmbedOverflow::Skip => write!(fmt, "skip"),
+        }
+    }
+}
+
+/// Options that control which files are embedded in HTML exports
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct EmbedOptions {
+    /// The size of the largest file that is embedded, in bytes
+    pub limit: u64,
+    /// What happens to files larger than `limit`
+    pub overflow: EmbedOverflow,
+}
+
+/// Get the media type of an image from its extension
+///
+/// Converted attachments no longer match the media type in the database, i.e. `HEIC` images that were copied as `JPEG`.
+pub fn image_mime_type(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_str()?.to_lowercase().as_str() {
+        "jpeg" | "jpg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        "gif" => Some("image/gif"),
+        "svg" => Some("image/svg+xml"),
+        _ => None,
+    }
+}
+
+/// Encode a file's contents as a `data:` URI
+pub fn data_uri(mime_type: &str, bytes: &[u8]) -> String {
+    format!("data:{mime_type};base64,{}", STANDARD.encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::app::embed::{data_uri, image_mime_type, EmbedOverflow};
+
+    #[test]
+    fn can_parse_overflow_any_case() {
+        assert_eq!(EmbedOverflow::from_cli("link"), Some(EmbedOverflow::Link));
+        assert_eq!(EmbedOverflow::from_cli("Skip"), Some(EmbedOverflow::Skip));
+    }
+
+    #[test]
+    fn cant_parse_invalid_overflow() {
+        assert!(EmbedOverflow::from_cli("drop").is_none());
+    }
+
+    #[test]
+    fn can_get_image_mime_type() {
+        assert_eq!(
+            image_mime_type(Path::new("attachments/1/2.jpeg")),
+            Some("image/jpeg")
+        );
+        assert_eq!(image_mime_type(Path::new("3.PNG")), Some("image/png"));
+        assert_eq!(image_mime_type(Path::new("4.mov")), None);
+        assert_eq!(image_mime_type(Path::new("5")), None);
+    }
+
+    #[test]
+    fn can_build_data_uri() {
+        assert_eq!(
+            data_uri("text/plain", b"Hello world"),
+            "data:text/plain;base64,SGVsbG8gd29ybGQ="
+        );
+    }
+}
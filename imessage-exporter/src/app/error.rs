@@ -8,9 +8,12 @@ use std::{
     path::PathBuf,
 };
 
-use imessage_database::{error::table::TableError, util::size::format_file_size};
+use imessage_database::{
+    error::{export::ExportError, table::TableError, typedstream::TypedStreamError},
+    util::size::format_file_size,
+};
 
-use crate::app::options::OPTION_BYPASS_FREE_SPACE_CHECK;
+use crate::app::options::{OPTION_BYPASS_FREE_SPACE_CHECK, OPTION_STRICT};
 
 /// Errors that can happen during the application's runtime
 #[derive(Debug)]
@@ -19,7 +22,17 @@ pub enum RuntimeError {
     CreateError(IoError, PathBuf),
     DiskError(IoError),
     DatabaseError(TableError),
+    TypedStreamError(TypedStreamError),
+    TemplateError(String, minijinja::Error),
+    ExportError(ExportError),
     NotEnoughAvailableSpace(u64, u64),
+    Warnings(usize, PathBuf),
+}
+
+impl From<TableError> for RuntimeError {
+    fn from(error: TableError) -> Self {
+        RuntimeError::DatabaseError(error)
+    }
 }
 
 impl Display for RuntimeError {
@@ -29,6 +42,11 @@ impl Display for RuntimeError {
             RuntimeError::CreateError(why, path) => write!(fmt, "{why}: {path:?}"),
             RuntimeError::DiskError(why) => write!(fmt, "{why}"),
             RuntimeError::DatabaseError(why) => write!(fmt, "{why}"),
+            RuntimeError::TypedStreamError(why) => write!(fmt, "{why}"),
+            RuntimeError::TemplateError(name, why) => {
+                write!(fmt, "Unable to load template {name}: {why:#}")
+            }
+            RuntimeError::ExportError(why) => write!(fmt, "{why}"),
             RuntimeError::NotEnoughAvailableSpace(estimated_bytes, available_bytes) => {
                 write!(
                     fmt, 
@@ -38,6 +56,10 @@ impl Display for RuntimeError {
                     OPTION_BYPASS_FREE_SPACE_CHECK
                 )
             }
+            RuntimeError::Warnings(count, path) => write!(
+                fmt,
+                "{count} problems found during export and `--{OPTION_STRICT}` is enabled!\nSee {path:?} for details"
+            ),
         }
     }
 }
@@ -4,8 +4,25 @@
 
 use std::fmt::Display;
 
+use imessage_database::{
+    error::export::ExportError,
+    export::{
+        document::{Builder, Render},
+        format::Format,
+        registry::{FormatFactory, FormatRegistry},
+    },
+};
+
+use crate::{
+    app::{error::RuntimeError, runtime::Config},
+    Exporter, Templated, HTML, JSON, TXT,
+};
+
+/// The output formats that can be selected with `--format`, keyed by name
+pub type Formats = FormatRegistry<FormatFactory<Config>>;
+
 /// Represents the type of file to export iMessage data into
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum ExportType {
     /// HTML file export
     Html,
@@ -36,6 +53,40 @@ impl ExportType {
     }
 }
 
+/// Build the registry of output formats, starting with the built-in export types
+///
+/// Formats added to this registry can be selected with `--format` alongside the built-in ones.
+pub fn builtin_formats() -> Formats {
+    let mut formats = Formats::new();
+    formats.register("html", |config| match config.options.templates {
+        Some(_) => format(Templated::new(config)),
+        None => render(HTML::new(config)),
+    });
+    formats.register("txt", |config| match config.options.templates {
+        Some(_) => format(Templated::new(config)),
+        None => render(TXT::new(config)),
+    });
+    formats.register("json", |config| render(JSON::new(config)));
+    formats
+}
+
+/// Box an exporter that implements [`Format`] directly
+fn format<'a, F: Format + 'a>(
+    exporter: Result<F, RuntimeError>,
+) -> Result<Box<dyn Format + 'a>, ExportError> {
+    match exporter {
+        Ok(exporter) => Ok(Box::new(exporter)),
+        Err(why) => Err(ExportError::Format(why.to_string())),
+    }
+}
+
+/// Box an exporter that renders each message from the document model
+fn render<'a, R: Render + 'a>(
+    exporter: Result<R, RuntimeError>,
+) -> Result<Box<dyn Format + 'a>, ExportError> {
+    format(exporter.map(Builder::new))
+}
+
 impl Display for ExportType {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -48,7 +99,7 @@ impl Display for ExportType {
 
 #[cfg(test)]
 mod tests {
-    use crate::app::export_type::ExportType;
+    use crate::app::export_type::{builtin_formats, ExportType};
 
     #[test]
     fn can_parse_html_any_case() {
@@ -89,6 +140,14 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn can_register_builtin_types() {
+        assert_eq!(
+            builtin_formats().names().collect::<Vec<_>>(),
+            vec!["html", "json", "txt"]
+        );
+    }
+
     #[test]
     fn cant_parse_invalid() {
         assert!(ExportType::from_cli("pdf").is_none());
@@ -0,0 +1,318 @@
+/*!
+ Contains data structures used to describe where exported conversations are written.
+*/
+
+use std::{collections::HashMap, fmt::Display};
+
+use imessage_database::tables::messages::Message;
+
+use crate::app::sanitizers::encode_uri_path;
+
+/// Represents how each conversation is split into separate files
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SplitBy {
+    /// One file for each year of a conversation
+    Year,
+    /// One file for each month of a conversation
+    Month,
+}
+
+impl SplitBy {
+    /// Given user's input, return a variant if the input matches one
+    pub fn from_cli(split: &str) -> Option<Self> {
+        match split.to_lowercase().as_str() {
+            "year" => Some(Self::Year),
+            "month" => Some(Self::Month),
+            _ => None,
+        }
+    }
+
+    /// Get the period a message was sent in, i.e. `2024` or `2024-06`
+    ///
+    /// Periods sort in the order they happened.
+    pub fn period(&self, message: &Message, offset: &i64) -> Option<String> {
+        let date = message.date(offset).ok()?;
+        Some(match self {
+            SplitBy::Year => date.format("%Y").to_string(),
+            SplitBy::Month => date.format("%Y-%m").to_string(),
+        })
+    }
+}
+
+impl Display for SplitBy {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SplitBy::Year => write!(fmt, "year"),
+            SplitBy::Month => write!(fmt, "month"),
+        }
+    }
+}
+
+/// Represents a value that can be used in a file name template
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Field {
+    /// The default name for the conversation
+    Name,
+    /// The name of the conversation, or its participants if it does not have a name
+    DisplayName,
+    /// The participants in the conversation
+    Participants,
+    /// The `ROWID` of the conversation
+    ChatId,
+    /// The unique identifier of the conversation, i.e. a phone number or group ID
+    ChatIdentifier,
+    /// The service the conversation used, i.e. iMessage or SMS
+    Service,
+    /// The year the messages in the file were sent
+    Year,
+    /// The month the messages in the file were sent
+    Month,
+}
+
+impl Field {
+    /// Given a placeholder in a template, return a variant if the placeholder matches one
+    fn from_placeholder(placeholder: &str) -> Option<Self> {
+        match placeholder {
+            "name" => Some(Self::Name),
+            "display_name" => Some(Self::DisplayName),
+            "participants" => Some(Self::Participants),
+            "chat_id" => Some(Self::ChatId),
+            "chat_identifier" => Some(Self::ChatIdentifier),
+            "service" => Some(Self::Service),
+            "year" => Some(Self::Year),
+            "month" => Some(Self::Month),
+            _ => None,
+        }
+    }
+}
+
+/// Placeholders that can be used in file name templates
+pub const SUPPORTED_FILE_NAME_FIELDS: &str =
+    "name, display_name, participants, chat_id, chat_identifier, service, year, month";
+
+/// A piece of a file name template
+#[derive(PartialEq, Eq, Debug, Clone)]
+enum Segment {
+    /// Text that is copied into the file name
+    Text(String),
+    /// A placeholder that is replaced with data about the conversation
+    Field(Field),
+}
+
+/// A template for the path of an exported file relative to the export directory, i.e. `{service}/{participants}/{year}`
+///
+/// Each `/` in the template starts a new directory.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct FileNameTemplate {
+    segments: Vec<Segment>,
+}
+
+impl FileNameTemplate {
+    /// Parse a template from the user's input
+    pub fn from_cli(template: &str) -> Result<Self, String> {
+        if template.trim().is_empty() {
+            return Err(String::from("File name templates cannot be empty!"));
+        }
+        if template.starts_with('/') || template.split('/').any(|part| part == "..") {
+            return Err(format!(
+                "`{template}` must be a path inside of the export directory!"
+            ));
+        }
+
+        let mut segments = vec![];
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                segments.push(Segment::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or(format!("`{template}` has a `{{` that is never closed!"))?;
+            let placeholder = &rest[start + 1..start + end];
+            let field = Field::from_placeholder(placeholder).ok_or(format!(
+                "`{{{placeholder}}}` is not a valid file name field! Must be one of <{SUPPORTED_FILE_NAME_FIELDS}>"
+            ))?;
+            segments.push(Segment::Field(field));
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_string()));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Determine whether the template contains a placeholder
+    pub fn uses(&self, field: Field) -> bool {
+        self.segments.contains(&Segment::Field(field))
+    }
+
+    /// Build a path from the template, replacing each placeholder with its value
+    pub fn render(&self, value: impl Fn(Field) -> String) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Field(field) => value(*field),
+            })
+            .collect()
+    }
+}
+
+/// A link from one file of a split conversation to another
+#[derive(PartialEq, Eq, Debug)]
+pub struct PageLink {
+    /// The period the linked file contains
+    pub period: String,
+    /// The percent-encoded path to the linked file, relative to the file that links to it
+    pub href: String,
+}
+
+/// The files that conversations were split into, used to link each file to the ones before and after it
+#[derive(Default)]
+pub struct SplitPages {
+    /// Map of file path to the conversation and period it contains
+    pages: HashMap<String, (i32, String)>,
+}
+
+impl SplitPages {
+    /// Remember that a file contains part of a conversation
+    pub fn insert(&mut self, path: String, chat_id: i32, period: String) {
+        self.pages.insert(path, (chat_id, period));
+    }
+
+    /// Get links to the files that contain the same conversation in the periods before and after `path`
+    pub fn adjacent(&self, path: &str) -> (Option<PageLink>, Option<PageLink>) {
+        let Some((chat_id, _)) = self.pages.get(path) else {
+            return (None, None);
+        };
+
+        let mut siblings: Vec<(&String, &String)> = self
+            .pages
+            .iter()
+            .filter(|(_, (id, _))| id == chat_id)
+            .map(|(page, (_, period))| (period, page))
+            .collect();
+        siblings.sort();
+
+        let Some(idx) = siblings.iter().position(|(_, page)| *page == path) else {
+            return (None, None);
+        };
+
+        // Links are relative to the directory `path` is in
+        let root = "../".repeat(path.matches('/').count());
+        let link = |(period, page): &(&String, &String)| PageLink {
+            period: period.to_string(),
+            href: format!("{root}{}", encode_uri_path(page)),
+        };
+
+        (
+            idx.checked_sub(1).map(|prev| link(&siblings[prev])),
+            siblings.get(idx + 1).map(link),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::file_layout::{Field, FileNameTemplate, PageLink, SplitBy, SplitPages};
+
+    #[test]
+    fn can_parse_split_any_case() {
+        assert_eq!(SplitBy::from_cli("year"), Some(SplitBy::Year));
+        assert_eq!(SplitBy::from_cli("Month"), Some(SplitBy::Month));
+    }
+
+    #[test]
+    fn cant_parse_invalid_split() {
+        assert!(SplitBy::from_cli("week").is_none());
+        assert!(SplitBy::from_cli("").is_none());
+    }
+
+    #[test]
+    fn can_render_template() {
+        let template = FileNameTemplate::from_cli("{service}/{participants}/{year}").unwrap();
+        let actual = template.render(|field| match field {
+            Field::Service => "iMessage".to_string(),
+            Field::Participants => "Contact 1, Contact 2".to_string(),
+            Field::Year => "2024".to_string(),
+            _ => unreachable!(),
+        });
+
+        assert_eq!(actual, "iMessage/Contact 1, Contact 2/2024");
+        assert!(template.uses(Field::Year));
+        assert!(!template.uses(Field::Month));
+    }
+
+    #[test]
+    fn can_render_template_text() {
+        let template = FileNameTemplate::from_cli("chat {chat_id}-{display_name}").unwrap();
+        let actual = template.render(|field| match field {
+            Field::ChatId => "3".to_string(),
+            Field::DisplayName => "Weekend Plans".to_string(),
+            _ => unreachable!(),
+        });
+
+        assert_eq!(actual, "chat 3-Weekend Plans");
+    }
+
+    #[test]
+    fn cant_parse_invalid_field() {
+        assert!(FileNameTemplate::from_cli("{week}").is_err());
+    }
+
+    #[test]
+    fn cant_parse_unclosed_field() {
+        assert!(FileNameTemplate::from_cli("{year").is_err());
+    }
+
+    #[test]
+    fn cant_parse_path_outside_export() {
+        assert!(FileNameTemplate::from_cli("/{name}").is_err());
+        assert!(FileNameTemplate::from_cli("../{name}").is_err());
+        assert!(FileNameTemplate::from_cli("").is_err());
+    }
+
+    #[test]
+    fn can_link_adjacent_pages() {
+        let mut pages = SplitPages::default();
+        pages.insert("Chat/2022.html".to_string(), 1, "2022".to_string());
+        pages.insert("Chat/2024.html".to_string(), 1, "2024".to_string());
+        pages.insert("Chat/2023.html".to_string(), 1, "2023".to_string());
+        pages.insert("Other/2023.html".to_string(), 2, "2023".to_string());
+
+        assert_eq!(
+            pages.adjacent("Chat/2023.html"),
+            (
+                Some(PageLink {
+                    period: "2022".to_string(),
+                    href: "../Chat/2022.html".to_string()
+                }),
+                Some(PageLink {
+                    period: "2024".to_string(),
+                    href: "../Chat/2024.html".to_string()
+                })
+            )
+        );
+        assert_eq!(pages.adjacent("Other/2023.html"), (None, None));
+        assert_eq!(pages.adjacent("Missing.html"), (None, None));
+    }
+
+    #[test]
+    fn can_link_adjacent_pages_encoded() {
+        let mut pages = SplitPages::default();
+        pages.insert("Q&A #1/2022.html".to_string(), 1, "2022".to_string());
+        pages.insert("Q&A #1/2023.html".to_string(), 1, "2023".to_string());
+
+        assert_eq!(
+            pages.adjacent("Q&A #1/2023.html"),
+            (
+                Some(PageLink {
+                    period: "2022".to_string(),
+                    href: "../Q%26A%20%231/2022.html".to_string()
+                }),
+                None
+            )
+        );
+    }
+}
@@ -0,0 +1,100 @@
+/*!
+ Contains data structures used to describe how handwritten messages are exported.
+*/
+
+use std::fmt::Display;
+
+/// Represents the file format used to render handwritten messages
+#[derive(PartialEq, Eq, Debug, Default, Clone, Copy)]
+pub enum HandwritingFormat {
+    /// Static `svg` graphic
+    #[default]
+    Svg,
+    /// `svg` graphic that draws each stroke in the order it was written
+    Animated,
+    /// `png` image, for viewers that do not support `svg`
+    Png,
+}
+
+impl HandwritingFormat {
+    /// Given user's input, return a variant if the input matches one
+    pub fn from_cli(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "svg" => Some(Self::Svg),
+            "animated" => Some(Self::Animated),
+            "png" => Some(Self::Png),
+            _ => None,
+        }
+    }
+
+    /// Get the file name extension for the given handwriting format
+    pub fn extension(&self) -> &str {
+        match self {
+            HandwritingFormat::Svg | HandwritingFormat::Animated => "svg",
+            HandwritingFormat::Png => "png",
+        }
+    }
+}
+
+impl Display for HandwritingFormat {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandwritingFormat::Svg => write!(fmt, "svg"),
+            HandwritingFormat::Animated => write!(fmt, "animated"),
+            HandwritingFormat::Png => write!(fmt, "png"),
+        }
+    }
+}
+
+/// Parse a stroke color from a hex string like `#1E90FF` or `1E90FF`
+pub fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let mut rgb = [0; 3];
+    for (idx, channel) in rgb.iter_mut().enumerate() {
+        *channel = u8::from_str_radix(&hex[idx * 2..idx * 2 + 2], 16).ok()?;
+    }
+    Some(rgb)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::handwriting_format::{parse_color, HandwritingFormat};
+
+    #[test]
+    fn can_parse_formats_any_case() {
+        assert_eq!(
+            HandwritingFormat::from_cli("svg"),
+            Some(HandwritingFormat::Svg)
+        );
+        assert_eq!(
+            HandwritingFormat::from_cli("Animated"),
+            Some(HandwritingFormat::Animated)
+        );
+        assert_eq!(
+            HandwritingFormat::from_cli("PNG"),
+            Some(HandwritingFormat::Png)
+        );
+    }
+
+    #[test]
+    fn cant_parse_invalid_format() {
+        assert!(HandwritingFormat::from_cli("gif").is_none());
+        assert!(HandwritingFormat::from_cli("").is_none());
+    }
+
+    #[test]
+    fn can_parse_color() {
+        assert_eq!(parse_color("#1E90FF"), Some([0x1e, 0x90, 0xff]));
+        assert_eq!(parse_color("000000"), Some([0, 0, 0]));
+    }
+
+    #[test]
+    fn cant_parse_invalid_color() {
+        assert!(parse_color("#FFF").is_none());
+        assert!(parse_color("GGGGGG").is_none());
+        assert!(parse_color("#ÿÿÿ").is_none());
+    }
+}
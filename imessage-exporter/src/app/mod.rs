@@ -1,8 +1,15 @@
 pub mod attachment_manager;
+pub mod config_file;
 pub mod converter;
+pub mod dump;
+pub mod edits;
+pub mod embed;
 pub mod error;
 pub mod export_type;
+pub mod file_layout;
+pub mod handwriting_format;
 pub mod options;
 pub mod progress;
+pub mod report;
 pub mod runtime;
 pub mod sanitizers;
@@ -1,8 +1,9 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::{crate_version, Arg, ArgAction, ArgMatches, Command};
 
 use imessage_database::{
+    message_types::handwriting::{RasterOptions, MAX_RASTER_SIZE},
     tables::{attachment::DEFAULT_ATTACHMENT_ROOT, table::DEFAULT_PATH_IOS},
     util::{
         dirs::{default_db_path, home},
@@ -12,11 +13,24 @@ use imessage_database::{
 };
 
 use crate::app::{
-    attachment_manager::AttachmentManager, error::RuntimeError, export_type::ExportType,
+    attachment_manager::AttachmentManager,
+    config_file::{ConfigFile, Settings, DEFAULT_CONFIG_FILE},
+    dump::DumpSource,
+    edits::EDIT_REPORT_FILE,
+    embed::{EmbedOptions, EmbedOverflow},
+    error::RuntimeError,
+    export_type::{ExportType, Formats},
+    file_layout::{Field, FileNameTemplate, SplitBy, SUPPORTED_FILE_NAME_FIELDS},
+    handwriting_format::{parse_color, HandwritingFormat},
+    report::REPORT_FILE,
 };
 
 /// Default export directory name
 pub const DEFAULT_OUTPUT_DIR: &str = "imessage_export";
+/// Default memory ceiling in MiB when low-memory mode is enabled
+pub const DEFAULT_MEMORY_LIMIT: &str = "256";
+/// Default size limit in MiB for attachments embedded in HTML exports
+pub const DEFAULT_EMBED_LIMIT: &str = "10";
 
 // CLI Arg Names
 pub const OPTION_DB_PATH: &str = "db-path";
@@ -32,11 +46,29 @@ pub const OPTION_CUSTOM_NAME: &str = "custom-name";
 pub const OPTION_PLATFORM: &str = "platform";
 pub const OPTION_BYPASS_FREE_SPACE_CHECK: &str = "ignore-disk-warning";
 pub const OPTION_USE_CALLER_ID: &str = "use-caller-id";
+pub const OPTION_LOW_MEMORY: &str = "low-memory";
+pub const OPTION_STRICT: &str = "strict";
+pub const OPTION_HANDWRITING_FORMAT: &str = "handwriting-format";
+pub const OPTION_HANDWRITING_SIZE: &str = "handwriting-size";
+pub const OPTION_HANDWRITING_COLOR: &str = "handwriting-color";
+pub const OPTION_EDIT_REPORT: &str = "edit-report";
+pub const OPTION_RECENTLY_DELETED: &str = "recently-deleted";
+pub const OPTION_DUMP_TYPEDSTREAM: &str = "dump-typedstream";
+pub const OPTION_TEMPLATES: &str = "templates";
+pub const OPTION_FILE_NAME: &str = "file-name";
+pub const OPTION_SPLIT_BY: &str = "split-by";
+pub const OPTION_EMBED_ATTACHMENTS: &str = "embed-attachments";
+pub const OPTION_EMBED_OVERFLOW: &str = "embed-overflow";
+pub const OPTION_CONFIG: &str = "config";
+pub const OPTION_PROFILE: &str = "profile";
 
 // Other CLI Text
 pub const SUPPORTED_FILE_TYPES: &str = "txt, html, json";
 pub const SUPPORTED_PLATFORMS: &str = "macOS, iOS";
 pub const SUPPORTED_ATTACHMENT_MANAGER_MODES: &str = "compatible, efficient, disabled";
+pub const SUPPORTED_HANDWRITING_FORMATS: &str = "svg, animated, png";
+pub const SUPPORTED_SPLITS: &str = "year, month";
+pub const SUPPORTED_EMBED_OVERFLOWS: &str = "link, skip";
 pub const ABOUT: &str = concat!(
     "The `imessage-exporter` binary exports iMessage data to\n",
     "`txt` or `html` formats. It can also run diagnostics\n",
@@ -55,6 +87,8 @@ pub struct Options {
     pub diagnostic: bool,
     /// The type of file we are exporting data to
     pub export_type: Option<ExportType>,
+    /// The name of the registered format we are exporting data with
+    pub format: Option<String>,
     /// Where the app will save exported data
     pub export_path: PathBuf,
     /// Query context describing SQL query filters
@@ -69,33 +103,81 @@ pub struct Options {
     pub platform: Platform,
     /// If true, disable the free disk space check
     pub ignore_disk_space: bool,
+    /// If set, index tapbacks on disk and cap `SQLite`'s memory use at this many MiB
+    pub memory_limit: Option<u64>,
+    /// If true, fail the export if any problems are recorded in the export report
+    pub strict: bool,
+    /// The format handwritten messages are rendered in
+    pub handwriting_format: HandwritingFormat,
+    /// The size and colors used when handwritten messages are rendered as `png`
+    pub handwriting_raster: RasterOptions,
+    /// If true, list every edited and unsent message in a report alongside the export
+    pub edit_report: bool,
+    /// If set, print the `typedstream` stored in the source as JSON and exit
+    pub dump_typedstream: Option<DumpSource>,
+    /// Directory of templates that replace the built-in templates used to render `html` or `txt`
+    pub templates: Option<PathBuf>,
+    /// Template for the path of each exported file, relative to the export directory
+    pub file_name: Option<FileNameTemplate>,
+    /// If set, write each conversation to a separate file for each year or month
+    pub split_by: Option<SplitBy>,
+    /// If set, embed attachments in HTML exports as `data:` URIs instead of linking to them
+    pub embed: Option<EmbedOptions>,
 }
 
 impl Options {
-    pub fn from_args(args: &ArgMatches) -> Result<Self, RuntimeError> {
-        let user_path: Option<&String> = args.get_one(OPTION_DB_PATH);
-        let attachment_root: Option<&String> = args.get_one(OPTION_ATTACHMENT_ROOT);
-        let attachment_manager_type: Option<&String> = args.get_one(OPTION_ATTACHMENT_MANAGER);
-        let diagnostic = args.get_flag(OPTION_DIAGNOSTIC);
-        let export_file_type: Option<&String> = args.get_one(OPTION_EXPORT_TYPE);
-        let user_export_path: Option<&String> = args.get_one(OPTION_EXPORT_PATH);
-        let start_date: Option<&String> = args.get_one(OPTION_START_DATE);
-        let end_date: Option<&String> = args.get_one(OPTION_END_DATE);
-        let no_lazy = args.get_flag(OPTION_DISABLE_LAZY_LOADING);
-        let custom_name: Option<&String> = args.get_one(OPTION_CUSTOM_NAME);
-        let use_caller_id = args.get_flag(OPTION_USE_CALLER_ID);
-        let platform_type: Option<&String> = args.get_one(OPTION_PLATFORM);
-        let ignore_disk_space = args.get_flag(OPTION_BYPASS_FREE_SPACE_CHECK);
-
-        // Build the export type
-        let export_type: Option<ExportType> = match export_file_type {
-            Some(export_type_str) => {
-                Some(ExportType::from_cli(export_type_str).ok_or(RuntimeError::InvalidOptions(format!(
-                    "{export_type_str} is not a valid export type! Must be one of <{SUPPORTED_FILE_TYPES}>"
-                )))?)
+    /// Build the options from the command line, filling in anything not passed there from the config file
+    ///
+    /// If `--config` is not passed, options are read from `default_config` if it exists. `--format` must name one of
+    /// the `formats`.
+    pub fn from_args(
+        args: &ArgMatches,
+        default_config: &Path,
+        formats: &Formats,
+    ) -> Result<Self, RuntimeError> {
+        // Options from the config file fill in anything that was not passed on the command line
+        let command = get_command();
+        let settings = Settings::new(args, ConfigFile::from_args(args, &command, default_config)?);
+
+        let user_path: Option<&String> = settings.value(OPTION_DB_PATH);
+        let attachment_root: Option<&String> = settings.value(OPTION_ATTACHMENT_ROOT);
+        let attachment_manager_type: Option<&String> = settings.value(OPTION_ATTACHMENT_MANAGER);
+        let diagnostic = settings.flag(OPTION_DIAGNOSTIC);
+        let export_file_type: Option<&String> = settings.value(OPTION_EXPORT_TYPE);
+        let user_export_path: Option<&String> = settings.value(OPTION_EXPORT_PATH);
+        let start_date: Option<&String> = settings.value(OPTION_START_DATE);
+        let end_date: Option<&String> = settings.value(OPTION_END_DATE);
+        let no_lazy = settings.flag(OPTION_DISABLE_LAZY_LOADING);
+        let custom_name: Option<&String> = settings.value(OPTION_CUSTOM_NAME);
+        let use_caller_id = settings.flag(OPTION_USE_CALLER_ID);
+        let platform_type: Option<&String> = settings.value(OPTION_PLATFORM);
+        let ignore_disk_space = settings.flag(OPTION_BYPASS_FREE_SPACE_CHECK);
+        let low_memory: Option<&String> = settings.value(OPTION_LOW_MEMORY);
+        let strict = settings.flag(OPTION_STRICT);
+        let handwriting_format_type: Option<&String> = settings.value(OPTION_HANDWRITING_FORMAT);
+        let handwriting_size: Option<&String> = settings.value(OPTION_HANDWRITING_SIZE);
+        let handwriting_color: Option<&String> = settings.value(OPTION_HANDWRITING_COLOR);
+        let edit_report = settings.flag(OPTION_EDIT_REPORT);
+        let recently_deleted = settings.flag(OPTION_RECENTLY_DELETED);
+        let dump_source: Option<&String> = settings.value(OPTION_DUMP_TYPEDSTREAM);
+        let template_dir: Option<&String> = settings.value(OPTION_TEMPLATES);
+        let file_name_template: Option<&String> = settings.value(OPTION_FILE_NAME);
+        let split_type: Option<&String> = settings.value(OPTION_SPLIT_BY);
+        let embed_limit: Option<&String> = settings.value(OPTION_EMBED_ATTACHMENTS);
+        let embed_overflow: Option<&String> = settings.value(OPTION_EMBED_OVERFLOW);
+
+        // Build the export type from a format registered with the exporter
+        let format = match export_file_type {
+            Some(name) if formats.contains(name) => Some(name.to_lowercase()),
+            Some(name) => {
+                return Err(RuntimeError::InvalidOptions(format!(
+                    "{name} is not a valid export type! Must be one of <{}>",
+                    formats.names().collect::<Vec<_>>().join(", ")
+                )));
             }
             None => None,
         };
+        let export_type = format.as_deref().and_then(ExportType::from_cli);
 
         // Ensure an export type is specified if other export options are selected
         if attachment_manager_type.is_some() && export_file_type.is_none() {
@@ -123,6 +205,56 @@ impl Options {
                 "Option {OPTION_USE_CALLER_ID} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
             )));
         }
+        if low_memory.is_some() && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_LOW_MEMORY} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if strict && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_STRICT} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if edit_report && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_EDIT_REPORT} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if recently_deleted && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_RECENTLY_DELETED} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if handwriting_format_type.is_some() && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_HANDWRITING_FORMAT} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if template_dir.is_some() && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_TEMPLATES} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if file_name_template.is_some() && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_FILE_NAME} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if split_type.is_some() && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_SPLIT_BY} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if embed_limit.is_some() && export_file_type.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_EMBED_ATTACHMENTS} is enabled, which requires `--{OPTION_EXPORT_TYPE}`"
+            )));
+        }
+        if embed_overflow.is_some() && embed_limit.is_none() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_EMBED_OVERFLOW} is enabled, which requires `--{OPTION_EMBED_ATTACHMENTS}`"
+            )));
+        }
 
         // Warn the user if they are exporting to a file type for which lazy loading has no effect
         if no_lazy && export_file_type != Some(&"html".to_string()) {
@@ -163,6 +295,24 @@ impl Options {
             )));
         }
 
+        // Ensure that if a typedstream is dumped, nothing is exported
+        if dump_source.is_some() && export_file_type.is_some() {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_DUMP_TYPEDSTREAM} is enabled; {OPTION_EXPORT_TYPE} is disallowed"
+            )));
+        }
+        if dump_source.is_some() && diagnostic {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Option {OPTION_DUMP_TYPEDSTREAM} is enabled; {OPTION_DIAGNOSTIC} is disallowed"
+            )));
+        }
+        let dump_typedstream = match dump_source {
+            Some(source) => Some(DumpSource::from_cli(source).ok_or(
+                RuntimeError::InvalidOptions(format!("{source} is not a file or a message ROWID!")),
+            )?),
+            None => None,
+        };
+
         // Ensure that there are no custom name conflicts
         if custom_name.is_some() && use_caller_id {
             return Err(RuntimeError::InvalidOptions(format!(
@@ -171,7 +321,10 @@ impl Options {
         }
 
         // Build query context
-        let mut query_context = QueryContext::default();
+        let mut query_context = QueryContext {
+            recently_deleted,
+            ..Default::default()
+        };
         if let Some(start) = start_date {
             if let Err(why) = query_context.set_start(start) {
                 return Err(RuntimeError::InvalidOptions(format!("{why}")));
@@ -208,6 +361,25 @@ impl Options {
             }
         };
 
+        // Validate that the template directory exists and that the export type is rendered with templates
+        let templates = match template_dir {
+            Some(path) => {
+                if !matches!(export_type, Some(ExportType::Html | ExportType::Txt)) {
+                    return Err(RuntimeError::InvalidOptions(format!(
+                        "Option {OPTION_TEMPLATES} is enabled, but templates are only supported for `html` and `txt`!"
+                    )));
+                }
+                let templates = PathBuf::from(path);
+                if !templates.is_dir() {
+                    return Err(RuntimeError::InvalidOptions(format!(
+                        "Supplied {OPTION_TEMPLATES} `{path}` is not a directory!"
+                    )));
+                }
+                Some(templates)
+            }
+            None => None,
+        };
+
         // Warn the user that custom attachment roots have no effect on iOS backups
         if attachment_root.is_some() && platform == Platform::iOS {
             eprintln!(
@@ -225,6 +397,132 @@ impl Options {
             None => AttachmentManager::default(),
         };
 
+        // Determine how handwritten messages are rendered
+        let handwriting_format = match handwriting_format_type {
+            Some(format) => {
+                HandwritingFormat::from_cli(format).ok_or(RuntimeError::InvalidOptions(format!(
+                    "{format} is not a valid handwriting format! Must be one of <{SUPPORTED_HANDWRITING_FORMATS}>"
+                )))?
+            }
+            None => HandwritingFormat::default(),
+        };
+
+        // Size and color only apply to rasterized handwriting
+        if (handwriting_size.is_some() || handwriting_color.is_some())
+            && handwriting_format != HandwritingFormat::Png
+        {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "Options {OPTION_HANDWRITING_SIZE} and {OPTION_HANDWRITING_COLOR} require `--{OPTION_HANDWRITING_FORMAT} {}`", HandwritingFormat::Png
+            )));
+        }
+        let mut handwriting_raster = RasterOptions::default();
+        if let Some(size) = handwriting_size {
+            handwriting_raster.size = size
+                .parse::<u32>()
+                .ok()
+                .filter(|size| (1..=MAX_RASTER_SIZE).contains(size))
+                .ok_or(RuntimeError::InvalidOptions(format!(
+                    "{size} is not a valid handwriting size! Must be a whole number of pixels from 1 to {MAX_RASTER_SIZE}"
+                )))?;
+        }
+        if let Some(color) = handwriting_color {
+            handwriting_raster.color =
+                parse_color(color).ok_or(RuntimeError::InvalidOptions(format!(
+                    "{color} is not a valid handwriting color! Must be a hex color like #000000"
+                )))?;
+        }
+
+        // Determine how conversations are split into files
+        let split_by = match split_type {
+            Some(split) => Some(SplitBy::from_cli(split).ok_or(RuntimeError::InvalidOptions(
+                format!("{split} is not a valid split! Must be one of <{SUPPORTED_SPLITS}>"),
+            ))?),
+            None => None,
+        };
+
+        // Parse the file name template, ensuring the dates it uses are the same for every message in a file
+        let file_name = match file_name_template {
+            Some(template) => {
+                let template =
+                    FileNameTemplate::from_cli(template).map_err(RuntimeError::InvalidOptions)?;
+                if template.uses(Field::Year) && split_by.is_none() {
+                    return Err(RuntimeError::InvalidOptions(format!(
+                        "Option {OPTION_FILE_NAME} uses {{year}}, which requires `--{OPTION_SPLIT_BY}`"
+                    )));
+                }
+                if template.uses(Field::Month) && split_by != Some(SplitBy::Month) {
+                    return Err(RuntimeError::InvalidOptions(format!(
+                        "Option {OPTION_FILE_NAME} uses {{month}}, which requires `--{OPTION_SPLIT_BY} {}`", SplitBy::Month
+                    )));
+                }
+                if template.uses(Field::Year) != template.uses(Field::Month)
+                    && split_by == Some(SplitBy::Month)
+                {
+                    return Err(RuntimeError::InvalidOptions(format!(
+                        "Option {OPTION_FILE_NAME} must use both {{year}} and {{month}} when `--{OPTION_SPLIT_BY} {}` is enabled", SplitBy::Month
+                    )));
+                }
+                Some(template)
+            }
+            None => None,
+        };
+
+        // Parse the memory ceiling for low-memory mode
+        let memory_limit = match low_memory {
+            Some(limit) => {
+                let limit = limit
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|limit| *limit > 0)
+                    .ok_or(RuntimeError::InvalidOptions(format!(
+                        "{limit} is not a valid memory limit! Must be a whole number of MiB greater than 0"
+                    )))?;
+                // The ceiling is passed to `SQLite` in bytes
+                if limit.checked_mul(1024 * 1024).is_none() {
+                    return Err(RuntimeError::InvalidOptions(format!(
+                        "{limit} MiB is too large to be a memory limit!"
+                    )));
+                }
+                Some(limit)
+            }
+            None => None,
+        };
+
+        // Parse the size limit and overflow behavior for embedded attachments
+        let embed = match embed_limit {
+            Some(limit) => {
+                if export_type != Some(ExportType::Html) {
+                    return Err(RuntimeError::InvalidOptions(format!(
+                        "Option {OPTION_EMBED_ATTACHMENTS} is enabled, but attachments can only be embedded in `html` exports!"
+                    )));
+                }
+                let limit = limit
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|limit| *limit > 0)
+                    .ok_or(RuntimeError::InvalidOptions(format!(
+                        "{limit} is not a valid embed limit! Must be a whole number of MiB greater than 0"
+                    )))?;
+                let overflow = match embed_overflow {
+                    Some(overflow) => EmbedOverflow::from_cli(overflow).ok_or(
+                        RuntimeError::InvalidOptions(format!(
+                            "{overflow} is not a valid embed overflow! Must be one of <{SUPPORTED_EMBED_OVERFLOWS}>"
+                        )),
+                    )?,
+                    None => EmbedOverflow::default(),
+                };
+                Some(EmbedOptions {
+                    limit: limit
+                        .checked_mul(1024 * 1024)
+                        .ok_or(RuntimeError::InvalidOptions(format!(
+                            "{limit} MiB is too large to be an embed limit!"
+                        )))?,
+                    overflow,
+                })
+            }
+            None => None,
+        };
+
         // Validate the provided export path
         let export_path = validate_path(user_export_path, &export_type.as_ref())?;
 
@@ -234,6 +532,7 @@ impl Options {
             attachment_manager: attachment_manager_mode,
             diagnostic,
             export_type,
+            format,
             export_path,
             query_context,
             no_lazy,
@@ -241,6 +540,16 @@ impl Options {
             use_caller_id,
             platform,
             ignore_disk_space,
+            memory_limit,
+            strict,
+            handwriting_format,
+            handwriting_raster,
+            edit_report,
+            dump_typedstream,
+            templates,
+            file_name,
+            split_by,
+            embed,
         })
     }
 
@@ -302,7 +611,7 @@ fn validate_path(
 }
 
 /// Build the command line argument parser
-fn get_command() -> Command {
+pub fn get_command() -> Command {
     Command::new("iMessage Exporter")
         .version(crate_version!())
         .about(ABOUT)
@@ -410,6 +719,123 @@ fn get_command() -> Command {
                 .action(ArgAction::SetTrue)
                 .display_order(12)
         )
+        .arg(
+            Arg::new(OPTION_LOW_MEMORY)
+                .long(OPTION_LOW_MEMORY)
+                .help(format!("Reduce memory use when exporting very large databases\nTapbacks are indexed in a temporary table instead of cached in memory\nGroup changes and deleted messages are read as needed instead of cached in memory\nOptionally specify the memory ceiling for the database connection in MiB\nIf no ceiling is given, the default is {DEFAULT_MEMORY_LIMIT} MiB\n"))
+                .num_args(0..=1)
+                .default_missing_value(DEFAULT_MEMORY_LIMIT)
+                .display_order(13)
+                .value_name("MiB"),
+        )
+        .arg(
+            Arg::new(OPTION_STRICT)
+                .long(OPTION_STRICT)
+                .help(format!("Fail the export if any messages, attachments, or chats could not be exported correctly\nProblems are always listed in `{REPORT_FILE}` in the export directory\n"))
+                .action(ArgAction::SetTrue)
+                .display_order(14)
+        )
+        .arg(
+            Arg::new(OPTION_HANDWRITING_FORMAT)
+                .long(OPTION_HANDWRITING_FORMAT)
+                .help(format!("Specify the format used to render handwritten messages
+Animated will draw each stroke in the order it was written
+PNG images are only written when attachments are copied
+If omitted, the default is `{}`
+", HandwritingFormat::default()))
+                .display_order(15)
+                .value_name(SUPPORTED_HANDWRITING_FORMATS),
+        )
+        .arg(
+            Arg::new(OPTION_HANDWRITING_SIZE)
+                .long(OPTION_HANDWRITING_SIZE)
+                .help(format!("The length of the longest side of handwriting PNG images, in pixels
+If omitted, the default is {}
+", RasterOptions::default().size))
+                .display_order(16)
+                .value_name("pixels"),
+        )
+        .arg(
+            Arg::new(OPTION_HANDWRITING_COLOR)
+                .long(OPTION_HANDWRITING_COLOR)
+                .help("The stroke color of handwriting PNG images
+If omitted, the default is #000000
+")
+                .display_order(17)
+                .value_name("#RRGGBB"),
+        )
+        .arg(
+            Arg::new(OPTION_EDIT_REPORT)
+                .long(OPTION_EDIT_REPORT)
+                .help(format!("List every edited and unsent message in `{EDIT_REPORT_FILE}` in the export directory\nEach edit includes the words that were added and removed, grouped by conversation\n"))
+                .action(ArgAction::SetTrue)
+                .display_order(18)
+        )
+        .arg(
+            Arg::new(OPTION_RECENTLY_DELETED)
+                .long(OPTION_RECENTLY_DELETED)
+                .help("Only export messages that can be recovered from Recently Deleted, grouped by the conversation they were deleted from\nRequires macOS Ventura or iOS 16 or newer\n")
+                .action(ArgAction::SetTrue)
+                .display_order(19)
+        )
+        .arg(
+            Arg::new(OPTION_DUMP_TYPEDSTREAM)
+                .long(OPTION_DUMP_TYPEDSTREAM)
+                .help("Print the `typedstream` data in a file, or in the `attributedBody` of the message with the given ROWID, as JSON and exit\nUseful for inspecting message attributes that are not supported yet\n")
+                .display_order(20)
+                .value_name("path/to/file or ROWID"),
+        )
+        .arg(
+            Arg::new(OPTION_TEMPLATES)
+                .long(OPTION_TEMPLATES)
+                .help("Render `html` or `txt` exports with the templates in this directory\nTemplates that are not in the directory use the built-in defaults\n")
+                .display_order(21)
+                .value_name("path/to/templates"),
+        )
+        .arg(
+            Arg::new(OPTION_FILE_NAME)
+                .long(OPTION_FILE_NAME)
+                .help(format!("Specify a template for the path of each exported file, relative to the export directory\nEach `/` starts a new directory, i.e. `{{service}}/{{participants}}/{{year}}`\nSupported fields are <{SUPPORTED_FILE_NAME_FIELDS}>\nIf omitted, the default is `{{name}}`\n"))
+                .display_order(22)
+                .value_name("template"),
+        )
+        .arg(
+            Arg::new(OPTION_SPLIT_BY)
+                .long(OPTION_SPLIT_BY)
+                .help("Write each conversation to a separate file for each year or month\nHTML exports link each file to the ones before and after it\n")
+                .display_order(23)
+                .value_name(SUPPORTED_SPLITS),
+        )
+        .arg(
+            Arg::new(OPTION_EMBED_ATTACHMENTS)
+                .long(OPTION_EMBED_ATTACHMENTS)
+                .help(format!("Embed attachments in `html` exports as `data:` URIs, so each conversation is a single portable file\nImages are embedded after they are converted by `--{OPTION_ATTACHMENT_MANAGER}`\nOptionally specify the size of the largest file to embed in MiB\nIf no size is given, the default is {DEFAULT_EMBED_LIMIT} MiB\n"))
+                .num_args(0..=1)
+                .default_missing_value(DEFAULT_EMBED_LIMIT)
+                .display_order(24)
+                .value_name("MiB"),
+        )
+        .arg(
+            Arg::new(OPTION_EMBED_OVERFLOW)
+                .long(OPTION_EMBED_OVERFLOW)
+                .help(format!("Specify what happens to attachments that are too large to embed\nLink will link to the file, like exports that do not embed attachments\nSkip will leave the file out of the export\nIf omitted, the default is `{}`\n", EmbedOverflow::default()))
+                .display_order(25)
+                .value_name(SUPPORTED_EMBED_OVERFLOWS),
+        )
+        .arg(
+            Arg::new(OPTION_CONFIG)
+                .long(OPTION_CONFIG)
+                .help(format!("Read options from a TOML config file, using the same names as the command line options\nOptions passed on the command line take precedence over options in the file\nIf omitted, options are read from {}/{DEFAULT_CONFIG_FILE} if it exists\n", home()))
+                .display_order(26)
+                .value_name("path/to/config.toml"),
+        )
+        .arg(
+            Arg::new(OPTION_PROFILE)
+                .long(OPTION_PROFILE)
+                .help("Apply the options in the `[profile.<name>]` table of the config file\nOptions in the profile take precedence over the rest of the file\n")
+                .display_order(27)
+                .value_name("name"),
+        )
 }
 
 /// Parse arguments from the command line
@@ -419,18 +845,32 @@ pub fn from_command_line() -> ArgMatches {
 
 #[cfg(test)]
 mod arg_tests {
-    use std::fs;
+    use std::{fs, path::PathBuf};
 
-    use imessage_database::util::{
-        dirs::default_db_path, platform::Platform, query_context::QueryContext,
+    use imessage_database::{
+        error::export::ExportError,
+        message_types::handwriting::RasterOptions,
+        util::{dirs::default_db_path, platform::Platform, query_context::QueryContext},
     };
 
     use crate::app::{
         attachment_manager::AttachmentManager,
-        export_type::ExportType,
+        dump::DumpSource,
+        embed::{EmbedOptions, EmbedOverflow},
+        error::RuntimeError,
+        export_type::{builtin_formats, ExportType},
+        file_layout::{FileNameTemplate, SplitBy},
+        handwriting_format::HandwritingFormat,
         options::{get_command, validate_path, Options},
     };
 
+    /// Get the path to an empty config file, so tests do not depend on the config file of whoever runs them
+    fn empty_config() -> PathBuf {
+        let path = std::env::temp_dir().join("imessage-exporter-empty.toml");
+        fs::write(&path, "").unwrap();
+        path
+    }
+
     #[test]
     fn can_build_option_diagnostic_flag() {
         // Get matches from sample args
@@ -439,7 +879,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args).unwrap();
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
 
         // Expected data
         let expected = Options {
@@ -448,6 +888,7 @@ mod arg_tests {
             attachment_manager: AttachmentManager::default(),
             diagnostic: true,
             export_type: None,
+            format: None,
             export_path: validate_path(None, &None).unwrap(),
             query_context: QueryContext::default(),
             no_lazy: false,
@@ -455,6 +896,16 @@ mod arg_tests {
             use_caller_id: false,
             platform: Platform::default(),
             ignore_disk_space: false,
+            memory_limit: None,
+            strict: false,
+            handwriting_format: HandwritingFormat::default(),
+            handwriting_raster: RasterOptions::default(),
+            edit_report: false,
+            dump_typedstream: None,
+            templates: None,
+            file_name: None,
+            split_by: None,
+            embed: None,
         };
 
         assert_eq!(actual, expected);
@@ -468,7 +919,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -481,7 +932,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -494,7 +945,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -507,7 +958,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -520,7 +971,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -533,7 +984,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -549,7 +1000,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args).unwrap();
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
 
         // Expected data
         let tmp_dir = String::from("/tmp");
@@ -559,6 +1010,7 @@ mod arg_tests {
             attachment_manager: AttachmentManager::default(),
             diagnostic: false,
             export_type: Some(ExportType::Html),
+            format: Some(String::from("html")),
             export_path: validate_path(Some(&tmp_dir), &None).unwrap(),
             query_context: QueryContext::default(),
             no_lazy: false,
@@ -566,6 +1018,16 @@ mod arg_tests {
             use_caller_id: false,
             platform: Platform::default(),
             ignore_disk_space: false,
+            memory_limit: None,
+            strict: false,
+            handwriting_format: HandwritingFormat::default(),
+            handwriting_raster: RasterOptions::default(),
+            edit_report: false,
+            dump_typedstream: None,
+            templates: None,
+            file_name: None,
+            split_by: None,
+            embed: None,
         };
 
         assert_eq!(actual, expected);
@@ -582,7 +1044,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args).unwrap();
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
 
         // Expected data
         let expected = Options {
@@ -591,6 +1053,7 @@ mod arg_tests {
             attachment_manager: AttachmentManager::default(),
             diagnostic: false,
             export_type: Some(ExportType::Txt),
+            format: Some(String::from("txt")),
             export_path: validate_path(None, &None).unwrap(),
             query_context: QueryContext::default(),
             no_lazy: true,
@@ -598,6 +1061,16 @@ mod arg_tests {
             use_caller_id: false,
             platform: Platform::default(),
             ignore_disk_space: false,
+            memory_limit: None,
+            strict: false,
+            handwriting_format: HandwritingFormat::default(),
+            handwriting_raster: RasterOptions::default(),
+            edit_report: false,
+            dump_typedstream: None,
+            templates: None,
+            file_name: None,
+            split_by: None,
+            embed: None,
         };
 
         assert_eq!(actual, expected);
@@ -611,7 +1084,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -624,7 +1097,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -637,7 +1110,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -650,7 +1123,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -663,7 +1136,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -676,7 +1149,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -689,11 +1162,31 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
 
+    #[test]
+    fn can_build_option_registered_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "CSV"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Register a format alongside the built-in ones
+        let mut formats = builtin_formats();
+        formats.register("csv", |_| {
+            Err(ExportError::UnknownFormat(String::from("csv")))
+        });
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &formats).unwrap();
+
+        assert_eq!(actual.format, Some(String::from("csv")));
+        assert_eq!(actual.export_type, None);
+    }
+
     #[test]
     fn can_build_option_custom_name() {
         // Get matches from sample args
@@ -702,7 +1195,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args).unwrap();
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
 
         // Expected data
         let expected = Options {
@@ -711,6 +1204,7 @@ mod arg_tests {
             attachment_manager: AttachmentManager::default(),
             diagnostic: false,
             export_type: Some(ExportType::Txt),
+            format: Some(String::from("txt")),
             export_path: validate_path(None, &None).unwrap(),
             query_context: QueryContext::default(),
             no_lazy: false,
@@ -718,6 +1212,16 @@ mod arg_tests {
             use_caller_id: false,
             platform: Platform::default(),
             ignore_disk_space: false,
+            memory_limit: None,
+            strict: false,
+            handwriting_format: HandwritingFormat::default(),
+            handwriting_raster: RasterOptions::default(),
+            edit_report: false,
+            dump_typedstream: None,
+            templates: None,
+            file_name: None,
+            split_by: None,
+            embed: None,
         };
 
         assert_eq!(actual, expected);
@@ -731,7 +1235,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args).unwrap();
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
 
         // Expected data
         let expected = Options {
@@ -740,6 +1244,7 @@ mod arg_tests {
             attachment_manager: AttachmentManager::default(),
             diagnostic: false,
             export_type: Some(ExportType::Txt),
+            format: Some(String::from("txt")),
             export_path: validate_path(None, &None).unwrap(),
             query_context: QueryContext::default(),
             no_lazy: false,
@@ -747,6 +1252,16 @@ mod arg_tests {
             use_caller_id: true,
             platform: Platform::default(),
             ignore_disk_space: false,
+            memory_limit: None,
+            strict: false,
+            handwriting_format: HandwritingFormat::default(),
+            handwriting_raster: RasterOptions::default(),
+            edit_report: false,
+            dump_typedstream: None,
+            templates: None,
+            file_name: None,
+            split_by: None,
+            embed: None,
         };
 
         assert_eq!(actual, expected);
@@ -760,7 +1275,7 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -773,7 +1288,723 @@ mod arg_tests {
         let args = command.get_matches_from(cli_args);
 
         // Build the Options
-        let actual = Options::from_args(&args);
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+    #[test]
+    fn can_build_option_low_memory_default() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--low-memory"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert_eq!(actual.memory_limit, Some(256));
+    }
+
+    #[test]
+    fn can_build_option_low_memory_custom() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--low-memory", "64"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert_eq!(actual.memory_limit, Some(64));
+    }
+
+    #[test]
+    fn cant_build_option_low_memory_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--low-memory", "0"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_low_memory_overflow() {
+        // Get matches from sample args
+        let limit = u64::MAX.to_string();
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--low-memory", &limit];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(matches!(actual, Err(RuntimeError::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn cant_build_option_low_memory_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--low-memory"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_strict() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--strict"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert!(actual.strict);
+    }
+
+    #[test]
+    fn cant_build_option_strict_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--strict"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_edit_report() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--edit-report"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert!(actual.edit_report);
+    }
+
+    #[test]
+    fn cant_build_option_edit_report_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--edit-report"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_recently_deleted() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--recently-deleted"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert!(actual.query_context.recently_deleted);
+        assert!(actual.query_context.has_filters());
+    }
+
+    #[test]
+    fn cant_build_option_recently_deleted_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--recently-deleted"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_dump_typedstream() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--dump-typedstream", "42"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert_eq!(actual.dump_typedstream, Some(DumpSource::Message(42)));
+        assert_eq!(actual.export_type, None);
+    }
+
+    #[test]
+    fn cant_build_option_dump_typedstream_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--dump-typedstream", "fake/path"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_dump_typedstream_with_export_type() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> =
+            vec!["imessage-exporter", "--dump-typedstream", "42", "-f", "txt"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_templates() {
+        // Get matches from sample args
+        let dir = std::env::temp_dir();
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--templates",
+            dir.to_str().unwrap(),
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert_eq!(actual.templates, Some(dir));
+    }
+
+    #[test]
+    fn cant_build_option_templates_no_export() {
+        // Get matches from sample args
+        let dir = std::env::temp_dir();
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--templates", dir.to_str().unwrap()];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_templates_json() {
+        // Get matches from sample args
+        let dir = std::env::temp_dir();
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "json",
+            "--templates",
+            dir.to_str().unwrap(),
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_templates_not_directory() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--templates",
+            "fake/templates",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_config_profile() {
+        // Write a sample config file
+        let path = std::env::temp_dir().join("imessage-exporter-profile.toml");
+        fs::write(
+            &path,
+            "format = \"txt\"\nstrict = true\n\n[profile.work-phone]\nformat = \"html\"\nend-date = 2021-01-01\n",
+        )
+        .unwrap();
+
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "--config",
+            path.to_str().unwrap(),
+            "--profile",
+            "work-phone",
+            "-a",
+            "macOS",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert_eq!(actual.export_type, Some(ExportType::Html));
+        assert_eq!(actual.platform, Platform::macOS);
+        assert!(actual.strict);
+        assert!(actual.query_context.end.is_some());
+    }
+
+    #[test]
+    fn can_build_option_config_command_line_precedence() {
+        // Write a sample config file
+        let path = std::env::temp_dir().join("imessage-exporter-precedence.toml");
+        fs::write(&path, "format = \"txt\"\ncopy-method = \"efficient\"\n").unwrap();
+
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "--config",
+            path.to_str().unwrap(),
+            "-f",
+            "json",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert_eq!(actual.export_type, Some(ExportType::Json));
+        assert_eq!(actual.attachment_manager, AttachmentManager::Efficient);
+    }
+
+    #[test]
+    fn cant_build_option_config_missing_file() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--config", "fake/config.toml"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_config_invalid_value() {
+        // Write a sample config file
+        let path = std::env::temp_dir().join("imessage-exporter-invalid.toml");
+        fs::write(&path, "format = \"pdf\"\n").unwrap();
+
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--config", path.to_str().unwrap()];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_file_name_split() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--file-name",
+            "{service}/{participants}/{year}",
+            "--split-by",
+            "year",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert_eq!(
+            actual.file_name,
+            Some(FileNameTemplate::from_cli("{service}/{participants}/{year}").unwrap())
+        );
+        assert_eq!(actual.split_by, Some(SplitBy::Year));
+    }
+
+    #[test]
+    fn can_build_option_embed_default() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "html", "--embed-attachments"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert_eq!(
+            actual.embed,
+            Some(EmbedOptions {
+                limit: 10 * 1024 * 1024,
+                overflow: EmbedOverflow::Link
+            })
+        );
+    }
+
+    #[test]
+    fn can_build_option_embed_custom() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--embed-attachments",
+            "2",
+            "--embed-overflow",
+            "skip",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert_eq!(
+            actual.embed,
+            Some(EmbedOptions {
+                limit: 2 * 1024 * 1024,
+                overflow: EmbedOverflow::Skip
+            })
+        );
+    }
+
+    #[test]
+    fn cant_build_option_embed_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--embed-attachments",
+            "0",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_embed_overflow_limit() {
+        // Get matches from sample args
+        let limit = u64::MAX.to_string();
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--embed-attachments",
+            &limit,
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(matches!(actual, Err(RuntimeError::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn cant_build_option_embed_txt() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--embed-attachments"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_embed_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--embed-attachments"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_embed_overflow_without_embed() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--embed-overflow",
+            "skip",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_embed_overflow_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--embed-attachments",
+            "--embed-overflow",
+            "drop",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_file_name_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--file-name", "{chat_id}"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_file_name_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--file-name", "{week}"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_file_name_year_no_split() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--file-name", "{year}"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_file_name_month_split_year() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--file-name",
+            "{year}-{month}",
+            "--split-by",
+            "year",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_file_name_year_split_month() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--file-name",
+            "{year}",
+            "--split-by",
+            "month",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_split_by_invalid() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "-f", "txt", "--split-by", "week"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn can_build_option_handwriting_png() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "html",
+            "--handwriting-format",
+            "png",
+            "--handwriting-size",
+            "256",
+            "--handwriting-color",
+            "#1E90FF",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats()).unwrap();
+
+        assert_eq!(actual.handwriting_format, HandwritingFormat::Png);
+        assert_eq!(
+            actual.handwriting_raster,
+            RasterOptions {
+                size: 256,
+                color: [0x1e, 0x90, 0xff],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn cant_build_option_handwriting_no_export() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec!["imessage-exporter", "--handwriting-format", "animated"];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_handwriting_invalid_format() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--handwriting-format",
+            "gif",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_handwriting_size_no_png() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--handwriting-size",
+            "256",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn cant_build_option_handwriting_invalid_size() {
+        // Get matches from sample args
+        let cli_args: Vec<&str> = vec![
+            "imessage-exporter",
+            "-f",
+            "txt",
+            "--handwriting-format",
+            "png",
+            "--handwriting-size",
+            "0",
+        ];
+        let command = get_command();
+        let args = command.get_matches_from(cli_args);
+
+        // Build the Options
+        let actual = Options::from_args(&args, &empty_config(), &builtin_formats());
 
         assert!(actual.is_err());
     }
@@ -0,0 +1,234 @@
+/*!
+ Contains data structures used to collect problems that do not stop an export.
+*/
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    fs::write,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+use crate::app::error::RuntimeError;
+
+/// The name of the file the report is written to in the export directory
+pub const REPORT_FILE: &str = "export_report.json";
+
+/// Represents the kinds of problems that can happen while exporting a message
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum WarningKind {
+    /// A message references a chat that does not exist in the chat table
+    MissingChat,
+    /// A chat has no participants
+    MissingMembers,
+    /// An app message's payload could not be parsed
+    Balloon,
+    /// An attachment does not exist in the attachments table or on disk
+    MissingAttachment,
+    /// An attachment could not be copied to the export directory
+    Copy,
+    /// An attachment could not be converted to a more compatible format
+    Conversion,
+    /// A copied attachment's metadata could not be updated
+    Metadata,
+    /// Cached data could not be read from the database in low-memory mode
+    Query,
+}
+
+impl Display for WarningKind {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WarningKind::MissingChat => write!(fmt, "missing_chat"),
+            WarningKind::MissingMembers => write!(fmt, "missing_members"),
+            WarningKind::Balloon => write!(fmt, "balloon"),
+            WarningKind::MissingAttachment => write!(fmt, "missing_attachment"),
+            WarningKind::Copy => write!(fmt, "copy"),
+            WarningKind::Conversion => write!(fmt, "conversion"),
+            WarningKind::Metadata => write!(fmt, "metadata"),
+            WarningKind::Query => write!(fmt, "query"),
+        }
+    }
+}
+
+/// A problem that caused part of the export to be incomplete
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Warning {
+    /// The GUID of the message that did not export correctly, if any
+    pub guid: Option<String>,
+    /// The `ROWID` of the chat the problem happened in, if any
+    pub chat_id: Option<i32>,
+    /// The `chat_identifier` of the chat the problem happened in, if it exists
+    pub chat: Option<String>,
+    /// What went wrong
+    pub kind: WarningKind,
+    /// A description of the problem
+    pub detail: String,
+}
+
+impl Warning {
+    /// Represent the warning as a JSON object
+    fn to_json(&self) -> Value {
+        json!({
+            "guid": self.guid,
+            "chat_id": self.chat_id,
+            "chat": self.chat,
+            "kind": self.kind.to_string(),
+            "detail": self.detail,
+        })
+    }
+}
+
+/// A collection of data gathered during an export that is written to the export directory as JSON when the export finishes
+///
+/// Exporters only borrow the application state, so implementors store what they collect behind a [`RefCell`].
+pub trait JsonReport {
+    /// The name of the file the report is written to in the export directory
+    const FILE_NAME: &'static str;
+
+    /// Represent the report as a JSON object
+    fn to_json(&self) -> Value;
+
+    /// Write the report to [`FILE_NAME`](JsonReport::FILE_NAME) in the provided directory, returning the path written to
+    fn write(&self, directory: &Path) -> Result<PathBuf, RuntimeError> {
+        let path = directory.join(Self::FILE_NAME);
+        let contents = serde_json::to_string_pretty(&self.to_json())
+            .map_err(|why| RuntimeError::DiskError(why.into()))?;
+        write(&path, contents).map_err(|why| RuntimeError::CreateError(why, path.clone()))?;
+        Ok(path)
+    }
+}
+
+/// Collects warnings during an export so they can be written out when the export finishes
+///
+/// Identical warnings, for example from a message that is rendered more than once, are only recorded once.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Warnings in the order they were recorded
+    warnings: RefCell<Vec<Warning>>,
+    /// Warnings that have already been recorded
+    seen: RefCell<HashSet<Warning>>,
+}
+
+impl Report {
+    /// Record a warning, ignoring it if an identical one was already recorded
+    pub fn record(&self, warning: Warning) {
+        if self.seen.borrow_mut().insert(warning.clone()) {
+            self.warnings.borrow_mut().push(warning);
+        }
+    }
+
+    /// The number of warnings recorded
+    pub fn len(&self) -> usize {
+        self.warnings.borrow().len()
+    }
+
+    /// `true` if no warnings were recorded
+    pub fn is_empty(&self) -> bool {
+        self.warnings.borrow().is_empty()
+    }
+
+    /// Count the recorded warnings by kind
+    pub fn counts(&self) -> BTreeMap<WarningKind, usize> {
+        let mut counts = BTreeMap::new();
+        for warning in self.warnings.borrow().iter() {
+            *counts.entry(warning.kind).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Summarize the recorded warnings in a single line
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return String::from("No problems found during export");
+        }
+        let counts = self
+            .counts()
+            .into_iter()
+            .map(|(kind, count)| format!("{count} {kind}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{} problems found during export ({counts})", self.len())
+    }
+}
+
+impl JsonReport for Report {
+    const FILE_NAME: &'static str = REPORT_FILE;
+
+    fn to_json(&self) -> Value {
+        let counts: BTreeMap<String, usize> = self
+            .counts()
+            .into_iter()
+            .map(|(kind, count)| (kind.to_string(), count))
+            .collect();
+        json!({
+            "total": self.len(),
+            "counts": counts,
+            "warnings": self.warnings.borrow().iter().map(Warning::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::report::{JsonReport, Report, Warning, WarningKind};
+
+    fn warning(guid: &str, kind: WarningKind) -> Warning {
+        Warning {
+            guid: Some(guid.to_string()),
+            chat_id: Some(1),
+            chat: Some("+15558675309".to_string()),
+            kind,
+            detail: "detail".to_string(),
+        }
+    }
+
+    #[test]
+    fn can_record_warning() {
+        let report = Report::default();
+        report.record(warning("a", WarningKind::Balloon));
+
+        assert_eq!(report.len(), 1);
+        assert!(!report.is_empty());
+    }
+
+    #[test]
+    fn can_dedupe_warnings() {
+        let report = Report::default();
+        report.record(warning("a", WarningKind::Balloon));
+        report.record(warning("a", WarningKind::Balloon));
+        report.record(warning("b", WarningKind::Balloon));
+
+        assert_eq!(report.len(), 2);
+    }
+
+    #[test]
+    fn can_count_warnings() {
+        let report = Report::default();
+        report.record(warning("a", WarningKind::Balloon));
+        report.record(warning("b", WarningKind::Balloon));
+        report.record(warning("c", WarningKind::Copy));
+
+        let json = report.to_json();
+        assert_eq!(json["total"], 3);
+        assert_eq!(json["counts"]["balloon"], 2);
+        assert_eq!(json["counts"]["copy"], 1);
+        assert_eq!(json["warnings"][2]["guid"], "c");
+        assert_eq!(json["warnings"][2]["kind"], "copy");
+    }
+
+    #[test]
+    fn can_summarize_warnings() {
+        let report = Report::default();
+        assert_eq!(report.summary(), "No problems found during export");
+
+        report.record(warning("a", WarningKind::Balloon));
+        report.record(warning("b", WarningKind::MissingChat));
+        assert_eq!(
+            report.summary(),
+            "2 problems found during export (1 missing_chat, 1 balloon)"
+        );
+    }
+}
@@ -1,36 +1,55 @@
 use std::{
-    cmp::min,
+    cell::Cell,
     collections::{BTreeSet, HashMap, HashSet},
-    fs::create_dir_all,
-    path::PathBuf,
+    fs::{create_dir_all, metadata, read},
+    path::{Component, Path, PathBuf},
 };
 
+use chrono::{DateTime, Local};
 use fdlimit::raise_fd_limit;
 use fs2::available_space;
 use rusqlite::Connection;
+use serde_json::{json, Value};
 
 use crate::{
     app::{
-        attachment_manager::AttachmentManager, converter::Converter, error::RuntimeError,
-        export_type::ExportType, options::Options, sanitizers::sanitize_filename,
+        attachment_manager::AttachmentManager,
+        converter::Converter,
+        edits::EditReport,
+        embed::{data_uri, image_mime_type, EmbedOverflow},
+        error::RuntimeError,
+        export_type::Formats,
+        file_layout::Field,
+        options::Options,
+        report::{JsonReport, Report, Warning, WarningKind},
+        sanitizers::{sanitize_filename, sanitize_path_segment, truncate},
     },
-    Exporter, HTML, JSON, TXT,
+    exporters::template::read_templates,
 };
 
 use imessage_database::{
     error::table::TableError,
+    export::{pipeline::Pipeline, tapbacks::TapbackStore},
+    message_types::edited::EditStatus,
     tables::{
         attachment::Attachment,
         chat::Chat,
         chat_handle::ChatToHandle,
+        group_photo::GroupPhotoHistory,
         handle::Handle,
-        messages::Message,
+        membership::MembershipTimeline,
+        messages::{models::ScheduleState, Message},
+        recently_deleted::DeletedMessage,
+        store::{CacheStore, Stored},
         table::{
-            get_connection, get_db_size, Cacheable, Deduplicate, Diagnostic, ATTACHMENTS_DIR,
-            MAX_LENGTH, ME, ORPHANED, UNKNOWN,
+            get_connection, get_db_size, set_memory_limit, Cacheable, Deduplicate, Diagnostic,
+            Queryable, ATTACHMENTS_DIR, MAX_LENGTH, ME, ORPHANED, UNKNOWN,
         },
     },
-    util::{dates::get_offset, size::format_file_size},
+    util::{
+        dates::{format, get_local_time, get_offset},
+        size::format_file_size,
+    },
 };
 
 /// Stores the application state and handles application lifecycle
@@ -41,12 +60,18 @@ pub struct Config {
     pub real_chatrooms: HashMap<i32, i32>,
     /// Map of chatroom ID to chatroom participants
     pub chatroom_participants: HashMap<i32, BTreeSet<i32>>,
+    /// Map of chatroom ID to the history of changes to its participants
+    pub memberships: CacheStore<MembershipTimeline>,
+    /// Map of chatroom ID to the messages that changed its group photo
+    pub group_photos: CacheStore<GroupPhotoHistory>,
+    /// Map of message ID to where and when it was moved to Recently Deleted
+    pub deleted: CacheStore<DeletedMessage>,
     /// Map of participant ID to contact info
     pub participants: HashMap<i32, String>,
     /// Map of participant ID to an internal unique participant ID
     pub real_participants: HashMap<i32, i32>,
     /// Messages that are tapbacks (reactions) to other messages
-    pub tapbacks: HashMap<String, HashMap<usize, Vec<Message>>>,
+    pub tapbacks: TapbackStore,
     /// App configuration options
     pub options: Options,
     /// Global date offset used by the iMessage database:
@@ -55,6 +80,16 @@ pub struct Config {
     pub db: Connection,
     /// Converter type used when converting image files
     pub converter: Option<Converter>,
+    /// Problems found during the export that did not stop it
+    pub report: Report,
+    /// Edited and unsent messages found during the export
+    pub edits: EditReport,
+    /// Templates read from the `--templates` directory, keyed by the name of the template they replace
+    pub templates: HashMap<String, String>,
+    /// How many directories below the export directory the file being written is, used to build links to exported files
+    pub link_depth: Cell<usize>,
+    /// When the export started, used as the current time for anything that counts down from a date
+    pub started: DateTime<Local>,
 }
 
 impl Config {
@@ -65,7 +100,11 @@ impl Config {
                 if let Some(chatroom) = self.chatrooms.get(&chat_id) {
                     self.real_chatrooms.get(&chat_id).map(|id| (chatroom, id))
                 } else {
-                    eprintln!("Chat ID {chat_id} does not exist in chat table!");
+                    self.warn(
+                        message,
+                        WarningKind::MissingChat,
+                        format!("Chat ID {chat_id} does not exist in chat table!"),
+                    );
                     None
                 }
             }
@@ -74,6 +113,213 @@ impl Config {
         }
     }
 
+    /// Get the name to use for a participant that was added to or removed from a conversation
+    ///
+    /// `0` is the database owner, who is written in the second person.
+    pub fn announcement_subject(&self, handle_id: i32) -> &str {
+        if handle_id == 0 {
+            return self.options.custom_name.as_deref().unwrap_or("you");
+        }
+        self.who(Some(handle_id), false, &None)
+    }
+
+    /// Record a problem exporting a message that did not stop the export
+    pub fn warn(&self, message: &Message, kind: WarningKind, detail: String) {
+        let chat_id = message.chat_id.or(message.deleted_from);
+        self.report.record(Warning {
+            guid: Some(message.guid.clone()),
+            chat_id,
+            chat: chat_id
+                .and_then(|id| self.chatrooms.get(&id))
+                .map(|chatroom| chatroom.chat_identifier.clone()),
+            kind,
+            detail,
+        });
+    }
+
+    /// Record a message in the edit report if it was edited or unsent and the report was requested
+    pub fn log_edits(&self, message: &Message) {
+        if !self.options.edit_report {
+            return;
+        }
+        let Some(edited_parts) = &message.edited_parts else {
+            return;
+        };
+
+        let parts: Vec<Value> = edited_parts
+            .parts
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, part)| {
+                let status = match part.status {
+                    EditStatus::Edited => "edited",
+                    EditStatus::Unsent => "unsent",
+                    EditStatus::Original => return None,
+                };
+                Some(json!({
+                    "index": idx,
+                    "status": status,
+                    "history": part.edit_history.iter().map(|event| json!({
+                        "date": format(&get_local_time(&event.date, &self.offset)),
+                        "text": event.text,
+                    })).collect::<Vec<_>>(),
+                    "diffs": part.diffs().iter().map(|diff| json!({
+                        "date": format(&get_local_time(&diff.date, &self.offset)),
+                        "spans": diff.spans,
+                    })).collect::<Vec<_>>(),
+                }))
+            })
+            .collect();
+        if parts.is_empty() {
+            return;
+        }
+
+        // Edits are listed under the file the message is written to
+        let conversation = match self.conversation(message) {
+            Some((chatroom, _)) => self.file_path(chatroom, message),
+            None => {
+                let mut path = String::from(ORPHANED);
+                if let Some(export_type) = &self.options.export_type {
+                    path.push_str(export_type.extension());
+                }
+                path
+            }
+        };
+        self.edits.record(
+            &conversation,
+            json!({
+                "guid": message.guid,
+                "date": format(&message.date(&self.offset)),
+                "date_edited": format(&message.date_edited(&self.offset)),
+                "sender": self.who(message.handle_id, message.is_from_me(), &message.destination_caller_id),
+                "fully_unsent": message.is_fully_unsent(),
+                "parts": parts,
+            }),
+        );
+    }
+
+    /// Get the message that set the current photo of a conversation, including any chats it was deduplicated with
+    pub fn current_group_photo(&self, chat_id: i32) -> Option<Stored<'_, Message>> {
+        let real_id = self.real_chatrooms.get(&chat_id)?;
+        self.real_chatrooms
+            .iter()
+            .filter(|(_, id)| *id == real_id)
+            .filter_map(
+                |(chat_id, _)| match self.lookup(&self.group_photos, chat_id)? {
+                    Stored::Cached(history) => history.current().map(Stored::Cached),
+                    Stored::Queried(mut history) => history.changes.pop().map(Stored::Queried),
+                },
+            )
+            .max_by_key(|message| message.date)
+    }
+
+    /// Copy the photo set by a group photo change, returning the path to use when linking to it
+    pub fn group_photo(&self, message: &Message) -> Option<String> {
+        let mut attachment = Attachment::from_message(&self.db, message)
+            .ok()?
+            .into_iter()
+            .next()?;
+        self.options
+            .attachment_manager
+            .handle_attachment(message, &mut attachment, self)?;
+        self.attachment_src(&attachment, message)
+    }
+
+    /// Get where and when a message was moved to Recently Deleted, if it was
+    pub fn deleted_message(&self, message: &Message) -> Option<Stored<'_, DeletedMessage>> {
+        self.lookup(&self.deleted, &message.rowid)
+    }
+
+    /// Read a value from a [`CacheStore`], recording a warning if the database could not be read
+    fn lookup<'a, T: Queryable<K = i32>>(
+        &'a self,
+        store: &'a CacheStore<T>,
+        key: &i32,
+    ) -> Option<Stored<'a, T::V>> {
+        store.get(&self.db, key).unwrap_or_else(|why| {
+            self.report.record(Warning {
+                guid: None,
+                chat_id: None,
+                chat: None,
+                kind: WarningKind::Query,
+                detail: why.to_string(),
+            });
+            None
+        })
+    }
+
+    /// Describe a message that was deleted from a conversation, including when it will be permanently deleted if the database recorded it
+    pub fn deletion_note(&self, message: &Message) -> String {
+        let Some(deleted) = self.deleted_message(message) else {
+            return String::from("This message was deleted from the conversation!");
+        };
+        let (Some(date), Some(days)) = (
+            deleted.date_deleted(&self.offset),
+            deleted.days_until_purge(&self.offset, &self.started),
+        ) else {
+            return String::from("This message was deleted from the conversation!");
+        };
+        let date = format(&Ok(date));
+        match days {
+            0 => format!("This message was deleted from the conversation on {date} and may be permanently deleted at any time!"),
+            1 => format!("This message was deleted from the conversation on {date} and will be permanently deleted in 1 day!"),
+            days => format!("This message was deleted from the conversation on {date} and will be permanently deleted in {days} days!"),
+        }
+    }
+
+    /// Describe a message scheduled with Send Later, or `None` if the message was not scheduled
+    pub fn schedule_note(&self, message: &Message) -> Option<String> {
+        let date = format(&message.date_scheduled(&self.offset)?);
+        Some(match message.schedule_state()? {
+            ScheduleState::Pending => format!("This message is scheduled to be sent on {date}"),
+            ScheduleState::Sent => {
+                format!("This message was scheduled with Send Later and sent on {date}")
+            }
+            ScheduleState::Canceled => {
+                format!("This message was scheduled with Send Later for {date} and then canceled")
+            }
+            ScheduleState::Unknown(_) => {
+                format!("This message was scheduled with Send Later for {date}")
+            }
+        })
+    }
+
+    /// Describe who has been in a conversation, given the chat IDs the conversation was exported from
+    pub fn membership(&self, chat_ids: &BTreeSet<i32>) -> Value {
+        let name = |handle_id: i32| {
+            if handle_id == 0 {
+                self.who(None, true, &None)
+            } else {
+                self.who(Some(handle_id), false, &None)
+            }
+        };
+
+        let current: BTreeSet<i32> = chat_ids
+            .iter()
+            .filter_map(|chat_id| self.chatroom_participants.get(chat_id))
+            .flatten()
+            .copied()
+            .collect();
+        let timelines: Vec<_> = chat_ids
+            .iter()
+            .filter_map(|id| self.lookup(&self.memberships, id))
+            .collect();
+        let timeline = MembershipTimeline::merge(timelines.iter().map(|timeline| &**timeline));
+        let historical = timeline.historical_participants(&current);
+
+        // Several handles can belong to the same contact, so names are deduplicated
+        json!({
+            "current": current.iter().map(|id| name(*id)).collect::<BTreeSet<_>>(),
+            "historical": historical.iter().map(|id| name(*id)).collect::<BTreeSet<_>>(),
+            "timeline": timeline.events.iter().map(|event| json!({
+                "date": format(&get_local_time(&event.date, &self.offset)),
+                "change": event.change,
+                "participant": name(event.participant),
+                "actor": name(event.actor),
+            })).collect::<Vec<_>>(),
+        })
+    }
+
     /// Get the attachment path for the current session
     pub fn attachment_path(&self) -> PathBuf {
         let mut path = self.options.export_path.clone();
@@ -100,7 +346,7 @@ impl Config {
         match &attachment.copied_path {
             Some(path) => {
                 if let Ok(relative_path) = path.strip_prefix(&self.options.export_path) {
-                    return relative_path.display().to_string();
+                    return format!("{}{}", self.link_root(), relative_path.display());
                 }
                 path.display().to_string()
             }
@@ -117,46 +363,240 @@ impl Config {
     /// Get a relative path for the provided file.
     pub fn relative_path(&self, path: PathBuf) -> Option<String> {
         if let Ok(relative_path) = path.strip_prefix(&self.options.export_path) {
-            return Some(relative_path.display().to_string());
+            return Some(format!("{}{}", self.link_root(), relative_path.display()));
         }
         Some(path.display().to_string())
     }
 
+    /// Get the `src` used to display an attachment in an HTML export
+    ///
+    /// If attachments are embedded, this is a `data:` URI; otherwise, it is the same as [`Config::message_attachment_path`]
+    pub fn attachment_src(&self, attachment: &Attachment, message: &Message) -> Option<String> {
+        if self.options.embed.is_none() {
+            return Some(self.message_attachment_path(attachment));
+        }
+
+        // Embed the copied file, since it may have been converted to a more compatible format
+        let path = match &attachment.copied_path {
+            Some(path) => path.clone(),
+            None => match attachment.resolved_attachment_path(
+                &self.options.platform,
+                &self.options.db_path,
+                self.options.attachment_root.as_deref(),
+            ) {
+                Some(path) => PathBuf::from(path),
+                None => return Some(self.message_attachment_path(attachment)),
+            },
+        };
+        let mime_type = image_mime_type(&path)
+            .or(attachment.mime_type.as_deref())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+
+        self.file_src(path, &mime_type, message)
+    }
+
+    /// Get the `src` used to display an exported file in an HTML export
+    ///
+    /// If attachments are embedded, files within the size limit are `data:` URIs. Larger files are linked
+    /// or left out, returning `None`, depending on the selected [`EmbedOverflow`].
+    pub fn file_src(&self, path: PathBuf, mime_type: &str, message: &Message) -> Option<String> {
+        let Some(embed) = &self.options.embed else {
+            return self.relative_path(path);
+        };
+
+        let size = match metadata(&path) {
+            Ok(metadata) => metadata.len(),
+            Err(why) => {
+                self.warn(
+                    message,
+                    WarningKind::MissingAttachment,
+                    format!("Unable to embed {path:?}: {why}"),
+                );
+                return self.relative_path(path);
+            }
+        };
+        if size > embed.limit {
+            return match embed.overflow {
+                EmbedOverflow::Link => self.relative_path(path),
+                EmbedOverflow::Skip => None,
+            };
+        }
+
+        match read(&path) {
+            Ok(bytes) => Some(data_uri(mime_type, &bytes)),
+            Err(why) => {
+                self.warn(
+                    message,
+                    WarningKind::Copy,
+                    format!("Unable to embed {path:?}: {why}"),
+                );
+                self.relative_path(path)
+            }
+        }
+    }
+
+    /// Make links to exported files relative to the file a message is written to
+    pub fn link_from(&self, message: &Message) {
+        let depth = match self.conversation(message) {
+            Some((chatroom, _)) => self.file_path(chatroom, message).matches('/').count(),
+            None => 0,
+        };
+        self.link_depth.set(depth);
+    }
+
+    /// Get the path from the file being written back to the export directory
+    fn link_root(&self) -> String {
+        "../".repeat(self.link_depth.get())
+    }
+
     /// Get a filename for a chat, possibly using cached data.
     ///
+    /// The name is built by [`Config::chat_name`], with the extension for the export type.
+    pub fn filename(&self, chatroom: &Chat) -> String {
+        let mut filename = self.chat_name(chatroom);
+
+        // Add the extension to the filename
+        if let Some(export_type) = &self.options.export_type {
+            filename.push_str(export_type.extension());
+        }
+
+        sanitize_filename(&filename)
+    }
+
+    /// Get the path of the file a message is written to, relative to the export directory
+    ///
+    /// Without a `--file-name` template or `--split-by`, this is the same as [`Config::filename`]. When conversations are
+    /// split, the period is added to the end of the name unless the template already contains it.
+    pub fn file_path(&self, chatroom: &Chat, message: &Message) -> String {
+        let period = self.options.split_by.map(|split| {
+            split
+                .period(message, &self.offset)
+                .unwrap_or(UNKNOWN.to_string())
+        });
+
+        let mut path = match &self.options.file_name {
+            Some(template) => template.render(|field| {
+                let value = match field {
+                    Field::Name => self.chat_name(chatroom),
+                    Field::DisplayName => match chatroom.display_name() {
+                        Some(name) => truncate(name, MAX_LENGTH).to_string(),
+                        None => self.chat_participants(chatroom),
+                    },
+                    Field::Participants => self.chat_participants(chatroom),
+                    Field::ChatId => chatroom.rowid.to_string(),
+                    Field::ChatIdentifier => chatroom.chat_identifier.clone(),
+                    Field::Service => chatroom.service_name.clone().unwrap_or(UNKNOWN.to_string()),
+                    Field::Year => period
+                        .as_deref()
+                        .and_then(|period| period.get(..4))
+                        .unwrap_or(UNKNOWN)
+                        .to_string(),
+                    Field::Month => period
+                        .as_deref()
+                        .and_then(|period| period.get(5..))
+                        .unwrap_or(UNKNOWN)
+                        .to_string(),
+                };
+                // Values cannot create directories or refer to a directory outside of their own
+                let value = sanitize_path_segment(&value);
+                if value.is_empty() {
+                    UNKNOWN.to_string()
+                } else {
+                    value
+                }
+            }),
+            None if period.is_none() => return self.filename(chatroom),
+            None => sanitize_filename(&self.chat_name(chatroom)),
+        };
+
+        // Keep each period in a separate file, even if the template does not use it
+        if let Some(period) = &period {
+            // Options ensure that templates with dates contain every part of the period
+            let uses_period = self
+                .options
+                .file_name
+                .as_ref()
+                .is_some_and(|template| template.uses(Field::Year));
+            if !uses_period {
+                path.push_str(" - ");
+                path.push_str(period);
+            }
+        }
+
+        // Add the extension to the path, unless the template already has it
+        if let Some(export_type) = &self.options.export_type {
+            if !path.ends_with(export_type.extension()) {
+                path.push_str(export_type.extension());
+            }
+        }
+
+        path
+    }
+
+    /// Get the full path of a file in the export directory, creating the directories a template places it in
+    ///
+    /// Fails if `file_path` could resolve to a location outside of the export directory.
+    pub fn export_file_path(
+        &self,
+        file_path: &str,
+        extension: &str,
+    ) -> Result<PathBuf, RuntimeError> {
+        let is_contained = Path::new(file_path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !is_contained {
+            return Err(RuntimeError::InvalidOptions(format!(
+                "{file_path} is not inside of the export directory!"
+            )));
+        }
+
+        let mut path = self.options.export_path.join(file_path);
+        path.set_extension(extension);
+
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)
+                .map_err(|err| RuntimeError::CreateError(err, parent.to_path_buf()))?;
+        }
+
+        Ok(path)
+    }
+
+    /// Get the default name for a chat
+    ///
     /// If the chat has an assigned name, use that, truncating if necessary.
     ///
     /// If it does not, first try and make a flat list of its members. Failing that, use the unique `chat_identifier` field.
-    pub fn filename(&self, chatroom: &Chat) -> String {
-        let mut filename = match &chatroom.display_name() {
+    fn chat_name(&self, chatroom: &Chat) -> String {
+        match &chatroom.display_name() {
             // If there is a display name, use that
             Some(name) => {
-                format!(
-                    "{} - {}",
-                    &name[..min(MAX_LENGTH, name.len())],
-                    chatroom.rowid
-                )
+                format!("{} - {}", truncate(name, MAX_LENGTH), chatroom.rowid)
             }
             // Fallback if there is no name set
             None => {
                 if let Some(participants) = self.chatroom_participants.get(&chatroom.rowid) {
                     self.filename_from_participants(participants)
                 } else {
-                    eprintln!(
-                        "Found error: message chat ID {} has no members!",
-                        chatroom.rowid
-                    );
+                    self.report.record(Warning {
+                        guid: None,
+                        chat_id: Some(chatroom.rowid),
+                        chat: Some(chatroom.chat_identifier.clone()),
+                        kind: WarningKind::MissingMembers,
+                        detail: format!("Chat ID {} has no members!", chatroom.rowid),
+                    });
                     chatroom.chat_identifier.clone()
                 }
             }
-        };
-
-        // Add the extension to the filename
-        if let Some(export_type) = &self.options.export_type {
-            filename.push_str(export_type.extension());
         }
+    }
 
-        sanitize_filename(&filename)
+    /// Get the participants in a chat, or its unique `chat_identifier` if it has no members
+    fn chat_participants(&self, chatroom: &Chat) -> String {
+        match self.chatroom_participants.get(&chatroom.rowid) {
+            Some(participants) => self.filename_from_participants(participants),
+            None => chatroom.chat_identifier.clone(),
+        }
     }
 
     /// Generate a filename from a set of participants, truncating if the name is too long
@@ -180,9 +620,10 @@ impl Config {
                 let extra = format!(", and {} others", participants.len() - added);
                 let space_remaining = extra.len() + out_s.len();
                 if space_remaining >= MAX_LENGTH {
-                    out_s.replace_range((MAX_LENGTH - extra.len()).., &extra);
+                    out_s.truncate(truncate(&out_s, MAX_LENGTH - extra.len()).len());
+                    out_s.push_str(&extra);
                 } else if out_s.is_empty() {
-                    out_s.push_str(&participant[..MAX_LENGTH]);
+                    out_s.push_str(truncate(participant, MAX_LENGTH));
                 } else {
                     out_s.push_str(&extra);
                 }
@@ -198,26 +639,50 @@ impl Config {
     ///
     /// ```
     /// use crate::app::{
+    ///    config_file::default_config_path,
     ///    options::{from_command_line, Options},
     ///    runtime::Config,
     /// };
     ///
     /// let args = from_command_line();
-    /// let options = Options::from_args(&args);
+    /// let options = Options::from_args(&args, &default_config_path());
     /// let app = Config::new(options).unwrap();
     /// ```
     pub fn new(options: Options) -> Result<Config, RuntimeError> {
         let conn = get_connection(&options.get_db_path()).map_err(RuntimeError::DatabaseError)?;
+        if let Some(limit) = options.memory_limit {
+            set_memory_limit(&conn, limit * 1024 * 1024).map_err(RuntimeError::DatabaseError)?;
+        }
+        if options.query_context.recently_deleted && !DeletedMessage::is_supported(&conn) {
+            return Err(RuntimeError::InvalidOptions(
+                "This database does not support Recently Deleted, which requires macOS Ventura or iOS 16 or newer".to_string(),
+            ));
+        }
         eprintln!("Building cache...");
-        eprintln!("[1/4] Caching chats...");
+        eprintln!("[1/7] Caching chats...");
         let chatrooms = Chat::cache(&conn).map_err(RuntimeError::DatabaseError)?;
-        eprintln!("[2/4] Caching chatrooms...");
+        eprintln!("[2/7] Caching chatrooms...");
         let chatroom_participants =
             ChatToHandle::cache(&conn).map_err(RuntimeError::DatabaseError)?;
-        eprintln!("[3/4] Caching participants...");
+        // In low-memory mode, these are read from the database as each chat or message needs them
+        let low_memory = options.memory_limit.is_some();
+        eprintln!("[3/7] Caching membership changes...");
+        let memberships =
+            CacheStore::build(&conn, low_memory).map_err(RuntimeError::DatabaseError)?;
+        eprintln!("[4/7] Caching group photos...");
+        let group_photos =
+            CacheStore::build(&conn, low_memory).map_err(RuntimeError::DatabaseError)?;
+        eprintln!("[5/7] Caching recently deleted messages...");
+        let deleted = CacheStore::build(&conn, low_memory).map_err(RuntimeError::DatabaseError)?;
+        eprintln!("[6/7] Caching participants...");
         let participants = Handle::cache(&conn).map_err(RuntimeError::DatabaseError)?;
-        eprintln!("[4/4] Caching tapbacks...");
-        let tapbacks = Message::cache(&conn).map_err(RuntimeError::DatabaseError)?;
+        if low_memory {
+            eprintln!("[7/7] Indexing tapbacks...");
+        } else {
+            eprintln!("[7/7] Caching tapbacks...");
+        }
+        let tapbacks =
+            TapbackStore::build(&conn, low_memory).map_err(RuntimeError::DatabaseError)?;
         eprintln!("Cache built!");
 
         // Only attempt to create a converter if we need it
@@ -227,10 +692,19 @@ impl Config {
             AttachmentManager::Efficient => None,
         };
 
+        // Only read templates if the export is rendered with them
+        let templates = match (&options.templates, &options.export_type) {
+            (Some(dir), Some(export_type)) => read_templates(dir, export_type)?,
+            _ => HashMap::new(),
+        };
+
         Ok(Config {
             chatrooms,
             real_chatrooms: ChatToHandle::dedupe(&chatroom_participants),
             chatroom_participants,
+            memberships,
+            group_photos,
+            deleted,
             real_participants: Handle::dedupe(&participants),
             participants,
             tapbacks,
@@ -238,6 +712,11 @@ impl Config {
             offset: get_offset(),
             db: conn,
             converter,
+            report: Report::default(),
+            edits: EditReport::default(),
+            templates,
+            link_depth: Cell::new(0),
+            started: Local::now(),
         })
     }
 
@@ -253,7 +732,10 @@ impl Config {
             available_space(&self.options.export_path).map_err(RuntimeError::DiskError)?;
 
         // Validate that there is enough disk space free to write the export
-        if let AttachmentManager::Disabled = self.options.attachment_manager {
+        // Attachments are written to the export directory if they are copied or embedded
+        if matches!(self.options.attachment_manager, AttachmentManager::Disabled)
+            && self.options.embed.is_none()
+        {
             if estimated_export_size >= free_space_at_location {
                 return Err(RuntimeError::NotEnoughAvailableSpace(
                     estimated_export_size,
@@ -288,6 +770,7 @@ impl Config {
         Message::run_diagnostic(&self.db)?;
         Attachment::run_diagnostic(&self.db, &self.options.db_path, &self.options.platform)?;
         ChatToHandle::run_diagnostic(&self.db)?;
+        DeletedMessage::run_diagnostic(&self.db)?;
 
         // Global Diagnostics
         println!("Global diagnostic data:");
@@ -321,19 +804,22 @@ impl Config {
     ///
     /// ```
     /// use crate::app::{
+    ///    config_file::default_config_path,
+    ///    export_type::builtin_formats,
     ///    options::{from_command_line, Options},
     ///    runtime::Config,
     /// };
     ///
+    /// let formats = builtin_formats();
     /// let args = from_command_line();
-    /// let options = Options::from_args(&args);
+    /// let options = Options::from_args(&args, &default_config_path(), &formats);
     /// let app = Config::new(options).unwrap();
-    /// app.start();
+    /// app.start(&formats);
     /// ```
-    pub fn start(&self) -> Result<(), RuntimeError> {
+    pub fn start(&self, formats: &Formats) -> Result<(), RuntimeError> {
         if self.options.diagnostic {
             self.run_diagnostic().map_err(RuntimeError::DatabaseError)?;
-        } else if let Some(export_type) = &self.options.export_type {
+        } else if let Some(format) = &self.options.format {
             // Ensure the path we want to export to exists
             create_dir_all(&self.options.export_path).map_err(RuntimeError::DiskError)?;
 
@@ -351,16 +837,26 @@ impl Config {
             let _ = raise_fd_limit();
 
             // Create exporter, pass it data we care about, then kick it off
-            match export_type {
-                ExportType::Html => {
-                    HTML::new(self)?.iter_messages()?;
-                }
-                ExportType::Txt => {
-                    TXT::new(self)?.iter_messages()?;
-                }
-                ExportType::Json => {
-                    JSON::new(self)?.iter_messages()?;
-                }
+            let mut exporter = formats
+                .create(format, self)
+                .map_err(RuntimeError::ExportError)?;
+            Pipeline::with_tapbacks(&self.db, self.options.query_context.clone(), &self.tapbacks)
+                .run(exporter.as_mut())
+                .map_err(RuntimeError::ExportError)?;
+
+            // Write out any problems we found during the export
+            let report_path = self.report.write(&self.options.export_path)?;
+            eprintln!("{}, see {}", self.report.summary(), report_path.display());
+            if self.options.edit_report {
+                let edits_path = self.edits.write(&self.options.export_path)?;
+                eprintln!(
+                    "{} edited or unsent messages found, see {}",
+                    self.edits.len(),
+                    edits_path.display()
+                );
+            }
+            if self.options.strict && !self.report.is_empty() {
+                return Err(RuntimeError::Warnings(self.report.len(), report_path));
             }
         }
         println!("Done!");
@@ -391,29 +887,46 @@ impl Config {
 
 #[cfg(test)]
 mod filename_tests {
+    use super::who_tests::blank;
     use crate::{
-        app::{attachment_manager::AttachmentManager, export_type::ExportType},
+        app::{
+            attachment_manager::AttachmentManager,
+            edits::EditReport,
+            export_type::{builtin_formats, ExportType},
+            file_layout::{FileNameTemplate, SplitBy},
+            handwriting_format::HandwritingFormat,
+            report::Report,
+        },
         Config, Options,
     };
     use imessage_database::{
+        export::tapbacks::TapbackStore,
+        message_types::handwriting::RasterOptions,
         tables::{
             chat::Chat,
+            messages::Message,
+            store::CacheStore,
             table::{get_connection, MAX_LENGTH},
         },
-        util::{dirs::default_db_path, platform::Platform, query_context::QueryContext},
+        util::{platform::Platform, query_context::QueryContext},
     };
+    use imessage_fixtures::Fixture;
     use std::{
+        cell::Cell,
         collections::{BTreeSet, HashMap},
         path::PathBuf,
     };
 
+    use chrono::Local;
+
     fn fake_options() -> Options {
         Options {
-            db_path: default_db_path(),
+            db_path: Fixture::shared().to_path_buf(),
             attachment_root: None,
             attachment_manager: AttachmentManager::Disabled,
             diagnostic: false,
             export_type: Some(ExportType::Html),
+            format: Some(String::from("html")),
             export_path: PathBuf::new(),
             query_context: QueryContext::default(),
             no_lazy: false,
@@ -421,6 +934,16 @@ mod filename_tests {
             use_caller_id: false,
             platform: Platform::macOS,
             ignore_disk_space: false,
+            memory_limit: None,
+            strict: false,
+            handwriting_format: HandwritingFormat::default(),
+            handwriting_raster: RasterOptions::default(),
+            edit_report: false,
+            dump_typedstream: None,
+            templates: None,
+            file_name: None,
+            split_by: None,
+            embed: None,
         }
     }
 
@@ -439,13 +962,21 @@ mod filename_tests {
             chatrooms: HashMap::new(),
             real_chatrooms: HashMap::new(),
             chatroom_participants: HashMap::new(),
+            memberships: CacheStore::Memory(HashMap::new()),
+            group_photos: CacheStore::Memory(HashMap::new()),
+            deleted: CacheStore::Memory(HashMap::new()),
             participants: HashMap::new(),
             real_participants: HashMap::new(),
-            tapbacks: HashMap::new(),
+            tapbacks: TapbackStore::Memory(HashMap::new()),
             options,
             offset: 0,
             db: connection,
             converter: Some(crate::app::converter::Converter::Sips),
+            report: Report::default(),
+            edits: EditReport::default(),
+            templates: HashMap::new(),
+            link_depth: Cell::new(0),
+            started: Local::now(),
         }
     }
 
@@ -454,8 +985,9 @@ mod filename_tests {
         let mut options = fake_options();
         // Disable the export
         options.export_type = None;
+        options.format = None;
         let app = fake_app(options);
-        app.start().unwrap();
+        app.start(&builtin_formats()).unwrap();
     }
 
     #[test]
@@ -629,24 +1161,215 @@ mod filename_tests {
         let filename = app.filename(&chat);
         assert_eq!(filename, "Default.html");
     }
+
+    /// A message sent in May 2023
+    fn fake_message() -> Message {
+        let mut message = blank();
+        message.date = 1_684_108_800_000_000_000;
+        message
+    }
+
+    #[test]
+    fn can_get_file_path_default() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Create chat
+        let mut chat = fake_chat();
+        chat.display_name = Some("Test Chat Name".to_string());
+
+        // Get path
+        let path = app.file_path(&chat, &fake_message());
+        assert_eq!(path, app.filename(&chat));
+    }
+
+    #[test]
+    fn can_get_file_path_split_year() {
+        let mut options = fake_options();
+        options.split_by = Some(SplitBy::Year);
+        let app = fake_app(options);
+
+        // Create chat
+        let mut chat = fake_chat();
+        chat.display_name = Some("Test Chat Name".to_string());
+
+        // Get path
+        let path = app.file_path(&chat, &fake_message());
+        assert_eq!(path, "Test Chat Name - 0 - 2023.html");
+    }
+
+    #[test]
+    fn can_get_file_path_template_split_month() {
+        let mut options = fake_options();
+        options.split_by = Some(SplitBy::Month);
+        options.file_name =
+            Some(FileNameTemplate::from_cli("{service}/{display_name}/{year}-{month}").unwrap());
+        let app = fake_app(options);
+
+        // Create chat
+        let mut chat = fake_chat();
+        chat.service_name = Some("iMessage".to_string());
+        chat.display_name = Some("Test/Chat".to_string());
+
+        // Get path
+        let path = app.file_path(&chat, &fake_message());
+        assert_eq!(path, "iMessage/Test_Chat/2023-05.html");
+    }
+
+    #[test]
+    fn can_get_file_path_template_extension() {
+        let mut options = fake_options();
+        options.file_name =
+            Some(FileNameTemplate::from_cli("{chat_id}-{display_name}.html").unwrap());
+        let app = fake_app(options);
+
+        // Get path
+        let path = app.file_path(&fake_chat(), &fake_message());
+        assert_eq!(path, "0-Default.html");
+    }
+
+    #[test]
+    fn can_get_file_path_template_split_without_period() {
+        let mut options = fake_options();
+        options.split_by = Some(SplitBy::Month);
+        options.file_name = Some(FileNameTemplate::from_cli("{chat_identifier}").unwrap());
+        let app = fake_app(options);
+
+        // Get path
+        let path = app.file_path(&fake_chat(), &fake_message());
+        assert_eq!(path, "Default - 2023-05.html");
+    }
+
+    #[test]
+    fn can_get_file_path_template_empty_value() {
+        let mut options = fake_options();
+        options.file_name = Some(FileNameTemplate::from_cli("{service}/{chat_id}").unwrap());
+        let app = fake_app(options);
+
+        // Create chat
+        let mut chat = fake_chat();
+        chat.service_name = Some(String::new());
+
+        // Get path
+        let path = app.file_path(&chat, &fake_message());
+        assert_eq!(path, "Unknown/0.html");
+    }
+
+    #[test]
+    fn can_get_file_path_template_dot_values() {
+        let mut options = fake_options();
+        options.file_name =
+            Some(FileNameTemplate::from_cli("{chat_identifier}/{display_name}").unwrap());
+        let app = fake_app(options);
+
+        // Create chat
+        let mut chat = fake_chat();
+        chat.chat_identifier = "..".to_string();
+        chat.display_name = Some(".hidden".to_string());
+
+        // Get path
+        let path = app.file_path(&chat, &fake_message());
+        assert_eq!(path, "__/_hidden.html");
+    }
+
+    #[test]
+    fn can_get_file_path_template_display_name_multibyte() {
+        let mut options = fake_options();
+        options.file_name = Some(FileNameTemplate::from_cli("{display_name}").unwrap());
+        let app = fake_app(options);
+
+        // Create chat, where the length limit falls inside of a character
+        let mut chat = fake_chat();
+        chat.display_name = Some(format!("a{}", "🤠".repeat(MAX_LENGTH)));
+
+        // Get path
+        let path = app.file_path(&chat, &fake_message());
+        assert_eq!(path, format!("a{}.html", "🤠".repeat((MAX_LENGTH - 1) / 4)));
+    }
+
+    #[test]
+    fn can_get_filename_chat_display_name_multibyte() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        // Create chat, where the length limit falls inside of a character
+        let mut chat = fake_chat();
+        chat.display_name = Some(format!("a{}", "🤠".repeat(MAX_LENGTH)));
+
+        // Get filename
+        let filename = app.filename(&chat);
+        assert_eq!(
+            filename,
+            format!("a{} - 0.html", "🤠".repeat((MAX_LENGTH - 1) / 4))
+        );
+    }
+
+    #[test]
+    fn can_get_filename_single_long_multibyte() {
+        let options = fake_options();
+        let mut app = fake_app(options);
+
+        // Create participant data, where the length limit falls inside of a character
+        app.participants
+            .insert(10, format!("a{}", "🤠".repeat(MAX_LENGTH)));
+
+        // Add 1 person
+        let mut people = BTreeSet::new();
+        people.insert(10);
+
+        // Get filename
+        let filename = app.filename_from_participants(&people);
+        assert_eq!(filename, format!("a{}", "🤠".repeat((MAX_LENGTH - 1) / 4)));
+    }
+
+    #[test]
+    fn cant_get_export_file_path_parent() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        assert!(app.export_file_path("../escaped.html", "html").is_err());
+        assert!(app
+            .export_file_path("a/../../escaped.html", "html")
+            .is_err());
+    }
+
+    #[test]
+    fn cant_get_export_file_path_absolute() {
+        let options = fake_options();
+        let app = fake_app(options);
+
+        assert!(app.export_file_path("/escaped.html", "html").is_err());
+    }
 }
 
 #[cfg(test)]
 mod who_tests {
-    use crate::{app::attachment_manager::AttachmentManager, Config, Options};
+    use crate::{
+        app::{
+            attachment_manager::AttachmentManager, edits::EditReport,
+            handwriting_format::HandwritingFormat, report::Report,
+        },
+        Config, Options,
+    };
     use imessage_database::{
-        tables::{chat::Chat, messages::Message, table::get_connection},
-        util::{dirs::default_db_path, platform::Platform, query_context::QueryContext},
+        export::tapbacks::TapbackStore,
+        message_types::handwriting::RasterOptions,
+        tables::{chat::Chat, messages::Message, store::CacheStore, table::get_connection},
+        util::{platform::Platform, query_context::QueryContext},
     };
-    use std::{collections::HashMap, path::PathBuf};
+    use imessage_fixtures::Fixture;
+    use std::{cell::Cell, collections::HashMap, path::PathBuf};
 
-    fn fake_options() -> Options {
+    use chrono::Local;
+
+    pub(super) fn fake_options() -> Options {
         Options {
-            db_path: default_db_path(),
+            db_path: Fixture::shared().to_path_buf(),
             attachment_root: None,
             attachment_manager: AttachmentManager::Disabled,
             diagnostic: false,
             export_type: None,
+            format: None,
             export_path: PathBuf::new(),
             query_context: QueryContext::default(),
             no_lazy: false,
@@ -654,10 +1377,20 @@ mod who_tests {
             use_caller_id: false,
             platform: Platform::macOS,
             ignore_disk_space: false,
+            memory_limit: None,
+            strict: false,
+            handwriting_format: HandwritingFormat::default(),
+            handwriting_raster: RasterOptions::default(),
+            edit_report: false,
+            dump_typedstream: None,
+            templates: None,
+            file_name: None,
+            split_by: None,
+            embed: None,
         }
     }
 
-    fn fake_chat() -> Chat {
+    pub(super) fn fake_chat() -> Chat {
         Chat {
             rowid: 0,
             chat_identifier: "Default".to_string(),
@@ -666,23 +1399,31 @@ mod who_tests {
         }
     }
 
-    fn fake_app(options: Options) -> Config {
+    pub(super) fn fake_app(options: Options) -> Config {
         let connection = get_connection(&options.db_path).unwrap();
         Config {
             chatrooms: HashMap::new(),
             real_chatrooms: HashMap::new(),
             chatroom_participants: HashMap::new(),
+            memberships: CacheStore::Memory(HashMap::new()),
+            group_photos: CacheStore::Memory(HashMap::new()),
+            deleted: CacheStore::Memory(HashMap::new()),
             participants: HashMap::new(),
             real_participants: HashMap::new(),
-            tapbacks: HashMap::new(),
+            tapbacks: TapbackStore::Memory(HashMap::new()),
             options,
             offset: 0,
             db: connection,
             converter: Some(crate::app::converter::Converter::Sips),
+            report: Report::default(),
+            edits: EditReport::default(),
+            templates: HashMap::new(),
+            link_depth: Cell::new(0),
+            started: Local::now(),
         }
     }
 
-    fn blank() -> Message {
+    pub(super) fn blank() -> Message {
         Message {
             rowid: i32::default(),
             guid: String::default(),
@@ -710,6 +1451,8 @@ mod who_tests {
             thread_originator_part: None,
             date_edited: 0,
             associated_message_emoji: None,
+            schedule_type: 0,
+            schedule_state: 0,
             chat_id: None,
             num_attachments: 0,
             deleted_from: None,
@@ -886,22 +1629,174 @@ mod who_tests {
     }
 }
 
+#[cfg(test)]
+mod edits_tests {
+    use super::who_tests::{blank, fake_app, fake_chat, fake_options};
+    use crate::app::{export_type::ExportType, file_layout::SplitBy, report::JsonReport};
+    use imessage_database::message_types::edited::{
+        EditStatus, EditedEvent, EditedMessage, EditedMessagePart,
+    };
+
+    fn edited_message() -> EditedMessage {
+        EditedMessage {
+            parts: vec![
+                EditedMessagePart {
+                    status: EditStatus::Edited,
+                    edit_history: vec![
+                        EditedEvent {
+                            date: 674526582885055488,
+                            text: "see you at 5".to_string(),
+                            guid: None,
+                        },
+                        EditedEvent {
+                            date: 674526592885055488,
+                            text: "see you at 6".to_string(),
+                            guid: None,
+                        },
+                    ],
+                },
+                EditedMessagePart {
+                    status: EditStatus::Original,
+                    edit_history: vec![],
+                },
+                EditedMessagePart {
+                    status: EditStatus::Unsent,
+                    edit_history: vec![],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn can_log_edits() {
+        let mut options = fake_options();
+        options.edit_report = true;
+        let mut app = fake_app(options);
+        app.chatrooms.insert(0, fake_chat());
+        app.real_chatrooms.insert(0, 0);
+        app.participants.insert(0, "Contact".to_string());
+
+        let mut message = blank();
+        message.guid = "guid".to_string();
+        message.chat_id = Some(0);
+        message.edited_parts = Some(edited_message());
+        app.log_edits(&message);
+
+        assert_eq!(app.edits.len(), 1);
+        let json = app.edits.to_json();
+        let logged = &json["conversations"][0]["messages"][0];
+        assert_eq!(json["conversations"][0]["conversation"], "Default");
+        assert_eq!(logged["guid"], "guid");
+        assert_eq!(logged["sender"], "Contact");
+        assert_eq!(logged["fully_unsent"], false);
+
+        // Only the changed parts are listed
+        let parts = logged["parts"].as_array().unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0]["index"], 0);
+        assert_eq!(parts[0]["status"], "edited");
+        assert_eq!(parts[0]["history"][1]["text"], "see you at 6");
+        assert_eq!(parts[0]["diffs"][0]["spans"][1]["op"], "deleted");
+        assert_eq!(parts[0]["diffs"][0]["spans"][1]["text"], "5");
+        assert_eq!(parts[1]["index"], 2);
+        assert_eq!(parts[1]["status"], "unsent");
+    }
+
+    #[test]
+    fn can_log_edits_split() {
+        let mut options = fake_options();
+        options.edit_report = true;
+        options.export_type = Some(ExportType::Html);
+        options.split_by = Some(SplitBy::Year);
+        let mut app = fake_app(options);
+        app.chatrooms.insert(0, fake_chat());
+        app.real_chatrooms.insert(0, 0);
+
+        let mut message = blank();
+        message.date = 1_684_108_800_000_000_000;
+        message.chat_id = Some(0);
+        message.edited_parts = Some(edited_message());
+        app.log_edits(&message);
+
+        // Edits are listed under the file the message was written to
+        let json = app.edits.to_json();
+        assert_eq!(
+            json["conversations"][0]["conversation"],
+            app.file_path(&fake_chat(), &message)
+        );
+        assert_eq!(
+            json["conversations"][0]["conversation"],
+            "Default - 2023.html"
+        );
+    }
+
+    #[test]
+    fn cant_log_edits_disabled() {
+        let app = fake_app(fake_options());
+
+        let mut message = blank();
+        message.edited_parts = Some(edited_message());
+        app.log_edits(&message);
+
+        assert!(app.edits.is_empty());
+    }
+
+    #[test]
+    fn cant_log_unedited() {
+        let mut options = fake_options();
+        options.edit_report = true;
+        let app = fake_app(options);
+
+        let mut message = blank();
+        app.log_edits(&message);
+
+        message.edited_parts = Some(EditedMessage {
+            parts: vec![EditedMessagePart::default()],
+        });
+        app.log_edits(&message);
+
+        assert!(app.edits.is_empty());
+    }
+}
+
 #[cfg(test)]
 mod directory_tests {
-    use crate::{app::attachment_manager::AttachmentManager, Config, Options};
+    use super::who_tests::blank;
+    use crate::{
+        app::{
+            attachment_manager::AttachmentManager,
+            edits::EditReport,
+            embed::{EmbedOptions, EmbedOverflow},
+            handwriting_format::HandwritingFormat,
+            report::Report,
+        },
+        Config, Options,
+    };
     use imessage_database::{
-        tables::{attachment::Attachment, table::get_connection},
-        util::{dirs::default_db_path, platform::Platform, query_context::QueryContext},
+        export::tapbacks::TapbackStore,
+        message_types::handwriting::RasterOptions,
+        tables::{attachment::Attachment, store::CacheStore, table::get_connection},
+        util::{platform::Platform, query_context::QueryContext},
+    };
+    use imessage_fixtures::Fixture;
+    use std::{
+        cell::Cell,
+        collections::HashMap,
+        env::temp_dir,
+        fs::{create_dir_all, write},
+        path::PathBuf,
     };
-    use std::{collections::HashMap, path::PathBuf};
+
+    use chrono::Local;
 
     fn fake_options() -> Options {
         Options {
-            db_path: default_db_path(),
+            db_path: Fixture::shared().to_path_buf(),
             attachment_root: None,
             attachment_manager: AttachmentManager::Disabled,
             diagnostic: false,
             export_type: None,
+            format: None,
             export_path: PathBuf::new(),
             query_context: QueryContext::default(),
             no_lazy: false,
@@ -909,6 +1804,16 @@ mod directory_tests {
             use_caller_id: false,
             platform: Platform::macOS,
             ignore_disk_space: false,
+            memory_limit: None,
+            strict: false,
+            handwriting_format: HandwritingFormat::default(),
+            handwriting_raster: RasterOptions::default(),
+            edit_report: false,
+            dump_typedstream: None,
+            templates: None,
+            file_name: None,
+            split_by: None,
+            embed: None,
         }
     }
 
@@ -918,13 +1823,21 @@ mod directory_tests {
             chatrooms: HashMap::new(),
             real_chatrooms: HashMap::new(),
             chatroom_participants: HashMap::new(),
+            memberships: CacheStore::Memory(HashMap::new()),
+            group_photos: CacheStore::Memory(HashMap::new()),
+            deleted: CacheStore::Memory(HashMap::new()),
             participants: HashMap::new(),
             real_participants: HashMap::new(),
-            tapbacks: HashMap::new(),
+            tapbacks: TapbackStore::Memory(HashMap::new()),
             options,
             offset: 0,
             db: connection,
             converter: Some(crate::app::converter::Converter::Sips),
+            report: Report::default(),
+            edits: EditReport::default(),
+            templates: HashMap::new(),
+            link_depth: Cell::new(0),
+            started: Local::now(),
         }
     }
 
@@ -938,6 +1851,8 @@ mod directory_tests {
             total_bytes: 100,
             is_sticker: false,
             hide_attachment: 0,
+            emoji_image_content_identifier: None,
+            emoji_image_short_description: None,
             copied_path: None,
         }
     }
@@ -1029,4 +1944,235 @@ mod directory_tests {
         let expected = String::from("a/b/c/d.jpg");
         assert_eq!(result, expected);
     }
+
+    /// Write a copied attachment to a temporary export directory
+    fn fake_copied_attachment(name: &str) -> (PathBuf, Attachment) {
+        let export_path = temp_dir().join(name);
+        create_dir_all(&export_path).unwrap();
+
+        let mut attachment = fake_attachment();
+        let copied_path = export_path.join("d.jpeg");
+        write(&copied_path, b"Hello world").unwrap();
+        attachment.copied_path = Some(copied_path);
+
+        (export_path, attachment)
+    }
+
+    #[test]
+    fn can_get_src_not_embedded() {
+        let app = fake_app(fake_options());
+
+        // Create attachment
+        let attachment = fake_attachment();
+
+        let result = app.attachment_src(&attachment, &blank());
+        assert_eq!(result, Some(app.message_attachment_path(&attachment)));
+    }
+
+    #[test]
+    fn can_get_src_embedded() {
+        let (export_path, attachment) = fake_copied_attachment("imessage-exporter-embed");
+        let mut options = fake_options();
+        options.export_path = export_path;
+        options.embed = Some(EmbedOptions {
+            limit: 1024,
+            overflow: EmbedOverflow::Link,
+        });
+        let app = fake_app(options);
+
+        // The copied file was converted, so its media type comes from its extension
+        let result = app.attachment_src(&attachment, &blank());
+        let expected = String::from("data:image/jpeg;base64,SGVsbG8gd29ybGQ=");
+        assert_eq!(result, Some(expected));
+    }
+
+    #[test]
+    fn can_get_src_too_large_link() {
+        let (export_path, attachment) = fake_copied_attachment("imessage-exporter-embed-link");
+        let mut options = fake_options();
+        options.export_path = export_path;
+        options.embed = Some(EmbedOptions {
+            limit: 1,
+            overflow: EmbedOverflow::Link,
+        });
+        let app = fake_app(options);
+
+        let result = app.attachment_src(&attachment, &blank());
+        assert_eq!(result, Some(String::from("d.jpeg")));
+    }
+
+    #[test]
+    fn can_get_src_too_large_skip() {
+        let (export_path, attachment) = fake_copied_attachment("imessage-exporter-embed-skip");
+        let mut options = fake_options();
+        options.export_path = export_path;
+        options.embed = Some(EmbedOptions {
+            limit: 1,
+            overflow: EmbedOverflow::Skip,
+        });
+        let app = fake_app(options);
+
+        let result = app.attachment_src(&attachment, &blank());
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn can_get_src_embedded_missing() {
+        let mut options = fake_options();
+        options.embed = Some(EmbedOptions {
+            limit: 1024,
+            overflow: EmbedOverflow::Skip,
+        });
+        let app = fake_app(options);
+
+        // Files that cannot be read are linked
+        let attachment = fake_attachment();
+        let result = app.attachment_src(&attachment, &blank());
+        assert_eq!(result, Some(String::from("a/b/c/d.jpg")));
+    }
+}
+
+#[cfg(test)]
+mod membership_tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use super::who_tests::{fake_app, fake_options};
+    use imessage_database::tables::{
+        membership::{MembershipChange, MembershipEvent, MembershipTimeline},
+        store::CacheStore,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn can_get_membership() {
+        let mut app = fake_app(fake_options());
+        app.participants = HashMap::from([
+            (1, "Alice".to_string()),
+            (2, "Bob".to_string()),
+            (3, "Carol".to_string()),
+        ]);
+        app.chatroom_participants.insert(0, BTreeSet::from([1, 2]));
+        let mut memberships = HashMap::new();
+        memberships.insert(
+            0,
+            MembershipTimeline {
+                events: vec![
+                    MembershipEvent {
+                        date: 674526582885055488,
+                        change: MembershipChange::Added,
+                        participant: 3,
+                        actor: 0,
+                    },
+                    MembershipEvent {
+                        date: 674526592885055488,
+                        change: MembershipChange::Left,
+                        participant: 3,
+                        actor: 3,
+                    },
+                ],
+            },
+        );
+        app.memberships = CacheStore::Memory(memberships);
+
+        let membership = app.membership(&BTreeSet::from([0]));
+        assert_eq!(membership["current"], json!(["Alice", "Bob"]));
+        assert_eq!(membership["historical"], json!(["Alice", "Bob", "Carol"]));
+        assert_eq!(membership["timeline"][0]["change"], "Added");
+        assert_eq!(membership["timeline"][0]["participant"], "Carol");
+        assert_eq!(membership["timeline"][0]["actor"], "Me");
+        assert_eq!(membership["timeline"][1]["change"], "Left");
+        assert_eq!(membership["timeline"][1]["actor"], "Carol");
+    }
+
+    #[test]
+    fn can_get_membership_unchanged() {
+        let mut app = fake_app(fake_options());
+        app.participants.insert(1, "Alice".to_string());
+        app.chatroom_participants.insert(0, BTreeSet::from([1]));
+
+        let membership = app.membership(&BTreeSet::from([0]));
+        assert_eq!(membership["current"], json!(["Alice"]));
+        assert_eq!(membership["historical"], json!(["Alice"]));
+        assert_eq!(membership["timeline"], json!([]));
+    }
+}
+
+#[cfg(test)]
+mod group_photo_tests {
+    use std::collections::HashMap;
+
+    use super::who_tests::{blank, fake_app, fake_options};
+    use imessage_database::tables::{
+        group_photo::GroupPhotoHistory, messages::Message, store::CacheStore,
+    };
+
+    fn photo_change(date: i64) -> Message {
+        let mut message = blank();
+        message.date = date;
+        message.item_type = 3;
+        message.group_action_type = 1;
+        message
+    }
+
+    #[test]
+    fn can_get_current_group_photo() {
+        let mut app = fake_app(fake_options());
+        app.real_chatrooms.insert(0, 0);
+        let mut group_photos = HashMap::new();
+        group_photos.insert(
+            0,
+            GroupPhotoHistory {
+                changes: vec![photo_change(1), photo_change(2)],
+            },
+        );
+        app.group_photos = CacheStore::Memory(group_photos);
+
+        assert_eq!(app.current_group_photo(0).unwrap().date, 2);
+    }
+
+    #[test]
+    fn can_get_current_group_photo_deduplicated() {
+        let mut app = fake_app(fake_options());
+        app.real_chatrooms.insert(0, 0);
+        app.real_chatrooms.insert(1, 0);
+        app.real_chatrooms.insert(2, 1);
+        let mut group_photos = HashMap::new();
+        group_photos.insert(
+            0,
+            GroupPhotoHistory {
+                changes: vec![photo_change(1)],
+            },
+        );
+        group_photos.insert(
+            1,
+            GroupPhotoHistory {
+                changes: vec![photo_change(3)],
+            },
+        );
+        group_photos.insert(
+            2,
+            GroupPhotoHistory {
+                changes: vec![photo_change(5)],
+            },
+        );
+        app.group_photos = CacheStore::Memory(group_photos);
+
+        assert_eq!(app.current_group_photo(0).unwrap().date, 3);
+        assert_eq!(app.current_group_photo(1).unwrap().date, 3);
+    }
+
+    #[test]
+    fn cant_get_current_group_photo_unchanged() {
+        let mut app = fake_app(fake_options());
+        app.real_chatrooms.insert(0, 0);
+
+        assert!(app.current_group_photo(0).is_none());
+    }
+
+    #[test]
+    fn cant_get_group_photo_without_attachment() {
+        let app = fake_app(fake_options());
+
+        assert!(app.group_photo(&photo_change(1)).is_none());
+    }
 }
@@ -88,8 +88,30 @@ pub fn sanitize_filename(filename: &str) -> String {
         .collect()
 }
 
+/// Remove unsafe chars in [this list](FILENAME_DISALLOWED_CHARS) and replace any leading dots, so a value
+/// cannot refer to the current or parent directory or create a hidden file.
+pub fn sanitize_path_segment(segment: &str) -> String {
+    let visible = segment.trim_start_matches('.');
+    let mut sanitized: String =
+        std::iter::repeat_n(FILENAME_REPLACEMENT_CHAR, segment.len() - visible.len()).collect();
+    sanitized.push_str(&sanitize_filename(visible));
+    sanitized
+}
+
+/// Shorten a string to at most `max_len` bytes without splitting a character.
+pub fn truncate(input: &str, max_len: usize) -> &str {
+    if input.len() <= max_len {
+        return input;
+    }
+    let mut end = max_len;
+    while !input.is_char_boundary(end) {
+        end -= 1;
+    }
+    &input[..end]
+}
+
 /// Escapes HTML special characters in the input string.
-pub fn sanitize_html(input: &str) -> Cow<str> {
+pub fn sanitize_html(input: &str) -> Cow<'_, str> {
     for (idx, c) in input.char_indices() {
         if HTML_DISALLOWED_CHARS.contains_key(&c) {
             let mut res = String::from(&input[..idx]);
@@ -106,7 +128,7 @@ pub fn sanitize_html(input: &str) -> Cow<str> {
 }
 
 /// Escapes JSON special characters and control characters in the input string.
-pub fn sanitize_json(input: &str) -> Cow<str> {
+pub fn sanitize_json(input: &str) -> Cow<'_, str> {
     for (idx, c) in input.char_indices() {
         if JSON_DISALLOWED_CHARS.contains_key(&c) {
             let mut res = String::from(&input[..idx]);
@@ -122,6 +144,49 @@ pub fn sanitize_json(input: &str) -> Cow<str> {
     Cow::Borrowed(input)
 }
 
+/// Percent-encodes every character except the [unreserved characters](https://www.rfc-editor.org/rfc/rfc3986#section-2.3) for use in a URI component.
+pub fn encode_uri_component(input: &str) -> Cow<'_, str> {
+    let is_unreserved = |byte: &u8| byte.is_ascii_alphanumeric() || b"-_.~".contains(byte);
+    if input.as_bytes().iter().all(is_unreserved) {
+        return Cow::Borrowed(input);
+    }
+
+    let mut res = String::with_capacity(input.len());
+    input.bytes().for_each(|byte| {
+        if is_unreserved(&byte) {
+            res.push(byte as char);
+        } else {
+            res.push_str(&format!("%{byte:02X}"));
+        }
+    });
+    Cow::Owned(res)
+}
+
+/// Percent-encodes each segment of a relative path, keeping the `/` between them, for use as a link to another exported file.
+pub fn encode_uri_path(path: &str) -> String {
+    path.split('/')
+        .map(encode_uri_component)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Encodes an [iCalendar](https://www.rfc-editor.org/rfc/rfc5545) event with the given summary as a `data:` URI that downloads it.
+pub fn encode_calendar_event(summary: &str) -> String {
+    // Text values must escape backslashes, semicolons, commas, and newlines
+    let summary = summary
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n");
+    let calendar = format!(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//imessage-exporter//EN\r\nBEGIN:VEVENT\r\nSUMMARY:{summary}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+    );
+    format!(
+        "data:text/calendar;charset=utf-8,{}",
+        encode_uri_component(&calendar)
+    )
+}
+
 #[cfg(test)]
 mod test_filename {
     use crate::app::sanitizers::sanitize_filename;
@@ -150,6 +215,62 @@ mod test_filename {
     }
 }
 
+#[cfg(test)]
+mod test_path_segment {
+    use crate::app::sanitizers::sanitize_path_segment;
+
+    #[test]
+    fn can_sanitize_parent_dir() {
+        assert_eq!(sanitize_path_segment(".."), "__");
+    }
+
+    #[test]
+    fn can_sanitize_current_dir() {
+        assert_eq!(sanitize_path_segment("."), "_");
+    }
+
+    #[test]
+    fn can_sanitize_hidden() {
+        assert_eq!(sanitize_path_segment(".hidden/file"), "_hidden_file");
+    }
+
+    #[test]
+    fn doesnt_sanitize_inner_dots() {
+        assert_eq!(sanitize_path_segment("a.b..c"), "a.b..c");
+    }
+
+    #[test]
+    fn can_sanitize_empty() {
+        assert_eq!(sanitize_path_segment(""), "");
+    }
+}
+
+#[cfg(test)]
+mod test_truncate {
+    use crate::app::sanitizers::truncate;
+
+    #[test]
+    fn doesnt_truncate_short() {
+        assert_eq!(truncate("abc", 5), "abc");
+    }
+
+    #[test]
+    fn can_truncate_ascii() {
+        assert_eq!(truncate("abcdef", 3), "abc");
+    }
+
+    #[test]
+    fn can_truncate_on_char_boundary() {
+        // Each emoji is 4 bytes
+        assert_eq!(truncate("🤠🤠", 6), "🤠");
+    }
+
+    #[test]
+    fn can_truncate_inside_first_char() {
+        assert_eq!(truncate("🤠", 2), "");
+    }
+}
+
 #[cfg(test)]
 mod test_html {
     use crate::app::sanitizers::sanitize_html;
@@ -274,3 +395,42 @@ mod test_json {
         );
     }
 }
+
+#[cfg(test)]
+mod test_uri {
+    use crate::app::sanitizers::{encode_calendar_event, encode_uri_component, encode_uri_path};
+
+    #[test]
+    fn doesnt_encode_unreserved() {
+        assert_eq!(encode_uri_component("a-b_c.d~1"), "a-b_c.d~1");
+    }
+
+    #[test]
+    fn can_encode_reserved() {
+        assert_eq!(
+            encode_uri_component("1 Infinite Loop, Cupertino"),
+            "1%20Infinite%20Loop%2C%20Cupertino"
+        );
+    }
+
+    #[test]
+    fn can_encode_multibyte() {
+        assert_eq!(encode_uri_component("café"), "caf%C3%A9");
+    }
+
+    #[test]
+    fn can_encode_path() {
+        assert_eq!(
+            encode_uri_path("../Q&A #1/100% done.html"),
+            "../Q%26A%20%231/100%25%20done.html"
+        );
+    }
+
+    #[test]
+    fn can_encode_calendar_event() {
+        assert_eq!(
+            encode_calendar_event("Lunch, tomorrow"),
+            "data:text/calendar;charset=utf-8,BEGIN%3AVCALENDAR%0D%0AVERSION%3A2.0%0D%0APRODID%3A-%2F%2Fimessage-exporter%2F%2FEN%0D%0ABEGIN%3AVEVENT%0D%0ASUMMARY%3ALunch%5C%2C%20tomorrow%0D%0AEND%3AVEVENT%0D%0AEND%3AVCALENDAR%0D%0A"
+        );
+    }
+}
@@ -1,18 +1,19 @@
 use std::{borrow::Cow, fs::File, io::BufWriter, marker::Sized};
 
 use imessage_database::{
-    error::{plist::PlistParseError, table::TableError},
+    error::{export::ExportError, table::TableError},
+    export::{balloon::Balloon, document::DocumentMessage},
     message_types::{
-        app::AppMessage,
-        app_store::AppStoreMessage,
-        collaboration::CollaborationMessage,
+        app::OwnedAppMessage,
+        app_store::OwnedAppStoreMessage,
+        collaboration::OwnedCollaborationMessage,
         digital_touch::DigitalTouch,
         edited::EditedMessage,
         handwriting::HandwrittenMessage,
-        music::MusicMessage,
-        placemark::PlacemarkMessage,
-        text_effects::{Animation, Style, TextEffect, Unit},
-        url::URLMessage,
+        music::OwnedMusicMessage,
+        placemark::OwnedPlacemarkMessage,
+        text_effects::{Animation, OwnedTextEffect, Style, Unit},
+        url::OwnedURLMessage,
     },
     tables::{attachment::Attachment, messages::Message},
 };
@@ -25,8 +26,6 @@ pub trait Exporter<'a> {
     fn new(config: &'a Config) -> Result<Self, RuntimeError>
     where
         Self: Sized;
-    /// Begin iterating over the messages table
-    fn iter_messages(&mut self) -> Result<(), RuntimeError>;
     /// Get the file handle to write to, otherwise create a new one
     fn get_or_create_file(
         &mut self,
@@ -37,7 +36,7 @@ pub trait Exporter<'a> {
 /// Defines behavior for formatting message instances to the desired output format
 pub(super) trait Writer<'a> {
     /// Format a message, including its tapbacks and replies
-    fn format_message(&self, msg: &Message, indent: usize) -> Result<String, TableError>;
+    fn format_message(&self, msg: &DocumentMessage, indent: usize) -> Result<String, ExportError>;
     /// Format an attachment, possibly by reading the disk
     fn format_attachment(
         &self,
@@ -46,13 +45,8 @@ pub(super) trait Writer<'a> {
     ) -> Result<String, &'a str>;
     /// Format a sticker, possibly by reading the disk
     fn format_sticker(&self, attachment: &'a mut Attachment, msg: &'a Message) -> String;
-    /// Format an app message by parsing some of its fields
-    fn format_app(
-        &self,
-        msg: &'a Message,
-        attachments: &mut Vec<Attachment>,
-        indent: &str,
-    ) -> Result<String, PlistParseError>;
+    /// Format a Genmoji that is displayed inline with the message text
+    fn format_genmoji(&self, genmoji: &mut Attachment, msg: &Message) -> String;
     /// Format a tapback (displayed under a message)
     fn format_tapback(&self, msg: &Message) -> Result<String, TableError>;
     /// Format an expressive message
@@ -72,44 +66,70 @@ pub(super) trait Writer<'a> {
         indent: &str,
     ) -> Option<String>;
     /// Format some attributed text
-    fn format_attributed(&'a self, text: &'a str, attribute: &'a TextEffect) -> Cow<'a, str>;
+    fn format_attributed(&'a self, text: &'a str, attribute: &'a OwnedTextEffect) -> Cow<'a, str>;
     fn write_to_file(file: &mut BufWriter<File>, text: &str) -> Result<(), RuntimeError>;
 }
 
 /// Defines behavior for formatting custom balloons to the desired output format
 pub(super) trait BalloonFormatter<T> {
     /// Format a URL message
-    fn format_url(&self, msg: &Message, balloon: &URLMessage, indent: T) -> String;
+    fn format_url(&self, msg: &Message, balloon: &OwnedURLMessage, indent: T) -> String;
     /// Format an Apple Music message
-    fn format_music(&self, balloon: &MusicMessage, indent: T) -> String;
+    fn format_music(&self, balloon: &OwnedMusicMessage, indent: T) -> String;
     /// Format a Rich Collaboration message
-    fn format_collaboration(&self, balloon: &CollaborationMessage, indent: T) -> String;
+    fn format_collaboration(&self, balloon: &OwnedCollaborationMessage, indent: T) -> String;
     /// Format an App Store link
-    fn format_app_store(&self, balloon: &AppStoreMessage, indent: T) -> String;
+    fn format_app_store(&self, balloon: &OwnedAppStoreMessage, indent: T) -> String;
     /// Format a shared location message
-    fn format_placemark(&self, balloon: &PlacemarkMessage, indent: T) -> String;
+    fn format_placemark(&self, balloon: &OwnedPlacemarkMessage, indent: T) -> String;
     /// Format a handwritten note message
     fn format_handwriting(&self, msg: &Message, balloon: &HandwrittenMessage, indent: T) -> String;
     /// Format a digital touch message
     fn format_digital_touch(&self, msg: &Message, balloon: &DigitalTouch, indent: T) -> String;
     /// Format an Apple Pay message
-    fn format_apple_pay(&self, balloon: &AppMessage, indent: T) -> String;
+    fn format_apple_pay(&self, balloon: &OwnedAppMessage, indent: T) -> String;
     /// Format a Fitness message
-    fn format_fitness(&self, balloon: &AppMessage, indent: T) -> String;
+    fn format_fitness(&self, balloon: &OwnedAppMessage, indent: T) -> String;
     /// Format a Photo Slideshow message
-    fn format_slideshow(&self, balloon: &AppMessage, indent: T) -> String;
+    fn format_slideshow(&self, balloon: &OwnedAppMessage, indent: T) -> String;
     /// Format a Find My message
-    fn format_find_my(&self, balloon: &AppMessage, indent: T) -> String;
+    fn format_find_my(&self, balloon: &OwnedAppMessage, indent: T) -> String;
     /// Format a Check In message
-    fn format_check_in(&self, balloon: &AppMessage, indent: T) -> String;
+    fn format_check_in(&self, balloon: &OwnedAppMessage, indent: T) -> String;
     /// Format a generic app, generally third party
     fn format_generic_app(
         &self,
-        balloon: &AppMessage,
+        balloon: &OwnedAppMessage,
         bundle_id: &str,
         attachments: &mut Vec<Attachment>,
         indent: T,
     ) -> String;
+    /// Format a parsed app balloon by calling the method for its type
+    fn format_balloon(
+        &self,
+        msg: &Message,
+        balloon: &Balloon,
+        attachments: &mut Vec<Attachment>,
+        indent: T,
+    ) -> String {
+        match balloon {
+            Balloon::URL(balloon) => self.format_url(msg, balloon, indent),
+            Balloon::Music(balloon) => self.format_music(balloon, indent),
+            Balloon::Collaboration(balloon) => self.format_collaboration(balloon, indent),
+            Balloon::AppStore(balloon) => self.format_app_store(balloon, indent),
+            Balloon::Placemark(balloon) => self.format_placemark(balloon, indent),
+            Balloon::Handwriting(balloon) => self.format_handwriting(msg, balloon, indent),
+            Balloon::DigitalTouch(balloon) => self.format_digital_touch(msg, balloon, indent),
+            Balloon::ApplePay(balloon) => self.format_apple_pay(balloon, indent),
+            Balloon::Fitness(balloon) => self.format_fitness(balloon, indent),
+            Balloon::Slideshow(balloon) => self.format_slideshow(balloon, indent),
+            Balloon::CheckIn(balloon) => self.format_check_in(balloon, indent),
+            Balloon::FindMy(balloon) => self.format_find_my(balloon, indent),
+            Balloon::Application(bundle_id, balloon) => {
+                self.format_generic_app(balloon, bundle_id, attachments, indent)
+            }
+        }
+    }
 }
 
 pub(super) trait TextEffectFormatter {
@@ -125,4 +145,14 @@ pub(super) trait TextEffectFormatter {
     fn format_styles(&self, text: &str, styles: &[Style]) -> String;
     /// Format [`Animated`](imessage_database::message_types::text_effects::TextEffect::Animated) message text
     fn format_animated(&self, text: &str, animation: &Animation) -> String;
+    /// Format message text containing an [`Address`](imessage_database::message_types::text_effects::TextEffect::Address)
+    fn format_address(&self, text: &str, address: &str) -> String;
+    /// Format message text containing an [`Event`](imessage_database::message_types::text_effects::TextEffect::Event)
+    fn format_event(&self, text: &str, event: &str) -> String;
+    /// Format message text containing a [`PhoneNumber`](imessage_database::message_types::text_effects::TextEffect::PhoneNumber)
+    fn format_phone_number(&self, text: &str, number: &str) -> String;
+    /// Format message text containing a [`FlightNumber`](imessage_database::message_types::text_effects::TextEffect::FlightNumber)
+    fn format_flight_number(&self, text: &str, flight: &str) -> String;
+    /// Format message text containing a [`TrackingNumber`](imessage_database::message_types::text_effects::TextEffect::TrackingNumber)
+    fn format_tracking_number(&self, text: &str, tracking: &str) -> String;
 }
@@ -2,50 +2,62 @@ use std::{
     borrow::Cow,
     collections::{
         hash_map::Entry::{Occupied, Vacant},
-        HashMap,
+        BTreeMap, HashMap,
     },
     fs::File,
     io::{BufWriter, Write},
 };
 
+use indicatif::ProgressBar;
+use rusqlite::Connection;
+
 use crate::{
     app::{
-        error::RuntimeError, progress::build_progress_bar_export, runtime::Config,
-        sanitizers::sanitize_html,
+        error::RuntimeError,
+        file_layout::SplitPages,
+        handwriting_format::HandwritingFormat,
+        progress::build_progress_bar_export,
+        report::WarningKind,
+        runtime::Config,
+        sanitizers::{encode_calendar_event, encode_uri_component, encode_uri_path, sanitize_html},
     },
     exporters::exporter::{BalloonFormatter, Exporter, TextEffectFormatter, Writer},
 };
 
 use imessage_database::{
-    error::{plist::PlistParseError, table::TableError},
+    error::{export::ExportError, plist::PlistParseError, table::TableError},
+    export::document::{Content, DocumentMessage, Item, Render},
     message_types::{
-        app::AppMessage,
-        app_store::AppStoreMessage,
-        collaboration::CollaborationMessage,
-        digital_touch::{self, DigitalTouch},
-        edited::{EditStatus, EditedMessage},
+        app::OwnedAppMessage,
+        app_store::OwnedAppStoreMessage,
+        collaboration::OwnedCollaborationMessage,
+        digital_touch::DigitalTouch,
+        edited::{DiffSpan, EditStatus, EditedMessage},
         expressives::{BubbleEffect, Expressive, ScreenEffect},
         handwriting::HandwrittenMessage,
-        music::MusicMessage,
-        placemark::PlacemarkMessage,
-        text_effects::{Animation, Style, TextEffect, Unit},
-        url::URLMessage,
-        variants::{Announcement, BalloonProvider, CustomBalloon, URLOverride, Variant},
+        music::OwnedMusicMessage,
+        placemark::OwnedPlacemarkMessage,
+        text_effects::{Animation, OwnedTextEffect, Style, Unit},
+        url::OwnedURLMessage,
+        variants::{Announcement, Variant},
     },
     tables::{
         attachment::{Attachment, MediaType},
-        messages::{models::BubbleComponent, Message},
-        table::{Table, FITNESS_RECEIVER, ME, ORPHANED, YOU},
-    },
-    util::{
-        dates::{format, get_local_time, readable_diff, TIMESTAMP_FACTOR},
-        plist::parse_plist,
+        chat::Chat,
+        messages::{
+            models::{BubbleComponent, OwnedTextAttributes},
+            Message,
+        },
+        table::{FITNESS_RECEIVER, ME, ORPHANED, YOU},
     },
+    util::dates::{format, get_local_time, readable_diff, TIMESTAMP_FACTOR},
 };
 
 const HEADER: &str = "<html>\n<head>\n<meta charset=\"UTF-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">";
 const FOOTER: &str = "</body></html>";
-const STYLE: &str = include_str!("resources/style.css");
+pub const STYLE: &str = include_str!("resources/style.css");
+/// The page that links to every exported conversation
+pub const INDEX_FILE: &str = "index.html";
 
 pub struct HTML<'a> {
     /// Data that is setup from the application's runtime
@@ -55,6 +67,14 @@ pub struct HTML<'a> {
     pub files: HashMap<String, BufWriter<File>>,
     /// Writer instance for orphaned messages
     pub orphaned: BufWriter<File>,
+    /// Map of exported file names to the chatroom they were created for
+    pub conversations: BTreeMap<String, i32>,
+    /// Files that conversations were split into, so each can link to the ones before and after it
+    pub pages: SplitPages,
+    /// Progress through the messages table
+    pb: ProgressBar,
+    /// The number of top-level messages and announcements rendered so far
+    current_message: u64,
 }
 
 impl<'a> Exporter<'a> for HTML<'a> {
@@ -72,79 +92,13 @@ impl<'a> Exporter<'a> for HTML<'a> {
             config,
             files: HashMap::new(),
             orphaned: BufWriter::new(file),
+            conversations: BTreeMap::new(),
+            pages: SplitPages::default(),
+            pb: ProgressBar::hidden(),
+            current_message: 0,
         })
     }
 
-    fn iter_messages(&mut self) -> Result<(), RuntimeError> {
-        // Tell the user what we are doing
-        eprintln!(
-            "Exporting to {} as html...",
-            self.config.options.export_path.display()
-        );
-
-        // Write orphaned file headers
-        HTML::write_headers(&mut self.orphaned)?;
-
-        // Keep track of current message ROWID
-        let mut current_message_row = -1;
-
-        // Set up progress bar
-        let mut current_message = 0;
-        let total_messages =
-            Message::get_count(&self.config.db, &self.config.options.query_context)
-                .map_err(RuntimeError::DatabaseError)?;
-        let pb = build_progress_bar_export(total_messages);
-
-        let mut statement =
-            Message::stream_rows(&self.config.db, &self.config.options.query_context)
-                .map_err(RuntimeError::DatabaseError)?;
-
-        let messages = statement
-            .query_map([], |row| Ok(Message::from_row(row)))
-            .map_err(|err| RuntimeError::DatabaseError(TableError::Messages(err)))?;
-
-        for message in messages {
-            let mut msg = Message::extract(message).map_err(RuntimeError::DatabaseError)?;
-
-            // Early escape if we try and render the same message GUID twice
-            // See https://github.com/ReagentX/imessage-exporter/issues/135 for rationale
-            if msg.rowid == current_message_row {
-                current_message += 1;
-                continue;
-            }
-            current_message_row = msg.rowid;
-
-            // Generate the text of the message
-            let _ = msg.generate_text(&self.config.db);
-
-            // Render the announcement in-line
-            if msg.is_announcement() {
-                let announcement = self.format_announcement(&msg);
-                HTML::write_to_file(self.get_or_create_file(&msg)?, &announcement)?;
-            }
-            // Message replies and tapbacks are rendered in context, so no need to render them separately
-            else if !msg.is_tapback() {
-                let message = self
-                    .format_message(&msg, 0)
-                    .map_err(RuntimeError::DatabaseError)?;
-                HTML::write_to_file(self.get_or_create_file(&msg)?, &message)?;
-            }
-            current_message += 1;
-            if current_message % 99 == 0 {
-                pb.set_position(current_message);
-            }
-        }
-        pb.finish();
-
-        eprintln!("Writing HTML footers...");
-        for (_, buf) in self.files.iter_mut() {
-            HTML::write_to_file(buf, FOOTER)?;
-        }
-        HTML::write_to_file(&mut self.orphaned, FOOTER)?;
-
-        Ok(())
-    }
-
     /// Create a file for the given chat, caching it so we don't need to build it later
     fn get_or_create_file(
         &mut self,
@@ -152,18 +106,31 @@ impl<'a> Exporter<'a> for HTML<'a> {
     ) -> Result<&mut BufWriter<File>, RuntimeError> {
         match self.config.conversation(message) {
             Some((chatroom, _)) => {
-                let filename = self.config.filename(chatroom);
-                return match self.files.entry(filename) {
+                let filename = self.config.file_path(chatroom, message);
+                match self.files.entry(filename) {
                     Occupied(entry) => Ok(entry.into_mut()),
                     Vacant(entry) => {
-                        let mut path = self.config.options.export_path.clone();
-                        path.push(self.config.filename(chatroom));
-                        path.set_extension("html");
+                        let path = self.config.export_file_path(entry.key(), "html")?;
 
                         // If the file already exists, don't write the headers again
                         // This can happen if multiple chats use the same group name
                         let file_exists = path.exists();
 
+                        // Remember the file so it can be linked from the index
+                        self.conversations
+                            .insert(entry.key().clone(), chatroom.rowid);
+
+                        // Remember which period the file contains so it can be linked to the files around it
+                        if let Some(period) = self
+                            .config
+                            .options
+                            .split_by
+                            .and_then(|split| split.period(message, &self.config.offset))
+                        {
+                            self.pages
+                                .insert(entry.key().clone(), chatroom.rowid, period);
+                        }
+
                         let file = File::options()
                             .append(true)
                             .create(true)
@@ -175,11 +142,14 @@ impl<'a> Exporter<'a> for HTML<'a> {
                         // Write headers if the file does not exist
                         if !file_exists {
                             let _ = HTML::write_headers(&mut buf);
+                            if let Some(header) = HTML::format_chat_header(self.config, chatroom) {
+                                HTML::write_to_file(&mut buf, &header)?;
+                            }
                         }
 
                         Ok(entry.insert(buf))
                     }
-                };
+                }
             }
             None => Ok(&mut self.orphaned),
         }
@@ -187,12 +157,20 @@ impl<'a> Exporter<'a> for HTML<'a> {
 }
 
 impl<'a> Writer<'a> for HTML<'a> {
-    fn format_message(&self, message: &Message, indent_size: usize) -> Result<String, TableError> {
+    fn format_message(
+        &self,
+        document: &DocumentMessage,
+        indent_size: usize,
+    ) -> Result<String, ExportError> {
+        let message = &document.message;
+        // Replies are rendered in a thread under the message they respond to
+        let in_thread = indent_size > 0;
+
         // Data we want to write to a file
         let mut formatted_message = String::new();
 
         // Message div
-        if message.is_reply() && indent_size == 0 {
+        if message.is_reply() && !in_thread {
             // Add an ID for any top-level message so we can link to them in threads
             self.add_line(
                 &mut formatted_message,
@@ -227,7 +205,7 @@ impl<'a> Writer<'a> for HTML<'a> {
 
         // Add reply anchor if necessary
         if message.is_reply() {
-            if indent_size > 0 {
+            if in_thread {
                 // If we are indented it means we are rendering in a thread
                 self.add_line(
                     &mut formatted_message,
@@ -262,23 +240,24 @@ impl<'a> Writer<'a> for HTML<'a> {
         if message.is_deleted() {
             self.add_line(
                 &mut formatted_message,
-                "This message was deleted from the conversation!",
+                &self.config.deletion_note(message),
                 "<span class=\"deleted\">",
                 "</span></p>",
             );
         }
 
-        // Useful message metadata
-        let message_parts = message.body();
-        let mut attachments = Attachment::from_message(&self.config.db, message)?;
-        let mut replies = message.get_replies(&self.config.db)?;
-
-        // Index of where we are in the attachment Vector
-        let mut attachment_index: usize = 0;
+        // If message was scheduled with Send Later, annotate it
+        if let Some(note) = self.config.schedule_note(message) {
+            self.add_line(
+                &mut formatted_message,
+                &note,
+                "<p><span class=\"scheduled\">",
+                "</span></p>",
+            );
+        }
 
         // Add message subject
         if let Some(subject) = &message.subject {
-            // Add message sender
             self.add_line(
                 &mut formatted_message,
                 &sanitize_html(subject),
@@ -308,7 +287,7 @@ impl<'a> Writer<'a> for HTML<'a> {
         }
 
         // Generate the message body from it's components
-        for (idx, message_part) in message_parts.iter().enumerate() {
+        for part in &document.parts {
             // Write the part div start
             self.add_line(
                 &mut formatted_message,
@@ -317,136 +296,9 @@ impl<'a> Writer<'a> for HTML<'a> {
                 "",
             );
 
-            match message_part {
-                BubbleComponent::Text(text_attrs) => {
-                    if let Some(text) = &message.text {
-                        // Render edited message content, if applicable
-                        if message.is_part_edited(idx) {
-                            if let Some(edited_parts) = &message.edited_parts {
-                                if let Some(edited) =
-                                    self.format_edited(message, edited_parts, idx, "")
-                                {
-                                    self.add_line(
-                                        &mut formatted_message,
-                                        &edited,
-                                        "<div class=\"edited\">",
-                                        "</div>",
-                                    );
-                                };
-                            }
-                        } else {
-                            let mut formatted_text = String::with_capacity(text.len());
-
-                            for text_attr in text_attrs {
-                                // We cannot sanitize the html beforehand because it may change the length of the text
-                                if let Some(message_content) =
-                                    text.get(text_attr.start..text_attr.end)
-                                {
-                                    formatted_text.push_str(&self.format_attributed(
-                                        &sanitize_html(message_content),
-                                        &text_attr.effect,
-                                    ))
-                                }
-                            }
-
-                            // If we failed to parse any text above, make sure we sanitize if before using it
-                            if formatted_text.is_empty() {
-                                formatted_text.push_str(&sanitize_html(text));
-                            }
-
-                            // Render the message body if the message or message part was not edited
-                            // If it was edited, it was rendered already
-                            // if match &edited_parts {
-                            //     Some(edited_parts) => edited_parts.is_unedited_at(idx),
-                            //     None => !message.is_edited(),
-                            // } {
-                            if formatted_text.starts_with(FITNESS_RECEIVER) {
-                                self.add_line(
-                                    &mut formatted_message,
-                                    &formatted_text.replace(FITNESS_RECEIVER, YOU),
-                                    "<span class=\"bubble\">",
-                                    "</span>",
-                                );
-                            } else {
-                                self.add_line(
-                                    &mut formatted_message,
-                                    &formatted_text,
-                                    "<span class=\"bubble\">",
-                                    "</span>",
-                                );
-                            }
-                        }
-                    }
-                }
-                BubbleComponent::Attachment(_) => {
-                    match attachments.get_mut(attachment_index) {
-                        Some(attachment) => {
-                            if attachment.is_sticker {
-                                let result = self.format_sticker(attachment, message);
-                                self.add_line(
-                                    &mut formatted_message,
-                                    &result,
-                                    "<div class=\"sticker\">",
-                                    "</div>",
-                                );
-                            } else {
-                                match self.format_attachment(attachment, message) {
-                                    Ok(result) => {
-                                        attachment_index += 1;
-                                        self.add_line(
-                                            &mut formatted_message,
-                                            &result,
-                                            "<div class=\"attachment\">",
-                                            "</div>",
-                                        );
-                                    }
-                                    Err(result) => {
-                                        self.add_line(
-                                        &mut formatted_message,
-                                        result,
-                                        "<span class=\"attachment_error\">Unable to locate attachment: ",
-                                        "</span>",
-                                    );
-                                    }
-                                }
-                            }
-                        }
-                        // Attachment does not exist in attachments table
-                        None => self.add_line(
-                            &mut formatted_message,
-                            "Attachment does not exist!",
-                            "<span class=\"attachment_error\">",
-                            "</span>",
-                        ),
-                    }
-                }
-                BubbleComponent::App => match self.format_app(message, &mut attachments, "") {
-                    Ok(ok_bubble) => self.add_line(
-                        &mut formatted_message,
-                        &ok_bubble,
-                        "<div class=\"app\">",
-                        "</div>",
-                    ),
-                    Err(why) => self.add_line(
-                        &mut formatted_message,
-                        &format!("Unable to format {:?} message: {why}", message.variant()),
-                        "<div class=\"app_error\">",
-                        "</div>",
-                    ),
-                },
-                BubbleComponent::Retracted => {
-                    if let Some(edited_parts) = &message.edited_parts {
-                        if let Some(edited) = self.format_edited(message, edited_parts, idx, "") {
-                            self.add_line(
-                                &mut formatted_message,
-                                &edited,
-                                "<span class=\"unsent\">",
-                                "</span>",
-                            );
-                        };
-                    }
-                }
-            };
+            if let Some(content) = &part.content {
+                self.format_content(&mut formatted_message, message, content, part.index)?;
+            }
 
             // Write the part div end
             self.add_line(&mut formatted_message, "</div>", "", "");
@@ -462,62 +314,48 @@ impl<'a> Writer<'a> for HTML<'a> {
             }
 
             // Handle Tapbacks
-            if let Some(tapbacks_map) = self.config.tapbacks.get(&message.guid) {
-                if let Some(tapbacks) = tapbacks_map.get(&idx) {
-                    let mut formatted_tapbacks = String::new();
-
-                    tapbacks
-                        .iter()
-                        .try_for_each(|tapback| -> Result<(), TableError> {
-                            let formatted = self.format_tapback(tapback)?;
-                            if !formatted.is_empty() {
-                                self.add_line(
-                                    &mut formatted_tapbacks,
-                                    &self.format_tapback(tapback)?,
-                                    "<div class=\"tapback\">",
-                                    "</div>",
-                                );
-                            }
-                            Ok(())
-                        })?;
-
-                    if !formatted_tapbacks.is_empty() {
-                        self.add_line(
-                            &mut formatted_message,
-                            "<hr><p>Tapbacks:</p>",
-                            "<div class=\"tapbacks\">",
-                            "",
-                        );
-                        self.add_line(&mut formatted_message, &formatted_tapbacks, "", "");
-                    }
-                    self.add_line(&mut formatted_message, "</div>", "", "");
+            if !part.tapbacks.is_empty() {
+                let mut formatted_tapbacks = String::new();
+                for tapback in &part.tapbacks {
+                    let formatted = self.format_tapback(tapback)?;
+                    self.add_line(
+                        &mut formatted_tapbacks,
+                        &formatted,
+                        "<div class=\"tapback\">",
+                        "</div>",
+                    );
+                }
+
+                if !formatted_tapbacks.is_empty() {
+                    self.add_line(
+                        &mut formatted_message,
+                        "<hr><p>Tapbacks:</p>",
+                        "<div class=\"tapbacks\">",
+                        "",
+                    );
+                    self.add_line(&mut formatted_message, &formatted_tapbacks, "", "");
                 }
+                self.add_line(&mut formatted_message, "</div>", "", "");
             }
 
             // Handle Replies
-            if let Some(replies) = replies.get_mut(&idx) {
+            if !part.replies.is_empty() {
                 self.add_line(&mut formatted_message, "<div class=\"replies\">", "", "");
-                replies
-                    .iter_mut()
-                    .try_for_each(|reply| -> Result<(), TableError> {
-                        let _ = reply.generate_text(&self.config.db);
-                        if !reply.is_tapback() {
-                            // Set indent to 1 so we know this is a recursive call
-                            self.add_line(
-                                &mut formatted_message,
-                                &self.format_message(reply, 1)?,
-                                &format!("<div class=\"reply\" id=\"{}\">", reply.guid),
-                                "</div>",
-                            );
-                        }
-                        Ok(())
-                    })?;
+                for reply in &part.replies {
+                    let formatted = self.format_message(reply, 1)?;
+                    self.add_line(
+                        &mut formatted_message,
+                        &formatted,
+                        &format!("<div class=\"reply\" id=\"{}\">", reply.message.guid),
+                        "</div>",
+                    );
+                }
                 self.add_line(&mut formatted_message, "</div>", "", "");
             }
         }
 
         // Add a note if the message is a reply and not rendered in a thread
-        if message.is_reply() && indent_size == 0 {
+        if message.is_reply() && !in_thread {
             self.add_line(
                 &mut formatted_message,
                 "This message responded to an earlier message.",
@@ -547,10 +385,24 @@ impl<'a> Writer<'a> for HTML<'a> {
             .handle_attachment(message, attachment, self.config)
             .ok_or(attachment.filename())?;
 
-        // Build a relative filepath from the fully qualified one on the `Attachment`
-        let embed_path = self.config.message_attachment_path(attachment);
+        let filename = sanitize_html(attachment.filename());
 
-        return Ok(match attachment.mime_type() {
+        // Build a relative filepath from the fully qualified one on the `Attachment`, or embed the file
+        let Some(embed_path) = self.config.attachment_src(attachment, message) else {
+            return Ok(format!(
+                "<p>{filename} ({}) is too large to embed</p>",
+                attachment.file_size()
+            ));
+        };
+        // Embedded files are `data:` URIs, so text that names the file uses its location instead
+        let location = sanitize_html(&self.config.message_attachment_path(attachment)).to_string();
+        // Browsers do not open `data:` URIs in a new page, so embedded files are downloaded instead
+        let download = match self.config.options.embed {
+            Some(_) => format!(" download=\"{filename}\""),
+            None => String::new(),
+        };
+
+        Ok(match attachment.mime_type() {
             MediaType::Image(_) => {
                 if self.config.options.no_lazy {
                     format!("<img src=\"{embed_path}\">")
@@ -567,23 +419,42 @@ impl<'a> Writer<'a> for HTML<'a> {
             }
             MediaType::Text(_) => {
                 format!(
-                    "<a href=\"{embed_path}\">Click to download {} ({})</a>",
-                    attachment.filename(),
+                    "<a href=\"{embed_path}\"{download}>Click to download {filename} ({})</a>",
                     attachment.file_size()
                 )
             }
             MediaType::Application(_) => format!(
-                "<a href=\"{embed_path}\">Click to download {} ({})</a>",
-                attachment.filename(),
+                "<a href=\"{embed_path}\"{download}>Click to download {filename} ({})</a>",
                 attachment.file_size()
             ),
             MediaType::Unknown => {
-                format!("<p>Unknown attachment type: {embed_path}</p> <a href=\"{embed_path}\">Download ({})</a>", attachment.file_size())
+                format!("<p>Unknown attachment type: {location}</p> <a href=\"{embed_path}\"{download}>Download ({})</a>", attachment.file_size())
             }
             MediaType::Other(media_type) => {
-                format!("<p>Unable to embed {media_type} attachments: {embed_path}</p>")
+                format!("<p>Unable to embed {media_type} attachments: {location}</p>")
             }
-        });
+        })
+    }
+
+    fn format_genmoji(&self, genmoji: &mut Attachment, message: &Message) -> String {
+        let description = sanitize_html(genmoji.genmoji_description()).to_string();
+
+        // Copy the file, if requested
+        if self
+            .config
+            .options
+            .attachment_manager
+            .handle_attachment(message, genmoji, self.config)
+            .is_none()
+        {
+            return description;
+        }
+
+        // Genmoji are sized to match the surrounding text
+        let Some(embed_path) = self.config.attachment_src(genmoji, message) else {
+            return description;
+        };
+        format!("<img class=\"genmoji\" src=\"{embed_path}\" alt=\"{description}\" title=\"{description}\">")
     }
 
     fn format_sticker(&self, sticker: &'a mut Attachment, message: &Message) -> String {
@@ -603,97 +474,6 @@ impl<'a> Writer<'a> for HTML<'a> {
         }
     }
 
-    fn format_app(
-        &self,
-        message: &'a Message,
-        attachments: &mut Vec<Attachment>,
-        _: &str,
-    ) -> Result<String, PlistParseError> {
-        if let Variant::App(balloon) = message.variant() {
-            let mut app_bubble = String::new();
-
-            // Handwritten messages use a different payload type, so check that first
-            if message.is_handwriting() {
-                if let Some(payload) = message.raw_payload_data(&self.config.db) {
-                    return match HandwrittenMessage::from_payload(&payload) {
-                        Ok(bubble) => Ok(self.format_handwriting(message, &bubble, message)),
-                        Err(why) => Err(PlistParseError::HandwritingError(why)),
-                    };
-                }
-            }
-
-            if message.is_digital_touch() {
-                if let Some(payload) = message.raw_payload_data(&self.config.db) {
-                    return match digital_touch::from_payload(&payload) {
-                        Some(bubble) => Ok(self.format_digital_touch(message, &bubble, message)),
-                        None => Err(PlistParseError::DigitalTouchError),
-                    };
-                }
-            }
-
-            if let Some(payload) = message.payload_data(&self.config.db) {
-                let res = if message.is_url() {
-                    let parsed = parse_plist(&payload)?;
-                    let bubble = URLMessage::get_url_message_override(&parsed)?;
-                    match bubble {
-                        URLOverride::Normal(balloon) => self.format_url(message, &balloon, message),
-                        URLOverride::AppleMusic(balloon) => self.format_music(&balloon, message),
-                        URLOverride::Collaboration(balloon) => {
-                            self.format_collaboration(&balloon, message)
-                        }
-                        URLOverride::AppStore(balloon) => self.format_app_store(&balloon, message),
-                        URLOverride::SharedPlacemark(balloon) => {
-                            self.format_placemark(&balloon, message)
-                        }
-                    }
-                } else {
-                    let parsed = parse_plist(&payload)?;
-                    match AppMessage::from_map(&parsed) {
-                        Ok(bubble) => match balloon {
-                            CustomBalloon::Application(bundle_id) => {
-                                self.format_generic_app(&bubble, bundle_id, attachments, message)
-                            }
-                            CustomBalloon::ApplePay => self.format_apple_pay(&bubble, message),
-                            CustomBalloon::Fitness => self.format_fitness(&bubble, message),
-                            CustomBalloon::Slideshow => self.format_slideshow(&bubble, message),
-                            CustomBalloon::CheckIn => self.format_check_in(&bubble, message),
-                            CustomBalloon::FindMy => self.format_find_my(&bubble, message),
-                            CustomBalloon::Handwriting => unreachable!(),
-                            CustomBalloon::DigitalTouch => unreachable!(),
-                            CustomBalloon::URL => unreachable!(),
-                        },
-                        Err(why) => return Err(why),
-                    }
-                };
-                app_bubble.push_str(&res);
-            } else {
-                // Sometimes, URL messages are missing their payloads
-                if message.is_url() {
-                    if let Some(text) = &message.text {
-                        let mut out_s = String::new();
-                        out_s.push_str("<a href=\"");
-                        out_s.push_str(text);
-                        out_s.push_str("\">");
-
-                        out_s.push_str("<div class=\"app_header\"><div class=\"name\">");
-                        out_s.push_str(text);
-                        out_s.push_str("</div></div>");
-
-                        out_s.push_str("<div class=\"app_footer\"><div class=\"caption\">");
-                        out_s.push_str(text);
-                        out_s.push_str("</div></div></a>");
-
-                        return Ok(out_s);
-                    }
-                }
-                return Err(PlistParseError::NoPayload);
-            }
-            Ok(app_bubble)
-        } else {
-            Err(PlistParseError::WrongMessageType)
-        }
-    }
-
     fn format_tapback(&self, msg: &Message) -> Result<String, TableError> {
         match msg.variant() {
             Variant::Tapback(_, added, tapback) => {
@@ -761,7 +541,7 @@ impl<'a> Writer<'a> for HTML<'a> {
         }
         let timestamp = format(&msg.date(&self.config.offset));
 
-        return match msg.get_announcement() {
+        match msg.get_announcement() {
             Some(announcement) => match announcement {
                 Announcement::NameChange(name) => {
                     let clean_name = sanitize_html(name);
@@ -770,8 +550,13 @@ impl<'a> Writer<'a> for HTML<'a> {
                     )
                 }
                 Announcement::PhotoChange => {
+                    let photo = self
+                        .config
+                        .group_photo(msg)
+                        .map(|path| HTML::format_avatar(self.config, &path))
+                        .unwrap_or_default();
                     format!(
-                        "\n<div class =\"announcement\"><p><span class=\"timestamp\">{timestamp}</span> {who} changed the group photo.</p></div>\n"
+                        "\n<div class =\"announcement\"><p><span class=\"timestamp\">{timestamp}</span> {who} changed the group photo.</p>{photo}</div>\n"
                     )
                 }
                 Announcement::Unknown(num) => {
@@ -784,11 +569,28 @@ impl<'a> Writer<'a> for HTML<'a> {
                         "<div class =\"announcement\"><p><span class=\"timestamp\">{timestamp}</span> {who} unsent a message.</p></div>"
                     )
                 }
+                Announcement::ParticipantAdded(id) => {
+                    let subject = self.config.announcement_subject(*id);
+                    format!(
+                        "\n<div class =\"announcement\"><p><span class=\"timestamp\">{timestamp}</span> {who} added {subject} to the conversation.</p></div>\n"
+                    )
+                }
+                Announcement::ParticipantRemoved(id) => {
+                    let subject = self.config.announcement_subject(*id);
+                    format!(
+                        "\n<div class =\"announcement\"><p><span class=\"timestamp\">{timestamp}</span> {who} removed {subject} from the conversation.</p></div>\n"
+                    )
+                }
+                Announcement::ParticipantLeft => {
+                    format!(
+                        "\n<div class =\"announcement\"><p><span class=\"timestamp\">{timestamp}</span> {who} left the conversation.</p></div>\n"
+                    )
+                }
             },
             None => String::from(
                 "\n<div class =\"announcement\"><p>Unable to format announcement!</p></div>\n",
             ),
-        };
+        }
     }
 
     fn format_shareplay(&self) -> &str {
@@ -819,10 +621,15 @@ impl<'a> Writer<'a> for HTML<'a> {
             match edited_message_part.status {
                 EditStatus::Edited => {
                     out_s.push_str("<table>");
+                    let diffs = edited_message_part.diffs();
 
                     for (idx, event) in edited_message_part.edit_history.iter().enumerate() {
                         let last = idx == edited_message_part.edit_history.len() - 1;
-                        let clean_text = sanitize_html(&event.text);
+                        // Edits show what changed from the version before them
+                        let clean_text = match idx.checked_sub(1).and_then(|prev| diffs.get(prev)) {
+                            Some(diff) => self.diff_to_html(&diff.spans),
+                            None => sanitize_html(&event.text).into_owned(),
+                        };
                         match previous_timestamp {
                             None => out_s.push_str(&self.edited_to_html("", &clean_text, last)),
                             Some(prev_timestamp) => {
@@ -877,15 +684,30 @@ impl<'a> Writer<'a> for HTML<'a> {
         None
     }
 
-    fn format_attributed(&'a self, text: &'a str, attribute: &'a TextEffect) -> Cow<'a, str> {
+    fn format_attributed(&'a self, text: &'a str, attribute: &'a OwnedTextEffect) -> Cow<'a, str> {
         match attribute {
-            TextEffect::Default => Cow::Borrowed(text),
-            TextEffect::Mention(mentioned) => Cow::Owned(self.format_mention(text, mentioned)),
-            TextEffect::Link(url) => Cow::Owned(self.format_link(text, url)),
-            TextEffect::OTP => Cow::Owned(self.format_otp(text)),
-            TextEffect::Styles(styles) => Cow::Owned(self.format_styles(text, styles)),
-            TextEffect::Animated(animation) => Cow::Owned(self.format_animated(text, animation)),
-            TextEffect::Conversion(unit) => Cow::Owned(self.format_conversion(text, unit)),
+            OwnedTextEffect::Default => Cow::Borrowed(text),
+            OwnedTextEffect::Mention(mentioned) => Cow::Owned(self.format_mention(text, mentioned)),
+            OwnedTextEffect::Link(url) => Cow::Owned(self.format_link(text, url)),
+            OwnedTextEffect::OTP => Cow::Owned(self.format_otp(text)),
+            OwnedTextEffect::Styles(styles) => Cow::Owned(self.format_styles(text, styles)),
+            OwnedTextEffect::Animated(animation) => {
+                Cow::Owned(self.format_animated(text, animation))
+            }
+            OwnedTextEffect::Conversion(unit) => Cow::Owned(self.format_conversion(text, unit)),
+            // Genmoji with an image are rendered by `format_genmoji()`
+            OwnedTextEffect::Genmoji(_) => Cow::Borrowed(text),
+            OwnedTextEffect::Address(address) => Cow::Owned(self.format_address(text, address)),
+            OwnedTextEffect::Event(event) => Cow::Owned(self.format_event(text, event)),
+            OwnedTextEffect::PhoneNumber(number) => {
+                Cow::Owned(self.format_phone_number(text, number))
+            }
+            OwnedTextEffect::FlightNumber(flight) => {
+                Cow::Owned(self.format_flight_number(text, flight))
+            }
+            OwnedTextEffect::TrackingNumber(tracking) => {
+                Cow::Owned(self.format_tracking_number(text, tracking))
+            }
         }
     }
 
@@ -895,8 +717,67 @@ impl<'a> Writer<'a> for HTML<'a> {
     }
 }
 
+impl Render for HTML<'_> {
+    fn start(&mut self, _: &Connection) -> Result<(), ExportError> {
+        // Tell the user what we are doing
+        eprintln!(
+            "Exporting to {} as html...",
+            self.config.options.export_path.display()
+        );
+
+        // Write orphaned file headers
+        HTML::write_headers(&mut self.orphaned)
+            .map_err(|why| ExportError::Format(why.to_string()))?;
+
+        // Set up progress bar
+        let total_messages =
+            Message::get_count(&self.config.db, &self.config.options.query_context)?;
+        self.pb = build_progress_bar_export(total_messages);
+        Ok(())
+    }
+
+    fn render(&mut self, item: Item) -> Result<(), ExportError> {
+        let message = item.message();
+        self.config.log_edits(message);
+        self.config.link_from(message);
+
+        let rendered = match &item {
+            // Message replies and tapbacks are rendered in context, so no need to render them separately
+            Item::Message(document) => self.format_message(document, 0)?,
+            // Render the announcement in-line
+            Item::Announcement(message) => self.format_announcement(message),
+        };
+        let file = self
+            .get_or_create_file(message)
+            .map_err(|why| ExportError::Format(why.to_string()))?;
+        file.write_all(rendered.as_bytes())?;
+
+        self.current_message += 1;
+        if self.current_message.is_multiple_of(99) {
+            self.pb.set_position(self.current_message);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), ExportError> {
+        self.pb.finish();
+
+        eprintln!("Writing HTML footers...");
+        for (filename, buf) in self.files.iter_mut() {
+            if let Some(navigation) = HTML::format_navigation(&self.pages, filename) {
+                buf.write_all(navigation.as_bytes())?;
+            }
+            buf.write_all(FOOTER.as_bytes())?;
+        }
+        self.orphaned.write_all(FOOTER.as_bytes())?;
+
+        self.write_index()
+            .map_err(|why| ExportError::Format(why.to_string()))
+    }
+}
+
 impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
-    fn format_url(&self, msg: &Message, balloon: &URLMessage, _: &Message) -> String {
+    fn format_url(&self, msg: &Message, balloon: &OwnedURLMessage, _: &Message) -> String {
         let mut out_s = String::new();
 
         // Make the whole bubble clickable
@@ -928,7 +809,7 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
             }
         });
 
-        if let Some(site_name) = balloon.site_name {
+        if let Some(site_name) = balloon.site_name.as_deref() {
             out_s.push_str("<div class=\"name\">");
             out_s.push_str(site_name);
             out_s.push_str("</div>");
@@ -951,14 +832,14 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
             out_s.push_str("<div class=\"app_footer\">");
 
             // Title
-            if let Some(title) = balloon.title {
+            if let Some(title) = balloon.title.as_deref() {
                 out_s.push_str("<div class=\"caption\">");
                 out_s.push_str(&sanitize_html(title));
                 out_s.push_str("</div>");
             }
 
             // Subtitle
-            if let Some(summary) = balloon.summary {
+            if let Some(summary) = balloon.summary.as_deref() {
                 out_s.push_str("<div class=\"subcaption\">");
                 out_s.push_str(&sanitize_html(summary));
                 out_s.push_str("</div>");
@@ -975,21 +856,21 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s
     }
 
-    fn format_music(&self, balloon: &MusicMessage, _: &Message) -> String {
+    fn format_music(&self, balloon: &OwnedMusicMessage, _: &Message) -> String {
         let mut out_s = String::new();
 
         // Header section
         out_s.push_str("<div class=\"app_header\">");
 
         // Name
-        if let Some(track_name) = balloon.track_name {
+        if let Some(track_name) = balloon.track_name.as_deref() {
             out_s.push_str("<div class=\"name\">");
             out_s.push_str(track_name);
             out_s.push_str("</div>");
         }
 
         // Add preview section
-        if let Some(preview) = balloon.preview {
+        if let Some(preview) = balloon.preview.as_deref() {
             out_s.push_str("<audio controls src=\"");
             out_s.push_str(preview);
             out_s.push_str("\" </audio>");
@@ -999,7 +880,7 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s.push_str("</div>");
 
         // Make the footer clickable so we can interact with the preview
-        if let Some(url) = balloon.url {
+        if let Some(url) = balloon.url.as_deref() {
             out_s.push_str("<a href=\"");
             out_s.push_str(url);
             out_s.push_str("\">");
@@ -1010,14 +891,14 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
             out_s.push_str("<div class=\"app_footer\">");
 
             // artist
-            if let Some(artist) = balloon.artist {
+            if let Some(artist) = balloon.artist.as_deref() {
                 out_s.push_str("<div class=\"caption\">");
                 out_s.push_str(artist);
                 out_s.push_str("</div>");
             }
 
             // Subtitle
-            if let Some(album) = balloon.album {
+            if let Some(album) = balloon.album.as_deref() {
                 out_s.push_str("<div class=\"subcaption\">");
                 out_s.push_str(album);
                 out_s.push_str("</div>");
@@ -1034,18 +915,18 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s
     }
 
-    fn format_collaboration(&self, balloon: &CollaborationMessage, _: &Message) -> String {
+    fn format_collaboration(&self, balloon: &OwnedCollaborationMessage, _: &Message) -> String {
         let mut out_s = String::new();
 
         // Header section
         out_s.push_str("<div class=\"app_header\">");
 
         // Name
-        if let Some(app_name) = balloon.app_name {
+        if let Some(app_name) = balloon.app_name.as_deref() {
             out_s.push_str("<div class=\"name\">");
             out_s.push_str(app_name);
             out_s.push_str("</div>");
-        } else if let Some(bundle_id) = balloon.bundle_id {
+        } else if let Some(bundle_id) = balloon.bundle_id.as_deref() {
             out_s.push_str("<div class=\"name\">");
             out_s.push_str(bundle_id);
             out_s.push_str("</div>");
@@ -1055,7 +936,7 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s.push_str("</div>");
 
         // Make the footer clickable so we can interact with the preview
-        if let Some(url) = balloon.url {
+        if let Some(url) = balloon.url.as_deref() {
             out_s.push_str("<a href=\"");
             out_s.push_str(url);
             out_s.push_str("\">");
@@ -1066,7 +947,7 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
             out_s.push_str("<div class=\"app_footer\">");
 
             // artist
-            if let Some(title) = balloon.title {
+            if let Some(title) = balloon.title.as_deref() {
                 out_s.push_str("<div class=\"caption\">");
                 out_s.push_str(title);
                 out_s.push_str("</div>");
@@ -1091,14 +972,14 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s
     }
 
-    fn format_app_store(&self, balloon: &AppStoreMessage, _: &'a Message) -> String {
+    fn format_app_store(&self, balloon: &OwnedAppStoreMessage, _: &'a Message) -> String {
         let mut out_s = String::new();
 
         // Header section
         out_s.push_str("<div class=\"app_header\">");
 
         // App name
-        if let Some(app_name) = balloon.app_name {
+        if let Some(app_name) = balloon.app_name.as_deref() {
             out_s.push_str("<div class=\"name\">");
             out_s.push_str(app_name);
             out_s.push_str("</div>");
@@ -1108,7 +989,7 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s.push_str("</div>");
 
         // Make the footer clickable so we can interact with the preview
-        if let Some(url) = balloon.url {
+        if let Some(url) = balloon.url.as_deref() {
             out_s.push_str("<a href=\"");
             out_s.push_str(url);
             out_s.push_str("\">");
@@ -1119,21 +1000,21 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
             out_s.push_str("<div class=\"app_footer\">");
 
             // App description
-            if let Some(description) = balloon.description {
+            if let Some(description) = balloon.description.as_deref() {
                 out_s.push_str("<div class=\"caption\">");
                 out_s.push_str(description);
                 out_s.push_str("</div>");
             }
 
             // App platform
-            if let Some(platform) = balloon.platform {
+            if let Some(platform) = balloon.platform.as_deref() {
                 out_s.push_str("<div class=\"subcaption\">");
                 out_s.push_str(platform);
                 out_s.push_str("</div>");
             }
 
             // App genre
-            if let Some(genre) = balloon.genre {
+            if let Some(genre) = balloon.genre.as_deref() {
                 out_s.push_str("<div class=\"trailing_subcaption\">");
                 out_s.push_str(genre);
                 out_s.push_str("</div>");
@@ -1150,7 +1031,7 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s
     }
 
-    fn format_placemark(&self, balloon: &PlacemarkMessage, _: &'a Message) -> String {
+    fn format_placemark(&self, balloon: &OwnedPlacemarkMessage, _: &'a Message) -> String {
         let mut out_s = String::new();
 
         // Make the whole bubble clickable
@@ -1163,7 +1044,7 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         // Header section
         out_s.push_str("<div class=\"app_header\">");
 
-        if let Some(place_name) = balloon.place_name {
+        if let Some(place_name) = balloon.place_name.as_deref() {
             out_s.push_str("<div class=\"name\">");
             out_s.push_str(place_name);
             out_s.push_str("</div>");
@@ -1185,28 +1066,28 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
             out_s.push_str("<div class=\"app_footer\">");
 
             // Address
-            if let Some(address) = balloon.placemark.address {
+            if let Some(address) = balloon.placemark.address.as_deref() {
                 out_s.push_str("<div class=\"caption\">");
                 out_s.push_str(address);
                 out_s.push_str("</div>");
             }
 
             // Postal Code
-            if let Some(postal_code) = balloon.placemark.postal_code {
+            if let Some(postal_code) = balloon.placemark.postal_code.as_deref() {
                 out_s.push_str("<div class=\"trailing_caption\">");
                 out_s.push_str(postal_code);
                 out_s.push_str("</div>");
             }
 
             // Country
-            if let Some(country) = balloon.placemark.country {
+            if let Some(country) = balloon.placemark.country.as_deref() {
                 out_s.push_str("<div class=\"subcaption\">");
                 out_s.push_str(country);
                 out_s.push_str("</div>");
             }
 
             // Administrative Area
-            if let Some(area) = balloon.placemark.sub_administrative_area {
+            if let Some(area) = balloon.placemark.sub_administrative_area.as_deref() {
                 out_s.push_str("<div class=\"trailing_subcaption\">");
                 out_s.push_str(area);
                 out_s.push_str("</div>");
@@ -1223,24 +1104,67 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s
     }
 
-    fn format_handwriting(&self, _: &Message, balloon: &HandwrittenMessage, _: &Message) -> String {
-        // svg can be embedded directly into the html
-        balloon.render_svg()
+    fn format_handwriting(
+        &self,
+        msg: &Message,
+        balloon: &HandwrittenMessage,
+        _: &Message,
+    ) -> String {
+        match self.config.options.handwriting_format {
+            // svg can be embedded directly into the html
+            HandwritingFormat::Svg => balloon.render_svg(),
+            HandwritingFormat::Animated => balloon.render_svg_animated(),
+            // png images are linked if they were written to the export directory
+            HandwritingFormat::Png => self
+                .config
+                .options
+                .attachment_manager
+                .handle_handwriting(msg, balloon, self.config)
+                .and_then(|filepath| self.config.file_src(filepath, "image/png", msg))
+                .map(|filepath| {
+                    if self.config.options.no_lazy {
+                        format!("<img src=\"{filepath}\">")
+                    } else {
+                        format!("<img src=\"{filepath}\" loading=\"lazy\">")
+                    }
+                })
+                .unwrap_or_else(|| balloon.render_svg()),
+        }
     }
 
     fn format_digital_touch(&self, _: &Message, balloon: &DigitalTouch, _: &'a Message) -> String {
-        format!(
-            "<div class=\"app_header\"><div class=\"name\">Digital Touch Message</div></div>\n<div class=\"app_footer\"><div class=\"caption\">{:?}</div></div>",
-            balloon
-        )
+        let mut out_s = String::new();
+        out_s.push_str("<div class=\"app_header\">");
+
+        // svg can be embedded directly into the html
+        if let Some(svg) = balloon.render_svg() {
+            out_s.push_str("<div class=\"digital_touch\">");
+            out_s.push_str(&svg);
+            out_s.push_str("</div>");
+        }
+
+        out_s.push_str("<div class=\"name\">Digital Touch Message</div>");
+
+        // Header end, footer begin
+        out_s.push_str("</div>");
+        out_s.push_str("<div class=\"app_footer\">");
+
+        out_s.push_str("<div class=\"caption\">");
+        out_s.push_str(&balloon.describe());
+        out_s.push_str("</div>");
+
+        // End footer
+        out_s.push_str("</div>");
+
+        out_s
     }
 
-    fn format_apple_pay(&self, balloon: &AppMessage, _: &Message) -> String {
+    fn format_apple_pay(&self, balloon: &OwnedAppMessage, _: &Message) -> String {
         let mut out_s = String::new();
 
         out_s.push_str("<div class=\"app_header\">");
 
-        if let Some(app_name) = balloon.app_name {
+        if let Some(app_name) = balloon.app_name.as_deref() {
             out_s.push_str("<div class=\"name\">");
             out_s.push_str(app_name);
             out_s.push_str("</div>");
@@ -1250,7 +1174,7 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s.push_str("</div>");
         out_s.push_str("<div class=\"app_footer\">");
 
-        if let Some(ldtext) = balloon.ldtext {
+        if let Some(ldtext) = balloon.ldtext.as_deref() {
             out_s.push_str("<div class=\"caption\">");
             out_s.push_str(ldtext);
             out_s.push_str("</div>");
@@ -1262,20 +1186,20 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s
     }
 
-    fn format_fitness(&self, balloon: &AppMessage, message: &Message) -> String {
+    fn format_fitness(&self, balloon: &OwnedAppMessage, message: &Message) -> String {
         self.balloon_to_html(balloon, "Fitness", &mut [], message)
     }
 
-    fn format_slideshow(&self, balloon: &AppMessage, message: &Message) -> String {
+    fn format_slideshow(&self, balloon: &OwnedAppMessage, message: &Message) -> String {
         self.balloon_to_html(balloon, "Slideshow", &mut [], message)
     }
 
-    fn format_find_my(&self, balloon: &AppMessage, _: &'a Message) -> String {
+    fn format_find_my(&self, balloon: &OwnedAppMessage, _: &'a Message) -> String {
         let mut out_s = String::new();
 
         out_s.push_str("<div class=\"app_header\">");
 
-        if let Some(app_name) = balloon.app_name {
+        if let Some(app_name) = balloon.app_name.as_deref() {
             out_s.push_str("<div class=\"name\">");
             out_s.push_str(app_name);
             out_s.push_str("</div>");
@@ -1285,7 +1209,7 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s.push_str("</div>");
         out_s.push_str("<div class=\"app_footer\">");
 
-        if let Some(ldtext) = balloon.ldtext {
+        if let Some(ldtext) = balloon.ldtext.as_deref() {
             out_s.push_str("<div class=\"caption\">");
             out_s.push_str(ldtext);
             out_s.push_str("</div>");
@@ -1297,18 +1221,18 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
         out_s
     }
 
-    fn format_check_in(&self, balloon: &AppMessage, _: &Message) -> String {
+    fn format_check_in(&self, balloon: &OwnedAppMessage, _: &Message) -> String {
         let mut out_s = String::new();
 
         out_s.push_str("<div class=\"app_header\">");
 
         // Name
         out_s.push_str("<div class=\"name\">");
-        out_s.push_str(balloon.app_name.unwrap_or("Check In"));
+        out_s.push_str(balloon.app_name.as_deref().unwrap_or("Check In"));
         out_s.push_str("</div>");
 
         // ldtext
-        if let Some(ldtext) = balloon.ldtext {
+        if let Some(ldtext) = balloon.ldtext.as_deref() {
             out_s.push_str("<div class=\"ldtext\">");
             out_s.push_str(ldtext);
             out_s.push_str("</div>");
@@ -1371,7 +1295,7 @@ impl<'a> BalloonFormatter<&'a Message> for HTML<'a> {
 
     fn format_generic_app(
         &self,
-        balloon: &AppMessage,
+        balloon: &OwnedAppMessage,
         bundle_id: &str,
         attachments: &mut Vec<Attachment>,
         message: &Message,
@@ -1419,9 +1343,229 @@ impl<'a> TextEffectFormatter for HTML<'a> {
     fn format_animated(&self, text: &str, animation: &Animation) -> String {
         format!("<span class=\"animation{animation:?}\">{text}</span>")
     }
+
+    fn format_address(&self, text: &str, address: &str) -> String {
+        format!(
+            "<a href=\"geo:0,0?q={}\">{text}</a>",
+            encode_uri_component(address)
+        )
+    }
+
+    fn format_event(&self, text: &str, event: &str) -> String {
+        format!(
+            "<a href=\"{}\" download=\"event.ics\">{text}</a>",
+            encode_calendar_event(event)
+        )
+    }
+
+    fn format_phone_number(&self, text: &str, number: &str) -> String {
+        let number: String = number
+            .chars()
+            .filter(|c| c.is_ascii_digit() || *c == '+')
+            .collect();
+        format!("<a href=\"tel:{number}\">{text}</a>")
+    }
+
+    fn format_flight_number(&self, text: &str, _: &str) -> String {
+        format!("<u title=\"Flight number\">{text}</u>")
+    }
+
+    fn format_tracking_number(&self, text: &str, _: &str) -> String {
+        format!("<u title=\"Tracking number\">{text}</u>")
+    }
 }
 
 impl<'a> HTML<'a> {
+    /// Render the contents of a single part of a message body
+    fn format_content(
+        &self,
+        formatted_message: &mut String,
+        message: &Message,
+        content: &Content,
+        idx: usize,
+    ) -> Result<(), ExportError> {
+        match content {
+            Content::Text {
+                text,
+                attributes,
+                genmoji,
+            } => {
+                let formatted_text = self.format_text(message, text, attributes, genmoji);
+                self.add_line(
+                    formatted_message,
+                    &formatted_text,
+                    "<span class=\"bubble\">",
+                    "</span>",
+                );
+            }
+            Content::Edited(_) => {
+                let Some(edited_parts) = &message.edited_parts else {
+                    return Ok(());
+                };
+                if let Some(edited) = self.format_edited(message, edited_parts, idx, "") {
+                    // Unsent parts are retracted from the message body
+                    match message.body().get(idx) {
+                        Some(BubbleComponent::Retracted) => self.add_line(
+                            formatted_message,
+                            &edited,
+                            "<span class=\"unsent\">",
+                            "</span>",
+                        ),
+                        _ => self.add_line(
+                            formatted_message,
+                            &edited,
+                            "<div class=\"edited\">",
+                            "</div>",
+                        ),
+                    }
+                }
+            }
+            Content::Attachment(attachment) => {
+                let mut attachment = attachment.clone();
+                if attachment.is_sticker {
+                    let result = self.format_sticker(&mut attachment, message);
+                    self.add_line(
+                        formatted_message,
+                        &result,
+                        "<div class=\"sticker\">",
+                        "</div>",
+                    );
+                    return Ok(());
+                }
+                let is_audio = matches!(attachment.mime_type(), MediaType::Audio(_));
+                match self.format_attachment(&mut attachment, message) {
+                    Ok(result) => self.add_line(
+                        formatted_message,
+                        &result,
+                        "<div class=\"attachment\">",
+                        "</div>",
+                    ),
+                    Err(result) => self.add_line(
+                        formatted_message,
+                        result,
+                        "<span class=\"attachment_error\">Unable to locate attachment: ",
+                        "</span>",
+                    ),
+                }
+                // Voice messages can include a transcription of the audio
+                if let (true, Some(transcription)) = (is_audio, message.audio_transcription()) {
+                    self.add_line(
+                        formatted_message,
+                        &sanitize_html(transcription),
+                        "<div class=\"transcription\">",
+                        "</div>",
+                    );
+                }
+            }
+            Content::MissingAttachment(idx) => {
+                self.config.warn(
+                    message,
+                    WarningKind::MissingAttachment,
+                    format!("Attachment {idx} does not exist!"),
+                );
+                self.add_line(
+                    formatted_message,
+                    "Attachment does not exist!",
+                    "<span class=\"attachment_error\">",
+                    "</span>",
+                );
+            }
+            Content::Balloon(balloon) => {
+                // Apps without their own image use the first attachment instead
+                let mut attachments: Vec<Attachment> =
+                    Attachment::from_message(&self.config.db, message)?
+                        .into_iter()
+                        .filter(|attachment| !attachment.is_genmoji())
+                        .collect();
+                let formatted = self.format_balloon(message, balloon, &mut attachments, message);
+                self.add_line(
+                    formatted_message,
+                    &formatted,
+                    "<div class=\"app\">",
+                    "</div>",
+                );
+            }
+            Content::MissingPayload => match &message.text {
+                // Sometimes, URL messages are missing their payloads
+                Some(text) if message.is_url() => {
+                    let mut out_s = String::new();
+                    out_s.push_str("<a href=\"");
+                    out_s.push_str(text);
+                    out_s.push_str("\">");
+
+                    out_s.push_str("<div class=\"app_header\"><div class=\"name\">");
+                    out_s.push_str(text);
+                    out_s.push_str("</div></div>");
+
+                    out_s.push_str("<div class=\"app_footer\"><div class=\"caption\">");
+                    out_s.push_str(text);
+                    out_s.push_str("</div></div></a>");
+
+                    self.add_line(formatted_message, &out_s, "<div class=\"app\">", "</div>");
+                }
+                _ => self.format_balloon_error(
+                    formatted_message,
+                    message,
+                    &PlistParseError::NoPayload.to_string(),
+                ),
+            },
+            Content::BalloonError(why) => {
+                self.format_balloon_error(formatted_message, message, why);
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the text of a message part, with its Genmoji shown in place
+    fn format_text(
+        &self,
+        message: &Message,
+        text: &str,
+        attributes: &[OwnedTextAttributes],
+        genmoji: &[Attachment],
+    ) -> String {
+        let mut genmoji = genmoji.iter();
+        let mut formatted_text = String::with_capacity(text.len());
+
+        for attribute in attributes {
+            if let OwnedTextEffect::Genmoji(_) = attribute.effect {
+                if let Some(image) = genmoji.next() {
+                    formatted_text.push_str(&self.format_genmoji(&mut image.clone(), message));
+                    continue;
+                }
+            }
+            if let Some(message_content) = text.get(attribute.start..attribute.end) {
+                // We cannot sanitize the html beforehand because it may change the length of the text
+                formatted_text.push_str(
+                    &self.format_attributed(&sanitize_html(message_content), &attribute.effect),
+                );
+            }
+        }
+
+        // If we failed to parse any text above, make sure we sanitize if before using it
+        if formatted_text.is_empty() {
+            formatted_text.push_str(&sanitize_html(text));
+        }
+
+        // Fitness messages have a prefix that we need to replace with the opposite if who sent the message
+        if formatted_text.starts_with(FITNESS_RECEIVER) {
+            return formatted_text.replace(FITNESS_RECEIVER, YOU);
+        }
+        formatted_text
+    }
+
+    /// Render an app balloon that could not be parsed, and note it in the export report
+    fn format_balloon_error(&self, formatted_message: &mut String, message: &Message, why: &str) {
+        let why = format!("Unable to format {:?} message: {why}", message.variant());
+        self.config.warn(message, WarningKind::Balloon, why.clone());
+        self.add_line(
+            formatted_message,
+            &why,
+            "<div class=\"app_error\">",
+            "</div>",
+        );
+    }
+
     fn get_time(&self, message: &Message) -> String {
         let mut date = format(&message.date(&self.config.offset));
         let read_after = message.time_until_read(&self.config.offset);
@@ -1459,6 +1603,111 @@ impl<'a> HTML<'a> {
         Ok(())
     }
 
+    /// Render a group photo as a round avatar
+    fn format_avatar(config: &Config, path: &str) -> String {
+        if config.options.no_lazy {
+            format!("<img class=\"avatar\" src=\"{path}\">")
+        } else {
+            format!("<img class=\"avatar\" src=\"{path}\" loading=\"lazy\">")
+        }
+    }
+
+    /// Build the header shown at the top of a conversation, if the conversation has a group photo
+    fn format_chat_header(config: &Config, chatroom: &Chat) -> Option<String> {
+        let photo = config
+            .current_group_photo(chatroom.rowid)
+            .and_then(|change| config.group_photo(&change))?;
+        let name = chatroom
+            .display_name()
+            .map(|name| format!("<p><b>{}</b></p>", sanitize_html(name)))
+            .unwrap_or_default();
+        Some(format!(
+            "<div class=\"chat_header\">{}{name}</div>\n",
+            HTML::format_avatar(config, &photo)
+        ))
+    }
+
+    /// Write a page that links to every exported conversation, showing each conversation's current group photo
+    fn write_index(&self) -> Result<(), RuntimeError> {
+        let path = self.config.options.export_path.join(INDEX_FILE);
+        let file = File::create(&path).map_err(|err| RuntimeError::CreateError(err, path))?;
+        let mut buf = BufWriter::new(file);
+
+        HTML::write_headers(&mut buf)?;
+        HTML::write_to_file(
+            &mut buf,
+            &HTML::format_index(self.config, &self.conversations),
+        )?;
+        HTML::write_to_file(&mut buf, FOOTER)
+    }
+
+    /// List every exported conversation, linking to the file it was written to
+    pub fn format_index(config: &Config, conversations: &BTreeMap<String, i32>) -> String {
+        // The index is in the export directory, so links are relative to it
+        config.link_depth.set(0);
+
+        let mut index = String::from("<div class=\"index\">\n<h1>Conversations</h1>\n<ul>\n");
+        for (filename, chat_id) in conversations {
+            let photo = config
+                .current_group_photo(*chat_id)
+                .and_then(|change| config.group_photo(&change))
+                .map(|path| HTML::format_avatar(config, &path))
+                .unwrap_or_default();
+            let name = sanitize_html(filename.strip_suffix(".html").unwrap_or(filename));
+            index.push_str(&format!(
+                "<li>{photo}<a href=\"{}\">{name}</a></li>\n",
+                encode_uri_path(filename)
+            ));
+        }
+        index.push_str("</ul>\n</div>\n");
+        index
+    }
+
+    /// Link a file of a split conversation to the files before and after it
+    fn format_navigation(pages: &SplitPages, filename: &str) -> Option<String> {
+        let (previous, next) = pages.adjacent(filename);
+        if previous.is_none() && next.is_none() {
+            return None;
+        }
+
+        let mut navigation = String::from("<div class=\"pages\">");
+        if let Some(previous) = previous {
+            navigation.push_str(&format!(
+                "<a class=\"previous\" href=\"{}\">&larr; {}</a>",
+                previous.href,
+                sanitize_html(&previous.period)
+            ));
+        }
+        if let Some(next) = next {
+            navigation.push_str(&format!(
+                "<a class=\"next\" href=\"{}\">{} &rarr;</a>",
+                next.href,
+                sanitize_html(&next.period)
+            ));
+        }
+        navigation.push_str("</div>\n");
+        Some(navigation)
+    }
+
+    /// Render the changes between two versions of a message part, striking out deleted text and underlining inserted text
+    fn diff_to_html(&self, spans: &[DiffSpan]) -> String {
+        let mut out_s = String::new();
+        spans.iter().for_each(|span| match span {
+            DiffSpan::Unchanged(text) => out_s.push_str(&sanitize_html(text)),
+            DiffSpan::Inserted(text) => {
+                out_s.push_str("<ins>");
+                out_s.push_str(&sanitize_html(text));
+                out_s.push_str("</ins>");
+            }
+            DiffSpan::Deleted(text) => {
+                out_s.push_str("<del>");
+                out_s.push_str(&sanitize_html(text));
+                out_s.push_str("</del>");
+            }
+        });
+        out_s
+    }
+
     fn edited_to_html(&self, timestamp: &str, text: &str, last: bool) -> String {
         let tag = if last { "tfoot" } else { "tbody" };
         format!("<{tag}><tr><td><span class=\"timestamp\">{timestamp}</span></td><td>{text}</td></tr></{tag}>")
@@ -1466,13 +1715,13 @@ impl<'a> HTML<'a> {
 
     fn balloon_to_html(
         &self,
-        balloon: &AppMessage,
+        balloon: &OwnedAppMessage,
         bundle_id: &str,
         attachments: &mut [Attachment],
         message: &Message,
     ) -> String {
         let mut out_s = String::new();
-        if let Some(url) = balloon.url {
+        if let Some(url) = balloon.url.as_deref() {
             out_s.push_str("<a href=\"");
             out_s.push_str(url);
             out_s.push_str("\">");
@@ -1480,7 +1729,7 @@ impl<'a> HTML<'a> {
         out_s.push_str("<div class=\"app_header\">");
 
         // Image
-        if let Some(image) = balloon.image {
+        if let Some(image) = balloon.image.as_deref() {
             out_s.push_str("<img src=\"");
             out_s.push_str(image);
             out_s.push_str("\">");
@@ -1494,25 +1743,25 @@ impl<'a> HTML<'a> {
 
         // Name
         out_s.push_str("<div class=\"name\">");
-        out_s.push_str(balloon.app_name.unwrap_or(bundle_id));
+        out_s.push_str(balloon.app_name.as_deref().unwrap_or(bundle_id));
         out_s.push_str("</div>");
 
         // Title
-        if let Some(title) = balloon.title {
+        if let Some(title) = balloon.title.as_deref() {
             out_s.push_str("<div class=\"image_title\">");
             out_s.push_str(title);
             out_s.push_str("</div>");
         }
 
         // Subtitle
-        if let Some(subtitle) = balloon.subtitle {
+        if let Some(subtitle) = balloon.subtitle.as_deref() {
             out_s.push_str("<div class=\"image_subtitle\">");
             out_s.push_str(subtitle);
             out_s.push_str("</div>");
         }
 
         // ldtext
-        if let Some(ldtext) = balloon.ldtext {
+        if let Some(ldtext) = balloon.ldtext.as_deref() {
             out_s.push_str("<div class=\"ldtext\">");
             out_s.push_str(ldtext);
             out_s.push_str("</div>");
@@ -1530,28 +1779,28 @@ impl<'a> HTML<'a> {
             out_s.push_str("<div class=\"app_footer\">");
 
             // Caption
-            if let Some(caption) = balloon.caption {
+            if let Some(caption) = balloon.caption.as_deref() {
                 out_s.push_str("<div class=\"caption\">");
                 out_s.push_str(caption);
                 out_s.push_str("</div>");
             }
 
             // Subcaption
-            if let Some(subcaption) = balloon.subcaption {
+            if let Some(subcaption) = balloon.subcaption.as_deref() {
                 out_s.push_str("<div class=\"subcaption\">");
                 out_s.push_str(subcaption);
                 out_s.push_str("</div>");
             }
 
             // Trailing Caption
-            if let Some(trailing_caption) = balloon.trailing_caption {
+            if let Some(trailing_caption) = balloon.trailing_caption.as_deref() {
                 out_s.push_str("<div class=\"trailing_caption\">");
                 out_s.push_str(trailing_caption);
                 out_s.push_str("</div>");
             }
 
             // Trailing Subcaption
-            if let Some(trailing_subcaption) = balloon.trailing_subcaption {
+            if let Some(trailing_subcaption) = balloon.trailing_subcaption.as_deref() {
                 out_s.push_str("<div class=\"trailing_subcaption\">");
                 out_s.push_str(trailing_subcaption);
                 out_s.push_str("</div>");
@@ -1569,26 +1818,36 @@ impl<'a> HTML<'a> {
 #[cfg(test)]
 mod tests {
     use std::{
+        cell::Cell,
         collections::HashMap,
-        env::{current_dir, set_var},
+        env::{current_dir, set_var, temp_dir},
+        fs::{create_dir_all, read_to_string, remove_dir_all},
         path::PathBuf,
     };
 
+    use chrono::{Duration, Local};
+
     use crate::{
-        app::attachment_manager::AttachmentManager, exporters::exporter::Writer, Config, Exporter,
-        Options, HTML,
+        app::{
+            attachment_manager::AttachmentManager, edits::EditReport, file_layout::SplitPages,
+            handwriting_format::HandwritingFormat, report::Report,
+        },
+        exporters::{exporter::Writer, html::INDEX_FILE},
+        Config, Exporter, Options, HTML,
     };
     use imessage_database::{
+        export::{document::DocumentMessage, tapbacks::TapbackStore},
+        message_types::handwriting::RasterOptions,
         tables::{
             attachment::Attachment,
             messages::Message,
+            recently_deleted::DeletedMessage,
+            store::CacheStore,
             table::{get_connection, ME},
         },
-        util::{
-            dates::get_offset, dirs::default_db_path, platform::Platform,
-            query_context::QueryContext,
-        },
+        util::{dates::get_offset, platform::Platform, query_context::QueryContext},
     };
+    use imessage_fixtures::Fixture;
 
     pub(super) fn blank() -> Message {
         Message {
@@ -1619,6 +1878,8 @@ mod tests {
             date_edited: 0,
             chat_id: None,
             associated_message_emoji: None,
+            schedule_type: 0,
+            schedule_state: 0,
             num_attachments: 0,
             deleted_from: None,
             num_replies: 0,
@@ -1629,11 +1890,12 @@ mod tests {
 
     pub(super) fn fake_options() -> Options {
         Options {
-            db_path: default_db_path(),
+            db_path: Fixture::shared().to_path_buf(),
             attachment_root: None,
             attachment_manager: AttachmentManager::Disabled,
             diagnostic: false,
             export_type: None,
+            format: None,
             export_path: PathBuf::from("/tmp"),
             query_context: QueryContext::default(),
             no_lazy: false,
@@ -1641,22 +1903,44 @@ mod tests {
             use_caller_id: false,
             platform: Platform::macOS,
             ignore_disk_space: false,
+            memory_limit: None,
+            strict: false,
+            handwriting_format: HandwritingFormat::default(),
+            handwriting_raster: RasterOptions::default(),
+            edit_report: false,
+            dump_typedstream: None,
+            templates: None,
+            file_name: None,
+            split_by: None,
+            embed: None,
         }
     }
 
+    pub(super) fn document(config: &Config, message: &Message) -> DocumentMessage {
+        DocumentMessage::build(&config.db, &config.tapbacks, message).unwrap()
+    }
+
     pub(super) fn fake_config(options: Options) -> Config {
         let db = get_connection(&options.get_db_path()).unwrap();
         Config {
             chatrooms: HashMap::new(),
             real_chatrooms: HashMap::new(),
             chatroom_participants: HashMap::new(),
+            memberships: CacheStore::Memory(HashMap::new()),
+            group_photos: CacheStore::Memory(HashMap::new()),
+            deleted: CacheStore::Memory(HashMap::new()),
             participants: HashMap::new(),
             real_participants: HashMap::new(),
-            tapbacks: HashMap::new(),
+            tapbacks: TapbackStore::Memory(HashMap::new()),
             options,
             offset: get_offset(),
             db,
             converter: None,
+            report: Report::default(),
+            edits: EditReport::default(),
+            templates: HashMap::new(),
+            link_depth: Cell::new(0),
+            started: Local::now(),
         }
     }
 
@@ -1670,6 +1954,8 @@ mod tests {
             total_bytes: 100,
             is_sticker: false,
             hide_attachment: 0,
+            emoji_image_content_identifier: None,
+            emoji_image_short_description: None,
             copied_path: None,
         }
     }
@@ -1682,6 +1968,34 @@ mod tests {
         assert_eq!(exporter.files.len(), 0);
     }
 
+    #[test]
+    fn can_format_navigation_encoded() {
+        let mut pages = SplitPages::default();
+        pages.insert("Q&A #1/2022.html".to_string(), 1, "2022".to_string());
+        pages.insert("Q&A #1/2023.html".to_string(), 1, "2023".to_string());
+
+        let actual = HTML::format_navigation(&pages, "Q&A #1/2022.html").unwrap();
+        let expected = "<div class=\"pages\"><a class=\"next\" href=\"../Q%26A%20%231/2023.html\">2023 &rarr;</a></div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_write_index_encoded() {
+        let mut options = fake_options();
+        options.export_path = temp_dir().join("html_index_encoded");
+        create_dir_all(&options.export_path).unwrap();
+        let config = fake_config(options);
+        let mut exporter = HTML::new(&config).unwrap();
+        exporter.conversations.insert("Q&A #1.html".to_string(), 1);
+
+        exporter.write_index().unwrap();
+        let actual = read_to_string(config.options.export_path.join(INDEX_FILE)).unwrap();
+        remove_dir_all(&config.options.export_path).unwrap();
+
+        assert!(actual.contains("<li><a href=\"Q%26A%20%231.html\">Q&amp;A #1</a></li>\n"));
+    }
+
     #[test]
     fn can_get_time_valid() {
         // Set timezone to America/Los_Angeles for consistent Local time
@@ -1788,7 +2102,9 @@ mod tests {
         message.is_from_me = true;
         message.chat_id = Some(0);
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -1811,7 +2127,9 @@ mod tests {
         message.is_from_me = true;
         message.chat_id = Some(0);
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">&lt;table&gt;&lt;/table&gt;</span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -1834,12 +2152,108 @@ mod tests {
         message.is_from_me = true;
         message.deleted_from = Some(0);
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<span class=\"deleted\">This message was deleted from the conversation!</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn can_format_html_from_me_recently_deleted() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        let mut deleted = HashMap::new();
+        deleted.insert(
+            0,
+            DeletedMessage {
+                message_id: 0,
+                chat_id: 0,
+                delete_date: Some(674526582885055488),
+            },
+        );
+        config.deleted = CacheStore::Memory(deleted);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("Hello world".to_string());
+        message.date = 674526582885055488;
+        message.is_from_me = true;
+        message.deleted_from = Some(0);
+
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<span class=\"deleted\">This message was deleted from the conversation on May 17, 2022  5:29:42 PM and may be permanently deleted at any time!</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_from_me_recently_deleted_countdown() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        let deleted = DeletedMessage {
+            message_id: 0,
+            chat_id: 0,
+            delete_date: Some(674526582885055488),
+        };
+        // The export started 12 days after the message was deleted
+        config.started = deleted.date_deleted(&config.offset).unwrap() + Duration::days(12);
+        config.deleted = CacheStore::Memory(HashMap::from([(0, deleted)]));
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("Hello world".to_string());
+        message.date = 674526582885055488;
+        message.is_from_me = true;
+        message.deleted_from = Some(0);
+
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<span class=\"deleted\">This message was deleted from the conversation on May 17, 2022  5:29:42 PM and will be permanently deleted in 18 days!</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_from_me_scheduled_sent() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("Hello world".to_string());
+        message.date = 674526582885055488;
+        message.is_from_me = true;
+        message.schedule_type = 2;
+        message.schedule_state = 2;
+
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<p><span class=\"scheduled\">This message was scheduled with Send Later and sent on May 17, 2022  5:29:42 PM</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_html_from_me_normal_read() {
         // Set timezone to America/Los_Angeles for consistent Local time
@@ -1858,7 +2272,9 @@ mod tests {
         message.date_delivered = 674530231992568192;
         message.is_from_me = true;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected =
             "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM (Read by them after 1 hour, 49 seconds)</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
 
@@ -1884,7 +2300,9 @@ mod tests {
         message.text = Some("Hello world".to_string());
         message.handle_id = Some(999999);
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"received\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Sample Contact</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -1898,102 +2316,183 @@ mod tests {
         // Create exporter
         let options = fake_options();
         let mut config = fake_config(options);
-        config
-            .participants
-            .insert(999999, "Sample Contact".to_string());
+        config
+            .participants
+            .insert(999999, "Sample Contact".to_string());
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        message.handle_id = Some(999999);
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        // May 17, 2022  8:29:42 PM
+        message.date_delivered = 674526582885055488;
+        // May 17, 2022  9:30:31 PM
+        message.date_read = 674530231992568192;
+
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+        let expected =
+            "<div class=\"message\">\n<div class=\"received\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM (Read by you after 1 hour, 49 seconds)</span>\n<span class=\"sender\">Sample Contact</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_from_them_custom_name_read() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let mut options = fake_options();
+        options.custom_name = Some("Name".to_string());
+        let mut config = fake_config(options);
+        config
+            .participants
+            .insert(999999, "Sample Contact".to_string());
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        message.handle_id = Some(999999);
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("Hello world".to_string());
+        // May 17, 2022  8:29:42 PM
+        message.date_delivered = 674526582885055488;
+        // May 17, 2022  9:30:31 PM
+        message.date_read = 674530231992568192;
+
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+        let expected =
+            "<div class=\"message\">\n<div class=\"received\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM (Read by Name after 1 hour, 49 seconds)</span>\n<span class=\"sender\">Sample Contact</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_shareplay() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.item_type = 6;
+
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"received\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<span class=\"shareplay\"><hr>SharePlay Message Ended</span>\n</div>\n</div>\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_html_announcement() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config.participants.insert(0, ME.to_string());
+
         let exporter = HTML::new(&config).unwrap();
 
         let mut message = blank();
-        message.handle_id = Some(999999);
         // May 17, 2022  8:29:42 PM
         message.date = 674526582885055488;
-        message.text = Some("Hello world".to_string());
-        // May 17, 2022  8:29:42 PM
-        message.date_delivered = 674526582885055488;
-        // May 17, 2022  9:30:31 PM
-        message.date_read = 674530231992568192;
+        message.group_title = Some("Hello world".to_string());
+        message.is_from_me = true;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
-        let expected =
-            "<div class=\"message\">\n<div class=\"received\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM (Read by you after 1 hour, 49 seconds)</span>\n<span class=\"sender\">Sample Contact</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+        let actual = exporter.format_announcement(&message);
+        let expected = "\n<div class =\"announcement\"><p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span> You named the conversation <b>Hello world</b></p></div>\n";
 
         assert_eq!(actual, expected);
     }
 
     #[test]
-    fn can_format_html_from_them_custom_name_read() {
+    fn can_format_html_announcement_participant_added() {
         // Set timezone to America/Los_Angeles for consistent Local time
         set_var("TZ", "America/Los_Angeles");
 
         // Create exporter
-        let mut options = fake_options();
-        options.custom_name = Some("Name".to_string());
+        let options = fake_options();
         let mut config = fake_config(options);
-        config
-            .participants
-            .insert(999999, "Sample Contact".to_string());
+        config.participants.insert(1, "Sample Contact".to_string());
+
         let exporter = HTML::new(&config).unwrap();
 
         let mut message = blank();
-        message.handle_id = Some(999999);
         // May 17, 2022  8:29:42 PM
         message.date = 674526582885055488;
-        message.text = Some("Hello world".to_string());
-        // May 17, 2022  8:29:42 PM
-        message.date_delivered = 674526582885055488;
-        // May 17, 2022  9:30:31 PM
-        message.date_read = 674530231992568192;
+        message.item_type = 1;
+        message.handle_id = Some(1);
 
-        let actual = exporter.format_message(&message, 0).unwrap();
-        let expected =
-            "<div class=\"message\">\n<div class=\"received\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM (Read by Name after 1 hour, 49 seconds)</span>\n<span class=\"sender\">Sample Contact</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hello world</span>\n</div>\n</div>\n</div>\n";
+        let actual = exporter.format_announcement(&message);
+        let expected = "\n<div class =\"announcement\"><p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span> Sample Contact added you to the conversation.</p></div>\n";
 
         assert_eq!(actual, expected);
     }
 
     #[test]
-    fn can_format_html_shareplay() {
+    fn can_format_html_announcement_participant_removed() {
         // Set timezone to America/Los_Angeles for consistent Local time
         set_var("TZ", "America/Los_Angeles");
 
         // Create exporter
         let options = fake_options();
         let mut config = fake_config(options);
-        config.participants.insert(0, ME.to_string());
+        config.participants.insert(1, "Sample Contact".to_string());
 
         let exporter = HTML::new(&config).unwrap();
 
         let mut message = blank();
         // May 17, 2022  8:29:42 PM
         message.date = 674526582885055488;
-        message.item_type = 6;
+        message.item_type = 1;
+        message.group_action_type = 1;
+        message.other_handle = 1;
+        message.is_from_me = true;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
-        let expected = "<div class=\"message\">\n<div class=\"received\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<span class=\"shareplay\"><hr>SharePlay Message Ended</span>\n</div>\n</div>\n";
+        let actual = exporter.format_announcement(&message);
+        let expected = "\n<div class =\"announcement\"><p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span> You removed Sample Contact from the conversation.</p></div>\n";
 
         assert_eq!(actual, expected);
     }
 
     #[test]
-    fn can_format_html_announcement() {
+    fn can_format_html_announcement_photo_change_missing() {
         // Set timezone to America/Los_Angeles for consistent Local time
         set_var("TZ", "America/Los_Angeles");
 
         // Create exporter
         let options = fake_options();
         let mut config = fake_config(options);
-        config.participants.insert(0, ME.to_string());
+        config.participants.insert(1, "Sample Contact".to_string());
 
         let exporter = HTML::new(&config).unwrap();
 
         let mut message = blank();
         // May 17, 2022  8:29:42 PM
         message.date = 674526582885055488;
-        message.group_title = Some("Hello world".to_string());
-        message.is_from_me = true;
+        message.item_type = 3;
+        message.group_action_type = 1;
+        message.handle_id = Some(1);
 
         let actual = exporter.format_announcement(&message);
-        let expected = "\n<div class =\"announcement\"><p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span> You named the conversation <b>Hello world</b></p></div>\n";
+        let expected = "\n<div class =\"announcement\"><p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span> Sample Contact changed the group photo.</p></div>\n";
 
         assert_eq!(actual, expected);
     }
@@ -2144,7 +2643,9 @@ mod tests {
         message.share_direction = false;
         message.item_type = 4;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">Dec 31, 2000  4:00:00 PM</span>\n<span class=\"sender\">Me</span></p>\n<span class=\"shared_location\"><hr>Started sharing location!</span>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -2167,7 +2668,9 @@ mod tests {
         message.share_direction = false;
         message.item_type = 4;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">Dec 31, 2000  4:00:00 PM</span>\n<span class=\"sender\">Me</span></p>\n<span class=\"shared_location\"><hr>Stopped sharing location!</span>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -2191,7 +2694,9 @@ mod tests {
         message.share_direction = false;
         message.item_type = 4;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"received\">\n<p><span class=\"timestamp\">Dec 31, 2000  4:00:00 PM</span>\n<span class=\"sender\">Unknown</span></p>\n<span class=\"shared_location\"><hr>Started sharing location!</span>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -2215,7 +2720,9 @@ mod tests {
         message.share_direction = false;
         message.item_type = 4;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"received\">\n<p><span class=\"timestamp\">Dec 31, 2000  4:00:00 PM</span>\n<span class=\"sender\">Unknown</span></p>\n<span class=\"shared_location\"><hr>Stopped sharing location!</span>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -2292,6 +2799,66 @@ mod tests {
         assert_eq!(actual, Err("d.jpg"));
     }
 
+    #[test]
+    fn can_format_html_attachment_download_escaped() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let message = blank();
+
+        let mut attachment = fake_attachment();
+        attachment.mime_type = Some("application/pdf".to_string());
+        attachment.transfer_name = Some("<b>.pdf".to_string());
+
+        let actual = exporter
+            .format_attachment(&mut attachment, &message)
+            .unwrap();
+
+        assert_eq!(
+            actual,
+            "<a href=\"a/b/c/d.jpg\">Click to download &lt;b&gt;.pdf (100.00 B)</a>"
+        );
+    }
+
+    #[test]
+    fn can_format_html_genmoji() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let message = blank();
+
+        let mut attachment = fake_attachment();
+        attachment.emoji_image_content_identifier = Some("8F1D0C9A".to_string());
+        attachment.emoji_image_short_description = Some("Crab wearing a party hat".to_string());
+
+        let actual = exporter.format_genmoji(&mut attachment, &message);
+
+        assert_eq!(actual, "<img class=\"genmoji\" src=\"a/b/c/d.jpg\" alt=\"Crab wearing a party hat\" title=\"Crab wearing a party hat\">");
+    }
+
+    #[test]
+    fn can_format_html_genmoji_missing() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let message = blank();
+
+        let mut attachment = fake_attachment();
+        attachment.filename = None;
+        attachment.emoji_image_content_identifier = Some("8F1D0C9A".to_string());
+        attachment.emoji_image_short_description = Some("Crab <3".to_string());
+
+        let actual = exporter.format_genmoji(&mut attachment, &message);
+
+        assert_eq!(actual, "Crab &lt;3");
+    }
+
     #[test]
     fn can_format_html_attachment_sticker() {
         // Create exporter
@@ -2331,14 +2898,26 @@ mod tests {
 
 #[cfg(test)]
 mod balloon_format_tests {
-    use std::env::set_var;
+    use std::{
+        env::{set_var, temp_dir},
+        fs::{create_dir_all, read},
+    };
 
     use super::tests::{blank, fake_config, fake_options};
-    use crate::{exporters::exporter::BalloonFormatter, Exporter, HTML};
+    use crate::{
+        app::{attachment_manager::AttachmentManager, handwriting_format::HandwritingFormat},
+        exporters::exporter::BalloonFormatter,
+        Exporter, HTML,
+    };
     use imessage_database::message_types::{
         app::AppMessage,
         app_store::AppStoreMessage,
         collaboration::CollaborationMessage,
+        digital_touch::{
+            models::{Color, Heartbeat, Point, Tap, TapPoint},
+            DigitalTouch,
+        },
+        handwriting::{models::Point as HandwritingPoint, HandwrittenMessage},
         music::MusicMessage,
         placemark::{Placemark, PlacemarkMessage},
         url::URLMessage,
@@ -2363,7 +2942,7 @@ mod balloon_format_tests {
             placeholder: false,
         };
 
-        let expected = exporter.format_url(&blank(), &balloon, &blank());
+        let expected = exporter.format_url(&blank(), &(&balloon).into(), &blank());
         let actual = "<a href=\"url\"><div class=\"app_header\"><img src=\"images\" loading=\"lazy\", onerror=\"this.style.display='none'\"><div class=\"name\">site_name</div></div><div class=\"app_footer\"><div class=\"caption\">title</div><div class=\"subcaption\">summary</div></div></a>";
 
         assert_eq!(expected, actual);
@@ -2389,7 +2968,7 @@ mod balloon_format_tests {
             placeholder: false,
         };
 
-        let expected = exporter.format_url(&blank(), &balloon, &blank());
+        let expected = exporter.format_url(&blank(), &(&balloon).into(), &blank());
         let actual = "<a href=\"url\"><div class=\"app_header\"><img src=\"images\" onerror=\"this.style.display='none'\"><div class=\"name\">site_name</div></div><div class=\"app_footer\"><div class=\"caption\">title</div><div class=\"subcaption\">summary</div></div></a>";
 
         assert_eq!(expected, actual);
@@ -2410,7 +2989,7 @@ mod balloon_format_tests {
             track_name: Some("track_name"),
         };
 
-        let expected = exporter.format_music(&balloon, &blank());
+        let expected = exporter.format_music(&(&balloon).into(), &blank());
         let actual = "<div class=\"app_header\"><div class=\"name\">track_name</div><audio controls src=\"preview\" </audio></div><a href=\"url\"><div class=\"app_footer\"><div class=\"caption\">artist</div><div class=\"subcaption\">album</div></div></a>";
 
         assert_eq!(expected, actual);
@@ -2432,12 +3011,150 @@ mod balloon_format_tests {
             app_name: Some("app_name"),
         };
 
-        let expected = exporter.format_collaboration(&balloon, &blank());
+        let expected = exporter.format_collaboration(&(&balloon).into(), &blank());
         let actual = "<div class=\"app_header\"><div class=\"name\">app_name</div></div><a href=\"url\"><div class=\"app_footer\"><div class=\"caption\">title</div><div class=\"subcaption\">url</div></div></a>";
 
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn can_format_html_digital_touch_heartbeat() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let balloon = DigitalTouch::Heartbeat(Heartbeat {
+            id: "id".to_string(),
+            bpm: 84.,
+            duration: 2,
+            broken_at: None,
+        });
+
+        let expected = exporter.format_digital_touch(&blank(), &balloon, &blank());
+        let actual = "<div class=\"app_header\"><div class=\"name\">Digital Touch Message</div></div><div class=\"app_footer\"><div class=\"caption\">Heartbeat at 84 BPM for 2 seconds</div></div>";
+
+        assert_eq!(expected, actual);
+    }
+
+    /// A handwritten message with a single diagonal stroke
+    fn handwriting(id: &str) -> HandwrittenMessage {
+        HandwrittenMessage {
+            id: id.to_string(),
+            created_at: 0,
+            height: 100,
+            width: 100,
+            strokes: vec![vec![
+                HandwritingPoint {
+                    x: 10,
+                    y: 10,
+                    width: 4,
+                },
+                HandwritingPoint {
+                    x: 90,
+                    y: 90,
+                    width: 4,
+                },
+            ]],
+        }
+    }
+
+    #[test]
+    fn can_format_html_handwriting() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let balloon = handwriting("id");
+        let expected = exporter.format_handwriting(&blank(), &balloon, &blank());
+
+        assert_eq!(expected, balloon.render_svg());
+        assert!(!expected.contains("<animate"));
+    }
+
+    #[test]
+    fn can_format_html_handwriting_animated() {
+        // Create exporter
+        let mut options = fake_options();
+        options.handwriting_format = HandwritingFormat::Animated;
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let balloon = handwriting("id");
+        let expected = exporter.format_handwriting(&blank(), &balloon, &blank());
+
+        assert!(expected.contains("<animate attributeName=\"stroke-dashoffset\""));
+    }
+
+    #[test]
+    fn can_format_html_handwriting_png() {
+        // Create exporter
+        let mut options = fake_options();
+        options.handwriting_format = HandwritingFormat::Png;
+        options.attachment_manager = AttachmentManager::Efficient;
+        options.export_path = temp_dir().join("imessage-exporter-handwriting");
+        create_dir_all(&options.export_path).unwrap();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let balloon = handwriting("png");
+        let expected = exporter.format_handwriting(&blank(), &balloon, &blank());
+
+        assert_eq!(
+            expected,
+            "<img src=\"attachments/orphaned/png.png\" loading=\"lazy\">"
+        );
+        let image = read(config.attachment_path().join("orphaned/png.png")).unwrap();
+        assert_eq!(&image[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn can_format_html_handwriting_png_no_attachments() {
+        // Create exporter
+        let mut options = fake_options();
+        options.handwriting_format = HandwritingFormat::Png;
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        // Without a place to write the image, fall back to the svg
+        let balloon = handwriting("id");
+        let expected = exporter.format_handwriting(&blank(), &balloon, &blank());
+
+        assert_eq!(expected, balloon.render_svg());
+    }
+
+    #[test]
+    fn can_format_html_digital_touch_tap() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let balloon = DigitalTouch::Tap(Tap {
+            id: "id".to_string(),
+            taps: vec![TapPoint {
+                location: Point { x: 0, y: u16::MAX },
+                delay: 250,
+                color: Color {
+                    red: 255,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                },
+            }],
+        });
+
+        let expected = exporter.format_digital_touch(&blank(), &balloon, &blank());
+
+        assert!(
+            expected.starts_with("<div class=\"app_header\"><div class=\"digital_touch\">\n<svg ")
+        );
+        assert!(expected.contains("<circle cx=\"0.0\" cy=\"1000.0\" r=\"0\" fill=\"#ff0000\""));
+        assert!(expected.contains("begin=\"0.250s\""));
+        assert!(expected.ends_with("<div class=\"caption\">Tapped 1 time</div></div>"));
+    }
+
     #[test]
     fn can_format_html_apple_pay() {
         // Create exporter
@@ -2458,7 +3175,7 @@ mod balloon_format_tests {
             ldtext: Some("ldtext"),
         };
 
-        let expected = exporter.format_apple_pay(&balloon, &blank());
+        let expected = exporter.format_apple_pay(&(&balloon).into(), &blank());
         let actual = "<div class=\"app_header\"><div class=\"name\">app_name</div></div><div class=\"app_footer\"><div class=\"caption\">ldtext</div></div>";
 
         assert_eq!(expected, actual);
@@ -2484,7 +3201,7 @@ mod balloon_format_tests {
             ldtext: Some("ldtext"),
         };
 
-        let expected = exporter.format_fitness(&balloon, &blank());
+        let expected = exporter.format_fitness(&(&balloon).into(), &blank());
         let actual = "<a href=\"url\"><div class=\"app_header\"><img src=\"image\"><div class=\"name\">app_name</div><div class=\"image_title\">title</div><div class=\"image_subtitle\">subtitle</div><div class=\"ldtext\">ldtext</div></div><div class=\"app_footer\"><div class=\"caption\">caption</div><div class=\"subcaption\">subcaption</div><div class=\"trailing_caption\">trailing_caption</div><div class=\"trailing_subcaption\">trailing_subcaption</div></div></a>";
 
         assert_eq!(expected, actual);
@@ -2510,7 +3227,7 @@ mod balloon_format_tests {
             ldtext: Some("ldtext"),
         };
 
-        let expected = exporter.format_slideshow(&balloon, &blank());
+        let expected = exporter.format_slideshow(&(&balloon).into(), &blank());
         let actual = "<a href=\"url\"><div class=\"app_header\"><img src=\"image\"><div class=\"name\">app_name</div><div class=\"image_title\">title</div><div class=\"image_subtitle\">subtitle</div><div class=\"ldtext\">ldtext</div></div><div class=\"app_footer\"><div class=\"caption\">caption</div><div class=\"subcaption\">subcaption</div><div class=\"trailing_caption\">trailing_caption</div><div class=\"trailing_subcaption\">trailing_subcaption</div></div></a>";
 
         assert_eq!(expected, actual);
@@ -2536,7 +3253,7 @@ mod balloon_format_tests {
             ldtext: Some("ldtext"),
         };
 
-        let expected = exporter.format_find_my(&balloon, &blank());
+        let expected = exporter.format_find_my(&(&balloon).into(), &blank());
         let actual = "<div class=\"app_header\"><div class=\"name\">app_name</div></div><div class=\"app_footer\"><div class=\"caption\">ldtext</div></div>";
 
         assert_eq!(expected, actual);
@@ -2565,7 +3282,7 @@ mod balloon_format_tests {
             ldtext: Some("Check In: Timer Started"),
         };
 
-        let expected = exporter.format_check_in(&balloon, &blank());
+        let expected = exporter.format_check_in(&(&balloon).into(), &blank());
         let actual = "<div class=\"app_header\"><div class=\"name\">Check\u{a0}In</div><div class=\"ldtext\">Check\u{a0}In: Timer Started</div></div><div class=\"app_footer\"><div class=\"caption\">Checked in at Oct 14, 2023  1:54:29 PM</div></div>";
 
         assert_eq!(expected, actual);
@@ -2594,7 +3311,7 @@ mod balloon_format_tests {
             ldtext: Some("Check In: Has not checked in when expected, location shared"),
         };
 
-        let expected = exporter.format_check_in(&balloon, &blank());
+        let expected = exporter.format_check_in(&(&balloon).into(), &blank());
         let actual = "<div class=\"app_header\"><div class=\"name\">Check\u{a0}In</div><div class=\"ldtext\">Check\u{a0}In: Has not checked in when expected, location shared</div></div><div class=\"app_footer\"><div class=\"caption\">Checked in at Oct 14, 2023  1:54:29 PM</div></div>";
 
         assert_eq!(expected, actual);
@@ -2623,7 +3340,7 @@ mod balloon_format_tests {
             ldtext: Some("Check In: Fake Location"),
         };
 
-        let expected = exporter.format_check_in(&balloon, &blank());
+        let expected = exporter.format_check_in(&(&balloon).into(), &blank());
         let actual = "<div class=\"app_header\"><div class=\"name\">Check\u{a0}In</div><div class=\"ldtext\">Check\u{a0}In: Fake Location</div></div><div class=\"app_footer\"><div class=\"caption\">Checked in at Oct 14, 2023  1:54:29 PM</div></div>";
 
         assert_eq!(expected, actual);
@@ -2645,7 +3362,7 @@ mod balloon_format_tests {
             genre: Some("genre"),
         };
 
-        let expected = exporter.format_app_store(&balloon, &blank());
+        let expected = exporter.format_app_store(&(&balloon).into(), &blank());
         let actual = "<div class=\"app_header\"><div class=\"name\">app_name</div></div><a href=\"url\"><div class=\"app_footer\"><div class=\"caption\">description</div><div class=\"subcaption\">platform</div><div class=\"trailing_subcaption\">genre</div></div></a>";
 
         assert_eq!(expected, actual);
@@ -2676,7 +3393,7 @@ mod balloon_format_tests {
             },
         };
 
-        let expected = exporter.format_placemark(&balloon, &blank());
+        let expected = exporter.format_placemark(&(&balloon).into(), &blank());
         let actual = "<a href=\"url\"><div class=\"app_header\"><div class=\"name\">Name</div></div><div class=\"app_footer\"><div class=\"caption\">address</div><div class=\"trailing_caption\">postal_code</div><div class=\"subcaption\">country</div><div class=\"trailing_subcaption\">sub_administrative_area</div></div></a>";
 
         assert_eq!(expected, actual);
@@ -2702,7 +3419,8 @@ mod balloon_format_tests {
             ldtext: Some("ldtext"),
         };
 
-        let expected = exporter.format_generic_app(&balloon, "bundle_id", &mut vec![], &blank());
+        let expected =
+            exporter.format_generic_app(&(&balloon).into(), "bundle_id", &mut vec![], &blank());
         let actual = "<a href=\"url\"><div class=\"app_header\"><img src=\"image\"><div class=\"name\">app_name</div><div class=\"image_title\">title</div><div class=\"image_subtitle\">subtitle</div><div class=\"ldtext\">ldtext</div></div><div class=\"app_footer\"><div class=\"caption\">caption</div><div class=\"subcaption\">subcaption</div><div class=\"trailing_caption\">trailing_caption</div><div class=\"trailing_subcaption\">trailing_subcaption</div></div></a>";
 
         assert_eq!(expected, actual);
@@ -2711,13 +3429,13 @@ mod balloon_format_tests {
 
 #[cfg(test)]
 mod text_effect_tests {
-    use super::tests::{blank, fake_config, fake_options};
+    use super::tests::{blank, document, fake_config, fake_options};
     use crate::{
         exporters::exporter::{TextEffectFormatter, Writer},
         Exporter, HTML,
     };
     use imessage_database::{
-        message_types::text_effects::{Style, TextEffect, Unit},
+        message_types::text_effects::{OwnedTextEffect, Style, Unit},
         util::typedstream::parser::TypedStreamReader,
     };
     use std::{
@@ -2733,7 +3451,7 @@ mod text_effect_tests {
         let config = fake_config(options);
         let exporter = HTML::new(&config).unwrap();
 
-        let expected = exporter.format_attributed("Chris", &TextEffect::Default);
+        let expected = exporter.format_attributed("Chris", &OwnedTextEffect::Default);
         let actual = "Chris";
 
         assert_eq!(expected, actual);
@@ -2838,6 +3556,61 @@ mod text_effect_tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn can_format_html_address() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let expected = exporter.format_address(
+            "1&nbsp;Infinite&nbsp;Loop",
+            "1 Infinite Loop, Cupertino, CA",
+        );
+        let actual = "<a href=\"geo:0,0?q=1%20Infinite%20Loop%2C%20Cupertino%2C%20CA\">1&nbsp;Infinite&nbsp;Loop</a>";
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn can_format_html_phone_number() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let expected = exporter.format_phone_number("(555) 555-0100", "+1 (555) 555-0100");
+        let actual = "<a href=\"tel:+15555550100\">(555) 555-0100</a>";
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn can_format_html_event() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let expected = exporter.format_event("tomorrow", "tomorrow");
+        let actual = "<a href=\"data:text/calendar;charset=utf-8,BEGIN%3AVCALENDAR%0D%0AVERSION%3A2.0%0D%0APRODID%3A-%2F%2Fimessage-exporter%2F%2FEN%0D%0ABEGIN%3AVEVENT%0D%0ASUMMARY%3Atomorrow%0D%0AEND%3AVEVENT%0D%0AEND%3AVCALENDAR%0D%0A\" download=\"event.ics\">tomorrow</a>";
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn can_format_html_tracking_number() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let expected = exporter.format_tracking_number("1Z999AA10123456784", "1Z999AA10123456784");
+        let actual = "<u title=\"Tracking number\">1Z999AA10123456784</u>";
+
+        assert_eq!(expected, actual);
+    }
+
     #[test]
     fn can_format_html_mention_end_to_end() {
         // Set timezone to America/Los_Angeles for consistent Local time
@@ -2867,7 +3640,9 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Test <span title=\"+15558675309\"><b>Dad</b></span> </span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -2902,7 +3677,9 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\"><u>000123</u> is your security code. Don&apos;t share your code.</span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -2937,7 +3714,9 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\"><a href=\"https://twitter.com/xxxxxxxxx/status/0000223300009216128\">https://twitter.com/xxxxxxxxx/status/0000223300009216128</a></span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -2972,8 +3751,10 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let actual = exporter.format_message(&message, 0).unwrap();
-        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hi. Right now or <u>tomorrow</u>?</span>\n</div>\n</div>\n</div>\n";
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+        let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">Hi. Right now or <a href=\"data:text/calendar;charset=utf-8,BEGIN%3AVCALENDAR%0D%0AVERSION%3A2.0%0D%0APRODID%3A-%2F%2Fimessage-exporter%2F%2FEN%0D%0ABEGIN%3AVEVENT%0D%0ASUMMARY%3Atomorrow%0D%0AEND%3AVEVENT%0D%0AEND%3AVCALENDAR%0D%0A\" download=\"event.ics\">tomorrow</a>?</span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
     }
@@ -3007,7 +3788,9 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\"><span class=\"animationBig\">Big</span> <span class=\"animationSmall\">small </span><span class=\"animationShake\">shake</span> <span class=\"animationNod\">nod</span> <span class=\"animationExplode\">explode </span><span class=\"animationRipple\">ripple</span> <span class=\"animationBloom\">bloom</span> <span class=\"animationJitter\">jitter</span></span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -3042,7 +3825,9 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\"><b>Bold</b> <u>underline</u> <i>italic</i> <s>strikethrough</s> all <i><u><s><b>four</b></s></u></i></span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -3077,7 +3862,9 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\"><i><u><s><b>Everything</b></s></u></i></span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -3112,7 +3899,9 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\"><u>Underline</u> normal <span class=\"animationJitter\">jitter</span> normal</span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -3127,14 +3916,50 @@ mod edited_tests {
         io::Read,
     };
 
-    use super::tests::{blank, fake_config, fake_options};
+    use super::tests::{blank, document, fake_config, fake_options};
 
     use crate::{exporters::exporter::Writer, Exporter, HTML};
     use imessage_database::{
-        message_types::edited::{EditStatus, EditedMessage, EditedMessagePart},
+        message_types::edited::{EditStatus, EditedEvent, EditedMessage, EditedMessagePart},
         util::typedstream::parser::TypedStreamReader,
     };
 
+    #[test]
+    fn can_format_html_edit_diff() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = HTML::new(&config).unwrap();
+
+        let mut message = blank();
+        message.is_from_me = true;
+        let edited = EditedMessage {
+            parts: vec![EditedMessagePart {
+                status: EditStatus::Edited,
+                edit_history: vec![
+                    EditedEvent {
+                        date: 674526582885055488,
+                        text: "see you at 5".to_string(),
+                        guid: None,
+                    },
+                    EditedEvent {
+                        date: 674526592885055488,
+                        text: "see you at 6 <3".to_string(),
+                        guid: None,
+                    },
+                ],
+            }],
+        };
+
+        let actual = exporter.format_edited(&message, &edited, 0, "").unwrap();
+        let expected = "<table><tbody><tr><td><span class=\"timestamp\"></span></td><td>see you at 5</td></tr></tbody><tfoot><tr><td><span class=\"timestamp\">Edited 10 seconds later</span></td><td>see you at <del>5</del><ins>6 &lt;3</ins></td></tr></tfoot></table>";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_html_conversion_final_unsent() {
         // Set timezone to America/Los_Angeles for consistent Local time
@@ -3187,7 +4012,9 @@ mod edited_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">From arbitrary byte stream:\r</span>\n</div>\n<hr><div class=\"message_part\">\n<span class=\"attachment_error\">Attachment does not exist!</span>\n</div>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">To native Rust data structures:\r</span>\n</div>\n<hr><div class=\"message_part\">\n<span class=\"unsent\"><span class=\"unsent\">You unsent this message part 1 hour, 49 seconds after sending!</span></span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -3224,7 +4051,9 @@ mod edited_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "<div class=\"message\">\n<div class=\"sent iMessage\">\n<p><span class=\"timestamp\">May 17, 2022  5:29:42 PM</span>\n<span class=\"sender\">Me</span></p>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">From arbitrary byte stream:\r</span>\n</div>\n<hr><div class=\"message_part\">\n<span class=\"attachment_error\">Attachment does not exist!</span>\n</div>\n<hr><div class=\"message_part\">\n<span class=\"bubble\">To native Rust data structures:\r</span>\n</div>\n</div>\n</div>\n";
 
         assert_eq!(actual, expected);
@@ -1,55 +1,103 @@
-#![allow(unused_imports)]
-
 use std::{
     borrow::Cow,
     collections::{
         hash_map::Entry::{Occupied, Vacant},
-        HashMap,
+        BTreeMap, BTreeSet, HashMap,
     },
-    fs::File,
+    fs::{write, File},
     io::{BufWriter, Write},
 };
 
+use indicatif::ProgressBar;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use crate::{
     app::{
-        error::RuntimeError, progress::build_progress_bar_export, runtime::Config,
-        sanitizers::sanitize_json,
+        error::RuntimeError,
+        progress::build_progress_bar_export,
+        report::WarningKind,
+        runtime::Config,
+        sanitizers::{encode_calendar_event, encode_uri_component, sanitize_json},
     },
     exporters::exporter::{BalloonFormatter, Exporter, TextEffectFormatter, Writer},
 };
 
 use imessage_database::{
-    error::{plist::PlistParseError, table::TableError},
+    error::{export::ExportError, plist::PlistParseError, table::TableError},
+    export::document::{Content, DocumentMessage, Item, Render},
     message_types::{
-        app::AppMessage,
-        app_store::AppStoreMessage,
-        collaboration::CollaborationMessage,
-        digital_touch::{self, DigitalTouch},
-        edited::{EditStatus, EditedMessage},
+        app::OwnedAppMessage,
+        app_store::OwnedAppStoreMessage,
+        collaboration::OwnedCollaborationMessage,
+        digital_touch::DigitalTouch,
+        edited::EditedMessage,
         expressives::{BubbleEffect, Expressive, ScreenEffect},
         handwriting::HandwrittenMessage,
-        music::MusicMessage,
-        placemark::PlacemarkMessage,
-        text_effects::{Animation, Style, TextEffect, Unit},
-        url::URLMessage,
-        variants::{Announcement, BalloonProvider, CustomBalloon, URLOverride, Variant},
+        music::OwnedMusicMessage,
+        placemark::OwnedPlacemarkMessage,
+        text_effects::{Animation, OwnedTextEffect, Style, Unit},
+        url::OwnedURLMessage,
     },
     tables::{
-        attachment::{Attachment, MediaType},
-        messages::{models::BubbleComponent, Message},
-        table::{Table, FITNESS_RECEIVER, ME, ORPHANED, YOU},
-    },
-    util::{
-        dates::{format, get_local_time, readable_diff, TIMESTAMP_FACTOR},
-        plist::parse_plist,
+        attachment::Attachment,
+        messages::{
+            models::{OwnedMessage, ScheduleState},
+            Message,
+        },
+        table::ORPHANED,
     },
+    util::dates::format,
 };
 
 const HEADER: &str = "[\n  ";
 const SEPARATOR: &str = ",\n  ";
 const FOOTER: &str = "\n]\n";
+/// The file that lists who has been in each exported conversation
+pub const PARTICIPANTS_FILE: &str = "participants.json";
+
+/// A message as written to JSON exports: the [`OwnedMessage`] row followed by the fields this exporter adds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedMessage {
+    /// The columns of the message's row
+    #[serde(flatten)]
+    pub message: OwnedMessage,
+    /// The formatted date a message in Recently Deleted was deleted on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_deleted: Option<String>,
+    /// The number of days until a message in Recently Deleted is permanently deleted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days_until_purge: Option<i64>,
+    /// The formatted date a message scheduled with Send Later is sent on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_scheduled: Option<String>,
+    /// The state of a message scheduled with Send Later
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule_status: Option<ScheduleState>,
+    /// The transcription of a voice message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_transcription: Option<String>,
+    /// The descriptions of the Genmoji in the message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub genmoji: Vec<String>,
+    /// The data detector results in the message text
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub detected_data: Vec<DetectedData>,
+    /// The parsed app balloon, whose shape depends on the kind of app message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balloon: Option<serde_json::Value>,
+}
+
+/// Data found by the data detector in a message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetectedData {
+    /// The kind of data, i.e. `address` or `phone_number`
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The detected text
+    pub value: String,
+}
 
 pub struct JSON<'a> {
     /// Data that is setup from the application's runtime
@@ -57,6 +105,12 @@ pub struct JSON<'a> {
     /// Handles to files we want to write messages to
     /// Map of resolved chatroom file location to a buffered writer
     pub files: HashMap<String, BufWriter<File>>,
+    /// Map of resolved chatroom file location to the chat IDs written to it
+    pub conversations: BTreeMap<String, BTreeSet<i32>>,
+    /// Progress through the messages table
+    pb: ProgressBar,
+    /// The number of top-level messages and announcements rendered so far
+    current_message: u64,
 }
 
 impl<'a> Exporter<'a> for JSON<'a> {
@@ -65,83 +119,27 @@ impl<'a> Exporter<'a> for JSON<'a> {
         Ok(JSON {
             config,
             files: HashMap::new(),
+            conversations: BTreeMap::new(),
+            pb: ProgressBar::hidden(),
+            current_message: 0,
         })
     }
 
     /// Begin iterating over the messages table
-    fn iter_messages(&mut self) -> Result<(), RuntimeError> {
-        // Tell the user what we are doing
-        eprintln!(
-            "Exporting to {} as JSON...",
-            self.config.options.export_path.display()
-        );
-
-        // Keep track of current message ROWID
-        let mut current_message_row = -1;
-
-        // Set up progress bar
-        let mut current_message = 0;
-        let total_messages =
-            Message::get_count(&self.config.db, &self.config.options.query_context)
-                .map_err(RuntimeError::DatabaseError)?;
-        let pb = build_progress_bar_export(total_messages);
-
-        let mut statement =
-            Message::stream_rows(&self.config.db, &self.config.options.query_context)
-                .map_err(RuntimeError::DatabaseError)?;
-
-        let messages = statement
-            .query_map([], |row| Ok(Message::from_row(row)))
-            .map_err(|err| RuntimeError::DatabaseError(TableError::Messages(err)))?;
-
-        for message in messages {
-            let mut msg = Message::extract(message).map_err(RuntimeError::DatabaseError)?;
-
-            // Early escape if we try and render the same message GUID twice
-            // See https://github.com/ReagentX/imessage-exporter/issues/135 for rationale
-            if msg.rowid == current_message_row {
-                current_message += 1;
-                continue;
-            }
-            current_message_row = msg.rowid;
-
-            // Generate the text of the message
-            let _ = msg.generate_text(&self.config.db);
-
-            // Render the announcement in-line
-            if msg.is_announcement() {
-                let announcement = self.format_announcement(&msg);
-                JSON::write_to_file(self.get_or_create_file(&msg)?, &announcement)?;
-            }
-            // Message replies and tapbacks are rendered in context, so no need to render them separately
-            else if !msg.is_tapback() {
-                let message = self
-                    .format_message(&msg, 0)
-                    .map_err(RuntimeError::DatabaseError)?;
-                JSON::write_to_file(self.get_or_create_file(&msg)?, &message)?;
-            }
-            current_message += 1;
-            if current_message % 99 == 0 {
-                pb.set_position(current_message);
-            }
-        }
-        pb.finish();
-
-        eprintln!("Writing JSON footers...");
-        for (_, buf) in self.files.iter_mut() {
-            JSON::write_to_file(buf, FOOTER)?;
-        }
-
-        Ok(())
-    }
-
     /// Create a file for the given chat, caching it so we don't need to build it later
     fn get_or_create_file(
         &mut self,
         message: &Message,
     ) -> Result<&mut BufWriter<File>, RuntimeError> {
         let filename = match self.config.conversation(message) {
-            Some((chatroom, _)) => self.config.filename(chatroom),
+            Some((chatroom, _)) => {
+                let filename = self.config.file_path(chatroom, message);
+                self.conversations
+                    .entry(filename.clone())
+                    .or_default()
+                    .insert(chatroom.rowid);
+                filename
+            }
             None => ORPHANED.to_string(),
         };
         match self.files.entry(filename.clone()) {
@@ -151,9 +149,7 @@ impl<'a> Exporter<'a> for JSON<'a> {
                 Ok(buf)
             }
             Vacant(entry) => {
-                let mut path = self.config.options.export_path.clone();
-                path.push(filename);
-                path.set_extension("json");
+                let path = self.config.export_file_path(&filename, "json")?;
 
                 // If the file already exists, don't write the headers again
                 // This can happen if multiple chats use the same group name
@@ -181,10 +177,108 @@ impl<'a> Exporter<'a> for JSON<'a> {
 }
 
 impl<'a> Writer<'a> for JSON<'a> {
-    fn format_message(&self, message: &Message, indent_size: usize) -> Result<String, TableError> {
+    fn format_message(
+        &self,
+        document: &DocumentMessage,
+        _indent_size: usize,
+    ) -> Result<String, ExportError> {
+        let message = &document.message;
         //let indent = String::from_iter((0..indent_size).map(|_| " "));
-        let mut formatted_message = serde_json::to_string(message)?;
-        Ok(formatted_message)
+        let mut exported = ExportedMessage {
+            message: OwnedMessage::from(message),
+            date_deleted: None,
+            days_until_purge: None,
+            date_scheduled: None,
+            schedule_status: None,
+            audio_transcription: message.audio_transcription().map(str::to_string),
+            genmoji: vec![],
+            detected_data: vec![],
+            balloon: None,
+        };
+
+        // Messages in Recently Deleted note when they will be permanently deleted
+        if let Some(deleted) = self.config.deleted_message(message) {
+            exported.date_deleted = deleted
+                .date_deleted(&self.config.offset)
+                .map(|date| format(&Ok(date)));
+            exported.days_until_purge =
+                deleted.days_until_purge(&self.config.offset, &self.config.started);
+        }
+
+        // Messages scheduled with Send Later note when they are sent
+        if let (Some(date), Some(state)) = (
+            message.date_scheduled(&self.config.offset),
+            message.schedule_state(),
+        ) {
+            exported.date_scheduled = Some(format(&date));
+            exported.schedule_status = Some(state);
+        }
+
+        // Replies are exported as messages of their own, so only the parts of this message are included
+        for part in &document.parts {
+            match &part.content {
+                Some(Content::Text {
+                    attributes,
+                    genmoji,
+                    ..
+                }) => {
+                    // Genmoji are stored as attachments, so include their descriptions in place of the images
+                    exported.genmoji.extend(
+                        genmoji
+                            .iter()
+                            .map(|image| self.format_genmoji(&mut image.clone(), message)),
+                    );
+
+                    // Data detector results, so every address or event mentioned in a chat can be found
+                    exported
+                        .detected_data
+                        .extend(attributes.iter().filter_map(|attribute| {
+                            let (kind, value) = match &attribute.effect {
+                                OwnedTextEffect::Address(value) => ("address", value),
+                                OwnedTextEffect::Event(value) => ("event", value),
+                                OwnedTextEffect::PhoneNumber(value) => ("phone_number", value),
+                                OwnedTextEffect::FlightNumber(value) => ("flight_number", value),
+                                OwnedTextEffect::TrackingNumber(value) => {
+                                    ("tracking_number", value)
+                                }
+                                _ => return None,
+                            };
+                            Some(DetectedData {
+                                kind: kind.to_string(),
+                                value: value.to_string(),
+                            })
+                        }));
+                }
+                // App messages include their parsed balloon
+                Some(Content::Balloon(balloon)) => {
+                    let formatted = self.format_balloon(message, balloon, &mut vec![], "");
+                    exported.balloon =
+                        Some(serde_json::from_str(&formatted).unwrap_or_else(|_| json!(formatted)));
+                }
+                Some(Content::MissingPayload) => match &message.text {
+                    // URL messages without a payload are formatted as their plain text
+                    Some(text) if message.is_url() => exported.balloon = Some(json!(text)),
+                    // Balloons that cannot be read are listed in the export report
+                    _ => self.config.warn(
+                        message,
+                        WarningKind::Balloon,
+                        format!(
+                            "Unable to format {:?} message: {}",
+                            message.variant(),
+                            PlistParseError::NoPayload
+                        ),
+                    ),
+                },
+                Some(Content::BalloonError(why)) => self.config.warn(
+                    message,
+                    WarningKind::Balloon,
+                    format!("Unable to format {:?} message: {why}", message.variant()),
+                ),
+                _ => {}
+            }
+        }
+
+        Ok(serde_json::to_string(&exported).map_err(TableError::JsonError)?)
     }
 
     /// Format a single attachment into a JSON object
@@ -214,86 +308,9 @@ impl<'a> Writer<'a> for JSON<'a> {
         }
     }
 
-    /// Format an app message by parsing some of its fields
-    fn format_app(
-        &self,
-        message: &'a Message,
-        attachments: &mut Vec<Attachment>,
-        indent: &str,
-    ) -> Result<String, PlistParseError> {
-        if let Variant::App(balloon) = message.variant() {
-            let mut app_bubble = String::new();
-
-            // Handwritten messages use a different payload type, so check that first
-            if message.is_handwriting() {
-                if let Some(payload) = message.raw_payload_data(&self.config.db) {
-                    return match HandwrittenMessage::from_payload(&payload) {
-                        Ok(bubble) => Ok(self.format_handwriting(message, &bubble, indent)),
-                        Err(why) => Err(PlistParseError::HandwritingError(why)),
-                    };
-                }
-            }
-
-            if message.is_digital_touch() {
-                if let Some(payload) = message.raw_payload_data(&self.config.db) {
-                    return match digital_touch::from_payload(&payload) {
-                        Some(bubble) => Ok(self.format_digital_touch(message, &bubble, indent)),
-                        None => Err(PlistParseError::DigitalTouchError),
-                    };
-                }
-            }
-
-            if let Some(payload) = message.payload_data(&self.config.db) {
-                // Handle URL messages separately since they are a special case
-                let res = if message.is_url() {
-                    let parsed = parse_plist(&payload)?;
-                    let bubble = URLMessage::get_url_message_override(&parsed)?;
-                    match bubble {
-                        URLOverride::Normal(balloon) => self.format_url(message, &balloon, indent),
-                        URLOverride::AppleMusic(balloon) => self.format_music(&balloon, indent),
-                        URLOverride::Collaboration(balloon) => {
-                            self.format_collaboration(&balloon, indent)
-                        }
-                        URLOverride::AppStore(balloon) => self.format_app_store(&balloon, indent),
-                        URLOverride::SharedPlacemark(balloon) => {
-                            self.format_placemark(&balloon, indent)
-                        }
-                    }
-                // Handwriting uses a different payload type than the rest of the branches
-                } else {
-                    // Handle the app case
-                    let parsed = parse_plist(&payload)?;
-                    match AppMessage::from_map(&parsed) {
-                        Ok(bubble) => match balloon {
-                            CustomBalloon::Application(bundle_id) => {
-                                self.format_generic_app(&bubble, bundle_id, attachments, indent)
-                            }
-                            CustomBalloon::ApplePay => self.format_apple_pay(&bubble, indent),
-                            CustomBalloon::Fitness => self.format_fitness(&bubble, indent),
-                            CustomBalloon::Slideshow => self.format_slideshow(&bubble, indent),
-                            CustomBalloon::CheckIn => self.format_check_in(&bubble, indent),
-                            CustomBalloon::FindMy => self.format_find_my(&bubble, indent),
-                            CustomBalloon::Handwriting => unreachable!(),
-                            CustomBalloon::DigitalTouch => unreachable!(),
-                            CustomBalloon::URL => unreachable!(),
-                        },
-                        Err(why) => return Err(why),
-                    }
-                };
-                app_bubble.push_str(&res);
-            } else {
-                // Sometimes, URL messages are missing their payloads
-                if message.is_url() {
-                    if let Some(text) = &message.text {
-                        return Ok(text.to_string());
-                    }
-                }
-                return Err(PlistParseError::NoPayload);
-            }
-            Ok(app_bubble)
-        } else {
-            Err(PlistParseError::WrongMessageType)
-        }
+    /// Format a Genmoji as its description
+    fn format_genmoji(&self, genmoji: &mut Attachment, _: &Message) -> String {
+        genmoji.genmoji_description().to_string()
     }
 
     /// Format a tapback (displayed under a message)
@@ -364,9 +381,8 @@ impl<'a> Writer<'a> for JSON<'a> {
     }
 
     /// Format some attributed text
-    fn format_attributed(&'a self, text: &'a str, attribute: &'a TextEffect) -> Cow<'a, str> {
-        let formatted_text = serde_json::to_string(text)
-            .unwrap_or_else(|_| "\"Error serializing text\"".to_string());
+    fn format_attributed(&'a self, text: &'a str, attribute: &'a OwnedTextEffect) -> Cow<'a, str> {
+        let formatted_text = format!("\"{}\"", sanitize_json(text));
         let formatted_attribute = serde_json::to_string(attribute)
             .unwrap_or_else(|_| "\"Error serializing text_effect\"".to_string());
         let formatted_message = format!(
@@ -382,40 +398,86 @@ impl<'a> Writer<'a> for JSON<'a> {
     }
 }
 
+impl Render for JSON<'_> {
+    fn start(&mut self, _: &Connection) -> Result<(), ExportError> {
+        // Tell the user what we are doing
+        eprintln!(
+            "Exporting to {} as JSON...",
+            self.config.options.export_path.display()
+        );
+
+        // Set up progress bar
+        let total_messages =
+            Message::get_count(&self.config.db, &self.config.options.query_context)?;
+        self.pb = build_progress_bar_export(total_messages);
+        Ok(())
+    }
+
+    fn render(&mut self, item: Item) -> Result<(), ExportError> {
+        let message = item.message();
+        self.config.log_edits(message);
+        self.config.link_from(message);
+
+        let rendered = match &item {
+            // Message replies and tapbacks are rendered in context, so no need to render them separately
+            Item::Message(document) => self.format_message(document, 0)?,
+            // Render the announcement in-line
+            Item::Announcement(message) => self.format_announcement(message),
+        };
+        let file = self
+            .get_or_create_file(message)
+            .map_err(|why| ExportError::Format(why.to_string()))?;
+        file.write_all(rendered.as_bytes())?;
+
+        self.current_message += 1;
+        if self.current_message.is_multiple_of(99) {
+            self.pb.set_position(self.current_message);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), ExportError> {
+        self.pb.finish();
+
+        eprintln!("Writing JSON footers...");
+        for (_, buf) in self.files.iter_mut() {
+            buf.write_all(FOOTER.as_bytes())?;
+        }
+
+        self.write_participants()
+            .map_err(|why| ExportError::Format(why.to_string()))
+    }
+}
+
 impl<'a> BalloonFormatter<&'a str> for JSON<'a> {
     /// Format a URL message
-    fn format_url(&self, _message: &Message, balloon: &URLMessage, _indent: &str) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+    fn format_url(&self, _message: &Message, balloon: &OwnedURLMessage, _indent: &str) -> String {
+        serde_json::to_string(balloon)
+            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string())
     }
 
     /// Format an Apple Music message
-    fn format_music(&self, balloon: &MusicMessage, _indent: &str) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+    fn format_music(&self, balloon: &OwnedMusicMessage, _indent: &str) -> String {
+        serde_json::to_string(balloon)
+            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string())
     }
 
     /// Format a Rich Collaboration message
-    fn format_collaboration(&self, balloon: &CollaborationMessage, _indent: &str) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+    fn format_collaboration(&self, balloon: &OwnedCollaborationMessage, _indent: &str) -> String {
+        serde_json::to_string(balloon)
+            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string())
     }
 
     /// Format an App Store link
-    fn format_app_store(&self, balloon: &AppStoreMessage, _indent: &str) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+    fn format_app_store(&self, balloon: &OwnedAppStoreMessage, _indent: &str) -> String {
+        serde_json::to_string(balloon)
+            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string())
     }
 
     /// Format a shared location message
-    fn format_placemark(&self, balloon: &PlacemarkMessage, _indent: &str) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+    fn format_placemark(&self, balloon: &OwnedPlacemarkMessage, _indent: &str) -> String {
+        serde_json::to_string(balloon)
+            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string())
     }
 
     /// Format a handwritten note message
@@ -425,9 +487,8 @@ impl<'a> BalloonFormatter<&'a str> for JSON<'a> {
         balloon: &HandwrittenMessage,
         _indent: &str,
     ) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+        serde_json::to_string(balloon)
+            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string())
     }
 
     /// Format a digital touch message
@@ -437,57 +498,54 @@ impl<'a> BalloonFormatter<&'a str> for JSON<'a> {
         balloon: &DigitalTouch,
         _indent: &str,
     ) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+        json!({
+            "kind": balloon.kind(),
+            "description": balloon.describe(),
+            "data": balloon,
+        })
+        .to_string()
     }
 
     /// Format an Apple Pay message
-    fn format_apple_pay(&self, balloon: &AppMessage, _indent: &str) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+    fn format_apple_pay(&self, balloon: &OwnedAppMessage, _indent: &str) -> String {
+        serde_json::to_string(balloon)
+            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string())
     }
 
     /// Format a Fitness message
-    fn format_fitness(&self, balloon: &AppMessage, _indent: &str) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+    fn format_fitness(&self, balloon: &OwnedAppMessage, _indent: &str) -> String {
+        serde_json::to_string(balloon)
+            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string())
     }
 
     /// Format a Photo Slideshow message
-    fn format_slideshow(&self, balloon: &AppMessage, _indent: &str) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+    fn format_slideshow(&self, balloon: &OwnedAppMessage, _indent: &str) -> String {
+        serde_json::to_string(balloon)
+            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string())
     }
 
     /// Format a Find My message
-    fn format_find_my(&self, balloon: &AppMessage, _indent: &str) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+    fn format_find_my(&self, balloon: &OwnedAppMessage, _indent: &str) -> String {
+        serde_json::to_string(balloon)
+            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string())
     }
 
     /// Format a Check In message
-    fn format_check_in(&self, balloon: &AppMessage, _indent: &str) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+    fn format_check_in(&self, balloon: &OwnedAppMessage, _indent: &str) -> String {
+        serde_json::to_string(balloon)
+            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string())
     }
 
     /// Format a generic app, generally third party
     fn format_generic_app(
         &self,
-        balloon: &AppMessage,
+        balloon: &OwnedAppMessage,
         _bundle_id: &str,
         _attachments: &mut Vec<Attachment>,
         _indent: &str,
     ) -> String {
-        let formatted_balloon = serde_json::to_string(balloon)
-            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string());
-        formatted_balloon
+        serde_json::to_string(balloon)
+            .unwrap_or_else(|_| "\"Error serializing balloon\"".to_string())
     }
 }
 
@@ -508,7 +566,7 @@ impl<'a> TextEffectFormatter for JSON<'a> {
     }
 
     /// Format message text containing a [`Conversion`](imessage_database::message_types::text_effects::TextEffect::Conversion)
-    fn format_conversion(&self, text: &str, unit: &Unit) -> String {
+    fn format_conversion(&self, text: &str, _unit: &Unit) -> String {
         format!("<u>{text}</u>")
     }
 
@@ -536,49 +594,94 @@ impl<'a> TextEffectFormatter for JSON<'a> {
     fn format_animated(&self, text: &str, animation: &Animation) -> String {
         format!("<span class=\"animation{animation:?}\">{text}</span>")
     }
+
+    /// Format message text containing an [`Address`](imessage_database::message_types::text_effects::TextEffect::Address)
+    fn format_address(&self, text: &str, address: &str) -> String {
+        format!(
+            "<a href=\"geo:0,0?q={}\">{text}</a>",
+            encode_uri_component(address)
+        )
+    }
+
+    /// Format message text containing an [`Event`](imessage_database::message_types::text_effects::TextEffect::Event)
+    fn format_event(&self, text: &str, event: &str) -> String {
+        format!(
+            "<a href=\"{}\" download=\"event.ics\">{text}</a>",
+            encode_calendar_event(event)
+        )
+    }
+
+    /// Format message text containing a [`PhoneNumber`](imessage_database::message_types::text_effects::TextEffect::PhoneNumber)
+    fn format_phone_number(&self, text: &str, number: &str) -> String {
+        let number: String = number
+            .chars()
+            .filter(|c| c.is_ascii_digit() || *c == '+')
+            .collect();
+        format!("<a href=\"tel:{number}\">{text}</a>")
+    }
+
+    /// Format message text containing a [`FlightNumber`](imessage_database::message_types::text_effects::TextEffect::FlightNumber)
+    fn format_flight_number(&self, text: &str, _: &str) -> String {
+        format!("<u title=\"Flight number\">{text}</u>")
+    }
+
+    /// Format message text containing a [`TrackingNumber`](imessage_database::message_types::text_effects::TextEffect::TrackingNumber)
+    fn format_tracking_number(&self, text: &str, _: &str) -> String {
+        format!("<u title=\"Tracking number\">{text}</u>")
+    }
 }
 
 impl<'a> JSON<'a> {
-    fn get_time(&self, message: &Message) -> String {
-        let mut date = format(&message.date(&self.config.offset));
-        let read_after = message.time_until_read(&self.config.offset);
-        if let Some(time) = read_after {
-            if !time.is_empty() {
-                let who = if message.is_from_me() {
-                    "them"
-                } else {
-                    self.config.options.custom_name.as_deref().unwrap_or("you")
-                };
-                date.push_str(&format!(" (Read by {who} after {time})"));
-            }
-        }
-        date
+    /// Write the current and historical participants of each exported conversation
+    fn write_participants(&self) -> Result<(), RuntimeError> {
+        let participants: serde_json::Map<String, serde_json::Value> = self
+            .conversations
+            .iter()
+            .map(|(filename, chat_ids)| (filename.clone(), self.config.membership(chat_ids)))
+            .collect();
+
+        let path = self.config.options.export_path.join(PARTICIPANTS_FILE);
+        let contents = serde_json::to_string_pretty(&participants)
+            .map_err(|why| RuntimeError::DiskError(why.into()))?;
+        write(&path, contents).map_err(|why| RuntimeError::CreateError(why, path))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use std::{
+        cell::Cell,
         collections::HashMap,
         env::{current_dir, set_var},
         path::PathBuf,
     };
 
+    use chrono::Local;
+
     use crate::{
-        app::attachment_manager::AttachmentManager, exporters::exporter::Writer, Config, Exporter,
-        Options, JSON,
+        app::{
+            attachment_manager::AttachmentManager, edits::EditReport,
+            handwriting_format::HandwritingFormat, report::Report,
+        },
+        exporters::{exporter::Writer, json::ExportedMessage},
+        Config, Exporter, Options, JSON,
     };
     use imessage_database::{
+        export::{document::DocumentMessage, tapbacks::TapbackStore},
+        message_types::handwriting::RasterOptions,
         tables::{
             attachment::Attachment,
-            messages::Message,
+            messages::{
+                models::{OwnedMessage, ScheduleState},
+                Message,
+            },
+            recently_deleted::DeletedMessage,
+            store::CacheStore,
             table::{get_connection, ME},
         },
-        util::{
-            dates::get_offset, dirs::default_db_path, platform::Platform,
-            query_context::QueryContext,
-        },
+        util::{dates::get_offset, platform::Platform, query_context::QueryContext},
     };
+    use imessage_fixtures::Fixture;
 
     pub(super) fn blank() -> Message {
         Message {
@@ -609,6 +712,8 @@ mod tests {
             date_edited: 0,
             chat_id: None,
             associated_message_emoji: None,
+            schedule_type: 0,
+            schedule_state: 0,
             num_attachments: 0,
             deleted_from: None,
             num_replies: 0,
@@ -619,11 +724,12 @@ mod tests {
 
     pub(super) fn fake_options() -> Options {
         Options {
-            db_path: default_db_path(),
+            db_path: Fixture::shared().to_path_buf(),
             attachment_root: None,
             attachment_manager: AttachmentManager::Disabled,
             diagnostic: false,
             export_type: None,
+            format: None,
             export_path: PathBuf::from("/tmp"),
             query_context: QueryContext::default(),
             no_lazy: false,
@@ -631,22 +737,44 @@ mod tests {
             use_caller_id: false,
             platform: Platform::macOS,
             ignore_disk_space: false,
+            memory_limit: None,
+            strict: false,
+            handwriting_format: HandwritingFormat::default(),
+            handwriting_raster: RasterOptions::default(),
+            edit_report: false,
+            dump_typedstream: None,
+            templates: None,
+            file_name: None,
+            split_by: None,
+            embed: None,
         }
     }
 
+    pub(super) fn document(config: &Config, message: &Message) -> DocumentMessage {
+        DocumentMessage::build(&config.db, &config.tapbacks, message).unwrap()
+    }
+
     pub(super) fn fake_config(options: Options) -> Config {
         let db = get_connection(&options.get_db_path()).unwrap();
         Config {
             chatrooms: HashMap::new(),
             real_chatrooms: HashMap::new(),
             chatroom_participants: HashMap::new(),
+            memberships: CacheStore::Memory(HashMap::new()),
+            group_photos: CacheStore::Memory(HashMap::new()),
+            deleted: CacheStore::Memory(HashMap::new()),
             participants: HashMap::new(),
             real_participants: HashMap::new(),
-            tapbacks: HashMap::new(),
+            tapbacks: TapbackStore::Memory(HashMap::new()),
             options,
             offset: get_offset(),
             db,
             converter: None,
+            report: Report::default(),
+            edits: EditReport::default(),
+            templates: HashMap::new(),
+            link_depth: Cell::new(0),
+            started: Local::now(),
         }
     }
 
@@ -660,6 +788,8 @@ mod tests {
             total_bytes: 100,
             is_sticker: false,
             hide_attachment: 0,
+            emoji_image_content_identifier: None,
+            emoji_image_short_description: None,
             copied_path: None,
         }
     }
@@ -673,7 +803,7 @@ mod tests {
     }
 
     #[test]
-    fn can_get_time_valid() {
+    fn can_format_json_from_me_normal() {
         // Set timezone to America/Los_Angeles for consistent Local time
         set_var("TZ", "America/Los_Angeles");
 
@@ -682,23 +812,23 @@ mod tests {
         let config = fake_config(options);
         let exporter = JSON::new(&config).unwrap();
 
-        // Create fake message
         let mut message = blank();
         // May 17, 2022  8:29:42 PM
         message.date = 674526582885055488;
-        // May 17, 2022  8:29:42 PM
-        message.date_delivered = 674526582885055488;
-        // May 17, 2022  9:30:31 PM
-        message.date_read = 674530231992568192;
+        message.text = Some("Hello world".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
 
-        assert_eq!(
-            "May 17, 2022  5:29:42 PM (Read by you after 1 hour, 49 seconds)",
-            exporter.get_time(&message)
-        );
+        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+
+        assert_eq!(expected, actual);
     }
 
     #[test]
-    fn can_get_time_invalid() {
+    fn can_format_json_message_with_html() {
         // Set timezone to America/Los_Angeles for consistent Local time
         set_var("TZ", "America/Los_Angeles");
 
@@ -707,19 +837,23 @@ mod tests {
         let config = fake_config(options);
         let exporter = JSON::new(&config).unwrap();
 
-        // Create fake message
         let mut message = blank();
-        // May 17, 2022  9:30:31 PM
-        message.date = 674530231992568192;
-        // May 17, 2022  9:30:31 PM
-        message.date_delivered = 674530231992568192;
-        // Wed May 18 2022 02:36:24 GMT+0000
-        message.date_read = 674526582885055488;
-        assert_eq!("May 17, 2022  6:30:31 PM", exporter.get_time(&message));
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("<table></table>".to_string());
+        message.is_from_me = true;
+        message.chat_id = Some(0);
+
+        let expected = r#"{"rowid":0,"guid":"","text":"<table></table>","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+
+        assert_eq!(expected, actual);
     }
 
     #[test]
-    fn can_format_json_from_me_normal() {
+    fn can_format_json_from_me_normal_deleted() {
         // Set timezone to America/Los_Angeles for consistent Local time
         set_var("TZ", "America/Los_Angeles");
 
@@ -730,19 +864,56 @@ mod tests {
 
         let mut message = blank();
         // May 17, 2022  8:29:42 PM
+        message.text = Some("Hello world".to_string());
         message.date = 674526582885055488;
+        message.is_from_me = true;
+        message.deleted_from = Some(0);
+
+        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":0,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn can_format_json_from_me_recently_deleted() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        let mut deleted = HashMap::new();
+        deleted.insert(
+            0,
+            DeletedMessage {
+                message_id: 0,
+                chat_id: 0,
+                delete_date: Some(674526582885055488),
+            },
+        );
+        config.deleted = CacheStore::Memory(deleted);
+        let exporter = JSON::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
         message.text = Some("Hello world".to_string());
+        message.date = 674526582885055488;
         message.is_from_me = true;
-        message.chat_id = Some(0);
+        message.deleted_from = Some(0);
 
-        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":0,"num_replies":0,"components":null,"edited_parts":null,"date_deleted":"May 17, 2022  5:29:42 PM","days_until_purge":0}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
 
     #[test]
-    fn can_format_json_message_with_html() {
+    fn can_format_json_from_me_scheduled_pending() {
         // Set timezone to America/Los_Angeles for consistent Local time
         set_var("TZ", "America/Los_Angeles");
 
@@ -753,19 +924,23 @@ mod tests {
 
         let mut message = blank();
         // May 17, 2022  8:29:42 PM
+        message.text = Some("Hello world".to_string());
         message.date = 674526582885055488;
-        message.text = Some("<table></table>".to_string());
         message.is_from_me = true;
         message.chat_id = Some(0);
+        message.schedule_type = 2;
+        message.schedule_state = 1;
 
-        let expected = r#"{"rowid":0,"guid":"","text":"<table></table>","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":2,"schedule_state":1,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null,"date_scheduled":"May 17, 2022  5:29:42 PM","schedule_status":"Pending"}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
 
     #[test]
-    fn can_format_json_from_me_normal_deleted() {
+    fn can_read_json_message() {
         // Set timezone to America/Los_Angeles for consistent Local time
         set_var("TZ", "America/Los_Angeles");
 
@@ -779,12 +954,22 @@ mod tests {
         message.text = Some("Hello world".to_string());
         message.date = 674526582885055488;
         message.is_from_me = true;
-        message.deleted_from = Some(0);
+        message.chat_id = Some(0);
+        message.schedule_type = 2;
+        message.schedule_state = 1;
 
-        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":0,"num_replies":0,"components":null,"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+        let exported: ExportedMessage = serde_json::from_str(&actual).unwrap();
 
-        assert_eq!(expected, actual);
+        assert_eq!(exported.message, OwnedMessage::from(&message));
+        assert_eq!(
+            exported.date_scheduled.as_deref(),
+            Some("May 17, 2022  5:29:42 PM")
+        );
+        assert_eq!(exported.schedule_status, Some(ScheduleState::Pending));
+        assert_eq!(serde_json::to_string(&exported).unwrap(), actual);
     }
 
     #[test]
@@ -805,8 +990,10 @@ mod tests {
         message.date_delivered = 674530231992568192;
         message.is_from_me = true;
 
-        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":674530231992568192,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":674530231992568192,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -830,8 +1017,10 @@ mod tests {
         message.text = Some("Hello world".to_string());
         message.handle_id = Some(999999);
 
-        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":999999,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":999999,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -859,8 +1048,10 @@ mod tests {
         // May 17, 2022  9:30:31 PM
         message.date_read = 674530231992568192;
 
-        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":999999,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":674530231992568192,"date_delivered":674526582885055488,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":999999,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":674530231992568192,"date_delivered":674526582885055488,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -889,8 +1080,10 @@ mod tests {
         // May 17, 2022  9:30:31 PM
         message.date_read = 674530231992568192;
 
-        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":999999,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":674530231992568192,"date_delivered":674526582885055488,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"Hello world","service":"iMessage","handle_id":999999,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":674530231992568192,"date_delivered":674526582885055488,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -912,8 +1105,10 @@ mod tests {
         message.date = 674526582885055488;
         message.item_type = 6;
 
-        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":6,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":6,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -936,7 +1131,7 @@ mod tests {
         message.group_title = Some("Hello world".to_string());
         message.is_from_me = true;
 
-        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":"Hello world","group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":"Hello world","group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
         let actual = exporter.format_announcement(&message);
 
         assert_eq!(expected, actual);
@@ -960,7 +1155,7 @@ mod tests {
         message.date = 674526582885055488;
         message.group_title = Some("Hello world".to_string());
 
-        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":"Hello world","group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":"Hello world","group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
         let actual = exporter.format_announcement(&message);
 
         assert_eq!(expected, actual);
@@ -984,7 +1179,7 @@ mod tests {
         message.associated_message_type = Some(2000);
         message.associated_message_guid = Some("fake_guid".to_string());
 
-        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":"fake_guid","associated_message_type":2000,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":"fake_guid","associated_message_type":2000,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
         let actual = exporter.format_tapback(&message).unwrap();
 
         assert_eq!(expected, actual);
@@ -1010,7 +1205,7 @@ mod tests {
         message.associated_message_guid = Some("fake_guid".to_string());
         message.handle_id = Some(999999);
 
-        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":999999,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":"fake_guid","associated_message_type":2000,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":999999,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":"fake_guid","associated_message_type":2000,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
         let actual = exporter.format_tapback(&message).unwrap();
 
         assert_eq!(expected, actual);
@@ -1037,7 +1232,11 @@ mod tests {
         message.handle_id = Some(999999);
         message.associated_message_emoji = Some("☕️".to_string());
 
-        let expected = concat!(r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":999999,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":"fake_guid","associated_message_type":2006,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":"☕"#, "\u{fe0f}", r#"","chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#);
+        let expected = concat!(
+            r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":999999,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":"fake_guid","associated_message_type":2006,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":"☕"#,
+            "\u{fe0f}",
+            r#"","schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#
+        );
         let actual = exporter.format_tapback(&message).unwrap();
 
         assert_eq!(expected, actual);
@@ -1064,7 +1263,11 @@ mod tests {
         message.handle_id = Some(999999);
         message.associated_message_emoji = Some("☕️".to_string());
 
-        let expected = concat!(r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":999999,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":"fake_guid","associated_message_type":2007,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":"☕"#, "\u{fe0f}", r#"","chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#);
+        let expected = concat!(
+            r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":999999,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":"fake_guid","associated_message_type":2007,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":"☕"#,
+            "\u{fe0f}",
+            r#"","schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#
+        );
         let actual = exporter.format_tapback(&message).unwrap();
 
         assert_eq!(expected, actual);
@@ -1087,8 +1290,10 @@ mod tests {
         message.share_direction = false;
         message.item_type = 4;
 
-        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":0,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":4,"other_handle":2,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":0,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":4,"other_handle":2,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -1110,8 +1315,10 @@ mod tests {
         message.share_direction = false;
         message.item_type = 4;
 
-        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":0,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":4,"other_handle":2,"share_status":true,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":0,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":4,"other_handle":2,"share_status":true,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -1134,8 +1341,10 @@ mod tests {
         message.share_direction = false;
         message.item_type = 4;
 
-        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":null,"destination_caller_id":null,"subject":null,"date":0,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":4,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":null,"destination_caller_id":null,"subject":null,"date":0,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":4,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -1158,8 +1367,10 @@ mod tests {
         message.share_direction = false;
         message.item_type = 4;
 
-        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":null,"destination_caller_id":null,"subject":null,"date":0,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":4,"other_handle":0,"share_status":true,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":null,"destination_caller_id":null,"subject":null,"date":0,"date_read":0,"date_delivered":0,"is_from_me":false,"is_read":false,"item_type":4,"other_handle":0,"share_status":true,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":null,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":null,"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -1175,7 +1386,7 @@ mod tests {
 
         let mut attachment = fake_attachment();
 
-        let expected = r#"{"rowid":0,"filename":"a/b/c/d.jpg","uti":"public.png","mime_type":"image/png","transfer_name":"d.jpg","total_bytes":100,"is_sticker":false,"hide_attachment":0,"copied_path":null}"#;
+        let expected = r#"{"rowid":0,"filename":"a/b/c/d.jpg","uti":"public.png","mime_type":"image/png","transfer_name":"d.jpg","total_bytes":100,"is_sticker":false,"hide_attachment":0,"emoji_image_content_identifier":null,"emoji_image_short_description":null,"copied_path":null}"#;
         let actual = exporter
             .format_attachment(&mut attachment, &message)
             .unwrap();
@@ -1212,7 +1423,7 @@ mod tests {
 
         let mut attachment = fake_attachment();
 
-        let expected = r#"{"rowid":0,"filename":"a/b/c/d.jpg","uti":"public.png","mime_type":"image/png","transfer_name":"d.jpg","total_bytes":100,"is_sticker":false,"hide_attachment":0,"copied_path":null}"#;
+        let expected = r#"{"rowid":0,"filename":"a/b/c/d.jpg","uti":"public.png","mime_type":"image/png","transfer_name":"d.jpg","total_bytes":100,"is_sticker":false,"hide_attachment":0,"emoji_image_content_identifier":null,"emoji_image_short_description":null,"copied_path":null}"#;
         let actual = exporter
             .format_attachment(&mut attachment, &message)
             .unwrap();
@@ -1260,18 +1471,13 @@ mod tests {
         attachment.filename = Some(sticker_path.to_string_lossy().to_string());
         attachment.copied_path = Some(PathBuf::from(sticker_path.to_string_lossy().to_string()));
 
-        let expected = r#"{"rowid":0,"filename":"/home/deven/git/imessage-exporter/imessage-database/test_data/stickers/outline.heic","uti":"public.png","mime_type":"image/png","transfer_name":"d.jpg","total_bytes":100,"is_sticker":true,"hide_attachment":0,"copied_path":"/home/deven/git/imessage-exporter/imessage-database/test_data/stickers/outline.heic"}"#;
+        let expected = format!(
+            r#"{{"rowid":0,"filename":"{0}","uti":"public.png","mime_type":"image/png","transfer_name":"d.jpg","total_bytes":100,"is_sticker":true,"hide_attachment":0,"emoji_image_content_identifier":null,"emoji_image_short_description":null,"copied_path":"{0}"}}"#,
+            sticker_path.display()
+        );
         let actual = exporter.format_sticker(&mut attachment, &message);
 
         assert_eq!(expected, actual);
-
-        // Remove the file created by the constructor for this test
-        let orphaned_path = current_dir()
-            .unwrap()
-            .parent()
-            .unwrap()
-            .join("orphaned.json");
-        std::fs::remove_file(orphaned_path).unwrap();
     }
 }
 
@@ -1285,6 +1491,7 @@ mod balloon_format_tests {
         app::AppMessage,
         app_store::AppStoreMessage,
         collaboration::CollaborationMessage,
+        digital_touch::{models::Heartbeat, DigitalTouch},
         music::MusicMessage,
         placemark::{Placemark, PlacemarkMessage},
         url::URLMessage,
@@ -1310,11 +1517,12 @@ mod balloon_format_tests {
         };
 
         let expected = r#"{"title":"title","summary":"summary","url":"url","original_url":"original_url","item_type":"item_type","images":["images"],"icons":["icons"],"site_name":"site_name","placeholder":false}"#;
-        let actual = exporter.format_url(&blank(), &balloon, "");
+        let actual = exporter.format_url(&blank(), &(&balloon).into(), "");
 
         assert_eq!(expected, actual);
     }
 
+    #[test]
     fn can_format_json_music() {
         // Create exporter
         let options = fake_options();
@@ -1329,8 +1537,8 @@ mod balloon_format_tests {
             track_name: Some("track_name"),
         };
 
-        let expected = "<div class=\"app_header\"><div class=\"name\">track_name</div><audio controls src=\"preview\" </audio></div><a href=\"url\"><div class=\"app_footer\"><div class=\"caption\">artist</div><div class=\"subcaption\">album</div></div></a>";
-        let actual = exporter.format_music(&balloon, "");
+        let expected = r#"{"url":"url","preview":"preview","artist":"artist","album":"album","track_name":"track_name"}"#;
+        let actual = exporter.format_music(&(&balloon).into(), "");
 
         assert_eq!(expected, actual);
     }
@@ -1352,7 +1560,27 @@ mod balloon_format_tests {
         };
 
         let expected = r#"{"original_url":"original_url","url":"url","title":"title","creation_date":0.0,"bundle_id":"bundle_id","app_name":"app_name"}"#;
-        let actual = exporter.format_collaboration(&balloon, "");
+        let actual = exporter.format_collaboration(&(&balloon).into(), "");
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn can_format_json_digital_touch() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = JSON::new(&config).unwrap();
+
+        let balloon = DigitalTouch::Heartbeat(Heartbeat {
+            id: "id".to_string(),
+            bpm: 84.,
+            duration: 2,
+            broken_at: None,
+        });
+
+        let expected = exporter.format_digital_touch(&blank(), &balloon, "");
+        let actual = r#"{"data":{"Heartbeat":{"bpm":84.0,"broken_at":null,"duration":2,"id":"id"}},"description":"Heartbeat at 84 BPM for 2 seconds","kind":"Heartbeat"}"#;
 
         assert_eq!(expected, actual);
     }
@@ -1378,7 +1606,7 @@ mod balloon_format_tests {
         };
 
         let expected = r#"{"image":"image","url":"url","title":"title","subtitle":"subtitle","caption":"caption","subcaption":"subcaption","trailing_caption":"trailing_caption","trailing_subcaption":"trailing_subcaption","app_name":"app_name","ldtext":"ldtext"}"#;
-        let actual = exporter.format_apple_pay(&balloon, "");
+        let actual = exporter.format_apple_pay(&(&balloon).into(), "");
 
         assert_eq!(expected, actual);
     }
@@ -1404,7 +1632,7 @@ mod balloon_format_tests {
         };
 
         let expected = r#"{"image":"image","url":"url","title":"title","subtitle":"subtitle","caption":"caption","subcaption":"subcaption","trailing_caption":"trailing_caption","trailing_subcaption":"trailing_subcaption","app_name":"app_name","ldtext":"ldtext"}"#;
-        let actual = exporter.format_fitness(&balloon, "");
+        let actual = exporter.format_fitness(&(&balloon).into(), "");
 
         assert_eq!(expected, actual);
     }
@@ -1430,7 +1658,7 @@ mod balloon_format_tests {
         };
 
         let expected = r#"{"image":"image","url":"url","title":"title","subtitle":"subtitle","caption":"caption","subcaption":"subcaption","trailing_caption":"trailing_caption","trailing_subcaption":"trailing_subcaption","app_name":"app_name","ldtext":"ldtext"}"#;
-        let actual = exporter.format_slideshow(&balloon, "");
+        let actual = exporter.format_slideshow(&(&balloon).into(), "");
 
         assert_eq!(expected, actual);
     }
@@ -1456,7 +1684,7 @@ mod balloon_format_tests {
         };
 
         let expected = r#"{"image":"image","url":"url","title":"title","subtitle":"subtitle","caption":"caption","subcaption":"subcaption","trailing_caption":"trailing_caption","trailing_subcaption":"trailing_subcaption","app_name":"app_name","ldtext":"ldtext"}"#;
-        let actual = exporter.format_find_my(&balloon, "");
+        let actual = exporter.format_find_my(&(&balloon).into(), "");
 
         assert_eq!(expected, actual);
     }
@@ -1484,8 +1712,16 @@ mod balloon_format_tests {
             ldtext: Some("Check In: Timer Started"),
         };
 
-        let expected = concat!(r#"{"image":null,"url":"?messageType=1&interfaceVersion=1&sendDate=1697316869.688709","title":null,"subtitle":null,"caption":"Check"#, "\u{a0}", r#"In: Timer Started","subcaption":null,"trailing_caption":null,"trailing_subcaption":null,"app_name":"Check"#, "\u{a0}", r#"In","ldtext":"Check"#, "\u{a0}", r#"In: Timer Started"}"#);
-        let actual = exporter.format_check_in(&balloon, "");
+        let expected = concat!(
+            r#"{"image":null,"url":"?messageType=1&interfaceVersion=1&sendDate=1697316869.688709","title":null,"subtitle":null,"caption":"Check"#,
+            "\u{a0}",
+            r#"In: Timer Started","subcaption":null,"trailing_caption":null,"trailing_subcaption":null,"app_name":"Check"#,
+            "\u{a0}",
+            r#"In","ldtext":"Check"#,
+            "\u{a0}",
+            r#"In: Timer Started"}"#
+        );
+        let actual = exporter.format_check_in(&(&balloon).into(), "");
 
         assert_eq!(expected, actual);
     }
@@ -1513,8 +1749,16 @@ mod balloon_format_tests {
             ldtext: Some("Check In: Has not checked in when expected, location shared"),
         };
 
-        let expected = concat!(r#"{"image":null,"url":"?messageType=1&interfaceVersion=1&sendDate=1697316869.688709","title":null,"subtitle":null,"caption":"Check"#, "\u{a0}", r#"In: Has not checked in when expected, location shared","subcaption":null,"trailing_caption":null,"trailing_subcaption":null,"app_name":"Check"#, "\u{a0}", r#"In","ldtext":"Check"#, "\u{a0}", r#"In: Has not checked in when expected, location shared"}"#);
-        let actual = exporter.format_check_in(&balloon, "");
+        let expected = concat!(
+            r#"{"image":null,"url":"?messageType=1&interfaceVersion=1&sendDate=1697316869.688709","title":null,"subtitle":null,"caption":"Check"#,
+            "\u{a0}",
+            r#"In: Has not checked in when expected, location shared","subcaption":null,"trailing_caption":null,"trailing_subcaption":null,"app_name":"Check"#,
+            "\u{a0}",
+            r#"In","ldtext":"Check"#,
+            "\u{a0}",
+            r#"In: Has not checked in when expected, location shared"}"#
+        );
+        let actual = exporter.format_check_in(&(&balloon).into(), "");
 
         assert_eq!(expected, actual);
     }
@@ -1542,8 +1786,16 @@ mod balloon_format_tests {
             ldtext: Some("Check In: Fake Location"),
         };
 
-        let expected = concat!(r#"{"image":null,"url":"?messageType=1&interfaceVersion=1&sendDate=1697316869.688709","title":null,"subtitle":null,"caption":"Check"#, "\u{a0}", r#"In: Fake Location","subcaption":null,"trailing_caption":null,"trailing_subcaption":null,"app_name":"Check"#, "\u{a0}", r#"In","ldtext":"Check"#, "\u{a0}", r#"In: Fake Location"}"#);
-        let actual = exporter.format_check_in(&balloon, "");
+        let expected = concat!(
+            r#"{"image":null,"url":"?messageType=1&interfaceVersion=1&sendDate=1697316869.688709","title":null,"subtitle":null,"caption":"Check"#,
+            "\u{a0}",
+            r#"In: Fake Location","subcaption":null,"trailing_caption":null,"trailing_subcaption":null,"app_name":"Check"#,
+            "\u{a0}",
+            r#"In","ldtext":"Check"#,
+            "\u{a0}",
+            r#"In: Fake Location"}"#
+        );
+        let actual = exporter.format_check_in(&(&balloon).into(), "");
 
         assert_eq!(expected, actual);
     }
@@ -1565,7 +1817,7 @@ mod balloon_format_tests {
         };
 
         let expected = r#"{"url":"url","original_url":"original_url","app_name":"app_name","description":"description","platform":"platform","genre":"genre"}"#;
-        let actual = exporter.format_app_store(&balloon, "");
+        let actual = exporter.format_app_store(&(&balloon).into(), "");
 
         assert_eq!(expected, actual);
     }
@@ -1596,7 +1848,7 @@ mod balloon_format_tests {
         };
 
         let expected = r#"{"url":"url","original_url":"original_url","place_name":"Name","placemark":{"name":"name","address":"address","state":"state","city":"city","iso_country_code":"iso_country_code","postal_code":"postal_code","country":"country","street":"street","sub_administrative_area":"sub_administrative_area","sub_locality":"sub_locality"}}"#;
-        let actual = exporter.format_placemark(&balloon, "");
+        let actual = exporter.format_placemark(&(&balloon).into(), "");
 
         assert_eq!(expected, actual);
     }
@@ -1622,7 +1874,7 @@ mod balloon_format_tests {
         };
 
         let expected = r#"{"image":"image","url":"url","title":"title","subtitle":"subtitle","caption":"caption","subcaption":"subcaption","trailing_caption":"trailing_caption","trailing_subcaption":"trailing_subcaption","app_name":"app_name","ldtext":"ldtext"}"#;
-        let actual = exporter.format_generic_app(&balloon, "bundle_id", &mut vec![], "");
+        let actual = exporter.format_generic_app(&(&balloon).into(), "bundle_id", &mut vec![], "");
 
         assert_eq!(expected, actual);
     }
@@ -1630,13 +1882,13 @@ mod balloon_format_tests {
 
 #[cfg(test)]
 mod text_effect_tests {
-    use super::tests::{blank, fake_config, fake_options};
+    use super::tests::{blank, document, fake_config, fake_options};
     use crate::{
         exporters::exporter::{TextEffectFormatter, Writer},
         Exporter, JSON,
     };
     use imessage_database::{
-        message_types::text_effects::{Style, TextEffect, Unit},
+        message_types::text_effects::{OwnedTextEffect, Style, Unit},
         util::typedstream::parser::TypedStreamReader,
     };
     use std::{
@@ -1653,7 +1905,7 @@ mod text_effect_tests {
         let exporter = JSON::new(&config).unwrap();
 
         let expected = r#"{ text: "Chris", text_effect: "Default" }"#;
-        let actual = exporter.format_attributed("Chris", &TextEffect::Default);
+        let actual = exporter.format_attributed("Chris", &OwnedTextEffect::Default);
 
         assert_eq!(expected, actual);
     }
@@ -1786,8 +2038,10 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let expected = exporter.format_message(&message, 0).unwrap();
-        let actual = r#"{"rowid":0,"guid":"","text":"Test Dad ","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSMutableString","version":1},[{"String":"Test Dad "}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":5}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":3}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMentionConfirmedMention"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"+15558675309"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":1}]}],"edited_parts":null}"#;
+        let expected = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+        let actual = r#"{"rowid":0,"guid":"","text":"Test Dad ","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSMutableString","version":1},[{"String":"Test Dad "}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":5}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":3}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMentionConfirmedMention"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"+15558675309"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":1}]}],"edited_parts":null}"#;
 
         assert_eq!(expected, actual);
     }
@@ -1821,8 +2075,10 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let expected = r#"{"rowid":0,"guid":"","text":"000123 is your security code. Don't share your code.","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSMutableString","version":1},[{"String":"000123 is your security code. Don't share your code."}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":6}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":3}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMOneTimeCodeAttributeName"}]]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"displayCode"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"000123"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"code"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"000123"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMDataDetectedAttributeName"}]]},{"Object":[{"name":"NSData","version":0},[{"SignedInteger":535}]]},{"Data":[{"Array":[98,112,108,105,115,116,48,48,212,1,2,3,4,5,6,7,12,88,36,118,101,114,115,105,111,110,89,36,97,114,99,104,105,118,101,114,84,36,116,111,112,88,36,111,98,106,101,99,116,115,18,0,1,134,160,95,16,15,78,83,75,101,121,101,100,65,114,99,104,105,118,101,114,210,8,9,10,11,87,118,101,114,115,105,111,110,89,100,100,45,114,101,115,117,108,116,128,11,128,1,172,13,14,28,36,37,38,44,45,46,50,53,57,85,36,110,117,108,108,215,15,16,17,18,19,20,21,22,23,24,25,26,27,26,82,77,83,86,36,99,108,97,115,115,82,65,82,81,84,81,80,82,83,82,82,86,78,128,6,128,10,128,2,128,7,16,1,128,8,212,29,30,31,16,32,33,34,35,95,16,18,78,83,46,114,97,110,103,101,118,97,108,46,108,101,110,103,116,104,95,16,20,78,83,46,114,97,110,103,101,118,97,108,46,108,111,99,97,116,105,111,110,90,78,83,46,115,112,101,99,105,97,108,128,3,128,4,16,4,128,5,16,6,16,0,210,39,40,41,42,90,36,99,108,97,115,115,110,97,109,101,88,36,99,108,97,115,115,101,115,87,78,83,86,97,108,117,101,162,41,43,88,78,83,79,98,106,101,99,116,86,48,48,48,49,50,51,88,65,117,116,104,67,111,100,101,210,47,16,48,49,90,78,83,46,111,98,106,101,99,116,115,160,128,9,210,39,40,51,52,87,78,83,65,114,114,97,121,162,51,43,210,39,40,54,55,95,16,15,68,68,83,99,97,110,110,101,114,82,101,115,117,108,116,162,56,43,95,16,15,68,68,83,99,97,110,110,101,114,82,101,115,117,108,116,16,1,0,8,0,17,0,26,0,36,0,41,0,50,0,55,0,73,0,78,0,86,0,96,0,98,0,100,0,113,0,119,0,134,0,137,0,144,0,147,0,149,0,151,0,154,0,157,0,159,0,161,0,163,0,165,0,167,0,169,0,178,0,199,0,222,0,233,0,235,0,237,0,239,0,241,0,243,0,245,0,250,1,5,1,14,1,22,1,25,1,34,1,41,1,50,1,55,1,66,1,67,1,69,1,74,1,82,1,85,1,90,1,108,1,111,1,129,0,0,0,0,0,0,2,1,0,0,0,0,0,0,0,58,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,131]}]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":46}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]}],"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"000123 is your security code. Don't share your code.","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSMutableString","version":1},[{"String":"000123 is your security code. Don't share your code."}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":6}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":3}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMOneTimeCodeAttributeName"}]]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"displayCode"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"000123"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"code"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"000123"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMDataDetectedAttributeName"}]]},{"Object":[{"name":"NSData","version":0},[{"SignedInteger":535}]]},{"Data":[{"Array":[98,112,108,105,115,116,48,48,212,1,2,3,4,5,6,7,12,88,36,118,101,114,115,105,111,110,89,36,97,114,99,104,105,118,101,114,84,36,116,111,112,88,36,111,98,106,101,99,116,115,18,0,1,134,160,95,16,15,78,83,75,101,121,101,100,65,114,99,104,105,118,101,114,210,8,9,10,11,87,118,101,114,115,105,111,110,89,100,100,45,114,101,115,117,108,116,128,11,128,1,172,13,14,28,36,37,38,44,45,46,50,53,57,85,36,110,117,108,108,215,15,16,17,18,19,20,21,22,23,24,25,26,27,26,82,77,83,86,36,99,108,97,115,115,82,65,82,81,84,81,80,82,83,82,82,86,78,128,6,128,10,128,2,128,7,16,1,128,8,212,29,30,31,16,32,33,34,35,95,16,18,78,83,46,114,97,110,103,101,118,97,108,46,108,101,110,103,116,104,95,16,20,78,83,46,114,97,110,103,101,118,97,108,46,108,111,99,97,116,105,111,110,90,78,83,46,115,112,101,99,105,97,108,128,3,128,4,16,4,128,5,16,6,16,0,210,39,40,41,42,90,36,99,108,97,115,115,110,97,109,101,88,36,99,108,97,115,115,101,115,87,78,83,86,97,108,117,101,162,41,43,88,78,83,79,98,106,101,99,116,86,48,48,48,49,50,51,88,65,117,116,104,67,111,100,101,210,47,16,48,49,90,78,83,46,111,98,106,101,99,116,115,160,128,9,210,39,40,51,52,87,78,83,65,114,114,97,121,162,51,43,210,39,40,54,55,95,16,15,68,68,83,99,97,110,110,101,114,82,101,115,117,108,116,162,56,43,95,16,15,68,68,83,99,97,110,110,101,114,82,101,115,117,108,116,16,1,0,8,0,17,0,26,0,36,0,41,0,50,0,55,0,73,0,78,0,86,0,96,0,98,0,100,0,113,0,119,0,134,0,137,0,144,0,147,0,149,0,151,0,154,0,157,0,159,0,161,0,163,0,165,0,167,0,169,0,178,0,199,0,222,0,233,0,235,0,237,0,239,0,241,0,243,0,245,0,250,1,5,1,14,1,22,1,25,1,34,1,41,1,50,1,55,1,66,1,67,1,69,1,74,1,82,1,85,1,90,1,108,1,111,1,129,0,0,0,0,0,0,2,1,0,0,0,0,0,0,0,58,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,131]}]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":46}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]}],"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -1856,8 +2112,10 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let expected = r#"{"rowid":0,"guid":"","text":"https://twitter.com/xxxxxxxxx/status/0000223300009216128","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSMutableString","version":1},[{"String":"https://twitter.com/xxxxxxxxx/status/0000223300009216128"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":56}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":4}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMLinkAttributeName"}]]},{"Object":[{"name":"NSURL","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"https://twitter.com/xxxxxxxxx/status/0000223300009216128"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMDataDetectedAttributeName"}]]},{"Object":[{"name":"NSMutableData","version":0},[{"SignedInteger":604}]]},{"Data":[{"Array":[98,112,108,105,115,116,48,48,212,1,2,3,4,5,6,7,12,88,36,118,101,114,115,105,111,110,89,36,97,114,99,104,105,118,101,114,84,36,116,111,112,88,36,111,98,106,101,99,116,115,18,0,1,134,160,95,16,15,78,83,75,101,121,101,100,65,114,99,104,105,118,101,114,210,8,9,13,11,87,118,101,114,115,105,111,110,89,100,100,45,114,101,115,117,108,116,128,11,128,1,172,13,14,28,36,37,38,44,45,46,50,54,58,85,36,110,117,108,108,215,15,16,17,18,19,20,21,22,23,24,25,26,27,26,82,77,83,86,36,99,108,97,115,115,82,65,82,81,84,81,80,82,83,82,82,86,78,128,6,128,13,128,2,128,7,16,1,128,8,212,29,30,31,16,32,33,34,35,95,16,18,78,83,46,114,97,110,103,101,118,97,108,46,108,101,110,103,116,104,95,16,20,78,83,46,114,97,110,103,101,118,97,108,46,108,111,99,97,116,105,111,110,90,78,83,46,115,112,101,99,105,97,108,128,3,128,4,16,4,128,5,16,56,16,0,210,39,40,41,42,90,36,99,108,97,115,115,110,97,109,101,88,36,99,108,97,115,115,101,115,87,78,83,86,97,108,117,101,162,41,43,88,78,83,79,98,106,101,99,116,95,16,56,104,116,116,112,115,58,47,47,116,119,105,116,116,101,114,46,99,111,109,47,120,120,120,120,120,120,120,120,120,47,115,116,97,116,117,115,47,48,48,48,48,50,50,51,51,48,48,48,48,57,50,49,54,49,50,56,87,72,116,116,112,85,82,76,210,47,16,48,49,90,78,83,46,111,98,106,101,99,116,115,160,128,9,210,39,40,51,52,94,78,83,77,117,116,97,98,108,101,65,114,114,97,121,163,51,53,43,87,78,83,65,114,114,97,121,210,39,40,55,56,95,16,15,68,68,83,99,97,110,110,101,114,82,101,115,117,108,116,162,57,43,95,16,15,68,68,83,99,97,110,110,101,114,82,101,115,117,108,116,16,1,0,8,0,17,0,26,0,36,0,41,0,50,0,55,0,73,0,78,0,86,0,96,0,98,0,100,0,113,0,119,0,134,0,137,0,144,0,147,0,149,0,151,0,154,0,157,0,159,0,161,0,163,0,165,0,167,0,169,0,178,0,199,0,222,0,233,0,235,0,237,0,239,0,241,0,243,0,245,0,250,1,5,1,14,1,22,1,25,1,34,1,93,1,101,1,106,1,117,1,118,1,120,1,125,1,140,1,144,1,152,1,157,1,175,1,178,1,196,0,0,0,0,0,0,2,1,0,0,0,0,0,0,0,59,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,198]}]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMBaseWritingDirectionAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":-1}]]}],"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"https://twitter.com/xxxxxxxxx/status/0000223300009216128","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSMutableString","version":1},[{"String":"https://twitter.com/xxxxxxxxx/status/0000223300009216128"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":56}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":4}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMLinkAttributeName"}]]},{"Object":[{"name":"NSURL","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"https://twitter.com/xxxxxxxxx/status/0000223300009216128"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMDataDetectedAttributeName"}]]},{"Object":[{"name":"NSMutableData","version":0},[{"SignedInteger":604}]]},{"Data":[{"Array":[98,112,108,105,115,116,48,48,212,1,2,3,4,5,6,7,12,88,36,118,101,114,115,105,111,110,89,36,97,114,99,104,105,118,101,114,84,36,116,111,112,88,36,111,98,106,101,99,116,115,18,0,1,134,160,95,16,15,78,83,75,101,121,101,100,65,114,99,104,105,118,101,114,210,8,9,13,11,87,118,101,114,115,105,111,110,89,100,100,45,114,101,115,117,108,116,128,11,128,1,172,13,14,28,36,37,38,44,45,46,50,54,58,85,36,110,117,108,108,215,15,16,17,18,19,20,21,22,23,24,25,26,27,26,82,77,83,86,36,99,108,97,115,115,82,65,82,81,84,81,80,82,83,82,82,86,78,128,6,128,13,128,2,128,7,16,1,128,8,212,29,30,31,16,32,33,34,35,95,16,18,78,83,46,114,97,110,103,101,118,97,108,46,108,101,110,103,116,104,95,16,20,78,83,46,114,97,110,103,101,118,97,108,46,108,111,99,97,116,105,111,110,90,78,83,46,115,112,101,99,105,97,108,128,3,128,4,16,4,128,5,16,56,16,0,210,39,40,41,42,90,36,99,108,97,115,115,110,97,109,101,88,36,99,108,97,115,115,101,115,87,78,83,86,97,108,117,101,162,41,43,88,78,83,79,98,106,101,99,116,95,16,56,104,116,116,112,115,58,47,47,116,119,105,116,116,101,114,46,99,111,109,47,120,120,120,120,120,120,120,120,120,47,115,116,97,116,117,115,47,48,48,48,48,50,50,51,51,48,48,48,48,57,50,49,54,49,50,56,87,72,116,116,112,85,82,76,210,47,16,48,49,90,78,83,46,111,98,106,101,99,116,115,160,128,9,210,39,40,51,52,94,78,83,77,117,116,97,98,108,101,65,114,114,97,121,163,51,53,43,87,78,83,65,114,114,97,121,210,39,40,55,56,95,16,15,68,68,83,99,97,110,110,101,114,82,101,115,117,108,116,162,57,43,95,16,15,68,68,83,99,97,110,110,101,114,82,101,115,117,108,116,16,1,0,8,0,17,0,26,0,36,0,41,0,50,0,55,0,73,0,78,0,86,0,96,0,98,0,100,0,113,0,119,0,134,0,137,0,144,0,147,0,149,0,151,0,154,0,157,0,159,0,161,0,163,0,165,0,167,0,169,0,178,0,199,0,222,0,233,0,235,0,237,0,239,0,241,0,243,0,245,0,250,1,5,1,14,1,22,1,25,1,34,1,93,1,101,1,106,1,117,1,118,1,120,1,125,1,140,1,144,1,152,1,157,1,175,1,178,1,196,0,0,0,0,0,0,2,1,0,0,0,0,0,0,0,59,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,198]}]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMBaseWritingDirectionAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":-1}]]}],"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -1891,12 +2149,51 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let expected = r#"{"rowid":0,"guid":"","text":"Hi. Right now or tomorrow?","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSMutableString","version":1},[{"String":"Hi. Right now or tomorrow?"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":17}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":8}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMCalendarEventAttributeName"}]]},{"Object":[{"name":"NSMutableData","version":0},[{"SignedInteger":669}]]},{"Data":[{"Array":[98,112,108,105,115,116,48,48,212,1,2,3,4,5,6,7,12,88,36,118,101,114,115,105,111,110,89,36,97,114,99,104,105,118,101,114,84,36,116,111,112,88,36,111,98,106,101,99,116,115,18,0,1,134,160,95,16,15,78,83,75,101,121,101,100,65,114,99,104,105,118,101,114,210,8,9,10,11,87,118,101,114,115,105,111,110,89,100,100,45,114,101,115,117,108,116,128,16,128,1,175,16,17,13,14,29,37,38,39,45,46,47,52,60,64,65,68,72,73,77,85,36,110,117,108,108,215,15,16,17,18,19,20,21,22,23,24,25,26,27,28,82,77,83,86,36,99,108,97,115,115,82,65,82,81,84,81,80,82,83,82,82,86,78,128,6,128,15,128,2,128,7,16,0,128,8,16,1,212,30,31,32,16,33,34,35,36,95,16,18,78,83,46,114,97,110,103,101,118,97,108,46,108,101,110,103,116,104,95,16,20,78,83,46,114,97,110,103,101,118,97,108,46,108,111,99,97,116,105,111,110,90,78,83,46,115,112,101,99,105,97,108,128,3,128,4,16,4,128,5,16,8,16,17,210,40,41,42,43,90,36,99,108,97,115,115,110,97,109,101,88,36,99,108,97,115,115,101,115,87,78,83,86,97,108,117,101,162,42,44,88,78,83,79,98,106,101,99,116,88,116,111,109,111,114,114,111,119,84,68,97,116,101,210,48,16,49,51,90,78,83,46,111,98,106,101,99,116,115,161,50,128,9,128,13,216,15,16,53,17,18,19,20,21,22,23,56,57,58,28,59,28,81,86,128,6,128,15,128,14,128,10,128,11,128,12,212,30,31,32,16,33,34,35,36,128,3,128,4,128,5,91,82,101,108,97,116,105,118,101,68,97,121,210,48,16,66,51,160,128,13,210,40,41,69,70,94,78,83,77,117,116,97,98,108,101,65,114,114,97,121,163,69,71,44,87,78,83,65,114,114,97,121,81,49,210,40,41,74,75,95,16,15,68,68,83,99,97,110,110,101,114,82,101,115,117,108,116,162,76,44,95,16,15,68,68,83,99,97,110,110,101,114,82,101,115,117,108,116,16,1,0,8,0,17,0,26,0,36,0,41,0,50,0,55,0,73,0,78,0,86,0,96,0,98,0,100,0,120,0,126,0,141,0,144,0,151,0,154,0,156,0,158,0,161,0,164,0,166,0,168,0,170,0,172,0,174,0,176,0,178,0,187,0,208,0,231,0,242,0,244,0,246,0,248,0,250,0,252,0,254,1,3,1,14,1,23,1,31,1,34,1,43,1,52,1,57,1,62,1,73,1,75,1,77,1,79,1,96,1,98,1,100,1,102,1,104,1,106,1,108,1,110,1,119,1,121,1,123,1,125,1,137,1,142,1,143,1,145,1,150,1,165,1,169,1,177,1,179,1,184,1,202,1,205,1,223,0,0,0,0,0,0,2,1,0,0,0,0,0,0,0,78,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,225]}]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":1}]}],"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"Hi. Right now or tomorrow?","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSMutableString","version":1},[{"String":"Hi. Right now or tomorrow?"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":17}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":8}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMCalendarEventAttributeName"}]]},{"Object":[{"name":"NSMutableData","version":0},[{"SignedInteger":669}]]},{"Data":[{"Array":[98,112,108,105,115,116,48,48,212,1,2,3,4,5,6,7,12,88,36,118,101,114,115,105,111,110,89,36,97,114,99,104,105,118,101,114,84,36,116,111,112,88,36,111,98,106,101,99,116,115,18,0,1,134,160,95,16,15,78,83,75,101,121,101,100,65,114,99,104,105,118,101,114,210,8,9,10,11,87,118,101,114,115,105,111,110,89,100,100,45,114,101,115,117,108,116,128,16,128,1,175,16,17,13,14,29,37,38,39,45,46,47,52,60,64,65,68,72,73,77,85,36,110,117,108,108,215,15,16,17,18,19,20,21,22,23,24,25,26,27,28,82,77,83,86,36,99,108,97,115,115,82,65,82,81,84,81,80,82,83,82,82,86,78,128,6,128,15,128,2,128,7,16,0,128,8,16,1,212,30,31,32,16,33,34,35,36,95,16,18,78,83,46,114,97,110,103,101,118,97,108,46,108,101,110,103,116,104,95,16,20,78,83,46,114,97,110,103,101,118,97,108,46,108,111,99,97,116,105,111,110,90,78,83,46,115,112,101,99,105,97,108,128,3,128,4,16,4,128,5,16,8,16,17,210,40,41,42,43,90,36,99,108,97,115,115,110,97,109,101,88,36,99,108,97,115,115,101,115,87,78,83,86,97,108,117,101,162,42,44,88,78,83,79,98,106,101,99,116,88,116,111,109,111,114,114,111,119,84,68,97,116,101,210,48,16,49,51,90,78,83,46,111,98,106,101,99,116,115,161,50,128,9,128,13,216,15,16,53,17,18,19,20,21,22,23,56,57,58,28,59,28,81,86,128,6,128,15,128,14,128,10,128,11,128,12,212,30,31,32,16,33,34,35,36,128,3,128,4,128,5,91,82,101,108,97,116,105,118,101,68,97,121,210,48,16,66,51,160,128,13,210,40,41,69,70,94,78,83,77,117,116,97,98,108,101,65,114,114,97,121,163,69,71,44,87,78,83,65,114,114,97,121,81,49,210,40,41,74,75,95,16,15,68,68,83,99,97,110,110,101,114,82,101,115,117,108,116,162,76,44,95,16,15,68,68,83,99,97,110,110,101,114,82,101,115,117,108,116,16,1,0,8,0,17,0,26,0,36,0,41,0,50,0,55,0,73,0,78,0,86,0,96,0,98,0,100,0,120,0,126,0,141,0,144,0,151,0,154,0,156,0,158,0,161,0,164,0,166,0,168,0,170,0,172,0,174,0,176,0,178,0,187,0,208,0,231,0,242,0,244,0,246,0,248,0,250,0,252,0,254,1,3,1,14,1,23,1,31,1,34,1,43,1,52,1,57,1,62,1,73,1,75,1,77,1,79,1,96,1,98,1,100,1,102,1,104,1,106,1,108,1,110,1,119,1,121,1,123,1,125,1,137,1,142,1,143,1,145,1,150,1,165,1,169,1,177,1,179,1,184,1,202,1,205,1,223,0,0,0,0,0,0,2,1,0,0,0,0,0,0,0,78,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,225]}]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":1}]}],"edited_parts":null,"detected_data":[{"type":"event","value":"tomorrow"}]}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn can_format_json_audio_transcription() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = JSON::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.text = Some("\u{FFFC}".to_string());
+        message.chat_id = Some(0);
+
+        let typedstream_path = current_dir()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("imessage-database/test_data/typedstream/AudioTranscription");
+        let mut file = File::open(typedstream_path).unwrap();
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes).unwrap();
+
+        let mut parser = TypedStreamReader::from(&bytes);
+        message.components = parser.parse().ok();
+
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+
+        assert!(actual.ends_with(
+            r#""edited_parts":null,"audio_transcription":"Hey, I'm running late but I'll be there soon"}"#
+        ));
+    }
+
     #[test]
     fn can_format_json_text_effect_end_to_end() {
         // Set timezone to America/Los_Angeles for consistent Local time
@@ -1926,8 +2223,10 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let expected = r#"{"rowid":0,"guid":"","text":"Big small shake nod explode ripple bloom jitter","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSString","version":1},[{"String":"Big small shake nod explode ripple bloom jitter"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":3}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":5}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":1}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":6}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":11}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":4},{"UnsignedInteger":5}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":9}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":1}]},{"Data":[{"SignedInteger":5},{"UnsignedInteger":3}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":8}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":1}]},{"Data":[{"SignedInteger":6},{"UnsignedInteger":8}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":12}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":7},{"UnsignedInteger":6}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":4}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":6},{"UnsignedInteger":1}]},{"Data":[{"SignedInteger":8},{"UnsignedInteger":5}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":6}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":6},{"UnsignedInteger":1}]},{"Data":[{"SignedInteger":9},{"UnsignedInteger":6}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":10}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]}],"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"Big small shake nod explode ripple bloom jitter","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSString","version":1},[{"String":"Big small shake nod explode ripple bloom jitter"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":3}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":5}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":1}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":6}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":11}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":4},{"UnsignedInteger":5}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":9}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":1}]},{"Data":[{"SignedInteger":5},{"UnsignedInteger":3}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":8}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":1}]},{"Data":[{"SignedInteger":6},{"UnsignedInteger":8}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":12}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":7},{"UnsignedInteger":6}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":4}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":6},{"UnsignedInteger":1}]},{"Data":[{"SignedInteger":8},{"UnsignedInteger":5}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":6}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":6},{"UnsignedInteger":1}]},{"Data":[{"SignedInteger":9},{"UnsignedInteger":6}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":10}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]}],"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -1961,8 +2260,10 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let expected = r#"{"rowid":0,"guid":"","text":"Bold underline italic strikethrough all four","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSString","version":1},[{"String":"Bold underline italic strikethrough all four"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":4}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextBoldAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":1}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":9}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextUnderlineAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":1}]},{"Data":[{"SignedInteger":4},{"UnsignedInteger":6}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextItalicAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":1}]},{"Data":[{"SignedInteger":5},{"UnsignedInteger":13}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextStrikethroughAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":5}]},{"Data":[{"SignedInteger":6},{"UnsignedInteger":4}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":5}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextBoldAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextStrikethroughAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextUnderlineAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextItalicAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]}],"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"Bold underline italic strikethrough all four","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSString","version":1},[{"String":"Bold underline italic strikethrough all four"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":4}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextBoldAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":1}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":9}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextUnderlineAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":1}]},{"Data":[{"SignedInteger":4},{"UnsignedInteger":6}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextItalicAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":1}]},{"Data":[{"SignedInteger":5},{"UnsignedInteger":13}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextStrikethroughAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":5}]},{"Data":[{"SignedInteger":6},{"UnsignedInteger":4}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":5}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextBoldAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextStrikethroughAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextUnderlineAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextItalicAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]}],"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -1996,8 +2297,10 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let expected = r#"{"rowid":0,"guid":"","text":"Everything","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSString","version":1},[{"String":"Everything"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":10}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":5}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextBoldAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextStrikethroughAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextUnderlineAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextItalicAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]}],"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"Everything","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSString","version":1},[{"String":"Everything"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":10}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":5}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextBoldAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextStrikethroughAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextUnderlineAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextItalicAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]}],"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -2031,8 +2334,10 @@ mod text_effect_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let expected = r#"{"rowid":0,"guid":"","text":"Underline normal jitter normal","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSString","version":1},[{"String":"Underline normal jitter normal"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":9}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":3}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMBaseWritingDirectionAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":-1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextUnderlineAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":8}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMBaseWritingDirectionAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":-1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":6}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":3}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMBaseWritingDirectionAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":-1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":10}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":7}]}],"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"Underline normal jitter normal","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSString","version":1},[{"String":"Underline normal jitter normal"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":9}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":3}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMBaseWritingDirectionAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":-1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextUnderlineAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":8}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMBaseWritingDirectionAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":-1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":6}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":3}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMBaseWritingDirectionAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":-1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMTextEffectAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":10}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":7}]}],"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -2046,7 +2351,7 @@ mod edited_tests {
         io::Read,
     };
 
-    use super::tests::{blank, fake_config, fake_options};
+    use super::tests::{blank, document, fake_config, fake_options};
 
     use crate::{exporters::exporter::Writer, Exporter, JSON};
     use imessage_database::{
@@ -2106,8 +2411,10 @@ mod edited_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let expected = r#"{"rowid":0,"guid":"","text":"From arbitrary byte stream:\r￼To native Rust data structures:\r","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":674530231992568192,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSMutableString","version":1},[{"String":"From arbitrary byte stream:\r￼To native Rust data structures:\r"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":28}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":1}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMFileTransferGUIDAttributeName"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"D0551D89-4E11-43D0-9A0E-06F19704E97B"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":32}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":2}]]}],"edited_parts":{"parts":[{"status":"Original","edit_history":[]},{"status":"Original","edit_history":[]},{"status":"Original","edit_history":[]},{"status":"Unsent","edit_history":[]}]}}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"From arbitrary byte stream:\r￼To native Rust data structures:\r","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":674530231992568192,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSMutableString","version":1},[{"String":"From arbitrary byte stream:\r￼To native Rust data structures:\r"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":28}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":1}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMFileTransferGUIDAttributeName"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"D0551D89-4E11-43D0-9A0E-06F19704E97B"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":32}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":2}]]}],"edited_parts":{"parts":[{"status":"Original","edit_history":[],"diffs":[]},{"status":"Original","edit_history":[],"diffs":[]},{"status":"Original","edit_history":[],"diffs":[]},{"status":"Unsent","edit_history":[],"diffs":[]}]}}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -2143,8 +2450,10 @@ mod edited_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let expected = r#"{"rowid":0,"guid":"","text":"From arbitrary byte stream:\r￼To native Rust data structures:\r","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSMutableString","version":1},[{"String":"From arbitrary byte stream:\r￼To native Rust data structures:\r"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":28}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":1}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMFileTransferGUIDAttributeName"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"D0551D89-4E11-43D0-9A0E-06F19704E97B"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":32}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":2}]]}],"edited_parts":null}"#;
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let expected = r#"{"rowid":0,"guid":"","text":"From arbitrary byte stream:\r￼To native Rust data structures:\r","service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":0,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSMutableString","version":1},[{"String":"From arbitrary byte stream:\r￼To native Rust data structures:\r"}]]},{"Data":[{"SignedInteger":1},{"UnsignedInteger":28}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":0}]]},{"Data":[{"SignedInteger":2},{"UnsignedInteger":1}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":2}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMFileTransferGUIDAttributeName"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"D0551D89-4E11-43D0-9A0E-06F19704E97B"}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":1}]]},{"Data":[{"SignedInteger":3},{"UnsignedInteger":32}]},{"Object":[{"name":"NSDictionary","version":0},[{"SignedInteger":1}]]},{"Object":[{"name":"NSString","version":1},[{"String":"__kIMMessagePartAttributeName"}]]},{"Object":[{"name":"NSNumber","version":0},[{"SignedInteger":2}]]}],"edited_parts":null}"#;
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
 
         assert_eq!(expected, actual);
     }
@@ -2185,7 +2494,7 @@ mod edited_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":674530231992568192,"associated_message_emoji":null,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSString","version":1},[{"String":""}]]}],"edited_parts":{"parts":[{"status":"Unsent","edit_history":[]}]}}"#;
+        let expected = r#"{"rowid":0,"guid":"","text":null,"service":"iMessage","handle_id":0,"destination_caller_id":null,"subject":null,"date":674526582885055488,"date_read":0,"date_delivered":0,"is_from_me":true,"is_read":false,"item_type":0,"other_handle":0,"share_status":false,"share_direction":false,"group_title":null,"group_action_type":0,"associated_message_guid":null,"associated_message_type":0,"balloon_bundle_id":null,"expressive_send_style_id":null,"thread_originator_guid":null,"thread_originator_part":null,"date_edited":674530231992568192,"associated_message_emoji":null,"schedule_type":0,"schedule_state":0,"chat_id":0,"num_attachments":0,"deleted_from":null,"num_replies":0,"components":[{"Object":[{"name":"NSString","version":1},[{"String":""}]]}],"edited_parts":{"parts":[{"status":"Unsent","edit_history":[],"diffs":[]}]}}"#;
         let actual = exporter.format_announcement(&message);
 
         assert_eq!(expected, actual);
@@ -1,4 +1,5 @@
 pub mod exporter;
 pub mod html;
 pub mod json;
+pub mod template;
 pub mod txt;
@@ -0,0 +1,1214 @@
+/*!
+ Renders conversations with user-editable templates instead of the built-in `html` and `txt` exporters.
+
+ Each piece of a conversation, i.e. a message, an attachment, or a single type of app balloon, has its own template.
+ The default template set reproduces the look of the built-in exporters, and any template in the `--templates`
+ directory replaces the default template with the same name.
+*/
+
+use std::{
+    collections::{
+        hash_map::Entry::{Occupied, Vacant},
+        BTreeMap, HashMap,
+    },
+    fs::{read_to_string, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use indicatif::ProgressBar;
+use minijinja::{context, escape_formatter, AutoEscape, Environment, Error, Value};
+use rusqlite::Connection;
+
+use crate::{
+    app::{
+        error::RuntimeError,
+        export_type::ExportType,
+        file_layout::{PageLink, SplitPages},
+        handwriting_format::HandwritingFormat,
+        progress::build_progress_bar_export,
+        report::WarningKind,
+        runtime::Config,
+        sanitizers::{encode_calendar_event, encode_uri_component, sanitize_html},
+    },
+    exporters::{
+        exporter::Exporter,
+        html::{HTML, INDEX_FILE, STYLE},
+    },
+};
+
+use imessage_database::{
+    error::{export::ExportError, plist::PlistParseError},
+    export::{balloon::Balloon, format::Format},
+    message_types::{
+        edited::{EditStatus, EditedMessagePart},
+        expressives::{BubbleEffect, Expressive, ScreenEffect},
+        text_effects::{OwnedTextEffect, TextEffect},
+        variants::{Announcement, Variant},
+    },
+    tables::{
+        attachment::{Attachment, MediaType},
+        messages::{models::TextAttributes, Message},
+        table::{FITNESS_RECEIVER, ME, ORPHANED, YOU},
+    },
+    util::dates::{format, get_local_time, readable_diff, TIMESTAMP_FACTOR},
+};
+
+/// Expand to the name and built-in source of every template for a file extension
+macro_rules! builtin_templates {
+    ($ext:literal) => {
+        builtin_templates!(
+            $ext,
+            "page",
+            "message",
+            "reply",
+            "text",
+            "edited",
+            "attachment",
+            "missing_attachment",
+            "tapback",
+            "announcement",
+            "balloon_error",
+            "balloon_url",
+            "balloon_music",
+            "balloon_app_store",
+            "balloon_collaboration",
+            "balloon_placemark",
+            "balloon_handwriting",
+            "balloon_digital_touch",
+            "balloon_apple_pay",
+            "balloon_fitness",
+            "balloon_slideshow",
+            "balloon_check_in",
+            "balloon_find_my",
+            "balloon_app"
+        )
+    };
+    ($ext:literal, $($name:literal),+) => {
+        [$((
+            $name,
+            include_str!(concat!("resources/templates/", $ext, "/", $name, ".", $ext)),
+        )),+]
+    };
+}
+
+/// The built-in templates used to render `html` exports
+const HTML_TEMPLATES: [(&str, &str); 23] = builtin_templates!("html");
+/// The built-in templates used to render `txt` exports
+const TXT_TEMPLATES: [(&str, &str); 23] = builtin_templates!("txt");
+
+/// Marks where the conversation goes when the page template is split into a header and a footer
+const BODY_MARKER: &str = "\u{0}body\u{0}";
+
+/// Get the built-in templates for an export type, or `None` if the export type is not rendered with templates
+fn builtin(export_type: &ExportType) -> Option<&'static [(&'static str, &'static str)]> {
+    match export_type {
+        ExportType::Html => Some(&HTML_TEMPLATES),
+        ExportType::Txt => Some(&TXT_TEMPLATES),
+        ExportType::Json => None,
+    }
+}
+
+/// Read the templates in `dir` that replace a built-in template for the export type, keyed by template name
+///
+/// Templates are named after the piece of the conversation they render, i.e. `message.html` or `tapback.txt`.
+pub fn read_templates(
+    dir: &Path,
+    export_type: &ExportType,
+) -> Result<HashMap<String, String>, RuntimeError> {
+    let mut templates = HashMap::new();
+    for (name, _) in builtin(export_type).unwrap_or_default() {
+        let path = dir.join(format!("{name}{}", export_type.extension()));
+        if path.exists() {
+            let source =
+                read_to_string(&path).map_err(|err| RuntimeError::CreateError(err, path))?;
+            templates.insert(name.to_string(), source);
+        }
+    }
+    Ok(templates)
+}
+
+/// A message whose body is being rendered
+struct Pending {
+    /// Data about the message itself, i.e. the sender and timestamp
+    message: Value,
+    /// The rendered parts of the message body, in order
+    parts: Vec<PendingPart>,
+}
+
+/// A single part of a message body, along with its tapbacks and replies
+#[derive(Default)]
+struct PendingPart {
+    /// The rendered content of the part, if the part has any content
+    content: Option<String>,
+    /// The rendered tapbacks that react to the part
+    tapbacks: Vec<String>,
+    /// The rendered replies to the part
+    replies: Vec<String>,
+}
+
+impl PendingPart {
+    fn to_value(&self) -> Value {
+        context! {
+            content => self.content.clone().map(Value::from_safe_string),
+            tapbacks => self.tapbacks.iter().cloned().map(Value::from_safe_string).collect::<Vec<_>>(),
+            replies => self.replies.iter().cloned().map(Value::from_safe_string).collect::<Vec<_>>(),
+        }
+    }
+}
+
+pub struct Templated<'a> {
+    /// Data that is setup from the application's runtime
+    pub config: &'a Config,
+    /// The templates used to render each piece of a conversation
+    env: Environment<'a>,
+    /// The type of file the templates render
+    export_type: &'a ExportType,
+    /// Handles to files we want to write messages to
+    /// Map of resolved chatroom file location to a buffered writer
+    pub files: HashMap<String, BufWriter<File>>,
+    /// Map of resolved chatroom file location to the title and data used to render its page
+    page_contexts: HashMap<String, (String, Value)>,
+    /// Map of exported file names to the chatroom they were created for
+    conversations: BTreeMap<String, i32>,
+    /// Files that conversations were split into, so each can link to the ones before and after it
+    pages: SplitPages,
+    /// Writer instance for orphaned messages
+    pub orphaned: BufWriter<File>,
+    /// The end of the page for orphaned messages
+    orphaned_footer: String,
+    /// Messages that have started rendering but not finished, outermost first
+    stack: Vec<Pending>,
+    /// Progress through the messages table
+    pb: ProgressBar,
+    /// The number of top-level messages and announcements rendered so far
+    current_message: u64,
+}
+
+impl<'a> Exporter<'a> for Templated<'a> {
+    fn new(config: &'a Config) -> Result<Self, RuntimeError> {
+        let export_type = config
+            .options
+            .export_type
+            .as_ref()
+            .filter(|export_type| builtin(export_type).is_some())
+            .ok_or(RuntimeError::InvalidOptions(String::from(
+                "Templates are only supported for `html` and `txt`!",
+            )))?;
+
+        let mut env = Environment::new();
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        env.set_keep_trailing_newline(true);
+        let escape = match export_type {
+            ExportType::Html => AutoEscape::Html,
+            _ => AutoEscape::None,
+        };
+        env.set_auto_escape_callback(move |_| escape);
+        // Escape text the same way as the built-in `html` exporter
+        env.set_formatter(
+            |out, state, value| match (state.auto_escape(), value.as_str()) {
+                (AutoEscape::Html, Some(text)) if !value.is_safe() => {
+                    out.write_str(&sanitize_html(text)).map_err(Error::from)
+                }
+                _ => escape_formatter(out, state, value),
+            },
+        );
+        env.add_global("lazy", !config.options.no_lazy);
+        env.add_global("embedded", config.options.embed.is_some());
+
+        for (name, source) in builtin(export_type).unwrap_or_default() {
+            let source = config.templates.get(*name).map_or(*source, String::as_str);
+            env.add_template(name, source)
+                .map_err(|why| RuntimeError::TemplateError(name.to_string(), why))?;
+        }
+
+        let mut orphaned = config.options.export_path.clone();
+        orphaned.push(ORPHANED);
+        orphaned.set_extension(export_type.to_string());
+        let file = File::options()
+            .append(true)
+            .create(true)
+            .open(&orphaned)
+            .map_err(|err| RuntimeError::CreateError(err, orphaned))?;
+
+        let mut templated = Templated {
+            config,
+            env,
+            export_type,
+            files: HashMap::new(),
+            page_contexts: HashMap::new(),
+            conversations: BTreeMap::new(),
+            pages: SplitPages::default(),
+            orphaned: BufWriter::new(file),
+            orphaned_footer: String::new(),
+            stack: vec![],
+            pb: ProgressBar::hidden(),
+            current_message: 0,
+        };
+
+        // Fail before the export starts if the page template cannot be used
+        let (header, footer) = templated
+            .render_page(ORPHANED, context! {})
+            .map_err(RuntimeError::ExportError)?;
+        templated
+            .orphaned
+            .write_all(header.as_bytes())
+            .map_err(RuntimeError::DiskError)?;
+        templated.orphaned_footer = footer;
+
+        Ok(templated)
+    }
+
+    /// Create a file for the given chat, caching it so we don't need to build it later
+    fn get_or_create_file(
+        &mut self,
+        message: &Message,
+    ) -> Result<&mut BufWriter<File>, RuntimeError> {
+        match self.config.conversation(message) {
+            Some((chatroom, _)) => {
+                let filename = self.config.file_path(chatroom, message);
+                match self.files.entry(filename) {
+                    Occupied(entry) => Ok(entry.into_mut()),
+                    Vacant(entry) => {
+                        let path = self
+                            .config
+                            .export_file_path(entry.key(), &self.export_type.to_string())?;
+
+                        // If the file already exists, don't write the header again
+                        // This can happen if multiple chats use the same group name
+                        let file_exists = path.exists();
+
+                        let file = File::options()
+                            .append(true)
+                            .create(true)
+                            .open(&path)
+                            .map_err(|err| RuntimeError::CreateError(err, path))?;
+                        let mut buf = BufWriter::new(file);
+
+                        let title = entry
+                            .key()
+                            .strip_suffix(self.export_type.extension())
+                            .unwrap_or(entry.key())
+                            .to_string();
+                        let page = context! {
+                            name => chatroom.display_name(),
+                            avatar => self
+                                .config
+                                .current_group_photo(chatroom.rowid)
+                                .and_then(|change| self.config.group_photo(&change)),
+                        };
+                        let (header, _) =
+                            Self::split_page(&self.env, self.export_type, &title, page.clone())
+                                .map_err(RuntimeError::ExportError)?;
+                        if !file_exists {
+                            buf.write_all(header.as_bytes())
+                                .map_err(RuntimeError::DiskError)?;
+                        }
+                        self.page_contexts
+                            .insert(entry.key().clone(), (title, page));
+
+                        // Remember the file so it can be linked from the index
+                        self.conversations
+                            .insert(entry.key().clone(), chatroom.rowid);
+
+                        // Remember which period the file contains so it can be linked to the files around it
+                        if let Some(period) = self
+                            .config
+                            .options
+                            .split_by
+                            .and_then(|split| split.period(message, &self.config.offset))
+                        {
+                            self.pages
+                                .insert(entry.key().clone(), chatroom.rowid, period);
+                        }
+
+                        Ok(entry.insert(buf))
+                    }
+                }
+            }
+            None => Ok(&mut self.orphaned),
+        }
+    }
+}
+
+impl<'a> Templated<'a> {
+    /// Write the page that links to every exported conversation
+    fn write_index(&self) -> Result<(), RuntimeError> {
+        let index = HTML::format_index(self.config, &self.conversations);
+        let (header, footer) = self
+            .render_page("Conversations", context! {})
+            .map_err(RuntimeError::ExportError)?;
+
+        let path = self.config.options.export_path.join(INDEX_FILE);
+        let mut file = File::create(&path).map_err(|err| RuntimeError::CreateError(err, path))?;
+        file.write_all(format!("{header}{index}{footer}").as_bytes())
+            .map_err(RuntimeError::DiskError)
+    }
+
+    /// Render a template with the given context
+    fn render(&self, name: &str, ctx: Value) -> Result<String, ExportError> {
+        Self::render_with(&self.env, name, ctx)
+    }
+
+    fn render_with(env: &Environment, name: &str, ctx: Value) -> Result<String, ExportError> {
+        env.get_template(name)
+            .and_then(|template| template.render(ctx))
+            .map_err(|why| ExportError::Format(format!("{why:#}")))
+    }
+
+    /// Render a template whose output is placed inside another template, without the newline that ends the template
+    fn render_fragment(&self, name: &str, ctx: Value) -> Result<String, ExportError> {
+        self.render(name, ctx)
+            .map(|rendered| rendered.strip_suffix('\n').unwrap_or(&rendered).to_string())
+    }
+
+    /// Render the page a conversation is written to, split into the parts before and after the conversation
+    fn render_page(&self, title: &str, page: Value) -> Result<(String, String), ExportError> {
+        Self::split_page(&self.env, self.export_type, title, page)
+    }
+
+    fn split_page(
+        env: &Environment,
+        export_type: &ExportType,
+        title: &str,
+        page: Value,
+    ) -> Result<(String, String), ExportError> {
+        let style = match export_type {
+            ExportType::Html => Some(Value::from_safe_string(STYLE.to_string())),
+            _ => None,
+        };
+        let rendered = Self::render_with(
+            env,
+            "page",
+            context! {
+                title,
+                style,
+                body => Value::from_safe_string(BODY_MARKER.to_string()),
+                ..page
+            },
+        )?;
+        rendered
+            .split_once(BODY_MARKER)
+            .map(|(header, footer)| (header.to_string(), footer.to_string()))
+            .ok_or(ExportError::Format(String::from(
+                "The page template must render `{{ body }}`",
+            )))
+    }
+
+    /// Write a rendered message or announcement to the file for its conversation
+    fn write(&mut self, message: &Message, rendered: &str) -> Result<(), ExportError> {
+        let file = self
+            .get_or_create_file(message)
+            .map_err(|why| ExportError::Format(why.to_string()))?;
+        file.write_all(rendered.as_bytes())?;
+
+        self.current_message += 1;
+        if self.current_message.is_multiple_of(99) {
+            self.pb.set_position(self.current_message);
+        }
+        Ok(())
+    }
+
+    /// Add rendered content to the part of the message that is being rendered
+    fn push_content(&mut self, content: String) {
+        if let Some(part) = self.stack.last_mut().and_then(|m| m.parts.last_mut()) {
+            part.content = Some(content);
+        }
+    }
+
+    /// Get the name of the sender of a message
+    fn who<'b>(&'b self, message: &'b Message) -> &'b str {
+        self.config.who(
+            message.handle_id,
+            message.is_from_me(),
+            &message.destination_caller_id,
+        )
+    }
+
+    /// Build the data available to the `message` template, except for the parts of the message body
+    fn message_context(&self, message: &Message, depth: usize) -> Value {
+        let read_after = message
+            .time_until_read(&self.config.offset)
+            .filter(|time| !time.is_empty())
+            .map(|time| {
+                let who = if message.is_from_me() {
+                    "them"
+                } else {
+                    self.config.options.custom_name.as_deref().unwrap_or("you")
+                };
+                format!("Read by {who} after {time}")
+            });
+
+        let shared_location = if message.started_sharing_location() {
+            Some("Started sharing location!")
+        } else if message.stopped_sharing_location() {
+            Some("Stopped sharing location!")
+        } else {
+            None
+        };
+
+        context! {
+            guid => message.guid,
+            sender => self.who(message),
+            is_from_me => message.is_from_me(),
+            service => format!("{:?}", message.service()),
+            timestamp => format(&message.date(&self.config.offset)),
+            read_after,
+            subject => message.subject,
+            deleted => message.is_deleted().then(|| self.config.deletion_note(message)),
+            scheduled => self.config.schedule_note(message),
+            expressive => expressive(message),
+            shareplay => message.is_shareplay(),
+            shared_location,
+            is_reply => message.is_reply(),
+            depth,
+        }
+    }
+
+    /// Copy an attachment, if requested, and build the data available to the `attachment` template
+    fn attachment_context(&self, message: &Message, attachment: &Attachment) -> Value {
+        let mut attachment = attachment.clone();
+        let found = self
+            .config
+            .options
+            .attachment_manager
+            .handle_attachment(message, &mut attachment, self.config)
+            .is_some();
+        let path = found
+            .then(|| self.config.attachment_src(&attachment, message))
+            .flatten();
+        // Embedded files are `data:` URIs, so text that names the file uses its location instead
+        let location = found.then(|| self.config.message_attachment_path(&attachment));
+
+        let (kind, media_type) = match attachment.mime_type() {
+            MediaType::Image(media_type) => ("image", Some(media_type)),
+            MediaType::Video(media_type) => ("video", Some(media_type)),
+            MediaType::Audio(media_type) => ("audio", Some(media_type)),
+            MediaType::Text(media_type) => ("text", Some(media_type)),
+            MediaType::Application(media_type) => ("application", Some(media_type)),
+            MediaType::Other(media_type) => ("other", Some(media_type)),
+            MediaType::Unknown => ("unknown", None),
+        };
+
+        let sticker_effect = if attachment.is_sticker {
+            attachment
+                .get_sticker_effect(
+                    &self.config.options.platform,
+                    &self.config.options.db_path,
+                    self.config.options.attachment_root.as_deref(),
+                )
+                .ok()
+                .flatten()
+                .map(|effect| effect.to_string())
+        } else {
+            None
+        };
+
+        context! {
+            sender => self.who(message),
+            attachment => context! {
+                path,
+                location,
+                too_large => found && path.is_none(),
+                filename => attachment.filename(),
+                media_type,
+                kind,
+                size => attachment.file_size(),
+                is_sticker => attachment.is_sticker,
+                sticker_effect,
+                transcription => (kind == "audio")
+                    .then(|| message.audio_transcription())
+                    .flatten(),
+            },
+        }
+    }
+
+    /// Build the template name and data used to render an app balloon
+    fn balloon_context(&self, message: &Message, balloon: &Balloon) -> (&'static str, Value) {
+        match balloon {
+            Balloon::URL(balloon) => (
+                "balloon_url",
+                context! {
+                    balloon,
+                    url => balloon.get_url().or(message.text.as_deref()),
+                },
+            ),
+            Balloon::Music(balloon) => ("balloon_music", context! { balloon }),
+            Balloon::AppStore(balloon) => ("balloon_app_store", context! { balloon }),
+            Balloon::Collaboration(balloon) => (
+                "balloon_collaboration",
+                context! {
+                    balloon,
+                    url => balloon.get_url(),
+                },
+            ),
+            Balloon::Placemark(balloon) => (
+                "balloon_placemark",
+                context! {
+                    balloon,
+                    url => balloon.get_url(),
+                },
+            ),
+            Balloon::Handwriting(balloon) => {
+                // Text exports always link the rendered image, like the built-in exporter
+                let image = match (self.export_type, &self.config.options.handwriting_format) {
+                    (ExportType::Txt, _) | (_, HandwritingFormat::Png) => self
+                        .config
+                        .options
+                        .attachment_manager
+                        .handle_handwriting(message, balloon, self.config)
+                        .and_then(|filepath| self.config.file_src(filepath, "image/png", message)),
+                    _ => None,
+                };
+                let svg = match self.config.options.handwriting_format {
+                    HandwritingFormat::Animated => balloon.render_svg_animated(),
+                    _ => balloon.render_svg(),
+                };
+                (
+                    "balloon_handwriting",
+                    context! {
+                        image,
+                        svg => Value::from_safe_string(svg),
+                        ascii => balloon.render_ascii(40),
+                    },
+                )
+            }
+            Balloon::DigitalTouch(balloon) => (
+                "balloon_digital_touch",
+                context! {
+                    svg => balloon.render_svg().map(Value::from_safe_string),
+                    description => balloon.describe(),
+                },
+            ),
+            Balloon::ApplePay(balloon) => ("balloon_apple_pay", context! { balloon }),
+            Balloon::Fitness(balloon) => ("balloon_fitness", context! { balloon }),
+            Balloon::Slideshow(balloon) => ("balloon_slideshow", context! { balloon }),
+            Balloon::CheckIn(balloon) => {
+                let metadata = balloon.parse_query_string();
+                let check_in = [
+                    ("estimatedEndTime", "expected"),
+                    ("triggerTime", "expired"),
+                    ("sendDate", "accepted"),
+                ]
+                .into_iter()
+                .find_map(|(key, state)| {
+                    let date_stamp =
+                        metadata.get(key)?.parse::<f64>().unwrap_or(0.) as i64 * TIMESTAMP_FACTOR;
+                    Some(context! {
+                        state,
+                        time => format(&get_local_time(&date_stamp, &0)),
+                    })
+                });
+                (
+                    "balloon_check_in",
+                    context! {
+                        balloon,
+                        check_in,
+                    },
+                )
+            }
+            Balloon::FindMy(balloon) => ("balloon_find_my", context! { balloon }),
+            Balloon::Application(bundle_id, balloon) => {
+                // Apps without their own image use the first attachment instead
+                let attachment = match self.export_type {
+                    ExportType::Html if balloon.image.is_none() => {
+                        Attachment::from_message(&self.config.db, message)
+                            .unwrap_or_default()
+                            .iter()
+                            .find(|attachment| !attachment.is_genmoji())
+                            .and_then(|attachment| {
+                                self.attachment_context(message, attachment)
+                                    .get_attr("attachment")
+                                    .ok()
+                            })
+                    }
+                    _ => None,
+                };
+                (
+                    "balloon_app",
+                    context! {
+                        balloon,
+                        bundle_id,
+                        attachment,
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl Format for Templated<'_> {
+    fn start(&mut self, _: &Connection) -> Result<(), ExportError> {
+        // Tell the user what we are doing
+        eprintln!(
+            "Exporting to {} as {} with templates...",
+            self.config.options.export_path.display(),
+            self.export_type
+        );
+
+        // Set up progress bar
+        let total_messages =
+            Message::get_count(&self.config.db, &self.config.options.query_context)?;
+        self.pb = build_progress_bar_export(total_messages);
+        Ok(())
+    }
+
+    fn start_message(&mut self, message: &Message, depth: usize) -> Result<(), ExportError> {
+        if depth == 0 {
+            self.config.log_edits(message);
+            self.config.link_from(message);
+        }
+        let pending = Pending {
+            message: self.message_context(message, depth),
+            parts: vec![],
+        };
+        self.stack.push(pending);
+        Ok(())
+    }
+
+    fn start_part(&mut self, _: &Message, _: usize) -> Result<(), ExportError> {
+        if let Some(pending) = self.stack.last_mut() {
+            pending.parts.push(PendingPart::default());
+        }
+        Ok(())
+    }
+
+    fn text(
+        &mut self,
+        message: &Message,
+        text: &str,
+        attributes: &[TextAttributes],
+        genmoji: &[Attachment],
+    ) -> Result<(), ExportError> {
+        // Fitness messages have a prefix that we need to replace with the opposite if who sent the message
+        let is_fitness = text.starts_with(FITNESS_RECEIVER);
+        let mut genmoji = genmoji.iter();
+
+        let mut segments: Vec<Value> = attributes
+            .iter()
+            .filter_map(|attr| {
+                // Only Genmoji attributes consume an image, so the images stay aligned with their text
+                if let TextEffect::Genmoji(_) = attr.effect {
+                    if let Some(image) = genmoji.next() {
+                        // Text exports only describe Genmoji, so the image is not copied
+                        let genmoji = match self.export_type {
+                            ExportType::Html => self
+                                .attachment_context(message, image)
+                                .get_attr("attachment")
+                                .unwrap_or_default(),
+                            _ => Value::UNDEFINED,
+                        };
+                        return Some(context! {
+                            text => image.genmoji_description(),
+                            genmoji,
+                        });
+                    }
+                }
+                let content = text.get(attr.start..attr.end)?;
+                let href = match &attr.effect {
+                    TextEffect::Link(url) => Some(url.to_string()),
+                    TextEffect::Address(address) => Some(format!(
+                        "geo:0,0?q={}",
+                        encode_uri_component(address)
+                    )),
+                    TextEffect::Event(event) => Some(encode_calendar_event(event)),
+                    TextEffect::PhoneNumber(number) => Some(format!(
+                        "tel:{}",
+                        number
+                            .chars()
+                            .filter(|c| c.is_ascii_digit() || *c == '+')
+                            .collect::<String>()
+                    )),
+                    _ => None,
+                };
+                Some(context! {
+                    text => if is_fitness { content.replace(FITNESS_RECEIVER, YOU) } else { content.to_string() },
+                    effect => OwnedTextEffect::from(&attr.effect),
+                    href,
+                })
+            })
+            .collect();
+
+        // If we failed to parse any text above, use the original text
+        if segments.is_empty() {
+            segments.push(context! { text });
+        }
+
+        let rendered = self.render_fragment("text", context! { text, segments })?;
+        self.push_content(rendered);
+        Ok(())
+    }
+
+    fn edited(
+        &mut self,
+        message: &Message,
+        part: &EditedMessagePart,
+        _: usize,
+    ) -> Result<(), ExportError> {
+        let rendered = match part.status {
+            EditStatus::Edited => {
+                let diffs = part.diffs();
+                let mut previous_timestamp: Option<&i64> = None;
+                let history: Vec<Value> = part
+                    .edit_history
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, event)| {
+                        // Subsequent edits get a relative timestamp
+                        let after = previous_timestamp.and_then(|prev| {
+                            readable_diff(
+                                get_local_time(prev, &self.config.offset),
+                                get_local_time(&event.date, &self.config.offset),
+                            )
+                        });
+                        previous_timestamp = Some(&event.date);
+                        context! {
+                            text => event.text,
+                            timestamp => format(&get_local_time(&event.date, &self.config.offset)),
+                            after,
+                            spans => idx
+                                .checked_sub(1)
+                                .and_then(|prev| diffs.get(prev))
+                                .map(|diff| &diff.spans),
+                        }
+                    })
+                    .collect();
+                self.render_fragment("edited", context! { status => "edited", history })?
+            }
+            EditStatus::Unsent => {
+                let sender = if message.is_from_me() {
+                    self.config.options.custom_name.as_deref().unwrap_or(YOU)
+                } else {
+                    self.who(message)
+                };
+                let unsent_after = readable_diff(
+                    message.date(&self.config.offset),
+                    message.date_edited(&self.config.offset),
+                );
+                self.render_fragment(
+                    "edited",
+                    context! {
+                        status => "unsent",
+                        sender,
+                        is_from_me => message.is_from_me(),
+                        unsent_after,
+                    },
+                )?
+            }
+            EditStatus::Original => return Ok(()),
+        };
+        self.push_content(rendered);
+        Ok(())
+    }
+
+    fn attachment(
+        &mut self,
+        message: &Message,
+        attachment: &Attachment,
+    ) -> Result<(), ExportError> {
+        let rendered =
+            self.render_fragment("attachment", self.attachment_context(message, attachment))?;
+        self.push_content(rendered);
+        Ok(())
+    }
+
+    fn missing_attachment(&mut self, message: &Message, idx: usize) -> Result<(), ExportError> {
+        self.config.warn(
+            message,
+            WarningKind::MissingAttachment,
+            format!("Attachment {idx} does not exist!"),
+        );
+        let rendered = self.render_fragment("missing_attachment", context! { index => idx })?;
+        self.push_content(rendered);
+        Ok(())
+    }
+
+    fn balloon(&mut self, message: &Message, balloon: &Balloon) -> Result<(), ExportError> {
+        let (name, ctx) = self.balloon_context(message, balloon);
+        let rendered = self.render_fragment(name, ctx)?;
+        self.push_content(rendered);
+        Ok(())
+    }
+
+    fn balloon_error(
+        &mut self,
+        message: &Message,
+        why: &PlistParseError,
+    ) -> Result<(), ExportError> {
+        let rendered = match (why, &message.text) {
+            // Sometimes, URL messages are missing their payloads
+            (PlistParseError::NoPayload, Some(text)) if message.is_url() => {
+                self.render_fragment("balloon_url", context! { url => text })?
+            }
+            _ => {
+                self.config.warn(
+                    message,
+                    WarningKind::Balloon,
+                    format!("Unable to format {:?} message: {why}", message.variant()),
+                );
+                self.render_fragment("balloon_error", context! { error => why.to_string() })?
+            }
+        };
+        self.push_content(rendered);
+        Ok(())
+    }
+
+    fn tapbacks(&mut self, _: &Message, _: usize, tapbacks: &[Message]) -> Result<(), ExportError> {
+        let mut rendered = vec![];
+        for tapback in tapbacks {
+            let sender = self.who(tapback);
+            match tapback.variant() {
+                Variant::Tapback(_, true, kind) => rendered.push(self.render_fragment(
+                    "tapback",
+                    context! { tapback => kind.to_string(), sender },
+                )?),
+                Variant::Sticker(_) => {
+                    // Sticker messages have only one attachment, the sticker image
+                    let sticker = match Attachment::from_message(&self.config.db, tapback)?.first()
+                    {
+                        Some(sticker) => Some(Value::from_safe_string(self.render_fragment(
+                            "attachment",
+                            context! {
+                                in_tapback => true,
+                                ..self.attachment_context(tapback, sticker)
+                            },
+                        )?)),
+                        None => None,
+                    };
+                    rendered.push(self.render_fragment(
+                        "tapback",
+                        context! { tapback => "Sticker", sender, sticker },
+                    )?);
+                }
+                _ => {}
+            }
+        }
+        if let Some(part) = self.stack.last_mut().and_then(|m| m.parts.last_mut()) {
+            part.tapbacks.extend(rendered);
+        }
+        Ok(())
+    }
+
+    fn end_message(&mut self, message: &Message, _: usize) -> Result<(), ExportError> {
+        let Some(pending) = self.stack.pop() else {
+            return Ok(());
+        };
+        let parts: Vec<Value> = pending.parts.iter().map(PendingPart::to_value).collect();
+        let ctx = context! { message => context! { parts, ..pending.message } };
+
+        if self.stack.is_empty() {
+            let rendered = self.render("message", ctx)?;
+            return self.write(message, &rendered);
+        }
+
+        let rendered = self.render_fragment("message", ctx)?;
+        let reply = self.render_fragment(
+            "reply",
+            context! {
+                guid => message.guid,
+                message => Value::from_safe_string(rendered),
+            },
+        )?;
+        if let Some(part) = self.stack.last_mut().and_then(|m| m.parts.last_mut()) {
+            part.replies.push(reply);
+        }
+        Ok(())
+    }
+
+    fn announcement(&mut self, message: &Message) -> Result<(), ExportError> {
+        self.config.link_from(message);
+        let mut sender = self.who(message);
+        // Rename yourself so we render the proper grammar here
+        if sender == ME {
+            sender = self.config.options.custom_name.as_deref().unwrap_or(YOU);
+        }
+
+        let (kind, name, subject, code) = match message.get_announcement() {
+            Some(Announcement::NameChange(name)) => ("name_change", Some(name), None, None),
+            Some(Announcement::PhotoChange) => ("photo_change", None, None, None),
+            Some(Announcement::Unknown(code)) => ("unknown", None, None, Some(*code)),
+            Some(Announcement::FullyUnsent) => ("fully_unsent", None, None, None),
+            Some(Announcement::ParticipantAdded(id)) => (
+                "participant_added",
+                None,
+                Some(self.config.announcement_subject(*id)),
+                None,
+            ),
+            Some(Announcement::ParticipantRemoved(id)) => (
+                "participant_removed",
+                None,
+                Some(self.config.announcement_subject(*id)),
+                None,
+            ),
+            Some(Announcement::ParticipantLeft) => ("participant_left", None, None, None),
+            None => ("invalid", None, None, None),
+        };
+        let photo = (kind == "photo_change")
+            .then(|| self.config.group_photo(message))
+            .flatten();
+
+        let rendered = self.render(
+            "announcement",
+            context! {
+                timestamp => format(&message.date(&self.config.offset)),
+                sender,
+                kind,
+                name,
+                subject,
+                code,
+                photo,
+            },
+        )?;
+        self.write(message, &rendered)
+    }
+
+    fn finish(&mut self) -> Result<(), ExportError> {
+        self.pb.finish();
+
+        eprintln!("Writing {} footers...", self.export_type);
+        for (filename, buf) in self.files.iter_mut() {
+            if let Some((title, page)) = self.page_contexts.get(filename) {
+                // The files around a split file are only known once every message is written
+                let (previous, next) = self.pages.adjacent(filename);
+                let link = |link: PageLink| context! { period => link.period, href => link.href };
+                let page = context! {
+                    previous => previous.map(link),
+                    next => next.map(link),
+                    ..page.clone()
+                };
+                let (_, footer) = Self::split_page(&self.env, self.export_type, title, page)?;
+                buf.write_all(footer.as_bytes())?;
+            }
+        }
+        self.orphaned.write_all(self.orphaned_footer.as_bytes())?;
+
+        if let ExportType::Html = self.export_type {
+            self.write_index()
+                .map_err(|why| ExportError::Format(why.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Describe the expressive a message was sent with, if any
+fn expressive(message: &Message) -> Option<&str> {
+    match message.get_expressive() {
+        Expressive::Screen(effect) => Some(match effect {
+            ScreenEffect::Confetti => "Sent with Confetti",
+            ScreenEffect::Echo => "Sent with Echo",
+            ScreenEffect::Fireworks => "Sent with Fireworks",
+            ScreenEffect::Balloons => "Sent with Balloons",
+            ScreenEffect::Heart => "Sent with Heart",
+            ScreenEffect::Lasers => "Sent with Lasers",
+            ScreenEffect::ShootingStar => "Sent with Shooting Star",
+            ScreenEffect::Sparkles => "Sent with Sparkles",
+            ScreenEffect::Spotlight => "Sent with Spotlight",
+        }),
+        Expressive::Bubble(effect) => Some(match effect {
+            BubbleEffect::Slam => "Sent with Slam",
+            BubbleEffect::Loud => "Sent with Loud",
+            BubbleEffect::Gentle => "Sent with Gentle",
+            BubbleEffect::InvisibleInk => "Sent with Invisible Ink",
+        }),
+        Expressive::Unknown(effect) => Some(effect),
+        Expressive::None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        collections::HashMap,
+        fs::{create_dir_all, write},
+        path::PathBuf,
+    };
+
+    use chrono::Local;
+
+    use minijinja::context;
+
+    use crate::{
+        app::{
+            attachment_manager::AttachmentManager, edits::EditReport, export_type::ExportType,
+            handwriting_format::HandwritingFormat, report::Report,
+        },
+        exporters::template::read_templates,
+        Config, Exporter, Options, Templated,
+    };
+    use imessage_database::{
+        export::tapbacks::TapbackStore,
+        message_types::handwriting::RasterOptions,
+        tables::{store::CacheStore, table::get_connection},
+        util::{dates::get_offset, platform::Platform, query_context::QueryContext},
+    };
+    use imessage_fixtures::Fixture;
+
+    fn fake_options(export_type: ExportType) -> Options {
+        Options {
+            db_path: Fixture::shared().to_path_buf(),
+            attachment_root: None,
+            attachment_manager: AttachmentManager::Disabled,
+            diagnostic: false,
+            export_type: Some(export_type),
+            format: Some(export_type.to_string()),
+            export_path: PathBuf::from("/tmp"),
+            query_context: QueryContext::default(),
+            no_lazy: false,
+            custom_name: None,
+            use_caller_id: false,
+            platform: Platform::macOS,
+            ignore_disk_space: false,
+            memory_limit: None,
+            strict: false,
+            handwriting_format: HandwritingFormat::default(),
+            handwriting_raster: RasterOptions::default(),
+            edit_report: false,
+            dump_typedstream: None,
+            templates: None,
+            file_name: None,
+            split_by: None,
+            embed: None,
+        }
+    }
+
+    fn fake_config(options: Options, templates: HashMap<String, String>) -> Config {
+        let db = get_connection(&options.get_db_path()).unwrap();
+        Config {
+            chatrooms: HashMap::new(),
+            real_chatrooms: HashMap::new(),
+            chatroom_participants: HashMap::new(),
+            memberships: CacheStore::Memory(HashMap::new()),
+            group_photos: CacheStore::Memory(HashMap::new()),
+            deleted: CacheStore::Memory(HashMap::new()),
+            participants: HashMap::new(),
+            real_participants: HashMap::new(),
+            tapbacks: TapbackStore::Memory(HashMap::new()),
+            options,
+            offset: get_offset(),
+            db,
+            converter: None,
+            report: Report::default(),
+            edits: EditReport::default(),
+            templates,
+            link_depth: Cell::new(0),
+            started: Local::now(),
+        }
+    }
+
+    #[test]
+    fn can_create_html() {
+        let config = fake_config(fake_options(ExportType::Html), HashMap::new());
+        let exporter = Templated::new(&config).unwrap();
+        assert_eq!(exporter.files.len(), 0);
+    }
+
+    #[test]
+    fn can_create_txt() {
+        let config = fake_config(fake_options(ExportType::Txt), HashMap::new());
+        let exporter = Templated::new(&config).unwrap();
+        assert_eq!(exporter.files.len(), 0);
+    }
+
+    #[test]
+    fn cant_create_json() {
+        let config = fake_config(fake_options(ExportType::Json), HashMap::new());
+        assert!(Templated::new(&config).is_err());
+    }
+
+    #[test]
+    fn cant_create_invalid_template() {
+        let templates = HashMap::from([("tapback".to_string(), "{% if %}".to_string())]);
+        let config = fake_config(fake_options(ExportType::Txt), templates);
+        assert!(Templated::new(&config).is_err());
+    }
+
+    #[test]
+    fn cant_create_page_without_body() {
+        let templates = HashMap::from([("page".to_string(), "No messages".to_string())]);
+        let config = fake_config(fake_options(ExportType::Txt), templates);
+        assert!(Templated::new(&config).is_err());
+    }
+
+    #[test]
+    fn can_render_builtin_template() {
+        let config = fake_config(fake_options(ExportType::Txt), HashMap::new());
+        let exporter = Templated::new(&config).unwrap();
+
+        let actual = exporter
+            .render_fragment(
+                "tapback",
+                context! { tapback => "Loved", sender => "Me", sticker => None::<String> },
+            )
+            .unwrap();
+        let expected = "Loved by Me";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_render_custom_template() {
+        let templates = HashMap::from([(
+            "tapback".to_string(),
+            "{{ sender }} reacted with {{ tapback }}".to_string(),
+        )]);
+        let config = fake_config(fake_options(ExportType::Txt), templates);
+        let exporter = Templated::new(&config).unwrap();
+
+        let actual = exporter
+            .render_fragment(
+                "tapback",
+                context! { tapback => "Loved", sender => "Me", sticker => None::<String> },
+            )
+            .unwrap();
+        let expected = "Me reacted with Loved";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_escape_html() {
+        let config = fake_config(fake_options(ExportType::Html), HashMap::new());
+        let exporter = Templated::new(&config).unwrap();
+
+        let actual = exporter
+            .render_fragment(
+                "tapback",
+                context! { tapback => "Loved", sender => "<b>Me</b>", sticker => None::<String> },
+            )
+            .unwrap();
+        let expected = "<div class=\"tapback\"><span class=\"tapback\"><b>Loved</b> by &lt;b&gt;Me&lt;/b&gt;</span></div>";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_split_page() {
+        let templates = HashMap::from([(
+            "page".to_string(),
+            "{{ title }}\n{{ body }}\nEnd".to_string(),
+        )]);
+        let config = fake_config(fake_options(ExportType::Txt), templates);
+        let exporter = Templated::new(&config).unwrap();
+
+        let actual = exporter.render_page("Chat", context! {}).unwrap();
+        let expected = ("Chat\n".to_string(), "\nEnd".to_string());
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_read_templates() {
+        let dir = std::env::temp_dir().join("imessage-exporter-templates");
+        create_dir_all(&dir).unwrap();
+        write(dir.join("message.txt"), "{{ message.sender }}").unwrap();
+        write(dir.join("message.html"), "<p>{{ message.sender }}</p>").unwrap();
+        write(dir.join("unknown.txt"), "Not a template").unwrap();
+
+        let actual = read_templates(&dir, &ExportType::Txt).unwrap();
+        let expected = HashMap::from([("message".to_string(), "{{ message.sender }}".to_string())]);
+
+        assert_eq!(actual, expected);
+    }
+}
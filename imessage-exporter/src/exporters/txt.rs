@@ -9,39 +9,40 @@ use std::{
     path::PathBuf,
 };
 
+use indicatif::ProgressBar;
+use rusqlite::Connection;
+
 use crate::{
     app::{
         attachment_manager::AttachmentManager, error::RuntimeError,
-        progress::build_progress_bar_export, runtime::Config,
+        progress::build_progress_bar_export, report::WarningKind, runtime::Config,
     },
     exporters::exporter::{BalloonFormatter, Exporter, Writer},
 };
 
 use imessage_database::{
-    error::{plist::PlistParseError, table::TableError},
+    error::{export::ExportError, plist::PlistParseError, table::TableError},
+    export::document::{Content, DocumentMessage, Item, Render},
     message_types::{
-        app::AppMessage,
-        app_store::AppStoreMessage,
-        collaboration::CollaborationMessage,
-        digital_touch::{self, DigitalTouch},
-        edited::{EditStatus, EditedMessage},
+        app::OwnedAppMessage,
+        app_store::OwnedAppStoreMessage,
+        collaboration::OwnedCollaborationMessage,
+        digital_touch::DigitalTouch,
+        edited::{DiffSpan, EditStatus, EditedMessage},
         expressives::{BubbleEffect, Expressive, ScreenEffect},
         handwriting::HandwrittenMessage,
-        music::MusicMessage,
-        placemark::PlacemarkMessage,
-        text_effects::TextEffect,
-        url::URLMessage,
-        variants::{Announcement, BalloonProvider, CustomBalloon, URLOverride, Variant},
+        music::OwnedMusicMessage,
+        placemark::OwnedPlacemarkMessage,
+        text_effects::OwnedTextEffect,
+        url::OwnedURLMessage,
+        variants::{Announcement, Variant},
     },
     tables::{
-        attachment::Attachment,
-        messages::{models::BubbleComponent, Message},
-        table::{Table, FITNESS_RECEIVER, ME, ORPHANED, YOU},
-    },
-    util::{
-        dates::{format, get_local_time, readable_diff, TIMESTAMP_FACTOR},
-        plist::parse_plist,
+        attachment::{Attachment, MediaType},
+        messages::{models::OwnedTextAttributes, Message},
+        table::{FITNESS_RECEIVER, ME, ORPHANED, YOU},
     },
+    util::dates::{format, get_local_time, readable_diff, TIMESTAMP_FACTOR},
 };
 
 pub struct TXT<'a> {
@@ -52,6 +53,10 @@ pub struct TXT<'a> {
     pub files: HashMap<String, BufWriter<File>>,
     /// Writer instance for orphaned messages
     pub orphaned: BufWriter<File>,
+    /// Progress through the messages table
+    pb: ProgressBar,
+    /// The number of top-level messages and announcements rendered so far
+    current_message: u64,
 }
 
 impl<'a> Exporter<'a> for TXT<'a> {
@@ -70,69 +75,11 @@ impl<'a> Exporter<'a> for TXT<'a> {
             config,
             files: HashMap::new(),
             orphaned: BufWriter::new(file),
+            pb: ProgressBar::hidden(),
+            current_message: 0,
         })
     }
 
-    fn iter_messages(&mut self) -> Result<(), RuntimeError> {
-        // Tell the user what we are doing
-        eprintln!(
-            "Exporting to {} as txt...",
-            self.config.options.export_path.display()
-        );
-
-        // Keep track of current message ROWID
-        let mut current_message_row = -1;
-
-        // Set up progress bar
-        let mut current_message = 0;
-        let total_messages =
-            Message::get_count(&self.config.db, &self.config.options.query_context)
-                .map_err(RuntimeError::DatabaseError)?;
-        let pb = build_progress_bar_export(total_messages);
-
-        let mut statement =
-            Message::stream_rows(&self.config.db, &self.config.options.query_context)
-                .map_err(RuntimeError::DatabaseError)?;
-
-        let messages = statement
-            .query_map([], |row| Ok(Message::from_row(row)))
-            .map_err(|err| RuntimeError::DatabaseError(TableError::Messages(err)))?;
-
-        for message in messages {
-            let mut msg = Message::extract(message).map_err(RuntimeError::DatabaseError)?;
-
-            // Early escape if we try and render the same message GUID twice
-            // See https://github.com/ReagentX/imessage-exporter/issues/135 for rationale
-            if msg.rowid == current_message_row {
-                current_message += 1;
-                continue;
-            }
-            current_message_row = msg.rowid;
-
-            // Generate the text of the message
-            let _ = msg.generate_text(&self.config.db);
-
-            // Render the announcement in-line
-            if msg.is_announcement() {
-                let announcement = self.format_announcement(&msg);
-                TXT::write_to_file(self.get_or_create_file(&msg)?, &announcement)?;
-            }
-            // Message replies and tapbacks are rendered in context, so no need to render them separately
-            else if !msg.is_tapback() {
-                let message = self
-                    .format_message(&msg, 0)
-                    .map_err(RuntimeError::DatabaseError)?;
-                TXT::write_to_file(self.get_or_create_file(&msg)?, &message)?;
-            }
-            current_message += 1;
-            if current_message % 99 == 0 {
-                pb.set_position(current_message);
-            }
-        }
-        pb.finish();
-        Ok(())
-    }
-
     /// Create a file for the given chat, caching it so we don't need to build it later
     fn get_or_create_file(
         &mut self,
@@ -140,13 +87,15 @@ impl<'a> Exporter<'a> for TXT<'a> {
     ) -> Result<&mut BufWriter<File>, RuntimeError> {
         match self.config.conversation(message) {
             Some((chatroom, _)) => {
-                let filename = self.config.filename(chatroom);
-                return match self.files.entry(filename) {
+                let filename = self.config.file_path(chatroom, message);
+                match self.files.entry(filename) {
                     Occupied(entry) => Ok(entry.into_mut()),
                     Vacant(entry) => {
-                        let mut path = self.config.options.export_path.clone();
-                        path.push(self.config.filename(chatroom));
-                        path.set_extension("txt");
+                        let path = self.config.export_file_path(entry.key(), "txt")?;
+
+                        // If the file already exists, don't write the header again
+                        // This can happen if multiple chats use the same group name
+                        let file_exists = path.exists();
 
                         let file = File::options()
                             .append(true)
@@ -154,9 +103,22 @@ impl<'a> Exporter<'a> for TXT<'a> {
                             .open(&path)
                             .map_err(|err| RuntimeError::CreateError(err, path))?;
 
-                        Ok(entry.insert(BufWriter::new(file)))
+                        let mut buf = BufWriter::new(file);
+
+                        // Note the conversation's current group photo, if it has one
+                        if !file_exists {
+                            if let Some(photo) = self
+                                .config
+                                .current_group_photo(chatroom.rowid)
+                                .and_then(|change| self.config.group_photo(&change))
+                            {
+                                TXT::write_to_file(&mut buf, &format!("Group photo: {photo}\n\n"))?;
+                            }
+                        }
+
+                        Ok(entry.insert(buf))
                     }
-                };
+                }
             }
             None => Ok(&mut self.orphaned),
         }
@@ -164,8 +126,14 @@ impl<'a> Exporter<'a> for TXT<'a> {
 }
 
 impl<'a> Writer<'a> for TXT<'a> {
-    fn format_message(&self, message: &Message, indent_size: usize) -> Result<String, TableError> {
+    fn format_message(
+        &self,
+        document: &DocumentMessage,
+        indent_size: usize,
+    ) -> Result<String, ExportError> {
+        let message = &document.message;
         let indent = String::from_iter((0..indent_size).map(|_| " "));
+
         // Data we want to write to a file
         let mut formatted_message = String::new();
 
@@ -187,18 +155,15 @@ impl<'a> Writer<'a> for TXT<'a> {
         if message.is_deleted() {
             self.add_line(
                 &mut formatted_message,
-                "This message was deleted from the conversation!",
+                &self.config.deletion_note(message),
                 &indent,
             );
         }
 
-        // Useful message metadata
-        let message_parts = message.body();
-        let mut attachments = Attachment::from_message(&self.config.db, message)?;
-        let mut replies = message.get_replies(&self.config.db)?;
-
-        // Index of where we are in the attachment Vector
-        let mut attachment_index: usize = 0;
+        // If message was scheduled with Send Later, annotate it
+        if let Some(note) = self.config.schedule_note(message) {
+            self.add_line(&mut formatted_message, &note, &indent);
+        }
 
         // Render subject
         if let Some(subject) = &message.subject {
@@ -220,89 +185,16 @@ impl<'a> Writer<'a> for TXT<'a> {
         }
 
         // Generate the message body from it's components
-        for (idx, message_part) in message_parts.iter().enumerate() {
-            match message_part {
-                // Fitness messages have a prefix that we need to replace with the opposite if who sent the message
-                BubbleComponent::Text(text_attrs) => {
-                    if let Some(text) = &message.text {
-                        // Render edited message content, if applicable
-                        if message.is_part_edited(idx) {
-                            if let Some(edited_parts) = &message.edited_parts {
-                                if let Some(edited) =
-                                    self.format_edited(message, edited_parts, idx, &indent)
-                                {
-                                    self.add_line(&mut formatted_message, &edited, &indent);
-                                };
-                            }
-                        } else {
-                            let mut formatted_text = String::with_capacity(text.len());
-
-                            for text_attr in text_attrs {
-                                if let Some(message_content) =
-                                    text.get(text_attr.start..text_attr.end)
-                                {
-                                    formatted_text.push_str(
-                                        &self.format_attributed(message_content, &text_attr.effect),
-                                    )
-                                }
-                            }
-
-                            // If we failed to parse any text above, use the original text
-                            if formatted_text.is_empty() {
-                                formatted_text.push_str(text);
-                            }
-
-                            if formatted_text.starts_with(FITNESS_RECEIVER) {
-                                self.add_line(
-                                    &mut formatted_message,
-                                    &formatted_text.replace(FITNESS_RECEIVER, YOU),
-                                    &indent,
-                                );
-                            } else {
-                                self.add_line(&mut formatted_message, &formatted_text, &indent);
-                            }
-                        }
-                    }
-                }
-                BubbleComponent::Attachment(_) => match attachments.get_mut(attachment_index) {
-                    Some(attachment) => {
-                        if attachment.is_sticker {
-                            let result = self.format_sticker(attachment, message);
-                            self.add_line(&mut formatted_message, &result, &indent);
-                        } else {
-                            match self.format_attachment(attachment, message) {
-                                Ok(result) => {
-                                    attachment_index += 1;
-                                    self.add_line(&mut formatted_message, &result, &indent);
-                                }
-                                Err(result) => {
-                                    self.add_line(&mut formatted_message, result, &indent);
-                                }
-                            }
-                        }
-                    }
-                    // Attachment does not exist in attachments table
-                    None => self.add_line(&mut formatted_message, "Attachment missing!", &indent),
-                },
-                BubbleComponent::App => match self.format_app(message, &mut attachments, &indent) {
-                    // We use an empty indent here because `format_app` handles building the entire message
-                    Ok(ok_bubble) => self.add_line(&mut formatted_message, &ok_bubble, &indent),
-                    Err(why) => self.add_line(
-                        &mut formatted_message,
-                        &format!("Unable to format app message: {why}"),
-                        &indent,
-                    ),
-                },
-                BubbleComponent::Retracted => {
-                    if let Some(edited_parts) = &message.edited_parts {
-                        if let Some(edited) =
-                            self.format_edited(message, edited_parts, idx, &indent)
-                        {
-                            self.add_line(&mut formatted_message, &edited, &indent);
-                        };
-                    }
-                }
-            };
+        for part in &document.parts {
+            if let Some(content) = &part.content {
+                self.format_content(
+                    &mut formatted_message,
+                    message,
+                    content,
+                    part.index,
+                    &indent,
+                );
+            }
 
             // Handle expressives
             if message.expressive_send_style_id.is_some() {
@@ -314,45 +206,20 @@ impl<'a> Writer<'a> for TXT<'a> {
             }
 
             // Handle Tapbacks
-            if let Some(tapbacks_map) = self.config.tapbacks.get(&message.guid) {
-                if let Some(tapbacks) = tapbacks_map.get(&idx) {
-                    let mut formatted_tapbacks = String::new();
-                    tapbacks
-                        .iter()
-                        .try_for_each(|tapbacks| -> Result<(), TableError> {
-                            let formatted = self.format_tapback(tapbacks)?;
-                            if !formatted.is_empty() {
-                                self.add_line(
-                                    &mut formatted_tapbacks,
-                                    &self.format_tapback(tapbacks)?,
-                                    &indent,
-                                );
-                            }
-                            Ok(())
-                        })?;
-
-                    if !formatted_tapbacks.is_empty() {
-                        self.add_line(&mut formatted_message, "Tapbacks:", &indent);
-                        self.add_line(&mut formatted_message, &formatted_tapbacks, &indent);
-                    }
-                }
+            let mut formatted_tapbacks = String::new();
+            for tapback in &part.tapbacks {
+                let formatted = self.format_tapback(tapback)?;
+                self.add_line(&mut formatted_tapbacks, &formatted, &indent);
+            }
+            if !formatted_tapbacks.is_empty() {
+                self.add_line(&mut formatted_message, "Tapbacks:", &indent);
+                self.add_line(&mut formatted_message, &formatted_tapbacks, &indent);
             }
 
-            // Handle Replies
-            if let Some(replies) = replies.get_mut(&idx) {
-                replies
-                    .iter_mut()
-                    .try_for_each(|reply| -> Result<(), TableError> {
-                        let _ = reply.generate_text(&self.config.db);
-                        if !reply.is_tapback() {
-                            self.add_line(
-                                &mut formatted_message,
-                                &self.format_message(reply, 4)?,
-                                &indent,
-                            );
-                        }
-                        Ok(())
-                    })?;
+            // Handle Replies, which are indented under the part they respond to
+            for reply in &part.replies {
+                let formatted = self.format_message(reply, 4)?;
+                self.add_line(&mut formatted_message, &formatted, &indent);
             }
         }
 
@@ -389,6 +256,10 @@ impl<'a> Writer<'a> for TXT<'a> {
         Ok(self.config.message_attachment_path(attachment))
     }
 
+    fn format_genmoji(&self, genmoji: &mut Attachment, _: &Message) -> String {
+        genmoji.genmoji_description().to_string()
+    }
+
     fn format_sticker(&self, sticker: &'a mut Attachment, message: &Message) -> String {
         let who = self.config.who(
             message.handle_id,
@@ -411,87 +282,6 @@ impl<'a> Writer<'a> for TXT<'a> {
         }
     }
 
-    fn format_app(
-        &self,
-        message: &'a Message,
-        attachments: &mut Vec<Attachment>,
-        indent: &str,
-    ) -> Result<String, PlistParseError> {
-        if let Variant::App(balloon) = message.variant() {
-            let mut app_bubble = String::new();
-
-            // Handwritten messages use a different payload type, so check that first
-            if message.is_handwriting() {
-                if let Some(payload) = message.raw_payload_data(&self.config.db) {
-                    return match HandwrittenMessage::from_payload(&payload) {
-                        Ok(bubble) => Ok(self.format_handwriting(message, &bubble, indent)),
-                        Err(why) => Err(PlistParseError::HandwritingError(why)),
-                    };
-                }
-            }
-
-            if message.is_digital_touch() {
-                if let Some(payload) = message.raw_payload_data(&self.config.db) {
-                    return match digital_touch::from_payload(&payload) {
-                        Some(bubble) => Ok(self.format_digital_touch(message, &bubble, indent)),
-                        None => Err(PlistParseError::DigitalTouchError),
-                    };
-                }
-            }
-
-            if let Some(payload) = message.payload_data(&self.config.db) {
-                // Handle URL messages separately since they are a special case
-                let res = if message.is_url() {
-                    let parsed = parse_plist(&payload)?;
-                    let bubble = URLMessage::get_url_message_override(&parsed)?;
-                    match bubble {
-                        URLOverride::Normal(balloon) => self.format_url(message, &balloon, indent),
-                        URLOverride::AppleMusic(balloon) => self.format_music(&balloon, indent),
-                        URLOverride::Collaboration(balloon) => {
-                            self.format_collaboration(&balloon, indent)
-                        }
-                        URLOverride::AppStore(balloon) => self.format_app_store(&balloon, indent),
-                        URLOverride::SharedPlacemark(balloon) => {
-                            self.format_placemark(&balloon, indent)
-                        }
-                    }
-                // Handwriting uses a different payload type than the rest of the branches
-                } else {
-                    // Handle the app case
-                    let parsed = parse_plist(&payload)?;
-                    match AppMessage::from_map(&parsed) {
-                        Ok(bubble) => match balloon {
-                            CustomBalloon::Application(bundle_id) => {
-                                self.format_generic_app(&bubble, bundle_id, attachments, indent)
-                            }
-                            CustomBalloon::ApplePay => self.format_apple_pay(&bubble, indent),
-                            CustomBalloon::Fitness => self.format_fitness(&bubble, indent),
-                            CustomBalloon::Slideshow => self.format_slideshow(&bubble, indent),
-                            CustomBalloon::CheckIn => self.format_check_in(&bubble, indent),
-                            CustomBalloon::FindMy => self.format_find_my(&bubble, indent),
-                            CustomBalloon::Handwriting => unreachable!(),
-                            CustomBalloon::DigitalTouch => unreachable!(),
-                            CustomBalloon::URL => unreachable!(),
-                        },
-                        Err(why) => return Err(why),
-                    }
-                };
-                app_bubble.push_str(&res);
-            } else {
-                // Sometimes, URL messages are missing their payloads
-                if message.is_url() {
-                    if let Some(text) = &message.text {
-                        return Ok(text.to_string());
-                    }
-                }
-                return Err(PlistParseError::NoPayload);
-            };
-            Ok(app_bubble)
-        } else {
-            Err(PlistParseError::WrongMessageType)
-        }
-    }
-
     fn format_tapback(&self, msg: &Message) -> Result<String, TableError> {
         match msg.variant() {
             Variant::Tapback(_, added, tapback) => {
@@ -556,21 +346,35 @@ impl<'a> Writer<'a> for TXT<'a> {
 
         let timestamp = format(&msg.date(&self.config.offset));
 
-        return match msg.get_announcement() {
+        match msg.get_announcement() {
             Some(announcement) => match announcement {
                 Announcement::NameChange(name) => {
                     format!("{timestamp} {who} renamed the conversation to {name}\n\n")
                 }
-                Announcement::PhotoChange => {
-                    format!("{timestamp} {who} changed the group photo.\n\n")
-                }
+                Announcement::PhotoChange => match self.config.group_photo(msg) {
+                    Some(photo) => {
+                        format!("{timestamp} {who} changed the group photo: {photo}\n\n")
+                    }
+                    None => format!("{timestamp} {who} changed the group photo.\n\n"),
+                },
                 Announcement::Unknown(num) => {
                     format!("{timestamp} {who} performed unknown action {num}.\n\n")
                 }
                 Announcement::FullyUnsent => format!("{timestamp} {who} unsent a message!\n\n"),
+                Announcement::ParticipantAdded(id) => {
+                    let subject = self.config.announcement_subject(*id);
+                    format!("{timestamp} {who} added {subject} to the conversation.\n\n")
+                }
+                Announcement::ParticipantRemoved(id) => {
+                    let subject = self.config.announcement_subject(*id);
+                    format!("{timestamp} {who} removed {subject} from the conversation.\n\n")
+                }
+                Announcement::ParticipantLeft => {
+                    format!("{timestamp} {who} left the conversation.\n\n")
+                }
             },
             None => String::from("Unable to format announcement!\n\n"),
-        };
+        }
     }
 
     fn format_shareplay(&self) -> &str {
@@ -600,7 +404,9 @@ impl<'a> Writer<'a> for TXT<'a> {
 
             match edited_message_part.status {
                 EditStatus::Edited => {
-                    for event in &edited_message_part.edit_history {
+                    let diffs = edited_message_part.diffs();
+
+                    for (idx, event) in edited_message_part.edit_history.iter().enumerate() {
                         match previous_timestamp {
                             // Original message get an absolute timestamp
                             None => {
@@ -627,6 +433,16 @@ impl<'a> Writer<'a> for TXT<'a> {
 
                         // Render the message text
                         self.add_line(&mut out_s, &event.text, indent);
+
+                        // Render what changed from the previous version
+                        if let Some(diff) = idx.checked_sub(1).and_then(|prev| diffs.get(prev)) {
+                            self.add_line(
+                                &mut out_s,
+                                &self.diff_to_txt(&diff.spans, false),
+                                indent,
+                            );
+                            self.add_line(&mut out_s, &self.diff_to_txt(&diff.spans, true), indent);
+                        }
                     }
                 }
                 EditStatus::Unsent => {
@@ -662,7 +478,7 @@ impl<'a> Writer<'a> for TXT<'a> {
         None
     }
 
-    fn format_attributed(&'a self, msg: &'a str, _: &'a TextEffect) -> Cow<'a, str> {
+    fn format_attributed(&'a self, msg: &'a str, _: &'a OwnedTextEffect) -> Cow<'a, str> {
         // There isn't really a way to represent formatted text in a plain text export
         Cow::Borrowed(msg)
     }
@@ -673,8 +489,52 @@ impl<'a> Writer<'a> for TXT<'a> {
     }
 }
 
+impl Render for TXT<'_> {
+    fn start(&mut self, _: &Connection) -> Result<(), ExportError> {
+        // Tell the user what we are doing
+        eprintln!(
+            "Exporting to {} as txt...",
+            self.config.options.export_path.display()
+        );
+
+        // Set up progress bar
+        let total_messages =
+            Message::get_count(&self.config.db, &self.config.options.query_context)?;
+        self.pb = build_progress_bar_export(total_messages);
+        Ok(())
+    }
+
+    fn render(&mut self, item: Item) -> Result<(), ExportError> {
+        let message = item.message();
+        self.config.log_edits(message);
+        self.config.link_from(message);
+
+        let rendered = match &item {
+            // Message replies and tapbacks are rendered in context, so no need to render them separately
+            Item::Message(document) => self.format_message(document, 0)?,
+            // Render the announcement in-line
+            Item::Announcement(message) => self.format_announcement(message),
+        };
+        let file = self
+            .get_or_create_file(message)
+            .map_err(|why| ExportError::Format(why.to_string()))?;
+        file.write_all(rendered.as_bytes())?;
+
+        self.current_message += 1;
+        if self.current_message.is_multiple_of(99) {
+            self.pb.set_position(self.current_message);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), ExportError> {
+        self.pb.finish();
+        Ok(())
+    }
+}
+
 impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
-    fn format_url(&self, msg: &Message, balloon: &URLMessage, indent: &str) -> String {
+    fn format_url(&self, msg: &Message, balloon: &OwnedURLMessage, indent: &str) -> String {
         let mut out_s = String::new();
 
         if let Some(url) = balloon.get_url() {
@@ -683,11 +543,11 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
             self.add_line(&mut out_s, text, indent);
         }
 
-        if let Some(title) = balloon.title {
+        if let Some(title) = balloon.title.as_deref() {
             self.add_line(&mut out_s, title, indent);
         }
 
-        if let Some(summary) = balloon.summary {
+        if let Some(summary) = balloon.summary.as_deref() {
             self.add_line(&mut out_s, summary, indent);
         }
 
@@ -695,34 +555,34 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
         out_s.strip_suffix('\n').unwrap_or(&out_s).to_string()
     }
 
-    fn format_music(&self, balloon: &MusicMessage, indent: &str) -> String {
+    fn format_music(&self, balloon: &OwnedMusicMessage, indent: &str) -> String {
         let mut out_s = String::new();
 
-        if let Some(track_name) = balloon.track_name {
+        if let Some(track_name) = balloon.track_name.as_deref() {
             self.add_line(&mut out_s, track_name, indent);
         }
 
-        if let Some(album) = balloon.album {
+        if let Some(album) = balloon.album.as_deref() {
             self.add_line(&mut out_s, album, indent);
         }
 
-        if let Some(artist) = balloon.artist {
+        if let Some(artist) = balloon.artist.as_deref() {
             self.add_line(&mut out_s, artist, indent);
         }
 
-        if let Some(url) = balloon.url {
+        if let Some(url) = balloon.url.as_deref() {
             self.add_line(&mut out_s, url, indent);
         }
 
         out_s
     }
 
-    fn format_collaboration(&self, balloon: &CollaborationMessage, indent: &str) -> String {
+    fn format_collaboration(&self, balloon: &OwnedCollaborationMessage, indent: &str) -> String {
         let mut out_s = String::from(indent);
 
-        if let Some(name) = balloon.app_name {
+        if let Some(name) = balloon.app_name.as_deref() {
             out_s.push_str(name);
-        } else if let Some(bundle_id) = balloon.bundle_id {
+        } else if let Some(bundle_id) = balloon.bundle_id.as_deref() {
             out_s.push_str(bundle_id);
         }
 
@@ -730,7 +590,7 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
             out_s.push_str(" message:\n");
         }
 
-        if let Some(title) = balloon.title {
+        if let Some(title) = balloon.title.as_deref() {
             self.add_line(&mut out_s, title, indent);
         }
 
@@ -742,26 +602,26 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
         out_s.strip_suffix('\n').unwrap_or(&out_s).to_string()
     }
 
-    fn format_app_store(&self, balloon: &AppStoreMessage, indent: &'a str) -> String {
+    fn format_app_store(&self, balloon: &OwnedAppStoreMessage, indent: &'a str) -> String {
         let mut out_s = String::from(indent);
 
-        if let Some(name) = balloon.app_name {
+        if let Some(name) = balloon.app_name.as_deref() {
             self.add_line(&mut out_s, name, indent);
         }
 
-        if let Some(description) = balloon.description {
+        if let Some(description) = balloon.description.as_deref() {
             self.add_line(&mut out_s, description, indent);
         }
 
-        if let Some(platform) = balloon.platform {
+        if let Some(platform) = balloon.platform.as_deref() {
             self.add_line(&mut out_s, platform, indent);
         }
 
-        if let Some(genre) = balloon.genre {
+        if let Some(genre) = balloon.genre.as_deref() {
             self.add_line(&mut out_s, genre, indent);
         }
 
-        if let Some(url) = balloon.url {
+        if let Some(url) = balloon.url.as_deref() {
             self.add_line(&mut out_s, url, indent);
         }
 
@@ -769,10 +629,10 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
         out_s.strip_suffix('\n').unwrap_or(&out_s).to_string()
     }
 
-    fn format_placemark(&self, balloon: &PlacemarkMessage, indent: &'a str) -> String {
+    fn format_placemark(&self, balloon: &OwnedPlacemarkMessage, indent: &'a str) -> String {
         let mut out_s = String::from(indent);
 
-        if let Some(name) = balloon.place_name {
+        if let Some(name) = balloon.place_name.as_deref() {
             self.add_line(&mut out_s, name, indent);
         }
 
@@ -780,43 +640,44 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
             self.add_line(&mut out_s, url, indent);
         }
 
-        if let Some(name) = balloon.placemark.name {
+        if let Some(name) = balloon.placemark.name.as_deref() {
             self.add_line(&mut out_s, name, indent);
         }
 
-        if let Some(address) = balloon.placemark.address {
+        if let Some(address) = balloon.placemark.address.as_deref() {
             self.add_line(&mut out_s, address, indent);
         }
 
-        if let Some(state) = balloon.placemark.state {
+        if let Some(state) = balloon.placemark.state.as_deref() {
             self.add_line(&mut out_s, state, indent);
         }
 
-        if let Some(city) = balloon.placemark.city {
+        if let Some(city) = balloon.placemark.city.as_deref() {
             self.add_line(&mut out_s, city, indent);
         }
 
-        if let Some(iso_country_code) = balloon.placemark.iso_country_code {
+        if let Some(iso_country_code) = balloon.placemark.iso_country_code.as_deref() {
             self.add_line(&mut out_s, iso_country_code, indent);
         }
 
-        if let Some(postal_code) = balloon.placemark.postal_code {
+        if let Some(postal_code) = balloon.placemark.postal_code.as_deref() {
             self.add_line(&mut out_s, postal_code, indent);
         }
 
-        if let Some(country) = balloon.placemark.country {
+        if let Some(country) = balloon.placemark.country.as_deref() {
             self.add_line(&mut out_s, country, indent);
         }
 
-        if let Some(street) = balloon.placemark.street {
+        if let Some(street) = balloon.placemark.street.as_deref() {
             self.add_line(&mut out_s, street, indent);
         }
 
-        if let Some(sub_administrative_area) = balloon.placemark.sub_administrative_area {
+        if let Some(sub_administrative_area) = balloon.placemark.sub_administrative_area.as_deref()
+        {
             self.add_line(&mut out_s, sub_administrative_area, indent);
         }
 
-        if let Some(sub_locality) = balloon.placemark.sub_locality {
+        if let Some(sub_locality) = balloon.placemark.sub_locality.as_deref() {
             self.add_line(&mut out_s, sub_locality, indent);
         }
 
@@ -854,17 +715,17 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
     }
 
     fn format_digital_touch(&self, _: &Message, balloon: &DigitalTouch, indent: &str) -> String {
-        format!("{indent}Digital Touch Message: {:?}", balloon)
+        format!("{indent}Digital Touch Message: {}", balloon.describe())
     }
 
-    fn format_apple_pay(&self, balloon: &AppMessage, indent: &str) -> String {
+    fn format_apple_pay(&self, balloon: &OwnedAppMessage, indent: &str) -> String {
         let mut out_s = String::from(indent);
-        if let Some(caption) = balloon.caption {
+        if let Some(caption) = balloon.caption.as_deref() {
             out_s.push_str(caption);
             out_s.push_str(" transaction: ");
         }
 
-        if let Some(ldtext) = balloon.ldtext {
+        if let Some(ldtext) = balloon.ldtext.as_deref() {
             out_s.push_str(ldtext);
         } else {
             out_s.push_str("unknown amount");
@@ -873,13 +734,13 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
         out_s
     }
 
-    fn format_fitness(&self, balloon: &AppMessage, indent: &str) -> String {
+    fn format_fitness(&self, balloon: &OwnedAppMessage, indent: &str) -> String {
         let mut out_s = String::from(indent);
-        if let Some(app_name) = balloon.app_name {
+        if let Some(app_name) = balloon.app_name.as_deref() {
             out_s.push_str(app_name);
             out_s.push_str(" message: ");
         }
-        if let Some(ldtext) = balloon.ldtext {
+        if let Some(ldtext) = balloon.ldtext.as_deref() {
             out_s.push_str(ldtext);
         } else {
             out_s.push_str("unknown workout");
@@ -887,14 +748,14 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
         out_s
     }
 
-    fn format_slideshow(&self, balloon: &AppMessage, indent: &str) -> String {
+    fn format_slideshow(&self, balloon: &OwnedAppMessage, indent: &str) -> String {
         let mut out_s = String::from(indent);
-        if let Some(ldtext) = balloon.ldtext {
+        if let Some(ldtext) = balloon.ldtext.as_deref() {
             out_s.push_str("Photo album: ");
             out_s.push_str(ldtext);
         }
 
-        if let Some(url) = balloon.url {
+        if let Some(url) = balloon.url.as_deref() {
             out_s.push(' ');
             out_s.push_str(url);
         }
@@ -902,14 +763,14 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
         out_s
     }
 
-    fn format_find_my(&self, balloon: &AppMessage, indent: &'a str) -> String {
+    fn format_find_my(&self, balloon: &OwnedAppMessage, indent: &'a str) -> String {
         let mut out_s = String::from(indent);
-        if let Some(app_name) = balloon.app_name {
+        if let Some(app_name) = balloon.app_name.as_deref() {
             out_s.push_str(app_name);
             out_s.push_str(": ");
         }
 
-        if let Some(ldtext) = balloon.ldtext {
+        if let Some(ldtext) = balloon.ldtext.as_deref() {
             out_s.push(' ');
             out_s.push_str(ldtext);
         }
@@ -917,10 +778,10 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
         out_s
     }
 
-    fn format_check_in(&self, balloon: &AppMessage, indent: &'a str) -> String {
+    fn format_check_in(&self, balloon: &OwnedAppMessage, indent: &'a str) -> String {
         let mut out_s = String::from(indent);
 
-        out_s.push_str(balloon.caption.unwrap_or("Check In"));
+        out_s.push_str(balloon.caption.as_deref().unwrap_or("Check In"));
 
         let metadata: HashMap<&str, &str> = balloon.parse_query_string();
 
@@ -960,14 +821,14 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
 
     fn format_generic_app(
         &self,
-        balloon: &AppMessage,
+        balloon: &OwnedAppMessage,
         bundle_id: &str,
         _: &mut Vec<Attachment>,
         indent: &str,
     ) -> String {
         let mut out_s = String::from(indent);
 
-        if let Some(name) = balloon.app_name {
+        if let Some(name) = balloon.app_name.as_deref() {
             out_s.push_str(name);
         } else {
             out_s.push_str(bundle_id);
@@ -977,27 +838,27 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
             out_s.push_str(" message:\n");
         }
 
-        if let Some(title) = balloon.title {
+        if let Some(title) = balloon.title.as_deref() {
             self.add_line(&mut out_s, title, indent);
         }
 
-        if let Some(subtitle) = balloon.subtitle {
+        if let Some(subtitle) = balloon.subtitle.as_deref() {
             self.add_line(&mut out_s, subtitle, indent);
         }
 
-        if let Some(caption) = balloon.caption {
+        if let Some(caption) = balloon.caption.as_deref() {
             self.add_line(&mut out_s, caption, indent);
         }
 
-        if let Some(subcaption) = balloon.subcaption {
+        if let Some(subcaption) = balloon.subcaption.as_deref() {
             self.add_line(&mut out_s, subcaption, indent);
         }
 
-        if let Some(trailing_caption) = balloon.trailing_caption {
+        if let Some(trailing_caption) = balloon.trailing_caption.as_deref() {
             self.add_line(&mut out_s, trailing_caption, indent);
         }
 
-        if let Some(trailing_subcaption) = balloon.trailing_subcaption {
+        if let Some(trailing_subcaption) = balloon.trailing_subcaption.as_deref() {
             self.add_line(&mut out_s, trailing_subcaption, indent);
         }
 
@@ -1007,6 +868,139 @@ impl<'a> BalloonFormatter<&'a str> for TXT<'a> {
 }
 
 impl<'a> TXT<'a> {
+    /// Render the contents of a single part of a message body
+    fn format_content(
+        &self,
+        formatted_message: &mut String,
+        message: &Message,
+        content: &Content,
+        idx: usize,
+        indent: &str,
+    ) {
+        match content {
+            Content::Text {
+                text,
+                attributes,
+                genmoji,
+            } => {
+                let formatted_text = self.format_text(message, text, attributes, genmoji);
+                self.add_line(formatted_message, &formatted_text, indent);
+            }
+            Content::Edited(_) => {
+                if let Some(edited) = message
+                    .edited_parts
+                    .as_ref()
+                    .and_then(|edited_parts| self.format_edited(message, edited_parts, idx, indent))
+                {
+                    self.add_line(formatted_message, &edited, indent);
+                }
+            }
+            Content::Attachment(attachment) => {
+                let mut attachment = attachment.clone();
+                if attachment.is_sticker {
+                    let result = self.format_sticker(&mut attachment, message);
+                    self.add_line(formatted_message, &result, indent);
+                    return;
+                }
+                let is_audio = matches!(attachment.mime_type(), MediaType::Audio(_));
+                match self.format_attachment(&mut attachment, message) {
+                    Ok(result) => self.add_line(formatted_message, &result, indent),
+                    Err(result) => self.add_line(formatted_message, result, indent),
+                }
+                // Voice messages can include a transcription of the audio
+                if let (true, Some(transcription)) = (is_audio, message.audio_transcription()) {
+                    self.add_line(
+                        formatted_message,
+                        &format!("Transcription: {transcription}"),
+                        indent,
+                    );
+                }
+            }
+            Content::MissingAttachment(idx) => {
+                self.config.warn(
+                    message,
+                    WarningKind::MissingAttachment,
+                    format!("Attachment {idx} does not exist!"),
+                );
+                self.add_line(formatted_message, "Attachment missing!", indent);
+            }
+            Content::Balloon(balloon) => {
+                // Text exports do not show the images attached to app balloons
+                let formatted = self.format_balloon(message, balloon, &mut vec![], indent);
+                self.add_line(formatted_message, &formatted, indent);
+            }
+            Content::MissingPayload => match &message.text {
+                // Sometimes, URL messages are missing their payloads
+                Some(text) if message.is_url() => self.add_line(formatted_message, text, indent),
+                _ => self.format_balloon_error(
+                    formatted_message,
+                    message,
+                    &PlistParseError::NoPayload.to_string(),
+                    indent,
+                ),
+            },
+            Content::BalloonError(why) => {
+                self.format_balloon_error(formatted_message, message, why, indent);
+            }
+        }
+    }
+
+    /// Render the text of a message part, with its Genmoji described in place
+    fn format_text(
+        &self,
+        message: &Message,
+        text: &str,
+        attributes: &[OwnedTextAttributes],
+        genmoji: &[Attachment],
+    ) -> String {
+        let mut genmoji = genmoji.iter();
+        let mut formatted_text = String::with_capacity(text.len());
+
+        for attribute in attributes {
+            if let OwnedTextEffect::Genmoji(_) = attribute.effect {
+                if let Some(image) = genmoji.next() {
+                    formatted_text.push_str(&self.format_genmoji(&mut image.clone(), message));
+                    continue;
+                }
+            }
+            if let Some(message_content) = text.get(attribute.start..attribute.end) {
+                formatted_text
+                    .push_str(&self.format_attributed(message_content, &attribute.effect));
+            }
+        }
+
+        // If we failed to parse any text above, use the original text
+        if formatted_text.is_empty() {
+            formatted_text.push_str(text);
+        }
+
+        // Fitness messages have a prefix that we need to replace with the opposite if who sent the message
+        if formatted_text.starts_with(FITNESS_RECEIVER) {
+            return formatted_text.replace(FITNESS_RECEIVER, YOU);
+        }
+        formatted_text
+    }
+
+    /// Render an app balloon that could not be parsed, and note it in the export report
+    fn format_balloon_error(
+        &self,
+        formatted_message: &mut String,
+        message: &Message,
+        why: &str,
+        indent: &str,
+    ) {
+        self.config.warn(
+            message,
+            WarningKind::Balloon,
+            format!("Unable to format {:?} message: {why}", message.variant()),
+        );
+        self.add_line(
+            formatted_message,
+            &format!("Unable to format app message: {why}"),
+            indent,
+        );
+    }
+
     fn get_time(&self, message: &Message) -> String {
         let mut date = format(&message.date(&self.config.offset));
         let read_after = message.time_until_read(&self.config.offset);
@@ -1023,6 +1017,29 @@ impl<'a> TXT<'a> {
         date
     }
 
+    /// Render one side of the changes between two versions of a message part
+    ///
+    /// The older side starts with `-` and wraps deleted text in `[-` and `-]`.
+    /// The newer side starts with `+` and wraps inserted text in `{+` and `+}`.
+    fn diff_to_txt(&self, spans: &[DiffSpan], newer: bool) -> String {
+        let mut out_s = String::from(if newer { "+ " } else { "- " });
+        spans.iter().for_each(|span| match (span, newer) {
+            (DiffSpan::Unchanged(text), _) => out_s.push_str(text),
+            (DiffSpan::Inserted(text), true) => {
+                out_s.push_str("{+");
+                out_s.push_str(text);
+                out_s.push_str("+}");
+            }
+            (DiffSpan::Deleted(text), false) => {
+                out_s.push_str("[-");
+                out_s.push_str(text);
+                out_s.push_str("-]");
+            }
+            _ => {}
+        });
+        out_s
+    }
+
     fn add_line(&self, string: &mut String, part: &str, indent: &str) {
         if !part.is_empty() {
             string.push_str(indent);
@@ -1035,26 +1052,35 @@ impl<'a> TXT<'a> {
 #[cfg(test)]
 mod tests {
     use std::{
+        cell::Cell,
         collections::HashMap,
         env::{current_dir, set_var},
         path::PathBuf,
     };
 
+    use chrono::Local;
+
     use crate::{
-        app::attachment_manager::AttachmentManager, exporters::exporter::Writer, Config, Exporter,
-        Options, TXT,
+        app::{
+            attachment_manager::AttachmentManager, edits::EditReport,
+            handwriting_format::HandwritingFormat, report::Report,
+        },
+        exporters::exporter::Writer,
+        Config, Exporter, Options, TXT,
     };
     use imessage_database::{
+        export::{document::DocumentMessage, tapbacks::TapbackStore},
+        message_types::handwriting::RasterOptions,
         tables::{
             attachment::Attachment,
             messages::Message,
+            recently_deleted::DeletedMessage,
+            store::CacheStore,
             table::{get_connection, ME},
         },
-        util::{
-            dates::get_offset, dirs::default_db_path, platform::Platform,
-            query_context::QueryContext,
-        },
+        util::{dates::get_offset, platform::Platform, query_context::QueryContext},
     };
+    use imessage_fixtures::Fixture;
 
     pub(super) fn blank() -> Message {
         Message {
@@ -1085,6 +1111,8 @@ mod tests {
             date_edited: 0,
             chat_id: None,
             associated_message_emoji: None,
+            schedule_type: 0,
+            schedule_state: 0,
             num_attachments: 0,
             deleted_from: None,
             num_replies: 0,
@@ -1095,11 +1123,12 @@ mod tests {
 
     pub(super) fn fake_options() -> Options {
         Options {
-            db_path: default_db_path(),
+            db_path: Fixture::shared().to_path_buf(),
             attachment_root: None,
             attachment_manager: AttachmentManager::Disabled,
             diagnostic: false,
             export_type: None,
+            format: None,
             export_path: PathBuf::from("/tmp"),
             query_context: QueryContext::default(),
             no_lazy: false,
@@ -1107,22 +1136,44 @@ mod tests {
             use_caller_id: false,
             platform: Platform::macOS,
             ignore_disk_space: false,
+            memory_limit: None,
+            strict: false,
+            handwriting_format: HandwritingFormat::default(),
+            handwriting_raster: RasterOptions::default(),
+            edit_report: false,
+            dump_typedstream: None,
+            templates: None,
+            file_name: None,
+            split_by: None,
+            embed: None,
         }
     }
 
+    pub(super) fn document(config: &Config, message: &Message) -> DocumentMessage {
+        DocumentMessage::build(&config.db, &config.tapbacks, message).unwrap()
+    }
+
     pub(super) fn fake_config(options: Options) -> Config {
         let db = get_connection(&options.get_db_path()).unwrap();
         Config {
             chatrooms: HashMap::new(),
             real_chatrooms: HashMap::new(),
             chatroom_participants: HashMap::new(),
+            memberships: CacheStore::Memory(HashMap::new()),
+            group_photos: CacheStore::Memory(HashMap::new()),
+            deleted: CacheStore::Memory(HashMap::new()),
             participants: HashMap::new(),
             real_participants: HashMap::new(),
-            tapbacks: HashMap::new(),
+            tapbacks: TapbackStore::Memory(HashMap::new()),
             options,
             offset: get_offset(),
             db,
             converter: None,
+            report: Report::default(),
+            edits: EditReport::default(),
+            templates: HashMap::new(),
+            link_depth: Cell::new(0),
+            started: Local::now(),
         }
     }
 
@@ -1136,6 +1187,8 @@ mod tests {
             total_bytes: 100,
             is_sticker: false,
             hide_attachment: 0,
+            emoji_image_content_identifier: None,
+            emoji_image_short_description: None,
             copied_path: None,
         }
     }
@@ -1239,7 +1292,9 @@ mod tests {
         message.is_from_me = true;
         message.chat_id = Some(0);
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "May 17, 2022  5:29:42 PM\nMe\nHello world\n\n";
 
         assert_eq!(actual, expected);
@@ -1262,13 +1317,78 @@ mod tests {
         message.is_from_me = true;
         message.deleted_from = Some(0);
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected =
             "May 17, 2022  5:29:42 PM\nMe\nThis message was deleted from the conversation!\nHello world\n\n";
 
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn can_format_txt_from_me_recently_deleted() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        let mut deleted = HashMap::new();
+        deleted.insert(
+            0,
+            DeletedMessage {
+                message_id: 0,
+                chat_id: 0,
+                delete_date: Some(674526582885055488),
+            },
+        );
+        config.deleted = CacheStore::Memory(deleted);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("Hello world".to_string());
+        message.date = 674526582885055488;
+        message.is_from_me = true;
+        message.deleted_from = Some(0);
+
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+        let expected =
+            "May 17, 2022  5:29:42 PM\nMe\nThis message was deleted from the conversation on May 17, 2022  5:29:42 PM and may be permanently deleted at any time!\nHello world\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_from_me_scheduled_pending() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.text = Some("Hello world".to_string());
+        message.date = 674526582885055488;
+        message.is_from_me = true;
+        message.schedule_type = 2;
+        message.schedule_state = 1;
+
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
+        let expected =
+            "May 17, 2022  5:29:42 PM\nMe\nThis message is scheduled to be sent on May 17, 2022  5:29:42 PM\nHello world\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_txt_from_me_normal_read() {
         // Set timezone to America/Los_Angeles for consistent Local time
@@ -1287,7 +1407,9 @@ mod tests {
         message.date_delivered = 674530231992568192;
         message.is_from_me = true;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected =
             "May 17, 2022  5:29:42 PM (Read by them after 1 hour, 49 seconds)\nMe\nHello world\n\n";
 
@@ -1313,7 +1435,9 @@ mod tests {
         message.text = Some("Hello world".to_string());
         message.handle_id = Some(999999);
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "May 17, 2022  5:29:42 PM\nSample Contact\nHello world\n\n";
 
         assert_eq!(actual, expected);
@@ -1342,7 +1466,9 @@ mod tests {
         // May 17, 2022  9:30:31 PM
         message.date_read = 674530231992568192;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected =
             "May 17, 2022  5:29:42 PM (Read by you after 1 hour, 49 seconds)\nSample Contact\nHello world\n\n";
 
@@ -1373,7 +1499,9 @@ mod tests {
         // May 17, 2022  9:30:31 PM
         message.date_read = 674530231992568192;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected =
             "May 17, 2022  5:29:42 PM (Read by Name after 1 hour, 49 seconds)\nSample Contact\nHello world\n\n";
 
@@ -1397,7 +1525,9 @@ mod tests {
         message.date = 674526582885055488;
         message.item_type = 6;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "May 17, 2022  5:29:42 PM\nMe\nSharePlay Message\nEnded\n\n";
 
         assert_eq!(actual, expected);
@@ -1427,6 +1557,82 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn can_format_txt_announcement_participant_added() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config.participants.insert(1, "Sample Contact".to_string());
+
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.item_type = 1;
+        message.handle_id = Some(1);
+
+        let actual = exporter.format_announcement(&message);
+        let expected = "May 17, 2022  5:29:42 PM Sample Contact added you to the conversation.\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_announcement_participant_removed() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config.participants.insert(1, "Sample Contact".to_string());
+
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.item_type = 1;
+        message.group_action_type = 1;
+        message.other_handle = 1;
+        message.is_from_me = true;
+
+        let actual = exporter.format_announcement(&message);
+        let expected =
+            "May 17, 2022  5:29:42 PM You removed Sample Contact from the conversation.\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn can_format_txt_announcement_photo_change_missing() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let mut config = fake_config(options);
+        config.participants.insert(1, "Sample Contact".to_string());
+
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        // May 17, 2022  8:29:42 PM
+        message.date = 674526582885055488;
+        message.item_type = 3;
+        message.group_action_type = 1;
+        message.handle_id = Some(1);
+
+        let actual = exporter.format_announcement(&message);
+        let expected = "May 17, 2022  5:29:42 PM Sample Contact changed the group photo.\n\n";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_txt_announcement_custom_name() {
         // Set timezone to America/Los_Angeles for consistent Local time
@@ -1572,7 +1778,9 @@ mod tests {
         message.share_direction = false;
         message.item_type = 4;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "Dec 31, 2000  4:00:00 PM\nMe\nStarted sharing location!\n\n";
 
         assert_eq!(actual, expected);
@@ -1595,7 +1803,9 @@ mod tests {
         message.share_direction = false;
         message.item_type = 4;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "Dec 31, 2000  4:00:00 PM\nMe\nStopped sharing location!\n\n";
 
         assert_eq!(actual, expected);
@@ -1619,7 +1829,9 @@ mod tests {
         message.share_direction = false;
         message.item_type = 4;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "Dec 31, 2000  4:00:00 PM\nUnknown\nStarted sharing location!\n\n";
 
         assert_eq!(actual, expected);
@@ -1643,7 +1855,9 @@ mod tests {
         message.share_direction = false;
         message.item_type = 4;
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "Dec 31, 2000  4:00:00 PM\nUnknown\nStopped sharing location!\n\n";
 
         assert_eq!(actual, expected);
@@ -1722,6 +1936,24 @@ mod tests {
         assert_eq!(actual, Err("d.jpg"));
     }
 
+    #[test]
+    fn can_format_txt_genmoji() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let message = blank();
+
+        let mut attachment = fake_attachment();
+        attachment.emoji_image_content_identifier = Some("8F1D0C9A".to_string());
+        attachment.emoji_image_short_description = Some("Crab wearing a party hat".to_string());
+
+        let actual = exporter.format_genmoji(&mut attachment, &message);
+
+        assert_eq!(actual, "Crab wearing a party hat");
+    }
+
     #[test]
     fn can_format_txt_attachment_sticker() {
         // Create exporter
@@ -1774,6 +2006,10 @@ mod balloon_format_tests {
         app::AppMessage,
         app_store::AppStoreMessage,
         collaboration::CollaborationMessage,
+        digital_touch::{
+            models::{Color, Heartbeat, Point, Tap, TapPoint},
+            DigitalTouch,
+        },
         music::MusicMessage,
         placemark::{Placemark, PlacemarkMessage},
         url::URLMessage,
@@ -1798,7 +2034,7 @@ mod balloon_format_tests {
             placeholder: false,
         };
 
-        let expected = exporter.format_url(&blank(), &balloon, "");
+        let expected = exporter.format_url(&blank(), &(&balloon).into(), "");
         let actual = "url\ntitle\nsummary";
 
         assert_eq!(expected, actual);
@@ -1819,7 +2055,7 @@ mod balloon_format_tests {
             track_name: Some("track_name"),
         };
 
-        let expected = exporter.format_music(&balloon, "");
+        let expected = exporter.format_music(&(&balloon).into(), "");
         let actual = "track_name\nalbum\nartist\nurl\n";
 
         assert_eq!(expected, actual);
@@ -1841,12 +2077,56 @@ mod balloon_format_tests {
             app_name: Some("app_name"),
         };
 
-        let expected = exporter.format_collaboration(&balloon, "");
+        let expected = exporter.format_collaboration(&(&balloon).into(), "");
         let actual = "app_name message:\ntitle\nurl";
 
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn can_format_txt_digital_touch() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let balloon = DigitalTouch::Heartbeat(Heartbeat {
+            id: "id".to_string(),
+            bpm: 84.,
+            duration: 2,
+            broken_at: Some(1.5),
+        });
+
+        let expected = exporter.format_digital_touch(&blank(), &balloon, "");
+        let actual =
+            "Digital Touch Message: Heartbreak at 84 BPM for 2 seconds, broken after 1.50 seconds";
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn can_format_txt_digital_touch_tap() {
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let tap = || TapPoint {
+            location: Point { x: 0, y: 0 },
+            delay: 0,
+            color: Color::default(),
+        };
+        let balloon = DigitalTouch::Tap(Tap {
+            id: "id".to_string(),
+            taps: vec![tap(), tap()],
+        });
+
+        let expected = exporter.format_digital_touch(&blank(), &balloon, "");
+        let actual = "Digital Touch Message: Tapped 2 times";
+
+        assert_eq!(expected, actual);
+    }
+
     #[test]
     fn can_format_txt_apple_pay() {
         // Create exporter
@@ -1867,7 +2147,7 @@ mod balloon_format_tests {
             ldtext: Some("ldtext"),
         };
 
-        let expected = exporter.format_apple_pay(&balloon, "");
+        let expected = exporter.format_apple_pay(&(&balloon).into(), "");
         let actual = "caption transaction: ldtext";
 
         assert_eq!(expected, actual);
@@ -1893,7 +2173,7 @@ mod balloon_format_tests {
             ldtext: Some("ldtext"),
         };
 
-        let expected = exporter.format_fitness(&balloon, "");
+        let expected = exporter.format_fitness(&(&balloon).into(), "");
         let actual = "app_name message: ldtext";
 
         assert_eq!(expected, actual);
@@ -1919,7 +2199,7 @@ mod balloon_format_tests {
             ldtext: Some("ldtext"),
         };
 
-        let expected = exporter.format_slideshow(&balloon, "");
+        let expected = exporter.format_slideshow(&(&balloon).into(), "");
         let actual = "Photo album: ldtext url";
 
         assert_eq!(expected, actual);
@@ -1945,7 +2225,7 @@ mod balloon_format_tests {
             ldtext: Some("ldtext"),
         };
 
-        let expected = exporter.format_find_my(&balloon, "");
+        let expected = exporter.format_find_my(&(&balloon).into(), "");
         let actual = "app_name:  ldtext";
 
         assert_eq!(expected, actual);
@@ -1974,7 +2254,7 @@ mod balloon_format_tests {
             ldtext: Some("Check In: Timer Started"),
         };
 
-        let expected = exporter.format_check_in(&balloon, "");
+        let expected = exporter.format_check_in(&(&balloon).into(), "");
         let actual = "Check\u{a0}In: Timer Started\nChecked in at Oct 14, 2023  1:54:29 PM";
 
         assert_eq!(expected, actual);
@@ -2003,7 +2283,7 @@ mod balloon_format_tests {
             ldtext: Some("Check In: Has not checked in when expected, location shared"),
         };
 
-        let expected = exporter.format_check_in(&balloon, "");
+        let expected = exporter.format_check_in(&(&balloon).into(), "");
         let actual = "Check\u{a0}In: Has not checked in when expected, location shared\nChecked in at Oct 14, 2023  1:54:29 PM";
 
         assert_eq!(expected, actual);
@@ -2032,7 +2312,7 @@ mod balloon_format_tests {
             ldtext: Some("Check In: Fake Location"),
         };
 
-        let expected = exporter.format_check_in(&balloon, "");
+        let expected = exporter.format_check_in(&(&balloon).into(), "");
         let actual = "Check\u{a0}In: Fake Location\nChecked in at Oct 14, 2023  1:54:29 PM";
 
         assert_eq!(expected, actual);
@@ -2054,7 +2334,7 @@ mod balloon_format_tests {
             genre: Some("genre"),
         };
 
-        let expected = exporter.format_app_store(&balloon, "");
+        let expected = exporter.format_app_store(&(&balloon).into(), "");
         let actual = "app_name\ndescription\nplatform\ngenre\nurl";
 
         assert_eq!(expected, actual);
@@ -2085,7 +2365,7 @@ mod balloon_format_tests {
             },
         };
 
-        let expected = exporter.format_placemark(&balloon, "");
+        let expected = exporter.format_placemark(&(&balloon).into(), "");
         let actual = "Name\nurl\nname\naddress\nstate\ncity\niso_country_code\npostal_code\ncountry\nstreet\nsub_administrative_area\nsub_locality";
 
         assert_eq!(expected, actual);
@@ -2111,7 +2391,8 @@ mod balloon_format_tests {
             ldtext: Some("ldtext"),
         };
 
-        let expected = exporter.format_generic_app(&balloon, "bundle_id", &mut vec![], "");
+        let expected =
+            exporter.format_generic_app(&(&balloon).into(), "bundle_id", &mut vec![], "");
         let actual = "app_name message:\ntitle\nsubtitle\ncaption\nsubcaption\ntrailing_caption\ntrailing_subcaption";
 
         assert_eq!(expected, actual);
@@ -2126,14 +2407,50 @@ mod edited_tests {
         io::Read,
     };
 
-    use super::tests::{blank, fake_config, fake_options};
+    use super::tests::{blank, document, fake_config, fake_options};
 
     use crate::{exporters::exporter::Writer, Exporter, TXT};
     use imessage_database::{
-        message_types::edited::{EditStatus, EditedMessage, EditedMessagePart},
+        message_types::edited::{EditStatus, EditedEvent, EditedMessage, EditedMessagePart},
         util::typedstream::parser::TypedStreamReader,
     };
 
+    #[test]
+    fn can_format_txt_edit_diff() {
+        // Set timezone to America/Los_Angeles for consistent Local time
+        set_var("TZ", "America/Los_Angeles");
+
+        // Create exporter
+        let options = fake_options();
+        let config = fake_config(options);
+        let exporter = TXT::new(&config).unwrap();
+
+        let mut message = blank();
+        message.is_from_me = true;
+        let edited = EditedMessage {
+            parts: vec![EditedMessagePart {
+                status: EditStatus::Edited,
+                edit_history: vec![
+                    EditedEvent {
+                        date: 674526582885055488,
+                        text: "see you at 5".to_string(),
+                        guid: None,
+                    },
+                    EditedEvent {
+                        date: 674526592885055488,
+                        text: "see you at 6 <3".to_string(),
+                        guid: None,
+                    },
+                ],
+            }],
+        };
+
+        let actual = exporter.format_edited(&message, &edited, 0, "").unwrap();
+        let expected = "May 17, 2022  5:29:42 PM see you at 5\nEdited 10 seconds later: see you at 6 <3\n- see you at [-5-]\n+ see you at {+6 <3+}\n";
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn can_format_txt_conversion_final_unsent() {
         // Set timezone to America/Los_Angeles for consistent Local time
@@ -2186,7 +2503,9 @@ mod edited_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "May 17, 2022  5:29:42 PM\nMe\nFrom arbitrary byte stream:\r\nAttachment missing!\nTo native Rust data structures:\r\nYou unsent this message part 1 hour, 49 seconds after sending!\n\n";
 
         assert_eq!(actual, expected);
@@ -2223,7 +2542,9 @@ mod edited_tests {
         let mut parser = TypedStreamReader::from(&bytes);
         message.components = parser.parse().ok();
 
-        let actual = exporter.format_message(&message, 0).unwrap();
+        let actual = exporter
+            .format_message(&document(&config, &message), 0)
+            .unwrap();
         let expected = "May 17, 2022  5:29:42 PM\nMe\nFrom arbitrary byte stream:\r\nAttachment missing!\nTo native Rust data structures:\r\n\n";
 
         assert_eq!(actual, expected);
@@ -3,35 +3,55 @@
 mod app;
 mod exporters;
 
-pub use exporters::{exporter::Exporter, html::HTML, json::JSON, txt::TXT};
+use std::process::ExitCode;
+
+pub use exporters::{exporter::Exporter, html::HTML, json::JSON, template::Templated, txt::TXT};
 
 use app::{
+    config_file::default_config_path,
+    dump::dump_typedstream,
+    export_type::builtin_formats,
     options::{from_command_line, Options},
     runtime::Config,
 };
 
-fn main() {
+fn main() -> ExitCode {
+    // Get the formats that can be exported to
+    let formats = builtin_formats();
     // Get args from command line
     let args = from_command_line();
     // Create application options
-    let options = Options::from_args(&args);
+    let options = Options::from_args(&args, &default_config_path(), &formats);
 
     // Create app state and start
     if let Err(why) = &options {
         eprintln!("{why}");
     } else {
         match options {
-            Ok(options) => match Config::new(options) {
-                Ok(app) => {
-                    if let Err(why) = app.start() {
-                        eprintln!("Unable to export: {why}");
+            Ok(options) => match &options.dump_typedstream {
+                // Dumping a typedstream does not need the caches built for an export
+                Some(source) => match dump_typedstream(source, &options.get_db_path()) {
+                    Ok(tree) => {
+                        println!("{tree}");
+                        return ExitCode::SUCCESS;
+                    }
+                    Err(why) => eprintln!("Unable to dump typedstream: {why}"),
+                },
+                None => match Config::new(options) {
+                    Ok(app) => {
+                        if let Err(why) = app.start(&formats) {
+                            eprintln!("Unable to export: {why}");
+                        } else {
+                            return ExitCode::SUCCESS;
+                        }
+                    }
+                    Err(why) => {
+                        eprintln!("Invalid configuration: {why}");
                     }
-                }
-                Err(why) => {
-                    eprintln!("Invalid configuration: {why}");
-                }
+                },
             },
             Err(why) => eprintln!("Invalid command line options: {why}"),
         }
     }
+    ExitCode::FAILURE
 }
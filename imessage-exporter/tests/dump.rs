@@ -0,0 +1,105 @@
+/*!
+ Dumps the `typedstream` in a fixture database message and in a file and checks that the JSON describes it.
+*/
+
+use std::{
+    env::{current_dir, temp_dir},
+    ffi::OsStr,
+    fs::{read, remove_file, write},
+    path::PathBuf,
+    process::{id, Command, Output},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use imessage_fixtures::{guid, Fixture, NewMessage, Schema};
+use serde_json::Value;
+
+/// The path to a `typedstream` file in the library's test data
+fn typedstream(name: &str) -> PathBuf {
+    current_dir()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("imessage-database/test_data/typedstream")
+        .join(name)
+}
+
+/// Run the binary with the given arguments and an empty config file, returning the process output
+fn run(args: &[&OsStr]) -> Output {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let config = temp_dir().join(format!(
+        "imessage-exporter-dump-{}-{}.toml",
+        id(),
+        RUNS.fetch_add(1, Ordering::Relaxed)
+    ));
+    write(&config, "").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_imessage-exporter"))
+        .args(args)
+        .arg("--config")
+        .arg(&config)
+        .output()
+        .unwrap();
+
+    remove_file(&config).unwrap();
+    output
+}
+
+#[test]
+fn can_dump_message() {
+    let fixture = Fixture::temporary(Schema::latest()).unwrap();
+    let handle = fixture
+        .add_handle("+15558675309", "iMessage", None)
+        .unwrap();
+    let chat = fixture
+        .add_chat("+15558675309", "iMessage", None, &[handle])
+        .unwrap();
+    let message = fixture
+        .add_message(&NewMessage {
+            guid: guid(1),
+            text: Some("Test Dad ".to_string()),
+            attributed_body: Some(read(typedstream("Mention")).unwrap()),
+            service: Some("iMessage".to_string()),
+            handle_id: handle,
+            chat_id: Some(chat),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let rowid = message.to_string();
+    let db_path = fixture.db_path();
+    let output = run(&[
+        "--dump-typedstream".as_ref(),
+        rowid.as_ref(),
+        "--db-path".as_ref(),
+        db_path.as_os_str(),
+    ]);
+    assert!(output.status.success());
+
+    let tree: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(tree["components"][0]["class"]["name"], "NSMutableString");
+    assert_eq!(tree["components"][0]["data"][0]["value"], "Test Dad ");
+}
+
+#[test]
+fn can_dump_file() {
+    let path = typedstream("TextEffects");
+    let output = run(&["--dump-typedstream".as_ref(), path.as_os_str()]);
+    assert!(output.status.success());
+
+    let tree: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(tree["components"][0]["class"]["name"], "NSString");
+    assert_eq!(
+        tree["components"][2]["children"][0]["data"][0]["value"],
+        "__kIMTextEffectAttributeName"
+    );
+}
+
+#[test]
+fn cant_dump_legacy_file() {
+    let path = typedstream("ExtraData");
+    let output = run(&["--dump-typedstream".as_ref(), path.as_os_str()]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unable to dump typedstream"));
+}
@@ -0,0 +1,69 @@
+/*!
+ Exports only the messages in Recently Deleted and checks that they are grouped by the chat they were deleted from.
+*/
+
+use std::{
+    fs::{read_dir, read_to_string, write},
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+use imessage_fixtures::{Fixture, Schema};
+
+/// Export the populated fixture in Recently Deleted mode, returning the process output, the fixture and the export directory inside it
+fn export(schema: Schema) -> (Output, Fixture, PathBuf) {
+    let fixture = Fixture::temporary(schema).unwrap();
+    fixture.populate().unwrap();
+    let export_root = fixture.root.join("export");
+    // Pass an empty config file so the default one is never read
+    let config = fixture.root.join("config.toml");
+    write(&config, "").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_imessage-exporter"))
+        .args(["--format", "txt", "--copy-method", "disabled"])
+        .arg("--config")
+        .arg(&config)
+        .arg("--recently-deleted")
+        .arg("--db-path")
+        .arg(fixture.db_path())
+        .arg("--export-path")
+        .arg(&export_root)
+        .arg("--ignore-disk-warning")
+        .env("TZ", "UTC")
+        .output()
+        .unwrap();
+
+    (output, fixture, export_root)
+}
+
+#[test]
+fn can_export_recently_deleted() {
+    let (output, _fixture, export_root) = export(Schema::latest());
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let mut conversations: Vec<String> = read_dir(&export_root)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".txt"))
+        .filter(|name| name != "orphaned.txt")
+        .collect();
+    conversations.sort();
+    assert_eq!(conversations, vec!["Weekend Plans - 3.txt"]);
+
+    let conversation = read_to_string(export_root.join("Weekend Plans - 3.txt")).unwrap();
+    assert!(conversation.contains("Oops, wrong chat"));
+    assert!(conversation.contains("This message was deleted from the conversation on"));
+    assert!(!conversation.contains("Thanks"));
+}
+
+#[test]
+fn cant_export_recently_deleted_unsupported() {
+    let (output, ..) = export(Schema::Catalina);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Recently Deleted"));
+}
@@ -0,0 +1,168 @@
+/*!
+ Exports a fixture database with problems in it and checks that they are listed in the export report.
+*/
+
+use std::{
+    fs::{read_to_string, write},
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+use imessage_fixtures::{guid, Fixture, NewAttachment, NewMessage, Schema};
+use serde_json::Value;
+
+/// Build a fixture with a missing attachment file, an unreadable app message and a message that references a chat that does not exist
+fn broken_fixture() -> Fixture {
+    let fixture = Fixture::temporary(Schema::latest()).unwrap();
+    let handle = fixture
+        .add_handle("+15558675309", "iMessage", None)
+        .unwrap();
+    let chat = fixture
+        .add_chat("+15558675309", "iMessage", None, &[handle])
+        .unwrap();
+
+    let photo = fixture
+        .add_message(&NewMessage {
+            guid: guid(1),
+            text: Some("\u{FFFC}".to_string()),
+            service: Some("iMessage".to_string()),
+            handle_id: handle,
+            chat_id: Some(chat),
+            ..Default::default()
+        })
+        .unwrap();
+    fixture
+        .add_attachment(
+            photo,
+            &NewAttachment {
+                guid: guid(2),
+                transfer_name: "IMG_0002.png".to_string(),
+                mime_type: Some("image/png".to_string()),
+                uti: Some("public.png".to_string()),
+                data: None,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    // An app message whose payload is not a property list
+    fixture
+        .add_message(&NewMessage {
+            guid: guid(4),
+            text: Some("\u{FFFC}".to_string()),
+            service: Some("iMessage".to_string()),
+            handle_id: handle,
+            balloon_bundle_id: Some(
+                "com.apple.messages.MSMessageExtensionBalloonPlugin:0000000000:com.example.app"
+                    .to_string(),
+            ),
+            payload_data: Some(b"not a plist".to_vec()),
+            chat_id: Some(chat),
+            ..Default::default()
+        })
+        .unwrap();
+
+    // Join a message to a chat that is not in the chat table
+    fixture.db.execute("PRAGMA foreign_keys = OFF", []).unwrap();
+    fixture
+        .add_message(&NewMessage {
+            guid: guid(3),
+            text: Some("Hello".to_string()),
+            service: Some("iMessage".to_string()),
+            handle_id: handle,
+            chat_id: Some(404),
+            ..Default::default()
+        })
+        .unwrap();
+
+    fixture
+}
+
+/// Export the broken fixture, returning the process output, the fixture and the export directory inside it
+fn export(format: &str, strict: bool) -> (Output, Fixture, PathBuf) {
+    let fixture = broken_fixture();
+    let export_root = fixture.root.join("export");
+    // Only the options below apply, even if the default config file exists
+    let config = fixture.root.join("config.toml");
+    write(&config, "").unwrap();
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_imessage-exporter"));
+    command
+        .args(["--format", format, "--copy-method", "efficient"])
+        .arg("--config")
+        .arg(&config)
+        .arg("--db-path")
+        .arg(fixture.db_path())
+        .arg("--export-path")
+        .arg(&export_root)
+        .arg("--ignore-disk-warning");
+    if strict {
+        command.arg("--strict");
+    }
+
+    (command.output().unwrap(), fixture, export_root)
+}
+
+/// Export the broken fixture in `format`, check the problems every format reports, and return the report
+fn check_report(format: &str) -> Value {
+    let (output, _fixture, export_root) = export(format, false);
+    assert!(output.status.success());
+
+    let report: Value =
+        serde_json::from_str(&read_to_string(export_root.join("export_report.json")).unwrap())
+            .unwrap();
+    assert_eq!(report["counts"]["balloon"], 1);
+    assert_eq!(report["counts"]["missing_chat"], 1);
+
+    let warnings = report["warnings"].as_array().unwrap();
+    let balloon = warnings
+        .iter()
+        .find(|warning| warning["kind"] == "balloon")
+        .unwrap();
+    assert_eq!(balloon["guid"], guid(4));
+    assert_eq!(balloon["chat"], "+15558675309");
+
+    let chat = warnings
+        .iter()
+        .find(|warning| warning["kind"] == "missing_chat")
+        .unwrap();
+    assert_eq!(chat["guid"], guid(3));
+    assert_eq!(chat["chat_id"], 404);
+    assert_eq!(chat["chat"], Value::Null);
+
+    let summary = format!("{} problems found during export", report["total"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains(&summary));
+
+    report
+}
+
+#[test]
+fn can_write_report_txt() {
+    let report = check_report("txt");
+    assert_eq!(report["total"], 3);
+    assert_eq!(report["counts"]["missing_attachment"], 1);
+
+    let attachment = report["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|warning| warning["kind"] == "missing_attachment")
+        .unwrap();
+    assert_eq!(attachment["guid"], guid(1));
+    assert_eq!(attachment["chat"], "+15558675309");
+}
+
+#[test]
+fn can_write_report_json() {
+    // JSON exports do not copy attachments, so they never find missing files
+    let report = check_report("json");
+    assert_eq!(report["total"], 2);
+}
+
+#[test]
+fn cant_export_strict_with_warnings() {
+    let (output, _fixture, export_root) = export("txt", true);
+    assert!(!output.status.success());
+    assert!(export_root.join("export_report.json").exists());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--strict"));
+}
@@ -0,0 +1,266 @@
+/*!
+ Exports fixture databases end to end and compares the output to the golden files in `tests/snapshots`.
+
+ Each export copies attachments, so the golden files also cover the paths attachments are copied to.
+ Text files are stored in full with the fixture and export directories replaced by `$FIXTURE` and `$EXPORT`.
+ Every file, including binary attachments, is listed with its size in `MANIFEST`.
+
+ To update the golden files after an intentional change to the output, run:
+
+ ```zsh
+ UPDATE_SNAPSHOTS=1 cargo test -p imessage-exporter --test snapshots
+ ```
+*/
+
+use std::{
+    collections::BTreeMap,
+    env::var_os,
+    fs::{create_dir_all, read, read_dir, remove_dir_all, write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use imessage_database::tables::messages::models::OwnedMessage;
+use imessage_fixtures::{Fixture, Schema};
+use serde_json::Value;
+
+/// Set this environment variable to overwrite the golden files with the current output
+const UPDATE: &str = "UPDATE_SNAPSHOTS";
+/// The name of the file that lists every file in an export
+const MANIFEST: &str = "MANIFEST";
+/// Files in JSON exports that do not contain messages
+const JSON_METADATA: [&str; 2] = ["export_report.json", "participants.json"];
+/// Files with these extensions are compared line by line, everything else is compared by size
+const TEXT_EXTENSIONS: [&str; 4] = ["txt", "html", "json", "svg"];
+
+/// Run the exporter against a fixture with the given schema and any extra arguments, returning the fixture and the export directory inside it
+fn export(schema: Schema, format: &str, args: &[&str]) -> (Fixture, PathBuf) {
+    let fixture = Fixture::temporary(schema).unwrap();
+    fixture.populate().unwrap();
+    let export_root = fixture.root.join("export");
+    // An empty config file keeps the default one from changing the golden output
+    let config = fixture.root.join("config.toml");
+    write(&config, "").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_imessage-exporter"))
+        .args(["--format", format, "--copy-method", "efficient"])
+        .arg("--config")
+        .arg(&config)
+        .arg("--db-path")
+        .arg(fixture.db_path())
+        .arg("--export-path")
+        .arg(&export_root)
+        .arg("--ignore-disk-warning")
+        .args(args)
+        // Dates are rendered in the local timezone
+        .env("TZ", "UTC")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    (fixture, export_root)
+}
+
+/// Get every file in `dir`, keyed by its path relative to `root` with `/` separators
+fn collect(root: &Path, dir: &Path, files: &mut BTreeMap<String, Vec<u8>>) {
+    for entry in read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect(root, &path, files);
+        } else {
+            let relative = path
+                .strip_prefix(root)
+                .unwrap()
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.insert(relative, read(&path).unwrap());
+        }
+    }
+}
+
+/// `true` if a file in an export should be compared by content
+fn is_text(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| TEXT_EXTENSIONS.contains(&ext))
+}
+
+/// Render an export as the files its golden directory should contain
+fn render(fixture_root: &Path, export_root: &Path) -> BTreeMap<String, String> {
+    let mut files = BTreeMap::new();
+    collect(export_root, export_root, &mut files);
+
+    let mut rendered = BTreeMap::new();
+    let mut manifest = String::new();
+    for (path, data) in files {
+        manifest.push_str(&format!("{path} ({} bytes)\n", data.len()));
+        if is_text(&path) {
+            let text = String::from_utf8_lossy(&data)
+                .replace(&export_root.to_string_lossy().to_string(), "$EXPORT")
+                .replace(&fixture_root.to_string_lossy().to_string(), "$FIXTURE");
+            rendered.insert(path, text);
+        }
+    }
+    rendered.insert(MANIFEST.to_string(), manifest);
+    rendered
+}
+
+/// Describe the first difference between two files
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(left), Some(right)) if left == right => line += 1,
+            (left, right) => {
+                return format!(
+                    "line {line}\n  expected: {}\n    actual: {}",
+                    left.unwrap_or("<end of file>"),
+                    right.unwrap_or("<end of file>")
+                )
+            }
+        }
+    }
+}
+
+/// Compare an export to its golden files, or overwrite them if [`UPDATE`] is set
+///
+/// Exports run with extra arguments must match the same golden files, so they never overwrite them.
+fn snapshot(schema: Schema, format: &str, args: &[&str]) -> Vec<String> {
+    let (fixture, export_root) = export(schema, format, args);
+    let actual = render(&fixture.root, &export_root);
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(schema.to_string())
+        .join(format);
+
+    if var_os(UPDATE).is_some() && args.is_empty() {
+        if golden.exists() {
+            remove_dir_all(&golden).unwrap();
+        }
+        for (path, contents) in &actual {
+            let path = golden.join(path);
+            create_dir_all(path.parent().unwrap()).unwrap();
+            write(path, contents).unwrap();
+        }
+        return vec![];
+    }
+
+    let mut expected = BTreeMap::new();
+    if golden.exists() {
+        collect(&golden, &golden, &mut expected);
+    }
+
+    let mut failures = vec![];
+    for path in expected.keys() {
+        if !actual.contains_key(path) {
+            failures.push(format!("{schema}/{format}/{path}: missing from export"));
+        }
+    }
+    for (path, contents) in &actual {
+        match expected.get(path) {
+            Some(golden) => {
+                let golden = String::from_utf8_lossy(golden);
+                if golden != *contents {
+                    failures.push(format!(
+                        "{schema}/{format}/{path}: differs at {}",
+                        first_difference(&golden, contents)
+                    ));
+                }
+            }
+            None => failures.push(format!("{schema}/{format}/{path}: missing golden file")),
+        }
+    }
+    failures
+}
+
+/// Snapshot every schema generation in the given format, failing with every difference found
+fn snapshot_all(format: &str, args: &[&str]) {
+    let failures: Vec<String> = Schema::ALL
+        .into_iter()
+        .flat_map(|schema| snapshot(schema, format, args))
+        .collect();
+
+    assert!(
+        failures.is_empty(),
+        "Export does not match golden files:\n{}\n\nIf this change is intentional, run `{UPDATE}=1 cargo test -p imessage-exporter --test snapshots`",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn can_snapshot_html() {
+    snapshot_all("html", &[]);
+}
+
+#[test]
+fn can_snapshot_txt() {
+    snapshot_all("txt", &[]);
+}
+
+#[test]
+fn can_snapshot_json() {
+    snapshot_all("json", &[]);
+}
+
+#[test]
+fn can_snapshot_low_memory() {
+    // Low-memory mode must produce the same output as the default mode
+    for format in ["html", "txt", "json"] {
+        snapshot_all(format, &["--low-memory"]);
+    }
+}
+
+#[test]
+fn can_read_json_snapshots_as_owned_messages() {
+    for schema in Schema::ALL {
+        let golden = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/snapshots")
+            .join(schema.to_string())
+            .join("json");
+        let mut files = BTreeMap::new();
+        collect(&golden, &golden, &mut files);
+
+        for (path, data) in files {
+            if !path.ends_with(".json") || JSON_METADATA.contains(&path.as_str()) {
+                continue;
+            }
+            let rows: Vec<Value> = serde_json::from_slice(&data).unwrap();
+            for row in rows {
+                let message: OwnedMessage = serde_json::from_value(row.clone())
+                    .unwrap_or_else(|why| panic!("{schema}/json/{path}: {why}"));
+                // Every column of the row is kept when reading it back, the exporter's own fields follow them
+                let Value::Object(columns) = serde_json::to_value(&message).unwrap() else {
+                    panic!("{schema}/json/{path}: {} is not an object", message.guid);
+                };
+                for (column, value) in columns {
+                    assert_eq!(
+                        Some(&value),
+                        row.get(&column),
+                        "{schema}/json/{path}: {} {column}",
+                        message.guid
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn can_snapshot_default_templates() {
+    // The default templates must render the same output as the built-in exporters
+    for format in ["html", "txt"] {
+        let templates = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/exporters/resources/templates")
+            .join(format);
+        snapshot_all(format, &["--templates", templates.to_str().unwrap()]);
+    }
+}
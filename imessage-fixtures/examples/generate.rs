@@ -0,0 +1,46 @@
+/*!
+ Writes a large fixture database for benchmarking exports.
+
+ Usage: `cargo run -p imessage-fixtures --example generate -- <directory> [chats] [messages per chat]`
+*/
+
+use std::{env::args, path::PathBuf, process::exit};
+
+use imessage_fixtures::{Fixture, Schema};
+
+fn main() {
+    let args: Vec<String> = args().skip(1).collect();
+    let Some(root) = args.first().map(PathBuf::from) else {
+        eprintln!("Usage: generate <directory> [chats] [messages per chat]");
+        exit(1);
+    };
+    let number = |idx: usize, default: usize| {
+        args.get(idx)
+            .map(|value| {
+                value.parse().unwrap_or_else(|_| {
+                    eprintln!("{value} is not a number");
+                    exit(1);
+                })
+            })
+            .unwrap_or(default)
+    };
+    let chats = number(1, 100);
+    let messages_per_chat = number(2, 1000);
+
+    let result = Fixture::create(&root, Schema::latest()).and_then(|fixture| {
+        fixture
+            .populate_scaled(chats, messages_per_chat)
+            .map(|_| fixture)
+    });
+    match result {
+        Ok(fixture) => println!(
+            "Wrote {} messages to {}",
+            chats * messages_per_chat,
+            fixture.db_path().display()
+        ),
+        Err(why) => {
+            eprintln!("{why}");
+            exit(1);
+        }
+    }
+}
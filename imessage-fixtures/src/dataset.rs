@@ -0,0 +1,630 @@
+/*!
+ Fills fixtures with realistic conversations.
+
+ The standard dataset uses the binary samples from `imessage-database/test_data` so the
+ messages it generates exercise the same parsers as real databases do.
+*/
+
+use std::{
+    env::temp_dir,
+    fs::{read_dir, remove_dir_all, remove_file, rename, File},
+    path::{Path, PathBuf},
+    process,
+    sync::OnceLock,
+};
+
+use crate::{
+    error::FixtureError,
+    fixture::{guid, Fixture, NewAttachment, NewMessage},
+    schema::Schema,
+};
+
+/// 2023-06-01 12:00:00 UTC, in nanoseconds since the Apple epoch
+pub const BASE_DATE: i64 = 707_313_600_000_000_000;
+/// The time between two consecutive generated messages, in nanoseconds
+pub const STEP: i64 = 60_000_000_000;
+
+const TEXT_STYLES: &[u8] =
+    include_bytes!("../../imessage-database/test_data/typedstream/TextStyles");
+const MENTION: &[u8] = include_bytes!("../../imessage-database/test_data/typedstream/Mention");
+const AUDIO_TRANSCRIPTION: &[u8] =
+    include_bytes!("../../imessage-database/test_data/typedstream/AudioTranscription");
+const GENMOJI: &[u8] = include_bytes!("../../imessage-database/test_data/typedstream/Genmoji");
+const TEXT_EFFECTS: &[u8] =
+    include_bytes!("../../imessage-database/test_data/typedstream/TextEffects");
+const URL: &[u8] = include_bytes!("../../imessage-database/test_data/url_message/URL.plist");
+const MUSIC: &[u8] =
+    include_bytes!("../../imessage-database/test_data/music_message/AppleMusic.plist");
+const HANDWRITING: &[u8] =
+    include_bytes!("../../imessage-database/test_data/handwritten_message/hello.bin");
+const DIGITAL_TOUCH: &[u8] =
+    include_bytes!("../../imessage-database/test_data/digital_touch_message/heartbeat.bin");
+const SKETCH: &[u8] =
+    include_bytes!("../../imessage-database/test_data/digital_touch_message/sketch.bin");
+const EDITED: &[u8] =
+    include_bytes!("../../imessage-database/test_data/edited_message/Edited.plist");
+const UNSENT: &[u8] =
+    include_bytes!("../../imessage-database/test_data/edited_message/Deleted.plist");
+const STICKER: &[u8] = include_bytes!("../../imessage-database/test_data/stickers/outline.heic");
+const IMAGE: &[u8] = include_bytes!("../assets/IMG_0001.png");
+const DOCUMENT: &[u8] = include_bytes!("../assets/Itinerary.txt");
+const AUDIO: &[u8] = include_bytes!("../assets/Audio Message.caf");
+
+/// Generates message rows with sequential GUIDs and dates
+struct Timeline {
+    count: u64,
+}
+
+impl Timeline {
+    /// Start a message with the next GUID and date
+    fn next(&mut self, chat_id: i32, service: &str) -> NewMessage {
+        self.count += 1;
+        let date = BASE_DATE + STEP * self.count as i64;
+        NewMessage {
+            guid: guid(self.count),
+            service: Some(service.to_string()),
+            date,
+            date_delivered: date,
+            chat_id: Some(chat_id),
+            ..Default::default()
+        }
+    }
+
+    /// Start a message sent by the database owner
+    fn sent(&mut self, chat_id: i32, service: &str, text: &str) -> NewMessage {
+        NewMessage {
+            text: Some(text.to_string()),
+            is_from_me: true,
+            is_read: true,
+            ..self.next(chat_id, service)
+        }
+    }
+
+    /// Start a message received from `handle_id`
+    fn received(&mut self, chat_id: i32, service: &str, handle_id: i32, text: &str) -> NewMessage {
+        let message = self.next(chat_id, service);
+        NewMessage {
+            text: Some(text.to_string()),
+            handle_id,
+            is_read: true,
+            date_read: message.date + STEP / 2,
+            ..message
+        }
+    }
+
+    /// The GUID of the next attachment; attachments share the message GUID sequence
+    fn attachment_guid(&mut self) -> String {
+        self.count += 1;
+        guid(self.count)
+    }
+}
+
+/// The prefix of the directories and lock files created by [`Fixture::shared`]
+const SHARED_PREFIX: &str = "imessage-fixtures-shared-";
+
+/// Lock the current process's shared fixture directory, returning the held lock and the directory
+///
+/// The lock is taken under a temporary name and then renamed, so [`remove_abandoned`] never sees a lock that is not held yet.
+fn lock_shared() -> Result<(File, PathBuf), FixtureError> {
+    let name = format!("{SHARED_PREFIX}{}", process::id());
+    let pending = temp_dir().join(format!(".{name}.lock"));
+    let lock = File::create(&pending)?;
+    lock.lock()?;
+    rename(&pending, temp_dir().join(format!("{name}.lock")))?;
+    Ok((lock, temp_dir().join(name)))
+}
+
+/// Remove shared fixtures left behind by processes that have exited
+///
+/// The operating system releases a process's locks when it exits, so a lock that can be taken belongs to a fixture nobody uses.
+fn remove_abandoned() {
+    let Ok(entries) = read_dir(temp_dir()) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let is_shared_lock = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(SHARED_PREFIX) && name.ends_with(".lock"));
+        if !is_shared_lock {
+            continue;
+        }
+        if let Ok(lock) = File::open(&path) {
+            if lock.try_lock().is_ok() {
+                let _ = remove_dir_all(path.with_extension(""));
+                drop(lock);
+                let _ = remove_file(&path);
+            }
+        }
+    }
+}
+
+impl Fixture {
+    /// Get the path to a `chat.db` with the standard dataset using the [latest](Schema::latest) schema, shared by every caller in the process
+    ///
+    /// The fixture is created in the system temporary directory the first time this is called. Statics are never dropped,
+    /// so the process holds a lock on the fixture until it exits and later processes remove fixtures whose lock was released.
+    pub fn shared() -> &'static Path {
+        static SHARED: OnceLock<(File, PathBuf)> = OnceLock::new();
+        &SHARED
+            .get_or_init(|| {
+                remove_abandoned();
+                let (lock, root) = lock_shared()
+                    .unwrap_or_else(|why| panic!("Unable to lock shared fixture: {why}"));
+                let fixture = Fixture::create(&root, Schema::latest())
+                    .unwrap_or_else(|why| panic!("Unable to create shared fixture: {why}"));
+                fixture
+                    .populate()
+                    .unwrap_or_else(|why| panic!("Unable to populate shared fixture: {why}"));
+                (lock, fixture.db_path())
+            })
+            .1
+    }
+
+    /// Fill the fixture with the standard dataset
+    ///
+    /// The dataset contains direct messages, a group chat and an SMS conversation with plain text, styled text,
+    /// mentions, text effects, tapbacks, stickers, app balloons, attachments, replies, edits, unsent messages,
+    /// group announcements, expressives, recently deleted and scheduled messages, and Genmoji. Rows for features the fixture's [`Schema`]
+    /// does not support are written without the columns or tables they need, as an older database would store them.
+    pub fn populate(&self) -> Result<(), FixtureError> {
+        let mut timeline = Timeline { count: 0 };
+
+        // Handles
+        let person = Some("PERSON-0001");
+        let phone = self.add_handle("+15555550100", "iMessage", person)?;
+        let email = self.add_handle("person@example.com", "iMessage", person)?;
+        let friend = self.add_handle("+15555550101", "iMessage", None)?;
+        let coworker = self.add_handle("+15555550102", "iMessage", None)?;
+        let sms = self.add_handle("+15555550103", "SMS", None)?;
+        let newcomer = self.add_handle("+15555550104", "iMessage", None)?;
+
+        // Chats
+        let direct = self.add_chat("+15555550100", "iMessage", None, &[phone])?;
+        let direct_email = self.add_chat("person@example.com", "iMessage", None, &[email])?;
+        let group = self.add_chat(
+            "chat000000000000000001",
+            "iMessage",
+            Some("Weekend Plans"),
+            &[phone, friend, coworker],
+        )?;
+        let text_chat = self.add_chat("+15555550103", "SMS", None, &[sms])?;
+
+        // Direct messages with text, tapbacks and attachments
+        let greeting = timeline.received(
+            direct,
+            "iMessage",
+            phone,
+            "Hey! Are we still on for Saturday?",
+        );
+        self.add_message(&greeting)?;
+        let answer = timeline.sent(direct, "iMessage", "Yes, see you at noon");
+        self.add_message(&answer)?;
+        self.add_message(&NewMessage {
+            text: Some("Loved “Yes, see you at noon”".to_string()),
+            associated_message_guid: Some(format!("p:0/{}", answer.guid)),
+            associated_message_type: 2000,
+            ..timeline.received(direct, "iMessage", phone, "")
+        })?;
+        self.add_message(&NewMessage {
+            text: Some("Liked “Hey! Are we still on for Saturday?”".to_string()),
+            associated_message_guid: Some(format!("p:0/{}", greeting.guid)),
+            associated_message_type: 2001,
+            ..timeline.sent(direct, "iMessage", "")
+        })?;
+        self.add_message(&NewMessage {
+            text: Some("Removed a like from “Hey! Are we still on for Saturday?”".to_string()),
+            associated_message_guid: Some(format!("p:0/{}", greeting.guid)),
+            associated_message_type: 3001,
+            ..timeline.sent(direct, "iMessage", "")
+        })?;
+        if self.schema.has_custom_emoji() {
+            self.add_message(&NewMessage {
+                text: Some("Reacted 🦀 to “Yes, see you at noon”".to_string()),
+                associated_message_guid: Some(format!("p:0/{}", answer.guid)),
+                associated_message_type: 2006,
+                associated_message_emoji: Some("🦀".to_string()),
+                ..timeline.received(direct, "iMessage", phone, "")
+            })?;
+        }
+
+        let sticker = self.add_message(&NewMessage {
+            text: Some("\u{FFFC}".to_string()),
+            associated_message_guid: Some(format!("p:0/{}", greeting.guid)),
+            associated_message_type: 1000,
+            ..timeline.received(direct, "iMessage", phone, "")
+        })?;
+        self.add_attachment(
+            sticker,
+            &NewAttachment {
+                guid: timeline.attachment_guid(),
+                transfer_name: "sticker.heic".to_string(),
+                mime_type: Some("image/heic".to_string()),
+                uti: Some("public.heic".to_string()),
+                is_sticker: true,
+                created_date: BASE_DATE,
+                data: Some(STICKER.to_vec()),
+                ..Default::default()
+            },
+        )?;
+
+        let photo = self.add_message(&timeline.sent(direct, "iMessage", "\u{FFFC}"))?;
+        self.add_attachment(
+            photo,
+            &NewAttachment {
+                guid: timeline.attachment_guid(),
+                transfer_name: "IMG_0001.png".to_string(),
+                mime_type: Some("image/png".to_string()),
+                uti: Some("public.png".to_string()),
+                created_date: BASE_DATE,
+                data: Some(IMAGE.to_vec()),
+                ..Default::default()
+            },
+        )?;
+
+        let audio = self.add_message(&NewMessage {
+            attributed_body: Some(AUDIO_TRANSCRIPTION.to_vec()),
+            ..timeline.received(direct, "iMessage", phone, "\u{FFFC}")
+        })?;
+        self.add_attachment(
+            audio,
+            &NewAttachment {
+                guid: timeline.attachment_guid(),
+                transfer_name: "Audio Message.caf".to_string(),
+                mime_type: Some("audio/x-caf".to_string()),
+                uti: Some("com.apple.coreaudio-format".to_string()),
+                created_date: BASE_DATE,
+                data: Some(AUDIO.to_vec()),
+                ..Default::default()
+            },
+        )?;
+
+        let document =
+            self.add_message(&timeline.sent(direct, "iMessage", "\u{FFFC}Here is the plan"))?;
+        self.add_attachment(
+            document,
+            &NewAttachment {
+                guid: timeline.attachment_guid(),
+                transfer_name: "Itinerary.txt".to_string(),
+                mime_type: Some("text/plain".to_string()),
+                uti: Some("public.plain-text".to_string()),
+                created_date: BASE_DATE,
+                data: Some(DOCUMENT.to_vec()),
+                ..Default::default()
+            },
+        )?;
+
+        // Rich text from `typedstream` bodies
+        self.add_message(&NewMessage {
+            attributed_body: Some(TEXT_STYLES.to_vec()),
+            ..timeline.sent(
+                direct,
+                "iMessage",
+                "Bold underline italic strikethrough all four",
+            )
+        })?;
+        self.add_message(&NewMessage {
+            attributed_body: Some(TEXT_EFFECTS.to_vec()),
+            ..timeline.received(direct, "iMessage", phone, "")
+        })?;
+
+        // App balloons
+        self.add_message(&NewMessage {
+            balloon_bundle_id: Some("com.apple.messages.URLBalloonProvider".to_string()),
+            payload_data: Some(URL.to_vec()),
+            ..timeline.sent(direct, "iMessage", "\u{FFFD}")
+        })?;
+        self.add_message(&NewMessage {
+            balloon_bundle_id: Some("com.apple.messages.URLBalloonProvider".to_string()),
+            payload_data: Some(MUSIC.to_vec()),
+            ..timeline.received(direct, "iMessage", phone, "\u{FFFD}")
+        })?;
+        self.add_message(&NewMessage {
+            balloon_bundle_id: Some("com.apple.Handwriting.HandwritingProvider".to_string()),
+            payload_data: Some(HANDWRITING.to_vec()),
+            ..timeline.sent(direct, "iMessage", "\u{FFFD}")
+        })?;
+        self.add_message(&NewMessage {
+            balloon_bundle_id: Some("com.apple.DigitalTouchBalloonProvider".to_string()),
+            payload_data: Some(DIGITAL_TOUCH.to_vec()),
+            ..timeline.received(direct, "iMessage", phone, "\u{FFFD}")
+        })?;
+        self.add_message(&NewMessage {
+            balloon_bundle_id: Some("com.apple.DigitalTouchBalloonProvider".to_string()),
+            payload_data: Some(SKETCH.to_vec()),
+            ..timeline.sent(direct, "iMessage", "\u{FFFD}")
+        })?;
+
+        // Edited and unsent messages
+        if self.schema.has_edits() {
+            self.add_message(&NewMessage {
+                date_edited: 690_513_494_000_000_000,
+                message_summary_info: Some(EDITED.to_vec()),
+                ..timeline.sent(direct, "iMessage", "Edited message")
+            })?;
+            self.add_message(&NewMessage {
+                text: None,
+                date_edited: 690_513_494_000_000_000,
+                message_summary_info: Some(UNSENT.to_vec()),
+                ..timeline.sent(direct, "iMessage", "")
+            })?;
+        }
+
+        // The same person, using their email address
+        self.add_message(&timeline.received(
+            direct_email,
+            "iMessage",
+            email,
+            "Sending from my laptop",
+        ))?;
+        self.add_message(&NewMessage {
+            subject: Some("Re: Saturday".to_string()),
+            expressive_send_style_id: Some("com.apple.MobileSMS.expressivesend.impact".to_string()),
+            ..timeline.sent(direct_email, "iMessage", "Got it!")
+        })?;
+
+        // Group chat with a rename, mentions, a reply thread, a photo change and membership changes
+        self.add_message(&NewMessage {
+            item_type: 2,
+            group_title: Some("Weekend Plans".to_string()),
+            ..timeline.sent(group, "iMessage", "")
+        })?;
+        let question = timeline.received(group, "iMessage", friend, "Who is bringing snacks?");
+        self.add_message(&question)?;
+        self.add_message(&NewMessage {
+            attributed_body: Some(MENTION.to_vec()),
+            ..timeline.received(group, "iMessage", coworker, "")
+        })?;
+        for (handle_id, text) in [(0, "I can bring chips"), (phone, "I'll bring drinks")] {
+            let reply = if handle_id == 0 {
+                timeline.sent(group, "iMessage", text)
+            } else {
+                timeline.received(group, "iMessage", handle_id, text)
+            };
+            self.add_message(&NewMessage {
+                thread_originator_guid: Some(question.guid.clone()),
+                thread_originator_part: Some("0:0:23".to_string()),
+                ..reply
+            })?;
+        }
+        let group_photo = self.add_message(&NewMessage {
+            item_type: 3,
+            group_action_type: 1,
+            ..timeline.received(group, "iMessage", coworker, "")
+        })?;
+        self.add_attachment(
+            group_photo,
+            &NewAttachment {
+                guid: timeline.attachment_guid(),
+                transfer_name: "GroupPhotoImage.png".to_string(),
+                mime_type: Some("image/png".to_string()),
+                uti: Some("public.png".to_string()),
+                created_date: BASE_DATE,
+                data: Some(IMAGE.to_vec()),
+                ..Default::default()
+            },
+        )?;
+        self.add_message(&NewMessage {
+            item_type: 1,
+            other_handle: newcomer,
+            ..timeline.received(group, "iMessage", friend, "")
+        })?;
+        self.add_message(&timeline.received(
+            group,
+            "iMessage",
+            newcomer,
+            "Thanks for the invite!",
+        ))?;
+        self.add_message(&NewMessage {
+            item_type: 3,
+            ..timeline.received(group, "iMessage", newcomer, "")
+        })?;
+        for group_action_type in [0, 1] {
+            self.add_message(&NewMessage {
+                item_type: 1,
+                group_action_type,
+                other_handle: newcomer,
+                ..timeline.sent(group, "iMessage", "")
+            })?;
+        }
+
+        // Recently deleted messages are no longer joined to their chat
+        self.add_message(&NewMessage {
+            chat_id: None,
+            deleted_from: Some(group),
+            ..timeline.sent(group, "iMessage", "Oops, wrong chat")
+        })?;
+
+        // SMS conversation
+        self.add_message(&timeline.received(
+            text_chat,
+            "SMS",
+            sms,
+            "Your appointment is confirmed for 3pm",
+        ))?;
+        self.add_message(&timeline.sent(text_chat, "SMS", "Thanks"))?;
+
+        // Messages scheduled with Send Later, one already sent and one still waiting to send
+        self.add_message(&NewMessage {
+            schedule_type: 2,
+            schedule_state: 2,
+            ..timeline.sent(direct, "iMessage", "Happy birthday!")
+        })?;
+        self.add_message(&NewMessage {
+            schedule_type: 2,
+            schedule_state: 1,
+            date_delivered: 0,
+            ..timeline.sent(direct, "iMessage", "Don't forget to bring the cake")
+        })?;
+
+        // Genmoji are stored as attachments, but older schemas do not know about them
+        if self.schema.has_genmoji() {
+            let genmoji = self.add_message(&NewMessage {
+                attributed_body: Some(GENMOJI.to_vec()),
+                ..timeline.received(direct, "iMessage", phone, "\u{FFFC}")
+            })?;
+            self.add_attachment(
+                genmoji,
+                &NewAttachment {
+                    guid: timeline.attachment_guid(),
+                    transfer_name: "Genmoji.png".to_string(),
+                    mime_type: Some("image/png".to_string()),
+                    uti: Some("public.png".to_string()),
+                    created_date: BASE_DATE,
+                    data: Some(IMAGE.to_vec()),
+                    emoji_image_content_identifier: Some(
+                        "8F1D0C9A-6B2E-4E57-9A3C-7D4B2E1F0A65".to_string(),
+                    ),
+                    emoji_image_short_description: Some("Crab wearing a party hat".to_string()),
+                    ..Default::default()
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Fill the fixture with `chats` direct message conversations of `messages_per_chat` messages each
+    ///
+    /// Every tenth message is a tapback on the message before it and every twenty-fifth message has an
+    /// image attachment, so large exports exercise the same caches and file handling as real ones.
+    pub fn populate_scaled(
+        &self,
+        chats: usize,
+        messages_per_chat: usize,
+    ) -> Result<(), FixtureError> {
+        let mut timeline = Timeline { count: 0 };
+        let transaction = self.db.unchecked_transaction()?;
+
+        for chat in 0..chats {
+            let id = format!("+1555{:07}", chat);
+            let handle = self.add_handle(&id, "iMessage", None)?;
+            let chat_id = self.add_chat(&id, "iMessage", None, &[handle])?;
+
+            let mut previous: Option<String> = None;
+            for idx in 0..messages_per_chat {
+                let text = format!("Message {idx} in conversation {chat}");
+                let mut message = if idx % 2 == 0 {
+                    timeline.sent(chat_id, "iMessage", &text)
+                } else {
+                    timeline.received(chat_id, "iMessage", handle, &text)
+                };
+
+                if idx % 10 == 9 {
+                    if let Some(target) = &previous {
+                        message.associated_message_guid = Some(format!("p:0/{target}"));
+                        message.associated_message_type = 2000;
+                    }
+                }
+                let has_attachment = idx % 25 == 24;
+                if has_attachment {
+                    message.text = Some("\u{FFFC}".to_string());
+                }
+
+                let message_id = self.add_message(&message)?;
+                if has_attachment {
+                    self.add_attachment(
+                        message_id,
+                        &NewAttachment {
+                            guid: timeline.attachment_guid(),
+                            transfer_name: format!("IMG_{idx:04}.png"),
+                            mime_type: Some("image/png".to_string()),
+                            uti: Some("public.png".to_string()),
+                            created_date: message.date,
+                            data: Some(IMAGE.to_vec()),
+                            ..Default::default()
+                        },
+                    )?;
+                }
+                previous = Some(message.guid);
+            }
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use crate::{fixture::Fixture, schema::Schema};
+
+    fn count(fixture: &Fixture, query: &str) -> i64 {
+        fixture.db.query_row(query, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn can_populate_each_schema() {
+        for schema in Schema::ALL {
+            let fixture = Fixture::temporary(schema).unwrap();
+            fixture.populate().unwrap();
+
+            assert_eq!(count(&fixture, "SELECT COUNT(*) FROM chat"), 4);
+            assert_eq!(count(&fixture, "SELECT COUNT(*) FROM handle"), 6);
+            assert_eq!(
+                count(&fixture, "SELECT COUNT(*) FROM attachment"),
+                if schema.has_genmoji() { 6 } else { 5 }
+            );
+        }
+    }
+
+    #[test]
+    fn can_populate_features_by_schema() {
+        let old = Fixture::temporary(Schema::Catalina).unwrap();
+        old.populate().unwrap();
+        let new = Fixture::temporary(Schema::Sequoia).unwrap();
+        new.populate().unwrap();
+
+        let edits = "SELECT COUNT(*) FROM message WHERE message_summary_info IS NOT NULL";
+        assert_eq!(count(&old, edits), 0);
+        assert_eq!(count(&new, edits), 2);
+        assert_eq!(
+            count(&new, "SELECT COUNT(*) FROM chat_recoverable_message_join"),
+            1
+        );
+        assert_eq!(
+            count(
+                &new,
+                "SELECT COUNT(*) FROM message WHERE thread_originator_guid IS NOT NULL"
+            ),
+            2
+        );
+        assert_eq!(
+            count(&new, "SELECT COUNT(*) FROM message WHERE schedule_type = 2"),
+            2
+        );
+    }
+
+    #[test]
+    fn can_write_attachment_files() {
+        let db = Connection::open(Fixture::shared()).unwrap();
+        let mut statement = db.prepare("SELECT filename FROM attachment").unwrap();
+        let paths: Vec<String> = statement
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+
+        assert!(!paths.is_empty());
+        assert!(paths.iter().all(|path| std::path::Path::new(path).exists()));
+    }
+
+    #[test]
+    fn can_populate_scaled() {
+        let fixture = Fixture::temporary(Schema::latest()).unwrap();
+        fixture.populate_scaled(3, 50).unwrap();
+
+        assert_eq!(count(&fixture, "SELECT COUNT(*) FROM chat"), 3);
+        assert_eq!(count(&fixture, "SELECT COUNT(*) FROM message"), 150);
+        assert_eq!(count(&fixture, "SELECT COUNT(*) FROM attachment"), 6);
+        assert_eq!(
+            count(
+                &fixture,
+                "SELECT COUNT(*) FROM message WHERE associated_message_type = 2000"
+            ),
+            15
+        );
+    }
+}
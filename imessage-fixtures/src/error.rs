@@ -0,0 +1,37 @@
+/*!
+ Errors that can happen when building a fixture database.
+*/
+
+use std::fmt::{Display, Formatter, Result};
+
+/// Errors that can happen when building a fixture database
+#[derive(Debug)]
+pub enum FixtureError {
+    /// The database could not be created or written to
+    Database(rusqlite::Error),
+    /// An attachment file or directory could not be written
+    Disk(std::io::Error),
+}
+
+impl From<rusqlite::Error> for FixtureError {
+    fn from(error: rusqlite::Error) -> Self {
+        FixtureError::Database(error)
+    }
+}
+
+impl From<std::io::Error> for FixtureError {
+    fn from(error: std::io::Error) -> Self {
+        FixtureError::Disk(error)
+    }
+}
+
+impl Display for FixtureError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result {
+        match self {
+            FixtureError::Database(why) => write!(fmt, "Failed to write fixture database: {why}"),
+            FixtureError::Disk(why) => write!(fmt, "Failed to write fixture file: {why}"),
+        }
+    }
+}
+
+impl std::error::Error for FixtureError {}
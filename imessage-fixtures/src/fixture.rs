@@ -0,0 +1,478 @@
+/*!
+ Creates fixture databases and inserts rows into them.
+*/
+
+use std::{
+    env::temp_dir,
+    fs::{create_dir_all, remove_dir_all, write},
+    mem::replace,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rusqlite::{types::Value, Connection};
+
+use crate::{error::FixtureError, schema::Schema};
+
+/// The file name of the database inside a fixture directory
+pub const DB_NAME: &str = "chat.db";
+/// The directory attachment files are written to inside a fixture directory
+pub const ATTACHMENTS_DIR: &str = "Attachments";
+
+/// Generate a deterministic message or attachment GUID from a number
+///
+/// Tapbacks reference the first 36 characters of a GUID, so these are always the same length as real ones.
+///
+/// # Example:
+///
+/// ```
+/// use imessage_fixtures::guid;
+///
+/// assert_eq!(guid(1), "00000000-0000-4000-8000-000000000001");
+/// ```
+pub fn guid(number: u64) -> String {
+    format!("00000000-0000-4000-8000-{number:012X}")
+}
+
+/// Represents a synthetic iMessage database and the attachment files it references
+pub struct Fixture {
+    /// The directory the fixture was created in
+    pub root: PathBuf,
+    /// The schema generation the database was created with
+    pub schema: Schema,
+    /// A writable connection to the database
+    pub db: Connection,
+    /// Whether the fixture's directory is removed when the fixture is dropped
+    temporary: bool,
+}
+
+/// Represents a row to insert into the `message` table
+///
+/// Fields for columns that do not exist in the fixture's [`Schema`] are ignored.
+#[derive(Debug, Default, Clone)]
+pub struct NewMessage {
+    pub guid: String,
+    pub text: Option<String>,
+    /// Raw `typedstream` data for the `attributedBody` column
+    pub attributed_body: Option<Vec<u8>>,
+    pub service: Option<String>,
+    /// The `ROWID` of the sender in the `handle` table, or `0` for the database owner
+    pub handle_id: i32,
+    pub destination_caller_id: Option<String>,
+    pub subject: Option<String>,
+    pub date: i64,
+    pub date_read: i64,
+    pub date_delivered: i64,
+    pub is_from_me: bool,
+    pub is_read: bool,
+    pub item_type: i32,
+    pub other_handle: i32,
+    pub share_status: bool,
+    pub share_direction: bool,
+    pub group_title: Option<String>,
+    pub group_action_type: i32,
+    pub associated_message_guid: Option<String>,
+    pub associated_message_type: i32,
+    pub associated_message_emoji: Option<String>,
+    pub balloon_bundle_id: Option<String>,
+    pub payload_data: Option<Vec<u8>>,
+    pub expressive_send_style_id: Option<String>,
+    pub message_summary_info: Option<Vec<u8>>,
+    pub thread_originator_guid: Option<String>,
+    pub thread_originator_part: Option<String>,
+    pub date_edited: i64,
+    /// `2` if the message was scheduled with Send Later
+    pub schedule_type: i32,
+    /// The state of a message scheduled with Send Later
+    pub schedule_state: i32,
+    /// The `ROWID` of the chat the message belongs to
+    pub chat_id: Option<i32>,
+    /// The `ROWID` of the chat the message was deleted from, if it is in Recently Deleted
+    pub deleted_from: Option<i32>,
+}
+
+/// Represents a row to insert into the `attachment` table, along with the file it points to
+#[derive(Debug, Default, Clone)]
+pub struct NewAttachment {
+    pub guid: String,
+    /// The name of the file when sent or received
+    pub transfer_name: String,
+    pub mime_type: Option<String>,
+    pub uti: Option<String>,
+    pub is_sticker: bool,
+    pub created_date: i64,
+    /// The contents of the file written to disk; if `None`, the file is missing from the disk
+    pub data: Option<Vec<u8>>,
+    /// The identifier of the image if the attachment is a Genmoji
+    pub emoji_image_content_identifier: Option<String>,
+    /// The text description of a Genmoji image
+    pub emoji_image_short_description: Option<String>,
+}
+
+impl Fixture {
+    /// Create an empty database with the given schema in `root`, replacing any existing fixture there
+    pub fn create(root: &Path, schema: Schema) -> Result<Self, FixtureError> {
+        if root.exists() {
+            remove_dir_all(root)?;
+        }
+        create_dir_all(root.join(ATTACHMENTS_DIR))?;
+
+        let db = Connection::open(root.join(DB_NAME))?;
+        db.execute_batch(&schema.ddl())?;
+
+        Ok(Fixture {
+            root: root.to_path_buf(),
+            schema,
+            db,
+            temporary: false,
+        })
+    }
+
+    /// Create an empty database with the given schema in a new directory that is removed when the fixture is dropped
+    ///
+    /// Each call uses a different directory, so tests that run at the same time do not overwrite each other's fixtures.
+    pub fn temporary(schema: Schema) -> Result<Self, FixtureError> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let root = temp_dir().join(format!(
+            "imessage-fixtures-{}-{}",
+            process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut fixture = Fixture::create(&root, schema)?;
+        fixture.temporary = true;
+        Ok(fixture)
+    }
+
+    /// The path to the fixture's `chat.db`
+    pub fn db_path(&self) -> PathBuf {
+        self.root.join(DB_NAME)
+    }
+
+    /// The directory the fixture's attachment files are written to
+    pub fn attachment_root(&self) -> PathBuf {
+        self.root.join(ATTACHMENTS_DIR)
+    }
+
+    /// Insert a row into the `handle` table, returning its `ROWID`
+    pub fn add_handle(
+        &self,
+        id: &str,
+        service: &str,
+        person_centric_id: Option<&str>,
+    ) -> Result<i32, FixtureError> {
+        self.db.execute(
+            "INSERT INTO handle (id, country, service, uncanonicalized_id, person_centric_id) VALUES (?1, 'us', ?2, ?1, ?3)",
+            (id, service, person_centric_id),
+        )?;
+        Ok(self.db.last_insert_rowid() as i32)
+    }
+
+    /// Insert a row into the `chat` table and its participants into `chat_handle_join`, returning its `ROWID`
+    pub fn add_chat(
+        &self,
+        chat_identifier: &str,
+        service_name: &str,
+        display_name: Option<&str>,
+        handles: &[i32],
+    ) -> Result<i32, FixtureError> {
+        // Style 43 is a group chat, 45 is a direct message
+        let style = if handles.len() > 1 { 43 } else { 45 };
+        self.db.execute(
+            "INSERT INTO chat (guid, style, state, chat_identifier, service_name, room_name, display_name)
+             VALUES (?1, ?2, 3, ?3, ?4, ?5, ?6)",
+            (
+                format!("{service_name};{};{chat_identifier}", if style == 43 { "+" } else { "-" }),
+                style,
+                chat_identifier,
+                service_name,
+                (style == 43).then_some(chat_identifier),
+                display_name.unwrap_or_default(),
+            ),
+        )?;
+        let chat_id = self.db.last_insert_rowid() as i32;
+
+        for handle_id in handles {
+            self.db.execute(
+                "INSERT INTO chat_handle_join (chat_id, handle_id) VALUES (?1, ?2)",
+                (chat_id, handle_id),
+            )?;
+        }
+
+        Ok(chat_id)
+    }
+
+    /// Insert a row into the `message` table and join it to its chat, returning its `ROWID`
+    pub fn add_message(&self, message: &NewMessage) -> Result<i32, FixtureError> {
+        let mut columns: Vec<(&str, Value)> = vec![
+            ("guid", message.guid.clone().into()),
+            ("text", message.text.clone().into()),
+            ("attributedBody", message.attributed_body.clone().into()),
+            ("service", message.service.clone().into()),
+            ("handle_id", message.handle_id.into()),
+            (
+                "destination_caller_id",
+                message.destination_caller_id.clone().into(),
+            ),
+            ("subject", message.subject.clone().into()),
+            ("date", message.date.into()),
+            ("date_read", message.date_read.into()),
+            ("date_delivered", message.date_delivered.into()),
+            ("is_delivered", (message.date_delivered != 0).into()),
+            ("is_finished", true.into()),
+            ("is_from_me", message.is_from_me.into()),
+            ("is_read", message.is_read.into()),
+            ("is_sent", message.is_from_me.into()),
+            ("item_type", message.item_type.into()),
+            ("other_handle", message.other_handle.into()),
+            ("share_status", message.share_status.into()),
+            ("share_direction", message.share_direction.into()),
+            ("group_title", message.group_title.clone().into()),
+            ("group_action_type", message.group_action_type.into()),
+            (
+                "associated_message_guid",
+                message.associated_message_guid.clone().into(),
+            ),
+            (
+                "associated_message_type",
+                message.associated_message_type.into(),
+            ),
+            (
+                "balloon_bundle_id",
+                message.balloon_bundle_id.clone().into(),
+            ),
+            ("payload_data", message.payload_data.clone().into()),
+            (
+                "expressive_send_style_id",
+                message.expressive_send_style_id.clone().into(),
+            ),
+            (
+                "message_summary_info",
+                message.message_summary_info.clone().into(),
+            ),
+        ];
+        if self.schema.has_replies() {
+            columns.push((
+                "thread_originator_guid",
+                message.thread_originator_guid.clone().into(),
+            ));
+            columns.push((
+                "thread_originator_part",
+                message.thread_originator_part.clone().into(),
+            ));
+        }
+        if self.schema.has_edits() {
+            columns.push(("date_edited", message.date_edited.into()));
+        }
+        if self.schema.has_custom_emoji() {
+            columns.push((
+                "associated_message_emoji",
+                message.associated_message_emoji.clone().into(),
+            ));
+        }
+        if self.schema.has_scheduled() {
+            columns.push(("schedule_type", message.schedule_type.into()));
+            columns.push(("schedule_state", message.schedule_state.into()));
+        }
+
+        let names: Vec<&str> = columns.iter().map(|(name, _)| *name).collect();
+        let placeholders: Vec<String> = (1..=columns.len()).map(|idx| format!("?{idx}")).collect();
+        self.db.execute(
+            &format!(
+                "INSERT INTO message ({}) VALUES ({})",
+                names.join(", "),
+                placeholders.join(", ")
+            ),
+            rusqlite::params_from_iter(columns.into_iter().map(|(_, value)| value)),
+        )?;
+        let message_id = self.db.last_insert_rowid() as i32;
+
+        if let Some(chat_id) = message.chat_id {
+            self.db.execute(
+                "INSERT INTO chat_message_join (chat_id, message_id, message_date) VALUES (?1, ?2, ?3)",
+                (chat_id, message_id, message.date),
+            )?;
+        }
+
+        // Older schemas do not have Recently Deleted, so those messages are dropped entirely
+        if let Some(chat_id) = message.deleted_from {
+            if self.schema.has_recently_deleted() {
+                self.db.execute(
+                    "INSERT INTO chat_recoverable_message_join (chat_id, message_id, delete_date) VALUES (?1, ?2, ?3)",
+                    (chat_id, message_id, message.date),
+                )?;
+            }
+        }
+
+        Ok(message_id)
+    }
+
+    /// Write an attachment's file to the disk and insert it into the `attachment` table, returning its `ROWID`
+    ///
+    /// The file is written to `Attachments/<xx>/<guid>/<transfer_name>`, mirroring the layout macOS uses,
+    /// and the `filename` column stores its absolute path.
+    pub fn add_attachment(
+        &self,
+        message_id: i32,
+        attachment: &NewAttachment,
+    ) -> Result<i32, FixtureError> {
+        let directory = self
+            .attachment_root()
+            .join(
+                attachment
+                    .guid
+                    .get(attachment.guid.len() - 2..)
+                    .unwrap_or("00"),
+            )
+            .join(&attachment.guid);
+        let path = directory.join(&attachment.transfer_name);
+
+        let total_bytes = match &attachment.data {
+            Some(data) => {
+                create_dir_all(&directory)?;
+                write(&path, data)?;
+                data.len()
+            }
+            None => 0,
+        };
+
+        self.db.execute(
+            "INSERT INTO attachment (guid, created_date, filename, uti, mime_type, transfer_state, transfer_name, total_bytes, is_sticker)
+             VALUES (?1, ?2, ?3, ?4, ?5, 5, ?6, ?7, ?8)",
+            (
+                &attachment.guid,
+                attachment.created_date,
+                path.to_string_lossy(),
+                &attachment.uti,
+                &attachment.mime_type,
+                &attachment.transfer_name,
+                total_bytes as i64,
+                attachment.is_sticker,
+            ),
+        )?;
+        let attachment_id = self.db.last_insert_rowid() as i32;
+
+        // Older schemas do not have Genmoji, so those attachments are stored as regular images
+        if self.schema.has_genmoji() {
+            self.db.execute(
+                "UPDATE attachment SET emoji_image_content_identifier = ?1, emoji_image_short_description = ?2 WHERE ROWID = ?3",
+                (
+                    &attachment.emoji_image_content_identifier,
+                    &attachment.emoji_image_short_description,
+                    attachment_id,
+                ),
+            )?;
+        }
+
+        self.db.execute(
+            "INSERT INTO message_attachment_join (message_id, attachment_id) VALUES (?1, ?2)",
+            (message_id, attachment_id),
+        )?;
+        self.db.execute(
+            "UPDATE message SET cache_has_attachments = 1 WHERE ROWID = ?1",
+            [message_id],
+        )?;
+
+        Ok(attachment_id)
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        if self.temporary {
+            // Close the database first, since some platforms cannot remove open files
+            if let Ok(closed) = Connection::open_in_memory() {
+                drop(replace(&mut self.db, closed));
+            }
+            let _ = remove_dir_all(&self.root);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fixture::{guid, Fixture, NewAttachment, NewMessage},
+        schema::Schema,
+    };
+
+    #[test]
+    fn can_generate_guid() {
+        assert_eq!(guid(255), "00000000-0000-4000-8000-0000000000FF");
+        assert_eq!(guid(255).len(), 36);
+    }
+
+    #[test]
+    fn can_create_each_schema() {
+        for schema in Schema::ALL {
+            let fixture = Fixture::temporary(schema).unwrap();
+            assert!(fixture.db_path().exists());
+        }
+    }
+
+    #[test]
+    fn can_use_distinct_temporary_dirs() {
+        let first = Fixture::temporary(Schema::latest()).unwrap();
+        let second = Fixture::temporary(Schema::latest()).unwrap();
+        assert_ne!(first.root, second.root);
+    }
+
+    #[test]
+    fn can_remove_temporary_on_drop() {
+        let fixture = Fixture::temporary(Schema::latest()).unwrap();
+        let root = fixture.root.clone();
+        assert!(root.exists());
+
+        drop(fixture);
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn can_ignore_missing_columns() {
+        let fixture = Fixture::temporary(Schema::Catalina).unwrap();
+        let chat = fixture
+            .add_chat("+15555550100", "iMessage", None, &[])
+            .unwrap();
+        let message = NewMessage {
+            guid: guid(1),
+            text: Some("Reply".to_string()),
+            thread_originator_guid: Some(guid(0)),
+            date_edited: 1,
+            associated_message_emoji: Some("🦀".to_string()),
+            chat_id: Some(chat),
+            deleted_from: Some(chat),
+            ..Default::default()
+        };
+
+        assert_eq!(fixture.add_message(&message).unwrap(), 1);
+    }
+
+    #[test]
+    fn can_write_attachment() {
+        let fixture = Fixture::temporary(Schema::latest()).unwrap();
+        let message = fixture
+            .add_message(&NewMessage {
+                guid: guid(1),
+                ..Default::default()
+            })
+            .unwrap();
+        fixture
+            .add_attachment(
+                message,
+                &NewAttachment {
+                    guid: guid(2),
+                    transfer_name: "file.txt".to_string(),
+                    data: Some(b"data".to_vec()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let path: String = fixture
+            .db
+            .query_row("SELECT filename FROM attachment", [], |row| row.get(0))
+            .unwrap();
+        assert!(path.ends_with("Attachments/02/00000000-0000-4000-8000-000000000002/file.txt"));
+        assert_eq!(std::fs::read(path).unwrap(), b"data");
+    }
+}
@@ -0,0 +1,29 @@
+/*!
+ Builds synthetic iMessage `chat.db` files for tests and benchmarks.
+
+ A [`Fixture`] is a directory containing a `chat.db` that uses one of several [`Schema`] generations,
+ along with any attachment files the database references. Fixtures can be filled with a realistic
+ [standard dataset](Fixture::populate) that exercises most exporter features, with a
+ [large generated dataset](Fixture::populate_scaled) for benchmarks, or row by row with the insert helpers.
+
+ # Example:
+
+ ```
+ use imessage_fixtures::{Fixture, Schema};
+
+ let fixture = Fixture::temporary(Schema::Sequoia).unwrap();
+ fixture.populate().unwrap();
+ println!("{}", fixture.db_path().display());
+ ```
+*/
+
+pub mod dataset;
+pub mod error;
+pub mod fixture;
+pub mod schema;
+
+pub use crate::{
+    error::FixtureError,
+    fixture::{guid, Fixture, NewAttachment, NewMessage},
+    schema::Schema,
+};
@@ -0,0 +1,279 @@
+/*!
+ Describes the `chat.db` schema generations a fixture can be built with.
+*/
+
+use std::fmt::Display;
+
+/// Represents a generation of the iMessage database schema
+///
+/// Each generation adds columns or tables to the one before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Schema {
+    /// macOS Catalina, iOS 13 and older
+    Catalina,
+    /// macOS Big Sur to Monterey, iOS 14 to iOS 15: adds `thread_originator_guid` and `thread_originator_part`
+    BigSur,
+    /// macOS Ventura to Sonoma, iOS 16 to iOS 17: adds `date_edited` and `chat_recoverable_message_join`
+    Ventura,
+    /// macOS Sequoia, iOS 18: adds `associated_message_emoji`, `schedule_type`, `schedule_state`, and the Genmoji columns of `attachment`
+    Sequoia,
+}
+
+impl Schema {
+    /// Every schema generation, oldest first
+    pub const ALL: [Schema; 4] = [
+        Schema::Catalina,
+        Schema::BigSur,
+        Schema::Ventura,
+        Schema::Sequoia,
+    ];
+
+    /// The most recent schema generation
+    pub fn latest() -> Self {
+        Schema::Sequoia
+    }
+
+    /// `true` if the `message` table has `thread_originator_guid` and `thread_originator_part`
+    pub fn has_replies(&self) -> bool {
+        *self >= Schema::BigSur
+    }
+
+    /// `true` if the `message` table has `date_edited`
+    pub fn has_edits(&self) -> bool {
+        *self >= Schema::Ventura
+    }
+
+    /// `true` if the database has the `chat_recoverable_message_join` table
+    pub fn has_recently_deleted(&self) -> bool {
+        *self >= Schema::Ventura
+    }
+
+    /// `true` if the `message` table has `associated_message_emoji`
+    pub fn has_custom_emoji(&self) -> bool {
+        *self >= Schema::Sequoia
+    }
+
+    /// `true` if the `message` table has `schedule_type` and `schedule_state`
+    pub fn has_scheduled(&self) -> bool {
+        *self >= Schema::Sequoia
+    }
+
+    /// `true` if the `attachment` table has `emoji_image_content_identifier` and `emoji_image_short_description`
+    pub fn has_genmoji(&self) -> bool {
+        *self >= Schema::Sequoia
+    }
+
+    /// Get the columns of the `message` table for this schema generation
+    pub fn message_columns(&self) -> Vec<(&'static str, &'static str)> {
+        let mut columns = vec![
+            ("ROWID", "INTEGER PRIMARY KEY AUTOINCREMENT"),
+            ("guid", "TEXT UNIQUE NOT NULL"),
+            ("text", "TEXT"),
+            ("replace", "INTEGER DEFAULT 0"),
+            ("handle_id", "INTEGER DEFAULT 0"),
+            ("subject", "TEXT"),
+            ("attributedBody", "BLOB"),
+            ("version", "INTEGER DEFAULT 0"),
+            ("type", "INTEGER DEFAULT 0"),
+            ("service", "TEXT"),
+            ("error", "INTEGER DEFAULT 0"),
+            ("date", "INTEGER"),
+            ("date_read", "INTEGER"),
+            ("date_delivered", "INTEGER"),
+            ("is_delivered", "INTEGER DEFAULT 0"),
+            ("is_finished", "INTEGER DEFAULT 0"),
+            ("is_from_me", "INTEGER DEFAULT 0"),
+            ("is_read", "INTEGER DEFAULT 0"),
+            ("is_sent", "INTEGER DEFAULT 0"),
+            ("cache_has_attachments", "INTEGER DEFAULT 0"),
+            ("item_type", "INTEGER DEFAULT 0"),
+            ("other_handle", "INTEGER DEFAULT 0"),
+            ("group_title", "TEXT"),
+            ("group_action_type", "INTEGER DEFAULT 0"),
+            ("share_status", "INTEGER DEFAULT 0"),
+            ("share_direction", "INTEGER DEFAULT 0"),
+            ("associated_message_guid", "STRING DEFAULT NULL"),
+            ("balloon_bundle_id", "STRING DEFAULT NULL"),
+            ("payload_data", "BLOB"),
+            ("associated_message_type", "INTEGER DEFAULT 0"),
+            ("expressive_send_style_id", "STRING DEFAULT NULL"),
+            ("message_summary_info", "BLOB DEFAULT NULL"),
+            ("destination_caller_id", "TEXT DEFAULT NULL"),
+        ];
+        if self.has_replies() {
+            columns.push(("thread_originator_guid", "TEXT DEFAULT NULL"));
+            columns.push(("thread_originator_part", "TEXT DEFAULT NULL"));
+        }
+        if self.has_edits() {
+            columns.push(("date_retracted", "INTEGER DEFAULT 0"));
+            columns.push(("date_edited", "INTEGER DEFAULT 0"));
+        }
+        if self.has_custom_emoji() {
+            columns.push(("associated_message_emoji", "TEXT DEFAULT NULL"));
+        }
+        if self.has_scheduled() {
+            columns.push(("schedule_type", "INTEGER DEFAULT 0"));
+            columns.push(("schedule_state", "INTEGER DEFAULT 0"));
+        }
+        columns
+    }
+
+    /// Generate the SQL that creates every table for this schema generation
+    pub fn ddl(&self) -> String {
+        let message_columns = self
+            .message_columns()
+            .iter()
+            .map(|(name, kind)| format!("{name} {kind}"))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let mut ddl = format!(
+            "CREATE TABLE handle (
+                 ROWID INTEGER PRIMARY KEY AUTOINCREMENT UNIQUE,
+                 id TEXT NOT NULL,
+                 country TEXT,
+                 service TEXT NOT NULL,
+                 uncanonicalized_id TEXT,
+                 person_centric_id TEXT DEFAULT NULL
+             );
+             CREATE TABLE chat (
+                 ROWID INTEGER PRIMARY KEY AUTOINCREMENT,
+                 guid TEXT UNIQUE NOT NULL,
+                 style INTEGER,
+                 state INTEGER,
+                 chat_identifier TEXT,
+                 service_name TEXT,
+                 room_name TEXT,
+                 is_archived INTEGER DEFAULT 0,
+                 display_name TEXT,
+                 group_id TEXT
+             );
+             CREATE TABLE attachment (
+                 ROWID INTEGER PRIMARY KEY AUTOINCREMENT,
+                 guid TEXT UNIQUE NOT NULL,
+                 created_date INTEGER DEFAULT 0,
+                 start_date INTEGER DEFAULT 0,
+                 filename TEXT,
+                 uti TEXT,
+                 mime_type TEXT,
+                 transfer_state INTEGER DEFAULT 0,
+                 is_outgoing INTEGER DEFAULT 0,
+                 transfer_name TEXT,
+                 total_bytes INTEGER DEFAULT 0,
+                 is_sticker INTEGER DEFAULT 0,
+                 hide_attachment INTEGER DEFAULT 0,
+                 ck_server_change_token_blob BLOB DEFAULT NULL,
+                 sr_ck_server_change_token_blob BLOB DEFAULT NULL
+             );
+             CREATE TABLE message ({message_columns});
+             CREATE TABLE chat_handle_join (
+                 chat_id INTEGER REFERENCES chat (ROWID) ON DELETE CASCADE,
+                 handle_id INTEGER REFERENCES handle (ROWID) ON DELETE CASCADE,
+                 UNIQUE(chat_id, handle_id)
+             );
+             CREATE TABLE chat_message_join (
+                 chat_id INTEGER REFERENCES chat (ROWID) ON DELETE CASCADE,
+                 message_id INTEGER REFERENCES message (ROWID) ON DELETE CASCADE,
+                 message_date INTEGER DEFAULT 0,
+                 PRIMARY KEY (chat_id, message_id)
+             );
+             CREATE TABLE message_attachment_join (
+                 message_id INTEGER REFERENCES message (ROWID) ON DELETE CASCADE,
+                 attachment_id INTEGER REFERENCES attachment (ROWID) ON DELETE CASCADE,
+                 UNIQUE(message_id, attachment_id)
+             );
+             CREATE INDEX chat_message_join_idx_message_id_only ON chat_message_join(message_id);
+             CREATE INDEX message_attachment_join_idx_message_id ON message_attachment_join(message_id);
+            "
+        );
+
+        if self.has_replies() {
+            ddl.push_str(
+                "CREATE INDEX message_idx_thread_originator_guid ON message(thread_originator_guid);",
+            );
+        }
+
+        if self.has_recently_deleted() {
+            ddl.push_str(
+                "CREATE TABLE chat_recoverable_message_join (
+                     chat_id INTEGER REFERENCES chat (ROWID) ON DELETE CASCADE,
+                     message_id INTEGER REFERENCES message (ROWID) ON DELETE CASCADE,
+                     delete_date INTEGER,
+                     ck_sync_state INTEGER DEFAULT 0,
+                     PRIMARY KEY (chat_id, message_id)
+                 );",
+            );
+        }
+
+        if self.has_genmoji() {
+            ddl.push_str(
+                "ALTER TABLE attachment ADD COLUMN emoji_image_content_identifier TEXT DEFAULT NULL;
+                 ALTER TABLE attachment ADD COLUMN emoji_image_short_description TEXT DEFAULT NULL;",
+            );
+        }
+
+        ddl
+    }
+}
+
+impl Display for Schema {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Schema::Catalina => write!(fmt, "catalina"),
+            Schema::BigSur => write!(fmt, "big_sur"),
+            Schema::Ventura => write!(fmt, "ventura"),
+            Schema::Sequoia => write!(fmt, "sequoia"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::Schema;
+
+    #[test]
+    fn can_order_generations() {
+        assert!(Schema::Catalina < Schema::BigSur);
+        assert!(Schema::BigSur < Schema::Ventura);
+        assert!(Schema::Ventura < Schema::Sequoia);
+    }
+
+    #[test]
+    fn can_gate_columns() {
+        let has = |schema: Schema, column: &str| {
+            schema
+                .message_columns()
+                .iter()
+                .any(|(name, _)| *name == column)
+        };
+
+        assert!(!has(Schema::Catalina, "thread_originator_guid"));
+        assert!(has(Schema::BigSur, "thread_originator_guid"));
+        assert!(!has(Schema::BigSur, "date_edited"));
+        assert!(has(Schema::Ventura, "date_edited"));
+        assert!(!has(Schema::Ventura, "associated_message_emoji"));
+        assert!(has(Schema::Sequoia, "associated_message_emoji"));
+        assert!(!has(Schema::Ventura, "schedule_type"));
+        assert!(has(Schema::Sequoia, "schedule_type"));
+    }
+
+    #[test]
+    fn can_gate_recently_deleted() {
+        assert!(!Schema::BigSur
+            .ddl()
+            .contains("chat_recoverable_message_join"));
+        assert!(Schema::Ventura
+            .ddl()
+            .contains("chat_recoverable_message_join"));
+    }
+
+    #[test]
+    fn can_gate_genmoji() {
+        assert!(!Schema::Ventura
+            .ddl()
+            .contains("emoji_image_content_identifier"));
+        assert!(Schema::Sequoia
+            .ddl()
+            .contains("emoji_image_content_identifier"));
+    }
+}